@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Measures listing a large LIB recursively with [cli::info_with_options]
+//! in each [CodeFormat].
+//!
+//! Run without `--bench`, as `cargo test --benches` does, each benchmark runs
+//! once as a smoke test.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use psyk::cli;
+use psyk::display::{CodeFormat, Options};
+use psyk::{io, Code, Module, ModuleMetadata, Section, LIB, OBJ};
+
+/// Instructions repeated to fill the code of the [synthetic] LIB's modules.
+/// The last word is data.
+const WORDS: [u32; 8] = [
+    0x27bdffe8, // addiu $sp, $sp, -0x18
+    0xafbf0010, // sw $ra, 0x10($sp)
+    0x3c040000, // lui $a0, 0x0
+    0x0c000000, // jal 0x0
+    0x24840000, // addiu $a0, $a0, 0x0
+    0x8fbf0010, // lw $ra, 0x10($sp)
+    0x03e00008, // jr $ra
+    0xffffffff,
+];
+
+/// A LIB of many copies of the synthetic fixture's modules, each followed
+/// by a module with 16 KB of code.
+fn synthetic(dir: &Path) -> PathBuf {
+    let simple = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+    let code = WORDS
+        .iter()
+        .cycle()
+        .take(0x1000)
+        .flat_map(|word| word.to_le_bytes())
+        .collect::<Vec<_>>();
+    let large = Module::new(
+        OBJ::new(vec![
+            Section::Code(Code::new(code).expect("code")),
+            Section::NOP,
+        ]),
+        ModuleMetadata::new(
+            "LARGE".into(),
+            UNIX_EPOCH + Duration::from_secs(832_176_578),
+            0,
+            Vec::new(),
+        )
+        .expect("metadata"),
+    );
+    let modules: Vec<Module> = (0..50)
+        .flat_map(|_| simple.modules().iter().cloned().chain([large.clone()]))
+        .collect();
+    let path = dir.join("MANY.LIB");
+    let mut file = std::fs::File::create(&path).expect("create");
+    io::write_lib_to(&LIB::new(modules), &mut file).expect("write");
+    path
+}
+
+fn info(c: &mut Criterion) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut libs = vec![synthetic(dir.path())];
+    let libgs = Path::new("tests/data/psy-q/3.5/PSX/LIB/LIBGS.LIB");
+    if libgs.exists() {
+        libs.push(libgs.to_path_buf());
+    }
+
+    let mut group = c.benchmark_group("info");
+    group.sample_size(10);
+    for path in &libs {
+        let name = path.file_name().expect("name").to_string_lossy();
+        for (format, code_format) in [
+            ("none", CodeFormat::None),
+            ("hex", CodeFormat::Hex),
+            ("disassembly", CodeFormat::Disassembly),
+            ("hex+disassembly", CodeFormat::HexAndDisassembly),
+        ] {
+            let options = Options {
+                code_format,
+                recursive: true,
+                ..Options::modern()
+            };
+            group.bench_with_input(BenchmarkId::new(format, &name), &options, |b, options| {
+                // written to memory; `io::sink` skips formatting altogether
+                let mut listing = Vec::new();
+                b.iter(|| {
+                    listing.clear();
+                    cli::info_with_options(&mut listing, path, options.clone()).expect("info")
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, info);
+criterion_main!(benches);
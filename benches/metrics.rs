@@ -0,0 +1,41 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Measures reading an OBJ through [io::read_obj_from] without a metrics
+//! recorder and with an [InMemoryRecorder], against parsing it directly.
+//!
+//! The recorder is process-wide and can't be removed, so the benchmarks
+//! with it run last.
+//!
+//! Run without `--bench`, as `cargo test --benches` does, each benchmark runs
+//! once as a smoke test.
+
+use binrw::io::Cursor;
+use binrw::BinRead;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use psyk::io;
+use psyk::metrics::{self, InMemoryRecorder};
+use psyk::{Section, OBJ};
+
+fn metrics(c: &mut Criterion) {
+    let mut sections = vec![Section::BSS(4); 2000];
+    sections.push(Section::NOP);
+    let bytes = OBJ::new(sections).to_bytes().expect("bytes");
+
+    let mut group = c.benchmark_group("metrics");
+    group.bench_function("parse", |b| {
+        b.iter(|| OBJ::read(&mut Cursor::new(&bytes)).expect("obj"))
+    });
+    group.bench_function("without recorder", |b| {
+        b.iter(|| io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj"))
+    });
+    metrics::set_recorder(Box::new(InMemoryRecorder::new())).expect("recorder");
+    group.bench_function("with recorder", |b| {
+        b.iter(|| io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj"))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, metrics);
+criterion_main!(benches);
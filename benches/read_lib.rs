@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Compares reading a LIB serially with [io::read_lib_from] to reading it
+//! with [io::read_lib], which parses modules in parallel.
+
+use std::path::{Path, PathBuf};
+
+use binrw::io::Cursor;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use psyk::{io, Module, LIB};
+
+/// A LIB of many copies of the synthetic fixture's modules.
+fn synthetic(dir: &Path) -> PathBuf {
+    let simple = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+    let modules: Vec<Module> = (0..2000)
+        .flat_map(|_| simple.modules().iter().cloned())
+        .collect();
+    let path = dir.join("MANY.LIB");
+    let mut file = std::fs::File::create(&path).expect("create");
+    io::write_lib_to(&LIB::new(modules), &mut file).expect("write");
+    path
+}
+
+fn read_lib(c: &mut Criterion) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut libs = vec![synthetic(dir.path())];
+    libs.extend(
+        ["LIBGS.LIB", "LIBGPU.LIB", "LIBC2.LIB"]
+            .iter()
+            .map(|name| Path::new("tests/data/psy-q/3.5/PSX/LIB").join(name))
+            .filter(|path| path.exists()),
+    );
+
+    let mut group = c.benchmark_group("read_lib");
+    for path in &libs {
+        let name = path.file_name().expect("name").to_string_lossy();
+        group.bench_with_input(BenchmarkId::new("serial", &name), path, |b, path| {
+            b.iter(|| {
+                let bytes = std::fs::read(path).expect("read");
+                io::read_lib_from(&mut Cursor::new(bytes)).expect("lib")
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", &name), path, |b, path| {
+            b.iter(|| io::read_lib(path).expect("lib"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read_lib);
+criterion_main!(benches);
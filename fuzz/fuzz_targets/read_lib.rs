@@ -0,0 +1,24 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Reads arbitrary bytes as a LIB, both parsed and opaque. Any error is
+//! fine; a panic, abort, or runaway allocation is not.
+
+#![no_main]
+
+use binrw::io::Cursor;
+use binrw::BinRead;
+use libfuzzer_sys::fuzz_target;
+
+use psyk::{OpaqueLIB, LIB};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(lib) = LIB::read(&mut Cursor::new(data)) {
+        let _ = lib.to_string();
+    }
+    if let Ok(lib) = OpaqueLIB::read(&mut Cursor::new(data)) {
+        for module in lib.modules() {
+            let _ = module.parse_obj();
+        }
+    }
+});
@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Reads arbitrary bytes as an OBJ. Any error is fine; a panic, abort, or
+//! runaway allocation is not.
+
+#![no_main]
+
+use binrw::io::Cursor;
+use binrw::BinRead;
+use libfuzzer_sys::fuzz_target;
+
+use psyk::OBJ;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(obj) = OBJ::read(&mut Cursor::new(data)) {
+        let _ = obj.to_string();
+    }
+});
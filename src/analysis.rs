@@ -0,0 +1,343 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Static checks of the code in an [OBJ].
+//!
+//! Code assembled for PSY-Q leaves every absolute address for the linker to
+//! fill in with a [Patch](super::Patch). An instruction that encodes an
+//! absolute address without a patch will link without complaint and then
+//! jump or load from the wrong place at run time. [unpatched_addresses]
+//! looks for them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use super::{disassemble, CpuType, Section, OBJ};
+
+/// How likely a [Suspect] is to be a bug.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The instruction may encode an address, or it may be a constant.
+    Medium,
+    /// The instruction almost certainly encodes an address.
+    High,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Medium => write!(f, "medium"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Why an instruction is suspected of needing a patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SuspectKind {
+    /// A `j` or `jal` whose target is outside of the code in the OBJ.
+    JumpOutside { target: u32, code_size: u32 },
+    /// A `j` or `jal` whose target is inside the code in the OBJ. Without a
+    /// patch the target isn't relocated with the section.
+    JumpInside { target: u32 },
+    /// A `lui` of a PS1 RAM address followed by an unpatched `addiu`, load,
+    /// or store completing the address.
+    HiLoPair { address: u32, lo_offset: u32 },
+    /// A `lui` of a PS1 RAM address without a matching low half.
+    LoneHi { hi: u16 },
+}
+
+impl fmt::Display for SuspectKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::JumpOutside { target, code_size } => write!(
+                f,
+                "jump target {target:#x} is outside of the {code_size:#x} bytes of code"
+            ),
+            Self::JumpInside { target } => {
+                write!(f, "jump target {target:#x} is not relocated")
+            }
+            Self::HiLoPair { address, lo_offset } => write!(
+                f,
+                "address {address:#010x} is completed at offset {lo_offset:#x} without a patch"
+            ),
+            Self::LoneHi { hi } => write!(f, "upper half {hi:#06x} looks like a RAM address"),
+        }
+    }
+}
+
+/// An instruction which appears to encode an absolute address but isn't
+/// covered by a patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suspect {
+    /// The section the instruction is in.
+    pub section: u16,
+    /// The offset of the instruction from the start of its section.
+    pub offset: u32,
+    /// The instruction word.
+    pub word: u32,
+    /// The disassembled instruction.
+    pub instruction: String,
+    pub kind: SuspectKind,
+    pub severity: Severity,
+}
+
+impl fmt::Display for Suspect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: section {} offset {:#06x}: {:08x} {}: {}",
+            self.severity, self.section, self.offset, self.word, self.instruction, self.kind
+        )
+    }
+}
+
+/// The code of one section with the offsets covered by patches.
+#[derive(Default)]
+struct SectionCode {
+    /// Words by offset from the start of the section.
+    words: BTreeMap<u32, u32>,
+    /// Word offsets covered by a patch.
+    patched: HashSet<u32>,
+    /// The size of the section, including uninitialized data.
+    size: u32,
+    /// The offset of the most recent code, which patches are relative to.
+    base: u32,
+}
+
+/// The base register and signed immediate of an `addiu`, load, or store.
+fn lo_half(word: u32) -> Option<(u32, i16)> {
+    match word >> 26 {
+        // addiu, loads and stores, including coprocessors
+        0x09 | 0x20..=0x26 | 0x28..=0x2b | 0x2e | 0x30..=0x33 | 0x38..=0x3b => {
+            Some(((word >> 21) & 0x1f, word as u16 as i16))
+        }
+        _ => None,
+    }
+}
+
+/// How many instructions after a `lui` to look for its low half.
+const PAIR_WINDOW: u32 = 8;
+
+/// Finds instructions in the MIPS code of `obj` that appear to encode
+/// absolute addresses without a patch:
+///
+/// * `j` and `jal` - [Severity::High] when the target is outside of the
+///   code in `obj`, otherwise [Severity::Medium].
+/// * `lui` of an address in PS1 RAM (`0x8000`-`0x801f`) followed by an
+///   unpatched `addiu`, load, or store using the same register -
+///   [Severity::High].
+/// * any other such `lui` - [Severity::Medium].
+///
+/// OBJs for other processors are not checked.
+pub fn unpatched_addresses(obj: &OBJ) -> Vec<Suspect> {
+    let is_mips = obj.sections().iter().all(|s| match s {
+        Section::CPU(cpu) => *cpu == CpuType::MipsR3000,
+        _ => true,
+    });
+    if !is_mips {
+        return Vec::new();
+    }
+
+    let mut sections: BTreeMap<u16, SectionCode> = BTreeMap::new();
+    let mut current = 0;
+    for section in obj.sections() {
+        match section {
+            Section::SectionSwitch(id) => current = *id,
+            Section::Code(code) => {
+                let code_section = sections.entry(current).or_default();
+                code_section.base = code_section.size;
+                // deferred code has no words to check
+                let words = code.code().unwrap_or_default();
+                for (i, word) in words.chunks_exact(4).enumerate() {
+                    let word = u32::from_le_bytes(word.try_into().expect("word"));
+                    code_section
+                        .words
+                        .insert(code_section.base + i as u32 * 4, word);
+                }
+                code_section.size += code.size() as u32;
+            }
+            Section::BSS(size) => sections.entry(current).or_default().size += size,
+            Section::Patch(patch) => {
+                let code_section = sections.entry(current).or_default();
+                let offset = code_section.base + patch.offset as u32;
+                code_section.patched.insert(offset & !3);
+            }
+            _ => (),
+        }
+    }
+
+    let code_size = sections.values().map(|s| s.size).sum::<u32>();
+    let mut suspects = Vec::new();
+    for (id, code) in &sections {
+        let mut suspect = |offset: u32, word: u32, kind: SuspectKind, severity: Severity| {
+            suspects.push(Suspect {
+                section: *id,
+                offset,
+                word,
+                instruction: disassemble(word)
+                    .map(|asm| asm.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .unwrap_or_else(|| format!(".word {word:#010x}")),
+                kind,
+                severity,
+            })
+        };
+
+        for (offset, word) in &code.words {
+            let (offset, word) = (*offset, *word);
+            if code.patched.contains(&offset) {
+                continue;
+            }
+            match word >> 26 {
+                // j, jal
+                0x02 | 0x03 => {
+                    let target = (word & 0x03ff_ffff) << 2;
+                    if target >= code_size {
+                        suspect(
+                            offset,
+                            word,
+                            SuspectKind::JumpOutside { target, code_size },
+                            Severity::High,
+                        );
+                    } else {
+                        suspect(
+                            offset,
+                            word,
+                            SuspectKind::JumpInside { target },
+                            Severity::Medium,
+                        );
+                    }
+                }
+                // lui
+                0x0f if (0x8000..=0x801f).contains(&(word as u16)) => {
+                    let hi = word as u16;
+                    let register = (word >> 16) & 0x1f;
+                    let lo = (1..=PAIR_WINDOW)
+                        .map(|i| offset + i * 4)
+                        .filter_map(|o| code.words.get(&o).map(|w| (o, *w)))
+                        .find_map(|(o, w)| match lo_half(w) {
+                            Some((base, imm)) if base == register => Some((o, imm)),
+                            _ => None,
+                        });
+                    match lo {
+                        Some((lo_offset, imm)) if !code.patched.contains(&lo_offset) => {
+                            let address = ((hi as u32) << 16).wrapping_add(imm as i32 as u32);
+                            suspect(
+                                offset,
+                                word,
+                                SuspectKind::HiLoPair { address, lo_offset },
+                                Severity::High,
+                            );
+                        }
+                        Some(_) => (),
+                        None => suspect(offset, word, SuspectKind::LoneHi { hi }, Severity::Medium),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    suspects
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Expression, Patch};
+
+    fn code(words: &[u32]) -> Section {
+        let code = words
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<u8>>();
+        Section::Code(Code {
+            size: code.len() as u16,
+            deferred: None,
+            code,
+        })
+    }
+
+    fn patch(tag: u8, offset: u16) -> Section {
+        Section::Patch(Patch {
+            tag,
+            offset,
+            expression: Expression::SymbolAddressIndex(2),
+        })
+    }
+
+    // lui $v0, 0x8001 ; lw $v0, 0x1234($v0)
+    const LUI: u32 = 0x3c02_8001;
+    const LW: u32 = 0x8c42_1234;
+    // jal 0x0
+    const JAL: u32 = 0x0c00_0000;
+    const NOP: u32 = 0;
+
+    fn obj(sections: Vec<Section>) -> OBJ {
+        let mut all = vec![Section::CPU(CpuType::MipsR3000), Section::SectionSwitch(1)];
+        all.extend(sections);
+        all.push(Section::NOP);
+        OBJ::new(all)
+    }
+
+    #[test]
+    fn test_patched() {
+        let obj = obj(vec![
+            code(&[LUI, LW, JAL, NOP]),
+            patch(82, 0),
+            patch(84, 4),
+            patch(74, 8),
+        ]);
+        assert!(unpatched_addresses(&obj).is_empty());
+    }
+
+    #[test]
+    fn test_dropped_patches() {
+        // the jal targets 0x1000 and the hi/lo pair lost both patches
+        let obj = obj(vec![
+            code(&[NOP, NOP]),
+            code(&[LUI, NOP, LW, JAL | 0x400, NOP]),
+        ]);
+        let suspects = unpatched_addresses(&obj);
+        assert_eq!(2, suspects.len());
+        assert_eq!(
+            SuspectKind::HiLoPair {
+                address: 0x8001_1234,
+                lo_offset: 0x10
+            },
+            suspects[0].kind
+        );
+        assert_eq!(8, suspects[0].offset);
+        assert_eq!(Severity::High, suspects[1].severity);
+        assert_eq!(
+            "high: section 1 offset 0x0014: 0c000400 jal func_80001000: \
+            jump target 0x1000 is outside of the 0x1c bytes of code",
+            suspects[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_relative_patch_offsets() {
+        // patches are relative to the code that precedes them
+        let obj = obj(vec![
+            code(&[NOP, NOP]),
+            code(&[JAL, NOP]),
+            patch(74, 0),
+            code(&[LUI, NOP]),
+        ]);
+        let suspects = unpatched_addresses(&obj);
+        assert_eq!(1, suspects.len());
+        assert_eq!(SuspectKind::LoneHi { hi: 0x8001 }, suspects[0].kind);
+        assert_eq!(Severity::Medium, suspects[0].severity);
+        assert_eq!(0x10, suspects[0].offset);
+    }
+
+    #[test]
+    fn test_other_cpus() {
+        let obj = OBJ::new(vec![
+            Section::CPU(CpuType::Motorola68000),
+            code(&[JAL | 0x400]),
+            Section::NOP,
+        ]);
+        assert!(unpatched_addresses(&obj).is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Assembly source for an OBJ.
+//!
+//! [to_asm] writes an [OBJ] as source for `ASMPSX.EXE` or `ASPSX.EXE`.
+//! Each section declared by an [LNKHeader](crate::LNKHeader) is written in
+//! turn with everything placed in it. Symbols become labels, instructions
+//! are disassembled, and the operands the linker patches are written as the
+//! expressions it would have evaluated, e.g. `lui $v0, %hi(_stacksize)`.
+//!
+//! Words in `.text` sections are disassembled, unless the OBJ is for
+//! another CPU. Other sections, and code for other CPUs, are written as
+//! data. Locations referred to without a symbol, like branch targets and
+//! `sectbase(n)+offset` patches, are given generated labels.
+//!
+//! [XBSS](crate::XBSS) symbols are placed by the linker. They are written
+//! as space reserved at the end of their section, which is where `PSYLINK`
+//! puts them, so the source defines them rather than declaring them common.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::asm::AsmOptions;
+//! use psyk::io;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?;
+//! let source = obj.to_asm(&AsmOptions::default())?;
+//! assert!(source.contains("\txdef\tmain\n"));
+//! assert!(source.contains("\nmain:\n"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::error::{bail, Result};
+
+use crate::display::expr::{self, ExprStyle};
+use crate::{reloc, CpuType, Expression, LNKHeader, Patch, Section, OBJ};
+
+/// Options for [to_asm].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AsmOptions {
+    /// Follow each instruction or data word with a comment giving its
+    /// offset in the section and its bytes as they appear in the OBJ.
+    pub comments: bool,
+}
+
+/// What is placed in a section, in order.
+enum Piece<'a> {
+    Code(&'a [u8]),
+    Bss(u32),
+}
+
+/// Everything placed in one section.
+#[derive(Default)]
+struct Contents<'a> {
+    pieces: Vec<Piece<'a>>,
+    size: u32,
+    /// Patches by their offset in the section.
+    patches: BTreeMap<u32, &'a Patch>,
+    /// XBSS symbols with their sizes, placed after everything else.
+    xbss: Vec<(String, u32)>,
+}
+
+struct Writer<'a> {
+    headers: Vec<&'a LNKHeader>,
+    contents: HashMap<u16, Contents<'a>>,
+    /// Names of symbols by number.
+    symbols: HashMap<u16, String>,
+    /// Label names by section and offset.
+    labels: BTreeMap<(u16, u32), Vec<String>>,
+    disassemble: bool,
+    options: &'a AsmOptions,
+}
+
+/// Writes `obj` as assembly source. It is an error for code, data, or a
+/// patch to come before the first section switch, or to be placed in a
+/// section without an [LNKHeader](crate::LNKHeader).
+pub fn to_asm(obj: &OBJ, options: &AsmOptions) -> Result<String> {
+    let writer = Writer::new(obj, options)?;
+    let mut out = String::new();
+    writer.write(&mut out, obj);
+    Ok(out)
+}
+
+impl<'a> Writer<'a> {
+    fn new(obj: &'a OBJ, options: &'a AsmOptions) -> Result<Self> {
+        let mut headers = Vec::new();
+        let mut contents: HashMap<u16, Contents> = HashMap::new();
+        let mut current = None;
+        let mut code_start = HashMap::new();
+        let mut cpu = None;
+        for section in obj.sections() {
+            match section {
+                Section::CPU(id) => cpu = Some(*id),
+                Section::LNKHeader(header) => headers.push(header),
+                Section::SectionSwitch(id) => current = Some(*id),
+                Section::Code(code) => {
+                    let section = contents_of(&mut contents, current)?;
+                    code_start.insert(current, section.size);
+                    section.pieces.push(Piece::Code(code.code()?));
+                    section.size += code.size() as u32;
+                }
+                Section::BSS(size) => {
+                    let section = contents_of(&mut contents, current)?;
+                    section.pieces.push(Piece::Bss(*size));
+                    section.size += size;
+                }
+                Section::Patch(patch) => {
+                    let start = code_start.get(&current).copied().unwrap_or_default();
+                    contents_of(&mut contents, current)?
+                        .patches
+                        .insert(start + patch.offset() as u32, patch);
+                }
+                Section::XBSS(xbss) => contents
+                    .entry(xbss.section())
+                    .or_default()
+                    .xbss
+                    .push((xbss.name(), xbss.size())),
+                _ => (),
+            }
+        }
+        for id in contents.keys() {
+            if !headers.iter().any(|h| h.section() == *id) {
+                bail!("section {id:x} is used but not declared");
+            }
+        }
+
+        let symbol_table = obj.symbols();
+        let mut symbols = HashMap::new();
+        let mut labels: BTreeMap<(u16, u32), Vec<String>> = BTreeMap::new();
+        for symbol in symbol_table.defined() {
+            if let Some(number) = symbol.number {
+                symbols.insert(number, symbol.name.clone());
+            }
+            if let Some(offset) = symbol.offset {
+                let names = labels.entry((symbol.section, offset)).or_default();
+                if !names.contains(&symbol.name) {
+                    names.push(symbol.name.clone());
+                }
+            }
+        }
+        for symbol in symbol_table.undefined() {
+            symbols.insert(symbol.number, symbol.name.clone());
+        }
+
+        let mut writer = Self {
+            headers,
+            contents,
+            symbols,
+            labels,
+            disassemble: cpu.is_none_or(|cpu| cpu == CpuType::MipsR3000),
+            options,
+        };
+        writer.generate_labels();
+        Ok(writer)
+    }
+
+    /// Labels locations that are referred to by branches or by patches
+    /// relative to a section, but which have no symbol.
+    fn generate_labels(&mut self) {
+        let mut targets = BTreeSet::new();
+        for (id, section) in &self.contents {
+            for patch in section.patches.values() {
+                if let Some(target) = section_relative(patch.expression()) {
+                    targets.insert(target);
+                }
+            }
+            if !self.is_text(*id) {
+                continue;
+            }
+            let mut offset = 0;
+            for piece in &section.pieces {
+                match piece {
+                    Piece::Code(code) => {
+                        for (i, word) in code.chunks_exact(4).enumerate() {
+                            let at = offset + i as u32 * 4;
+                            let word = u32::from_le_bytes(word.try_into().expect("4 bytes"));
+                            if let Some(target) = branch_target(word, at, section.size) {
+                                targets.insert((*id, target));
+                            }
+                        }
+                        offset += code.len() as u32;
+                    }
+                    Piece::Bss(size) => offset += size,
+                }
+            }
+        }
+        for (id, offset) in targets {
+            self.labels
+                .entry((id, offset))
+                .or_insert_with(|| vec![format!("L{id:x}_{offset:x}")]);
+        }
+    }
+
+    fn is_text(&self, id: u16) -> bool {
+        self.disassemble
+            && self
+                .headers
+                .iter()
+                .any(|h| h.section() == id && h.type_name().starts_with(".text"))
+    }
+
+    fn write(&self, out: &mut String, obj: &OBJ) {
+        for section in obj.sections() {
+            if let Section::XDEF(xdef) = section {
+                writeln!(out, "\txdef\t{}", xdef.symbol_name()).expect("write");
+            } else if let Section::XBSS(xbss) = section {
+                writeln!(out, "\txdef\t{}", xbss.name()).expect("write");
+            }
+        }
+        for section in obj.sections() {
+            if let Section::XREF(xref) = section {
+                writeln!(out, "\txref\t{}", xref.symbol_name()).expect("write");
+            }
+        }
+
+        for header in &self.headers {
+            let id = header.section();
+            writeln!(out).expect("write");
+            writeln!(out, "\tsection\t{}", header.type_name()).expect("write");
+            writeln!(out, "\talign\t{}", header.align()).expect("write");
+            let Some(section) = self.contents.get(&id) else {
+                self.write_labels(out, id, 0, u32::MAX);
+                continue;
+            };
+            let text = self.is_text(id);
+            let mut offset = 0;
+            for piece in &section.pieces {
+                match piece {
+                    Piece::Code(code) if text => self.write_code(out, id, section, offset, code),
+                    Piece::Code(code) => self.write_data(out, id, section, offset, code),
+                    Piece::Bss(size) => self.write_bss(out, id, offset, *size),
+                }
+                offset += match piece {
+                    Piece::Code(code) => code.len() as u32,
+                    Piece::Bss(size) => *size,
+                };
+            }
+            self.write_labels(out, id, offset, u32::MAX);
+            for (name, size) in &section.xbss {
+                writeln!(out, "{name}:").expect("write");
+                writeln!(out, "\tds.b\t{size}").expect("write");
+            }
+        }
+    }
+
+    /// Writes the labels in section `id` from offset `start` to `end`,
+    /// inclusive.
+    fn write_labels(&self, out: &mut String, id: u16, start: u32, end: u32) {
+        for names in self.labels.range((id, start)..=(id, end)).map(|(_, n)| n) {
+            for name in names {
+                writeln!(out, "{name}:").expect("write");
+            }
+        }
+    }
+
+    /// Writes a line of source, with a comment if requested.
+    fn write_line(&self, out: &mut String, line: &str, offset: u32, bytes: &[u8]) {
+        if self.options.comments {
+            let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            writeln!(out, "\t{line:<40}; {offset:06x}: {hex}").expect("write");
+        } else {
+            writeln!(out, "\t{line}").expect("write");
+        }
+    }
+
+    fn write_code(&self, out: &mut String, id: u16, section: &Contents, start: u32, code: &[u8]) {
+        for (i, bytes) in code.chunks(4).enumerate() {
+            let offset = start + i as u32 * 4;
+            self.write_labels(out, id, offset, offset);
+            let Ok(bytes4) = <[u8; 4]>::try_from(bytes) else {
+                self.write_bytes(out, offset, bytes);
+                continue;
+            };
+            let word = u32::from_le_bytes(bytes4);
+            let patch = section.patches.get(&offset);
+            let operand = match patch {
+                Some(patch) => match patch.tag() {
+                    reloc::HI16 => Some(format!("%hi({})", self.expression(patch.expression()))),
+                    reloc::LO16 => Some(format!("%lo({})", self.expression(patch.expression()))),
+                    reloc::JUMP => Some(self.expression(patch.expression())),
+                    _ => None,
+                },
+                None => branch_target(word, offset, section.size)
+                    .and_then(|target| self.labels.get(&(id, target)))
+                    .map(|names| names[0].clone()),
+            };
+            let line = match (patch, &operand) {
+                (Some(patch), None) => self.patched_word(word, patch),
+                _ => crate::disassemble_with_operand(word, operand.as_deref())
+                    .unwrap_or_else(|| format!("dw\t${word:08x}")),
+            };
+            self.write_line(out, &line, offset, bytes);
+        }
+    }
+
+    fn write_data(&self, out: &mut String, id: u16, section: &Contents, start: u32, data: &[u8]) {
+        let mut i = 0;
+        while i < data.len() {
+            let offset = start + i as u32;
+            self.write_labels(out, id, offset, offset);
+            let next_label = self
+                .labels
+                .range((id, offset + 1)..=(id, u32::MAX))
+                .next()
+                .map_or(u32::MAX, |((_, at), _)| *at);
+            let word = data.get(i..i + 4).filter(|_| {
+                self.disassemble && offset.is_multiple_of(4) && offset + 4 <= next_label
+            });
+            if let Some(bytes) = word {
+                let word = u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+                let line = match section.patches.get(&offset) {
+                    Some(patch) => self.patched_word(word, patch),
+                    None => format!("dw\t${word:08x}"),
+                };
+                self.write_line(out, &line, offset, bytes);
+                i += 4;
+                continue;
+            }
+            // bytes up to the next label, patch, or aligned word
+            let mut end = (i + 8).min(data.len());
+            end = end.min((next_label - start) as usize);
+            if self.disassemble {
+                end = end.min(i + 4 - offset as usize % 4);
+            }
+            if let Some((at, _)) = section.patches.range(offset + 1..start + end as u32).next() {
+                end = (at - start) as usize;
+            }
+            self.write_bytes(out, offset, &data[i..end]);
+            i = end;
+        }
+    }
+
+    fn write_bytes(&self, out: &mut String, offset: u32, bytes: &[u8]) {
+        let values = bytes
+            .iter()
+            .map(|b| format!("${b:02x}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.write_line(out, &format!("dc.b\t{values}"), offset, bytes);
+    }
+
+    fn write_bss(&self, out: &mut String, id: u16, start: u32, size: u32) {
+        let end = start + size;
+        let mut offset = start;
+        while offset < end {
+            self.write_labels(out, id, offset, offset);
+            let next = self
+                .labels
+                .range((id, offset + 1)..(id, end))
+                .next()
+                .map_or(end, |((_, at), _)| *at);
+            writeln!(out, "\tds.b\t{}", next - offset).expect("write");
+            offset = next;
+        }
+    }
+
+    /// A word written by a patch which can't be written as an operand.
+    fn patched_word(&self, word: u32, patch: &Patch) -> String {
+        let expression = self.expression(patch.expression());
+        if patch.tag() == reloc::WORD {
+            format!("dw\t{expression}")
+        } else {
+            format!(
+                "dw\t${word:08x}\t; patch type {} with {expression}",
+                patch.tag()
+            )
+        }
+    }
+
+    /// Writes `e` with symbols named and section-relative addresses
+    /// replaced by labels.
+    fn expression(&self, e: &Expression) -> String {
+        if let Some((id, offset)) = section_relative(e) {
+            if let Some(names) = self.labels.get(&(id, offset)) {
+                return names[0].clone();
+            }
+        }
+        let symbol = |number| self.symbols.get(&number).cloned();
+        let section = |id| {
+            self.headers
+                .iter()
+                .find(|h| h.section() == id)
+                .map(|h| h.type_name())
+        };
+        expr::render(
+            e,
+            &ExprStyle {
+                symbol_name: Some(&symbol),
+                section_name: Some(&section),
+                ..ExprStyle::minimal()
+            },
+        )
+    }
+}
+
+fn contents_of<'a, 'b>(
+    contents: &'b mut HashMap<u16, Contents<'a>>,
+    section: Option<u16>,
+) -> Result<&'b mut Contents<'a>> {
+    let Some(section) = section else {
+        bail!("code or data comes before the first section switch");
+    };
+    Ok(contents.entry(section).or_default())
+}
+
+/// The section and offset `e` refers to, if it is `sectbase(n)` or
+/// `sectbase(n)+offset`.
+fn section_relative(e: &Expression) -> Option<(u16, u32)> {
+    match e {
+        Expression::SectionAddressIndex(id) => Some((*id, 0)),
+        Expression::Add(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+            (Expression::SectionAddressIndex(id), Expression::Constant(offset))
+            | (Expression::Constant(offset), Expression::SectionAddressIndex(id)) => {
+                Some((*id, *offset))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The offset a branch at `offset` goes to, if `word` is a branch whose
+/// target is in a section of `size` bytes.
+fn branch_target(word: u32, offset: u32, size: u32) -> Option<u32> {
+    let target = offset.checked_add_signed(crate::branch_offset(word)?)?;
+    (target < size).then_some(target)
+}
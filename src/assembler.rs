@@ -0,0 +1,521 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Build a [LIB] from modules that come from different places.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::assembler::{LibAssembler, ModuleOptions};
+//! use psyk::io;
+//!
+//! # fn main() -> Result<()> {
+//! let libc = io::read_lib(Path::new("LIBC.LIB"))?;
+//! let lib = LibAssembler::new()
+//!     .push_path(Path::new("MAIN.OBJ"))
+//!     .push_path_with(
+//!         Path::new("util_v2.obj"),
+//!         ModuleOptions {
+//!             name: Some("UTIL".into()),
+//!             ..Default::default()
+//!         },
+//!     )
+//!     .push_from_lib(&libc, "SPRINTF")
+//!     .assemble()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::{bail, Result};
+use binrw::io::Cursor;
+use binrw::BinRead;
+
+use super::io::{self, read_bytes};
+use super::symbols;
+use super::{path_to_module_name_with_case, Module, ModuleMetadata, NameCase, LIB, OBJ};
+
+/// Overrides for the metadata of a single module.
+///
+/// Any field left as `None` is derived from the module's source.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleOptions {
+    /// The module name. Names longer than 8 bytes are truncated.
+    pub name: Option<String>,
+    /// The module creation time.
+    pub timestamp: Option<SystemTime>,
+    /// The exports listed in the module metadata. Every export must be
+    /// defined by the module's [OBJ]. Symbols its OBJ declares with an XBSS
+    /// are listed as [common](crate::Export::is_common) with or without a `*`.
+    pub exports: Option<Vec<String>>,
+}
+
+/// What [LibAssembler] does with a module named like one already
+/// assembled. Names are compared without regard to case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameCollision {
+    /// Fail, naming the module and where it came from.
+    #[default]
+    Error,
+    /// Leave the later module out.
+    FirstWins,
+    /// Number the later modules, shortening their names to fit, e.g.
+    /// `UTIL1`, `UTIL2`, or `LONGNAM1`.
+    Suffix,
+}
+
+/// How [LibAssembler] names modules added from files and resolves
+/// modules that share a name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamePolicy {
+    /// How a module's name is derived from its file name. Names given
+    /// with [ModuleOptions::name] are used as they are.
+    pub case: NameCase,
+    pub on_collision: NameCollision,
+}
+
+/// Where an assembled module came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModuleSource {
+    /// An OBJ file on disk.
+    Path(PathBuf),
+    /// An [OBJ] provided in memory.
+    Obj,
+    /// Serialized OBJ bytes.
+    Raw,
+    /// A module copied from another [LIB], identified by its original name.
+    Lib(String),
+}
+
+impl fmt::Display for ModuleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Obj => write!(f, "<obj>"),
+            Self::Raw => write!(f, "<raw>"),
+            Self::Lib(name) => write!(f, "<lib>:{name}"),
+        }
+    }
+}
+
+/// The name and source of each module in an assembled [LIB], in archive
+/// order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssemblyReport {
+    pub modules: Vec<(String, ModuleSource)>,
+}
+
+enum Pending {
+    Path(PathBuf, ModuleOptions),
+    Obj(String, OBJ, ModuleOptions),
+    Raw(String, Vec<u8>, ModuleOptions),
+    Lib(Option<Module>, String),
+}
+
+/// Collects modules from paths, in-memory [OBJ]s, raw bytes, and other
+/// [LIB]s, then assembles them into a single [LIB] in the order they were
+/// pushed.
+///
+/// Nothing is read or validated until [LibAssembler::assemble] is called.
+/// Assembly fails if two modules share a name, unless a [NamePolicy] says
+/// otherwise, or if a module lists an export its [OBJ] doesn't define.
+#[derive(Default)]
+pub struct LibAssembler {
+    pending: Vec<Pending>,
+    timestamp: Option<SystemTime>,
+    names: NamePolicy,
+}
+
+impl LibAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `timestamp` for every module without an explicit
+    /// [ModuleOptions::timestamp], producing reproducible output. Modules
+    /// copied from another [LIB] keep their original metadata.
+    pub fn timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Names modules and resolves name collisions with `names` rather than
+    /// the default, which uppercases file names and fails on a collision.
+    pub fn name_policy(mut self, names: NamePolicy) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Adds the OBJ file at `path`. The module name is derived from the file
+    /// name.
+    pub fn push_path(self, path: &Path) -> Self {
+        self.push_path_with(path, ModuleOptions::default())
+    }
+
+    /// Adds the OBJ file at `path` with metadata overrides.
+    pub fn push_path_with(mut self, path: &Path, options: ModuleOptions) -> Self {
+        self.pending
+            .push(Pending::Path(path.to_path_buf(), options));
+        self
+    }
+
+    /// Adds an in-memory [OBJ] named `name`.
+    pub fn push_obj(mut self, name: &str, obj: OBJ, options: ModuleOptions) -> Self {
+        self.pending
+            .push(Pending::Obj(name.to_string(), obj, options));
+        self
+    }
+
+    /// Adds a serialized OBJ named `name`.
+    pub fn push_raw(mut self, name: &str, bytes: &[u8], options: ModuleOptions) -> Self {
+        self.pending
+            .push(Pending::Raw(name.to_string(), bytes.to_vec(), options));
+        self
+    }
+
+    /// Copies the module named `name` from `lib`, including its metadata.
+    pub fn push_from_lib(mut self, lib: &LIB, name: &str) -> Self {
+        let module = lib
+            .modules()
+            .iter()
+            .find(|m| symbols::module_eq(&m.name(), name))
+            .cloned();
+        self.pending.push(Pending::Lib(module, name.to_string()));
+        self
+    }
+
+    /// Builds the [LIB].
+    pub fn assemble(self) -> Result<LIB> {
+        Ok(self.assemble_with_report()?.0)
+    }
+
+    /// Builds the [LIB] along with a report of where each module came from.
+    pub fn assemble_with_report(self) -> Result<(LIB, AssemblyReport)> {
+        if self.pending.is_empty() {
+            bail!("A LIB must contain at least one module");
+        }
+
+        let mut modules = Vec::with_capacity(self.pending.len());
+        let mut report = AssemblyReport::default();
+        let mut names = HashSet::new();
+
+        for pending in self.pending {
+            let (mut module, source) = match pending {
+                Pending::Path(path, options) => {
+                    let bytes = read_bytes(&path)?;
+                    let obj = OBJ::read(&mut Cursor::new(&bytes))?;
+                    let default_name = String::from_utf8_lossy(&path_to_module_name_with_case(
+                        &path,
+                        self.names.case,
+                    ))
+                    .trim_end()
+                    .to_string();
+                    let module = build_module(
+                        obj,
+                        bytes.len(),
+                        default_name,
+                        options,
+                        self.timestamp.or_else(|| file_created(&path)),
+                    )?;
+                    (module, ModuleSource::Path(path))
+                }
+                Pending::Obj(name, obj, options) => {
+                    let size = io::serialized_size(&obj)? as usize;
+                    let module = build_module(obj, size, name, options, self.timestamp)?;
+                    (module, ModuleSource::Obj)
+                }
+                Pending::Raw(name, bytes, options) => {
+                    let obj = OBJ::read(&mut Cursor::new(&bytes))?;
+                    let module = build_module(obj, bytes.len(), name, options, self.timestamp)?;
+                    (module, ModuleSource::Raw)
+                }
+                Pending::Lib(module, name) => {
+                    let Some(module) = module else {
+                        bail!("Module {name} not found in LIB");
+                    };
+                    (module, ModuleSource::Lib(name))
+                }
+            };
+
+            let mut name = module.name();
+            if names.contains(&name.to_ascii_uppercase()) {
+                match self.names.on_collision {
+                    NameCollision::Error => bail!("Duplicate module name {name} from {source}"),
+                    NameCollision::FirstWins => continue,
+                    NameCollision::Suffix => {
+                        name = suffixed(&name, &names);
+                        module.set_name(&name)?;
+                    }
+                }
+            }
+            names.insert(name.to_ascii_uppercase());
+            report.modules.push((name, source));
+            modules.push(module);
+        }
+
+        Ok((LIB::new(modules), report))
+    }
+}
+
+/// `name` followed by the lowest number that makes it unlike every name in
+/// `taken`, which are uppercase, shortened to fit in 8 bytes.
+fn suffixed(name: &str, taken: &HashSet<String>) -> String {
+    (1..)
+        .map(|n: u32| {
+            let number = n.to_string();
+            let mut end = name.len().min(8 - number.len());
+            while !name.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}{number}", &name[..end])
+        })
+        .find(|candidate| !taken.contains(&candidate.to_ascii_uppercase()))
+        .expect("a free number")
+}
+
+fn file_created(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.created()).ok()
+}
+
+fn build_module(
+    obj: OBJ,
+    size: usize,
+    name: String,
+    options: ModuleOptions,
+    timestamp: Option<SystemTime>,
+) -> Result<Module> {
+    let name = options.name.unwrap_or(name);
+    let defined = obj.exports_psylib_order()?;
+    let exports = match options.exports {
+        Some(exports) => exports
+            .iter()
+            .map(|listed| {
+                let symbol = listed.strip_prefix('*').unwrap_or(listed);
+                match defined.iter().find(|e| e.symbol_name() == symbol) {
+                    Some(export) => Ok(export.clone()),
+                    None => {
+                        bail!("Module {name} lists export {listed} which its OBJ does not define")
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => defined,
+    };
+    let created = options
+        .timestamp
+        .or(timestamp)
+        .unwrap_or_else(SystemTime::now);
+
+    let metadata = ModuleMetadata::new(name, created, size as u32, exports)?;
+    Ok(Module::new(obj, metadata))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FromPSYQTimestamp, Section, XDEF};
+    use binrw::BinWrite;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn obj(exports: &[&str]) -> OBJ {
+        let mut sections = exports
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                Section::XDEF(XDEF {
+                    number: i as u16 + 1,
+                    section: 1,
+                    offset: 0,
+                    symbol_name_size: name.len() as u8,
+                    symbol_name: name.as_bytes().to_vec(),
+                })
+            })
+            .collect::<Vec<Section>>();
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    fn bytes(obj: &OBJ) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        obj.write(&mut writer).unwrap();
+        writer.into_inner()
+    }
+
+    fn time(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_assemble_mixed_sources() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let a = dir.path().join("a.obj");
+        let b = dir.path().join("b_long_name.obj");
+        std::fs::write(&a, bytes(&obj(&["a"])))?;
+        std::fs::write(&b, bytes(&obj(&["b1", "b2"])))?;
+
+        let other = LibAssembler::new()
+            .push_obj("SPRINTF", obj(&["sprintf"]), ModuleOptions::default())
+            .timestamp(time(820_000_000))
+            .assemble()?;
+
+        let (lib, report) = LibAssembler::new()
+            .timestamp(time(830_000_000))
+            .push_path(&a)
+            .push_path_with(
+                &b,
+                ModuleOptions {
+                    name: Some("B".into()),
+                    timestamp: Some(time(840_000_000)),
+                    exports: Some(vec!["b2".into()]),
+                },
+            )
+            .push_obj("MEM", obj(&["mem"]), ModuleOptions::default())
+            .push_raw("RAW", &bytes(&obj(&["raw"])), ModuleOptions::default())
+            .push_from_lib(&other, "SPRINTF")
+            .assemble_with_report()?;
+
+        let names = lib
+            .modules()
+            .iter()
+            .map(|m| m.name())
+            .collect::<Vec<String>>();
+        assert_eq!(vec!["A", "B", "MEM", "RAW", "SPRINTF"], names);
+        assert_eq!(
+            vec![
+                ("A".to_string(), ModuleSource::Path(a)),
+                ("B".to_string(), ModuleSource::Path(b)),
+                ("MEM".to_string(), ModuleSource::Obj),
+                ("RAW".to_string(), ModuleSource::Raw),
+                ("SPRINTF".to_string(), ModuleSource::Lib("SPRINTF".into())),
+            ],
+            report.modules
+        );
+
+        let modules = lib.modules();
+        assert_eq!(vec!["b2"], modules[1].exports());
+        assert_eq!(vec!["b1", "b2"], modules[1].object().exports());
+        assert_eq!(vec!["raw"], modules[3].exports());
+        assert_eq!(
+            time(840_000_000).to_psyq_timestamp(),
+            modules[1].metadata.created
+        );
+        for i in [0, 2, 3] {
+            assert_eq!(
+                time(830_000_000).to_psyq_timestamp(),
+                modules[i].metadata.created
+            );
+        }
+        assert_eq!(other.modules()[0], modules[4]);
+
+        // sizes match what would have been read from disk
+        for module in modules {
+            assert_eq!(
+                module.metadata.size,
+                module.metadata.offset + bytes(module.object()).len() as u32
+            );
+        }
+
+        let mut writer = Cursor::new(Vec::new());
+        lib.write(&mut writer)?;
+        let written = writer.into_inner();
+        let read = LIB::read(&mut Cursor::new(&written))?;
+        assert_eq!(lib, read);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let duplicate = LibAssembler::new()
+            .push_obj("A", obj(&["a"]), ModuleOptions::default())
+            .push_obj("A", obj(&["b"]), ModuleOptions::default())
+            .assemble();
+        assert_eq!(
+            "Duplicate module name A from <obj>",
+            duplicate.unwrap_err().to_string()
+        );
+
+        let exports = LibAssembler::new()
+            .push_obj(
+                "A",
+                obj(&["a"]),
+                ModuleOptions {
+                    exports: Some(vec!["b".into()]),
+                    ..Default::default()
+                },
+            )
+            .assemble();
+        assert_eq!(
+            "Module A lists export b which its OBJ does not define",
+            exports.unwrap_err().to_string()
+        );
+
+        let other = LIB::new(vec![]);
+        let missing = LibAssembler::new().push_from_lib(&other, "NOPE").assemble();
+        assert_eq!(
+            "Module NOPE not found in LIB",
+            missing.unwrap_err().to_string()
+        );
+
+        assert!(LibAssembler::new().assemble().is_err());
+        assert!(LibAssembler::new()
+            .push_raw("BAD", b"LIB", ModuleOptions::default())
+            .assemble()
+            .is_err());
+    }
+
+    #[test]
+    fn test_name_policy() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let lower = dir.path().join("util.obj");
+        let upper = dir.path().join("more").join("UTIL.OBJ");
+        std::fs::create_dir(upper.parent().unwrap())?;
+        std::fs::write(&lower, bytes(&obj(&["a"])))?;
+        std::fs::write(&upper, bytes(&obj(&["b"])))?;
+        let names = |policy: NamePolicy| -> Result<Vec<String>> {
+            let (_, report) = LibAssembler::new()
+                .name_policy(policy)
+                .push_path(&lower)
+                .push_path(&upper)
+                .push_obj("longname", obj(&["c"]), ModuleOptions::default())
+                .push_obj("LONGNAME", obj(&["d"]), ModuleOptions::default())
+                .push_obj("UTIL1", obj(&["e"]), ModuleOptions::default())
+                .assemble_with_report()?;
+            Ok(report.modules.into_iter().map(|(name, _)| name).collect())
+        };
+
+        // names that differ only in case collide
+        for case in [NameCase::Upper, NameCase::Preserve] {
+            let policy = NamePolicy {
+                case,
+                ..Default::default()
+            };
+            assert!(names(policy)
+                .unwrap_err()
+                .to_string()
+                .starts_with("Duplicate module name UTIL from"));
+        }
+
+        let policy = NamePolicy {
+            case: NameCase::Preserve,
+            on_collision: NameCollision::FirstWins,
+        };
+        assert_eq!(vec!["util", "longname", "UTIL1"], names(policy)?);
+
+        let policy = NamePolicy {
+            case: NameCase::Preserve,
+            on_collision: NameCollision::Suffix,
+        };
+        assert_eq!(
+            vec!["util", "UTIL1", "longname", "LONGNAM1", "UTIL11"],
+            names(policy)?
+        );
+        assert_eq!("UTIL2", suffixed("UTIL", &["UTIL1".to_string()].into()));
+        assert_eq!("👾☕1", suffixed("👾☕☕", &HashSet::new()));
+        Ok(())
+    }
+}
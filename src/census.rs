@@ -0,0 +1,211 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Counting which parts of the format a file uses.
+//!
+//! Some [Section] variants and [Expression] operators have never been seen
+//! in a real file and are implemented from `dumpobj` output alone. A
+//! [FormatCensus] counts every variant and operator in a file so tools can
+//! tell whether it relies on any of them before trusting the results.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::io;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+//! let census = lib.format_census();
+//! assert_eq!(2, census.total().sections["Code"]);
+//! assert!(census.total().uses_untested().is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::{Expression, Section, LIB, OBJ};
+
+/// [Section] variants that are untested.
+pub const UNTESTED_SECTIONS: &[&str] = &[
+    "RepeatByte",
+    "RepeatWord",
+    "RepeatLong",
+    "ProcedureCall",
+    "ProcedureDefinition",
+    "Repeat3Byte",
+];
+
+/// [Expression] operators that are untested.
+pub const UNTESTED_EXPRESSIONS: &[&str] = &[
+    "Bank",
+    "SectionOffset",
+    "Offset",
+    "GroupStart",
+    "GroupOffset",
+    "Segment",
+    "GroupOrg",
+];
+
+/// The number of times each [Section] variant and [Expression] operator
+/// appears in an [OBJ], keyed by their [kind](Section::kind).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatCensus {
+    pub sections: BTreeMap<&'static str, u64>,
+    /// Every expression is counted, including the operands of binary
+    /// expressions.
+    pub expressions: BTreeMap<&'static str, u64>,
+}
+
+impl FormatCensus {
+    /// Counts the sections and expressions of `obj`.
+    pub fn of_obj(obj: &OBJ) -> Self {
+        let mut census = Self::default();
+        for section in obj.sections() {
+            *census.sections.entry(section.kind()).or_default() += 1;
+            if let Section::Patch(patch) = section {
+                patch.expression().walk(&mut |e: &Expression| {
+                    *census.expressions.entry(e.kind()).or_default() += 1;
+                });
+            }
+        }
+        census
+    }
+
+    /// Adds the counts from `other` to this census.
+    pub fn merge(&mut self, other: &FormatCensus) {
+        for (kind, count) in &other.sections {
+            *self.sections.entry(kind).or_default() += count;
+        }
+        for (kind, count) in &other.expressions {
+            *self.expressions.entry(kind).or_default() += count;
+        }
+    }
+
+    /// Returns `true` if the census counted `kind` as either a section or
+    /// an expression.
+    pub fn uses(&self, kind: &str) -> bool {
+        self.sections.contains_key(kind) || self.expressions.contains_key(kind)
+    }
+
+    /// The untested sections and then the untested expressions that were
+    /// counted.
+    pub fn uses_untested(&self) -> Vec<&'static str> {
+        UNTESTED_SECTIONS
+            .iter()
+            .filter(|kind| self.sections.contains_key(*kind))
+            .chain(
+                UNTESTED_EXPRESSIONS
+                    .iter()
+                    .filter(|kind| self.expressions.contains_key(*kind)),
+            )
+            .copied()
+            .collect()
+    }
+}
+
+/// The [FormatCensus] of each module of a [LIB].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibCensus {
+    /// Each module's name and census, in order.
+    pub modules: Vec<(String, FormatCensus)>,
+}
+
+impl LibCensus {
+    /// Takes a census of every module of `lib`.
+    pub fn of_lib(lib: &LIB) -> Self {
+        Self {
+            modules: lib
+                .modules()
+                .iter()
+                .map(|m| (m.name(), m.object().format_census()))
+                .collect(),
+        }
+    }
+
+    /// The counts of every module together.
+    pub fn total(&self) -> FormatCensus {
+        let mut total = FormatCensus::default();
+        for (_, census) in &self.modules {
+            total.merge(census);
+        }
+        total
+    }
+
+    /// The names of the modules that use the section or expression `kind`.
+    pub fn modules_using(&self, kind: &str) -> Vec<&str> {
+        self.modules
+            .iter()
+            .filter(|(_, census)| census.uses(kind))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Module, ModuleMetadata, Patch};
+    use std::time::SystemTime;
+
+    fn patch(expression: Expression) -> Section {
+        Section::Patch(Patch {
+            tag: 16,
+            offset: 0,
+            expression,
+        })
+    }
+
+    #[test]
+    fn test_census() {
+        let obj = OBJ::new(vec![
+            Section::RepeatByte(4),
+            patch(Expression::Add(
+                Box::new(Expression::Bank(1)),
+                Box::new(Expression::Add(
+                    Box::new(Expression::Constant(4)),
+                    Box::new(Expression::SectionAddressIndex(1)),
+                )),
+            )),
+            patch(Expression::Constant(0)),
+            Section::NOP,
+        ]);
+        let census = obj.format_census();
+        assert_eq!(
+            BTreeMap::from([("NOP", 1), ("Patch", 2), ("RepeatByte", 1)]),
+            census.sections
+        );
+        assert_eq!(
+            BTreeMap::from([
+                ("Add", 2),
+                ("Bank", 1),
+                ("Constant", 2),
+                ("SectionAddressIndex", 1)
+            ]),
+            census.expressions
+        );
+        assert_eq!(vec!["RepeatByte", "Bank"], census.uses_untested());
+    }
+
+    #[test]
+    fn test_lib_census() {
+        let module = |name: &str, sections| {
+            let metadata =
+                ModuleMetadata::new(name.into(), SystemTime::now(), 0, vec![]).expect("metadata");
+            Module::new(OBJ::new(sections), metadata)
+        };
+        let lib = LIB::new(vec![
+            module("A", vec![Section::NOP]),
+            module("B", vec![Section::Repeat3Byte(1), Section::NOP]),
+            module("C", vec![patch(Expression::GroupOrg(1)), Section::NOP]),
+        ]);
+        let census = lib.format_census();
+        assert_eq!(3, census.total().sections["NOP"]);
+        assert_eq!(
+            vec!["Repeat3Byte", "GroupOrg"],
+            census.total().uses_untested()
+        );
+        assert_eq!(vec!["B"], census.modules_using("Repeat3Byte"));
+        assert_eq!(vec!["C"], census.modules_using("GroupOrg"));
+        assert_eq!(vec!["A", "B", "C"], census.modules_using("NOP"));
+    }
+}
@@ -0,0 +1,294 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Build chronologies from module timestamps.
+//!
+//! Every module in a [LIB](super::LIB) records when its OBJ was built.
+//! Across an SDK release those timestamps show the order the libraries were
+//! built in, which modules were rebuilt late, and the batches the build was
+//! run in.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::{chronology, io};
+//!
+//! # fn main() -> Result<()> {
+//! let path = Path::new("LIBSN.LIB");
+//! let entries = io::read_lib_metadata(path)?
+//!     .map(|metadata| Ok((path.to_path_buf(), metadata?)))
+//!     .collect::<Result<Vec<_>>>()?;
+//! let report = chronology::analyze(entries.into_iter());
+//! for cluster in &report.clusters {
+//!     println!("{} modules built from {} to {}", cluster.modules, cluster.start, cluster.end);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use super::ModuleMetadata;
+
+/// Options for [analyze_with].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// Modules built no more than this far apart are in the same
+    /// [Cluster].
+    pub cluster_gap: TimeDelta,
+    /// The fewest modules reported as a [Cluster].
+    pub min_cluster_size: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            cluster_gap: TimeDelta::minutes(2),
+            min_cluster_size: 3,
+        }
+    }
+}
+
+/// A module with a valid timestamp.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Entry {
+    pub created: NaiveDateTime,
+    pub library: PathBuf,
+    pub module: String,
+}
+
+/// The range of module timestamps in one library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibrarySpan {
+    pub library: PathBuf,
+    /// The number of modules with valid timestamps.
+    pub modules: usize,
+    pub oldest: NaiveDateTime,
+    /// The middle timestamp, or the earlier of the two middle timestamps
+    /// if there are an even number of modules.
+    pub median: NaiveDateTime,
+    pub newest: NaiveDateTime,
+}
+
+/// Modules built in quick succession, most likely by one invocation of a
+/// build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cluster {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// The number of modules built between `start` and `end`.
+    pub modules: usize,
+    /// The libraries containing those modules.
+    pub libraries: BTreeSet<PathBuf>,
+}
+
+impl Cluster {
+    /// The time between the first and last module.
+    pub fn window(&self) -> TimeDelta {
+        self.end - self.start
+    }
+}
+
+/// The result of [analyze].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChronologyReport {
+    /// Modules with valid timestamps, oldest first.
+    pub entries: Vec<Entry>,
+    /// The number of modules excluded because their timestamps don't decode.
+    pub invalid: usize,
+    /// Each library, ordered by median timestamp.
+    pub libraries: Vec<LibrarySpan>,
+    /// Rebuild clusters, oldest first.
+    pub clusters: Vec<Cluster>,
+}
+
+impl ChronologyReport {
+    /// The `n` most recently built modules, newest first.
+    pub fn most_recent(&self, n: usize) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().rev().take(n)
+    }
+}
+
+/// Analyzes the timestamps of `entries` with the default [Options].
+pub fn analyze(entries: impl Iterator<Item = (PathBuf, ModuleMetadata)>) -> ChronologyReport {
+    analyze_with(entries, &Options::default())
+}
+
+/// Analyzes the timestamps of `entries`, each a module's metadata and the
+/// library containing it.
+pub fn analyze_with(
+    entries: impl Iterator<Item = (PathBuf, ModuleMetadata)>,
+    options: &Options,
+) -> ChronologyReport {
+    let mut report = ChronologyReport::default();
+    for (library, metadata) in entries {
+        match metadata.created_datetime() {
+            Some(created) => report.entries.push(Entry {
+                created,
+                library,
+                module: metadata.name(),
+            }),
+            None => report.invalid += 1,
+        }
+    }
+    report.entries.sort();
+
+    let mut by_library: BTreeMap<&PathBuf, Vec<NaiveDateTime>> = BTreeMap::new();
+    for entry in &report.entries {
+        by_library
+            .entry(&entry.library)
+            .or_default()
+            .push(entry.created);
+    }
+    // entries are sorted, so each library's timestamps are too
+    report.libraries = by_library
+        .into_iter()
+        .map(|(library, times)| LibrarySpan {
+            library: library.clone(),
+            modules: times.len(),
+            oldest: times[0],
+            median: times[(times.len() - 1) / 2],
+            newest: times[times.len() - 1],
+        })
+        .collect();
+    report
+        .libraries
+        .sort_by(|a, b| a.median.cmp(&b.median).then(a.library.cmp(&b.library)));
+
+    let mut groups: Vec<&[Entry]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=report.entries.len() {
+        let split = i == report.entries.len()
+            || report.entries[i].created - report.entries[i - 1].created > options.cluster_gap;
+        if split {
+            groups.push(&report.entries[start..i]);
+            start = i;
+        }
+    }
+    report.clusters = groups
+        .into_iter()
+        .filter(|group| group.len() >= options.min_cluster_size.max(1))
+        .map(|group| Cluster {
+            start: group[0].created,
+            end: group[group.len() - 1].created,
+            modules: group.len(),
+            libraries: group.iter().map(|e| e.library.clone()).collect(),
+        })
+        .collect();
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FromPSYQTimestamp;
+    use chrono::NaiveDate;
+    use std::time::SystemTime;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(1995, 9, day)
+            .and_then(|d| d.and_hms_opt(hour, minute, second))
+            .expect("datetime")
+    }
+
+    fn entry(
+        library: &str,
+        module: &str,
+        created: Option<NaiveDateTime>,
+    ) -> (PathBuf, ModuleMetadata) {
+        let mut metadata =
+            ModuleMetadata::new(module.into(), SystemTime::now(), 0, vec![]).expect("metadata");
+        metadata.created = created.map_or(0, |c| c.to_psyq_timestamp());
+        (PathBuf::from(library), metadata)
+    }
+
+    fn entries() -> Vec<(PathBuf, ModuleMetadata)> {
+        vec![
+            // a batch build of LIBSN and LIBC
+            entry("LIBSN.LIB", "A", Some(at(20, 20, 44, 22))),
+            entry("LIBSN.LIB", "B", Some(at(20, 20, 44, 24))),
+            entry("LIBC.LIB", "C", Some(at(20, 20, 45, 4))),
+            entry("LIBSN.LIB", "D", Some(at(20, 20, 46, 30))),
+            // a late fix
+            entry("LIBSN.LIB", "FIX", Some(at(28, 9, 0, 0))),
+            // an earlier build of LIBGPU
+            entry("LIBGPU.LIB", "E", Some(at(2, 10, 0, 0))),
+            entry("LIBGPU.LIB", "F", Some(at(2, 10, 0, 30))),
+            entry("LIBGPU.LIB", "G", Some(at(2, 10, 1, 0))),
+            entry("LIBGPU.LIB", "BAD", None),
+        ]
+    }
+
+    #[test]
+    fn test_clusters() {
+        let report = analyze(entries().into_iter());
+        assert_eq!(1, report.invalid);
+        assert_eq!(8, report.entries.len());
+        assert_eq!(2, report.clusters.len());
+
+        let gpu = &report.clusters[0];
+        assert_eq!(
+            (at(2, 10, 0, 0), at(2, 10, 1, 0), 3),
+            (gpu.start, gpu.end, gpu.modules)
+        );
+        assert_eq!(TimeDelta::minutes(1), gpu.window());
+
+        let batch = &report.clusters[1];
+        assert_eq!(4, batch.modules);
+        assert_eq!(at(20, 20, 44, 22), batch.start);
+        assert_eq!(at(20, 20, 46, 30), batch.end);
+        assert_eq!(
+            vec!["LIBC.LIB", "LIBSN.LIB"],
+            batch
+                .libraries
+                .iter()
+                .map(|l| l.to_str().expect("path"))
+                .collect::<Vec<_>>()
+        );
+
+        // a shorter gap splits the batch
+        let options = Options {
+            cluster_gap: TimeDelta::seconds(30),
+            min_cluster_size: 2,
+        };
+        let report = analyze_with(entries().into_iter(), &options);
+        assert_eq!(
+            vec![3, 2],
+            report
+                .clusters
+                .iter()
+                .map(|c| c.modules)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let report = analyze(entries().into_iter());
+        assert_eq!(
+            vec!["FIX", "D", "C"],
+            report
+                .most_recent(3)
+                .map(|e| e.module.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let libraries = report
+            .libraries
+            .iter()
+            .map(|l| (l.library.to_str().expect("path"), l.modules, l.median))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("LIBGPU.LIB", 3, at(2, 10, 0, 30)),
+                ("LIBSN.LIB", 4, at(20, 20, 44, 24)),
+                ("LIBC.LIB", 1, at(20, 20, 45, 4)),
+            ],
+            libraries
+        );
+        assert_eq!(at(28, 9, 0, 0), report.libraries[1].newest);
+    }
+}
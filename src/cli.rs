@@ -1,19 +1,124 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
-use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{File, FileTimes};
-use std::io::Write;
+use std::io::{BufRead, Cursor, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::bail;
-use anyhow::Result;
-use clap::crate_version;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use clap::{crate_version, ValueEnum};
 
+use super::analysis;
+use super::asm;
+use super::assembler::{LibAssembler, NamePolicy};
+use super::chronology;
+use super::compat;
+use super::diff;
 use super::display;
-use super::io::{read, read_lib, write_lib, write_obj};
-use super::{Module, LIB};
+use super::display::human::ReportStyle;
+use super::editors::{self, GrammarFormat};
+use super::error::{bail, Error, Result};
+use super::index;
+use super::io::{
+    self, read_lib_metadata, read_lib_recover, read_opaque_lib, write_lib_atomic, write_lib_to,
+    write_obj_to, ReadOptions, Type, WriteOptions,
+};
+use super::kernel;
+use super::ldscript;
+use super::link;
+use super::names::{self, NameOverlay, Resolution};
+use super::plugin;
+use super::poke;
+use super::recover::RecoveredLib;
+use super::sizes;
+use super::splat;
+use super::symbols::{self, CaseFold};
+use super::verify::{self, Severity};
+use super::xref;
+use super::{path_to_module_name, MergeStrategy, Module, ParseWarning, Section, LIB, OBJ};
+
+/// Whether the warnings found reading files are printed. See [set_verbose].
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Prints the [ParseWarning]s found reading each LIB and OBJ to stderr,
+/// which otherwise aren't. The command line tools set it with `--verbose`.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Prints `warnings` found reading the file at `path` if [set_verbose].
+fn report_warnings(path: &Path, warnings: Vec<ParseWarning>) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        for warning in warnings {
+            eprintln!("warning: {}: {warning}", path.display());
+        }
+    }
+}
+
+/// Whether files are read [leniently](ReadOptions::lenient). See
+/// [set_lenient].
+static LENIENT: AtomicBool = AtomicBool::new(false);
+
+/// Reads each LIB and OBJ [leniently](ReadOptions::lenient), keeping
+/// sections with unknown tags rather than failing. The command line tools
+/// set it with `--lenient`.
+pub fn set_lenient(lenient: bool) {
+    LENIENT.store(lenient, Ordering::Relaxed);
+}
+
+/// [io::read], reporting warnings.
+fn read(path: &Path) -> Result<Type> {
+    read_with_options(path, &ReadOptions::default())
+}
+
+/// [io::read_with_options], reporting warnings.
+fn read_with_options(path: &Path, options: &ReadOptions) -> Result<Type> {
+    let read = if LENIENT.load(Ordering::Relaxed) {
+        let options = ReadOptions {
+            lenient: true,
+            ..options.clone()
+        };
+        io::read_with_options(path, &options)?
+    } else if *options == ReadOptions::default() {
+        io::read(path)?
+    } else {
+        io::read_with_options(path, options)?
+    };
+    report_warnings(path, read.warnings());
+    Ok(read)
+}
+
+/// [io::read_lib], reporting warnings.
+fn read_lib(path: &Path) -> Result<LIB> {
+    let lib = if LENIENT.load(Ordering::Relaxed) {
+        let options = ReadOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        io::read_lib_with_options(path, &options)?
+    } else {
+        io::read_lib(path)?
+    };
+    report_warnings(path, lib.warnings());
+    Ok(lib)
+}
+
+/// [io::read_obj], reporting warnings.
+fn read_obj(path: &Path) -> Result<OBJ> {
+    if !LENIENT.load(Ordering::Relaxed) {
+        let obj = io::read_obj(path)?;
+        report_warnings(path, obj.warnings());
+        return Ok(obj);
+    }
+    match read(path)? {
+        Type::OBJ(obj) => Ok(obj),
+        Type::LIB(_) => bail!("{} is a LIB, not an OBJ", path.display()),
+    }
+}
 
 /// Prints information about an [OBJ](super::OBJ) or [LIB].
 pub fn info(
@@ -23,110 +128,1713 @@ pub fn info(
     disassembly: bool,
     recursive: bool,
 ) -> Result<()> {
-    let o = read(lib_or_obj)?;
-    let mut options = display::Options::default();
+    let mut options = display::Options::modern();
     if disassembly {
         options.code_format = display::CodeFormat::Disassembly;
     } else if code {
         options.code_format = display::CodeFormat::Hex;
     }
     options.recursive = recursive;
+    options.spelling = locale_spelling();
+    info_with_options(write, lib_or_obj, options)
+}
+
+/// Returns the spelling for the locale named by the `LC_ALL` or `LANG`
+/// environment variable, as `DUMPOBJ.EXE` would use.
+pub fn locale_spelling() -> display::Spelling {
+    let locale = env::var("LC_ALL")
+        .or_else(|_| env::var("LANG"))
+        .unwrap_or_default();
+    display::Spelling::from_locale(&locale)
+}
+
+/// Prints information about an [OBJ] or [LIB] using the provided display
+/// options.
+pub fn info_with_options(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    mut options: display::Options,
+) -> Result<()> {
+    if lists_metadata_only(&options) {
+        if let Ok(lib) = read_opaque_lib(lib_or_obj) {
+            writeln!(write, "{}", display::PsyXDisplayable::wrap(&lib, options))?;
+            return Ok(());
+        }
+    }
+    let read_options = ReadOptions {
+        skip_code_bytes: !lists_code(&options),
+        ..Default::default()
+    };
+    let o = read_with_options(lib_or_obj, &read_options)?;
+    if let Some(names) = &options.names {
+        let library = options
+            .library
+            .get_or_insert_with(|| library_name(lib_or_obj));
+        for (module, obj) in overlay_modules(&o, library) {
+            warn_overlay(&names.resolve(library, &module, obj));
+        }
+    }
     writeln!(write, "{}", display::PsyXDisplayable::wrap(&o, options))?;
     Ok(())
 }
 
-pub fn split(lib_path: &Path) -> Result<()> {
+/// `true` if a listing with `options` depends on the bytes of code sections,
+/// which can otherwise be skipped as they are read.
+fn lists_code(options: &display::Options) -> bool {
+    options.code_format != display::CodeFormat::None
+        || options.show_fingerprints
+        || options.show_stable_ids
+        || options.names.is_some()
+}
+
+/// `true` if a LIB listed with `options` only shows the module table, which
+/// can be read without parsing the modules.
+fn lists_metadata_only(options: &display::Options) -> bool {
+    !options.recursive
+        && options.code_format == display::CodeFormat::None
+        && !options.show_fingerprints
+        && !options.show_stable_ids
+        && options.names.is_none()
+}
+
+/// How `psyk list` prints a LIB or OBJ.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// a listing like DUMPOBJ.EXE's
+    #[default]
+    Text,
+    /// every module and section as JSON
+    Json,
+}
+
+/// Prints an [OBJ] or [LIB] as JSON. Requires the `serde` feature.
+pub fn list_json(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    #[cfg(feature = "serde")]
+    {
+        match read(lib_or_obj)? {
+            Type::LIB(lib) => serde_json::to_writer_pretty(&mut *write, &lib)?,
+            Type::OBJ(obj) => serde_json::to_writer_pretty(&mut *write, &obj)?,
+        }
+        writeln!(write)?;
+        Ok(())
+    }
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = (write, lib_or_obj);
+        bail!("JSON listings require the serde feature")
+    }
+}
+
+/// The name a [NameOverlay] uses for the LIB or OBJ at `path`.
+pub fn library_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The modules of a LIB or OBJ and the names a [NameOverlay] uses for
+/// them. A loose OBJ is named after its library.
+fn overlay_modules<'a>(o: &'a Type, library: &str) -> Vec<(String, &'a OBJ)> {
+    match o {
+        Type::LIB(lib) => lib
+            .modules()
+            .iter()
+            .map(|m| (m.name(), m.object()))
+            .collect(),
+        Type::OBJ(obj) => vec![(library.to_string(), obj)],
+    }
+}
+
+fn warn_overlay(resolution: &Resolution) {
+    for warning in &resolution.warnings {
+        eprintln!("warning: {warning}");
+    }
+}
+
+/// Prints the named locations in the code of each module of a LIB or OBJ,
+/// including those named by `names`. Names from `names` are prefixed with
+/// [names::MARKER] if `mark_overlay` is set.
+pub fn functions(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    names: Option<Arc<NameOverlay>>,
+    mark_overlay: bool,
+) -> Result<()> {
+    let o = read(lib_or_obj)?;
+    let library = library_name(lib_or_obj);
+    let options = display::Options {
+        names,
+        mark_overlay,
+        ..display::Options::modern()
+    };
+    for (module, obj) in overlay_modules(&o, &library) {
+        let resolution = options
+            .names
+            .as_ref()
+            .map(|names| names.resolve(&library, &module, obj))
+            .unwrap_or_default();
+        warn_overlay(&resolution);
+        let labels = names::functions(obj, &resolution);
+        if labels.is_empty() {
+            continue;
+        }
+        writeln!(write, "{module}")?;
+        for label in labels {
+            let name = if label.overlay {
+                options.overlay_name(&label.name)
+            } else {
+                options.symbol_name(&label.name)
+            };
+            writeln!(write, "    {:x}:{:08x} {name}", label.section, label.offset)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compares the listing of a [LIB] or [OBJ] with a capture of
+/// `DUMPOBJ.EXE`'s output, skipping its first `skip_lines` lines. Mismatched
+/// lines are printed as a unified diff and an error is returned.
+pub fn check_compat(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    expected: &Path,
+    skip_lines: usize,
+) -> Result<()> {
+    match compat::compare_listing(lib_or_obj, expected, skip_lines)? {
+        Ok(()) => writeln!(write, "{}: ok", lib_or_obj.display())?,
+        Err(differences) => {
+            compat::write_unified_diff(
+                write,
+                &expected.display().to_string(),
+                &lib_or_obj.display().to_string(),
+                &differences,
+            )?;
+            bail!(
+                "{}: {} line(s) differ from {}",
+                lib_or_obj.display(),
+                differences.len(),
+                expected.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the structural differences between two [LIB]s or two [OBJ]s. With
+/// `brief`, only the fingerprints of changed sections are printed.
+pub fn diff(write: &mut impl Write, old: &Path, new: &Path, brief: bool) -> Result<()> {
+    match (read(old)?, read(new)?) {
+        (Type::LIB(old), Type::LIB(new)) => {
+            let diff = diff::diff_libs(&old, &new);
+            if brief {
+                write!(write, "{}", diff.brief())?;
+            } else {
+                write!(write, "{diff}")?;
+            }
+        }
+        (Type::OBJ(old), Type::OBJ(new)) => {
+            let diff = diff::diff_objs(&old, &new);
+            if brief {
+                write!(write, "{}", diff.brief())?;
+            } else {
+                write!(write, "{diff}")?;
+            }
+        }
+        _ => bail!("Cannot compare a LIB with an OBJ"),
+    }
+    Ok(())
+}
+
+/// What [extract] does when two modules would be written to the same file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnCollision {
+    /// Fail before writing anything.
+    #[default]
+    Error,
+    /// Write the later modules as `NAME.1.OBJ`, `NAME.2.OBJ`, and so on,
+    /// which are added back to a LIB as `NAME`.
+    Suffix,
+}
+
+/// Writes every module of a LIB as an OBJ to `out_dir`, or the current
+/// directory. Modules with the same name are an error.
+pub fn split(lib_path: &Path, out_dir: Option<&Path>) -> Result<()> {
+    extract(lib_path, &[], out_dir, OnCollision::Error)
+}
+
+/// Writes the modules of a LIB named by `modules`, or all of them, as OBJs
+/// to the `output` directory or the current directory, creating it if
+/// needed. If `output` is `-`, the single selected module is written to
+/// stdout. Each file's modification time, and on Windows its creation time,
+/// is the module's timestamp.
+///
+/// Names are matched like [delete]'s, so `sprintf.obj` selects `SPRINTF`.
+/// It is an error, listing them, if any names aren't in the LIB.
+pub fn extract(
+    lib_path: &Path,
+    modules: &[String],
+    output: Option<&Path>,
+    on_collision: OnCollision,
+) -> Result<()> {
     let lib = read_lib(lib_path)?;
+    let names = modules
+        .iter()
+        .map(|name| requested_module_name(Path::new(name)))
+        .collect::<Result<Vec<_>>>()?;
+    let selected = lib
+        .modules()
+        .iter()
+        .filter(|m| names.is_empty() || names.iter().any(|n| symbols::module_eq(n, &m.name())))
+        .collect::<Vec<_>>();
+    let unmatched = modules
+        .iter()
+        .zip(&names)
+        .filter(|(_, name)| !selected.iter().any(|m| symbols::module_eq(name, &m.name())))
+        .map(|(requested, _)| requested.clone())
+        .collect::<Vec<_>>();
+    check_unmatched(lib_path, &unmatched)?;
+
+    if let Some(output) = output.filter(|o| is_stdio(o)) {
+        let [module] = selected.as_slice() else {
+            bail!(
+                "{} writes one module, but {} were selected",
+                output.display(),
+                selected.len()
+            );
+        };
+        write_obj_to(module.object(), &mut std::io::stdout().lock())?;
+        eprintln!("Extracted object file {}.OBJ", module.name());
+        return Ok(());
+    }
+
+    // file names are compared without regard to case, as some file
+    // systems do
+    let mut filenames: Vec<String> = Vec::new();
+    for module in &selected {
+        let name = module.name();
+        let count = filenames
+            .iter()
+            .filter(|f| {
+                f.split('.')
+                    .next()
+                    .is_some_and(|n| symbols::module_eq(n, &name))
+            })
+            .count();
+        filenames.push(match (count, on_collision) {
+            (0, _) => format!("{name}.OBJ"),
+            (_, OnCollision::Suffix) => format!("{name}.{count}.OBJ"),
+            (_, OnCollision::Error) => bail!(
+                "{}: more than one module is named {name}",
+                lib_path.display()
+            ),
+        });
+    }
+
+    let dir = output.unwrap_or(Path::new(""));
+    std::fs::create_dir_all(dir)?;
     println!("psyk version {}\n", crate_version!());
-    for module in lib.modules() {
-        let object_filename = format!("{}.OBJ", module.name());
-        let time = module.created_at().expect("created timestamp");
-        let mut file = File::create(&object_filename)?;
-        let times = FileTimes::new().set_accessed(time).set_modified(time);
-        file.set_times(times)?;
-        write_obj(module.object(), &mut file)?;
+    for (module, object_filename) in selected.iter().zip(filenames) {
+        let mut file = File::create(dir.join(&object_filename))?;
+        write_obj_to(module.object(), &mut file)?;
+        if let Some(time) = module.created_at() {
+            set_module_times(&file, time)?;
+        }
 
         println!("Extracted object file {}", object_filename);
     }
     Ok(())
 }
 
-pub fn delete(lib_path: &Path, obj_names: Vec<String>) -> Result<()> {
+/// Writes the module of the LIB at `lib_path` named `module` to `write`,
+/// exactly as [extract] would write it to a file. Names are matched like
+/// [extract]'s.
+pub fn extract_to(write: &mut impl Write, lib_path: &Path, module: &str) -> Result<()> {
     let lib = read_lib(lib_path)?;
+    let name = requested_module_name(Path::new(module))?;
+    let Some(found) = lib
+        .modules()
+        .iter()
+        .find(|m| symbols::module_eq(&name, &m.name()))
+    else {
+        bail!("not in {}: {module}", lib_path.display());
+    };
+    write_obj_to(found.object(), write)?;
+    Ok(())
+}
+
+/// Sets the times of `file` to `time`, the timestamp of the module written
+/// to it. `file` must already be written and flushed, as writing changes its
+/// modification time.
+///
+/// The timestamp records when the module was created. Windows can set when
+/// a file was created, so it is set there along with the access and
+/// modification times. Other platforms only set those two: Linux can't set
+/// a file's creation time, and macOS only moves a file's birth time back
+/// when its modification time is set earlier than it.
+fn set_module_times(file: &File, time: SystemTime) -> Result<()> {
+    let times = FileTimes::new().set_accessed(time).set_modified(time);
+    #[cfg(windows)]
+    let times = {
+        use std::os::windows::fs::FileTimesExt;
+        times.set_created(time)
+    };
+    file.set_times(times)?;
+    Ok(())
+}
 
-    let module_names: HashSet<String> = HashSet::from_iter(obj_names);
+/// Returns `true` if `path` is `-`, which names stdout as an output or stdin
+/// as an input.
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
 
-    let new_modules: Vec<Module> = lib
+/// The name of a module read from stdin by [add] or [join_with]. Use
+/// [rename] to give it another.
+pub const STDIN_MODULE: &str = "STDIN";
+
+/// Fails if `-`, which names stdin as an input, is among `paths` more than
+/// once, as stdin can only be read once.
+fn check_stdin(paths: &[PathBuf]) -> Result<()> {
+    if paths.iter().filter(|path| is_stdio(path)).count() > 1 {
+        bail!("- (stdin) can only be given once");
+    }
+    Ok(())
+}
+
+/// Reads an OBJ from stdin. It is read into memory first, as stdin can't
+/// seek.
+fn read_stdin_obj() -> Result<OBJ> {
+    let mut bytes = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut bytes)?;
+    let obj = io::read_obj_from(&mut Cursor::new(bytes))?;
+    report_warnings(Path::new("-"), obj.warnings());
+    Ok(obj)
+}
+
+/// Writes `lib` to the file at `path`, or streams it to stdout if `path` is
+/// `-`.
+fn write_lib_path(lib: &LIB, path: &Path) -> Result<()> {
+    if is_stdio(path) {
+        write_lib_to(lib, &mut std::io::stdout().lock()).map(|_| ())
+    } else {
+        write_lib_atomic(lib, path, &WriteOptions::default())
+    }
+}
+
+/// Replaces the LIB at `lib_path` with `lib`, keeping the original as a
+/// `.BAK` if `backup` is set. See [write_lib_atomic].
+fn replace_lib(lib: &LIB, lib_path: &Path, backup: bool) -> Result<()> {
+    let options = WriteOptions {
+        backup,
+        ..Default::default()
+    };
+    write_lib_atomic(lib, lib_path, &options)
+}
+
+/// Returns the name of the module `path` would be added as, uppercased,
+/// without an extension, and truncated to 8 bytes. Module names given on
+/// the command line are normalized the same way, so `sprintf.obj` names
+/// `SPRINTF`.
+fn requested_module_name(path: &Path) -> Result<String> {
+    if path.file_prefix().is_none() {
+        bail!("{} does not name a module", path.display());
+    }
+    Ok(String::from_utf8_lossy(&path_to_module_name(path))
+        .trim_end()
+        .to_string())
+}
+
+/// Fails, listing `unmatched`, if any of the requested modules aren't in
+/// the LIB at `lib_path`.
+fn check_unmatched(lib_path: &Path, unmatched: &[String]) -> Result<()> {
+    if unmatched.is_empty() {
+        return Ok(());
+    }
+    bail!("not in {}: {}", lib_path.display(), unmatched.join(", "))
+}
+
+pub fn delete(lib_path: &Path, obj_names: Vec<String>, backup: bool) -> Result<()> {
+    let mut lib = read_lib(lib_path)?;
+    let mut unmatched = Vec::new();
+    for name in obj_names {
+        let module_name = requested_module_name(Path::new(&name))?;
+        let mut removed = false;
+        while lib.remove_module(&module_name).is_some() {
+            removed = true;
+        }
+        if !removed {
+            unmatched.push(name);
+        }
+    }
+    check_unmatched(lib_path, &unmatched)?;
+    if lib.modules().is_empty() {
+        bail!("{} would have no modules left", lib_path.display());
+    }
+
+    replace_lib(&lib, lib_path, backup)
+}
+
+/// Renames the module `old` of the LIB at `lib_path` to `new`. Both names
+/// are matched like [delete]'s, so `sprintf.obj` names `SPRINTF`. The rest
+/// of the LIB is written as it was read.
+pub fn rename(lib_path: &Path, old: &str, new: &str, backup: bool) -> Result<()> {
+    let mut lib = read_lib(lib_path)?;
+    let old_name = requested_module_name(Path::new(old))?;
+    if !lib
         .modules()
         .iter()
-        .filter(|m| !module_names.contains(&m.name()))
-        .cloned()
-        .collect::<Vec<Module>>();
-    let lib = LIB::new(new_modules);
+        .any(|m| symbols::module_eq(&m.name(), &old_name))
+    {
+        check_unmatched(lib_path, &[old.to_string()])?;
+    }
+    let new_name = requested_module_name(Path::new(new))?;
+    lib.rename_module(&old_name, &new_name)
+        .map_err(|e| Error::Other(format!("{}: {e}", lib_path.display()).into()))?;
+
+    replace_lib(&lib, lib_path, backup)
+}
 
-    let mut file = File::create(lib_path)?;
-    write_lib(&lib, &mut file)
+/// Writes the modules of the LIB at `first` followed by the modules of the
+/// LIB at `second` it doesn't have to `output`, or to stdout if it is `-`.
+/// See [LIB::merge].
+pub fn merge(output: &Path, first: &Path, second: &Path, strategy: MergeStrategy) -> Result<()> {
+    let merged = read_lib(first)?
+        .merge(&read_lib(second)?, strategy)
+        .map_err(|e| {
+            Error::Other(format!("{} and {}: {e}", first.display(), second.display()).into())
+        })?;
+    write_lib_path(&merged, output)
 }
 
-pub fn join(lib_path: &Path, obj_paths: Vec<PathBuf>) -> Result<()> {
-    let modules = obj_paths
+/// Fails with every [issue](LIB::validate) of `lib`, which is about to be
+/// written to `lib_path`, unless `force` is set.
+fn validate(lib: &LIB, lib_path: &Path, force: bool) -> Result<()> {
+    let issues = lib.validate();
+    if force || issues.is_empty() {
+        return Ok(());
+    }
+    let issues = issues
         .iter()
-        .map(|path| Module::new_from_path(path).expect("module"))
-        .collect::<Vec<Module>>();
+        .map(|issue| format!("  {issue}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    bail!(
+        "{} would have conflicts (use --force to write it anyway):\n{issues}",
+        lib_path.display()
+    )
+}
 
-    let lib = LIB::new(modules);
+/// Writes a LIB of the OBJs at `obj_paths` to `lib_path`. See [join_at].
+pub fn join(lib_path: &Path, obj_paths: Vec<PathBuf>, force: bool) -> Result<()> {
+    join_at(lib_path, obj_paths, force, None)
+}
 
-    let mut file = File::create(lib_path)?;
-    write_lib(&lib, &mut file)
+/// Writes a LIB of the OBJs at `obj_paths` to `lib_path`. See [join_with].
+pub fn join_at(
+    lib_path: &Path,
+    obj_paths: Vec<PathBuf>,
+    force: bool,
+    timestamp: Option<SystemTime>,
+) -> Result<()> {
+    let options = JoinOptions {
+        force,
+        timestamp,
+        ..Default::default()
+    };
+    join_with(lib_path, obj_paths, &options)
 }
 
-pub fn add(lib_path: &Path, obj_path: &Path) -> Result<()> {
-    let lib = read_lib(lib_path)?;
+/// Options for [join_with].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JoinOptions {
+    /// Write the LIB even if it has [issues](LIB::validate).
+    pub force: bool,
+    /// The timestamp of every module.
+    pub timestamp: Option<SystemTime>,
+    /// How modules are named after their OBJs, and what happens to modules
+    /// with the same name.
+    pub names: NamePolicy,
+}
 
-    let module = Module::new_from_path(obj_path)?;
-    let mut modules: Vec<Module> = lib.modules().clone();
-    modules.push(module);
+/// Writes a LIB of the OBJs at `obj_paths` to `lib_path`. Every module's
+/// timestamp is [JoinOptions::timestamp], or the time in
+/// `SOURCE_DATE_EPOCH` if it isn't given, so building the same OBJs makes
+/// the same LIB. Without either, each module has the creation time of its
+/// OBJ.
+///
+/// A path of `-` reads an OBJ from stdin, named [STDIN_MODULE] and created
+/// now unless a timestamp is given.
+pub fn join_with(lib_path: &Path, obj_paths: Vec<PathBuf>, options: &JoinOptions) -> Result<()> {
+    check_stdin(&obj_paths)?;
+    let mut assembler = LibAssembler::new().name_policy(options.names);
+    for path in &obj_paths {
+        assembler = if is_stdio(path) {
+            assembler.push_obj(STDIN_MODULE, read_stdin_obj()?, Default::default())
+        } else {
+            assembler.push_path(path)
+        };
+    }
+    let timestamp = options.timestamp;
+    if let Some(timestamp) = timestamp.map_or_else(source_date_epoch, |t| Ok(Some(t)))? {
+        assembler = assembler.timestamp(timestamp);
+    }
+    let lib = assembler.assemble()?;
+    validate(&lib, lib_path, options.force)?;
 
-    let lib = LIB::new(modules);
+    write_lib_path(&lib, lib_path)
+}
 
-    let mut file = File::create(lib_path)?;
-    write_lib(&lib, &mut file)
+/// Writes the bytes of one section of the OBJ at `obj_path` to `output`,
+/// or to stdout if it is `-`. See [OBJ::section_bytes].
+///
+/// `section` is a section type like `.text`, which must name exactly one
+/// section, or with `by_id` a section id in hex, as listings show them.
+pub fn dump_section(obj_path: &Path, section: &str, by_id: bool, output: &Path) -> Result<()> {
+    let obj = read_obj(obj_path)?;
+    let id = if by_id {
+        let hex = section.trim_start_matches("0x");
+        let Ok(id) = u16::from_str_radix(hex, 16) else {
+            bail!("{section} is not a section id");
+        };
+        let declared = obj.sections().iter().any(|s| match s {
+            Section::LNKHeader(header) => header.section() == id,
+            _ => false,
+        });
+        if !declared {
+            bail!("{} has no section {id:x}", obj_path.display());
+        }
+        id
+    } else {
+        match obj.section_ids(section).as_slice() {
+            [id] => *id,
+            [] => bail!("{} has no {section} section", obj_path.display()),
+            ids => bail!(
+                "{} has {} {section} sections; pick one with --by-id: {}",
+                obj_path.display(),
+                ids.len(),
+                ids.iter()
+                    .map(|id| format!("{id:x}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    };
+
+    let bytes = obj.section_bytes(id)?;
+    if is_stdio(output) {
+        std::io::stdout().lock().write_all(&bytes)?;
+    } else {
+        std::fs::write(output, &bytes)?;
+    }
+    Ok(())
 }
 
-pub fn update(lib_path: &Path, obj_paths: Vec<PathBuf>) -> Result<()> {
-    let lib = read_lib(lib_path)?;
+/// Returns the time in the `SOURCE_DATE_EPOCH` environment variable, in
+/// seconds since the Unix epoch, if it is set. See
+/// <https://reproducible-builds.org/specs/source-date-epoch/>.
+pub fn source_date_epoch() -> Result<Option<SystemTime>> {
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(seconds) if !seconds.is_empty() => {
+            let Ok(seconds) = seconds.parse::<u64>() else {
+                bail!("SOURCE_DATE_EPOCH is not a number of seconds: {seconds}");
+            };
+            module_time(UNIX_EPOCH + Duration::from_secs(seconds)).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parses a module timestamp written as seconds since the Unix epoch or in
+/// ISO 8601, like `1996-05-15T16:12:06Z` or `1996-05-15`. Times without an
+/// offset are UTC.
+pub fn parse_timestamp(s: &str) -> Result<SystemTime> {
+    let time = if let Ok(seconds) = s.parse::<u64>() {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    } else if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        datetime.into()
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+        datetime.and_utc().into()
+    } else if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        date.and_time(NaiveTime::MIN).and_utc().into()
+    } else {
+        bail!("expected seconds since 1970 or an ISO 8601 date and time: {s}");
+    };
+    module_time(time)
+}
 
-    let mut updated_module_paths: HashMap<String, PathBuf> = HashMap::new();
+/// Parses an address written in hex with a `0x` prefix, like `0x80010000`,
+/// or in decimal.
+pub fn parse_address(s: &str) -> Result<u32> {
+    let address = match s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    match address {
+        Ok(address) => Ok(address),
+        Err(_) => bail!("invalid address: {s} (expected 0xADDRESS)"),
+    }
+}
+
+/// Fails if `time` can't be stored as a module timestamp, whose years are
+/// 1980 through 2107.
+fn module_time(time: SystemTime) -> Result<SystemTime> {
+    let year = DateTime::<Utc>::from(time).year();
+    if !(1980..=2107).contains(&year) {
+        bail!("module timestamps must be from 1980 through 2107, not {year}");
+    }
+    Ok(time)
+}
+
+/// Adds the OBJs at `obj_paths` to the end of the LIB at `lib_path`, in
+/// order.
+///
+/// A path of `-` reads an OBJ from stdin. Its module is named
+/// [STDIN_MODULE] and its timestamp is the time in `SOURCE_DATE_EPOCH`, or
+/// now.
+pub fn add(lib_path: &Path, obj_paths: Vec<PathBuf>, force: bool, backup: bool) -> Result<()> {
+    check_stdin(&obj_paths)?;
+    let mut lib = read_lib(lib_path)?;
+    for path in obj_paths {
+        let module = if is_stdio(&path) {
+            let created = source_date_epoch()?.unwrap_or_else(SystemTime::now);
+            Module::from_obj(STDIN_MODULE, created, read_stdin_obj()?)?
+        } else {
+            Module::new_from_path(&path)?
+        };
+        lib.push_module(module);
+    }
+    validate(&lib, lib_path, force)?;
+
+    replace_lib(&lib, lib_path, backup)
+}
+
+/// Replaces the modules of the LIB at `lib_path` with the OBJs at
+/// `obj_paths`, matched by name. Each module keeps its place in the LIB.
+///
+/// With `preserve_timestamps`, each replacement keeps the timestamp of the
+/// module it replaces rather than taking the creation time of its file.
+pub fn update(
+    lib_path: &Path,
+    obj_paths: Vec<PathBuf>,
+    preserve_timestamps: bool,
+    force: bool,
+    backup: bool,
+) -> Result<()> {
+    let mut lib = read_lib(lib_path)?;
+
+    let mut unmatched = Vec::new();
     for path in obj_paths {
         if !Path::exists(&path) {
-            bail!(format!("File not found: {}", path.display()));
+            bail!("File not found: {}", path.display());
         }
 
-        let module_name = String::from(path.file_stem().expect("file").to_string_lossy());
-        updated_module_paths.insert(module_name, path);
+        let module_name = requested_module_name(&path)?;
+        let Some(previous) = lib
+            .modules()
+            .iter()
+            .find(|m| symbols::module_eq(&m.name(), &module_name))
+        else {
+            unmatched.push(path.display().to_string());
+            continue;
+        };
+        let created = previous.metadata.created;
+        let Ok(mut module) = Module::new_from_path(&path) else {
+            eprintln!("could not read: {path:?}. Skipping.");
+            continue;
+        };
+        if preserve_timestamps {
+            // copied as stored, even if it doesn't decode
+            module.metadata.created = created;
+        }
+        lib.replace_module(module);
     }
+    check_unmatched(lib_path, &unmatched)?;
+    validate(&lib, lib_path, force)?;
 
-    let new_modules = lib
-        .modules()
+    replace_lib(&lib, lib_path, backup)
+}
+
+/// Checks a [LIB] or [OBJ] for problems and prints any findings.
+///
+/// Returns an error if any finding has [Severity::Error].
+///
+/// With a `baseline`, only the modules of a LIB that changed since the
+/// baseline was written are checked, and the baseline is rewritten. A
+/// missing baseline is created.
+pub fn verify(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    options: &verify::Options,
+    baseline: Option<&Path>,
+) -> Result<()> {
+    let mut summary = None;
+    let findings = match (read(lib_or_obj)?, baseline) {
+        (Type::LIB(lib), Some(path)) => {
+            let previous = if path.exists() {
+                verify::Baseline::load(path)?
+            } else {
+                verify::Baseline::default()
+            };
+            let (findings, next) = lib.verify_incremental(options, &previous);
+            next.save(path)?;
+            summary = Some(format!(
+                "{}: checked {} module(s), skipped {} unchanged",
+                path.display(),
+                next.checked,
+                next.reused
+            ));
+            findings
+        }
+        (Type::LIB(lib), None) => lib.verify(options),
+        (Type::OBJ(_), Some(_)) => bail!("--baseline can only be used with a LIB"),
+        (Type::OBJ(obj), None) => obj.verify(options),
+    };
+
+    for finding in &findings {
+        writeln!(write, "{finding}")?;
+    }
+    if let Some(summary) = summary {
+        writeln!(write, "{summary}")?;
+    }
+
+    let errors = findings
         .iter()
-        .map({
-            |m| {
-                if let Some(module_path) = updated_module_paths.get(&m.name()) {
-                    let Ok(new_mod) = Module::new_from_path(module_path) else {
-                        eprintln!("could not read: {module_path:?}. Skipping.");
-                        return m.clone();
-                    };
-                    new_mod
-                } else {
-                    m.clone()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        bail!("{}: {errors} error(s)", lib_or_obj.display());
+    }
+    if findings.is_empty() {
+        writeln!(write, "{}: ok", lib_or_obj.display())?;
+    }
+    Ok(())
+}
+
+/// Indexes the LIBs and OBJs in `dir` and writes the index to `output`. With
+/// `update`, the index at `output` is refreshed instead and `dir` is
+/// ignored.
+pub fn index(
+    write: &mut impl Write,
+    dir: Option<&Path>,
+    output: &Path,
+    update: bool,
+) -> Result<()> {
+    if update {
+        let mut index = index::load(output)?;
+        let report = index::refresh(&mut index)?;
+        index::save(&index, output)?;
+        writeln!(
+            write,
+            "{}: re-read {} file(s), removed {}",
+            output.display(),
+            report.reread.len(),
+            report.removed.len()
+        )?;
+        return Ok(());
+    }
+
+    let Some(dir) = dir else {
+        bail!("a directory is required unless --update is given");
+    };
+    let index = index::build(dir)?;
+    index::save(&index, output)?;
+    writeln!(
+        write,
+        "{}: {} symbol(s) in {} file(s)",
+        output.display(),
+        index.files.iter().map(|f| f.symbols.len()).sum::<usize>(),
+        index.files.len()
+    )?;
+    Ok(())
+}
+
+/// Prints the LIBs and OBJs providing `symbol`, either from an index built
+/// by [index] or by reading every LIB and OBJ in `dir`. Files that changed
+/// since the index was built are read again and reported to stderr.
+pub fn find(
+    write: &mut impl Write,
+    symbol: &str,
+    case: CaseFold,
+    dir: Option<&Path>,
+    index_path: Option<&Path>,
+) -> Result<()> {
+    let index = match (index_path, dir) {
+        (Some(path), _) => index::load(path)?,
+        (None, Some(dir)) => index::build(dir)?,
+        (None, None) => bail!("a directory or --index is required"),
+    };
+    let result = index::query_with(&index, symbol, case)?;
+    for path in &result.stale {
+        eprintln!(
+            "warning: {} changed since the index was built. Scanned it instead.",
+            path.display()
+        );
+    }
+    let tag = kernel::is_kernel_symbol(symbol)
+        .map(|call| format!(" [{call}]"))
+        .unwrap_or_default();
+    for hit in &result.hits {
+        writeln!(
+            write,
+            "{}: {} ({}){tag}",
+            hit.path.display(),
+            hit.module,
+            hit.kind
+        )?;
+    }
+    Ok(())
+}
+
+/// How `psyk chronology` reports module timestamps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChronologyView {
+    /// the timestamp range of each library, ordered by median
+    #[default]
+    Library,
+    /// the most recently built modules and rebuild clusters
+    Module,
+}
+
+/// Prints a build chronology of every LIB in `dir` and its subdirectories.
+/// Only module metadata is read. LIBs that can't be read are reported to
+/// stderr and skipped.
+pub fn chronology(
+    write: &mut impl Write,
+    dir: &Path,
+    view: ChronologyView,
+    count: usize,
+    style: &ReportStyle,
+) -> Result<()> {
+    let libs = io::find_files(dir, &["lib"])?;
+    let entries = libs.iter().flat_map(|path| {
+        let name = path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+        let metadata = match read_lib_metadata(path) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                eprintln!("could not read: {path:?}: {e}. Skipping.");
+                None
+            }
+        };
+        metadata
+            .into_iter()
+            .flatten()
+            .map_while(move |metadata| match metadata {
+                Ok(metadata) => Some((name.clone(), metadata)),
+                Err(e) => {
+                    eprintln!("could not read: {name:?}: {e}. Skipping the rest.");
+                    None
                 }
+            })
+    });
+    let report = chronology::analyze(entries);
+
+    match view {
+        ChronologyView::Library => {
+            // every date is written with the same number of characters
+            let width = style.date(&chrono::NaiveDateTime::default()).len();
+            writeln!(
+                write,
+                "{:<32} {:>7}  {:<width$}  {:<width$}  {:<width$}",
+                "Library", "Modules", "Oldest", "Median", "Newest"
+            )?;
+            for library in &report.libraries {
+                writeln!(
+                    write,
+                    "{:<32} {:>7}  {:<width$}  {:<width$}  {:<width$}",
+                    library.library.display(),
+                    style.count(library.modules as u64),
+                    style.date(&library.oldest),
+                    style.date(&library.median),
+                    style.date(&library.newest)
+                )?;
+            }
+        }
+        ChronologyView::Module => {
+            writeln!(write, "Most recently built modules:")?;
+            for entry in report.most_recent(count) {
+                writeln!(
+                    write,
+                    "  {}  {:<8} {}",
+                    style.date(&entry.created),
+                    entry.module,
+                    entry.library.display()
+                )?;
+            }
+            writeln!(write)?;
+            if report.clusters.is_empty() {
+                writeln!(write, "Rebuild clusters: none")?;
+            } else {
+                writeln!(write, "Rebuild clusters:")?;
             }
+            for cluster in &report.clusters {
+                writeln!(
+                    write,
+                    "  {} .. {}  ({}s)  {} modules in {} libraries",
+                    style.date(&cluster.start),
+                    style.date(&cluster.end),
+                    cluster.window().num_seconds(),
+                    style.count(cluster.modules as u64),
+                    style.count(cluster.libraries.len() as u64)
+                )?;
+            }
+        }
+    }
+    if report.invalid > 0 {
+        writeln!(
+            write,
+            "{} module(s) with invalid timestamps excluded",
+            style.count(report.invalid as u64)
+        )?;
+    }
+    Ok(())
+}
+
+/// Runs analyzer plugins over a [LIB] or [OBJ] and prints their findings.
+/// `plugins` selects plugins by name, or all plugins if it is empty. Plugins
+/// in `plugin_dir` are loaded first.
+///
+/// Returns an error if any finding has [Severity::Error].
+pub fn analyze(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    plugins: &[String],
+    plugin_dir: Option<&Path>,
+) -> Result<()> {
+    #[allow(unused_mut)]
+    let mut registry = plugin::Registry::with_builtins();
+    if let Some(dir) = plugin_dir {
+        #[cfg(all(feature = "dynamic-plugins", unix))]
+        for (path, e) in registry.load_dir(dir)? {
+            eprintln!("could not load plugin: {}: {e}. Skipping.", path.display());
+        }
+        #[cfg(not(all(feature = "dynamic-plugins", unix)))]
+        bail!(
+            "{}: loading plugins requires the dynamic-plugins feature",
+            dir.display()
+        );
+    }
+
+    let plugins = plugins.iter().map(String::as_str).collect::<Vec<_>>();
+    let findings = registry.analyze(&read(lib_or_obj)?, &plugins)?;
+    for finding in &findings {
+        writeln!(write, "{finding}")?;
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        bail!("{}: {errors} error(s)", lib_or_obj.display());
+    }
+    if findings.is_empty() {
+        writeln!(write, "{}: no findings", lib_or_obj.display())?;
+    }
+    Ok(())
+}
+
+/// Writes a report of the damage [recover](super::recover::recover) found
+/// and the modules it recovered.
+fn write_recovery_report(
+    write: &mut impl Write,
+    lib_path: &Path,
+    recovered: &RecoveredLib,
+) -> Result<()> {
+    writeln!(
+        write,
+        "{}: {} module(s) recovered, {} damaged region(s)",
+        lib_path.display(),
+        recovered.modules().count(),
+        recovered.damage.len()
+    )?;
+    if !recovered.leading.is_empty() {
+        writeln!(write)?;
+        writeln!(write, "Read before any damage:")?;
+        for module in &recovered.leading {
+            writeln!(write, "  {}", module.name())?;
+        }
+    }
+    if !recovered.damage.is_empty() {
+        writeln!(write)?;
+        writeln!(write, "Damaged regions:")?;
+        for region in &recovered.damage {
+            writeln!(write, "  {region}")?;
+        }
+    }
+    if !recovered.trailing.is_empty() {
+        writeln!(write)?;
+        writeln!(write, "Found by scanning past damage:")?;
+        for candidate in &recovered.trailing {
+            writeln!(
+                write,
+                "  {:#x} {}",
+                candidate.offset,
+                candidate.module.name()
+            )?;
+        }
+    }
+    if recovered.padding > 0 {
+        writeln!(write)?;
+        writeln!(write, "Ignored {} byte(s) of padding", recovered.padding)?;
+    }
+    Ok(())
+}
+
+/// Writes the modules which can be recovered from a damaged [LIB] to a new
+/// [LIB] at `output`. A report of the damage is written to `report`, or to
+/// `write` if no report path is provided.
+pub fn recover(
+    write: &mut impl Write,
+    lib_path: &Path,
+    output: &Path,
+    report: Option<&Path>,
+) -> Result<()> {
+    let recovered = read_lib_recover(lib_path)?;
+    match report {
+        Some(report) => write_recovery_report(&mut File::create(report)?, lib_path, &recovered)?,
+        // keep the report out of the recovered LIB
+        None if is_stdio(output) => {
+            write_recovery_report(&mut std::io::stderr(), lib_path, &recovered)?
+        }
+        None => write_recovery_report(write, lib_path, &recovered)?,
+    }
+    if recovered.modules().next().is_none() {
+        bail!("{}: no modules could be recovered", lib_path.display());
+    }
+
+    write_lib_path(&recovered.salvage(), output)
+}
+
+/// Parses `SYMBOL`, `SYMBOL+0xOFFSET`, or `SYMBOL+OFFSET`.
+fn parse_poke_target(target: &str) -> Result<(&str, u32)> {
+    let Some((symbol, offset)) = target.rsplit_once('+') else {
+        return Ok((target, 0));
+    };
+    let offset = match offset.strip_prefix("0x").or(offset.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => offset.parse(),
+    };
+    match offset {
+        Ok(offset) if !symbol.is_empty() => Ok((symbol, offset)),
+        _ => bail!("invalid target: {target} (expected SYMBOL+0xOFFSET)"),
+    }
+}
+
+/// Parses hex bytes like `02 80 00 0c` or `0280000c`.
+fn parse_hex_bytes(bytes: &str) -> Result<Vec<u8>> {
+    let digits = bytes.split_whitespace().collect::<String>();
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        bail!("invalid bytes: {bytes:?} (expected pairs of hex digits)");
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| match u8::from_str_radix(&digits[i..i + 2], 16) {
+            Ok(byte) => Ok(byte),
+            Err(_) => bail!("invalid bytes: {bytes:?} (expected pairs of hex digits)"),
         })
-        .collect::<Vec<Module>>();
-    let lib = LIB::new(new_modules);
+        .collect()
+}
+
+fn write_poke_words(write: &mut impl Write, sign: char, start: u32, bytes: &[u8]) -> Result<()> {
+    for (i, word) in bytes.chunks(4).enumerate() {
+        let offset = start + i as u32 * 4;
+        let Ok(word) = <[u8; 4]>::try_from(word) else {
+            let hex = word.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            writeln!(write, "{sign} {offset:#06x}  {hex}")?;
+            continue;
+        };
+        let word = u32::from_le_bytes(word);
+        let asm = super::disassemble(word)
+            .map(|asm| asm.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_else(|| format!(".word {word:#010x}"));
+        writeln!(write, "{sign} {offset:#06x}  {word:08x}  {asm}")?;
+    }
+    Ok(())
+}
+
+/// Overwrites code in `module` of the [LIB] at `lib_path` with `bytes`, a
+/// string of hex digits. `target` is a symbol and optional offset,
+/// `SYMBOL+0xOFFSET`.
+///
+/// The affected instructions are written to `write` before and after the
+/// change. Unless `yes` is set, the change is only saved if confirmed on
+/// `input`. The [LIB] is written to `output`, or back to `lib_path`.
+#[allow(clippy::too_many_arguments)]
+pub fn poke(
+    write: &mut impl Write,
+    input: &mut impl BufRead,
+    lib_path: &Path,
+    module_name: &str,
+    target: &str,
+    bytes: &str,
+    output: Option<&Path>,
+    options: &poke::Options,
+    yes: bool,
+) -> Result<()> {
+    let (symbol, offset) = parse_poke_target(target)?;
+    let bytes = parse_hex_bytes(bytes)?;
+    let lib = read_lib(lib_path)?;
+
+    let mut modules = lib.modules().clone();
+    let Some(module) = modules
+        .iter_mut()
+        .find(|m| symbols::module_eq(&m.name(), module_name))
+    else {
+        bail!("{}: module not found: {module_name}", lib_path.display());
+    };
+    let patched = module.patch_code_with(symbol, offset, &bytes, options)?;
+
+    writeln!(
+        write,
+        "{module_name}: {symbol}+{offset:#x} is {:#x}..{:#x} in section {}",
+        patched.range.start, patched.range.end, patched.section
+    )?;
+    write_poke_words(write, '-', patched.word_start(), &patched.before)?;
+    write_poke_words(write, '+', patched.word_start(), &patched.after)?;
+
+    let output = output.unwrap_or(lib_path);
+    if !yes {
+        write!(write, "Write {}? [y/N] ", output.display())?;
+        write.flush()?;
+        let mut answer = String::new();
+        input.read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            bail!("not written");
+        }
+    }
+
+    write_lib_atomic(&LIB::new(modules), output, &WriteOptions::default())
+}
+
+/// Prints instructions in a [LIB] or [OBJ] which appear to encode absolute
+/// addresses without a patch. With `deny_high`, an error is returned if any
+/// are [High](analysis::Severity::High) severity.
+pub fn check_relocs(write: &mut impl Write, lib_or_obj: &Path, deny_high: bool) -> Result<()> {
+    let suspects = match read(lib_or_obj)? {
+        Type::LIB(lib) => lib
+            .modules()
+            .iter()
+            .flat_map(|m| {
+                analysis::unpatched_addresses(m.object())
+                    .into_iter()
+                    .map(|s| (Some(m.name()), s))
+            })
+            .collect::<Vec<_>>(),
+        Type::OBJ(obj) => analysis::unpatched_addresses(&obj)
+            .into_iter()
+            .map(|s| (None, s))
+            .collect(),
+    };
+
+    for (module, suspect) in &suspects {
+        match module {
+            Some(module) => writeln!(write, "{module}: {suspect}")?,
+            None => writeln!(write, "{suspect}")?,
+        }
+    }
+
+    let high = suspects
+        .iter()
+        .filter(|(_, s)| s.severity == analysis::Severity::High)
+        .count();
+    if deny_high && high > 0 {
+        bail!("{}: {high} high severity suspect(s)", lib_or_obj.display());
+    }
+    if suspects.is_empty() {
+        writeln!(write, "{}: ok", lib_or_obj.display())?;
+    }
+    Ok(())
+}
+
+/// Prints how many times each section variant and expression operator
+/// appears in a [LIB] or [OBJ], followed by any untested ones and, for a
+/// LIB, the modules using them. With `rare_only`, only the untested ones are
+/// printed. With `deny_untested`, any untested use is an error.
+pub fn census(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    rare_only: bool,
+    deny_untested: bool,
+) -> Result<()> {
+    let (total, lib_census) = match read(lib_or_obj)? {
+        Type::LIB(lib) => {
+            let census = lib.format_census();
+            (census.total(), Some(census))
+        }
+        Type::OBJ(obj) => (obj.format_census(), None),
+    };
 
-    let mut file = File::create(lib_path)?;
-    write_lib(&lib, &mut file)
+    if !rare_only {
+        for (heading, counts) in [
+            ("Sections", &total.sections),
+            ("Expressions", &total.expressions),
+        ] {
+            if counts.is_empty() {
+                continue;
+            }
+            writeln!(write, "{heading}")?;
+            for (kind, count) in counts {
+                writeln!(write, "  {kind:<24} {count:>8}")?;
+            }
+        }
+    }
+
+    let untested = total.uses_untested();
+    if !untested.is_empty() {
+        writeln!(write, "Untested")?;
+        for kind in &untested {
+            let count = total
+                .sections
+                .get(kind)
+                .or_else(|| total.expressions.get(kind))
+                .copied()
+                .unwrap_or_default();
+            write!(write, "  {kind:<24} {count:>8}")?;
+            if let Some(census) = &lib_census {
+                write!(write, "  {}", census.modules_using(kind).join(" "))?;
+            }
+            writeln!(write)?;
+        }
+    }
+
+    if deny_untested && !untested.is_empty() {
+        bail!(
+            "{}: uses untested constructs: {}",
+            lib_or_obj.display(),
+            untested.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Writes the modules of a LIB needed to define `roots` to `output`, a
+/// directory, as OBJs.
+pub fn closure(
+    write: &mut impl Write,
+    lib_path: &Path,
+    roots: &[String],
+    output: &Path,
+    combined: bool,
+) -> Result<()> {
+    let lib = read_lib(lib_path)?;
+    let roots = roots.iter().map(String::as_str).collect::<Vec<_>>();
+    let closure = link::closure(&lib, &roots)?;
+
+    writeln!(write, "Modules")?;
+    for name in closure.module_names() {
+        writeln!(write, "  {name}")?;
+    }
+    writeln!(write, "Code: {} bytes", closure.code_size())?;
+    writeln!(write, "BSS:  {} bytes", closure.bss_size())?;
+    writeln!(write, "Imports")?;
+    for name in &closure.imports {
+        writeln!(write, "  {name}")?;
+    }
+
+    let mode = if combined {
+        link::EmitMode::Combined
+    } else {
+        link::EmitMode::SeparateModules
+    };
+    std::fs::create_dir_all(output)?;
+    for (name, obj) in closure.emit(mode) {
+        let mut file = File::create(output.join(format!("{name}.OBJ")))?;
+        write_obj_to(&obj, &mut file)?;
+    }
+    Ok(())
+}
+
+/// Prints summary statistics for a [LIB] or [OBJ].
+pub fn stat(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    stat_with_style(write, lib_or_obj, &ReportStyle::default())
+}
+
+/// Prints summary statistics for a [LIB] or [OBJ], formatting numbers with
+/// `style`.
+pub fn stat_with_style(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    style: &ReportStyle,
+) -> Result<()> {
+    match read(lib_or_obj)? {
+        Type::LIB(lib) => {
+            let objs = lib.modules().iter().map(|m| m.object());
+            let suspicious = verify::TimestampPolicy::default().check(&lib).len();
+            writeln!(
+                write,
+                "Modules:               {}",
+                style.count(lib.modules().len() as u64)
+            )?;
+            writeln!(
+                write,
+                "Exports:               {}",
+                style.count(
+                    lib.modules()
+                        .iter()
+                        .map(|m| m.exports().len() as u64)
+                        .sum::<u64>()
+                )
+            )?;
+            writeln!(
+                write,
+                "Sections:              {}",
+                style.count(objs.clone().map(|o| o.sections().len() as u64).sum::<u64>())
+            )?;
+            writeln!(
+                write,
+                "Code bytes:            {}",
+                style.bytes(objs.map(|o| o.total_code_len() as u64).sum::<u64>())
+            )?;
+            writeln!(
+                write,
+                "Suspicious timestamps: {}",
+                style.count(suspicious as u64)
+            )?;
+        }
+        Type::OBJ(obj) => {
+            writeln!(
+                write,
+                "Exports:               {}",
+                style.count(obj.exports().len() as u64)
+            )?;
+            writeln!(
+                write,
+                "Sections:              {}",
+                style.count(obj.sections().len() as u64)
+            )?;
+            writeln!(
+                write,
+                "Code bytes:            {}",
+                style.bytes(obj.total_code_len() as u64)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The section types [sizes] prints a column for.
+const SIZE_COLUMNS: [&str; 4] = [".text", ".data", ".bss", ".sbss"];
+
+/// Prints the bytes of the `.text`, `.data`, `.bss`, and `.sbss` sections of
+/// each module of a [LIB], or of an [OBJ], like `size(1)`. The total column
+/// includes sections of every type.
+pub fn sizes(write: &mut impl Write, lib_or_obj: &Path) -> Result<()> {
+    sizes_with_style(write, lib_or_obj, &ReportStyle::default())
+}
+
+/// Like [sizes], formatting byte counts with `style`.
+pub fn sizes_with_style(
+    write: &mut impl Write,
+    lib_or_obj: &Path,
+    style: &ReportStyle,
+) -> Result<()> {
+    let options = ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    let rows = match read_with_options(lib_or_obj, &options)? {
+        Type::LIB(lib) => lib.size_report(),
+        Type::OBJ(obj) => vec![(
+            lib_or_obj
+                .file_name()
+                .map_or(String::new(), |n| n.to_string_lossy().into_owned()),
+            obj.section_sizes(),
+        )],
+    };
+
+    let row = |write: &mut dyn Write, name: &str, sizes: &sizes::SectionSizes| {
+        for column in SIZE_COLUMNS {
+            write!(write, "{:>8} ", style.bytes(sizes.get(column).total()))?;
+        }
+        writeln!(write, "{:>8} {name}", style.bytes(sizes.total()))
+    };
+
+    for column in SIZE_COLUMNS {
+        write!(write, "{column:>8} ")?;
+    }
+    writeln!(write, "{:>8} module", "total")?;
+    let mut total = sizes::SectionSizes::default();
+    for (name, sizes) in &rows {
+        row(write, name, sizes)?;
+        total.merge(sizes);
+    }
+    if rows.len() > 1 {
+        row(write, "(TOTALS)", &total)?;
+    }
+    Ok(())
+}
+
+/// Prints each external symbol of the OBJ at `obj_path` followed by every
+/// patched offset that refers to it, as `section+offset`. Sections are named
+/// by type, like `.text`, or by id in hex if they have no header.
+pub fn xref(write: &mut impl Write, obj_path: &Path) -> Result<()> {
+    let obj = read_obj(obj_path)?;
+    let names = obj
+        .sections()
+        .iter()
+        .filter_map(|s| match s {
+            Section::LNKHeader(header) => Some((header.section(), header.type_name())),
+            _ => None,
+        })
+        .collect::<std::collections::HashMap<u16, String>>();
+    let references = obj.references();
+    for symbol in obj.symbols().undefined() {
+        writeln!(write, "{}", symbol.name)?;
+        for reference in &references {
+            if reference.symbol_or_section != xref::SymbolOrSection::Symbol(symbol.number) {
+                continue;
+            }
+            let section = names
+                .get(&reference.active_section)
+                .cloned()
+                .unwrap_or(format!("{:x}", reference.active_section));
+            writeln!(write, "    {section}+{:x}", reference.patch_offset)?;
+        }
+    }
+    Ok(())
+}
+
+/// How `psyk graph` prints the dependencies of a LIB's modules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// each module followed by the modules it depends on
+    #[default]
+    Text,
+    /// a Graphviz digraph
+    Dot,
+}
+
+/// Prints which modules of the LIB at `lib_path` depend on which. See
+/// [LIB::dependency_graph].
+pub fn graph(write: &mut impl Write, lib_path: &Path, format: GraphFormat) -> Result<()> {
+    let options = ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    let Type::LIB(lib) = read_with_options(lib_path, &options)? else {
+        bail!("{} is not a LIB", lib_path.display());
+    };
+    let graph = lib.dependency_graph();
+    match format {
+        GraphFormat::Text => write!(write, "{graph}")?,
+        GraphFormat::Dot => write!(write, "{}", graph.to_dot())?,
+    }
+    Ok(())
+}
+
+/// Writes a syntax highlighting grammar for listings.
+pub fn generate_grammar(write: &mut impl Write, format: GrammarFormat) -> Result<()> {
+    write.write_all(editors::generate(format).as_bytes())?;
+    Ok(())
+}
+
+/// Writes a GNU ld script fragment reserving space for the XBSS symbols in
+/// each [OBJ], or each module of a [LIB], in `lib_or_objs`.
+pub fn ldscript_bss(
+    write: &mut impl Write,
+    lib_or_objs: &[PathBuf],
+    options: &ldscript::Options,
+) -> Result<()> {
+    let mut objs = Vec::new();
+    for path in lib_or_objs {
+        match read(path)? {
+            Type::OBJ(obj) => objs.push(obj),
+            Type::LIB(lib) => objs.extend(lib.modules().iter().map(|m| m.object().clone())),
+        }
+    }
+    let objs = objs.iter().collect::<Vec<&OBJ>>();
+    write.write_all(ldscript::bss_fragment(&objs, options).as_bytes())?;
+    Ok(())
+}
+
+/// Writes the [OBJ] at `obj_path` as assembly source.
+pub fn export_asm(
+    write: &mut impl Write,
+    obj_path: &Path,
+    options: &asm::AsmOptions,
+) -> Result<()> {
+    let Type::OBJ(obj) = read(obj_path)? else {
+        bail!("{} is a LIB; extract a module first", obj_path.display());
+    };
+    write.write_all(obj.to_asm(options)?.as_bytes())?;
+    Ok(())
+}
+
+/// Matches the code segments of a splat config against the modules of
+/// `libs`. Matches with a confidence below `min_confidence` are reported as
+/// unmatched. Requires the `splat` feature.
+pub fn splat_match(
+    write: &mut impl Write,
+    config: &Path,
+    rom: &Path,
+    libs: &[PathBuf],
+    min_confidence: f64,
+    format: splat::ReportFormat,
+) -> Result<()> {
+    #[cfg(feature = "splat")]
+    {
+        let config = splat::load_config(&std::fs::read_to_string(config)?)?;
+        write_splat_matches(write, &config, rom, libs, min_confidence, format)
+    }
+    #[cfg(not(feature = "splat"))]
+    {
+        let _ = (write, config, rom, libs, min_confidence, format);
+        bail!("splat configs require the splat feature")
+    }
+}
+
+#[cfg(feature = "splat")]
+fn write_splat_matches(
+    write: &mut impl Write,
+    config: &splat::SplatConfig,
+    rom: &Path,
+    libs: &[PathBuf],
+    min_confidence: f64,
+    format: splat::ReportFormat,
+) -> Result<()> {
+    let rom = std::fs::read(rom)?;
+    let libs = libs
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            Ok((name, read_lib(path)?))
+        })
+        .collect::<Result<Vec<(String, LIB)>>>()?;
+    let libs = libs
+        .iter()
+        .map(|(name, lib)| (name.as_str(), lib))
+        .collect::<Vec<_>>();
+
+    let mut matches = config.correlate_with(&rom, &libs);
+    for m in &mut matches {
+        if m.best
+            .as_ref()
+            .is_some_and(|b| b.confidence < min_confidence)
+        {
+            m.best = None;
+        }
+    }
+
+    match format {
+        splat::ReportFormat::Text => {
+            writeln!(
+                write,
+                "{:<20} {:>10} {:>8}  {:<8} {:<8} {:<24} {:>10} {:>5}",
+                "Segment", "Start", "Size", "Library", "Module", "Function", "Confidence", "Size"
+            )?;
+            for m in &matches {
+                write!(
+                    write,
+                    "{:<20} {:>#10x} {:>#8x}  ",
+                    m.segment, m.start, m.size
+                )?;
+                match &m.best {
+                    Some(best) => writeln!(
+                        write,
+                        "{:<8} {:<8} {:<24} {:>10.2} {:>5.2}",
+                        best.library,
+                        best.module,
+                        best.function.as_deref().unwrap_or("-"),
+                        best.confidence,
+                        m.size_agreement().unwrap_or(0.0)
+                    )?,
+                    None => writeln!(write, "-")?,
+                }
+            }
+        }
+        splat::ReportFormat::Yaml => {
+            for m in &matches {
+                writeln!(write, "- name: {}", m.segment)?;
+                writeln!(write, "  start: {:#x}", m.start)?;
+                writeln!(write, "  size: {:#x}", m.size)?;
+                if let Some(best) = &m.best {
+                    writeln!(write, "  library: {}", best.library)?;
+                    writeln!(write, "  module: {}", best.module)?;
+                    if let Some(function) = &best.function {
+                        writeln!(write, "  function: {function}")?;
+                    }
+                    writeln!(write, "  confidence: {:.2}", best.confidence)?;
+                    writeln!(
+                        write,
+                        "  size_agreement: {:.2}",
+                        m.size_agreement().unwrap_or(0.0)
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a bundle of the LIB at `lib_path` and the changelog at
+/// `changelog` to `output`, signed with the raw ed25519 secret key at
+/// `sign`. Requires the `package` feature.
+pub fn package(
+    lib_path: &Path,
+    output: &Path,
+    changelog: Option<&Path>,
+    sign: Option<&Path>,
+) -> Result<()> {
+    #[cfg(feature = "package")]
+    {
+        use super::package::{self, BundleFile};
+
+        let lib = BundleFile::read(lib_path)?;
+        let changelog = changelog.map(BundleFile::read).transpose()?;
+        let secret_key = sign.map(package::read_key).transpose()?;
+        let mut bundle = Cursor::new(Vec::new());
+        package::write_bundle(&mut bundle, &lib, changelog.as_ref(), secret_key.as_ref())?;
+        std::fs::write(output, bundle.into_inner())?;
+        Ok(())
+    }
+    #[cfg(not(feature = "package"))]
+    {
+        let _ = (lib_path, output, changelog, sign);
+        bail!("bundles require the package feature")
+    }
+}
+
+/// Verifies the bundle at `bundle_path` and extracts its files into
+/// `output_dir`, checking its signature with the raw ed25519 public key at
+/// `verify_key`. A bundle which fails verification is only extracted if
+/// `force` is set. Requires the `package` feature.
+pub fn unpack(
+    write: &mut impl Write,
+    bundle_path: &Path,
+    output_dir: &Path,
+    verify_key: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    #[cfg(feature = "package")]
+    {
+        use super::package;
+
+        let public_key = verify_key.map(package::read_key).transpose()?;
+        let bundle = package::read_bundle(File::open(bundle_path)?, public_key.as_ref())?;
+        writeln!(write, "{}: {}", bundle_path.display(), bundle.verification)?;
+        if !bundle.verification.is_valid() && !force {
+            bail!(
+                "{}: {}; use --force to extract it anyway",
+                bundle_path.display(),
+                bundle.verification
+            );
+        }
+        std::fs::create_dir_all(output_dir)?;
+        for file in bundle.files() {
+            let path = output_dir.join(&file.name);
+            std::fs::write(&path, &file.bytes)?;
+            writeln!(write, "{}", path.display())?;
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "package"))]
+    {
+        let _ = (write, bundle_path, output_dir, verify_key, force);
+        bail!("bundles require the package feature")
+    }
 }
 
 fn stem_or_psyk(path: Option<String>) -> String {
@@ -139,6 +1847,15 @@ fn stem_or_psyk(path: Option<String>) -> String {
     .unwrap_or_else(|| "psyk".to_string())
 }
 
+/// Reports each module to stderr as it is listed.
+pub struct ProgressReporter;
+
+impl display::Observer for ProgressReporter {
+    fn module(&self, name: &str) {
+        eprintln!("{}: listing {name}", get_binary_name());
+    }
+}
+
 /// Get the binary name from the executable path
 pub fn get_binary_name() -> String {
     stem_or_psyk(env::args().next())
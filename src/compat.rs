@@ -0,0 +1,243 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Comparison of listings with those written by `DUMPOBJ.EXE`.
+//!
+//! Captures of the vendor tool's output differ from a [LIB](super::LIB) or
+//! [OBJ](super::OBJ) listing in ways that don't matter: they have CRLF line
+//! endings, begin with a version banner, and wrap long export lists onto
+//! indented continuation lines. These are normalized before the listings are
+//! compared line by line.
+//!
+//! ```
+//! use psyk::compat;
+//!
+//! let expected = "\r\nPsyLib version 2.04\r\n\r\nModule     Date\r\n";
+//! assert!(compat::compare_text("Module     Date\n", expected, 0).is_empty());
+//!
+//! let differences = compat::compare_text("Module     Time\n", expected, 0);
+//! assert_eq!(1, differences[0].line);
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use super::display::{self, PsyXDisplayable, Spelling};
+use super::io;
+use super::Result;
+
+/// Prefixes of the banner lines `DUMPOBJ.EXE` and `PSYLIB.EXE` begin their
+/// output with.
+const BANNERS: &[&str] = &["PsyLib version", "DUMPOBJ version"];
+
+/// A line which differs between an expected and an actual listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    /// The 1-based line number in the normalized listings.
+    pub line: usize,
+    /// The expected line, or `None` if the expected listing is shorter.
+    pub expected: Option<String>,
+    /// The actual line, or `None` if the actual listing is shorter.
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "line {}:", self.line)?;
+        if let Some(expected) = &self.expected {
+            writeln!(f, "-{expected}")?;
+        }
+        if let Some(actual) = &self.actual {
+            writeln!(f, "+{actual}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the listing of the [LIB](super::LIB) or [OBJ](super::OBJ) at
+/// `lib_or_obj` with the `DUMPOBJ.EXE` output captured in `expected`, after
+/// skipping the first `skip_lines` lines of `expected`.
+///
+/// An error is returned if either file can't be read. Otherwise the result
+/// is `Ok` if the listings match and the [Difference]s if they don't.
+pub fn compare_listing(
+    lib_or_obj: &Path,
+    expected: &Path,
+    skip_lines: usize,
+) -> Result<std::result::Result<(), Vec<Difference>>> {
+    let read = io::read(lib_or_obj)?;
+    let expected = std::fs::read(expected)?;
+    // DUMPOBJ.EXE was written for a British English locale
+    let options = display::Options::builder()
+        .spelling(Spelling::BritishEnglish)
+        .build();
+    let actual = PsyXDisplayable::wrap(&read, options).to_string();
+    let differences = compare_text(&actual, &String::from_utf8_lossy(&expected), skip_lines);
+    if differences.is_empty() {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(differences))
+    }
+}
+
+/// Compares an `actual` listing with an `expected` `DUMPOBJ.EXE` capture,
+/// after skipping the first `skip_lines` lines of `expected`.
+///
+/// Both are normalized first: line endings are made LF, the banner and
+/// blank lines preceding the listing are removed, continuation lines are
+/// joined to the lines they continue, and trailing blank lines are removed.
+pub fn compare_text(actual: &str, expected: &str, skip_lines: usize) -> Vec<Difference> {
+    let actual = normalize(actual, 0);
+    let expected = normalize(expected, skip_lines);
+    (0..actual.len().max(expected.len()))
+        .filter_map(|i| {
+            let (actual, expected) = (actual.get(i), expected.get(i));
+            (actual != expected).then(|| Difference {
+                line: i + 1,
+                expected: expected.cloned(),
+                actual: actual.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Writes `differences` as a unified diff from `expected_name` to
+/// `actual_name`, with consecutive differences in the same hunk.
+///
+/// Hunks start at the [line](Difference::line) of the expected listing.
+/// The actual listing's start is shifted by the lines earlier hunks added
+/// or removed.
+pub fn write_unified_diff(
+    write: &mut impl std::io::Write,
+    expected_name: &str,
+    actual_name: &str,
+    differences: &[Difference],
+) -> std::io::Result<()> {
+    writeln!(write, "--- {expected_name}")?;
+    writeln!(write, "+++ {actual_name}")?;
+    // lines the actual listing has gained before the current hunk
+    let mut offset = 0isize;
+    for hunk in differences.chunk_by(|a, b| a.line + 1 == b.line) {
+        let expected = hunk.iter().filter(|d| d.expected.is_some()).count();
+        let actual = hunk.iter().filter(|d| d.actual.is_some()).count();
+        // an empty side starts at the line before the hunk
+        let start = |count: usize, offset: isize| {
+            hunk[0].line.saturating_add_signed(offset) - usize::from(count == 0)
+        };
+        writeln!(
+            write,
+            "@@ -{},{expected} +{},{actual} @@",
+            start(expected, 0),
+            start(actual, offset)
+        )?;
+        offset += actual as isize - expected as isize;
+        for expected in hunk.iter().filter_map(|d| d.expected.as_ref()) {
+            writeln!(write, "-{expected}")?;
+        }
+        for actual in hunk.iter().filter_map(|d| d.actual.as_ref()) {
+            writeln!(write, "+{actual}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits `listing` into normalized lines.
+fn normalize(listing: &str, skip_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let body = listing
+        .lines()
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .skip(skip_lines)
+        .skip_while(|l| l.trim().is_empty() || BANNERS.iter().any(|b| l.starts_with(b)));
+    for line in body {
+        match lines.last_mut() {
+            Some(last) if is_continuation(line) => {
+                // exports are separated by a space, which usually ends the
+                // wrapped line
+                if !last.ends_with(' ') {
+                    last.push(' ');
+                }
+                last.push_str(line.trim_start());
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Continuation lines of wrapped export lists are indented to the column
+/// the exports begin in.
+fn is_continuation(line: &str) -> bool {
+    line.starts_with("        ") && !line.trim().is_empty()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let expected = "\r\nPsyLib version 2.04\r\n\r\n\
+            Module     Date     Time   Externals defined\r\n\r\n\
+            _EH      13-02-95 21:48:16 __throw_type_match \r\n\
+            \x20                          __find_first_exception_table_match \r\n\r\n";
+        assert_eq!(
+            vec![
+                "Module     Date     Time   Externals defined",
+                "",
+                "_EH      13-02-95 21:48:16 __throw_type_match __find_first_exception_table_match ",
+            ],
+            normalize(expected, 0)
+        );
+        assert_eq!(normalize(expected, 0), normalize(expected, 3));
+
+        let unspaced = "_EH      13-02-95 21:48:16 __throw_type_match\n\
+            \x20                          __register_exceptions\n";
+        assert_eq!(
+            vec!["_EH      13-02-95 21:48:16 __throw_type_match __register_exceptions"],
+            normalize(unspaced, 0)
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let differences = compare_text("a\nB\nC\nd\ne\nF\n", "a\nb\nc\nd\ne\nf\ng\n", 0);
+        let mut diff = Vec::new();
+        write_unified_diff(&mut diff, "DUMP.TXT", "A.OBJ", &differences).unwrap();
+        assert_eq!(
+            "--- DUMP.TXT\n+++ A.OBJ\n\
+            @@ -2,2 +2,2 @@\n-b\n-c\n+B\n+C\n\
+            @@ -6,2 +6,1 @@\n-f\n-g\n+F\n",
+            String::from_utf8(diff).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_offsets() {
+        let difference = |line, expected: Option<&str>, actual: Option<&str>| Difference {
+            line,
+            expected: expected.map(String::from),
+            actual: actual.map(String::from),
+        };
+        // two lines become three, one changes, then one is removed
+        let differences = [
+            difference(2, Some("b"), Some("B")),
+            difference(3, Some("c"), Some("C")),
+            difference(4, None, Some("C2")),
+            difference(8, Some("h"), Some("H")),
+            difference(12, Some("l"), None),
+        ];
+        let mut diff = Vec::new();
+        write_unified_diff(&mut diff, "DUMP.TXT", "A.OBJ", &differences).unwrap();
+        assert_eq!(
+            "--- DUMP.TXT\n+++ A.OBJ\n\
+            @@ -2,2 +2,3 @@\n-b\n-c\n+B\n+C\n+C2\n\
+            @@ -8,1 +9,1 @@\n-h\n+H\n\
+            @@ -12,1 +12,0 @@\n-l\n",
+            String::from_utf8(diff).unwrap()
+        );
+    }
+}
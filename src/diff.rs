@@ -0,0 +1,453 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Structural comparison of [LIB]s and [OBJ]s.
+//!
+//! Sections are compared by position using their
+//! [fingerprints](Section::fingerprint) and modules are matched by name.
+//! Alongside the sections that changed, an [ObjDiff] summarizes what those
+//! changes amount to: exports added or removed, how many sections of each
+//! kind there are, and how much code there is.
+//!
+//! ```
+//! use psyk::diff;
+//! use psyk::{Section, OBJ};
+//!
+//! let old = OBJ::new(vec![Section::BSS(4), Section::NOP]);
+//! let new = OBJ::new(vec![Section::BSS(4), Section::BSS(8), Section::NOP]);
+//! let diff = diff::diff_objs(&old, &new);
+//! assert_eq!(vec![("BSS", 1, 2)], diff.section_counts);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::display;
+use super::{Section, LIB, OBJ};
+
+/// A section that differs between two [OBJ]s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SectionChange {
+    /// The position of the section within the OBJ.
+    pub index: usize,
+    /// The section in the first OBJ, if it has one at this position.
+    pub old: Option<Section>,
+    /// The section in the second OBJ, if it has one at this position.
+    pub new: Option<Section>,
+}
+
+/// The differences between two [OBJ]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjDiff {
+    pub sections: Vec<SectionChange>,
+    /// Symbols only the second OBJ exports.
+    pub exports_added: Vec<String>,
+    /// Symbols only the first OBJ exports.
+    pub exports_removed: Vec<String>,
+    /// The kinds of section there are a different number of, with the
+    /// number in each OBJ, e.g. `("Patch", 12, 14)`.
+    pub section_counts: Vec<(&'static str, usize, usize)>,
+    /// The bytes of code in each OBJ, if they differ.
+    pub code_size: Option<(usize, usize)>,
+}
+
+impl ObjDiff {
+    /// Returns `true` if the objects are identical.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// A summary listing only the fingerprints of changed sections.
+    pub fn brief(&self) -> Brief<'_, Self> {
+        Brief(self)
+    }
+}
+
+/// A module in both of two [LIB]s which differs between them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleDiff {
+    pub name: String,
+    /// When the module was created in each LIB, if that differs.
+    pub created: Option<(String, String)>,
+    pub obj: ObjDiff,
+}
+
+/// The differences between two [LIB]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibDiff {
+    /// Names of modules only in the first LIB.
+    pub removed: Vec<String>,
+    /// Names of modules only in the second LIB.
+    pub added: Vec<String>,
+    /// Modules in both LIBs with different OBJs or timestamps.
+    pub changed: Vec<ModuleDiff>,
+}
+
+/// A module which differs between two [LIB]s. See [LibDiff::iter].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModuleChange<'a> {
+    Removed(&'a str),
+    Added(&'a str),
+    Changed(&'a ModuleDiff),
+}
+
+impl LibDiff {
+    /// Returns `true` if the libraries contain the same modules with
+    /// identical OBJs and timestamps.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns every module which differs: those removed, then those added,
+    /// then those changed.
+    pub fn iter(&self) -> impl Iterator<Item = ModuleChange<'_>> {
+        self.removed
+            .iter()
+            .map(|name| ModuleChange::Removed(name))
+            .chain(self.added.iter().map(|name| ModuleChange::Added(name)))
+            .chain(self.changed.iter().map(ModuleChange::Changed))
+    }
+
+    /// A summary listing only the fingerprints of changed sections.
+    pub fn brief(&self) -> Brief<'_, Self> {
+        Brief(self)
+    }
+}
+
+/// Compares the sections of two [OBJ]s.
+pub fn diff_objs(old: &OBJ, new: &OBJ) -> ObjDiff {
+    let sections = diff_sections(old.sections(), new.sections());
+    if sections.is_empty() {
+        return ObjDiff::default();
+    }
+
+    let (old_exports, new_exports) = (old.exports(), new.exports());
+    let exports_added = new_exports
+        .iter()
+        .filter(|name| !old_exports.contains(name))
+        .cloned()
+        .collect();
+    let exports_removed = old_exports
+        .iter()
+        .filter(|name| !new_exports.contains(name))
+        .cloned()
+        .collect();
+
+    let mut counts: BTreeMap<&'static str, (usize, usize)> = BTreeMap::new();
+    for section in old.sections() {
+        counts.entry(section.kind()).or_default().0 += 1;
+    }
+    for section in new.sections() {
+        counts.entry(section.kind()).or_default().1 += 1;
+    }
+    let section_counts = counts
+        .into_iter()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(kind, (old, new))| (kind, old, new))
+        .collect();
+
+    let code_size = |obj: &OBJ| obj.iter_code().map(|code| code.size() as usize).sum();
+    let code_size = Some((code_size(old), code_size(new))).filter(|(old, new)| old != new);
+
+    ObjDiff {
+        sections,
+        exports_added,
+        exports_removed,
+        section_counts,
+        code_size,
+    }
+}
+
+/// Sections are the same if their fingerprints are. Deferred code can't be
+/// fingerprinted, so it is the same only if it was skipped at the same
+/// position.
+fn same_section(old: &Section, new: &Section) -> bool {
+    match (old.fingerprint(), new.fingerprint()) {
+        (Ok(old), Ok(new)) => old == new,
+        _ => old == new,
+    }
+}
+
+fn diff_sections(old: &[Section], new: &[Section]) -> Vec<SectionChange> {
+    (0..old.len().max(new.len()))
+        .filter_map(|index| {
+            let (o, n) = (old.get(index), new.get(index));
+            match (o, n) {
+                (Some(o), Some(n)) if same_section(o, n) => None,
+                _ => Some(SectionChange {
+                    index,
+                    old: o.cloned(),
+                    new: n.cloned(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Compares two [LIB]s, matching modules by name.
+pub fn diff_libs(old: &LIB, new: &LIB) -> LibDiff {
+    let mut diff = LibDiff::default();
+    for module in old.modules() {
+        let name = module.name();
+        match new.modules().iter().find(|m| m.name() == name) {
+            Some(other) => {
+                let obj = diff_objs(module.object(), other.object());
+                let created = Some((module.created(), other.created())).filter(|(o, n)| o != n);
+                if !obj.is_empty() || created.is_some() {
+                    diff.changed.push(ModuleDiff { name, created, obj });
+                }
+            }
+            None => diff.removed.push(name),
+        }
+    }
+    for module in new.modules() {
+        let name = module.name();
+        if !old.modules().iter().any(|m| m.name() == name) {
+            diff.added.push(name);
+        }
+    }
+    diff
+}
+
+/// The first line of a section listing, e.g. `2 : Code 196 bytes`.
+fn summary(section: &Section) -> String {
+    section.to_string().lines().next().unwrap_or("").to_string()
+}
+
+fn fmt_section(
+    f: &mut fmt::Formatter,
+    prefix: &str,
+    indent: &str,
+    section: &Section,
+) -> fmt::Result {
+    for line in section.to_string().lines() {
+        writeln!(f, "{indent}{prefix} {line}")?;
+    }
+    Ok(())
+}
+
+/// Writes what the section changes in `diff` amount to.
+fn fmt_summary(f: &mut fmt::Formatter, diff: &ObjDiff, indent: &str) -> fmt::Result {
+    if !diff.exports_added.is_empty() {
+        writeln!(
+            f,
+            "{indent}exports added: {}",
+            diff.exports_added.join(", ")
+        )?;
+    }
+    if !diff.exports_removed.is_empty() {
+        writeln!(
+            f,
+            "{indent}exports removed: {}",
+            diff.exports_removed.join(", ")
+        )?;
+    }
+    if let Some((old, new)) = diff.code_size {
+        let delta = new as i64 - old as i64;
+        writeln!(f, "{indent}code: {old} -> {new} bytes ({delta:+})")?;
+    }
+    if !diff.section_counts.is_empty() {
+        let counts = diff
+            .section_counts
+            .iter()
+            .map(|(kind, old, new)| format!("{kind} {old} -> {new}"))
+            .collect::<Vec<_>>();
+        writeln!(f, "{indent}sections: {}", counts.join(", "))?;
+    }
+    Ok(())
+}
+
+fn fmt_obj(f: &mut fmt::Formatter, diff: &ObjDiff, indent: &str) -> fmt::Result {
+    fmt_summary(f, diff, indent)?;
+    for change in &diff.sections {
+        writeln!(f, "{indent}@ section {}", change.index)?;
+        if let Some(old) = &change.old {
+            fmt_section(f, "-", indent, old)?;
+        }
+        if let Some(new) = &change.new {
+            fmt_section(f, "+", indent, new)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_obj_brief(f: &mut fmt::Formatter, diff: &ObjDiff, prefix: &str) -> fmt::Result {
+    for change in &diff.sections {
+        write!(f, "{prefix}section {}: ", change.index)?;
+        match (&change.old, &change.new) {
+            (Some(old), Some(new)) => {
+                write!(f, "{}", summary(old))?;
+                display::write_fingerprint(f, old.fingerprint())?;
+                write!(f, " -> {}", summary(new))?;
+                display::write_fingerprint(f, new.fingerprint())?;
+                writeln!(f)?;
+            }
+            (Some(old), None) => {
+                write!(f, "- {}", summary(old))?;
+                display::write_fingerprint(f, old.fingerprint())?;
+                writeln!(f)?;
+            }
+            (None, Some(new)) => {
+                write!(f, "+ {}", summary(new))?;
+                display::write_fingerprint(f, new.fingerprint())?;
+                writeln!(f)?;
+            }
+            (None, None) => (),
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for ObjDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_obj(f, self, "")
+    }
+}
+
+impl fmt::Display for LibDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.removed {
+            writeln!(f, "- {name}")?;
+        }
+        for name in &self.added {
+            writeln!(f, "+ {name}")?;
+        }
+        for module in &self.changed {
+            writeln!(f, "~ {}", module.name)?;
+            if let Some((old, new)) = &module.created {
+                writeln!(f, "    created: {old} -> {new}")?;
+            }
+            fmt_obj(f, &module.obj, "    ")?;
+        }
+        Ok(())
+    }
+}
+
+/// A brief rendering of a diff. See [ObjDiff::brief] and [LibDiff::brief].
+pub struct Brief<'a, T>(&'a T);
+
+impl fmt::Display for Brief<'_, ObjDiff> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_obj_brief(f, self.0, "")
+    }
+}
+
+impl fmt::Display for Brief<'_, LibDiff> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.0.removed {
+            writeln!(f, "- {name}")?;
+        }
+        for name in &self.0.added {
+            writeln!(f, "+ {name}")?;
+        }
+        for module in &self.0.changed {
+            let prefix = format!("{}: ", module.name);
+            if let Some((old, new)) = &module.created {
+                writeln!(f, "{prefix}created: {old} -> {new}")?;
+            }
+            fmt_obj_brief(f, &module.obj, &prefix)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::{LibAssembler, ModuleOptions};
+    use crate::{Code, XDEF};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn lib(modules: &[(&str, OBJ)]) -> LIB {
+        modules
+            .iter()
+            .fold(LibAssembler::new(), |a, (name, obj)| {
+                a.push_obj(name, obj.clone(), ModuleOptions::default())
+            })
+            .timestamp(UNIX_EPOCH + Duration::from_secs(820_000_000))
+            .assemble()
+            .expect("lib")
+    }
+
+    #[test]
+    fn test_diff_libs() {
+        let a = OBJ::new(vec![Section::BSS(4), Section::NOP]);
+        let b = OBJ::new(vec![Section::BSS(8), Section::NOP]);
+        let old = lib(&[
+            ("KEEP", a.clone()),
+            ("GONE", a.clone()),
+            ("EDIT", a.clone()),
+        ]);
+        let new = lib(&[("KEEP", a.clone()), ("EDIT", b.clone()), ("NEW", a)]);
+
+        let diff = diff_libs(&old, &new);
+        assert_eq!(vec!["GONE"], diff.removed);
+        assert_eq!(vec!["NEW"], diff.added);
+        assert_eq!(1, diff.changed.len());
+        assert_eq!("EDIT", diff.changed[0].name);
+        assert_eq!(None, diff.changed[0].created);
+        assert_eq!(0, diff.changed[0].obj.sections[0].index);
+        assert_eq!(
+            vec![
+                ModuleChange::Removed("GONE"),
+                ModuleChange::Added("NEW"),
+                ModuleChange::Changed(&diff.changed[0]),
+            ],
+            diff.iter().collect::<Vec<_>>()
+        );
+        assert!(diff_libs(&old, &old).is_empty());
+
+        let old_crc = Section::BSS(4).fingerprint().unwrap();
+        let new_crc = Section::BSS(8).fingerprint().unwrap();
+        assert_eq!(
+            format!(
+                "- GONE\n+ NEW\nEDIT: section 0: 8 : Uninitialized data, 4 bytes [crc:{old_crc:08x}] \
+                -> 8 : Uninitialized data, 8 bytes [crc:{new_crc:08x}]\n"
+            ),
+            diff.brief().to_string()
+        );
+    }
+
+    #[test]
+    fn test_diff_objs_summary() {
+        let old = OBJ::new(vec![
+            Section::Code(Code::new(vec![0; 8]).expect("code")),
+            Section::XDEF(XDEF::new(1, 0, 0, "main").expect("xdef")),
+            Section::NOP,
+        ]);
+        let new = OBJ::new(vec![
+            Section::Code(Code::new(vec![0; 12]).expect("code")),
+            Section::XDEF(XDEF::new(1, 0, 0, "start").expect("xdef")),
+            Section::XDEF(XDEF::new(2, 0, 8, "exit").expect("xdef")),
+            Section::NOP,
+        ]);
+
+        let diff = diff_objs(&old, &new);
+        assert_eq!(vec!["start", "exit"], diff.exports_added);
+        assert_eq!(vec!["main"], diff.exports_removed);
+        assert_eq!(vec![("XDEF", 1, 2)], diff.section_counts);
+        assert_eq!(Some((8, 12)), diff.code_size);
+        assert!(diff.to_string().starts_with(
+            "exports added: start, exit\n\
+            exports removed: main\n\
+            code: 8 -> 12 bytes (+4)\n\
+            sections: XDEF 1 -> 2\n\
+            @ section 0\n"
+        ));
+
+        let lib = |timestamp| {
+            LibAssembler::new()
+                .push_obj("MAIN", old.clone(), ModuleOptions::default())
+                .timestamp(UNIX_EPOCH + Duration::from_secs(timestamp))
+                .assemble()
+                .expect("lib")
+        };
+        let diff = diff_libs(&lib(820_000_000), &lib(830_000_000));
+        assert!(diff.changed[0].obj.is_empty());
+        let (old_created, new_created) = diff.changed[0].created.clone().expect("created");
+        assert_eq!(
+            format!("~ MAIN\n    created: {old_created} -> {new_created}\n"),
+            diff.to_string()
+        );
+    }
+}
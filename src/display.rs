@@ -2,19 +2,131 @@
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
 use std::default::Default;
-use std::fmt::{Display, Formatter, Result};
+use std::fmt::{Debug, Display, Formatter, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use super::names::{self, NameOverlay};
+use super::CpuType;
 
 /// The format used to display code.
-#[derive(Clone, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum CodeFormat {
     #[default]
     None,
     Hex,
     Disassembly,
+    /// Hex followed by disassembly, like `DUMPOBJ.EXE /c /d`
+    HexAndDisassembly,
+    /// Disassembly of an [OBJ](super::OBJ) with the address of each
+    /// instruction, a label for each symbol defined in the code, and the
+    /// expression of each patch applied to it
+    AnnotatedDisassembly,
+}
+
+impl CodeFormat {
+    /// Returns the format showing hex, disassembly, both, or neither.
+    pub fn from_flags(hex: bool, disassembly: bool) -> Self {
+        match (hex, disassembly) {
+            (false, false) => Self::None,
+            (true, false) => Self::Hex,
+            (false, true) => Self::Disassembly,
+            (true, true) => Self::HexAndDisassembly,
+        }
+    }
+
+    /// Returns `true` if code is shown as hex.
+    pub fn shows_hex(&self) -> bool {
+        matches!(self, Self::Hex | Self::HexAndDisassembly)
+    }
+
+    /// Returns `true` if code is disassembled.
+    pub fn shows_disassembly(&self) -> bool {
+        matches!(
+            self,
+            Self::Disassembly | Self::HexAndDisassembly | Self::AnnotatedDisassembly
+        )
+    }
+}
+
+/// How module timestamps are written in listings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// `DD-MM-YY HH:MM:SS`, as written by `DUMPOBJ.EXE`
+    #[default]
+    DayMonthYear,
+    /// `MM-DD-YY HH:MM:SS`
+    MonthDayYear,
+    /// `YYYY-MM-DDTHH:MM:SS`
+    Iso8601,
+    /// A [chrono format string](chrono::format::strftime)
+    Custom(String),
+}
+
+impl DateFormat {
+    /// Writes `datetime` in this format.
+    pub fn format(&self, datetime: &NaiveDateTime) -> String {
+        let pattern = match self {
+            Self::DayMonthYear => "%d-%m-%y %H:%M:%S",
+            Self::MonthDayYear => "%m-%d-%y %H:%M:%S",
+            Self::Iso8601 => "%Y-%m-%dT%H:%M:%S",
+            Self::Custom(pattern) => pattern,
+        };
+        datetime.format(pattern).to_string()
+    }
+}
+
+/// How words with regional spellings are written in listings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Spelling {
+    /// e.g. `Uninitialized data`
+    #[default]
+    UsEnglish,
+    /// e.g. `Uninitialised data`, as written by `DUMPOBJ.EXE` in an
+    /// `en_GB` locale
+    BritishEnglish,
+}
+
+impl Spelling {
+    /// Returns the spelling for a POSIX locale name such as `en_GB.UTF-8`.
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.starts_with("en_GB") {
+            Self::BritishEnglish
+        } else {
+            Self::UsEnglish
+        }
+    }
+}
+
+/// Receives progress notifications while a listing is formatted.
+///
+/// Formatting a large [LIB](super::LIB) with disassembly can take a while.
+/// An observer makes it possible to report which module is being worked on.
+pub trait Observer: Send + Sync {
+    /// Called before each module of a [LIB](super::LIB) is formatted in
+    /// recursive mode.
+    fn module(&self, _name: &str) {}
 }
 
 /// Options for displaying [LIB](super::LIB) and [OBJ](super::OBJ) data.
-#[derive(Clone, Default)]
+///
+/// Start from [dumpobj_compat](Self::dumpobj_compat),
+/// [modern](Self::modern), or [builder](Self::builder) rather than listing
+/// every field, so that fields added later take their defaults.
+///
+/// ```
+/// use psyk::display::{CodeFormat, Options};
+///
+/// let options = Options::builder()
+///     .code_format(CodeFormat::Disassembly)
+///     .recursive(true)
+///     .build();
+/// assert_eq!(CodeFormat::Disassembly, options.code_format);
+/// assert_eq!(Options::default().max_instructions, options.max_instructions);
+/// ```
+#[derive(Clone)]
 pub struct Options {
     /// The code format to emit
     pub code_format: CodeFormat,
@@ -24,9 +136,216 @@ pub struct Options {
 
     /// Level to indent
     pub indent_level: u8,
+
+    /// Append a CRC-32 of each section and module, e.g. `[crc:9a3f01b2]`
+    pub show_fingerprints: bool,
+
+    /// Append the [stable id](super::Module::stable_id) of each module, e.g.
+    /// `[id:2k7c...]`
+    pub show_stable_ids: bool,
+
+    /// Prefix each section with the offset it was read from, e.g.
+    /// `0000001c: `. See [OBJ::section_offsets](super::OBJ::section_offsets).
+    pub show_offsets: bool,
+
+    /// The maximum number of instructions disassembled per code section.
+    /// Longer sections are truncated with a note.
+    pub max_instructions: usize,
+
+    /// The maximum time spent disassembling a single code section. Longer
+    /// sections are truncated with a note.
+    pub max_disassembly_time: Duration,
+
+    /// Notified as a listing is formatted
+    pub observer: Option<Arc<dyn Observer>>,
+
+    /// The module currently being formatted, used to identify truncated
+    /// disassembly. Set while recursing into a [LIB](super::LIB).
+    pub module: Option<String>,
+
+    /// The index of the section currently being formatted within its
+    /// [OBJ](super::OBJ).
+    pub section: Option<usize>,
+
+    /// The [processor type](super::CpuType) from the most recent
+    /// [CPU](super::Section::CPU) section of the [OBJ](super::OBJ) being
+    /// formatted. Code is only disassembled for the R3000; `None` is
+    /// treated as the R3000.
+    pub cpu: Option<CpuType>,
+
+    /// Names for locations the archive doesn't name, shown in disassembly,
+    /// and aliases for the symbols it does
+    pub names: Option<Arc<NameOverlay>>,
+
+    /// Prefix names from [names](Self::names) with [names::MARKER]
+    pub mark_overlay: bool,
+
+    /// The name of the [LIB](super::LIB) being formatted, used to find its
+    /// modules in [names](Self::names). For an [OBJ](super::OBJ), its name.
+    pub library: Option<String>,
+
+    /// The names from [names](Self::names) within the code section currently
+    /// being formatted, by offset from the start of the section.
+    pub code_labels: Vec<(u32, String)>,
+
+    /// The address assumed for the start of each section when code is shown
+    /// as [annotated disassembly](CodeFormat::AnnotatedDisassembly). Jump
+    /// targets and instruction addresses depend on it.
+    pub base_address: u32,
+
+    /// The offset of the code section currently being formatted from the
+    /// start of its section, when shown as
+    /// [annotated disassembly](CodeFormat::AnnotatedDisassembly).
+    pub code_offset: u32,
+
+    /// The symbols defined within the code section currently being
+    /// formatted, by offset from the start of the code section. Set for
+    /// [annotated disassembly](CodeFormat::AnnotatedDisassembly).
+    pub code_symbols: Vec<(u32, String)>,
+
+    /// The patches applied to the code section currently being formatted,
+    /// by offset from the start of the code section, described as
+    /// `patch {type}: {expression}`. Set for
+    /// [annotated disassembly](CodeFormat::AnnotatedDisassembly).
+    pub code_patches: Vec<(u32, String)>,
+
+    /// Follow the storage class and type of [Def](super::Def) and
+    /// [Def2](super::Def2) sections with their decoded forms, e.g.
+    /// `class 2 (C_EXT)` and `type 36 (int ())`. See [super::types].
+    pub decode_types: bool,
+
+    /// How module timestamps are written
+    pub date_format: DateFormat,
+
+    /// How words with regional spellings are written. Listings don't
+    /// depend on the environment; see [Spelling::from_locale].
+    pub spelling: Spelling,
+
+    /// Print processor types as `DUMPOBJ.EXE` does, as a bare number.
+    /// Otherwise a known processor's name follows it, e.g.
+    /// `Processor type 7 (MIPS R3000)`.
+    pub dumpobj_compat: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            code_format: CodeFormat::default(),
+            recursive: false,
+            indent_level: 0,
+            show_fingerprints: false,
+            show_stable_ids: false,
+            show_offsets: false,
+            // a code section is at most 64 KiB
+            max_instructions: 0x4000,
+            max_disassembly_time: Duration::from_secs(10),
+            observer: None,
+            module: None,
+            section: None,
+            cpu: None,
+            names: None,
+            mark_overlay: true,
+            library: None,
+            code_labels: Vec::new(),
+            base_address: 0x80000000,
+            code_offset: 0,
+            code_symbols: Vec::new(),
+            code_patches: Vec::new(),
+            decode_types: false,
+            date_format: DateFormat::default(),
+            spelling: Spelling::default(),
+            dumpobj_compat: true,
+        }
+    }
+}
+
+impl Debug for Options {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        f.debug_struct("Options")
+            .field("code_format", &self.code_format)
+            .field("recursive", &self.recursive)
+            .field("indent_level", &self.indent_level)
+            .field("show_fingerprints", &self.show_fingerprints)
+            .field("show_stable_ids", &self.show_stable_ids)
+            .field("show_offsets", &self.show_offsets)
+            .field("max_instructions", &self.max_instructions)
+            .field("max_disassembly_time", &self.max_disassembly_time)
+            .field("observer", &self.observer.as_ref().map(|_| "Observer"))
+            .field("module", &self.module)
+            .field("section", &self.section)
+            .field("cpu", &self.cpu)
+            .field("names", &self.names)
+            .field("mark_overlay", &self.mark_overlay)
+            .field("library", &self.library)
+            .field("code_labels", &self.code_labels)
+            .field("base_address", &self.base_address)
+            .field("code_offset", &self.code_offset)
+            .field("code_symbols", &self.code_symbols)
+            .field("code_patches", &self.code_patches)
+            .field("decode_types", &self.decode_types)
+            .field("date_format", &self.date_format)
+            .field("spelling", &self.spelling)
+            .field("dumpobj_compat", &self.dumpobj_compat)
+            .finish()
+    }
 }
 
+/// Observers are equal only if they are the same observer.
+impl PartialEq for Options {
+    fn eq(&self, other: &Self) -> bool {
+        let observers = match (&self.observer, &other.observer) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.code_format == other.code_format
+            && self.recursive == other.recursive
+            && self.indent_level == other.indent_level
+            && self.show_fingerprints == other.show_fingerprints
+            && self.show_stable_ids == other.show_stable_ids
+            && self.show_offsets == other.show_offsets
+            && self.max_instructions == other.max_instructions
+            && self.max_disassembly_time == other.max_disassembly_time
+            && observers
+            && self.module == other.module
+            && self.section == other.section
+            && self.cpu == other.cpu
+            && self.names == other.names
+            && self.mark_overlay == other.mark_overlay
+            && self.library == other.library
+            && self.code_labels == other.code_labels
+            && self.base_address == other.base_address
+            && self.code_offset == other.code_offset
+            && self.code_symbols == other.code_symbols
+            && self.code_patches == other.code_patches
+            && self.decode_types == other.decode_types
+            && self.date_format == other.date_format
+            && self.spelling == other.spelling
+            && self.dumpobj_compat == other.dumpobj_compat
+    }
+}
+
+impl Eq for Options {}
+
 impl Options {
+    /// The listing written by `dumpobj`, matching `DUMPOBJ.EXE` from the
+    /// PSY-Q SDK.
+    pub fn dumpobj_compat() -> Self {
+        Self::default()
+    }
+
+    /// The listing written by `psyk list`. It is the `dumpobj` listing
+    /// until psyk has options of its own enabled by default.
+    pub fn modern() -> Self {
+        Self::default()
+    }
+
+    /// Returns a builder starting from the [default](Self::default) options.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder {
+            options: Self::default(),
+        }
+    }
+
     pub fn indent(&self) -> Self {
         let mut o = self.clone();
         o.indent_level += 1;
@@ -36,6 +355,135 @@ impl Options {
     pub fn write_indent(&self, f: &mut Formatter) -> Result {
         write!(f, "{:width$}", "", width = 4 * (self.indent_level as usize))
     }
+
+    /// Appends the indentation [write_indent](Self::write_indent) writes to
+    /// `line`.
+    pub(crate) fn push_indent(&self, line: &mut String) {
+        line.extend(std::iter::repeat_n(' ', 4 * self.indent_level as usize));
+    }
+
+    /// `symbol`, or its alias from [names](Self::names).
+    pub fn symbol_name(&self, symbol: &str) -> String {
+        match self.names.as_ref().and_then(|names| names.alias(symbol)) {
+            Some(alias) => self.overlay_name(alias),
+            None => symbol.to_string(),
+        }
+    }
+
+    /// `name` from [names](Self::names) as it is shown.
+    pub fn overlay_name(&self, name: &str) -> String {
+        if self.mark_overlay {
+            format!("{}{name}", names::MARKER)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Builds [Options]. Any option not set keeps its default.
+#[derive(Clone, Debug)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    pub fn code_format(mut self, code_format: CodeFormat) -> Self {
+        self.options.code_format = code_format;
+        self
+    }
+
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.options.recursive = recursive;
+        self
+    }
+
+    pub fn indent_level(mut self, indent_level: u8) -> Self {
+        self.options.indent_level = indent_level;
+        self
+    }
+
+    pub fn show_fingerprints(mut self, show_fingerprints: bool) -> Self {
+        self.options.show_fingerprints = show_fingerprints;
+        self
+    }
+
+    pub fn show_stable_ids(mut self, show_stable_ids: bool) -> Self {
+        self.options.show_stable_ids = show_stable_ids;
+        self
+    }
+
+    pub fn show_offsets(mut self, show_offsets: bool) -> Self {
+        self.options.show_offsets = show_offsets;
+        self
+    }
+
+    pub fn max_instructions(mut self, max_instructions: usize) -> Self {
+        self.options.max_instructions = max_instructions;
+        self
+    }
+
+    pub fn max_disassembly_time(mut self, max_disassembly_time: Duration) -> Self {
+        self.options.max_disassembly_time = max_disassembly_time;
+        self
+    }
+
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.options.observer = Some(observer);
+        self
+    }
+
+    pub fn names(mut self, names: Arc<NameOverlay>) -> Self {
+        self.options.names = Some(names);
+        self
+    }
+
+    pub fn mark_overlay(mut self, mark_overlay: bool) -> Self {
+        self.options.mark_overlay = mark_overlay;
+        self
+    }
+
+    pub fn library(mut self, library: impl Into<String>) -> Self {
+        self.options.library = Some(library.into());
+        self
+    }
+
+    pub fn base_address(mut self, base_address: u32) -> Self {
+        self.options.base_address = base_address;
+        self
+    }
+
+    pub fn decode_types(mut self, decode_types: bool) -> Self {
+        self.options.decode_types = decode_types;
+        self
+    }
+
+    pub fn date_format(mut self, date_format: DateFormat) -> Self {
+        self.options.date_format = date_format;
+        self
+    }
+
+    pub fn spelling(mut self, spelling: Spelling) -> Self {
+        self.options.spelling = spelling;
+        self
+    }
+
+    pub fn dumpobj_compat(mut self, dumpobj_compat: bool) -> Self {
+        self.options.dumpobj_compat = dumpobj_compat;
+        self
+    }
+
+    pub fn build(self) -> Options {
+        self.options
+    }
+}
+
+/// Writes a fingerprint as it is appended to listing lines, or `unread` for
+/// something that can't be fingerprinted because its code was deferred.
+pub(crate) fn write_fingerprint(f: &mut Formatter, fingerprint: crate::Result<u32>) -> Result {
+    match fingerprint {
+        Ok(fingerprint) => write!(f, " [crc:{fingerprint:08x}]"),
+        Err(_) => write!(f, " [crc:unread]"),
+    }
 }
 
 /// Display something with options.
@@ -67,3 +515,275 @@ where
         self.p.fmt_with_options(f, &self.options)
     }
 }
+
+pub mod expr;
+pub mod human;
+
+/// Labels used in listings.
+///
+/// The [Display] implementations for [LIB](super::LIB), [OBJ](super::OBJ),
+/// [Section](super::Section), and [Expression](super::Expression) write these
+/// labels, and [editors](super::editors) builds syntax highlighting rules from
+/// them, so any change to the listing format should happen here.
+pub mod labels {
+    /// The column headings printed before the modules of a LIB.
+    pub const LIB_HEADER: &str = "Module     Date     Time   Externals defined";
+    /// Printed before the sections of an OBJ, followed by its version.
+    pub const OBJ_HEADER: &str = "Header : LNK version";
+    /// Printed in place of a module name that is only whitespace.
+    pub const UNNAMED_MODULE: &str = "<unnamed>";
+
+    // Sections are listed as `<tag> : <label> ...`
+    pub const END_OF_FILE: &str = "End of file";
+    pub const CODE: &str = "Code";
+    pub const RUN_AT_OFFSET: &str = "Run at offset";
+    pub const SWITCH_TO_SECTION: &str = "Switch to section";
+    pub const UNINITIALIZED_DATA: &str = "Uninitialized data";
+    pub const UNINITIALISED_DATA: &str = "Uninitialised data";
+    pub const PATCH: &str = "Patch type";
+    pub const XDEF: &str = "XDEF symbol number";
+    pub const XREF: &str = "XREF symbol number";
+    pub const SECTION_SYMBOL: &str = "Section symbol number";
+    pub const LOCAL_SYMBOL: &str = "Local symbol";
+    pub const GROUP_SYMBOL: &str = "Group symbol number";
+    pub const BYTE_SIZE_REGISTER: &str = "Set byte size register";
+    pub const WORD_SIZE_REGISTER: &str = "Set word size register";
+    pub const LONG_SIZE_REGISTER: &str = "Set long size register";
+    pub const DEFINE_FILE: &str = "Define file number";
+    pub const SET_TO_FILE: &str = "Set to";
+    pub const SET_TO_LINE: &str = "Set to line";
+    pub const INCREMENT_LINE_NUMBER: &str = "Increment line number";
+    pub const VERY_LOCAL_SYMBOL: &str = "Very local symbol";
+    pub const THREE_BYTE_SIZE_REGISTER: &str = "Set 3-byte size register";
+    pub const SET_MX_INFO: &str = "Set MX info";
+    pub const PROCESSOR_TYPE: &str = "Processor type";
+    pub const XBSS: &str = "XBSS symbol number";
+    pub const INC_SLD_LINENUM: &str = "Inc SLD linenum";
+    pub const SET_SLD_LINENUM: &str = "Set SLD linenum";
+    pub const END_SLD_INFO: &str = "End SLD info";
+    pub const REPEAT_BYTE: &str = "Repeat byte";
+    pub const REPEAT_WORD: &str = "Repeat word";
+    pub const REPEAT_LONG: &str = "Repeat long";
+    pub const REPEAT_3_BYTE: &str = "Repeat 3-byte";
+    pub const UNIMPLEMENTED: &str = "<<<<Unimplemented>>>>";
+    pub const FUNCTION_START: &str = "Function start";
+    pub const FUNCTION_END: &str = "Function end";
+    pub const BLOCK_START: &str = "Block start";
+    pub const BLOCK_END: &str = "Block end";
+    pub const DEF: &str = "Def";
+    pub const DEF2: &str = "Def2";
+    pub const UNKNOWN_SECTION: &str = "Unknown section";
+
+    /// Every section label.
+    pub const SECTIONS: &[&str] = &[
+        END_OF_FILE,
+        CODE,
+        RUN_AT_OFFSET,
+        SWITCH_TO_SECTION,
+        UNINITIALIZED_DATA,
+        UNINITIALISED_DATA,
+        PATCH,
+        XDEF,
+        XREF,
+        SECTION_SYMBOL,
+        LOCAL_SYMBOL,
+        GROUP_SYMBOL,
+        BYTE_SIZE_REGISTER,
+        WORD_SIZE_REGISTER,
+        LONG_SIZE_REGISTER,
+        DEFINE_FILE,
+        SET_TO_FILE,
+        SET_TO_LINE,
+        INCREMENT_LINE_NUMBER,
+        VERY_LOCAL_SYMBOL,
+        THREE_BYTE_SIZE_REGISTER,
+        SET_MX_INFO,
+        PROCESSOR_TYPE,
+        XBSS,
+        INC_SLD_LINENUM,
+        SET_SLD_LINENUM,
+        END_SLD_INFO,
+        REPEAT_BYTE,
+        REPEAT_WORD,
+        REPEAT_LONG,
+        REPEAT_3_BYTE,
+        UNIMPLEMENTED,
+        FUNCTION_START,
+        FUNCTION_END,
+        BLOCK_START,
+        BLOCK_END,
+        DEF,
+        DEF2,
+        UNKNOWN_SECTION,
+    ];
+
+    // Expression functions are listed as `<name>(<hex>)`
+    pub const SECTBASE: &str = "sectbase";
+    pub const BANK: &str = "bank";
+    pub const SECTOF: &str = "sectof";
+    pub const OFFS: &str = "offs";
+    pub const SECTSTART: &str = "sectstart";
+    pub const GROUPSTART: &str = "groupstart";
+    pub const GROUPOF: &str = "groupof";
+    pub const SEG: &str = "seg";
+    pub const GROUPORG: &str = "grouporg";
+    pub const SECTEND: &str = "sectend";
+
+    /// Every expression function.
+    pub const FUNCTIONS: &[&str] = &[
+        SECTBASE, BANK, SECTOF, OFFS, SECTSTART, GROUPSTART, GROUPOF, SEG, GROUPORG, SECTEND,
+    ];
+
+    // Binary operators are listed as `(<lhs><operator><rhs>)`
+    pub const EQUALS: &str = "=";
+    pub const NOT_EQUALS: &str = "<>";
+    pub const LTE: &str = "<=";
+    pub const LESS_THAN: &str = "<";
+    pub const GTE: &str = ">=";
+    pub const GREATER_THAN: &str = ">";
+    pub const ADD: &str = "+";
+    pub const SUBTRACT: &str = "-";
+    pub const MULTIPLY: &str = "*";
+    pub const DIVIDE: &str = "/";
+    pub const AND: &str = "&";
+    pub const OR: &str = "!";
+    pub const XOR: &str = "^";
+    pub const LEFT_SHIFT: &str = "<<";
+    pub const RIGHT_SHIFT: &str = ">>";
+    pub const MOD: &str = "%%";
+    pub const DASHES: &str = "---";
+    pub const REVWORD: &str = "-revword-";
+    pub const CHECK0: &str = "-check0-";
+    pub const CHECK1: &str = "-check1-";
+    pub const BIT_RANGE: &str = "-bitrange-";
+    pub const ARSHIFT_CHK: &str = "-arshift_chk-";
+
+    /// Every binary operator.
+    pub const OPERATORS: &[&str] = &[
+        EQUALS,
+        NOT_EQUALS,
+        LTE,
+        LESS_THAN,
+        GTE,
+        GREATER_THAN,
+        ADD,
+        SUBTRACT,
+        MULTIPLY,
+        DIVIDE,
+        AND,
+        OR,
+        XOR,
+        LEFT_SHIFT,
+        RIGHT_SHIFT,
+        MOD,
+        DASHES,
+        REVWORD,
+        CHECK0,
+        CHECK1,
+        BIT_RANGE,
+        ARSHIFT_CHK,
+    ];
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Silent;
+
+    impl Observer for Silent {}
+
+    #[test]
+    fn test_named_options() {
+        for options in [Options::dumpobj_compat(), Options::modern()] {
+            assert_eq!(CodeFormat::None, options.code_format);
+            assert!(!options.recursive);
+            assert_eq!(0, options.indent_level);
+            assert!(!options.show_fingerprints);
+            assert!(!options.show_stable_ids);
+            assert!(!options.show_offsets);
+            assert_eq!(0x4000, options.max_instructions);
+            assert_eq!(Duration::from_secs(10), options.max_disassembly_time);
+            assert!(options.observer.is_none());
+            assert_eq!(None, options.module);
+            assert_eq!(None, options.section);
+            assert!(options.names.is_none());
+            assert!(options.mark_overlay);
+            assert_eq!(None, options.library);
+            assert_eq!(0x80000000, options.base_address);
+            assert!(!options.decode_types);
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        assert_eq!(Options::default(), Options::builder().build());
+
+        let options = Options::builder()
+            .code_format(CodeFormat::Disassembly)
+            .recursive(true)
+            .indent_level(1)
+            .build();
+        assert_eq!(
+            Options {
+                code_format: CodeFormat::Disassembly,
+                recursive: true,
+                indent_level: 1,
+                ..Default::default()
+            },
+            options
+        );
+        assert_eq!(
+            options.indent(),
+            Options::builder()
+                .code_format(CodeFormat::Disassembly)
+                .recursive(true)
+                .indent_level(2)
+                .build()
+        );
+
+        let options = Options::builder()
+            .show_fingerprints(true)
+            .show_stable_ids(true)
+            .show_offsets(true)
+            .max_instructions(16)
+            .max_disassembly_time(Duration::from_millis(5))
+            .base_address(0x80010000)
+            .decode_types(true)
+            .build();
+        assert_eq!(CodeFormat::None, options.code_format);
+        assert!(options.show_fingerprints && options.show_stable_ids);
+        assert!(options.show_offsets);
+        assert_eq!(16, options.max_instructions);
+        assert_eq!(Duration::from_millis(5), options.max_disassembly_time);
+        assert_eq!(0x80010000, options.base_address);
+        assert!(options.decode_types);
+        assert_ne!(Options::default(), options);
+    }
+
+    #[test]
+    fn test_symbol_name() {
+        let names: NameOverlay = "symbol _padd=pad_output".parse().unwrap();
+        let options = Options::builder().names(Arc::new(names)).build();
+        assert_eq!("~pad_output", options.symbol_name("_padd"));
+        assert_eq!("sprintf", options.symbol_name("sprintf"));
+        assert_eq!("~pad", options.overlay_name("pad"));
+        let options = Options::builder()
+            .names(options.names.clone().unwrap())
+            .mark_overlay(false)
+            .build();
+        assert_eq!("pad_output", options.symbol_name("_padd"));
+        assert_eq!("sprintf", Options::default().symbol_name("sprintf"));
+    }
+
+    #[test]
+    fn test_options_observer_eq() {
+        let observer: Arc<dyn Observer> = Arc::new(Silent);
+        let a = Options::builder().observer(observer.clone()).build();
+        assert_eq!(a, a.clone());
+        assert_eq!(a, Options::builder().observer(observer).build());
+        assert_ne!(a, Options::builder().observer(Arc::new(Silent)).build());
+        assert_ne!(a, Options::default());
+        assert!(format!("{a:?}").contains("observer: Some(\"Observer\")"));
+    }
+}
@@ -0,0 +1,594 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Rendering [Expression]s with adjustable style, and parsing them back.
+//!
+//! The [Display](std::fmt::Display) of an [Expression] matches `dumpobj`,
+//! which wraps every binary operation in parentheses. [render] can leave
+//! out the parentheses that precedence makes unnecessary, change how
+//! constants and some operators are spelled, and name symbols and sections.
+//!
+//! ```
+//! use psyk::display::expr::{self, ExprStyle};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let e = expr::parse("(($fffffffc&(sectbase(1)+$22))-(sectbase(1)+$60))")?;
+//! assert_eq!(
+//!     "$fffffffc&(sectbase(1)+$22)-(sectbase(1)+$60)",
+//!     e.render(&ExprStyle::minimal())
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Precedence
+//!
+//! Minimal parentheses follow the precedence of the assembler, from the
+//! operators that bind most tightly to the least:
+//!
+//! | Level | Operators                        |
+//! |-------|----------------------------------|
+//! | 5     | `<<` `>>`                        |
+//! | 4     | `&` `!` `^`                      |
+//! | 3     | `*` `/` `%%`                     |
+//! | 2     | `+` `-`                          |
+//! | 1     | `=` `<>` `<` `<=` `>` `>=`       |
+//!
+//! Operators on the same level are applied left to right. The keyword
+//! operators only the linker uses, like `-revword-` and `---`, have no
+//! level and are always parenthesized.
+//!
+//! Anything [render] produces can be read by [parse] unless symbols or
+//! sections were named.
+
+use std::fmt::{self, Write};
+
+use super::labels;
+use crate::Expression;
+
+/// How binary expressions are parenthesized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Parens {
+    /// Every binary expression, like `dumpobj`.
+    #[default]
+    All,
+    /// Only where precedence requires them.
+    Minimal,
+}
+
+/// How constants are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConstantStyle {
+    /// `$1f`
+    #[default]
+    Dollar,
+    /// `0x1f`
+    ZeroX,
+    /// `31`
+    Decimal,
+}
+
+/// How bitwise or is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrSpelling {
+    /// `!`, like the assembler.
+    #[default]
+    Bang,
+    /// `|`
+    Pipe,
+}
+
+/// How modulo is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModSpelling {
+    /// `%%`, like the assembler.
+    #[default]
+    DoublePercent,
+    /// `%`
+    Percent,
+}
+
+/// Looks up the name of a symbol or section by its number.
+pub type NameHook<'a> = &'a dyn Fn(u16) -> Option<String>;
+
+/// How [render] writes an [Expression]. The default matches its
+/// [Display](std::fmt::Display).
+#[derive(Clone, Copy, Default)]
+pub struct ExprStyle<'a> {
+    pub parens: Parens,
+    pub constants: ConstantStyle,
+    pub or: OrSpelling,
+    pub modulo: ModSpelling,
+    /// Names symbols, which are otherwise written as `[number]`.
+    pub symbol_name: Option<NameHook<'a>>,
+    /// Names the sections passed to `sectbase`, `sectstart`, `sectend`,
+    /// and `sectof`.
+    pub section_name: Option<NameHook<'a>>,
+}
+
+impl ExprStyle<'_> {
+    /// The default style with only the parentheses precedence requires.
+    pub fn minimal() -> Self {
+        Self {
+            parens: Parens::Minimal,
+            ..Default::default()
+        }
+    }
+}
+
+/// The spelling and precedence of a binary operator. Keyword operators have
+/// a precedence of 0.
+fn operator(e: &Expression, style: &ExprStyle) -> Option<(&'static str, u8)> {
+    Some(match e {
+        Expression::Equals(..) => (labels::EQUALS, 1),
+        Expression::NotEquals(..) => (labels::NOT_EQUALS, 1),
+        Expression::LTE(..) => (labels::LTE, 1),
+        Expression::LessThan(..) => (labels::LESS_THAN, 1),
+        Expression::GTE(..) => (labels::GTE, 1),
+        Expression::GreaterThan(..) => (labels::GREATER_THAN, 1),
+        Expression::Add(..) => (labels::ADD, 2),
+        Expression::Subtract(..) => (labels::SUBTRACT, 2),
+        Expression::Multiply(..) => (labels::MULTIPLY, 3),
+        Expression::Divide(..) => (labels::DIVIDE, 3),
+        Expression::Mod(..) => match style.modulo {
+            ModSpelling::DoublePercent => (labels::MOD, 3),
+            ModSpelling::Percent => ("%", 3),
+        },
+        Expression::And(..) => (labels::AND, 4),
+        Expression::Or(..) => match style.or {
+            OrSpelling::Bang => (labels::OR, 4),
+            OrSpelling::Pipe => ("|", 4),
+        },
+        Expression::XOR(..) => (labels::XOR, 4),
+        Expression::LeftShift(..) => (labels::LEFT_SHIFT, 5),
+        Expression::RightShift(..) => (labels::RIGHT_SHIFT, 5),
+        Expression::Dashes(..) => (labels::DASHES, 0),
+        Expression::Revword(..) => (labels::REVWORD, 0),
+        Expression::Check0(..) => (labels::CHECK0, 0),
+        Expression::Check1(..) => (labels::CHECK1, 0),
+        Expression::BitRange(..) => (labels::BIT_RANGE, 0),
+        Expression::ArshiftChk(..) => (labels::ARSHIFT_CHK, 0),
+        _ => return None,
+    })
+}
+
+/// Renders `e` in `style`.
+pub fn render(e: &Expression, style: &ExprStyle) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, e, style, 0);
+    out
+}
+
+/// Writes `e`, parenthesized if its operator binds less tightly than
+/// `min`.
+fn write_expr(out: &mut String, e: &Expression, style: &ExprStyle, min: u8) {
+    if let (Some((op, level)), Some((lhs, rhs))) = (operator(e, style), e.operands()) {
+        let parens = style.parens == Parens::All || level == 0 || level < min;
+        if parens {
+            out.push('(');
+        }
+        // operators are left associative, so an equal operator on the
+        // right needs parentheses
+        write_expr(out, lhs, style, level);
+        out.push_str(op);
+        write_expr(out, rhs, style, level + 1);
+        if parens {
+            out.push(')');
+        }
+        return;
+    }
+
+    let function = |out: &mut String, name: &str, value: u16, hook: Option<NameHook>| {
+        match hook.and_then(|hook| hook(value)) {
+            Some(resolved) => write!(out, "{name}({resolved})"),
+            None => write!(out, "{name}({value:x})"),
+        }
+        .expect("writing to a String can't fail")
+    };
+    let section = style.section_name;
+    match e {
+        Expression::Constant(value) => match style.constants {
+            ConstantStyle::Dollar => write!(out, "${value:x}"),
+            ConstantStyle::ZeroX => write!(out, "{value:#x}"),
+            ConstantStyle::Decimal => write!(out, "{value}"),
+        }
+        .expect("writing to a String can't fail"),
+        Expression::SymbolAddressIndex(index) => {
+            match style.symbol_name.and_then(|hook| hook(*index)) {
+                Some(name) => out.push_str(&name),
+                None => write!(out, "[{index:x}]").expect("writing to a String can't fail"),
+            }
+        }
+        Expression::SectionAddressIndex(v) => function(out, labels::SECTBASE, *v, section),
+        Expression::Bank(v) => function(out, labels::BANK, *v, None),
+        Expression::SectionOffset(v) => function(out, labels::SECTOF, *v, section),
+        Expression::Offset(v) => function(out, labels::OFFS, *v, None),
+        Expression::SectionStart(v) => function(out, labels::SECTSTART, *v, section),
+        Expression::GroupStart(v) => function(out, labels::GROUPSTART, *v, None),
+        Expression::GroupOffset(v) => function(out, labels::GROUPOF, *v, None),
+        Expression::Segment(v) => function(out, labels::SEG, *v, None),
+        Expression::GroupOrg(v) => function(out, labels::GROUPORG, *v, None),
+        Expression::SectionEnd(v) => function(out, labels::SECTEND, *v, section),
+        _ => unreachable!("binary expressions have an operator"),
+    }
+}
+
+/// Binary operators as they may be written, longest first so that e.g.
+/// `<<` isn't read as `<`.
+const OPERATORS: &[(&str, u8)] = &[
+    (labels::ARSHIFT_CHK, 0),
+    (labels::BIT_RANGE, 0),
+    (labels::REVWORD, 0),
+    (labels::CHECK0, 0),
+    (labels::CHECK1, 0),
+    (labels::DASHES, 0),
+    (labels::NOT_EQUALS, 1),
+    (labels::LTE, 1),
+    (labels::GTE, 1),
+    (labels::LEFT_SHIFT, 5),
+    (labels::RIGHT_SHIFT, 5),
+    (labels::MOD, 3),
+    (labels::EQUALS, 1),
+    (labels::LESS_THAN, 1),
+    (labels::GREATER_THAN, 1),
+    (labels::ADD, 2),
+    (labels::SUBTRACT, 2),
+    (labels::MULTIPLY, 3),
+    (labels::DIVIDE, 3),
+    ("%", 3),
+    (labels::AND, 4),
+    (labels::OR, 4),
+    ("|", 4),
+    (labels::XOR, 4),
+];
+
+fn binary(op: &str, lhs: Expression, rhs: Expression) -> Expression {
+    let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+    match op {
+        labels::EQUALS => Expression::Equals(lhs, rhs),
+        labels::NOT_EQUALS => Expression::NotEquals(lhs, rhs),
+        labels::LTE => Expression::LTE(lhs, rhs),
+        labels::LESS_THAN => Expression::LessThan(lhs, rhs),
+        labels::GTE => Expression::GTE(lhs, rhs),
+        labels::GREATER_THAN => Expression::GreaterThan(lhs, rhs),
+        labels::ADD => Expression::Add(lhs, rhs),
+        labels::SUBTRACT => Expression::Subtract(lhs, rhs),
+        labels::MULTIPLY => Expression::Multiply(lhs, rhs),
+        labels::DIVIDE => Expression::Divide(lhs, rhs),
+        labels::MOD | "%" => Expression::Mod(lhs, rhs),
+        labels::AND => Expression::And(lhs, rhs),
+        labels::OR | "|" => Expression::Or(lhs, rhs),
+        labels::XOR => Expression::XOR(lhs, rhs),
+        labels::LEFT_SHIFT => Expression::LeftShift(lhs, rhs),
+        labels::RIGHT_SHIFT => Expression::RightShift(lhs, rhs),
+        labels::DASHES => Expression::Dashes(lhs, rhs),
+        labels::REVWORD => Expression::Revword(lhs, rhs),
+        labels::CHECK0 => Expression::Check0(lhs, rhs),
+        labels::CHECK1 => Expression::Check1(lhs, rhs),
+        labels::BIT_RANGE => Expression::BitRange(lhs, rhs),
+        labels::ARSHIFT_CHK => Expression::ArshiftChk(lhs, rhs),
+        _ => unreachable!("every operator is listed"),
+    }
+}
+
+/// Why an expression couldn't be [parse]d.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The byte offset into the parsed text where the error was found.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// What was wrong where a [ParseError] was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A constant, function, or parenthesized expression was expected.
+    ExpectedExpression,
+    /// This character, like a closing parenthesis, was expected.
+    Expected(char),
+    /// A number has no digits or doesn't fit.
+    InvalidNumber,
+    /// A function, named here, that isn't known.
+    UnknownFunction(String),
+    /// Text follows a complete expression.
+    Unexpected,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::ExpectedExpression => write!(f, "expected an expression")?,
+            ParseErrorKind::Expected(c) => write!(f, "expected {c}")?,
+            ParseErrorKind::InvalidNumber => write!(f, "invalid number")?,
+            ParseErrorKind::UnknownFunction(name) => write!(f, "unknown function {name:?}")?,
+            ParseErrorKind::Unexpected => write!(f, "unexpected text")?,
+        }
+        write!(f, " at offset {}", self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// While parsing, errors hold the length of the input left where they were
+/// found. [parse] turns it into a position.
+fn error(input: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError {
+        position: input.len(),
+        kind,
+    }
+}
+
+/// Parses an expression written by [render] in any style that doesn't
+/// name symbols or sections, including the [Display](std::fmt::Display)
+/// of an [Expression].
+///
+/// ```
+/// use psyk::display::expr::{self, ParseErrorKind};
+///
+/// let e = expr::parse("(sectbase(1)+$22").expect_err("unbalanced");
+/// assert_eq!(ParseErrorKind::Expected(')'), e.kind);
+/// assert_eq!(16, e.position);
+/// ```
+pub fn parse(s: &str) -> Result<Expression> {
+    let mut input = s;
+    let parsed = parse_binary(&mut input, 0).and_then(|e| match input {
+        "" => Ok(e),
+        _ => Err(error(input, ParseErrorKind::Unexpected)),
+    });
+    parsed.map_err(|e| ParseError {
+        position: s.len() - e.position,
+        ..e
+    })
+}
+
+/// Parses operators of at least `min` precedence by precedence climbing.
+fn parse_binary(input: &mut &str, min: u8) -> Result<Expression> {
+    let mut lhs = parse_primary(input)?;
+    while let Some((op, level)) = OPERATORS
+        .iter()
+        .find(|(op, _)| input.starts_with(op))
+        .copied()
+    {
+        if level < min {
+            break;
+        }
+        *input = &input[op.len()..];
+        let rhs = parse_binary(input, level + 1)?;
+        lhs = binary(op, lhs, rhs);
+    }
+    Ok(lhs)
+}
+
+/// Takes the leading characters of `input` that match `f`.
+fn take<'a>(input: &mut &'a str, f: impl Fn(char) -> bool) -> &'a str {
+    let end = input.find(|c| !f(c)).unwrap_or(input.len());
+    let (taken, rest) = input.split_at(end);
+    *input = rest;
+    taken
+}
+
+/// Takes the number in `radix` at the start of `input`.
+fn number<T: TryFrom<u32>>(input: &mut &str, radix: u32) -> Result<T> {
+    let start = *input;
+    let digits = take(input, |c| c.is_digit(radix));
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| error(start, ParseErrorKind::InvalidNumber))
+}
+
+/// Takes `c` from the start of `input`.
+fn expect(input: &mut &str, c: char) -> Result<()> {
+    let Some(rest) = input.strip_prefix(c) else {
+        return Err(error(input, ParseErrorKind::Expected(c)));
+    };
+    *input = rest;
+    Ok(())
+}
+
+fn parse_primary(input: &mut &str) -> Result<Expression> {
+    if let Some(rest) = input.strip_prefix('(') {
+        *input = rest;
+        let e = parse_binary(input, 0)?;
+        expect(input, ')')?;
+        return Ok(e);
+    }
+    if let Some(rest) = input.strip_prefix('[') {
+        *input = rest;
+        let index = number(input, 16)?;
+        expect(input, ']')?;
+        return Ok(Expression::SymbolAddressIndex(index));
+    }
+    if let Some(rest) = input.strip_prefix('$').or_else(|| input.strip_prefix("0x")) {
+        *input = rest;
+        return Ok(Expression::Constant(number(input, 16)?));
+    }
+    if input.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(Expression::Constant(number(input, 10)?));
+    }
+
+    let start = *input;
+    let name = take(input, |c| c.is_ascii_lowercase());
+    let Some(rest) = input.strip_prefix('(') else {
+        return Err(error(input, ParseErrorKind::ExpectedExpression));
+    };
+    *input = rest;
+    let value = number(input, 16)?;
+    expect(input, ')')?;
+    Ok(match name {
+        labels::SECTBASE => Expression::SectionAddressIndex(value),
+        labels::BANK => Expression::Bank(value),
+        labels::SECTOF => Expression::SectionOffset(value),
+        labels::OFFS => Expression::Offset(value),
+        labels::SECTSTART => Expression::SectionStart(value),
+        labels::GROUPSTART => Expression::GroupStart(value),
+        labels::GROUPOF => Expression::GroupOffset(value),
+        labels::SEG => Expression::Segment(value),
+        labels::GROUPORG => Expression::GroupOrg(value),
+        labels::SECTEND => Expression::SectionEnd(value),
+        _ => {
+            let kind = ParseErrorKind::UnknownFunction(name.to_string());
+            return Err(error(start, kind));
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SATURN: &str = "(($fffffffc&(sectbase(1)+$22))-(sectbase(1)+$60))";
+
+    /// A deterministic linear congruential generator.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, n: u64) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 33) % n
+        }
+    }
+
+    fn random(rng: &mut Lcg, depth: u32) -> Expression {
+        if depth == 0 || rng.next(3) == 0 {
+            let v = rng.next(0x10000) as u16;
+            return match rng.next(12) {
+                0 => Expression::Constant(rng.next(u32::MAX as u64) as u32),
+                1 => Expression::SymbolAddressIndex(v),
+                2 => Expression::SectionAddressIndex(v),
+                3 => Expression::Bank(v),
+                4 => Expression::SectionOffset(v),
+                5 => Expression::Offset(v),
+                6 => Expression::SectionStart(v),
+                7 => Expression::GroupStart(v),
+                8 => Expression::GroupOffset(v),
+                9 => Expression::Segment(v),
+                10 => Expression::GroupOrg(v),
+                _ => Expression::SectionEnd(v),
+            };
+        }
+        let (op, _) = OPERATORS[rng.next(OPERATORS.len() as u64) as usize];
+        binary(op, random(rng, depth - 1), random(rng, depth - 1))
+    }
+
+    #[test]
+    fn test_legacy() {
+        let e = parse(SATURN).expect("parse");
+        assert_eq!(SATURN, e.to_string());
+        assert_eq!(SATURN, render(&e, &ExprStyle::default()));
+    }
+
+    #[test]
+    fn test_minimal() {
+        let e = parse(SATURN).expect("parse");
+        let minimal = render(&e, &ExprStyle::minimal());
+        assert_eq!("$fffffffc&(sectbase(1)+$22)-(sectbase(1)+$60)", minimal);
+        assert_eq!(e, parse(&minimal).expect("parse"));
+
+        for (text, expected) in [
+            ("(($1+$2)+$3)", "$1+$2+$3"),
+            ("($1+($2+$3))", "$1+($2+$3)"),
+            ("(($1*$2)+$3)", "$1*$2+$3"),
+            ("($1*($2+$3))", "$1*($2+$3)"),
+            ("(($1<<$2)&$3)", "$1<<$2&$3"),
+            ("(($1+$2)-revword-$3)", "($1+$2-revword-$3)"),
+            ("(($1---$2)+$3)", "($1---$2)+$3"),
+        ] {
+            let e = parse(text).expect("parse");
+            assert_eq!(expected, render(&e, &ExprStyle::minimal()), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_spelling() {
+        let e = parse("(($1f!$2)%%[a])").expect("parse");
+        let style = ExprStyle {
+            constants: ConstantStyle::ZeroX,
+            or: OrSpelling::Pipe,
+            modulo: ModSpelling::Percent,
+            ..ExprStyle::minimal()
+        };
+        assert_eq!("0x1f|0x2%[a]", render(&e, &style));
+        let decimal = ExprStyle {
+            constants: ConstantStyle::Decimal,
+            ..Default::default()
+        };
+        assert_eq!("((31!2)%%[a])", render(&e, &decimal));
+
+        let symbol = |n: u16| (n == 0xa).then(|| "main".to_string());
+        let section = |n: u16| (n == 1).then(|| ".text".to_string());
+        let named = ExprStyle {
+            symbol_name: Some(&symbol),
+            section_name: Some(&section),
+            ..ExprStyle::minimal()
+        };
+        let e = parse("([a]-(sectbase(1)+bank(1)))").expect("parse");
+        assert_eq!("main-(sectbase(.text)+bank(1))", render(&e, &named));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = Lcg(0x5053_594b);
+        let styles = [
+            ExprStyle::default(),
+            ExprStyle::minimal(),
+            ExprStyle {
+                constants: ConstantStyle::Decimal,
+                or: OrSpelling::Pipe,
+                modulo: ModSpelling::Percent,
+                ..ExprStyle::minimal()
+            },
+            ExprStyle {
+                constants: ConstantStyle::ZeroX,
+                ..ExprStyle::minimal()
+            },
+        ];
+        for _ in 0..500 {
+            let e = random(&mut rng, 5);
+            for style in &styles {
+                let text = render(&e, style);
+                assert_eq!(e, parse(&text).expect("parse"), "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("($1+").is_err());
+        assert!(parse("nope(1)").is_err());
+        assert!(parse("$1 $2").is_err());
+
+        // unbalanced parentheses
+        assert!(parse("(($1+$2)").is_err());
+        assert!(parse("($1+$2))").is_err());
+        assert!(parse("sectbase(1").is_err());
+        assert!(parse("").is_err());
+
+        let e = parse("(groupstrat(1)+$4)").expect_err("unknown function");
+        assert_eq!(1, e.position);
+        assert_eq!(ParseErrorKind::UnknownFunction("groupstrat".into()), e.kind);
+        assert_eq!("unknown function \"groupstrat\" at offset 1", e.to_string());
+
+        let e = parse("$1 $2").expect_err("trailing");
+        assert_eq!((2, ParseErrorKind::Unexpected), (e.position, e.kind));
+        let e = parse("[$1]").expect_err("no digits");
+        assert_eq!((1, ParseErrorKind::InvalidNumber), (e.position, e.kind));
+        let e = parse("$100000000").expect_err("too large");
+        assert_eq!((1, ParseErrorKind::InvalidNumber), (e.position, e.kind));
+        let e = parse("").expect_err("empty");
+        assert_eq!(
+            (0, ParseErrorKind::ExpectedExpression),
+            (e.position, e.kind)
+        );
+        assert_eq!(
+            Expression::GroupStart(1),
+            "groupstart(1)".parse::<Expression>().expect("parse")
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Number and date formatting for human-oriented reports such as
+//! `psyk stat`.
+//!
+//! Listings never use these functions; their format is fixed by
+//! [labels](super::labels). Nothing here depends on the system locale, so
+//! reports are reproducible given the same [ReportStyle].
+
+use chrono::NaiveDateTime;
+
+/// How the digits of a number are grouped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// `1234567`
+    #[default]
+    None,
+    /// Thousands separated by a character, e.g. `1,234,567`
+    Thousands(char),
+}
+
+/// How a byte count is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteStyle {
+    /// `1474560`
+    #[default]
+    Raw,
+    /// Thousands separated by a character, e.g. `1 474 560`
+    Grouped(char),
+    /// Powers of 1024, e.g. `1.4 MiB`
+    Binary,
+    /// Powers of 1000, e.g. `1.5 MB`
+    Decimal,
+}
+
+/// How dates are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// `DD-MM-YY HH:MM:SS`, as in listings
+    #[default]
+    Listing,
+    /// `YYYY-MM-DD HH:MM:SS`
+    Iso,
+    /// `DD/MM/YYYY HH:MM:SS`
+    DayMonthYear,
+    /// `MM/DD/YYYY HH:MM:SS`
+    MonthDayYear,
+}
+
+/// The `--bytes` option of report commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteUnits {
+    /// plain byte counts
+    Raw,
+    /// byte counts with thousands separators
+    Grouped,
+    /// KiB, MiB, ... (powers of 1024)
+    Iec,
+    /// kB, MB, ... (powers of 1000)
+    Si,
+}
+
+/// Writes `n` with its digits grouped by `grouping`.
+pub fn count(n: u64, grouping: Grouping) -> String {
+    let digits = n.to_string();
+    let Grouping::Thousands(separator) = grouping else {
+        return digits;
+    };
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn scaled(n: u64, base: f64, units: &[&str]) -> String {
+    if (n as f64) < base {
+        return format!("{n} B");
+    }
+    let mut value = n as f64;
+    let mut unit = 0;
+    // also move up when rounding to one decimal would print e.g. 1024.0 KiB
+    while unit < units.len() && (value * 10.0).round() / 10.0 >= base {
+        value /= base;
+        unit += 1;
+    }
+    format!("{value:.1} {}", units[unit - 1])
+}
+
+/// Writes a byte count of `n` in `style`.
+pub fn bytes(n: u64, style: ByteStyle) -> String {
+    match style {
+        ByteStyle::Raw => count(n, Grouping::None),
+        ByteStyle::Grouped(separator) => count(n, Grouping::Thousands(separator)),
+        ByteStyle::Binary => scaled(n, 1024.0, &["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        ByteStyle::Decimal => scaled(n, 1000.0, &["kB", "MB", "GB", "TB", "PB", "EB"]),
+    }
+}
+
+/// Writes `datetime` in `format`.
+pub fn date(datetime: &NaiveDateTime, format: DateFormat) -> String {
+    let pattern = match format {
+        DateFormat::Listing => "%d-%m-%y %H:%M:%S",
+        DateFormat::Iso => "%Y-%m-%d %H:%M:%S",
+        DateFormat::DayMonthYear => "%d/%m/%Y %H:%M:%S",
+        DateFormat::MonthDayYear => "%m/%d/%Y %H:%M:%S",
+    };
+    datetime.format(pattern).to_string()
+}
+
+/// How reports write numbers and dates. The default matches the output of
+/// reports before styles were introduced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReportStyle {
+    pub bytes: ByteStyle,
+    pub counts: Grouping,
+    pub dates: DateFormat,
+}
+
+impl ReportStyle {
+    /// Builds a style from the `--human`, `--bytes`, and `--locale-sep`
+    /// options of report commands.
+    ///
+    /// `--human` groups counts and writes bytes in IEC units. A separator
+    /// groups counts and is used for `--bytes grouped`, which otherwise
+    /// separates with `,`.
+    pub fn from_options(human: bool, units: Option<ByteUnits>, separator: Option<char>) -> Self {
+        let mut style = Self::default();
+        if human {
+            style.bytes = ByteStyle::Binary;
+            style.counts = Grouping::Thousands(separator.unwrap_or(','));
+            style.dates = DateFormat::Iso;
+        }
+        if let Some(separator) = separator {
+            style.counts = Grouping::Thousands(separator);
+        }
+        if let Some(units) = units {
+            style.bytes = match units {
+                ByteUnits::Raw => ByteStyle::Raw,
+                ByteUnits::Grouped => ByteStyle::Grouped(separator.unwrap_or(',')),
+                ByteUnits::Iec => ByteStyle::Binary,
+                ByteUnits::Si => ByteStyle::Decimal,
+            };
+        }
+        style
+    }
+
+    pub fn bytes(&self, n: u64) -> String {
+        bytes(n, self.bytes)
+    }
+
+    pub fn count(&self, n: u64) -> String {
+        count(n, self.counts)
+    }
+
+    pub fn date(&self, datetime: &NaiveDateTime) -> String {
+        date(datetime, self.dates)
+    }
+}
+
+/// Parses the `--locale-sep` option, which must be a single character.
+pub fn parse_separator(s: &str) -> crate::error::Result<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => crate::error::bail!("the separator must be a single character: {s:?}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_count() {
+        assert_eq!("0", count(0, Grouping::Thousands(',')));
+        assert_eq!("999", count(999, Grouping::Thousands(',')));
+        assert_eq!("1,000", count(1000, Grouping::Thousands(',')));
+        assert_eq!("1 234 567", count(1_234_567, Grouping::Thousands(' ')));
+        assert_eq!("1234567", count(1_234_567, Grouping::None));
+        assert_eq!(
+            "18.446.744.073.709.551.615",
+            count(u64::MAX, Grouping::Thousands('.'))
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        for style in [
+            ByteStyle::Raw,
+            ByteStyle::Grouped(','),
+            ByteStyle::Binary,
+            ByteStyle::Decimal,
+        ] {
+            let zero = bytes(0, style);
+            assert!(zero == "0" || zero == "0 B", "{style:?}: {zero}");
+        }
+
+        assert_eq!("1048576", bytes(1 << 20, ByteStyle::Raw));
+        assert_eq!("1,048,576", bytes(1 << 20, ByteStyle::Grouped(',')));
+        assert_eq!("1023 B", bytes(1023, ByteStyle::Binary));
+        assert_eq!("1.0 KiB", bytes(1024, ByteStyle::Binary));
+        assert_eq!("1.0 MiB", bytes(1 << 20, ByteStyle::Binary));
+        assert_eq!("1.4 MiB", bytes(1_474_560, ByteStyle::Binary));
+        // rounds up into the next unit rather than printing 1024.0 KiB
+        assert_eq!("1.0 MiB", bytes((1 << 20) - 1, ByteStyle::Binary));
+        assert_eq!("1.0 EiB", bytes(1 << 60, ByteStyle::Binary));
+        assert_eq!("16.0 EiB", bytes(u64::MAX, ByteStyle::Binary));
+
+        assert_eq!("999 B", bytes(999, ByteStyle::Decimal));
+        assert_eq!("1.0 kB", bytes(1000, ByteStyle::Decimal));
+        assert_eq!("1.0 kB", bytes(1024, ByteStyle::Decimal));
+        assert_eq!("1.5 MB", bytes(1_474_560, ByteStyle::Decimal));
+        assert_eq!("18.4 EB", bytes(u64::MAX, ByteStyle::Decimal));
+        assert_eq!("18446744073709551615", bytes(u64::MAX, ByteStyle::Raw));
+    }
+
+    #[test]
+    fn test_date() {
+        let datetime = NaiveDate::from_ymd_opt(1996, 5, 15)
+            .and_then(|d| d.and_hms_opt(16, 9, 38))
+            .expect("datetime");
+        assert_eq!("15-05-96 16:09:38", date(&datetime, DateFormat::Listing));
+        assert_eq!("1996-05-15 16:09:38", date(&datetime, DateFormat::Iso));
+        assert_eq!(
+            "15/05/1996 16:09:38",
+            date(&datetime, DateFormat::DayMonthYear)
+        );
+        assert_eq!(
+            "05/15/1996 16:09:38",
+            date(&datetime, DateFormat::MonthDayYear)
+        );
+    }
+
+    #[test]
+    fn test_report_style() {
+        assert_eq!(
+            ReportStyle::default(),
+            ReportStyle::from_options(false, None, None)
+        );
+        let style = ReportStyle::from_options(true, None, Some(' '));
+        assert_eq!(ByteStyle::Binary, style.bytes);
+        assert_eq!("12 345", style.count(12345));
+        let style = ReportStyle::from_options(false, Some(ByteUnits::Grouped), Some('.'));
+        assert_eq!("12.345", style.bytes(12345));
+        assert!(parse_separator("ab").is_err());
+        assert_eq!(' ', parse_separator(" ").expect("separator"));
+    }
+}
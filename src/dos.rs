@@ -4,17 +4,18 @@
 //! A module for providing a DOS-like interface to `psyk`.
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use anyhow::{bail, Result};
 
 use crate::cli;
-use psyk::{display, io};
+use psyk::{display, io, LIB};
 
 fn dumpobj_usage() -> ! {
     let args: Vec<String> = env::args().collect();
-    eprintln!("Usage: {} <file> [/c] [/d]", args[0]);
+    eprintln!("Usage: {} [/c] [/d] <file> [/c] [/d]", args[0]);
     eprintln!();
     eprintln!("Options:");
     eprintln!("  /c    Show code listing");
@@ -24,30 +25,40 @@ fn dumpobj_usage() -> ! {
 
 /// Alternate main that accepts DOS-style arguments.
 ///
+/// Switches may come before or after the file and are case-insensitive.
+/// A switch is a `/` and one character, so absolute paths are still files.
+///
 /// Usage:
 /// - `program file.obj` - basic info
 /// - `program file.obj /c` - info with code listing
-/// - `program file.obj /d` - info with disassembly
+/// - `program /d file.obj` - info with disassembly
+/// - `program /c file.obj /d` - info with code listing and disassembly
 pub fn dumpobj_main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    let mut options = display::Options::default();
+    let mut options = display::Options::dumpobj_compat();
+    options.spelling = cli::locale_spelling();
 
-    let obj_path = match args.len() {
-        2 => PathBuf::from(args[1].clone()),
-        3 => {
-            match args[2].as_str() {
-                "/c" => options.code_format = display::CodeFormat::Hex,
-                "/d" => options.code_format = display::CodeFormat::Disassembly,
+    let (mut hex, mut disassembly) = (false, false);
+    let mut obj_path = None;
+    for arg in &args[1..] {
+        if arg.starts_with('/') && arg.chars().count() == 2 {
+            match arg.to_lowercase().as_str() {
+                "/c" => hex = true,
+                "/d" => disassembly = true,
                 _ => {
-                    eprintln!("Invalid option: {}\n", args[2]);
+                    eprintln!("Invalid option: {arg}\n");
                     dumpobj_usage()
                 }
             }
-            PathBuf::from(args[1].clone())
+        } else if obj_path.replace(PathBuf::from(arg)).is_some() {
+            dumpobj_usage()
         }
-        _ => dumpobj_usage(),
+    }
+    let Some(obj_path) = obj_path else {
+        dumpobj_usage()
     };
+    options.code_format = display::CodeFormat::from_flags(hex, disassembly);
 
     // info
     let obj = io::read(&obj_path)?;
@@ -58,16 +69,131 @@ pub fn dumpobj_main() -> Result<()> {
 fn psylib_usage() -> ! {
     let args: Vec<String> = env::args().collect();
     eprintln!("Usage: {} <option> <library> ...", args[0]);
-    eprintln!("Usage: {} /a add modules", args[0]);
-    eprintln!("       {} /d delete modules []", args[0]);
+    eprintln!("       {} /a <library.lib> <obj1> [obj2...]", args[0]);
+    eprintln!("       {} /c <library.lib> <obj1> [obj2...]", args[0]);
+    eprintln!("       {} /d <library.lib> <module1> [module2...]", args[0]);
+    eprintln!("       {} /r <library.lib> <module> <new name>", args[0]);
     eprintln!("       {} /u <library.lib> <obj1> [obj2...]", args[0]);
-    eprintln!("       {} /x <library.lib>", args[0]);
-    eprintln!("       {} /l <library.lib>", args[0]);
+    eprintln!("       {} /x <library.lib> [module1...]", args[0]);
+    eprintln!("       {} /l <library.lib> [module1...]", args[0]);
+    eprintln!();
+    eprintln!("OBJ names may use * and ? wildcards, as may module names for /l.");
     process::exit(1);
 }
 
-/// - `psylib /x file.lib` - split library
-/// - `psylib /a output.lib file1.obj file2.obj` - join objects
+/// Returns `true` if `name` matches the DOS-style wildcard `pattern`, where
+/// `*` matches any run of characters and `?` any one. Case is ignored.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_uppercase().chars().collect::<Vec<_>>();
+    let name = name.to_uppercase().chars().collect::<Vec<_>>();
+
+    // the last `*` and the position in `name` it was tried from
+    let mut star = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Expands the wildcards in the file names of `args`, like `PSYLIB.EXE`
+/// did for DOS shells which don't. Arguments without wildcards, or which
+/// name a file that exists, are kept as they are.
+fn expand_paths(args: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for arg in args {
+        let path = PathBuf::from(arg);
+        let pattern = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if path.exists() || !pattern.contains(['*', '?']) {
+            paths.push(path);
+            continue;
+        }
+
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut matches = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .filter(|entry| wildcard_match(&pattern, &entry.file_name().to_string_lossy()))
+            .map(|entry| path.with_file_name(entry.file_name()))
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            bail!("No files match {arg}");
+        }
+        matches.sort();
+        paths.extend(matches);
+    }
+    Ok(paths)
+}
+
+/// Lists the modules of the LIB at `lib_path` whose names match any of
+/// `patterns`, or every module if there are none.
+fn psylib_list(lib_path: &Path, patterns: &[String]) -> Result<()> {
+    if patterns.is_empty() {
+        return Ok(cli::info(
+            &mut std::io::stdout(),
+            lib_path,
+            false,
+            false,
+            false,
+        )?);
+    }
+
+    let lib = io::read_lib(lib_path)?;
+    let modules = lib
+        .modules()
+        .iter()
+        .filter(|module| patterns.iter().any(|p| wildcard_match(p, &module.name())))
+        .cloned()
+        .collect::<Vec<_>>();
+    if modules.is_empty() {
+        bail!(
+            "{}: no modules match {}",
+            lib_path.display(),
+            patterns.join(", ")
+        );
+    }
+    let mut options = display::Options::modern();
+    options.spelling = cli::locale_spelling();
+    println!(
+        "{}",
+        display::PsyXDisplayable::wrap(&LIB::new(modules), options)
+    );
+    Ok(())
+}
+
+/// Alternate main that accepts `PSYLIB.EXE`-style arguments.
+///
+/// Usage:
+/// - `psylib /a file.lib file1.obj file2.obj` - add objects
+/// - `psylib /c file.lib *.obj` - create a library
+/// - `psylib /d file.lib MODULE1 MODULE2` - delete modules
+/// - `psylib /r file.lib MODULE NEWNAME` - rename a module
+/// - `psylib /u file.lib file1.obj` - update objects
+/// - `psylib /x file.lib [MODULE...]` - split library, or extract modules
+/// - `psylib /l file.lib [MODULE...]` - list modules
 pub fn psylib_main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
 
@@ -75,52 +201,32 @@ pub fn psylib_main() -> Result<()> {
         psylib_usage();
     }
 
-    // Check for subcommands
-    match args[1].to_lowercase().as_str() {
-        "/a" => {
-            if args.len() < 4 {
-                bail!("Usage: {} /a <library> <obj>", args[0]);
-            }
-            cli::add(&PathBuf::from(&args[2]), &PathBuf::from(&args[3]))
-        }
-        "/d" => {
-            if args.len() < 4 {
-                bail!("Usage: {} /d <output.lib> <obj1> [obj2...]", args[0]);
-            }
-            let lib_path = PathBuf::from(&args[2]);
-            let obj_name = args[3].clone();
-
-            cli::delete(&lib_path, [obj_name].to_vec())
-        }
-        "/u" => {
-            if args.len() < 4 {
-                bail!("Usage: {} /u <output.lib> <obj1> [obj2...]", args[0]);
-            }
-            let lib_path = &PathBuf::from(&args[2]);
-            let obj_paths: Vec<PathBuf> = args[3..].iter().map(PathBuf::from).collect();
-            cli::update(lib_path, obj_paths)
-        }
-        "/x" => {
-            if args.len() < 3 {
-                bail!("Usage: {} /x <library>", args[0]);
-            }
-            cli::split(&PathBuf::from(&args[2]))
-        }
-        "/l" => {
-            if args.len() < 3 {
-                bail!("Usage: {} /l <library>", args[0]);
-            }
-            cli::info(
-                &mut std::io::stdout(),
-                &PathBuf::from(&args[2]),
-                false,
-                false,
-                false,
-            )
-        }
+    let option = args[1].to_lowercase();
+    // the least each option needs, including the library
+    let required = match option.as_str() {
+        "/a" | "/c" | "/d" | "/u" => 2,
+        "/r" => 3,
+        "/x" | "/l" => 1,
         _ => {
             eprintln!("Invalid option: {}\n", args[1]);
             psylib_usage()
         }
+    };
+    if args.len() < 2 + required {
+        psylib_usage();
     }
+    let lib_path = PathBuf::from(&args[2]);
+    let rest = &args[3..];
+
+    let result = match option.as_str() {
+        "/a" => cli::add(&lib_path, expand_paths(rest)?, false, false),
+        "/c" => cli::join(&lib_path, expand_paths(rest)?, false),
+        "/d" => cli::delete(&lib_path, rest.to_vec(), false),
+        "/r" => cli::rename(&lib_path, &rest[0], &rest[1], false),
+        "/u" => cli::update(&lib_path, expand_paths(rest)?, false, false, false),
+        "/x" => cli::extract(&lib_path, rest, None, cli::OnCollision::Error),
+        "/l" => return psylib_list(&lib_path, rest),
+        _ => unreachable!("checked above"),
+    };
+    Ok(result?)
 }
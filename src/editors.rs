@@ -0,0 +1,301 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Syntax highlighting grammars for `psyk list` output.
+//!
+//! Grammars are built from the [labels] used by the listing itself, so they
+//! can be regenerated whenever the listing format changes rather than being
+//! maintained by hand.
+
+use std::fmt::Write;
+
+use super::display::labels;
+
+/// Editor grammar formats that can be generated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrammarFormat {
+    /// A TextMate grammar (`.tmLanguage.json`), also used by VS Code and
+    /// Sublime Text.
+    TextMate,
+    /// A Vim syntax file.
+    Vim,
+}
+
+/// The name used for the grammar scope and syntax group prefixes.
+const NAME: &str = "psyk";
+
+/// A single highlighting rule.
+///
+/// Patterns are written using a small, shared subset of regular expression
+/// syntax: `\s`, `\d`, `+`, `*`, `?`, `(...)`, `|`, `[...]`, `^`, and `$`.
+/// [vim_pattern] rewrites them into Vim's "magic" dialect.
+struct Rule {
+    /// The TextMate scope name.
+    scope: &'static str,
+    /// The Vim highlight group the rule links to.
+    vim_group: &'static str,
+    pattern: String,
+}
+
+/// Escapes regular expression metacharacters in `s`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Builds an alternation from `words`, longest first so that shorter
+/// prefixes (`Set to`) don't hide longer labels (`Set to line`).
+fn alternation(words: &[&str]) -> String {
+    let mut words = words.to_vec();
+    words.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    words
+        .iter()
+        .map(|w| escape(w))
+        .collect::<Vec<String>>()
+        .join("|")
+}
+
+fn rules() -> Vec<Rule> {
+    vec![
+        Rule {
+            scope: "markup.heading.lib.psyk",
+            vim_group: "Title",
+            pattern: format!(
+                "^{}$",
+                labels::LIB_HEADER
+                    .split_whitespace()
+                    .map(escape)
+                    .collect::<Vec<String>>()
+                    .join("\\s+")
+            ),
+        },
+        Rule {
+            scope: "markup.heading.obj.psyk",
+            vim_group: "Title",
+            pattern: format!("^\\s*{} \\d+$", escape(labels::OBJ_HEADER)),
+        },
+        Rule {
+            scope: "entity.name.section.module.psyk",
+            vim_group: "Identifier",
+            pattern: "^\\S+\\s+\\d\\d-\\d\\d-\\d\\d \\d\\d:\\d\\d:\\d\\d".into(),
+        },
+        Rule {
+            scope: "keyword.other.section.psyk",
+            vim_group: "Statement",
+            pattern: format!("^\\s*\\d+ : ({})", alternation(labels::SECTIONS)),
+        },
+        Rule {
+            scope: "constant.numeric.address.psyk",
+            vim_group: "LineNr",
+            pattern: "^\\s*[0-9a-f][0-9a-f][0-9a-f][0-9a-f]:".into(),
+        },
+        Rule {
+            scope: "comment.block.opcode.psyk",
+            vim_group: "Comment",
+            pattern: "/\\* [0-9a-f]+ \\*/".into(),
+        },
+        Rule {
+            scope: "support.function.expression.psyk",
+            vim_group: "Function",
+            pattern: format!("({})\\(", alternation(labels::FUNCTIONS)),
+        },
+        Rule {
+            scope: "keyword.operator.expression.psyk",
+            vim_group: "Operator",
+            pattern: alternation(labels::OPERATORS),
+        },
+        Rule {
+            scope: "constant.numeric.hex.psyk",
+            vim_group: "Number",
+            pattern: "\\$[0-9a-f]+".into(),
+        },
+        Rule {
+            scope: "string.quoted.psyk",
+            vim_group: "String",
+            pattern: "('[^']*'|\"[^\"]*\"|`[^`]*`)".into(),
+        },
+    ]
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Generates a TextMate grammar for listings.
+pub fn textmate_grammar() -> String {
+    let patterns = rules()
+        .iter()
+        .map(|rule| {
+            format!(
+                "    {{\n      \"name\": {},\n      \"match\": {}\n    }}",
+                json_string(rule.scope),
+                json_string(&rule.pattern)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    format!(
+        "{{\n  \"name\": \"PSY-Q Listing\",\n  \"scopeName\": \"source.{NAME}\",\n  \
+        \"fileTypes\": [],\n  \"patterns\": [\n{patterns}\n  ]\n}}\n"
+    )
+}
+
+/// Rewrites a pattern into Vim's "magic" regular expression dialect.
+fn vim_pattern(pattern: &str) -> String {
+    let mut vim = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // escaped characters which are literal in vim
+                Some(c @ ('(' | ')' | '|' | '+' | '?' | '{' | '}')) => vim.push(c),
+                Some(c) => {
+                    vim.push('\\');
+                    vim.push(c);
+                }
+                None => vim.push('\\'),
+            },
+            '[' if !in_class => {
+                in_class = true;
+                vim.push(c);
+            }
+            ']' if in_class => {
+                in_class = false;
+                vim.push(c);
+            }
+            '(' | ')' | '|' | '+' | '?' if !in_class => {
+                vim.push('\\');
+                vim.push(c);
+            }
+            '"' => vim.push_str("\\\""),
+            c => vim.push(c),
+        }
+    }
+    vim
+}
+
+/// Generates a Vim syntax file for listings.
+pub fn vim_syntax() -> String {
+    let mut syntax = String::new();
+    let _ = writeln!(syntax, "\" Vim syntax file");
+    let _ = writeln!(syntax, "\" Language: PSY-Q listing (psyk list)");
+    let _ = writeln!(syntax, "\" Generated by psyk generate-grammar");
+    let _ = writeln!(syntax);
+    let _ = writeln!(syntax, "if exists(\"b:current_syntax\")");
+    let _ = writeln!(syntax, "  finish");
+    let _ = writeln!(syntax, "endif");
+    let _ = writeln!(syntax);
+
+    let rules = rules();
+    for (i, rule) in rules.iter().enumerate() {
+        let _ = writeln!(
+            syntax,
+            "syn match {NAME}Rule{i} \"{}\"",
+            vim_pattern(&rule.pattern)
+        );
+    }
+    let _ = writeln!(syntax);
+    for (i, rule) in rules.iter().enumerate() {
+        let _ = writeln!(syntax, "hi def link {NAME}Rule{i} {}", rule.vim_group);
+    }
+    let _ = writeln!(syntax);
+    let _ = writeln!(syntax, "let b:current_syntax = \"{NAME}\"");
+    syntax
+}
+
+/// Generates a grammar in the requested format.
+pub fn generate(format: GrammarFormat) -> String {
+    match format {
+        GrammarFormat::TextMate => textmate_grammar(),
+        GrammarFormat::Vim => vim_syntax(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn all_labels() -> Vec<&'static str> {
+        let mut all = vec![labels::OBJ_HEADER];
+        all.extend(labels::SECTIONS);
+        all.extend(labels::FUNCTIONS);
+        all.extend(labels::OPERATORS);
+        all
+    }
+
+    #[test]
+    fn test_every_label_has_a_rule() {
+        let rules = rules();
+        for label in all_labels() {
+            assert!(
+                rules.iter().any(|r| r.pattern.contains(&escape(label))),
+                "no rule for {label:?}"
+            );
+        }
+        for word in labels::LIB_HEADER.split_whitespace() {
+            assert!(rules[0].pattern.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_textmate_grammar() {
+        let grammar: serde_json::Value =
+            serde_json::from_str(&textmate_grammar()).expect("valid JSON");
+        assert_eq!("source.psyk", grammar["scopeName"]);
+
+        let patterns = grammar["patterns"].as_array().expect("patterns");
+        assert_eq!(rules().len(), patterns.len());
+        let matches = patterns
+            .iter()
+            .map(|p| p["match"].as_str().expect("match").to_string())
+            .collect::<Vec<String>>();
+        for label in all_labels() {
+            assert!(
+                matches.iter().any(|m| m.contains(&escape(label))),
+                "no rule for {label:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_vim_syntax() {
+        let syntax = vim_syntax();
+        assert!(
+            syntax.contains("syn match psykRule3 \"^\\s*\\d\\+ : \\(Set 3-byte size register\\|")
+        );
+        assert!(syntax.contains("hi def link psykRule0 Title"));
+        assert!(syntax.contains("Set to line\\|"));
+        assert!(syntax.contains("\\(groupstart\\|"));
+    }
+
+    #[test]
+    fn test_vim_pattern() {
+        assert_eq!("\\(a\\|b\\)\\+", vim_pattern("(a|b)+"));
+        assert_eq!("[+|]", vim_pattern("[+|]"));
+        assert_eq!("a(b)|", vim_pattern("a\\(b\\)\\|"));
+        assert_eq!("\\*\\$\\.", vim_pattern("\\*\\$\\."));
+    }
+}
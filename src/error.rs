@@ -0,0 +1,266 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! The errors returned throughout the library, and the [ParseWarning]s for
+//! anomalies that don't stop a file from being read.
+//!
+//! Each [Error] says what went wrong in a way that can be matched on:
+//!
+//! | Variant | Returned when |
+//! |---------|---------------|
+//! | [BadMagic](Error::BadMagic) | a file read as a [LIB](crate::LIB) or [OBJ](crate::OBJ) starts with another magic number |
+//! | [Unrecognized](Error::Unrecognized) | a file read as either starts with a magic number that isn't known |
+//! | [Unsupported](Error::Unsupported) | the file is a COFF object, PS-X EXE, or SYM file instead |
+//! | [UnknownVersion](Error::UnknownVersion) | a file has an unknown format version and versions are [strict](crate::io::ReadOptions::strict_versions) |
+//! | [TooSmall](Error::TooSmall) | the file is too small to hold a magic number |
+//! | [Parse](Error::Parse) | the contents after the magic number can't be parsed |
+//! | [Io](Error::Io) | a file can't be opened, read, or written |
+//! | [InvalidName](Error::InvalidName) | a name can't be stored in a section or module |
+//! | [Other](Error::Other) | anything else, like a module missing from a LIB |
+//!
+//! Messages are the same as they were when these functions returned
+//! `anyhow::Result`, so an [Error] converts into an `anyhow::Error` with `?`
+//! without changing what is printed.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::{io, Error};
+//!
+//! match io::read_obj(Path::new("tests/data/truncated.lib")) {
+//!     Err(Error::BadMagic { found, .. }) => assert_eq!(*b"LIB", found),
+//!     other => panic!("{other:?}"),
+//! }
+//! ```
+
+use std::fmt;
+
+use crate::metrics::FileKind;
+
+/// A [Result](std::result::Result) whose error defaults to [Error].
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Why reading, writing, or building a file failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The file starts with the wrong magic number for the type being read.
+    BadMagic {
+        offset: u64,
+        found: [u8; 3],
+    },
+    /// The file's magic number isn't that of any known file.
+    Unrecognized {
+        found: [u8; 3],
+    },
+    /// The file is a known type that isn't a [LIB](crate::LIB) or
+    /// [OBJ](crate::OBJ).
+    Unsupported(FileKind),
+    /// The file, or the OBJ of a LIB's `module`, has a format version other
+    /// than [LIB::VERSION](crate::LIB::VERSION) or
+    /// [OBJ::VERSION](crate::OBJ::VERSION).
+    UnknownVersion {
+        kind: FileKind,
+        found: u8,
+        module: Option<String>,
+    },
+    /// The file is too small to hold a magic number.
+    TooSmall,
+    /// The file couldn't be parsed or serialized. `offset` is where the
+    /// innermost error was found.
+    Parse {
+        offset: u64,
+        source: binrw::Error,
+    },
+    Io(std::io::Error),
+    /// A name that can't be stored, with the reason.
+    InvalidName(String),
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Returns an [Other](Error::Other) error wrapping `error`, which may be
+    /// another error or a message.
+    pub(crate) fn other(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Other(error.into())
+    }
+
+    /// Returns `true` if the file ended before it could be parsed.
+    pub fn is_truncated(&self) -> bool {
+        match self {
+            Self::Parse { source, .. } => source.root_cause().is_eof(),
+            Self::Io(e) => e.kind() == std::io::ErrorKind::UnexpectedEof,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadMagic { offset, found } => write!(f, "bad magic at {offset:#x}: {found:?}"),
+            Self::Unrecognized { found } => write!(f, "Unrecognized magic {found:?}"),
+            Self::Unsupported(FileKind::COFF) => {
+                write!(f, "this is a COFF object, not a PSY-Q LNK object")
+            }
+            Self::Unsupported(FileKind::EXE) => {
+                write!(f, "this is a PS-X EXE, not a PSY-Q LIB or LNK object")
+            }
+            Self::Unsupported(FileKind::SYM) => {
+                write!(f, "this is a PSY-Q SYM file, not a LIB or LNK object")
+            }
+            Self::Unsupported(kind) => {
+                write!(f, "this is a {kind:?} file, not a LIB or LNK object")
+            }
+            Self::UnknownVersion {
+                kind,
+                found,
+                module,
+            } => {
+                if let Some(module) = module {
+                    write!(f, "module {module}: ")?;
+                }
+                let (name, known) = match kind {
+                    FileKind::LIB => ("LIB", crate::LIB::VERSION),
+                    _ => ("LNK", crate::OBJ::VERSION),
+                };
+                write!(
+                    f,
+                    "unknown {name} version {found}; only version {known} is known"
+                )
+            }
+            Self::TooSmall => write!(f, "File too small to contain valid PSY-Q magic number"),
+            Self::Parse { source, .. } => write!(f, "{source}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::InvalidName(message) => write!(f, "{message}"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse { source, .. } => source.source(),
+            Self::Io(e) => e.source(),
+            Self::Other(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Something unusual found while reading a [LIB](crate::LIB) or
+/// [OBJ](crate::OBJ) that didn't stop it from being read. See
+/// [OBJ::warnings](crate::OBJ::warnings), [LIB::warnings](crate::LIB::warnings),
+/// and [read_with_warnings](crate::io::read_with_warnings).
+///
+/// Nothing is printed when a warning is found; the command line tools print
+/// them with `--verbose`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// The file has a format version other than
+    /// [LIB::VERSION](crate::LIB::VERSION) or
+    /// [OBJ::VERSION](crate::OBJ::VERSION).
+    UnknownVersion { kind: FileKind, found: u8 },
+    /// A section whose format is assumed from `DUMPOBJ.EXE`'s output rather
+    /// than known, like a [ProcedureCall](crate::Section::ProcedureCall),
+    /// with tag `magic` at `offset` in the file.
+    UnimplementedSection { magic: u8, offset: u64 },
+    /// A section with tag `magic`, which isn't known, at `offset` in the
+    /// file. It was read [leniently](crate::io::ReadOptions::lenient) as
+    /// [Unknown](crate::Section::Unknown) by skipping `skipped_bytes`, its
+    /// tag included, to where the next section appears to start.
+    UnknownSection {
+        magic: u8,
+        offset: u64,
+        skipped_bytes: u64,
+    },
+    /// `count` bytes follow the end of an OBJ. See
+    /// [OBJ::trailing_bytes](crate::OBJ::trailing_bytes).
+    TrailingBytes { count: usize },
+    /// A module's timestamp, `created`, isn't a valid date and time.
+    SuspiciousTimestamp { created: u32 },
+    /// `warning` was found in the LIB's `module`.
+    InModule {
+        module: String,
+        warning: Box<ParseWarning>,
+    },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownVersion { kind, found } => {
+                let (name, known) = match kind {
+                    FileKind::LIB => ("LIB", crate::LIB::VERSION),
+                    _ => ("LNK", crate::OBJ::VERSION),
+                };
+                write!(
+                    f,
+                    "unknown {name} version {found}; only version {known} is known"
+                )
+            }
+            Self::UnimplementedSection { magic, offset } => write!(
+                f,
+                "section {magic} at {offset:#x} is read in an untested format"
+            ),
+            Self::UnknownSection {
+                magic,
+                offset,
+                skipped_bytes,
+            } => write!(
+                f,
+                "unknown section {magic} at {offset:#x}; skipped {skipped_bytes} bytes"
+            ),
+            Self::TrailingBytes { count } => {
+                write!(f, "{count} bytes follow the end of the object")
+            }
+            Self::SuspiciousTimestamp { created } => {
+                write!(f, "timestamp {created:#010x} is not a valid date")
+            }
+            Self::InModule { module, warning } => write!(f, "{module}: {warning}"),
+        }
+    }
+}
+
+/// I/O errors are unwrapped. Anything else is a [Parse](Error::Parse) error
+/// at the position binrw reports.
+impl From<binrw::Error> for Error {
+    fn from(e: binrw::Error) -> Self {
+        if let binrw::Error::Io(e) = e {
+            return Self::Io(e);
+        }
+        let offset = match e.root_cause() {
+            binrw::Error::BadMagic { pos, .. }
+            | binrw::Error::AssertFail { pos, .. }
+            | binrw::Error::Custom { pos, .. }
+            | binrw::Error::NoVariantMatch { pos }
+            | binrw::Error::EnumErrors { pos, .. } => *pos,
+            _ => 0,
+        };
+        Self::Parse { offset, source: e }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Other(e.into())
+    }
+}
+
+/// Returns an [Other](Error::Other) error with a formatted message, like
+/// `anyhow::bail!`.
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::Error::Other(format!($($arg)*).into()))
+    };
+}
+
+pub(crate) use bail;
@@ -0,0 +1,380 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Evaluating patch [Expression]s.
+//!
+//! An expression refers to addresses that aren't known until a link lays
+//! out its sections: the address of a symbol, the base of a section in this
+//! module, the start of a group. An [ExpressionResolver] provides them, and
+//! [Expression::evaluate] computes the value that is patched into the code.
+//!
+//! Arithmetic is done on `u32`s and wraps on overflow. Shift amounts are
+//! taken modulo 32, shifts to the right are logical, and comparisons are
+//! unsigned and produce `1` or `0`. The Saturn operators and
+//! [Dashes](Expression::Dashes) aren't understood well enough to evaluate
+//! and are [unsupported](EvalError::Unsupported).
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use psyk::eval::ExpressionResolver;
+//! use psyk::Expression;
+//!
+//! struct Layout(HashMap<u16, u32>);
+//!
+//! impl ExpressionResolver for Layout {
+//!     fn symbol_address(&self, _symbol: u16) -> Option<u32> {
+//!         None
+//!     }
+//!
+//!     fn section_base(&self, section: u16) -> Option<u32> {
+//!         self.0.get(&section).copied()
+//!     }
+//! }
+//!
+//! let expression = Expression::Add(
+//!     Box::new(Expression::SectionAddressIndex(1)),
+//!     Box::new(Expression::Constant(0x22)),
+//! );
+//! let layout = Layout(HashMap::from([(1, 0x8001_0000)]));
+//! assert_eq!(Ok(0x8001_0022), expression.evaluate(&layout));
+//! ```
+
+use std::fmt;
+
+use crate::Expression;
+
+/// Provides the addresses an [Expression] refers to.
+///
+/// Only [symbol_address](Self::symbol_address) and
+/// [section_base](Self::section_base) are required. The others don't
+/// resolve unless they're implemented.
+pub trait ExpressionResolver {
+    /// The address of the symbol with `number`, `[x]`.
+    fn symbol_address(&self, symbol: u16) -> Option<u32>;
+
+    /// The address of the part of `section` this module contributes,
+    /// `sectbase(x)`.
+    fn section_base(&self, section: u16) -> Option<u32>;
+
+    /// The address of the start of `section` in the link, `sectstart(x)`.
+    fn section_start(&self, _section: u16) -> Option<u32> {
+        None
+    }
+
+    /// The address of the end of `section` in the link, `sectend(x)`.
+    fn section_end(&self, _section: u16) -> Option<u32> {
+        None
+    }
+
+    /// The address of the start of `group`, `groupstart(x)`.
+    fn group_start(&self, _group: u16) -> Option<u32> {
+        None
+    }
+
+    /// The offset of `group`, `groupof(x)`.
+    fn group_offset(&self, _group: u16) -> Option<u32> {
+        None
+    }
+
+    /// The `ORG` address of the group of `symbol`, `grouporg(x)`.
+    fn group_org(&self, _symbol: u16) -> Option<u32> {
+        None
+    }
+
+    /// `bank(x)`.
+    fn bank(&self, _symbol: u16) -> Option<u32> {
+        None
+    }
+
+    /// `sectof(x)`.
+    fn section_of(&self, _symbol: u16) -> Option<u32> {
+        None
+    }
+
+    /// `offs(x)`.
+    fn offset(&self, _symbol: u16) -> Option<u32> {
+        None
+    }
+
+    /// `seg(x)`.
+    fn segment(&self, _symbol: u16) -> Option<u32> {
+        None
+    }
+}
+
+/// Why an [Expression] couldn't be evaluated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvalError {
+    /// The resolver didn't provide the value of this expression.
+    Unresolved(Expression),
+    /// The right operand of this division or modulo is zero.
+    DivisionByZero(Expression),
+    /// The operator, named by [Expression::kind], can't be evaluated yet.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unresolved(expression) => write!(f, "{expression} could not be resolved"),
+            Self::DivisionByZero(expression) => write!(f, "{expression} divides by zero"),
+            Self::Unsupported(kind) => write!(f, "{kind} expressions can't be evaluated"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Evaluates `expression` with the addresses provided by `resolver`. See
+/// [Expression::evaluate].
+pub fn evaluate(
+    expression: &Expression,
+    resolver: &impl ExpressionResolver,
+) -> Result<u32, EvalError> {
+    let resolve =
+        |value: Option<u32>| value.ok_or_else(|| EvalError::Unresolved(expression.clone()));
+    let binary = |lhs: &Expression, rhs: &Expression| -> Result<(u32, u32), EvalError> {
+        Ok((evaluate(lhs, resolver)?, evaluate(rhs, resolver)?))
+    };
+    let divisor = |rhs: u32| {
+        if rhs == 0 {
+            Err(EvalError::DivisionByZero(expression.clone()))
+        } else {
+            Ok(rhs)
+        }
+    };
+
+    Ok(match expression {
+        Expression::Constant(value) => *value,
+        Expression::SymbolAddressIndex(symbol) => resolve(resolver.symbol_address(*symbol))?,
+        Expression::SectionAddressIndex(section) => resolve(resolver.section_base(*section))?,
+        Expression::Bank(symbol) => resolve(resolver.bank(*symbol))?,
+        Expression::SectionOffset(symbol) => resolve(resolver.section_of(*symbol))?,
+        Expression::Offset(symbol) => resolve(resolver.offset(*symbol))?,
+        Expression::SectionStart(section) => resolve(resolver.section_start(*section))?,
+        Expression::GroupStart(group) => resolve(resolver.group_start(*group))?,
+        Expression::GroupOffset(group) => resolve(resolver.group_offset(*group))?,
+        Expression::Segment(symbol) => resolve(resolver.segment(*symbol))?,
+        Expression::GroupOrg(symbol) => resolve(resolver.group_org(*symbol))?,
+        Expression::SectionEnd(section) => resolve(resolver.section_end(*section))?,
+
+        Expression::Equals(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs == rhs).into()
+        }
+        Expression::NotEquals(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs != rhs).into()
+        }
+        Expression::LTE(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs <= rhs).into()
+        }
+        Expression::LessThan(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs < rhs).into()
+        }
+        Expression::GTE(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs >= rhs).into()
+        }
+        Expression::GreaterThan(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            (lhs > rhs).into()
+        }
+
+        Expression::Add(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs.wrapping_add(rhs)
+        }
+        Expression::Subtract(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs.wrapping_sub(rhs)
+        }
+        Expression::Multiply(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs.wrapping_mul(rhs)
+        }
+        Expression::Divide(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs / divisor(rhs)?
+        }
+        Expression::Mod(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs % divisor(rhs)?
+        }
+        Expression::And(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs & rhs
+        }
+        Expression::Or(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs | rhs
+        }
+        Expression::XOR(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs ^ rhs
+        }
+        Expression::LeftShift(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs.wrapping_shl(rhs)
+        }
+        Expression::RightShift(lhs, rhs) => {
+            let (lhs, rhs) = binary(lhs, rhs)?;
+            lhs.wrapping_shr(rhs)
+        }
+
+        // the meaning of these isn't known yet
+        Expression::Dashes(..)
+        | Expression::Revword(..)
+        | Expression::Check0(..)
+        | Expression::Check1(..)
+        | Expression::BitRange(..)
+        | Expression::ArshiftChk(..) => return Err(EvalError::Unsupported(expression.kind())),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct Layout {
+        symbols: HashMap<u16, u32>,
+        sections: HashMap<u16, u32>,
+    }
+
+    impl ExpressionResolver for Layout {
+        fn symbol_address(&self, symbol: u16) -> Option<u32> {
+            self.symbols.get(&symbol).copied()
+        }
+
+        fn section_base(&self, section: u16) -> Option<u32> {
+            self.sections.get(&section).copied()
+        }
+
+        fn section_start(&self, section: u16) -> Option<u32> {
+            self.section_base(section).map(|base| base & !0xFFFF)
+        }
+    }
+
+    fn layout() -> Layout {
+        Layout {
+            symbols: HashMap::from([(0x0B, 0x8001_2000)]),
+            sections: HashMap::from([(1, 0x8001_0100)]),
+        }
+    }
+
+    fn constant(value: u32) -> Box<Expression> {
+        Box::new(Expression::Constant(value))
+    }
+
+    #[test]
+    fn test_nested() {
+        let layout = layout();
+        // (sectbase(1)+$22)
+        let base = Expression::Add(Box::new(Expression::SectionAddressIndex(1)), constant(0x22));
+        assert_eq!(Ok(0x8001_0122), base.evaluate(&layout));
+
+        // (([b]-(sectbase(1)+$22))>>$2)
+        let offset = Expression::RightShift(
+            Box::new(Expression::Subtract(
+                Box::new(Expression::SymbolAddressIndex(0x0B)),
+                Box::new(base),
+            )),
+            constant(2),
+        );
+        assert_eq!(Ok((0x2000 - 0x122) >> 2), offset.evaluate(&layout));
+
+        let start = Expression::SectionStart(1);
+        assert_eq!(Ok(0x8001_0000), start.evaluate(&layout));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let layout = layout();
+        let cases = [
+            (Expression::Add(constant(u32::MAX), constant(2)), 1),
+            (Expression::Subtract(constant(0), constant(1)), u32::MAX),
+            (Expression::Multiply(constant(0x8000_0000), constant(2)), 0),
+            (Expression::LeftShift(constant(1), constant(33)), 2),
+            (
+                Expression::RightShift(constant(0x8000_0000), constant(31)),
+                1,
+            ),
+            (Expression::Divide(constant(7), constant(2)), 3),
+            (Expression::Mod(constant(7), constant(2)), 1),
+            (Expression::And(constant(0xF0F0), constant(0xFF00)), 0xF000),
+            (Expression::Or(constant(0xF0F0), constant(0xFF00)), 0xFFF0),
+            (Expression::XOR(constant(0xF0F0), constant(0xFF00)), 0x0FF0),
+        ];
+        for (expression, value) in cases {
+            assert_eq!(Ok(value), expression.evaluate(&layout), "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let layout = layout();
+        let compare = |lhs: u32, rhs: u32| {
+            [
+                Expression::Equals(constant(lhs), constant(rhs)),
+                Expression::NotEquals(constant(lhs), constant(rhs)),
+                Expression::LTE(constant(lhs), constant(rhs)),
+                Expression::LessThan(constant(lhs), constant(rhs)),
+                Expression::GTE(constant(lhs), constant(rhs)),
+                Expression::GreaterThan(constant(lhs), constant(rhs)),
+            ]
+            .iter()
+            .map(|e| e.evaluate(&layout).expect("value"))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, 0, 1, 0, 1, 0], compare(5, 5));
+        assert_eq!(vec![0, 1, 1, 1, 0, 0], compare(4, 5));
+        assert_eq!(vec![0, 1, 0, 0, 1, 1], compare(6, 5));
+        // unsigned
+        assert_eq!(vec![0, 1, 0, 0, 1, 1], compare(u32::MAX, 0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let layout = layout();
+        let divide = Expression::Divide(
+            constant(1),
+            Box::new(Expression::Subtract(constant(2), constant(2))),
+        );
+        assert_eq!(
+            Err(EvalError::DivisionByZero(divide.clone())),
+            divide.evaluate(&layout)
+        );
+        assert!(Expression::Mod(constant(1), constant(0))
+            .evaluate(&layout)
+            .is_err());
+
+        let missing = Expression::Add(Box::new(Expression::SymbolAddressIndex(0x0C)), constant(4));
+        let error = missing.evaluate(&layout).expect_err("unresolved");
+        assert_eq!(
+            EvalError::Unresolved(Expression::SymbolAddressIndex(0x0C)),
+            error
+        );
+        assert_eq!("[c] could not be resolved", error.to_string());
+        assert_eq!(
+            Err(EvalError::Unresolved(Expression::GroupStart(1))),
+            Expression::GroupStart(1).evaluate(&layout)
+        );
+
+        for expression in [
+            Expression::Revword(constant(1), constant(2)),
+            Expression::Check0(constant(1), constant(2)),
+            Expression::Check1(constant(1), constant(2)),
+            Expression::BitRange(constant(1), constant(2)),
+            Expression::ArshiftChk(constant(1), constant(2)),
+        ] {
+            assert_eq!(
+                Err(EvalError::Unsupported(expression.kind())),
+                expression.evaluate(&layout)
+            );
+        }
+    }
+}
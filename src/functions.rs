@@ -0,0 +1,249 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! The functions described by an OBJ's debug information.
+//!
+//! Objects built with debug information bracket each function with a
+//! [FunctionStart](crate::FunctionStart) and a
+//! [FunctionEnd](Section::FunctionEnd). [Functions::of_obj] pairs them up
+//! into a [FunctionInfo] per function. Offsets are taken to be from the
+//! start of the section in this object.
+//!
+//! An end is paired with the latest unpaired start in its section. Ends
+//! with no start and starts with no end are reported as
+//! [warnings](Functions::warnings) rather than errors; a start without an
+//! end is still listed, but has no [end](FunctionInfo::end).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Section, OBJ};
+
+/// A function and its stack frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub section: u16,
+    pub start: u32,
+    /// The offset of the function's end, if it has one.
+    pub end: Option<u32>,
+    pub start_line: u32,
+    pub end_line: Option<u32>,
+    /// The id of the source file, as used by [Filename](crate::Filename).
+    pub file: u16,
+    /// The name of the source file, if `file` is defined.
+    pub file_name: Option<String>,
+    pub frame_register: u16,
+    pub frame_size: u32,
+    pub return_pc_register: u16,
+    pub mask: u32,
+    pub mask_offset: i32,
+}
+
+impl FunctionInfo {
+    /// Returns the number of bytes from the start of the function to its
+    /// end.
+    pub fn size(&self) -> Option<u32> {
+        self.end.and_then(|end| end.checked_sub(self.start))
+    }
+
+    /// Returns the function's bytes from the [Code](Section::Code) section
+    /// of `obj` that holds them. Returns `None` if the function has no end,
+    /// isn't contained by a single code section, or that section's code was
+    /// [deferred](crate::Code::deferred).
+    pub fn code<'a>(&self, obj: &'a OBJ) -> Option<&'a [u8]> {
+        let end = self.end.filter(|end| *end >= self.start)?;
+        let mut sizes: HashMap<u16, u32> = HashMap::new();
+        let mut current = None;
+        for section in obj.sections() {
+            match section {
+                Section::SectionSwitch(id) => current = Some(*id),
+                Section::Code(code) => {
+                    let Some(id) = current else {
+                        continue;
+                    };
+                    let size = sizes.entry(id).or_default();
+                    let chunk = *size;
+                    *size += code.size() as u32;
+                    if id == self.section && chunk <= self.start && end <= *size {
+                        let start = (self.start - chunk) as usize;
+                        return Some(&code.code().ok()?[start..(end - chunk) as usize]);
+                    }
+                }
+                Section::BSS(bss) => {
+                    if let Some(id) = current {
+                        *sizes.entry(id).or_default() += bss;
+                    }
+                }
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+/// A function start or end that couldn't be paired.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionWarning {
+    /// The index of the start or end within the OBJ.
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FunctionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "section {}: {}", self.index, self.message)
+    }
+}
+
+/// The functions of an OBJ. See [OBJ::functions].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Functions {
+    /// In the order they start.
+    pub functions: Vec<FunctionInfo>,
+    pub warnings: Vec<FunctionWarning>,
+}
+
+impl Functions {
+    /// Pairs the function starts and ends of `obj`.
+    pub fn of_obj(obj: &OBJ) -> Self {
+        let names = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::Filename(filename) => Some((filename.number(), filename.name())),
+                _ => None,
+            })
+            .collect::<HashMap<u16, String>>();
+
+        let mut functions = Self::default();
+        // (index of the start, index into functions) per section
+        let mut open: HashMap<u16, Vec<(usize, usize)>> = HashMap::new();
+        for (index, section) in obj.sections().iter().enumerate() {
+            match section {
+                Section::FunctionStart(start) => {
+                    open.entry(start.section)
+                        .or_default()
+                        .push((index, functions.functions.len()));
+                    functions.functions.push(FunctionInfo {
+                        name: start.name(),
+                        section: start.section,
+                        start: start.offset,
+                        end: None,
+                        start_line: start.linenum,
+                        end_line: None,
+                        file: start.file,
+                        file_name: names.get(&start.file).cloned(),
+                        frame_register: start.frame_register,
+                        frame_size: start.frame_size,
+                        return_pc_register: start.return_pc_register,
+                        mask: start.mask,
+                        mask_offset: start.mask_offset,
+                    });
+                }
+                Section::FunctionEnd(end) => {
+                    let Some((_, function)) = open.get_mut(&end.section).and_then(Vec::pop) else {
+                        functions.warn(
+                            index,
+                            format!(
+                                "function end at {:04x}:{:08x} has no start",
+                                end.section, end.offset
+                            ),
+                        );
+                        continue;
+                    };
+                    let function = &mut functions.functions[function];
+                    if end.offset < function.start {
+                        let message = format!(
+                            "{} ends at {:08x}, before it starts at {:08x}",
+                            function.name, end.offset, function.start
+                        );
+                        functions.warn(index, message);
+                        continue;
+                    }
+                    function.end = Some(end.offset);
+                    function.end_line = Some(end.linenum);
+                }
+                _ => (),
+            }
+        }
+
+        let mut unended = open.into_values().flatten().collect::<Vec<_>>();
+        unended.sort();
+        for (index, function) in unended {
+            let name = functions.functions[function].name.clone();
+            functions.warn(index, format!("{name} has no end"));
+        }
+        functions
+    }
+
+    fn warn(&mut self, index: usize, message: String) {
+        self.warnings.push(FunctionWarning { index, message });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FunctionStart, SectionOffsetLine};
+
+    fn start(offset: u32, name: &str) -> Section {
+        Section::FunctionStart(FunctionStart {
+            section: 1,
+            offset,
+            file: 1,
+            linenum: 1,
+            frame_register: 29,
+            frame_size: 0,
+            return_pc_register: 31,
+            mask: 0,
+            mask_offset: 0,
+            name_size: name.len() as u8,
+            name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn end(offset: u32) -> Section {
+        Section::FunctionEnd(SectionOffsetLine {
+            section: 1,
+            offset,
+            linenum: 2,
+        })
+    }
+
+    #[test]
+    fn test_unmatched() {
+        let obj = OBJ::new(vec![
+            end(0),
+            start(8, "f"),
+            end(4),
+            start(8, "g"),
+            end(16),
+            start(16, "h"),
+            Section::NOP,
+        ]);
+        let functions = Functions::of_obj(&obj);
+        assert_eq!(
+            vec![
+                "section 0: function end at 0001:00000000 has no start",
+                "section 2: f ends at 00000004, before it starts at 00000008",
+                "section 5: h has no end",
+            ],
+            functions
+                .warnings
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![("f", None), ("g", Some(8)), ("h", None)],
+            functions
+                .functions
+                .iter()
+                .map(|f| (f.name.as_str(), f.size()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, functions.functions[0].code(&obj));
+        assert_eq!(None, functions.functions[0].file_name);
+    }
+}
@@ -0,0 +1,306 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Content-derived identifiers for modules and symbols.
+//!
+//! A [StableId] identifies a [Module](super::Module) by what it contains
+//! rather than where it was found, so the same module extracted from two
+//! copies of an SDK has the same id regardless of library path, archive
+//! position, or timestamp. Ids are suitable as keys in external databases
+//! and caches.
+//!
+//! # Derivation (version 1)
+//!
+//! Ids are the 128-bit FNV-1a hash of:
+//!
+//! | Module id                                      | Symbol id                                   |
+//! |------------------------------------------------|---------------------------------------------|
+//! | `b"psyk.module.v1\0"`                          | `b"psyk.symbol.v1\0"`                       |
+//! | the size of the serialized [OBJ] as a `u64` LE | the module id, 16 bytes big-endian          |
+//! | the serialized [OBJ]                           | the size of the symbol name as a `u32` LE   |
+//! | the number of exports as a `u32` LE            | the symbol name bytes                       |
+//! | each export as a `u32` LE size and its bytes   |                                             |
+//!
+//! Exports are the names from the module metadata, sorted by their bytes
+//! with duplicates removed. The module name and timestamp are not included.
+//!
+//! Any change to the derivation must increment [DERIVATION_VERSION] and the
+//! prefixes above. The derivation is guarded by tests with fixed ids.
+//!
+//! Ids are displayed as 26 lowercase [Crockford base32] digits, which are
+//! safe to use in URLs and file names.
+//!
+//! [Crockford base32]: https://www.crockford.com/base32.html
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{bail, Result};
+use binrw::BinWrite;
+
+use super::OBJ;
+
+/// The version of the id derivation described in the [module](self) docs.
+pub const DERIVATION_VERSION: u32 = 1;
+
+const MODULE_PREFIX: &[u8] = b"psyk.module.v1\0";
+const SYMBOL_PREFIX: &[u8] = b"psyk.symbol.v1\0";
+
+const DIGITS: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+const ENCODED_LEN: usize = 26;
+
+/// 128-bit FNV-1a.
+struct Fnv128(u128);
+
+impl Fnv128 {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn update(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.0 ^= *byte as u128;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    fn finish(&self) -> StableId {
+        StableId(self.0)
+    }
+}
+
+/// A content-derived 128-bit identifier. See the [module](self) docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableId(u128);
+
+impl StableId {
+    pub fn from_u128(value: u128) -> Self {
+        Self(value)
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut encoded = [0u8; ENCODED_LEN];
+        for (i, digit) in encoded.iter_mut().rev().enumerate() {
+            *digit = DIGITS[((self.0 >> (5 * i)) & 0x1f) as usize];
+        }
+        write!(f, "{}", std::str::from_utf8(&encoded).expect("ascii"))
+    }
+}
+
+impl FromStr for StableId {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_stable_id(s)
+    }
+}
+
+/// Parses a [StableId] from its displayed form. Upper case digits and the
+/// Crockford aliases `i`, `l` (for `1`) and `o` (for `0`) are accepted.
+pub fn parse_stable_id(s: &str) -> Result<StableId> {
+    if s.len() != ENCODED_LEN {
+        bail!("stable ids are {ENCODED_LEN} characters: {s}");
+    }
+    let mut value = 0u128;
+    for (i, c) in s.chars().enumerate() {
+        let c = match c.to_ascii_lowercase() {
+            'i' | 'l' => '1',
+            'o' => '0',
+            c => c,
+        };
+        let Some(digit) = DIGITS.iter().position(|d| *d as char == c) else {
+            bail!("invalid stable id character {c:?}: {s}");
+        };
+        // the first digit only holds the top 3 bits
+        if i == 0 && digit > 0b111 {
+            bail!("stable id out of range: {s}");
+        }
+        value = (value << 5) | digit as u128;
+    }
+    Ok(StableId(value))
+}
+
+/// Derives the id of a module containing `obj` which exports `exports`. It
+/// is an error for `obj` to have [deferred](crate::Code::deferred) code.
+pub(crate) fn module_id<'a>(
+    obj: &OBJ,
+    exports: impl IntoIterator<Item = &'a [u8]>,
+) -> crate::Result<StableId> {
+    let mut bytes = binrw::io::Cursor::new(Vec::new());
+    obj.write_le(&mut bytes)?;
+    let bytes = bytes.into_inner();
+
+    let mut exports = exports
+        .into_iter()
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<&[u8]>>();
+    exports.sort();
+    exports.dedup();
+
+    let mut hash = Fnv128::new();
+    hash.update(MODULE_PREFIX)
+        .update(&(bytes.len() as u64).to_le_bytes())
+        .update(&bytes)
+        .update(&(exports.len() as u32).to_le_bytes());
+    for export in exports {
+        hash.update(&(export.len() as u32).to_le_bytes())
+            .update(export);
+    }
+    Ok(hash.finish())
+}
+
+/// A symbol defined by a module, identified by the module's [StableId] and
+/// the symbol's name as it is stored on disk.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymbolRef {
+    module: StableId,
+    name: Vec<u8>,
+}
+
+impl SymbolRef {
+    pub fn new(module: StableId, name: &[u8]) -> Self {
+        Self {
+            module,
+            name: name.to_vec(),
+        }
+    }
+
+    /// The id of the module defining the symbol.
+    pub fn module(&self) -> StableId {
+        self.module
+    }
+
+    /// The symbol name bytes.
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// Returns an id combining the module id and the symbol name.
+    pub fn stable_id(&self) -> StableId {
+        Fnv128::new()
+            .update(SYMBOL_PREFIX)
+            .update(&self.module.0.to_be_bytes())
+            .update(&(self.name.len() as u32).to_le_bytes())
+            .update(&self.name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, ModuleMetadata, Section, XDEF};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn obj() -> OBJ {
+        OBJ::new(vec![
+            Section::Code(Code {
+                size: 8,
+                deferred: None,
+                code: vec![0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00],
+            }),
+            Section::XDEF(XDEF {
+                number: 1,
+                section: 1,
+                offset: 0,
+                symbol_name_size: 4,
+                symbol_name: b"nop_".to_vec(),
+            }),
+            Section::NOP,
+        ])
+    }
+
+    fn module(name: &str, secs: u64, exports: &[&str]) -> crate::Module {
+        crate::Module::new(
+            obj(),
+            ModuleMetadata::new(
+                name.into(),
+                UNIX_EPOCH + Duration::from_secs(secs),
+                0,
+                exports
+                    .iter()
+                    .map(|e| crate::Export::new(e.to_string()).expect("export"))
+                    .collect(),
+            )
+            .expect("metadata"),
+        )
+    }
+
+    #[test]
+    fn test_fnv128() {
+        // published FNV-1a test vectors
+        assert_eq!(Fnv128::OFFSET_BASIS, Fnv128::new().finish().as_u128());
+        assert_eq!(
+            0xd228cb696f1a8caf78912b704e4a8964,
+            Fnv128::new().update(b"a").finish().as_u128()
+        );
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        for value in [
+            0,
+            1,
+            0x1f,
+            u128::MAX,
+            0x0123_4567_89ab_cdef_fedc_ba98_7654_3210,
+        ] {
+            let id = StableId(value);
+            let s = id.to_string();
+            assert_eq!(ENCODED_LEN, s.len());
+            assert_eq!(id, parse_stable_id(&s).expect("id"));
+            assert_eq!(id, s.to_uppercase().parse().expect("id"));
+        }
+        assert_eq!(
+            "7zzzzzzzzzzzzzzzzzzzzzzzzz",
+            StableId(u128::MAX).to_string()
+        );
+        assert_eq!(
+            StableId(1),
+            parse_stable_id("oooooooooooooooooooooooooI").expect("id")
+        );
+        assert!(parse_stable_id("8zzzzzzzzzzzzzzzzzzzzzzzzz").is_err());
+        assert!(parse_stable_id("0000000000000000000000000u").is_err());
+        assert!(parse_stable_id("00").is_err());
+    }
+
+    #[test]
+    fn test_derivation_v1() {
+        // these ids must never change without a new derivation version
+        let module = module("NOP", 820_000_000, &["nop_"]);
+        assert_eq!(
+            "2effkm08fm0nbybeq3k7tea5r2",
+            module.stable_id().unwrap().to_string()
+        );
+        assert_eq!(
+            "2sgwfxew2qy16qxzrrxtm5x7jh",
+            SymbolRef::new(module.stable_id().unwrap(), b"nop_")
+                .stable_id()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_excluded_metadata() {
+        let a = module("NOP", 820_000_000, &["nop_", "b"]);
+        let b = module("RENAMED", 900_000_000, &["b", "nop_", "b"]);
+        assert_eq!(a.stable_id().unwrap(), b.stable_id().unwrap());
+
+        let c = module("NOP", 820_000_000, &["nop_"]);
+        assert_ne!(a.stable_id().unwrap(), c.stable_id().unwrap());
+        assert_ne!(
+            SymbolRef::new(a.stable_id().unwrap(), b"nop_").stable_id(),
+            SymbolRef::new(c.stable_id().unwrap(), b"nop_").stable_id()
+        );
+    }
+}
@@ -0,0 +1,630 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! A symbol index of a directory of LIBs and OBJs.
+//!
+//! Finding which library exports a symbol means reading the metadata of
+//! every LIB in a tree. An [Index] records the symbols of each file once,
+//! along with the file's size and modification time, so later queries only
+//! re-read files that have changed.
+//!
+//! ```no_run
+//! use psyk::index;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let built = index::build("PSX/LIB")?;
+//! index::save(&built, "psyk.idx")?;
+//!
+//! let mut loaded = index::load("psyk.idx")?;
+//! for hit in index::query(&loaded, "InitHeap")?.hits {
+//!     println!("{}: {}", hit.path.display(), hit.module);
+//! }
+//! index::refresh(&mut loaded)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Format
+//!
+//! All integers are little-endian. Strings are UTF-8 and prefixed with
+//! their length.
+//!
+//! ```text
+//! magic     "PSYKIDX"
+//! version   u8, currently 1
+//! root      u16 length, bytes
+//! files     u32 count, then for each file:
+//!   path      u16 length, bytes
+//!   size      u64
+//!   modified  u64 seconds and u32 nanoseconds since the Unix epoch
+//!   symbols   u32 count, then for each symbol:
+//!     kind      u8, 0 for an export and 1 for a definition
+//!     module    u8 length, bytes
+//!     symbol    u8 length, bytes
+//! ```
+//!
+//! An index with a different version is rejected by [load] rather than
+//! read incorrectly.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use binrw::io::NoSeek;
+use binrw::{binrw, BinRead, BinWrite};
+
+use crate::error::{bail, Error, Result};
+
+use super::io;
+use super::path_to_module_name;
+use super::symbols::CaseFold;
+
+/// The version of the index format written by [save].
+pub const VERSION: u8 = 1;
+
+/// How a file provides a symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// Exported by a module of a LIB, according to its metadata.
+    Export,
+    /// Defined by an OBJ.
+    Definition,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Export => write!(f, "export"),
+            Self::Definition => write!(f, "definition"),
+        }
+    }
+}
+
+/// A symbol provided by an indexed file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// The module providing the symbol. For an OBJ, this is the name
+    /// `psylib` would give it.
+    pub module: String,
+    pub name: String,
+}
+
+/// The size and modification time of a file when it was indexed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    /// The modification time as a duration since the Unix epoch.
+    pub modified: Duration,
+}
+
+impl FileStamp {
+    /// The current stamp of the file at `path`.
+    pub fn of(path: &Path) -> Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// An indexed LIB or OBJ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    pub stamp: FileStamp,
+    /// The symbols of the file. Files which couldn't be read have none.
+    pub symbols: Vec<Symbol>,
+}
+
+/// The symbols of every LIB and OBJ in a directory and its
+/// subdirectories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Index {
+    /// The directory that was indexed.
+    pub root: PathBuf,
+    /// The indexed files, ordered by path.
+    pub files: Vec<IndexedFile>,
+}
+
+/// A symbol found by [query].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub module: String,
+    pub kind: SymbolKind,
+}
+
+/// The result of [query].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryResult {
+    pub hits: Vec<Hit>,
+    /// Files that changed since they were indexed. Their symbols were
+    /// read from the files instead of the index.
+    pub stale: Vec<PathBuf>,
+}
+
+/// The files [refresh] changed in an [Index].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// Files that were new or changed, and were read again.
+    pub reread: Vec<PathBuf>,
+    /// Files that no longer exist.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Finds files with a `.LIB` or `.OBJ` extension, ignoring case, in `dir`
+/// and its subdirectories.
+fn find_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    io::find_files(dir, &["lib", "obj"])
+}
+
+/// Reads the symbols of a LIB, from its metadata, or an OBJ.
+fn scan(path: &Path) -> Result<Vec<Symbol>> {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("obj"))
+    {
+        let obj = io::read_obj(path)?;
+        let module = String::from_utf8_lossy(&path_to_module_name(path))
+            .trim_end()
+            .to_string();
+        return Ok(obj
+            .exports()
+            .into_iter()
+            .map(|name| Symbol {
+                kind: SymbolKind::Definition,
+                module: module.clone(),
+                name,
+            })
+            .collect());
+    }
+
+    let mut symbols = Vec::new();
+    for metadata in io::read_lib_metadata(path)? {
+        let metadata = metadata?;
+        let module = metadata.name();
+        symbols.extend(metadata.exports().into_iter().map(|name| Symbol {
+            kind: SymbolKind::Export,
+            module: module.clone(),
+            name,
+        }));
+    }
+    Ok(symbols)
+}
+
+fn index_file(path: &Path) -> Result<IndexedFile> {
+    let stamp = FileStamp::of(path)?;
+    Ok(IndexedFile {
+        path: path.to_path_buf(),
+        stamp,
+        symbols: scan(path).unwrap_or_default(),
+    })
+}
+
+/// Indexes every LIB and OBJ in `dir` and its subdirectories. Only the
+/// metadata of LIBs is read.
+pub fn build(dir: impl AsRef<Path>) -> Result<Index> {
+    let root = dir.as_ref().to_path_buf();
+    let files = find_files(&root)?
+        .iter()
+        .map(|path| index_file(path))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Index { root, files })
+}
+
+/// Finds the files providing `symbol`. Files whose size or modification
+/// time no longer match the index are read again and reported as
+/// [stale](QueryResult::stale). Files added since the index was built are
+/// not searched; see [refresh].
+pub fn query(index: &Index, symbol: &str) -> Result<QueryResult> {
+    query_with(index, symbol, CaseFold::Sensitive)
+}
+
+/// Like [query], comparing symbol names as `case` says.
+pub fn query_with(index: &Index, symbol: &str, case: CaseFold) -> Result<QueryResult> {
+    let mut result = QueryResult::default();
+    for file in &index.files {
+        let current = FileStamp::of(&file.path).ok();
+        let symbols = if current == Some(file.stamp) {
+            file.symbols.clone()
+        } else {
+            result.stale.push(file.path.clone());
+            match current {
+                Some(_) => scan(&file.path).unwrap_or_default(),
+                None => Vec::new(),
+            }
+        };
+        result.hits.extend(
+            symbols
+                .into_iter()
+                .filter(|s| case.matches(&s.name, symbol))
+                .map(|s| Hit {
+                    path: file.path.clone(),
+                    module: s.module,
+                    kind: s.kind,
+                }),
+        );
+    }
+    Ok(result)
+}
+
+/// Brings `index` up to date with its [root](Index::root). Only files that
+/// are new or whose size or modification time changed are read.
+pub fn refresh(index: &mut Index) -> Result<RefreshReport> {
+    let mut report = RefreshReport::default();
+    let mut files = Vec::new();
+    for path in find_files(&index.root)? {
+        let stamp = FileStamp::of(&path)?;
+        match index.files.iter().find(|f| f.path == path) {
+            Some(file) if file.stamp == stamp => files.push(file.clone()),
+            _ => {
+                files.push(index_file(&path)?);
+                report.reread.push(path);
+            }
+        }
+    }
+    report.removed = index
+        .files
+        .iter()
+        .filter(|f| !files.iter().any(|new| new.path == f.path))
+        .map(|f| f.path.clone())
+        .collect();
+    index.files = files;
+    Ok(report)
+}
+
+/// Reads an index written by [save].
+pub fn load(path: impl AsRef<Path>) -> Result<Index> {
+    read(&mut BufReader::new(File::open(path.as_ref())?))
+}
+
+/// Writes `index` to `path`.
+pub fn save(index: &Index, path: impl AsRef<Path>) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path.as_ref())?);
+    write(index, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A string prefixed with its length as a `u8`.
+#[binrw]
+#[brw(little)]
+struct ShortStr {
+    #[bw(calc = bytes.len() as u8)]
+    len: u8,
+    #[br(count = len)]
+    bytes: Vec<u8>,
+}
+
+/// A string prefixed with its length as a `u16`.
+#[binrw]
+#[brw(little)]
+struct LongStr {
+    #[bw(calc = bytes.len() as u16)]
+    len: u16,
+    #[br(count = len)]
+    bytes: Vec<u8>,
+}
+
+/// An [Index] as it is stored. See the [module documentation](self).
+#[binrw]
+#[brw(little, magic = b"PSYKIDX")]
+struct RawIndex {
+    #[br(assert(version == VERSION, "unsupported index version {version}, expected {VERSION}"))]
+    version: u8,
+    root: LongStr,
+    #[bw(calc = files.len() as u32)]
+    count: u32,
+    #[br(count = count)]
+    files: Vec<RawFile>,
+}
+
+#[binrw]
+#[brw(little)]
+struct RawFile {
+    path: LongStr,
+    size: u64,
+    secs: u64,
+    #[br(assert(nanos < 1_000_000_000, "{nanos} nanoseconds is more than a second"))]
+    nanos: u32,
+    #[bw(calc = symbols.len() as u32)]
+    count: u32,
+    #[br(count = count)]
+    symbols: Vec<RawSymbol>,
+}
+
+#[binrw]
+#[brw(little)]
+struct RawSymbol {
+    #[br(assert(kind <= 1, "unknown symbol kind {kind}"))]
+    kind: u8,
+    module: ShortStr,
+    name: ShortStr,
+}
+
+impl ShortStr {
+    fn new(s: &str) -> Result<Self> {
+        if s.len() > u8::MAX as usize {
+            bail!("{s} is longer than {} bytes", u8::MAX);
+        }
+        Ok(Self {
+            bytes: s.as_bytes().to_vec(),
+        })
+    }
+
+    fn into_string(self) -> Result<String> {
+        String::from_utf8(self.bytes).map_err(Error::other)
+    }
+}
+
+impl LongStr {
+    fn new(path: &Path) -> Result<Self> {
+        let s = path.to_string_lossy();
+        if s.len() > u16::MAX as usize {
+            bail!("{s} is longer than {} bytes", u16::MAX);
+        }
+        Ok(Self {
+            bytes: s.as_bytes().to_vec(),
+        })
+    }
+
+    fn into_path(self) -> Result<PathBuf> {
+        Ok(PathBuf::from(
+            String::from_utf8(self.bytes).map_err(Error::other)?,
+        ))
+    }
+}
+
+fn write(index: &Index, write: &mut impl Write) -> Result<()> {
+    let files = index
+        .files
+        .iter()
+        .map(|file| {
+            let symbols = file
+                .symbols
+                .iter()
+                .map(|symbol| {
+                    Ok(RawSymbol {
+                        kind: match symbol.kind {
+                            SymbolKind::Export => 0,
+                            SymbolKind::Definition => 1,
+                        },
+                        module: ShortStr::new(&symbol.module)?,
+                        name: ShortStr::new(&symbol.name)?,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            Ok(RawFile {
+                path: LongStr::new(&file.path)?,
+                size: file.stamp.size,
+                secs: file.stamp.modified.as_secs(),
+                nanos: file.stamp.modified.subsec_nanos(),
+                symbols,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let raw = RawIndex {
+        version: VERSION,
+        root: LongStr::new(&index.root)?,
+        files,
+    };
+    raw.write(&mut NoSeek::new(write))?;
+    Ok(())
+}
+
+fn read(read: &mut (impl Read + Seek)) -> Result<Index> {
+    let raw = match RawIndex::read(read) {
+        Err(binrw::Error::BadMagic { .. }) => bail!("not a psyk index"),
+        raw => raw?,
+    };
+    let files = raw
+        .files
+        .into_iter()
+        .map(|file| {
+            let symbols = file
+                .symbols
+                .into_iter()
+                .map(|symbol| {
+                    Ok(Symbol {
+                        kind: match symbol.kind {
+                            0 => SymbolKind::Export,
+                            _ => SymbolKind::Definition,
+                        },
+                        module: symbol.module.into_string()?,
+                        name: symbol.name.into_string()?,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            Ok(IndexedFile {
+                path: file.path.into_path()?,
+                stamp: FileStamp {
+                    size: file.size,
+                    modified: Duration::new(file.secs, file.nanos),
+                },
+                symbols,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Index {
+        root: raw.root.into_path()?,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::SystemTime;
+
+    const SYNTHETIC: &str = "tests/data/synthetic";
+
+    /// Sets the modification time of `path` to `time`, for tests that need a
+    /// file to look changed without changing its contents.
+    fn touch(path: &Path, time: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .and_then(|f| f.set_modified(time))
+            .expect("set modified");
+    }
+
+    /// Two copies of SIMPLE.LIB and a copy of HELPER.OBJ.
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).expect("mkdir");
+        let lib = Path::new(SYNTHETIC).join("SIMPLE.LIB");
+        std::fs::copy(&lib, dir.path().join("A.LIB")).expect("copy");
+        std::fs::copy(&lib, sub.join("b.lib")).expect("copy");
+        std::fs::copy(
+            Path::new(SYNTHETIC).join("HELPER.OBJ"),
+            sub.join("HELPER.OBJ"),
+        )
+        .expect("copy");
+        dir
+    }
+
+    fn paths(hits: &[Hit]) -> Vec<(PathBuf, String, SymbolKind)> {
+        hits.iter()
+            .map(|h| (h.path.clone(), h.module.clone(), h.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_and_query() {
+        let dir = tree();
+        let index = build(dir.path()).expect("build");
+        assert_eq!(3, index.files.len());
+
+        let result = query(&index, "helper").expect("query");
+        assert!(result.stale.is_empty());
+        assert_eq!(
+            vec![
+                (
+                    dir.path().join("A.LIB"),
+                    "HELPER".into(),
+                    SymbolKind::Export
+                ),
+                (
+                    dir.path().join("sub/HELPER.OBJ"),
+                    "HELPER".into(),
+                    SymbolKind::Definition
+                ),
+                (
+                    dir.path().join("sub/b.lib"),
+                    "HELPER".into(),
+                    SymbolKind::Export
+                ),
+            ],
+            paths(&result.hits)
+        );
+        assert!(query(&index, "missing").expect("query").hits.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tree();
+        let index = build(dir.path()).expect("build");
+        let path = dir.path().join("psyk.idx");
+        save(&index, &path).expect("save");
+        assert_eq!(index, load(&path).expect("load"));
+
+        let mut bytes = std::fs::read(&path).expect("read");
+        bytes[7] = VERSION + 1;
+        std::fs::write(&path, bytes).expect("write");
+        let e = load(&path).expect_err("version");
+        assert!(e.to_string().contains("unsupported index version 2"));
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let dir = tree();
+        let index = build(dir.path()).expect("build");
+        let path = dir.path().join("psyk.idx");
+        save(&index, &path).expect("save");
+        let bytes = std::fs::read(&path).expect("read");
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        std::fs::write(&path, bad).expect("write");
+        let e = load(&path).expect_err("magic");
+        assert_eq!("not a psyk index", e.to_string());
+
+        // the nanoseconds of the first file's modification time
+        let root = index.root.to_string_lossy().len();
+        let file = index.files[0].path.to_string_lossy().len();
+        let nanos = 8 + 2 + root + 4 + 2 + file + 8 + 8;
+        let mut bad = bytes;
+        bad[nanos..nanos + 4].copy_from_slice(&1_000_000_000u32.to_le_bytes());
+        std::fs::write(&path, bad).expect("write");
+        let e = load(&path).expect_err("nanos");
+        assert!(
+            e.to_string()
+                .contains("1000000000 nanoseconds is more than a second"),
+            "{e}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_cycle() {
+        let dir = tree();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("sub/loop")).expect("symlink");
+        let index = build(dir.path()).expect("build");
+        assert_eq!(3, index.files.len());
+    }
+
+    #[test]
+    fn test_stale_and_refresh() {
+        let dir = tree();
+        let mut index = build(dir.path()).expect("build");
+        let changed = dir.path().join("sub/b.lib");
+        touch(&changed, UNIX_EPOCH + Duration::from_secs(820_000_000));
+
+        let result = query(&index, "main").expect("query");
+        assert_eq!(vec![changed.clone()], result.stale);
+        assert_eq!(2, result.hits.len());
+
+        std::fs::remove_file(dir.path().join("A.LIB")).expect("remove");
+        let report = refresh(&mut index).expect("refresh");
+        assert_eq!(vec![changed], report.reread);
+        assert_eq!(vec![dir.path().join("A.LIB")], report.removed);
+        assert_eq!(2, index.files.len());
+
+        let result = query(&index, "main").expect("query");
+        assert!(result.stale.is_empty());
+        assert_eq!(1, result.hits.len());
+        assert!(refresh(&mut index).expect("refresh").reread.is_empty());
+    }
+
+    #[test]
+    fn test_case_sensitivity() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let xdef = |name: &str| {
+            crate::Section::XDEF(crate::XDEF {
+                number: 1,
+                section: 1,
+                offset: 0,
+                symbol_name_size: name.len() as u8,
+                symbol_name: name.as_bytes().to_vec(),
+            })
+        };
+        let obj = crate::OBJ::new(vec![xdef("Foo"), xdef("foo"), crate::Section::NOP]);
+        let path = dir.path().join("FOO.OBJ");
+        io::write_obj_to(&obj, &mut File::create(&path).expect("create")).expect("write");
+
+        let index = build(dir.path()).expect("build");
+        assert_eq!(1, query(&index, "foo").expect("query").hits.len());
+        assert_eq!(1, query(&index, "Foo").expect("query").hits.len());
+        assert!(query(&index, "FOO").expect("query").hits.is_empty());
+        let result = query_with(&index, "FOO", CaseFold::Insensitive).expect("query");
+        assert_eq!(2, result.hits.len());
+    }
+}
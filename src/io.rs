@@ -3,20 +3,38 @@
 
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::{display, LIB, OBJ};
-use anyhow::{bail, Result};
+use crate::error::{bail, Error, ParseWarning, Result};
+use crate::metrics::{self, Outcome, SectionCounts};
+use crate::recover::{self, RecoveredLib};
+use crate::sym::SYM;
+use crate::{display, ModuleMetadata, OpaqueLIB, Section, LIB, OBJ};
 use binrw::io::Cursor;
+use binrw::io::NoSeek;
 use binrw::{meta::ReadMagic, BinRead, BinWrite};
 
+pub use crate::metrics::FileKind;
+
 #[derive(Debug)]
 pub enum Type {
     OBJ(OBJ),
     LIB(LIB),
 }
 
+impl Type {
+    /// Returns the anomalies found while reading the [LIB] or [OBJ]. See
+    /// [LIB::warnings] and [OBJ::warnings].
+    pub fn warnings(&self) -> Vec<ParseWarning> {
+        match self {
+            Self::OBJ(obj) => obj.warnings(),
+            Self::LIB(lib) => lib.warnings(),
+        }
+    }
+}
+
 impl Display for Type {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
@@ -39,64 +57,782 @@ impl display::DisplayWithOptions for Type {
 
 pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
     if !Path::exists(path) {
-        bail!(format!("File not found: {}", path.display()));
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("File not found: {}", path.display()),
+        )
+        .into());
     }
 
     Ok(std::fs::read(path)?)
 }
 
-/// Reads a Psy-Q [LIB] or [OBJ]. If the file cannot be found or if the file
-/// does not contain valid data an error will be returned.
-pub fn read(lib_or_obj_path: &Path) -> Result<Type> {
-    let bytes = read_bytes(lib_or_obj_path)?;
+/// A parsed file whose sections can be counted for [metrics]. Files which
+/// aren't parsed into sections have none.
+trait Counted {
+    fn section_counts(&self) -> SectionCounts {
+        SectionCounts::default()
+    }
+}
+
+impl Counted for OBJ {
+    fn section_counts(&self) -> SectionCounts {
+        SectionCounts::of_obj(self)
+    }
+}
+
+impl Counted for LIB {
+    fn section_counts(&self) -> SectionCounts {
+        SectionCounts::of_lib(self)
+    }
+}
+
+impl Counted for OpaqueLIB {}
+
+impl Counted for SYM {}
+
+/// Only the header of a [LIB] read by [read_lib_metadata] is checked as it
+/// is opened.
+impl Counted for () {}
+
+impl Counted for RecoveredLib {
+    fn section_counts(&self) -> SectionCounts {
+        let candidates = self.trailing.iter().map(|candidate| &candidate.module);
+        let mut counts = SectionCounts::default();
+        for module in self.leading.iter().chain(candidates) {
+            counts.merge(&SectionCounts::of_obj(module.object()));
+        }
+        counts
+    }
+}
+
+impl Counted for Type {
+    fn section_counts(&self) -> SectionCounts {
+        match self {
+            Self::OBJ(obj) => obj.section_counts(),
+            Self::LIB(lib) => lib.section_counts(),
+        }
+    }
+}
+
+/// Reads the file at `path` and parses it with `parse`. A file that can't
+/// be read is reported to the installed [metrics] recorder if there is one.
+fn read_with<T>(path: &Path, parse: impl FnOnce(&mut Cursor<Vec<u8>>) -> Result<T>) -> Result<T> {
+    match read_bytes(path) {
+        Ok(bytes) => parse(&mut Cursor::new(bytes)),
+        Err(e) => {
+            let result = Err(e);
+            if let Some(recorder) = metrics::recorder() {
+                recorder.record_parse(FileKind::Unknown, 0, Duration::ZERO, &Outcome::of(&result));
+            }
+            result
+        }
+    }
+}
+
+/// Opens the file at `path` and parses it with `parse` as it is read,
+/// rather than reading it into memory first. A file that can't be opened
+/// is reported to the installed [metrics] recorder if there is one.
+///
+/// The file is buffered with [binrw::io::BufReader], which, unlike
+/// [BufReader], keeps its buffer when parsing seeks within it.
+fn read_streamed<T>(
+    path: &Path,
+    parse: impl FnOnce(&mut binrw::io::BufReader<File>) -> Result<T>,
+) -> Result<T> {
+    match File::open(path) {
+        Ok(file) => parse(&mut binrw::io::BufReader::new(file)),
+        Err(e) => {
+            let message = format!("File not found: {}", path.display());
+            let result = Err(Error::Io(std::io::Error::new(e.kind(), message)));
+            if let Some(recorder) = metrics::recorder() {
+                recorder.record_parse(FileKind::Unknown, 0, Duration::ZERO, &Outcome::of(&result));
+            }
+            result
+        }
+    }
+}
+
+/// Reads up to the first 8 bytes of `reader` and seeks back to where it
+/// started.
+fn peek_magic<R: Read + Seek>(reader: &mut R) -> Result<Vec<u8>> {
+    let start = reader.stream_position()?;
+    let mut magic = Vec::with_capacity(8);
+    reader.by_ref().take(8).read_to_end(&mut magic)?;
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(magic)
+}
+
+/// Parses `reader` with `parse`, reporting to the installed [metrics]
+/// recorder if there is one. The size recorded is the length of the stream
+/// from where parsing started.
+fn read_from_with<T: Counted, R: Read + Seek>(
+    reader: &mut R,
+    parse: impl FnOnce(&mut R) -> Result<T>,
+) -> Result<T> {
+    let Some(recorder) = metrics::recorder() else {
+        return parse(reader);
+    };
+
+    let start = Instant::now();
+    let position = reader.stream_position()?;
+    let kind = FileKind::from_magic(&peek_magic(reader)?);
+    let result = parse(reader);
+    let end = reader.stream_position()?;
+    let size = reader.seek(SeekFrom::End(0))? - position;
+    reader.seek(SeekFrom::Start(end))?;
+    recorder.record_parse(kind, size, start.elapsed(), &Outcome::of(&result));
+    if let Ok(parsed) = &result {
+        recorder.record_sections(&parsed.section_counts());
+    }
+    result
+}
+
+/// Reads a Psy-Q [LIB] or [OBJ] from `reader`, starting at its current
+/// position. The magic number is checked before anything is parsed.
+pub fn read_from(reader: &mut (impl Read + Seek)) -> Result<Type> {
+    read_from_options(reader, &ReadOptions::default())
+}
+
+/// Reads a Psy-Q [LIB] or [OBJ] from `reader` with the parsing `options`,
+/// [skip_code_bytes](ReadOptions::skip_code_bytes),
+/// [max_exports](ReadOptions::max_exports), and
+/// [lenient](ReadOptions::lenient).
+fn read_from_options(reader: &mut (impl Read + Seek), options: &ReadOptions) -> Result<Type> {
+    read_from_with(reader, |reader| {
+        let magic = peek_magic(reader)?;
+        let Some(found) = magic.first_chunk::<3>() else {
+            return Err(Error::TooSmall);
+        };
+
+        match *found {
+            LIB::MAGIC => Ok(Type::LIB(LIB::read_args(reader, options.lib_args())?)),
+            OBJ::MAGIC => Ok(Type::OBJ(OBJ::read_args(reader, options.obj_args())?)),
+            found => Err(unsupported(&magic, found)),
+        }
+    })
+}
+
+/// Identifies the file at `path` by its magic number without reading the
+/// rest of it.
+pub fn detect(path: &Path) -> Result<FileKind> {
+    let mut magic = Vec::with_capacity(8);
+    File::open(path)?.take(8).read_to_end(&mut magic)?;
+    Ok(FileKind::from_magic(&magic))
+}
+
+/// The error for a file starting with `magic` that isn't a [LIB] or [OBJ],
+/// naming what it is if it's recognized. `found` is its first 3 bytes.
+fn unsupported(magic: &[u8], found: [u8; 3]) -> Error {
+    match FileKind::from_magic(magic) {
+        kind @ (FileKind::COFF | FileKind::EXE | FileKind::SYM) => Error::Unsupported(kind),
+        _ => Error::Unrecognized { found },
+    }
+}
+
+/// Fails unless `reader` starts with the magic number `expected`. A
+/// recognized file that isn't a [LIB] or [OBJ] fails with an [unsupported]
+/// error instead.
+fn check_magic(reader: &mut (impl Read + Seek), expected: [u8; 3]) -> Result<()> {
+    let offset = reader.stream_position()?;
+    let magic = peek_magic(reader)?;
+    let Some(&found) = magic.first_chunk::<3>() else {
+        return Err(Error::TooSmall);
+    };
+    match FileKind::from_magic(&magic) {
+        FileKind::COFF | FileKind::EXE | FileKind::SYM => Err(unsupported(&magic, found)),
+        _ if found != expected => Err(Error::BadMagic { offset, found }),
+        _ => Ok(()),
+    }
+}
+
+/// Reads a Psy-Q [OBJ] from `reader`, starting at its current position.
+pub fn read_obj_from(reader: &mut (impl Read + Seek)) -> Result<OBJ> {
+    read_from_with(reader, |reader| {
+        check_magic(reader, OBJ::MAGIC)?;
+        Ok(OBJ::read(reader)?)
+    })
+}
+
+/// Reads a Psy-Q [LIB] from `reader`, starting at its current position.
+pub fn read_lib_from(reader: &mut (impl Read + Seek)) -> Result<LIB> {
+    read_from_with(reader, |reader| {
+        check_magic(reader, LIB::MAGIC)?;
+        Ok(LIB::read(reader)?)
+    })
+}
+
+/// Serializes `value` to `write` and returns the number of bytes written.
+/// I/O errors are returned as they are rather than wrapped by binrw.
+fn serialize(value: &impl for<'a> BinWrite<Args<'a> = ()>, write: &mut impl Write) -> Result<u64> {
+    let mut writer = NoSeek::new(write);
+    value.write_le(&mut writer)?;
+    let size = writer.stream_position()?;
+    writer.into_inner().flush()?;
+    Ok(size)
+}
 
-    if bytes.len() < 3 {
-        bail!("File too small to contain valid PSY-Q magic number");
+/// Serializes `value` to `write`, reporting to the installed [metrics]
+/// recorder if there is one.
+fn write_with(kind: FileKind, write: impl FnOnce() -> Result<u64>) -> Result<u64> {
+    let start = metrics::recorder().map(|_| Instant::now());
+    let result = write();
+    if let (Some(recorder), Some(start)) = (metrics::recorder(), start) {
+        let size = *result.as_ref().unwrap_or(&0);
+        recorder.record_write(kind, size, start.elapsed(), &Outcome::of(&result));
     }
+    result
+}
+
+/// A [Write] that only counts the bytes written to it.
+#[derive(Default)]
+struct Counter(u64);
 
-    let mut magic: [u8; 3] = [0; 3];
-    magic.clone_from_slice(&bytes[0..3]);
-    let mut data = Cursor::new(&bytes);
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
 
-    match magic {
-        LIB::MAGIC => Ok(Type::LIB(LIB::read(&mut data)?)),
-        OBJ::MAGIC => Ok(Type::OBJ(OBJ::read(&mut data)?)),
-        _ => bail!(format!("Unrecognized magic {:?}", &bytes[0..3])),
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
+/// Reads a Psy-Q [LIB] or [OBJ]. If the file cannot be found or if the file
+/// does not contain valid data an error will be returned.
+pub fn read(lib_or_obj_path: &Path) -> Result<Type> {
+    read_with(lib_or_obj_path, |reader| match read_from(reader)? {
+        Type::OBJ(obj) => Ok(Type::OBJ(with_trailing_bytes(obj, reader)?)),
+        lib => Ok(lib),
+    })
+}
+
+/// Reads a Psy-Q [LIB] or [OBJ] like [read], returning it with the
+/// [ParseWarning]s found while reading it. See [Type::warnings].
+pub fn read_with_warnings(lib_or_obj_path: &Path) -> Result<(Type, Vec<ParseWarning>)> {
+    let read = read(lib_or_obj_path)?;
+    let warnings = read.warnings();
+    Ok((read, warnings))
+}
+
 /// Reads a Psy-Q [OBJ]. If the file cannot be found or if the file
 /// does not contain valid data an error will be returned.
 pub fn read_obj(obj_path: &Path) -> Result<OBJ> {
-    let bytes = read_bytes(obj_path)?;
-    let mut data = Cursor::new(&bytes);
-    Ok(OBJ::read(&mut data)?)
+    read_with(obj_path, |reader| {
+        let obj = read_obj_from(reader)?;
+        with_trailing_bytes(obj, reader)
+    })
+}
+
+/// Keeps the rest of a file read with [read] or [read_obj] as the
+/// [trailing bytes](OBJ::trailing_bytes) of `obj`. The stream readers stop
+/// after the OBJ instead, since other data may follow it.
+fn with_trailing_bytes(mut obj: OBJ, reader: &mut impl Read) -> Result<OBJ> {
+    reader.read_to_end(&mut obj.trailing)?;
+    Ok(obj)
 }
 
 /// Reads a Psy-Q [LIB]. If the file cannot be found or if the file
 /// does not contain valid data an error will be returned.
+///
+/// With the `parallel` feature, the modules' [OBJ]s are parsed on multiple
+/// threads. The result is the same either way.
 pub fn read_lib(lib_path: &Path) -> Result<LIB> {
-    let bytes = read_bytes(lib_path)?;
-    let mut data = Cursor::new(&bytes);
-    Ok(LIB::read(&mut data)?)
-}
-
-/// Writes a Psy-Q [OBJ]. If the file cannot be written an error will
-/// be returned.
-pub fn write_obj(obj: &OBJ, file: &mut File) -> Result<()> {
-    let mut writer = Cursor::new(Vec::new());
-    obj.write(&mut writer)?;
-    let gen = writer.into_inner();
-    file.write_all(&gen)?;
-    Ok(())
+    #[cfg(feature = "parallel")]
+    return read_with(lib_path, |reader| read_from_with(reader, read_lib_parallel));
+    #[cfg(not(feature = "parallel"))]
+    read_with(lib_path, read_lib_from)
+}
+
+/// Splits a [LIB] into modules by their declared sizes, like [OpaqueLIB],
+/// then parses each module's [OBJ] on the rayon thread pool. An error names
+/// the module that couldn't be parsed.
+///
+/// A module has to fill its declared size exactly to be split this way. If
+/// any doesn't, the [LIB] is read serially so its
+/// [size mismatches](LIB::size_mismatches) are found as they would be
+/// otherwise.
+#[cfg(feature = "parallel")]
+fn read_lib_parallel(reader: &mut Cursor<Vec<u8>>) -> Result<LIB> {
+    use rayon::prelude::*;
+
+    let start = reader.stream_position()?;
+    let serial = |reader: &mut Cursor<Vec<u8>>| -> Result<LIB> {
+        reader.seek(SeekFrom::Start(start))?;
+        Ok(LIB::read(reader)?)
+    };
+
+    check_magic(reader, LIB::MAGIC)?;
+    let Ok(opaque) = OpaqueLIB::read(reader) else {
+        return serial(reader);
+    };
+    // where each module starts, as the serial reader would record it
+    let offsets = opaque
+        .modules()
+        .iter()
+        .scan(start + 4, |position, module| {
+            let offset = *position;
+            *position +=
+                module.metadata().expected_offset() as u64 + module.obj_blob().len() as u64;
+            Some(offset)
+        })
+        .collect::<Vec<_>>();
+    let parsed = opaque
+        .modules()
+        .par_iter()
+        .zip(offsets)
+        .map(|(module, offset)| {
+            let blob = module.obj_blob();
+            let mut cursor = Cursor::new(blob);
+            let mut obj = OBJ::read(&mut cursor)
+                .map_err(|e| Error::Other(format!("module {}: {e}", module.name()).into()))?;
+            let filled = cursor.position() == blob.len() as u64;
+            let obj_start = offset + module.metadata().expected_offset() as u64;
+            obj.section_offsets
+                .0
+                .iter_mut()
+                .for_each(|o| *o += obj_start);
+            let mut parsed = crate::Module::new(obj, module.metadata().clone());
+            parsed.offset.0 = Some(offset);
+            Ok(filled.then_some(parsed))
+        })
+        .collect::<Result<Vec<_>>>();
+
+    match parsed.map(|modules| modules.into_iter().collect::<Option<Vec<_>>>()) {
+        Ok(Some(objs)) if !objs.is_empty() => Ok(LIB {
+            version: opaque.version(),
+            warnings: crate::version_warning(FileKind::LIB, opaque.version(), LIB::VERSION)
+                .into_iter()
+                .collect(),
+            objs,
+            size_mismatches: Vec::new(),
+        }),
+        Ok(_) => serial(reader),
+        // a wrong declared size can cut a module short that reads serially
+        Err(e) => serial(reader).map_err(|_| e),
+    }
+}
+
+/// Reads an SN Systems [SYM] debugging symbol file. If the file cannot be
+/// found or does not contain valid data an error will be returned.
+pub fn read_sym(sym_path: &Path) -> Result<SYM> {
+    read_with(sym_path, |reader| {
+        read_from_with(reader, |reader| Ok(SYM::read(reader)?))
+    })
 }
 
-/// Writes a Psy-Q [LIB]. If the file cannot be written an error will
-/// be returned.
-pub fn write_lib(lib: &LIB, file: &mut File) -> Result<()> {
-    let mut writer = Cursor::new(Vec::new());
-    lib.write(&mut writer)?;
-    let gen = writer.into_inner();
-    file.write_all(&gen)?;
+/// Reads a Psy-Q [LIB] without parsing its modules. See [OpaqueLIB]. If
+/// the file cannot be found or isn't a [LIB] an error will be returned.
+pub fn read_opaque_lib(lib_path: &Path) -> Result<OpaqueLIB> {
+    read_with(lib_path, |reader| {
+        read_from_with(reader, |reader| Ok(OpaqueLIB::read(reader)?))
+    })
+}
+
+/// Options for [read_lib_with_options] and [read_with_options].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadOptions {
+    /// Fail if any module's declared size doesn't match the bytes it was
+    /// read from. See [LIB::size_mismatches].
+    pub strict_sizes: bool,
+
+    /// Skip the bytes of every [Code](crate::Code) section, keeping only
+    /// their size and position in the file. The file is read as it is
+    /// parsed rather than all at once. Deferred code can be read later with
+    /// [LIB::load_code], and a [LIB] or [OBJ] can't be written until it is.
+    pub skip_code_bytes: bool,
+
+    /// The most exports a module's metadata may list before the file is
+    /// taken to be corrupt. Defaults to [ModuleMetadata::MAX_EXPORTS].
+    pub max_exports: Option<usize>,
+
+    /// Fail if a [LIB] or any [OBJ] has a format version other than
+    /// [LIB::VERSION] or [OBJ::VERSION]. Otherwise the file is parsed as if
+    /// it were and the version is reported in [LIB::warnings] or
+    /// [OBJ::warnings].
+    pub strict_versions: bool,
+
+    /// Read past sections whose tag isn't known rather than failing. Each
+    /// is kept as a [Section::Unknown](crate::Section::Unknown) holding the
+    /// bytes up to where the next section appears to start, and reported
+    /// as a [ParseWarning::UnknownSection]. Writing one back reproduces the
+    /// bytes it was read from.
+    pub lenient: bool,
+}
+
+impl ReadOptions {
+    /// The arguments [LIB::read_args] takes for these options.
+    fn lib_args(&self) -> (bool, Option<usize>, bool) {
+        (self.skip_code_bytes, self.max_exports, self.lenient)
+    }
+
+    /// The arguments [OBJ::read_args] takes for these options.
+    fn obj_args(&self) -> (bool, bool) {
+        (self.skip_code_bytes, self.lenient)
+    }
+}
+
+/// Reads a Psy-Q [LIB] or [OBJ], applying `options`. If the file cannot be
+/// found, does not contain valid data, or fails a check enabled by
+/// `options` an error will be returned.
+pub fn read_with_options(lib_or_obj_path: &Path, options: &ReadOptions) -> Result<Type> {
+    let path = lib_or_obj_path;
+    if options.skip_code_bytes {
+        read_streamed(path, |reader| read_file_options(path, reader, options))
+    } else {
+        read_with(path, |reader| read_file_options(path, reader, options))
+    }
+}
+
+/// Reads the file at `path` from `reader` for [read_with_options].
+fn read_file_options(
+    path: &Path,
+    reader: &mut (impl Read + Seek),
+    options: &ReadOptions,
+) -> Result<Type> {
+    match read_from_options(reader, options)? {
+        Type::OBJ(obj) => Ok(Type::OBJ(with_trailing_bytes(
+            check_obj_version(obj, options)?,
+            reader,
+        )?)),
+        Type::LIB(lib) => Ok(Type::LIB(check_sizes(
+            path,
+            check_lib_versions(lib, options)?,
+            options,
+        )?)),
+    }
+}
+
+/// Reads a Psy-Q [LIB], applying `options`. If the file cannot be found,
+/// does not contain valid data, or fails a check enabled by `options` an
+/// error will be returned.
+pub fn read_lib_with_options(lib_path: &Path, options: &ReadOptions) -> Result<LIB> {
+    let lib = if options.skip_code_bytes {
+        read_streamed(lib_path, |reader| read_lib_options(reader, options))?
+    } else if options.max_exports.is_some() || options.lenient {
+        read_with(lib_path, |reader| read_lib_options(reader, options))?
+    } else {
+        read_lib(lib_path)?
+    };
+    check_sizes(lib_path, check_lib_versions(lib, options)?, options)
+}
+
+/// Reads a [LIB] from `reader` with the parsing `options`.
+fn read_lib_options(reader: &mut (impl Read + Seek), options: &ReadOptions) -> Result<LIB> {
+    read_from_with(reader, |reader| {
+        check_magic(reader, LIB::MAGIC)?;
+        Ok(LIB::read_args(reader, options.lib_args())?)
+    })
+}
+
+/// Fails if `options` are strict about versions and `obj` isn't
+/// [OBJ::VERSION].
+fn check_obj_version(obj: OBJ, options: &ReadOptions) -> Result<OBJ> {
+    if options.strict_versions && obj.version() != OBJ::VERSION {
+        return Err(Error::UnknownVersion {
+            kind: FileKind::OBJ,
+            found: obj.version(),
+            module: None,
+        });
+    }
+    Ok(obj)
+}
+
+/// Fails if `options` are strict about versions and `lib` or any of its
+/// modules isn't [LIB::VERSION] or [OBJ::VERSION].
+fn check_lib_versions(lib: LIB, options: &ReadOptions) -> Result<LIB> {
+    if options.strict_versions {
+        if lib.version() != LIB::VERSION {
+            return Err(Error::UnknownVersion {
+                kind: FileKind::LIB,
+                found: lib.version(),
+                module: None,
+            });
+        }
+        if let Some(module) = lib
+            .modules()
+            .iter()
+            .find(|module| module.object().version() != OBJ::VERSION)
+        {
+            return Err(Error::UnknownVersion {
+                kind: FileKind::OBJ,
+                found: module.object().version(),
+                module: Some(module.name()),
+            });
+        }
+    }
+    Ok(lib)
+}
+
+/// Fails if `options` are strict about sizes and a module of `lib` declares
+/// the wrong size.
+fn check_sizes(lib_path: &Path, lib: LIB, options: &ReadOptions) -> Result<LIB> {
+    if options.strict_sizes {
+        if let Some(mismatch) = lib.size_mismatches().first() {
+            bail!(
+                "{}: module {} declares a size of {} bytes but is {} bytes",
+                lib_path.display(),
+                lib.modules()[mismatch.index].name(),
+                mismatch.declared,
+                mismatch.actual
+            );
+        }
+    }
+    Ok(lib)
+}
+
+/// Reads everything salvageable from a damaged Psy-Q [LIB]. See
+/// [recover::recover]. An error is only returned if the file can't be read
+/// or isn't a [LIB].
+pub fn read_lib_recover(lib_path: &Path) -> Result<RecoveredLib> {
+    read_with(lib_path, |reader| {
+        read_from_with(reader, |reader| recover::recover(reader.get_ref()))
+    })
+}
+
+/// Writes a Psy-Q [OBJ] to a file or any other writer, such as stdout or a
+/// `Vec<u8>`. If it cannot be written an error will be returned.
+#[deprecated(note = "use write_obj_to")]
+pub fn write_obj(obj: &OBJ, file: &mut impl Write) -> Result<()> {
+    write_obj_to(obj, file).map(|_| ())
+}
+
+/// Options for [write_obj_with_options] and [write_lib_atomic].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WriteOptions {
+    /// Write the [trailing bytes](OBJ::trailing_bytes) after the OBJ, so a
+    /// file that was read is reproduced byte for byte. They are dropped by
+    /// default, like `DUMPOBJ.EXE` and `PSYLINK.EXE` ignore them.
+    pub preserve_trailing_bytes: bool,
+
+    /// Keep the file [write_lib_atomic] replaces with a `.BAK` extension,
+    /// e.g. `LIBCD.BAK` for `LIBCD.LIB`.
+    pub backup: bool,
+}
+
+/// Writes a Psy-Q [OBJ] like [write_obj_to], applying `options`. Returns the
+/// number of bytes written.
+pub fn write_obj_with_options(
+    obj: &OBJ,
+    file: &mut impl Write,
+    options: &WriteOptions,
+) -> Result<u64> {
+    let mut write = BufWriter::new(file);
+    write_with(FileKind::OBJ, || {
+        let mut size = serialize(obj, &mut write)?;
+        if options.preserve_trailing_bytes {
+            write.write_all(obj.trailing_bytes())?;
+            write.flush()?;
+            size += obj.trailing_bytes().len() as u64;
+        }
+        Ok(size)
+    })
+}
+
+/// Writes a Psy-Q [LIB] to a file or any other writer, such as stdout or a
+/// `Vec<u8>`. If it cannot be written an error will be returned.
+#[deprecated(note = "use write_lib_to")]
+pub fn write_lib(lib: &LIB, file: &mut impl Write) -> Result<()> {
+    write_lib_to(lib, file).map(|_| ())
+}
+
+/// Writes a Psy-Q [LIB] to the file at `path` without ever leaving a
+/// partially written file there. The LIB is written to a temporary file in
+/// the same directory, synced, and renamed over `path`, so if it can't be
+/// written, any file already at `path` is untouched.
+///
+/// With [WriteOptions::backup], the file being replaced is copied to its
+/// [backup_path] first.
+pub fn write_lib_atomic(lib: &LIB, path: &Path, options: &WriteOptions) -> Result<()> {
+    let Some(name) = path.file_name() else {
+        bail!("{} does not name a file", path.display());
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp = dir.join(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let written = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .map_err(Error::from)
+        .and_then(|mut file| {
+            write_lib_to(lib, &mut file)?;
+            Ok(file.sync_all()?)
+        })
+        .and_then(|_| {
+            if options.backup && path.exists() {
+                std::fs::copy(path, backup_path(path))?;
+            }
+            Ok(std::fs::rename(&temp, path)?)
+        });
+    if written.is_err() {
+        // the temporary file may not have been created
+        let _ = std::fs::remove_file(&temp);
+    }
+    written?;
+
+    // the rename isn't durable until the directory is synced
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
     Ok(())
 }
+
+/// Where [write_lib_atomic] keeps the file it replaces: `path` with a
+/// `.BAK` extension.
+pub fn backup_path(path: &Path) -> std::path::PathBuf {
+    path.with_extension("BAK")
+}
+
+/// Writes a Psy-Q [OBJ] to a file or any other writer, such as stdout, a
+/// pipe or a `Vec<u8>`, and flushes it. Returns the number of bytes written.
+pub fn write_obj_to(obj: &OBJ, write: &mut impl Write) -> Result<u64> {
+    write_with(FileKind::OBJ, || serialize(obj, &mut BufWriter::new(write)))
+}
+
+/// Writes a Psy-Q [LIB] to a file or any other writer, such as stdout, a
+/// pipe or a `Vec<u8>`, and flushes it. Returns the number of bytes written.
+///
+/// Each module is serialized to a buffer that is reused for the next, so no
+/// more than the largest module is held in memory in addition to `lib`.
+pub fn write_lib_to(lib: &LIB, write: &mut impl Write) -> Result<u64> {
+    write_with(FileKind::LIB, || {
+        if lib.modules().is_empty() {
+            bail!("a LIB must contain at least one module");
+        }
+        write.write_all(&LIB::MAGIC)?;
+        write.write_all(&[lib.version])?;
+        let mut size = LIB::MAGIC.len() as u64 + 1;
+        let mut buffer = Vec::new();
+        for module in lib.modules() {
+            buffer.clear();
+            module.write_le(&mut Cursor::new(&mut buffer))?;
+            write.write_all(&buffer)?;
+            size += buffer.len() as u64;
+        }
+        write.flush()?;
+        Ok(size)
+    })
+}
+
+/// The number of bytes [write_obj_to] would write for `obj`, computed without
+/// keeping them. [Deferred](crate::Code::deferred) code is counted by its
+/// recorded size, so an OBJ read without its code can still be measured.
+pub fn serialized_size(obj: &OBJ) -> Result<u64> {
+    // "LNK" and the version
+    let mut size = 4;
+    for section in obj.sections() {
+        size += match section {
+            Section::Code(code) if code.deferred().is_some() => 3 + code.size() as u64,
+            section => serialize(section, &mut Counter::default())?,
+        };
+    }
+    Ok(size)
+}
+
+/// The number of bytes [write_lib_to] would write for `lib`, computed without
+/// keeping them. Like [serialized_size], a LIB read without its code can
+/// still be measured.
+pub fn serialized_size_lib(lib: &LIB) -> Result<u64> {
+    if lib.modules().is_empty() {
+        bail!("a LIB must contain at least one module");
+    }
+    let mut size = LIB::MAGIC.len() as u64 + 1;
+    for module in lib.modules() {
+        size += serialize(module.metadata(), &mut Counter::default())?;
+        size += serialized_size(module.object())?;
+    }
+    Ok(size)
+}
+
+/// Iterates over the [ModuleMetadata] of each module in a [LIB] file. See
+/// [read_lib_metadata].
+pub struct LibMetadata {
+    reader: BufReader<File>,
+    done: bool,
+}
+
+impl LibMetadata {
+    fn read_next(&mut self) -> Result<Option<ModuleMetadata>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let metadata = ModuleMetadata::read_le(&mut self.reader)?;
+        let Some(obj_size) = metadata.size.checked_sub(metadata.offset) else {
+            bail!(
+                "Module {} is smaller than its metadata ({} < {})",
+                metadata.name(),
+                metadata.size,
+                metadata.offset
+            );
+        };
+        self.reader.seek_relative(obj_size as i64)?;
+        Ok(Some(metadata))
+    }
+}
+
+impl Iterator for LibMetadata {
+    type Item = Result<ModuleMetadata>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.read_next().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+/// Reads the [ModuleMetadata] of each module in a Psy-Q [LIB] without
+/// reading the modules' [OBJ]s. Only one module's metadata is held in
+/// memory at a time, so this is suitable for scanning many large LIBs.
+pub fn read_lib_metadata(lib_path: &Path) -> Result<LibMetadata> {
+    let mut reader = BufReader::new(File::open(lib_path)?);
+    read_from_with(&mut reader, |reader| {
+        let mut magic = [0u8; 4];
+        std::io::Read::read_exact(reader, &mut magic)?;
+        if magic[0..3] != LIB::MAGIC {
+            let found = [magic[0], magic[1], magic[2]];
+            return Err(Error::BadMagic { offset: 0, found });
+        }
+        Ok(())
+    })?;
+    Ok(LibMetadata {
+        reader,
+        done: false,
+    })
+}
+
+/// Finds the files in `dir` and its subdirectories whose extension is one
+/// of `extensions`, ignoring case, ordered by path. Symbolic links to
+/// directories aren't followed, so a link back up the tree can't make the
+/// walk endless.
+pub(crate) fn find_files(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if !path.is_dir()
+                && path
+                    .extension()
+                    .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
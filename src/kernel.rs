@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! The PlayStation BIOS calls wrapped by the PSY-Q libraries.
+//!
+//! The BIOS provides its functions through three tables, called by jumping
+//! to `0xA0`, `0xB0`, or `0xC0` with the index of the function in `t1`.
+//! Many functions in `LIBAPI`, `LIBC`, and `LIBCARD` do nothing more than
+//! that. [KERNEL_CALLS] lists those wrappers by the symbol the library
+//! exports, so tools can tell a kernel call from library code, and
+//! [shadowed_kernel_calls] finds OBJs that define their own version of one.
+//!
+//! ```
+//! use psyk::kernel::{self, KernelTable};
+//!
+//! let call = kernel::is_kernel_symbol("printf").expect("printf");
+//! assert_eq!((KernelTable::A0, 0x3f), (call.table, call.index));
+//! assert_eq!("[kernel A0:0x3F]", format!("[{call}]"));
+//! assert!(kernel::is_kernel_symbol("sprintf").is_none());
+//! ```
+
+use std::fmt;
+
+use super::{Section, OBJ};
+
+/// The BIOS function table a call goes through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KernelTable {
+    A0,
+    B0,
+    C0,
+}
+
+impl fmt::Display for KernelTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// A BIOS function and the library symbol that calls it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KernelCallInfo {
+    pub table: KernelTable,
+    /// The index of the function in its table.
+    pub index: u8,
+    /// The symbol exported by the library.
+    pub symbol: &'static str,
+    /// The library that exports the wrapper.
+    pub library: &'static str,
+    pub description: &'static str,
+}
+
+/// Written as it is tagged in listings, e.g. `kernel B0:0x34`.
+impl fmt::Display for KernelCallInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "kernel {}:{:#04X}", self.table, self.index)
+    }
+}
+
+macro_rules! calls {
+    ($($table:ident $index:literal $library:ident $symbol:literal $description:literal,)*) => {
+        &[$(KernelCallInfo {
+            table: KernelTable::$table,
+            index: $index,
+            symbol: $symbol,
+            library: stringify!($library),
+            description: $description,
+        },)*]
+    };
+}
+
+/// The BIOS calls wrapped by the PSY-Q libraries. `exit` is wrapped by both
+/// `LIBAPI` and `LIBC`, through different tables.
+pub const KERNEL_CALLS: &[KernelCallInfo] = calls![
+    // LIBAPI
+    A0 0x39 LIBAPI "InitHeap" "initialize the heap for malloc",
+    A0 0x3a LIBAPI "_exit" "terminate the program with a system error",
+    A0 0x41 LIBAPI "LoadTest" "read the header of a PS-X EXE",
+    A0 0x42 LIBAPI "Load" "load a PS-X EXE",
+    A0 0x43 LIBAPI "Exec" "execute a loaded PS-X EXE",
+    A0 0x44 LIBAPI "FlushCache" "flush the instruction cache",
+    A0 0x49 LIBAPI "GPU_cw" "send a GPU command word",
+    A0 0x51 LIBAPI "LoadExec" "load and execute a PS-X EXE",
+    A0 0x52 LIBAPI "GetSysSp" "get the system stack pointer",
+    A0 0x70 LIBAPI "_bu_init" "initialize memory card access",
+    A0 0x71 LIBAPI "_96_init" "initialize CD-ROM access",
+    A0 0x72 LIBAPI "_96_remove" "stop CD-ROM access",
+    A0 0x9c LIBAPI "SetConf" "set the number of events, threads, and stack top",
+    A0 0x9d LIBAPI "GetConf" "get the number of events, threads, and stack top",
+    A0 0x9f LIBAPI "SetMem" "set the size of RAM",
+    B0 0x02 LIBAPI "SetRCnt" "initialize a root counter",
+    B0 0x03 LIBAPI "GetRCnt" "read a root counter",
+    B0 0x04 LIBAPI "StartRCnt" "enable a root counter interrupt",
+    B0 0x05 LIBAPI "StopRCnt" "disable a root counter interrupt",
+    B0 0x06 LIBAPI "ResetRCnt" "reset a root counter",
+    B0 0x07 LIBAPI "DeliverEvent" "deliver an event",
+    B0 0x08 LIBAPI "OpenEvent" "open an event",
+    B0 0x09 LIBAPI "CloseEvent" "close an event",
+    B0 0x0a LIBAPI "WaitEvent" "wait for an event",
+    B0 0x0b LIBAPI "TestEvent" "test whether an event has occurred",
+    B0 0x0c LIBAPI "EnableEvent" "enable an event",
+    B0 0x0d LIBAPI "DisableEvent" "disable an event",
+    B0 0x0e LIBAPI "OpenTh" "open a thread",
+    B0 0x0f LIBAPI "CloseTh" "close a thread",
+    B0 0x10 LIBAPI "ChangeTh" "switch to a thread",
+    B0 0x12 LIBAPI "InitPAD" "initialize controllers",
+    B0 0x13 LIBAPI "StartPAD" "start reading controllers",
+    B0 0x14 LIBAPI "StopPAD" "stop reading controllers",
+    B0 0x15 LIBAPI "PAD_init" "initialize and start controllers",
+    B0 0x16 LIBAPI "PAD_dr" "read the controller buttons",
+    B0 0x17 LIBAPI "ReturnFromException" "return from an exception handler",
+    B0 0x18 LIBAPI "ResetEntryInt" "restore the default exception exit",
+    B0 0x19 LIBAPI "HookEntryInt" "set a custom exception exit",
+    B0 0x20 LIBAPI "UnDeliverEvent" "clear a delivered event",
+    B0 0x32 LIBAPI "open" "open a file",
+    B0 0x33 LIBAPI "lseek" "seek in a file",
+    B0 0x34 LIBAPI "read" "read from a file",
+    B0 0x35 LIBAPI "write" "write to a file",
+    B0 0x36 LIBAPI "close" "close a file",
+    B0 0x37 LIBAPI "ioctl" "control a device",
+    B0 0x38 LIBAPI "exit" "terminate the program",
+    B0 0x40 LIBAPI "cd" "change the current directory",
+    B0 0x41 LIBAPI "format" "format a device",
+    B0 0x42 LIBAPI "firstfile" "find the first file matching a name",
+    B0 0x43 LIBAPI "nextfile" "find the next file matching a name",
+    B0 0x44 LIBAPI "rename" "rename a file",
+    B0 0x45 LIBAPI "delete" "delete a file",
+    B0 0x46 LIBAPI "undelete" "restore a deleted file",
+    B0 0x47 LIBAPI "AddDrv" "add a device driver",
+    B0 0x48 LIBAPI "DelDrv" "remove a device driver",
+    B0 0x51 LIBAPI "Krom2RawAdd" "find a Shift-JIS character in the BIOS font",
+    B0 0x54 LIBAPI "_get_errno" "get the last error",
+    B0 0x55 LIBAPI "_get_error" "get the last error of a file",
+    B0 0x5b LIBAPI "ChangeClearPAD" "set whether controller interrupts are acknowledged",
+    C0 0x02 LIBAPI "SysEnqIntRP" "add an interrupt handler",
+    C0 0x03 LIBAPI "SysDeqIntRP" "remove an interrupt handler",
+    C0 0x0a LIBAPI "ChangeClearRCnt" "set whether root counter interrupts are acknowledged",
+    // LIBC
+    A0 0x06 LIBC "exit" "terminate the program",
+    A0 0x08 LIBC "getc" "read a character from a file",
+    A0 0x09 LIBC "putc" "write a character to a file",
+    A0 0x0a LIBC "todigit" "convert a character to a digit",
+    A0 0x0c LIBC "strtoul" "convert a string to an unsigned long",
+    A0 0x0d LIBC "strtol" "convert a string to a long",
+    A0 0x0e LIBC "abs" "absolute value of an int",
+    A0 0x0f LIBC "labs" "absolute value of a long",
+    A0 0x10 LIBC "atoi" "convert a string to an int",
+    A0 0x11 LIBC "atol" "convert a string to a long",
+    A0 0x12 LIBC "atob" "convert a string to a number",
+    A0 0x13 LIBC "setjmp" "save the registers for longjmp",
+    A0 0x14 LIBC "longjmp" "restore the registers saved by setjmp",
+    A0 0x15 LIBC "strcat" "append a string",
+    A0 0x16 LIBC "strncat" "append part of a string",
+    A0 0x17 LIBC "strcmp" "compare strings",
+    A0 0x18 LIBC "strncmp" "compare the start of strings",
+    A0 0x19 LIBC "strcpy" "copy a string",
+    A0 0x1a LIBC "strncpy" "copy part of a string",
+    A0 0x1b LIBC "strlen" "length of a string",
+    A0 0x1c LIBC "index" "find the first occurrence of a character",
+    A0 0x1d LIBC "rindex" "find the last occurrence of a character",
+    A0 0x1e LIBC "strchr" "find the first occurrence of a character",
+    A0 0x1f LIBC "strrchr" "find the last occurrence of a character",
+    A0 0x20 LIBC "strpbrk" "find the first of a set of characters",
+    A0 0x21 LIBC "strspn" "length of a prefix made of a set of characters",
+    A0 0x22 LIBC "strcspn" "length of a prefix without a set of characters",
+    A0 0x23 LIBC "strtok" "split a string into tokens",
+    A0 0x24 LIBC "strstr" "find a substring",
+    A0 0x25 LIBC "toupper" "convert a character to upper case",
+    A0 0x26 LIBC "tolower" "convert a character to lower case",
+    A0 0x27 LIBC "bcopy" "copy bytes",
+    A0 0x28 LIBC "bzero" "zero bytes",
+    A0 0x29 LIBC "bcmp" "compare bytes",
+    A0 0x2a LIBC "memcpy" "copy memory",
+    A0 0x2b LIBC "memset" "fill memory",
+    A0 0x2c LIBC "memmove" "copy overlapping memory",
+    A0 0x2d LIBC "memcmp" "compare memory",
+    A0 0x2e LIBC "memchr" "find a byte in memory",
+    A0 0x2f LIBC "rand" "generate a random number",
+    A0 0x30 LIBC "srand" "seed the random number generator",
+    A0 0x31 LIBC "qsort" "sort an array",
+    A0 0x33 LIBC "malloc" "allocate memory",
+    A0 0x34 LIBC "free" "free allocated memory",
+    A0 0x35 LIBC "lsearch" "search an array",
+    A0 0x36 LIBC "bsearch" "search a sorted array",
+    A0 0x37 LIBC "calloc" "allocate zeroed memory",
+    A0 0x38 LIBC "realloc" "resize allocated memory",
+    A0 0x3b LIBC "getchar" "read a character from the console",
+    A0 0x3c LIBC "putchar" "write a character to the console",
+    A0 0x3d LIBC "gets" "read a line from the console",
+    A0 0x3e LIBC "puts" "write a line to the console",
+    A0 0x3f LIBC "printf" "write formatted text to the console",
+    // LIBCARD
+    A0 0xab LIBCARD "_card_info" "get the status of a memory card",
+    A0 0xac LIBCARD "_card_load" "read the directory of a memory card",
+    A0 0xad LIBCARD "_card_auto" "set whether memory cards are formatted automatically",
+    B0 0x4a LIBCARD "InitCARD" "initialize memory cards",
+    B0 0x4b LIBCARD "StartCARD" "start memory card access",
+    B0 0x4c LIBCARD "StopCARD" "stop memory card access",
+    B0 0x4e LIBCARD "_card_write" "write a memory card sector",
+    B0 0x4f LIBCARD "_card_read" "read a memory card sector",
+    B0 0x50 LIBCARD "_new_card" "accept a newly inserted memory card",
+    B0 0x58 LIBCARD "_card_chan" "get the port of the last memory card event",
+    B0 0x5c LIBCARD "_card_status" "get the status of a memory card port",
+    B0 0x5d LIBCARD "_card_wait" "wait for a memory card port",
+];
+
+/// Returns the BIOS call wrapped by the library symbol `name`, if any. A
+/// symbol wrapped by more than one library is reported for the first one in
+/// [KERNEL_CALLS].
+pub fn is_kernel_symbol(name: &str) -> Option<KernelCallInfo> {
+    KERNEL_CALLS
+        .iter()
+        .find(|call| super::symbols::symbol_eq(call.symbol, name))
+        .copied()
+}
+
+/// The symbols defined by `obj` that have the same name as a kernel call
+/// wrapper, in the order they're defined. Linked before the library, these
+/// replace the wrapper for every module that calls it.
+pub fn shadowed_kernel_calls(obj: &OBJ) -> Vec<KernelCallInfo> {
+    obj.sections()
+        .iter()
+        .filter_map(|section| match section {
+            Section::XDEF(xdef) => is_kernel_symbol(&xdef.symbol_name()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_is_kernel_symbol() {
+        let call = is_kernel_symbol("InitHeap").expect("InitHeap");
+        assert_eq!(
+            (KernelTable::A0, 0x39, "LIBAPI"),
+            (call.table, call.index, call.library)
+        );
+        let call = is_kernel_symbol("exit").expect("exit");
+        assert_eq!(
+            (KernelTable::B0, 0x38, "LIBAPI"),
+            (call.table, call.index, call.library)
+        );
+        assert_eq!("kernel B0:0x38", call.to_string());
+        let call = is_kernel_symbol("ChangeClearRCnt").expect("ChangeClearRCnt");
+        assert_eq!("kernel C0:0x0A", call.to_string());
+
+        for name in [
+            "",
+            "print",
+            "printf2",
+            "Printf",
+            "InitHeap2",
+            "_printf",
+            "sprintf",
+        ] {
+            assert_eq!(None, is_kernel_symbol(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_kernel_calls() {
+        // each table index is wrapped by one symbol per library
+        let mut seen = HashSet::new();
+        for call in KERNEL_CALLS {
+            assert!(
+                seen.insert((call.library, call.table, call.index)),
+                "{call:?}"
+            );
+            assert!(!call.description.is_empty());
+        }
+    }
+}
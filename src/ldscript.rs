@@ -0,0 +1,290 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! GNU ld script fragments for PSY-Q uninitialized data.
+//!
+//! Converted PSY-Q libraries declare zero-filled symbols with
+//! [XBSS](super::XBSS) records rather than as sized data in a section. When
+//! linking with GNU ld those symbols still need space reserved for them.
+//! [bss_fragment] emits the statements to do that, which can be included in
+//! an output section description:
+//!
+//! ```text
+//! SECTIONS {
+//!     .bss (NOLOAD) : {
+//!         INCLUDE bss.ld
+//!         *(.bss)
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{Section, OBJ};
+
+/// Options for [bss_fragment].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    /// Wrap symbol definitions in `PROVIDE()` so they can be overridden by
+    /// definitions in user code.
+    pub provide: bool,
+    /// Define start and length symbols for each section class following
+    /// the PSY-Q convention, e.g. `__bss` and `__bsslen`.
+    pub compat_symbols: bool,
+}
+
+/// An XBSS symbol placed by [bss_layout].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BssSymbol {
+    pub name: String,
+    /// The offset of the symbol from the start of its section class.
+    pub offset: u32,
+    pub size: u32,
+    pub align: u32,
+}
+
+/// All XBSS symbols allocated in sections of the same type, e.g. `.bss`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BssClass {
+    /// The section type name, including the leading `.`.
+    pub name: String,
+    /// The largest alignment of any symbol in the class.
+    pub align: u32,
+    /// The total size including alignment padding.
+    pub size: u32,
+    /// Symbols in layout order.
+    pub symbols: Vec<BssSymbol>,
+}
+
+/// The alignment of a symbol of `size` bytes in a section aligned to
+/// `section_align` bytes. Symbols are naturally aligned, but never to more
+/// than their section.
+fn symbol_align(size: u32, section_align: u8) -> u32 {
+    let natural = size.max(1).checked_next_power_of_two().unwrap_or(1 << 31);
+    natural.min(section_align.max(1) as u32)
+}
+
+/// Lays out the XBSS symbols of `objs` in the order they are declared,
+/// grouped by the type of the section they are allocated in. Classes are
+/// returned in the order they are first used.
+pub fn bss_layout(objs: &[&OBJ]) -> Vec<BssClass> {
+    let mut classes: Vec<BssClass> = Vec::new();
+    for obj in objs {
+        let headers = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::LNKHeader(h) => Some((h.section(), h)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        for xbss in obj.sections().iter().filter_map(|s| match s {
+            Section::XBSS(xbss) => Some(xbss),
+            _ => None,
+        }) {
+            let (class_name, section_align) = match headers.get(&xbss.section()) {
+                Some(header) => (header.type_name(), header.align()),
+                None => (".bss".to_string(), 1),
+            };
+            let align = symbol_align(xbss.size(), section_align);
+
+            let class = match classes.iter().position(|c| c.name == class_name) {
+                Some(i) => &mut classes[i],
+                None => {
+                    classes.push(BssClass {
+                        name: class_name,
+                        align: 1,
+                        size: 0,
+                        symbols: Vec::new(),
+                    });
+                    classes.last_mut().expect("class")
+                }
+            };
+
+            let offset = class.size.next_multiple_of(align);
+            class.align = class.align.max(align);
+            class.size = offset + xbss.size();
+            class.symbols.push(BssSymbol {
+                name: xbss.name(),
+                offset,
+                size: xbss.size(),
+                align,
+            });
+        }
+    }
+    classes
+}
+
+/// Generates a GNU ld script fragment reserving space for the XBSS symbols
+/// of `objs`. See [bss_layout] for how symbols are ordered.
+pub fn bss_fragment(objs: &[&OBJ], options: &Options) -> String {
+    let define = |name: &str| {
+        if options.provide {
+            format!("PROVIDE({name} = .);")
+        } else {
+            format!("{name} = .;")
+        }
+    };
+
+    let mut script = String::new();
+    let _ = writeln!(script, "/* Generated by psyk ldscript-bss */");
+    for class in bss_layout(objs) {
+        let base = class.name.trim_start_matches('.');
+        let _ = writeln!(script);
+        let _ = writeln!(script, "/* {} */", class.name);
+        let _ = writeln!(script, ". = ALIGN({});", class.align);
+        if options.compat_symbols {
+            let _ = writeln!(script, "{}", define(&format!("__{base}")));
+        }
+
+        let mut offset = 0;
+        for symbol in &class.symbols {
+            if symbol.offset != offset {
+                let _ = writeln!(script, ". = ALIGN({});", symbol.align);
+            }
+            let _ = writeln!(script, "{}", define(&symbol.name));
+            let _ = writeln!(script, ". += {};", symbol.size);
+            offset = symbol.offset + symbol.size;
+        }
+
+        if options.compat_symbols {
+            let length = format!("__{base}len = . - __{base};");
+            if options.provide {
+                let _ = writeln!(script, "PROVIDE({});", length.trim_end_matches(';'));
+            } else {
+                let _ = writeln!(script, "{length}");
+            }
+        }
+    }
+    script
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LNKHeader, XBSS};
+
+    fn header(section: u16, align: u8, name: &str) -> Section {
+        Section::LNKHeader(LNKHeader {
+            section,
+            group: 0,
+            align,
+            type_name_size: name.len() as u8,
+            type_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn xbss(number: u16, section: u16, size: u32, name: &str) -> Section {
+        Section::XBSS(XBSS {
+            number,
+            section,
+            size,
+            name_size: name.len() as u8,
+            name: name.as_bytes().to_vec(),
+        })
+    }
+
+    /// Declares the same symbols as SNDEF in LIBSN.LIB.
+    fn sndef() -> OBJ {
+        OBJ::new(vec![
+            header(1, 8, ".text"),
+            header(2, 8, ".sbss"),
+            header(3, 8, ".bss"),
+            xbss(4, 2, 4, "_stacksize"),
+            xbss(5, 2, 4, "_ramsize"),
+            Section::NOP,
+        ])
+    }
+
+    #[test]
+    fn test_sndef_fragment() {
+        let sndef = sndef();
+        assert_eq!(
+            "\
+            /* Generated by psyk ldscript-bss */\n\
+            \n\
+            /* .sbss */\n\
+            . = ALIGN(4);\n\
+            _stacksize = .;\n\
+            . += 4;\n\
+            _ramsize = .;\n\
+            . += 4;\n",
+            bss_fragment(&[&sndef], &Options::default())
+        );
+    }
+
+    #[test]
+    fn test_alignment() {
+        let a = OBJ::new(vec![
+            header(1, 8, ".bss"),
+            xbss(2, 1, 1, "flag"),
+            xbss(3, 1, 8, "matrix"),
+            xbss(4, 1, 2, "count"),
+            Section::NOP,
+        ]);
+        let b = OBJ::new(vec![
+            header(7, 2, ".bss"),
+            header(8, 4, ".sbss"),
+            xbss(2, 7, 3, "rgb"),
+            xbss(3, 7, 16, "buffer"),
+            xbss(4, 8, 4, "small"),
+            Section::NOP,
+        ]);
+
+        let layout = bss_layout(&[&a, &b]);
+        assert_eq!(2, layout.len());
+        let offsets = layout[0]
+            .symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("flag", 0),
+                ("matrix", 8),
+                ("count", 16),
+                ("rgb", 18),
+                ("buffer", 22)
+            ],
+            offsets
+        );
+        assert_eq!(8, layout[0].align);
+        assert_eq!(38, layout[0].size);
+
+        let options = Options {
+            provide: true,
+            compat_symbols: true,
+        };
+        assert_eq!(
+            "\
+            /* Generated by psyk ldscript-bss */\n\
+            \n\
+            /* .bss */\n\
+            . = ALIGN(8);\n\
+            PROVIDE(__bss = .);\n\
+            PROVIDE(flag = .);\n\
+            . += 1;\n\
+            . = ALIGN(8);\n\
+            PROVIDE(matrix = .);\n\
+            . += 8;\n\
+            PROVIDE(count = .);\n\
+            . += 2;\n\
+            PROVIDE(rgb = .);\n\
+            . += 3;\n\
+            . = ALIGN(2);\n\
+            PROVIDE(buffer = .);\n\
+            . += 16;\n\
+            PROVIDE(__bsslen = . - __bss);\n\
+            \n\
+            /* .sbss */\n\
+            . = ALIGN(4);\n\
+            PROVIDE(__sbss = .);\n\
+            PROVIDE(small = .);\n\
+            . += 4;\n\
+            PROVIDE(__sbsslen = . - __sbss);\n",
+            bss_fragment(&[&a, &b], &options)
+        );
+    }
+}
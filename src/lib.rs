@@ -53,26 +53,71 @@
 //! ```
 
 use core::cmp;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
 use binrw::binrw;
-use binrw::helpers::{until, until_eof};
+use binrw::helpers::until_eof;
+use binrw::io::{Cursor, NoSeek, Read, Seek, SeekFrom};
+use binrw::{BinRead, BinWrite};
 use chrono::{
     DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
 };
 use rabbitizer::{InstrCategory, Instruction};
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::display::DisplayWithOptions;
+use crate::crc32::Crc32;
+use crate::display::{labels, DisplayWithOptions};
+use crate::error::{bail, Result};
+use crate::metrics::FileKind;
 
+pub mod analysis;
+pub mod asm;
+pub mod assembler;
+pub mod census;
+pub mod chronology;
 pub mod cli;
+pub mod compat;
+mod crc32;
+pub mod diff;
 pub mod display;
+pub mod editors;
+pub mod error;
+pub mod eval;
+pub mod functions;
+pub mod ident;
+pub mod index;
 pub mod io;
+pub mod kernel;
+pub mod ldscript;
+pub mod lines;
 pub mod link;
+pub mod masked;
+pub mod metrics;
+pub mod names;
+#[cfg(feature = "package")]
+pub mod package;
+pub mod plugin;
+pub mod poke;
+pub mod prelude;
+pub mod recover;
+pub mod reloc;
+#[cfg(feature = "serde")]
+mod ser;
+pub mod simple;
+pub mod sizes;
+pub mod splat;
+pub mod sym;
+pub mod symbols;
+pub mod symtab;
+pub mod types;
+pub mod verify;
+pub mod xref;
+
+pub use error::{Error, ParseWarning};
 
 /// A [LIB] is an archive of several [OBJ] files. It consists
 /// of a magic number followed by one or more [Modules](Module).
@@ -95,23 +140,266 @@ pub mod link;
 /// | Offset | Type       | Description                                           |
 /// |--------|------------|-------------------------------------------------------|
 /// |   0    | `[u8;3]`   | Magic: "LIB"                                          |
-/// |   3    | `u8`       | Archive format version (1)                            |
+/// |   3    | `u8`       | Archive format version ([LIB::VERSION])               |
 /// |   4    | `[Module]` | One or more [Modules](Module) which wrap [OBJ] files. |
 #[binrw]
 #[brw(little, magic = b"LIB", assert(!objs.is_empty()))]
+#[br(import(defer_code: bool, max_exports: Option<usize>, lenient: bool))]
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LIB {
     version: u8,
 
-    #[br(parse_with = until_eof)]
+    #[br(temp, parse_with = parse_modules, args(defer_code, max_exports, lenient))]
+    #[bw(ignore)]
+    parsed: (Vec<Module>, Vec<SizeMismatch>),
+
+    #[cfg_attr(feature = "serde", serde(rename = "modules"))]
+    #[br(calc = parsed.0)]
     objs: Vec<Module>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = parsed.1)]
+    #[bw(ignore)]
+    size_mismatches: Vec<SizeMismatch>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = version_warning(FileKind::LIB, version, LIB::VERSION).into_iter().collect())]
+    #[bw(ignore)]
+    warnings: Vec<ParseWarning>,
+}
+
+/// The warning for a file whose format version isn't `expected`, if it
+/// isn't.
+fn version_warning(kind: FileKind, version: u8, expected: u8) -> Option<ParseWarning> {
+    (version != expected).then_some(ParseWarning::UnknownVersion {
+        kind,
+        found: version,
+    })
+}
+
+/// Positions in the stream a [LIB] or [OBJ] was read from. They don't
+/// take part in comparisons, so the same contents read from different
+/// places, or built in memory, are equal.
+#[derive(Clone, Debug, Default)]
+struct ReadOffsets<T>(T);
+
+impl<T> PartialEq for ReadOffsets<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// A module whose metadata declares a different size than the module
+/// occupies in its [LIB].
+///
+/// Modules are read one after another, each starting where the previous
+/// module's [OBJ] ended, so a wrong size doesn't prevent a [LIB] from being
+/// read. Readers which skip from module to module using the declared sizes,
+/// like [io::read_lib_metadata], will find different boundaries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeMismatch {
+    /// The index of the module in [LIB::modules].
+    pub index: usize,
+    /// The size stored in the module metadata.
+    pub declared: u32,
+    /// The size of the metadata and [OBJ] as they were read.
+    pub actual: u32,
+}
+
+/// What [LIB::merge] does with a module of the second library named like
+/// one of the first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the first library's module.
+    KeepFirst,
+    /// Replace the first library's module with the second's, in place.
+    KeepSecond,
+    /// Fail, naming every module in both.
+    #[default]
+    ErrorOnConflict,
+}
+
+/// A problem [LIB::validate] finds with a library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LibIssue {
+    /// Two modules both strongly define `symbol`, so which one a program
+    /// links against depends on the linker.
+    DuplicateExport {
+        symbol: String,
+        first: String,
+        second: String,
+    },
+    /// More than one module is named `name`. Names are compared without
+    /// regard to case.
+    DuplicateModule { name: String },
+    /// The stored offset to the end of a module's metadata doesn't match
+    /// the metadata.
+    MetadataOffset {
+        module: String,
+        stored: u32,
+        expected: u32,
+    },
+    /// The size stored in a module's metadata doesn't match its metadata
+    /// and OBJ as they will be written.
+    SizeMismatch {
+        module: String,
+        declared: u32,
+        actual: u32,
+    },
+    /// A module's OBJ can't be serialized, so its size can't be checked.
+    Unwritable { module: String, reason: String },
+}
+
+impl fmt::Display for LibIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateExport {
+                symbol,
+                first,
+                second,
+            } => write!(f, "{symbol} is exported by both {first} and {second}"),
+            Self::DuplicateModule { name } => {
+                write!(f, "more than one module is named {name}")
+            }
+            Self::MetadataOffset {
+                module,
+                stored,
+                expected,
+            } => write!(
+                f,
+                "{module}: metadata offset {stored} does not match the {expected} bytes of metadata"
+            ),
+            Self::SizeMismatch {
+                module,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "{module}: metadata declares a size of {declared} bytes but the module is {actual} bytes"
+            ),
+            Self::Unwritable { module, reason } => {
+                write!(f, "{module}: can't be written: {reason}")
+            }
+        }
+    }
+}
+
+/// Reads modules until the end of the stream, checking each module's
+/// declared size against the number of bytes it was read from.
+#[binrw::parser(reader, endian)]
+fn parse_modules(
+    defer_code: bool,
+    max_exports: Option<usize>,
+    lenient: bool,
+) -> binrw::BinResult<(Vec<Module>, Vec<SizeMismatch>)> {
+    let mut modules = Vec::new();
+    let mut mismatches = Vec::new();
+    let start = reader.stream_position()?;
+    let end = reader.seek(binrw::io::SeekFrom::End(0))?;
+    reader.seek(binrw::io::SeekFrom::Start(start))?;
+
+    let mut position = start;
+    while position < end {
+        let args = (defer_code, max_exports, lenient);
+        let mut module = <Module as binrw::BinRead>::read_options(reader, endian, args)?;
+        module.offset = ReadOffsets(Some(position));
+        let next = reader.stream_position()?;
+        let actual = (next - position) as u32;
+        if actual != module.metadata.size {
+            mismatches.push(SizeMismatch {
+                index: modules.len(),
+                declared: module.metadata.size,
+                actual,
+            });
+        }
+        modules.push(module);
+        position = next;
+    }
+    Ok((modules, mismatches))
 }
 
 impl LIB {
+    /// The archive format version written by `PSYLIB.EXE`.
+    pub const VERSION: u8 = 1;
+
     /// Creates a new [LIB] with the provided modules.
     pub fn new(objs: Vec<Module>) -> Self {
-        Self { version: 1, objs }
+        Self::with_version(Self::VERSION, objs)
+    }
+
+    /// Creates a new [LIB] with the provided modules that is written with
+    /// archive format `version` instead of [VERSION](Self::VERSION).
+    pub fn with_version(version: u8, objs: Vec<Module>) -> Self {
+        Self {
+            version,
+            objs,
+            size_mismatches: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Returns the archive format version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Anomalies found while reading this library that didn't prevent it
+    /// from being read, like an unknown archive or OBJ version: its own,
+    /// then those of each module, [in](ParseWarning::InModule) the module.
+    /// A module's are a
+    /// [suspicious timestamp](ParseWarning::SuspiciousTimestamp) and its
+    /// [OBJ::warnings].
+    ///
+    /// Reading with [io::ReadOptions::strict_versions] fails on unknown
+    /// versions instead.
+    pub fn warnings(&self) -> Vec<ParseWarning> {
+        let mut warnings = self.warnings.clone();
+        for module in &self.objs {
+            let timestamp =
+                module
+                    .created_at()
+                    .is_none()
+                    .then_some(ParseWarning::SuspiciousTimestamp {
+                        created: module.metadata.created,
+                    });
+            warnings.extend(
+                timestamp
+                    .into_iter()
+                    .chain(module.obj.warnings())
+                    .map(|warning| ParseWarning::InModule {
+                        module: module.name(),
+                        warning: Box::new(warning),
+                    }),
+            );
+        }
+        warnings
+    }
+
+    /// Modules whose declared size didn't match the bytes they were read
+    /// from. Always empty for a [LIB] that wasn't read from a file or whose
+    /// sizes were [repaired](Self::repair_sizes).
+    pub fn size_mismatches(&self) -> &[SizeMismatch] {
+        &self.size_mismatches
+    }
+
+    /// Rewrites the metadata offset and size of every module to match its
+    /// metadata and [OBJ] as they will be written. It is an error for a
+    /// module's OBJ not to be serializable, in which case no sizes are
+    /// changed.
+    pub fn repair_sizes(&mut self) -> Result<()> {
+        let obj_sizes = self
+            .objs
+            .iter()
+            .map(|module| io::serialized_size(&module.obj))
+            .collect::<Result<Vec<u64>>>()?;
+        for (module, obj_size) in self.objs.iter_mut().zip(obj_sizes) {
+            module.metadata.offset = module.metadata.expected_offset();
+            module.metadata.size = module.metadata.offset + obj_size as u32;
+        }
+        self.size_mismatches.clear();
+        Ok(())
     }
 
     /// The modules contained in this library.
@@ -121,6 +409,327 @@ impl LIB {
     pub fn modules(&self) -> &Vec<Module> {
         &self.objs
     }
+
+    /// Counts the sections and expressions of each module. See [census].
+    pub fn format_census(&self) -> census::LibCensus {
+        census::LibCensus::of_lib(self)
+    }
+
+    /// Checks this library for problems. See [verify] for details.
+    pub fn verify(&self, options: &verify::Options) -> Vec<verify::Finding> {
+        verify::verify_lib(self, options)
+    }
+
+    /// Returns which modules reference symbols defined by which others, and
+    /// the symbols no module defines. See [link::DependencyGraph].
+    pub fn dependency_graph(&self) -> link::DependencyGraph {
+        link::DependencyGraph::of_lib(self)
+    }
+
+    /// Reads any [deferred](Code::deferred) code of every module from
+    /// `reader`, the stream this library was read from.
+    pub fn load_code(&mut self, reader: &mut (impl Read + Seek)) -> Result<()> {
+        for module in &mut self.objs {
+            module.obj.load_code(reader)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the name and section sizes of each module. See [sizes].
+    pub fn size_report(&self) -> Vec<(String, sizes::SectionSizes)> {
+        sizes::size_report(self)
+    }
+
+    /// Checks that this library can be written without ambiguity: that no
+    /// two modules strongly define the same symbol or share a name, and
+    /// that each module's metadata offset and size match what will be
+    /// written. `PSYLIB.EXE` refuses to build libraries with duplicate
+    /// symbols. Common symbols may be defined by more than one module.
+    pub fn validate(&self) -> Vec<LibIssue> {
+        let mut issues = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut exporters: HashMap<String, String> = HashMap::new();
+        for module in &self.objs {
+            let name = module.name();
+            // reported once, at the second module with the name
+            if names
+                .iter()
+                .filter(|n| symbols::module_eq(n, &name))
+                .count()
+                == 1
+            {
+                issues.push(LibIssue::DuplicateModule { name: name.clone() });
+            }
+            names.push(name.clone());
+
+            for definition in module.obj.definitions() {
+                if definition.strength != DefinitionStrength::Strong {
+                    continue;
+                }
+                match exporters.get(&definition.name) {
+                    Some(first) => issues.push(LibIssue::DuplicateExport {
+                        symbol: definition.name,
+                        first: first.clone(),
+                        second: name.clone(),
+                    }),
+                    None => {
+                        exporters.insert(definition.name, name.clone());
+                    }
+                }
+            }
+
+            let stored = module.metadata.offset;
+            let expected = module.metadata.expected_offset();
+            if stored != expected {
+                issues.push(LibIssue::MetadataOffset {
+                    module: name.clone(),
+                    stored,
+                    expected,
+                });
+            }
+            let obj_size = match io::serialized_size(&module.obj) {
+                Ok(size) => size as u32,
+                Err(e) => {
+                    issues.push(LibIssue::Unwritable {
+                        module: name,
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let actual = stored + obj_size;
+            if module.metadata.size != actual {
+                issues.push(LibIssue::SizeMismatch {
+                    module: name,
+                    declared: module.metadata.size,
+                    actual,
+                });
+            }
+        }
+        issues
+    }
+
+    /// Checks this library for problems, reusing the results in `baseline`
+    /// for modules that haven't changed. See
+    /// [verify_lib_incremental](verify::verify_lib_incremental).
+    pub fn verify_incremental(
+        &self,
+        options: &verify::Options,
+        baseline: &verify::Baseline,
+    ) -> (Vec<verify::Finding>, verify::Baseline) {
+        verify::verify_lib_incremental(self, options, baseline)
+    }
+
+    /// Appends `module` to the end of this library.
+    pub fn push_module(&mut self, module: Module) {
+        self.objs.push(module);
+    }
+
+    /// Removes and returns the first module named `name`. Names are
+    /// compared without regard to case, as `PSYLIB.EXE` does.
+    ///
+    /// Removing the last module leaves the library empty, which is an error
+    /// when it is written.
+    pub fn remove_module(&mut self, name: &str) -> Option<Module> {
+        let index = self.module_index(name)?;
+        Some(self.objs.remove(index))
+    }
+
+    /// Replaces the first module with the same name as `module` and returns
+    /// it. Names are compared without regard to case. If no module has the
+    /// name, `module` is appended and `None` is returned.
+    pub fn replace_module(&mut self, module: Module) -> Option<Module> {
+        match self.module_index(&module.name()) {
+            Some(index) => Some(std::mem::replace(&mut self.objs[index], module)),
+            None => {
+                self.objs.push(module);
+                None
+            }
+        }
+    }
+
+    /// Renames the first module named `old` to `new`, keeping its
+    /// timestamp, exports, and place in the library. Names are compared
+    /// without regard to case. See [Module::set_name].
+    ///
+    /// It is an error if no module is named `old` or another module is
+    /// already named `new`.
+    pub fn rename_module(&mut self, old: &str, new: &str) -> Result<()> {
+        let Some(index) = self.module_index(old) else {
+            bail!("no module is named {old}");
+        };
+        let mut renamed = self.objs[index].clone();
+        renamed.set_name(new)?;
+        let name = renamed.name();
+        if self.module_index(&name).is_some_and(|i| i != index) {
+            bail!("another module is already named {name}");
+        }
+        self.objs[index] = renamed;
+        Ok(())
+    }
+
+    /// Returns a library of this library's modules followed by the modules
+    /// of `other` it doesn't have, both in order. A module of `other` named
+    /// like one of these is kept, replaces it in place, or is an error
+    /// listing every such name, as `strategy` says. Names are compared
+    /// without regard to case.
+    ///
+    /// It is also an error, listing them, if a symbol is strongly defined
+    /// by a module from each library. See [validate](Self::validate).
+    pub fn merge(&self, other: &LIB, strategy: MergeStrategy) -> Result<LIB> {
+        let mut merged = self.clone();
+        let mut conflicts = Vec::new();
+        let mut taken = Vec::new();
+        for module in &other.objs {
+            let name = module.name();
+            match (merged.module_index(&name), strategy) {
+                (None, _) => merged.objs.push(module.clone()),
+                (Some(_), MergeStrategy::KeepFirst) => continue,
+                (Some(index), MergeStrategy::KeepSecond) => merged.objs[index] = module.clone(),
+                (Some(_), MergeStrategy::ErrorOnConflict) => {
+                    conflicts.push(name);
+                    continue;
+                }
+            }
+            taken.push(module.name());
+        }
+        if !conflicts.is_empty() {
+            bail!("modules are in both libraries: {}", conflicts.join(", "));
+        }
+
+        let from_other = |name: &String| taken.iter().any(|t| symbols::module_eq(t, name));
+        let duplicates = merged
+            .validate()
+            .into_iter()
+            .filter(|issue| match issue {
+                LibIssue::DuplicateExport { first, second, .. } => {
+                    from_other(first) != from_other(second)
+                }
+                _ => false,
+            })
+            .map(|issue| format!("  {issue}"))
+            .collect::<Vec<_>>();
+        if !duplicates.is_empty() {
+            bail!(
+                "symbols are exported by both libraries:\n{}",
+                duplicates.join("\n")
+            );
+        }
+        merged.size_mismatches.clear();
+        merged.warnings.clear();
+        Ok(merged)
+    }
+
+    fn module_index(&self, name: &str) -> Option<usize> {
+        self.objs
+            .iter()
+            .position(|module| symbols::module_eq(&module.name(), name))
+    }
+
+    /// Returns the library as it is written to a file. It is an error for
+    /// the library to have no modules.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        io::write_lib_to(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the first module that exports `symbol`.
+    ///
+    /// Only each module's [metadata](ModuleMetadata::exports) is searched.
+    /// To look up many symbols, build a [SymbolIndex] once with
+    /// [symbol_index](Self::symbol_index).
+    pub fn find_module_by_export(&self, symbol: &str) -> Option<&Module> {
+        self.objs.iter().find(|module| {
+            module
+                .exports()
+                .iter()
+                .any(|export| symbols::symbol_eq(export, symbol))
+        })
+    }
+
+    /// Indexes the modules of this library by the symbols their metadata
+    /// exports.
+    pub fn symbol_index(&self) -> SymbolIndex<'_> {
+        let mut modules: HashMap<String, Vec<&Module>> = HashMap::new();
+        for module in &self.objs {
+            for export in module.exports() {
+                let entry = modules.entry(export).or_default();
+                if !entry.iter().any(|m| std::ptr::eq(*m, module)) {
+                    entry.push(module);
+                }
+            }
+        }
+        SymbolIndex { modules }
+    }
+
+    /// Returns each section of each module in this library, with the module
+    /// it belongs to, in order.
+    ///
+    /// The `Section::NOP` ending each module is not included.
+    pub fn iter_sections(&self) -> impl Iterator<Item = (&Module, &Section)> {
+        self.objs.iter().flat_map(|module| {
+            module
+                .object()
+                .sections()
+                .iter()
+                .filter(|section| !matches!(section, Section::NOP))
+                .map(move |section| (module, section))
+        })
+    }
+}
+
+/// The modules of a [LIB] that export each symbol. See [LIB::symbol_index].
+///
+/// ```
+/// use std::path::Path;
+/// use psyk::io;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+/// let index = lib.symbol_index();
+/// assert_eq!("HELPER", index.find("helper").unwrap().name());
+/// assert!(index.find("printf").is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SymbolIndex<'a> {
+    modules: HashMap<String, Vec<&'a Module>>,
+}
+
+impl<'a> SymbolIndex<'a> {
+    /// The first module that exports `symbol`, as the linker would choose.
+    pub fn find(&self, symbol: &str) -> Option<&'a Module> {
+        self.find_all(symbol).first().copied()
+    }
+
+    /// Every module that exports `symbol`, in the order they appear in the
+    /// [LIB].
+    pub fn find_all(&self, symbol: &str) -> &[&'a Module] {
+        self.modules.get(symbol).map_or(&[], Vec::as_slice)
+    }
+
+    /// The symbols exported by more than one module, sorted.
+    pub fn duplicates(&self) -> Vec<(&str, &[&'a Module])> {
+        let mut duplicates = self
+            .modules
+            .iter()
+            .filter(|(_, modules)| modules.len() > 1)
+            .map(|(symbol, modules)| (symbol.as_str(), modules.as_slice()))
+            .collect::<Vec<_>>();
+        duplicates.sort_by_key(|(symbol, _)| *symbol);
+        duplicates
+    }
+
+    /// The number of symbols indexed.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
 }
 
 impl fmt::Display for LIB {
@@ -131,15 +740,21 @@ impl fmt::Display for LIB {
 
 impl display::DisplayWithOptions for LIB {
     fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
-        writeln!(f, "Module     Date     Time   Externals defined")?;
+        writeln!(f, "{}", labels::LIB_HEADER)?;
         writeln!(f)?;
         for module in &self.objs {
             module.fmt_with_options(f, options)?;
             writeln!(f)?;
 
             if options.recursive {
+                let name = module.name();
+                if let Some(observer) = &options.observer {
+                    observer.module(&name);
+                }
+                let mut options = options.indent();
+                options.module = Some(name);
                 writeln!(f)?;
-                module.obj.fmt_with_options(f, &options.indent())?;
+                module.obj.fmt_with_options(f, &options)?;
                 writeln!(f)?;
             }
         }
@@ -158,11 +773,15 @@ impl display::DisplayWithOptions for LIB {
 /// |--------|--------|------------------------------------------------------------------|
 /// | 0      | `u8`   | Size of the symbol name.                                         |
 /// | 0      | `[u8]` | The ASCII name of of the exported symbol. Not `NULL` terminated. |
+///
+/// The name of a [common](Export::is_common) symbol starts with a `NUL`,
+/// which listings show as `*`.
 #[binrw]
 #[brw(little)]
 #[repr(C)]
 #[derive(Clone, PartialEq)]
 pub struct Export {
+    #[bw(assert(name.len() == *name_size as usize, "Export name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
     #[br(count = name_size)]
     name: Vec<u8>,
@@ -172,14 +791,41 @@ pub struct Export {
 ///
 /// The export table is terminated by an export with a zero-length name.
 impl Export {
-    pub fn new(name: String) -> Self {
+    /// Creates an export of `name`. A `name` starting with `*`, as
+    /// [name](Self::name) returns them, is a [common](Self::common) symbol.
+    /// It is an error for `name` to be longer than 255 bytes or to contain a
+    /// `NUL`.
+    pub fn new(name: String) -> Result<Self> {
+        match name.strip_prefix('*') {
+            Some(symbol) => Self::common(symbol),
+            None => Self::defined(&name),
+        }
+    }
+
+    /// Creates an export of `name` as it is, even if it starts with `*`.
+    fn defined(name: &str) -> Result<Self> {
         // TODO: should this restrict to ascii?
-        let mut utf8 = name.as_bytes().to_vec();
-        utf8.truncate(u8::MAX.into());
-        Self {
+        let name = name_bytes(name)?;
+        Ok(Self {
             name_size: name.len() as u8,
-            name: utf8,
-        }
+            name,
+        })
+    }
+
+    /// Creates an export of the common symbol `name`, which is stored after
+    /// a `NUL`. It is an error for `name` to be longer than 254 bytes or to
+    /// contain a `NUL`.
+    pub fn common(name: &str) -> Result<Self> {
+        let name = [&[0], name_bytes(name)?.as_slice()].concat();
+        let Ok(name_size) = u8::try_from(name.len()) else {
+            return Err(Error::InvalidName(format!(
+                "{} is {} bytes long; common names are at most {} bytes",
+                &name[1..].escape_ascii(),
+                name.len() - 1,
+                u8::MAX - 1
+            )));
+        };
+        Ok(Self { name_size, name })
     }
 
     pub fn empty() -> Self {
@@ -192,14 +838,28 @@ impl Export {
     /// Returns the name of this exported symbol.
     ///
     /// Non-UTF-8 characters are replaced with the Unicode replacement character (�)
+    /// A [common](Self::is_common) symbol's name starts with `*`.
     pub fn name(&self) -> String {
-        // TODO: what are * prefixed symbols for?
-        if !self.name.is_empty() && self.name[0] == 0 {
-            format!("*{}", String::from_utf8_lossy(&self.name[1..]).into_owned())
+        if self.is_common() {
+            format!("*{}", self.symbol_name())
         } else {
-            String::from_utf8_lossy(&self.name).into_owned()
+            self.symbol_name()
         }
     }
+
+    /// Returns the name of this exported symbol without the `*` of a
+    /// [common](Self::is_common) symbol.
+    pub fn symbol_name(&self) -> String {
+        let name = self.name.strip_prefix(&[0]).unwrap_or(&self.name);
+        String::from_utf8_lossy(name).into_owned()
+    }
+
+    /// Returns `true` if this is a common symbol, one that is declared by
+    /// an [XBSS] rather than defined by an [XDEF]. `PSYLIB.EXE` lists them
+    /// after a `NUL`, and any number of modules may declare the same one.
+    pub fn is_common(&self) -> bool {
+        self.name.first() == Some(&0)
+    }
 }
 
 /// Trait for converting PSY-Q timestamps to standard Rust date/time types.
@@ -330,18 +990,45 @@ impl FromPSYQTimestamp for SystemTime {
 /// can be retrieved by querying the [OBJ] directly using [OBJ::exports].
 #[binrw]
 #[brw(little)]
+#[br(import(max_exports: Option<usize>))]
 #[repr(C)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ModuleMetadata {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::module_name"))]
     name: [u8; 8],
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::timestamp"))]
     created: u32,
     offset: u32,
     size: u32,
 
-    #[br(parse_with=until(|e: &Export| e.name_size == 0))]
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::exports"))]
+    #[br(parse_with = parse_exports, args(max_exports.unwrap_or(ModuleMetadata::MAX_EXPORTS)))]
     exports: Vec<Export>,
 }
 
+/// Reads exports through the zero-length one that ends them. It is an
+/// error for more than `max` to come before it.
+#[binrw::parser(reader, endian)]
+fn parse_exports(max: usize) -> binrw::BinResult<Vec<Export>> {
+    let mut exports = Vec::new();
+    loop {
+        let pos = reader.stream_position()?;
+        let export = <Export as binrw::BinRead>::read_options(reader, endian, ())?;
+        if export.name_size == 0 {
+            exports.push(export);
+            return Ok(exports);
+        }
+        if exports.len() == max {
+            return Err(binrw::Error::AssertFail {
+                pos,
+                message: format!("module lists more than {max} exports"),
+            });
+        }
+        exports.push(export);
+    }
+}
+
 #[inline]
 fn string_to_module_name(name: &str) -> [u8; 8] {
     let mut module_name: [u8; 8] = [0x20; 8];
@@ -379,10 +1066,29 @@ fn string_to_module_name(name: &str) -> [u8; 8] {
 /// with only the bytes that represent full code points.
 #[inline]
 fn path_to_module_name(path: &Path) -> [u8; 8] {
+    path_to_module_name_with_case(path, NameCase::Upper)
+}
+
+/// How the name of a module added from a file is derived from the file
+/// name. See [assembler::NamePolicy].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameCase {
+    /// Uppercase ASCII letters, as `PSYLIB.EXE` does.
+    #[default]
+    Upper,
+    /// Keep the case of the file name, e.g. `foo.obj` is added as `foo`.
+    Preserve,
+}
+
+/// Like [path_to_module_name], but only uppercased with [NameCase::Upper].
+fn path_to_module_name_with_case(path: &Path, case: NameCase) -> [u8; 8] {
     let Some(prefix) = path.file_prefix() else {
         panic!("Module paths must contain a file name: {:?}", path);
     };
-    let binding = prefix.to_ascii_uppercase();
+    let binding = match case {
+        NameCase::Upper => prefix.to_ascii_uppercase(),
+        NameCase::Preserve => prefix.to_os_string(),
+    };
 
     if !prefix.is_ascii() {
         let Some(prefix_str) = binding.to_str() else {
@@ -401,58 +1107,116 @@ fn path_to_module_name(path: &Path) -> [u8; 8] {
 }
 
 impl ModuleMetadata {
-    pub fn new(name: String, created: SystemTime, size: u32, exports: Vec<Export>) -> Self {
+    /// The most exports a module's metadata may list when it is read,
+    /// unless [io::ReadOptions::max_exports] says otherwise. The largest
+    /// modules in the Psy-Q libraries list a few hundred.
+    pub const MAX_EXPORTS: usize = 8192;
+
+    /// Creates metadata for a module whose OBJ is `size` bytes.
+    ///
+    /// `exports` is terminated with an [Export::empty] unless it already
+    /// ends with one, so the export table of a parsed module can be passed
+    /// back in unchanged. A module without exports has a table containing
+    /// only the terminator and a 21 byte header. It is an error for the
+    /// module to be larger than 4 GiB with its metadata.
+    pub fn new(name: String, created: SystemTime, size: u32, exports: Vec<Export>) -> Result<Self> {
         let name = string_to_module_name(&name);
         let created = created.to_psyq_timestamp();
         let mut exports = exports;
-        exports.push(Export::empty());
+        if exports.last().is_none_or(|e| e.name_size != 0) {
+            exports.push(Export::empty());
+        }
 
-        let offset: u32 = 20 + exports.iter().map(|e| 1 + e.name_size as u32).sum::<u32>();
-        Self {
+        let mut metadata = Self {
             name,
             created,
-            offset,
-            size: offset + size,
+            offset: 0,
+            size,
             exports,
+        };
+        metadata.offset = metadata.expected_offset();
+        let Some(size) = metadata.size.checked_add(metadata.offset) else {
+            bail!("module {} is too large", metadata.name());
+        };
+        metadata.size = size;
+        Ok(metadata)
+    }
+
+    /// Returns this metadata with its creation timestamp set to `created`.
+    /// Timestamps are stored to the nearest 2 seconds.
+    pub fn with_created(mut self, created: SystemTime) -> Self {
+        self.created = created.to_psyq_timestamp();
+        self
+    }
+
+    /// Sets the module name to `name` in upper case, as `PSYLIB.EXE` names
+    /// modules. Names longer than 8 bytes are cut after the last whole
+    /// grapheme cluster that fits. It is an error for `name` to be empty or
+    /// contain whitespace, which couldn't be read back.
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(Error::InvalidName(format!(
+                "{name:?} is not a valid module name"
+            )));
         }
+        self.name = string_to_module_name(&name.to_uppercase());
+        Ok(())
+    }
+
+    /// The size of this metadata on disk, which is what the stored offset
+    /// should be: 20 bytes of fixed fields followed by the export table,
+    /// including its terminator.
+    pub(crate) fn expected_offset(&self) -> u32 {
+        let terminated = self.exports.last().is_some_and(|e| e.name_size == 0);
+        20 + self
+            .exports
+            .iter()
+            .map(|e| 1 + e.name_size as u32)
+            .sum::<u32>()
+            + if terminated { 0 } else { 1 }
     }
 
     pub fn new_from_path(path: &Path, obj: &OBJ) -> Result<Self> {
+        let created = fs::metadata(path)?
+            .created()
+            .unwrap_or_else(|_| SystemTime::now());
+        Self::new_from_path_with_time(path, obj, created)
+    }
+
+    /// Creates metadata for `obj`, read from the file at `path`, like
+    /// [new_from_path](Self::new_from_path), but created at `created`
+    /// rather than when the file was. See [from_obj](Self::from_obj).
+    pub fn new_from_path_with_time(path: &Path, obj: &OBJ, created: SystemTime) -> Result<Self> {
         let name = path_to_module_name(path);
+        let name =
+            String::from_utf8(name.to_vec()).map_err(|e| Error::InvalidName(e.to_string()))?;
+        Self::from_obj(&name, created, obj)
+    }
 
-        let file_metadata = fs::metadata(path)?;
-        let created = if let Ok(creation_time) = file_metadata.created() {
-            creation_time
-        } else {
-            SystemTime::now()
+    /// Creates metadata for a module named `name` holding `obj`, exporting
+    /// the symbols `obj` defines as
+    /// [`PSYLIB.EXE` lists them](OBJ::exports_psylib_order). The size is
+    /// that of `obj` as it will be written, which leaves out any
+    /// [trailing bytes](OBJ::trailing_bytes) of the file it was read from,
+    /// so no file is needed.
+    pub fn from_obj(name: &str, created: SystemTime, obj: &OBJ) -> Result<Self> {
+        let Ok(size) = u32::try_from(io::serialized_size(obj)?) else {
+            bail!("module {name} is too large");
         };
-        let exports = obj
-            .exports()
-            .into_iter()
-            .map(Export::new)
-            .collect::<Vec<Export>>();
-
-        let size = file_metadata.len() as u32;
-
-        Ok(Self::new(
-            String::from_utf8(name.to_vec())?,
-            created,
-            size,
-            exports,
-        ))
+        Self::new(name.to_string(), created, size, obj.exports_psylib_order()?)
     }
 
     /// Returns the module name, with trailing whitespace removed.
     ///
-    /// Names will be at most 8-ASCII characters long (or 8 UTF-8 bytes).
+    /// Names will be at most 8-ASCII characters long (or 8 UTF-8 bytes). A
+    /// name that is only whitespace, as found in damaged LIBs, is empty.
     pub fn name(&self) -> String {
         // trim_end for the name array
         let end = self
             .name
             .iter()
             .rposition(|x| !x.is_ascii_whitespace())
-            .expect("Module.name trim_end")
-            + 1;
+            .map_or(0, |i| i + 1);
         String::from_utf8_lossy(&self.name[..end]).into_owned()
     }
 
@@ -474,13 +1238,21 @@ impl ModuleMetadata {
 
     /// Returns the creation timestamp as a formatted string.
     ///
-    /// Format: `DD-MM-YY HH:MM:SS`
+    /// Format: `DD-MM-YY HH:MM:SS`, or the raw value in hex if it isn't a
+    /// valid date. See [created_with_format](Self::created_with_format) for
+    /// other formats.
     ///
     /// # Example
     /// ```text
     /// 15-05-96 16:09:38
     /// ```
     pub fn created(&self) -> String {
+        self.created_with_format(&display::DateFormat::default())
+    }
+
+    /// Returns the creation timestamp written in `format`, or the raw value
+    /// in hex if it isn't a valid date.
+    pub fn created_with_format(&self, format: &display::DateFormat) -> String {
         // 15-05-96 16:09:38
         //    hhhh hmmm mmms ssss yyyy yyyM MMMd dddd
         // LE 1000 0001 0011 0011 0010 0000 1010 1111
@@ -493,10 +1265,10 @@ impl ModuleMetadata {
         // second - 38 00010
 
         // format!("{} {}", self.date(), self.time())
-        self.created_datetime()
-            .expect("created")
-            .format("%d-%m-%y %H:%M:%S")
-            .to_string()
+        match self.created_datetime() {
+            Some(created) => format.format(&created),
+            None => format!("0x{:08x}", self.created),
+        }
     }
 
     /// Returns the creation timestamp as a `NaiveDateTime`.
@@ -517,6 +1289,31 @@ impl ModuleMetadata {
     }
 }
 
+/// A row of a [LIB] listing: the name, timestamp, and exports.
+impl fmt::Display for ModuleMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_options(f, &display::Options::default())
+    }
+}
+
+impl display::DisplayWithOptions for ModuleMetadata {
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:<17} ",
+            match self.name() {
+                name if name.is_empty() => labels::UNNAMED_MODULE.into(),
+                name => name,
+            },
+            self.created_with_format(&options.date_format),
+        )?;
+        for export in self.exports.iter().filter(|e| !e.name.is_empty()) {
+            write!(f, "{} ", export.name())?;
+        }
+        Ok(())
+    }
+}
+
 /// A module entry in a LIB archive.
 ///
 /// Each module consists of metadata (name, timestamp, exports) and the
@@ -533,26 +1330,65 @@ impl ModuleMetadata {
 /// instead.
 #[binrw]
 #[brw(little)]
+#[br(import(defer_code: bool, max_exports: Option<usize>, lenient: bool))]
 #[repr(C)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Module {
+    #[br(args(max_exports))]
     metadata: ModuleMetadata,
+    #[br(args(defer_code, lenient))]
     obj: OBJ,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = ReadOffsets(None))]
+    #[bw(ignore)]
+    offset: ReadOffsets<Option<u64>>,
 }
 
 impl Module {
     /// Create a new [Module] programmatically
     pub fn new(obj: OBJ, metadata: ModuleMetadata) -> Self {
-        Self { metadata, obj }
+        Self {
+            metadata,
+            obj,
+            offset: ReadOffsets::default(),
+        }
     }
 
     /// Creates a new [Module] from the file at `path`.
     ///
-    /// `path` must point to a valid [OBJ] file.
+    /// `path` must point to a valid [OBJ] file. The module's timestamp is
+    /// the file's creation time. See [with_created](Self::with_created).
     pub fn new_from_path(path: &Path) -> Result<Self> {
         let obj = io::read_obj(path)?;
         let metadata = ModuleMetadata::new_from_path(path, &obj)?;
-        Ok(Self { metadata, obj })
+        Ok(Self::new(obj, metadata))
+    }
+
+    /// Creates a new [Module] from the file at `path` whose timestamp is
+    /// `created`, so the same OBJ always makes the same module. Timestamps
+    /// are rounded down to an even second.
+    pub fn new_from_path_with_time(path: &Path, created: SystemTime) -> Result<Self> {
+        let obj = io::read_obj(path)?;
+        let metadata = ModuleMetadata::new_from_path_with_time(path, &obj, created)?;
+        Ok(Self::new(obj, metadata))
+    }
+
+    /// Creates a new [Module] named `name` from `obj`, which needn't have
+    /// been read from a file. See [ModuleMetadata::from_obj].
+    pub fn from_obj(name: &str, created: SystemTime, obj: OBJ) -> Result<Self> {
+        let metadata = ModuleMetadata::from_obj(name, created, &obj)?;
+        Ok(Self::new(obj, metadata))
+    }
+
+    /// Returns this module with its creation timestamp set to `created`.
+    /// See [ModuleMetadata::with_created].
+    pub fn with_created(self, created: SystemTime) -> Self {
+        Self {
+            metadata: self.metadata.with_created(created),
+            ..self
+        }
     }
 
     /// Returns the module name.
@@ -560,6 +1396,11 @@ impl Module {
         self.metadata.name()
     }
 
+    /// Renames the module. See [ModuleMetadata::set_name].
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        self.metadata.set_name(name)
+    }
+
     /// Returns the list of exported symbol names.
     pub fn exports(&self) -> Vec<String> {
         self.metadata.exports()
@@ -575,6 +1416,12 @@ impl Module {
         self.metadata.created_at()
     }
 
+    /// The offset of this module's metadata in the stream its [LIB] was read
+    /// from, or `None` for a module that wasn't read from a [LIB].
+    pub fn offset(&self) -> Option<u64> {
+        self.offset.0
+    }
+
     /// Returns the creation timestamp as a `NaiveDateTime`
     pub fn created_datetime(&self) -> Option<NaiveDateTime> {
         self.metadata.created_datetime()
@@ -584,6 +1431,77 @@ impl Module {
     pub fn object(&self) -> &OBJ {
         &self.obj
     }
+
+    /// Returns a reference to the module's metadata.
+    pub fn metadata(&self) -> &ModuleMetadata {
+        &self.metadata
+    }
+
+    /// Returns the [fingerprint](OBJ::fingerprint) of the module's OBJ. The
+    /// module metadata is not included. It is an error for the OBJ to have
+    /// [deferred](Code::deferred) code.
+    pub fn fingerprint(&self) -> Result<u32> {
+        self.obj.fingerprint()
+    }
+
+    /// Returns an id derived from the module's OBJ and exports which is
+    /// independent of its name, timestamp, and position in a LIB. See
+    /// [ident] for the derivation. It is an error for the OBJ to have
+    /// [deferred](Code::deferred) code.
+    pub fn stable_id(&self) -> Result<ident::StableId> {
+        ident::module_id(
+            &self.obj,
+            self.metadata.exports.iter().map(|e| e.name.as_slice()),
+        )
+    }
+
+    /// Returns a reference to each symbol exported by this module. It is an
+    /// error for the OBJ to have [deferred](Code::deferred) code.
+    pub fn symbols(&self) -> Result<Vec<ident::SymbolRef>> {
+        let id = self.stable_id()?;
+        Ok(self
+            .metadata
+            .exports
+            .iter()
+            .filter(|e| !e.name.is_empty())
+            .map(|e| ident::SymbolRef::new(id, &e.name))
+            .collect())
+    }
+
+    /// Overwrites the code at `offset_within_symbol` bytes from the start of
+    /// `symbol` with `bytes`, using the default [poke::Options]. See
+    /// [patch_code_with](Self::patch_code_with).
+    pub fn patch_code(
+        &mut self,
+        symbol: &str,
+        offset_within_symbol: u32,
+        bytes: &[u8],
+    ) -> Result<poke::PatchedRange> {
+        self.patch_code_with(
+            symbol,
+            offset_within_symbol,
+            bytes,
+            &poke::Options::default(),
+        )
+    }
+
+    /// Overwrites the code at `offset_within_symbol` bytes from the start of
+    /// `symbol` with `bytes`.
+    ///
+    /// `symbol` may be exported or local. The patch must end before the next
+    /// symbol in the same section, or the end of the section's code if there
+    /// isn't one. Unless [allow_reloc_overlap](poke::Options::allow_reloc_overlap)
+    /// is set, a patch touching a word with a relocation fails with a
+    /// [poke::RelocConflict]. The module's size and metadata are unchanged.
+    pub fn patch_code_with(
+        &mut self,
+        symbol: &str,
+        offset_within_symbol: u32,
+        bytes: &[u8],
+        options: &poke::Options,
+    ) -> Result<poke::PatchedRange> {
+        poke::patch_code(&mut self.obj, symbol, offset_within_symbol, bytes, options)
+    }
 }
 
 impl fmt::Display for Module {
@@ -593,18 +1511,18 @@ impl fmt::Display for Module {
 }
 
 impl display::DisplayWithOptions for Module {
-    fn fmt_with_options(&self, f: &mut fmt::Formatter, _options: &display::Options) -> fmt::Result {
-        write!(
-            f,
-            "{:<8} {} {}",
-            self.name(),
-            self.created(),
-            self.exports()
-                .into_iter()
-                .map(|e| format!("{e} "))
-                .collect::<Vec<_>>()
-                .join("")
-        )
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        self.metadata.fmt_with_options(f, options)?;
+        if options.show_fingerprints {
+            display::write_fingerprint(f, self.fingerprint())?;
+        }
+        if options.show_stable_ids {
+            match self.stable_id() {
+                Ok(id) => write!(f, " [id:{id}]")?,
+                Err(_) => write!(f, " [id:unread]")?,
+            }
+        }
+        Ok(())
     }
 }
 
@@ -638,14 +1556,49 @@ impl fmt::Debug for Module {
 /// If a parsed and hydrated [OBJ] is needed, use [Module] instead.
 #[binrw]
 #[brw(little)]
+#[br(import(max_exports: Option<usize>))]
 #[repr(C)]
 pub struct OpaqueModule {
+    #[br(args(max_exports))]
     metadata: ModuleMetadata,
 
-    #[br(count = metadata.size - 16)]
+    #[br(parse_with = parse_obj_blob, args(&metadata))]
     obj: Vec<u8>,
 }
 
+/// Reads the OBJ of a module with `metadata`, whose size is checked
+/// against its offset and the rest of the stream before anything is
+/// allocated.
+#[binrw::parser(reader)]
+fn parse_obj_blob(metadata: &ModuleMetadata) -> binrw::BinResult<Vec<u8>> {
+    let pos = reader.stream_position()?;
+    let Some(size) = metadata.size.checked_sub(metadata.offset) else {
+        return Err(binrw::Error::AssertFail {
+            pos,
+            message: format!(
+                "module {} is smaller than its metadata ({} < {})",
+                metadata.name(),
+                metadata.size,
+                metadata.offset
+            ),
+        });
+    };
+    let remaining = reader.seek(binrw::io::SeekFrom::End(0))? - pos;
+    reader.seek(binrw::io::SeekFrom::Start(pos))?;
+    if u64::from(size) > remaining {
+        return Err(binrw::Error::AssertFail {
+            pos,
+            message: format!(
+                "module {} declares {size} bytes of OBJ but only {remaining} remain",
+                metadata.name()
+            ),
+        });
+    }
+    let mut obj = vec![0; size as usize];
+    reader.read_exact(&mut obj)?;
+    Ok(obj)
+}
+
 impl OpaqueModule {
     /// Returns the module name.
     pub fn name(&self) -> String {
@@ -676,6 +1629,75 @@ impl OpaqueModule {
     pub fn obj_blob(&self) -> &[u8] {
         &self.obj
     }
+
+    /// Returns the module's metadata.
+    pub fn metadata(&self) -> &ModuleMetadata {
+        &self.metadata
+    }
+
+    /// Parses the [OBJ] this module wraps.
+    pub fn parse_obj(&self) -> Result<OBJ> {
+        Ok(OBJ::read(&mut Cursor::new(&self.obj))?)
+    }
+}
+
+impl fmt::Display for OpaqueModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.metadata)
+    }
+}
+
+/// A [LIB] whose modules haven't been parsed.
+///
+/// Reading an `OpaqueLIB` only parses the metadata of each module, which is
+/// much faster than reading a [LIB] when only module names, timestamps, and
+/// exports are needed. See [io::read_opaque_lib]. Individual modules can be
+/// parsed with [OpaqueModule::parse_obj].
+///
+/// Like [io::read_lib_metadata], modules are found using the sizes their
+/// metadata declares. See [SizeMismatch].
+#[binrw]
+#[brw(little, magic = b"LIB")]
+#[br(import(max_exports: Option<usize>))]
+#[repr(C)]
+pub struct OpaqueLIB {
+    version: u8,
+
+    #[br(parse_with = until_eof, args(max_exports))]
+    modules: Vec<OpaqueModule>,
+}
+
+impl OpaqueLIB {
+    /// Returns the archive format version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the modules of this library, in order.
+    pub fn modules(&self) -> &Vec<OpaqueModule> {
+        &self.modules
+    }
+}
+
+/// The module table of a [LIB] listing. Only the metadata is available, so
+/// [recursive](display::Options::recursive) listings aren't supported.
+impl fmt::Display for OpaqueLIB {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_options(f, &display::Options::default())
+    }
+}
+
+/// Only the module table; the modules aren't parsed.
+impl display::DisplayWithOptions for OpaqueLIB {
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        writeln!(f, "{}", labels::LIB_HEADER)?;
+        writeln!(f)?;
+        for module in &self.modules {
+            module.metadata.fmt_with_options(f, options)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 /// A PSY-Q object file (LNK format).
@@ -709,28 +1731,112 @@ impl OpaqueModule {
 /// ```
 #[binrw]
 #[brw(little, magic = b"LNK")]
+#[br(import(defer_code: bool, lenient: bool))]
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OBJ {
     version: u8,
-    #[br(parse_with=until(|section: &Section| matches!(section, Section::NOP)))]
+
+    #[br(temp, parse_with = parse_sections, args(defer_code, lenient))]
+    #[bw(ignore)]
+    parsed: (Vec<Section>, Vec<u64>),
+
+    #[br(calc = parsed.0)]
     sections: Vec<Section>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = ReadOffsets(parsed.1))]
+    #[bw(ignore)]
+    section_offsets: ReadOffsets<Vec<u64>>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = Vec::new())]
+    #[bw(ignore)]
+    trailing: Vec<u8>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(calc = version_warning(FileKind::OBJ, version, OBJ::VERSION).into_iter().collect())]
+    #[bw(ignore)]
+    warnings: Vec<ParseWarning>,
 }
 
 impl OBJ {
+    /// The LNK format version written by `ASPSX.EXE` and `CCPSX.EXE`.
+    pub const VERSION: u8 = 2;
+
     pub fn new(sections: Vec<Section>) -> Self {
+        Self::with_version(Self::VERSION, sections)
+    }
+
+    /// Creates a new [OBJ] with the provided sections that is written with
+    /// LNK format `version` instead of [VERSION](Self::VERSION).
+    pub fn with_version(version: u8, sections: Vec<Section>) -> Self {
         assert!(matches!(sections.last(), Some(Section::NOP)));
         Self {
-            version: 2,
+            version,
             sections,
+            section_offsets: ReadOffsets::default(),
+            trailing: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
-    /// Returns the OBJ format version (typically 2).
+    /// Returns the OBJ format version (typically [VERSION](Self::VERSION)).
     pub fn version(&self) -> u8 {
         self.version
     }
 
+    /// The offset of each of [sections](Self::sections) in the stream this
+    /// OBJ was read from. The offsets of an OBJ read from a [LIB] are those
+    /// in the [LIB]. Empty for an OBJ that wasn't read from a stream.
+    pub fn section_offsets(&self) -> &[u64] {
+        &self.section_offsets.0
+    }
+
+    /// Anomalies found while reading this object file that didn't prevent
+    /// it from being read: an unknown LNK version, sections in a
+    /// [census::UNTESTED_SECTIONS] format, sections with unknown tags read
+    /// [leniently](io::ReadOptions::lenient), and
+    /// [trailing bytes](Self::trailing_bytes). Always empty for an [OBJ]
+    /// that wasn't read from a file.
+    ///
+    /// Reading with [io::ReadOptions::strict_versions] fails on unknown
+    /// versions instead.
+    pub fn warnings(&self) -> Vec<ParseWarning> {
+        let untested = self
+            .sections
+            .iter()
+            .zip(self.section_offsets())
+            .filter(|(section, _)| census::UNTESTED_SECTIONS.contains(&section.kind()))
+            .map(|(section, &offset)| ParseWarning::UnimplementedSection {
+                magic: section.tag(),
+                offset,
+            });
+        let unknown =
+            self.sections
+                .iter()
+                .zip(self.section_offsets())
+                .filter_map(|(section, &offset)| match section {
+                    Section::Unknown(magic, bytes) => Some(ParseWarning::UnknownSection {
+                        magic: *magic,
+                        offset,
+                        skipped_bytes: bytes.len() as u64 + 1,
+                    }),
+                    _ => None,
+                });
+        let trailing = (!self.trailing.is_empty()).then_some(ParseWarning::TrailingBytes {
+            count: self.trailing.len(),
+        });
+        self.warnings
+            .iter()
+            .cloned()
+            .chain(untested)
+            .chain(unknown)
+            .chain(trailing)
+            .collect()
+    }
+
     /// Returns the sections contained in this object file.
     ///
     /// Sections include code, data, symbols, relocations, and debug info.
@@ -739,6 +1845,146 @@ impl OBJ {
         &self.sections
     }
 
+    /// Returns the bytes following the [NOP](Section::NOP) which ends this
+    /// object file.
+    ///
+    /// The PSY-Q tools stop reading at the first `NOP`, so anything after
+    /// it is ignored. `PSX/UTILITY/MENU/CDSFILE.OBJ` from the 3.3 SDK has a
+    /// stray `NOP` followed by one more XDEF, which `DUMPOBJ.EXE` doesn't
+    /// list either. These bytes are only kept by [io::read_obj] and
+    /// [io::read], and only written by [io::write_obj_with_options].
+    pub fn trailing_bytes(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    /// Returns the processor type of the first [CPU](Section::CPU) section,
+    /// or `None` if there isn't one.
+    pub fn cpu(&self) -> Option<CpuType> {
+        self.sections.iter().find_map(|section| match section {
+            Section::CPU(cpu) => Some(*cpu),
+            _ => None,
+        })
+    }
+
+    /// Returns the code sections of this object file, in order.
+    pub fn iter_code(&self) -> impl Iterator<Item = &Code> {
+        self.sections.iter().filter_map(|section| match section {
+            Section::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    /// Returns the number of bytes of code in this object file, adding up
+    /// every [Code] section, including consecutive runs of one section's
+    /// code and code that was [deferred](Code::deferred).
+    pub fn total_code_len(&self) -> usize {
+        self.iter_code().map(|code| code.size as usize).sum()
+    }
+
+    /// Returns the patch sections of this object file, in order, so their
+    /// offsets and expressions can be changed before it is written again.
+    pub fn patches_mut(&mut self) -> impl Iterator<Item = &mut Patch> {
+        self.sections
+            .iter_mut()
+            .filter_map(|section| match section {
+                Section::Patch(patch) => Some(patch),
+                _ => None,
+            })
+    }
+
+    /// Returns the patches applied to each code section, by the index of the
+    /// code section, as shown in
+    /// [annotated disassembly](display::CodeFormat::AnnotatedDisassembly).
+    /// A patch applies to the most recent code in its section.
+    fn code_patches(&self) -> HashMap<usize, Vec<(u32, String)>> {
+        let mut current = 0;
+        let mut last_code = HashMap::new();
+        let mut patches: HashMap<usize, Vec<(u32, String)>> = HashMap::new();
+        for (i, section) in self.sections.iter().enumerate() {
+            match section {
+                Section::SectionSwitch(id) => current = *id,
+                Section::Code(_) => {
+                    last_code.insert(current, i);
+                }
+                Section::Patch(patch) => {
+                    if let Some(code) = last_code.get(&current) {
+                        patches.entry(*code).or_default().push((
+                            patch.offset.into(),
+                            format!("patch {}: {}", patch.tag, patch.expression),
+                        ));
+                    }
+                }
+                _ => (),
+            }
+        }
+        patches
+    }
+
+    /// Returns every symbol this object file defines or references. See
+    /// [symtab] for details.
+    pub fn symbols(&self) -> symtab::SymbolTable {
+        symtab::SymbolTable::of_obj(self)
+    }
+
+    /// Returns every symbol and section this object file's patches refer
+    /// to. See [xref] for details.
+    pub fn references(&self) -> Vec<xref::Reference> {
+        xref::references(self)
+    }
+
+    /// Returns this object file as assembly source. See [asm] for details.
+    pub fn to_asm(&self, options: &asm::AsmOptions) -> Result<String> {
+        asm::to_asm(self, options)
+    }
+
+    /// Returns the exports a [LIB] lists for this object file, in the same
+    /// order as [exports](Self::exports). Symbols declared by an [XBSS] are
+    /// [common](Export::is_common). It is an error for the name of a common
+    /// symbol to be 255 bytes long, which can't be listed.
+    pub fn exports_detailed(&self) -> Result<Vec<Export>> {
+        self.sections()
+            .iter()
+            .filter_map(|section| match section {
+                Section::XDEF(xdef) => Some(Export::defined(&xdef.symbol_name())),
+                Section::XBSS(xbss) => Some(Export::common(&xbss.name())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the exports `PSYLIB.EXE` lists for this object file, in the
+    /// order it lists them. [Module]s created from an OBJ list these, so an
+    /// archive rebuilt from its extracted OBJs has the same export tables.
+    ///
+    /// `PSYLIB.EXE` doesn't list symbols in the order the OBJ defines them,
+    /// but by symbol number, which the assembler assigns as symbols are
+    /// declared. An XBSS declared before an XDEF is listed first even if
+    /// the XDEF comes first in the OBJ. A name defined more than once is
+    /// only listed the first time. [exports_detailed](Self::exports_detailed)
+    /// lists the same exports in the order the OBJ defines them.
+    pub fn exports_psylib_order(&self) -> Result<Vec<Export>> {
+        let mut exports = self
+            .sections()
+            .iter()
+            .filter_map(|section| match section {
+                Section::XDEF(xdef) => {
+                    Some(Export::defined(&xdef.symbol_name()).map(|export| (xdef.number(), export)))
+                }
+                Section::XBSS(xbss) => {
+                    Some(Export::common(&xbss.name()).map(|export| (xbss.number(), export)))
+                }
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+        exports.sort_by_key(|(number, _)| *number);
+        let mut listed = HashSet::new();
+        Ok(exports
+            .into_iter()
+            .map(|(_, export)| export)
+            .filter(|export| listed.insert(export.symbol_name()))
+            .collect())
+    }
+
     /// Returns symbols exported by this object file.
     ///
     /// Exported symbols can be functions or globals.
@@ -754,6 +2000,292 @@ impl OBJ {
             })
             .collect()
     }
+
+    /// Returns the ids of the sections which hold only uninitialized data,
+    /// with the bytes each reserves. Sections named `.bss` or `.sbss` are
+    /// uninitialized, as are sections with [Section::BSS] but no code. XDEFs
+    /// in them are [common](DefinitionStrength::Common).
+    pub(crate) fn uninitialized_sections(&self) -> HashMap<u16, u32> {
+        let mut names = HashMap::new();
+        let mut code = HashSet::new();
+        let mut bss = HashMap::new();
+        let mut current = 0;
+        for section in &self.sections {
+            match section {
+                Section::LNKHeader(header) => {
+                    names.insert(header.section, header.type_name());
+                }
+                Section::SectionSwitch(id) => current = *id,
+                Section::Code(_) => {
+                    code.insert(current);
+                }
+                Section::BSS(size) => *bss.entry(current).or_insert(0) += size,
+                _ => (),
+            }
+        }
+        let is_bss = |id: &u16| match names.get(id).map(String::as_str) {
+            Some(".bss" | ".sbss") => true,
+            Some(_) | None => bss.contains_key(id) && !code.contains(id),
+        };
+        names
+            .keys()
+            .chain(bss.keys())
+            .filter(|id| is_bss(id))
+            .map(|id| (*id, bss.get(id).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// Returns the symbols defined by this object file and how strongly
+    /// each is defined.
+    ///
+    /// XBSS symbols and XDEFs in a section which holds only uninitialized
+    /// data are [common](DefinitionStrength::Common). Their size runs to the
+    /// next symbol in the section or the end of the section.
+    pub fn definitions(&self) -> Vec<Definition> {
+        let bss = self.uninitialized_sections();
+        let xdefs = self
+            .sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::XDEF(xdef) => Some(xdef),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        self.sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::XDEF(xdef) if bss.contains_key(&xdef.section) => {
+                    let end = xdefs
+                        .iter()
+                        .filter(|x| x.section == xdef.section && x.offset > xdef.offset)
+                        .map(|x| x.offset)
+                        .min()
+                        .unwrap_or(bss[&xdef.section]);
+                    Some(Definition {
+                        name: xdef.symbol_name(),
+                        strength: DefinitionStrength::Common,
+                        size: end.saturating_sub(xdef.offset),
+                    })
+                }
+                Section::XDEF(xdef) => Some(Definition {
+                    name: xdef.symbol_name(),
+                    strength: DefinitionStrength::Strong,
+                    size: 0,
+                }),
+                Section::XBSS(xbss) => Some(Definition {
+                    name: xbss.name(),
+                    strength: DefinitionStrength::Common,
+                    size: xbss.size,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the source files referenced by line number information.
+    ///
+    /// Line information can associate code with a source file through either
+    /// the legacy [Section::SetToFile] directive or the source line debugger
+    /// (SLD) [Section::SetSLDLineNumFile] directive. Both use file ids defined
+    /// by [Section::Filename]. Each file appears once, in the order it is
+    /// first referenced, regardless of which mechanism referenced it.
+    pub fn file_references(&self) -> Vec<FileRef> {
+        let names = self
+            .sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::Filename(filename) => Some((filename.number, filename.name())),
+                _ => None,
+            })
+            .collect::<HashMap<u16, String>>();
+
+        let mut refs: Vec<FileRef> = Vec::new();
+        for section in &self.sections {
+            let (id, mechanism, offset) = match section {
+                Section::SetToFile(file, _) => (*file, LineInfoMechanism::Legacy, None),
+                Section::SetSLDLineNumFile(line) => {
+                    (line.file, LineInfoMechanism::SLD, Some(line.offset as u32))
+                }
+                _ => continue,
+            };
+
+            if let Some(file_ref) = refs.iter_mut().find(|r| r.id == id) {
+                if file_ref.mechanism != mechanism {
+                    file_ref.mechanism = LineInfoMechanism::Mixed;
+                }
+                file_ref.first_offset = file_ref.first_offset.or(offset);
+                continue;
+            }
+
+            let file = match names.get(&id) {
+                Some(name) => FileIdOrName::Name(name.clone()),
+                None => FileIdOrName::Id(id),
+            };
+            refs.push(FileRef {
+                id,
+                mechanism,
+                file,
+                first_offset: offset,
+            });
+        }
+        refs
+    }
+
+    /// Resolves the line number information of this object into the source
+    /// location of each offset. See [lines] for details.
+    pub fn line_map(&self) -> Result<lines::LineMap> {
+        lines::LineMap::of_obj(self)
+    }
+
+    /// Returns the functions described by this object's debug information.
+    /// Starts and ends which can't be paired are reported by
+    /// [functions::Functions::of_obj].
+    pub fn functions(&self) -> Vec<functions::FunctionInfo> {
+        functions::Functions::of_obj(self).functions
+    }
+
+    /// Reads any [deferred](Code::deferred) code from `reader`, the stream
+    /// this object was read from.
+    pub fn load_code(&mut self, reader: &mut (impl Read + Seek)) -> Result<()> {
+        for section in &mut self.sections {
+            if let Section::Code(code) = section {
+                code.load(reader)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the ids of the sections whose type is `name`, like `.text`,
+    /// in the order their [LNKHeader]s appear.
+    pub fn section_ids(&self, name: &str) -> Vec<u16> {
+        self.sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::LNKHeader(header) if header.type_name() == name => Some(header.section()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the bytes this object contributes to the section
+    /// `section_id`, without any [Patch]es applied. [Code] is added in
+    /// order and [BSS](Section::BSS) as zeros, so offsets into the section
+    /// are offsets into the bytes. External BSS isn't included. It is an
+    /// error for the section's code to have been [deferred](Code::deferred).
+    pub fn section_bytes(&self, section_id: u16) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut current = None;
+        for section in &self.sections {
+            match section {
+                Section::SectionSwitch(id) => current = Some(*id),
+                Section::Code(code) if current == Some(section_id) => {
+                    bytes.extend_from_slice(code.code()?)
+                }
+                Section::BSS(size) if current == Some(section_id) => {
+                    bytes.resize(bytes.len() + *size as usize, 0)
+                }
+                _ => (),
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Adds up the bytes of this object's sections by section type. See
+    /// [sizes].
+    pub fn section_sizes(&self) -> sizes::SectionSizes {
+        sizes::SectionSizes::of_obj(self)
+    }
+
+    /// Counts the sections and expressions of this object. See [census].
+    pub fn format_census(&self) -> census::FormatCensus {
+        census::FormatCensus::of_obj(self)
+    }
+
+    /// Checks this object for problems. See [verify] for details.
+    pub fn verify(&self, options: &verify::Options) -> Vec<verify::Finding> {
+        verify::verify_obj(self, options)
+    }
+
+    /// Returns a CRC-32 of the entire object as it is stored on disk. See
+    /// also [Section::fingerprint]. It is an error for the object to have
+    /// [deferred](Code::deferred) code.
+    pub fn fingerprint(&self) -> Result<u32> {
+        fingerprint(self)
+    }
+
+    /// Returns the object as it is written to a file.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        io::write_obj_to(self, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// The directives used to associate line information with a source file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineInfoMechanism {
+    /// [Section::SetToFile] and the line number directives that follow it.
+    Legacy,
+    /// Source line debugger directives, [Section::SetSLDLineNumFile].
+    SLD,
+    /// The file is referenced by both mechanisms in the same object.
+    Mixed,
+}
+
+/// A source file reference, resolved to the name defined by a
+/// [Section::Filename] when one exists.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FileIdOrName {
+    Id(u16),
+    Name(String),
+}
+
+impl fmt::Display for FileIdOrName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "file number {id:x}"),
+            Self::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+/// How a symbol is defined, which decides what the linker does when more
+/// than one module defines it.
+///
+/// A strong definition replaces common definitions of the same symbol, and
+/// common definitions are merged into one the size of the largest. Two
+/// strong definitions are an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DefinitionStrength {
+    /// Defined in code or initialized data.
+    Strong,
+    /// Space reserved in uninitialized data.
+    Common,
+}
+
+/// A symbol defined by an [OBJ]. See [OBJ::definitions].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub strength: DefinitionStrength,
+    /// The bytes reserved by a common definition. Strong definitions have no
+    /// size.
+    pub size: u32,
+}
+
+/// A source file referenced by line information in an [OBJ]. See
+/// [OBJ::file_references].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRef {
+    /// The file number line information refers to the file by.
+    pub id: u16,
+    /// Which directives reference the file.
+    pub mechanism: LineInfoMechanism,
+    /// The referenced file.
+    pub file: FileIdOrName,
+    /// The code offset of the first SLD reference. Legacy references don't
+    /// include an offset.
+    pub first_offset: Option<u32>,
 }
 
 impl fmt::Display for OBJ {
@@ -765,15 +2297,174 @@ impl fmt::Display for OBJ {
 impl display::DisplayWithOptions for OBJ {
     fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
         options.write_indent(f)?;
-        writeln!(f, "Header : LNK version {}", self.version)?;
-        for section in &self.sections {
-            section.fmt_with_options(f, options)?;
+        writeln!(f, "{} {}", labels::OBJ_HEADER, self.version)?;
+        let resolution = options.names.as_ref().map(|names| {
+            let library = options.library.as_deref().unwrap_or_default();
+            let module = options.module.as_deref().unwrap_or(library);
+            names.resolve(library, module, self)
+        });
+        let annotated = options.code_format == display::CodeFormat::AnnotatedDisassembly;
+        let symbols = annotated.then(|| self.symbols());
+        let mut patches = if annotated {
+            self.code_patches()
+        } else {
+            HashMap::new()
+        };
+        let mut options = options.clone();
+        let mut current = 0;
+        let mut sizes = HashMap::new();
+        for (i, section) in self.sections.iter().enumerate() {
+            options.section = Some(i);
+            match section {
+                Section::SectionSwitch(id) => current = *id,
+                Section::CPU(cpu) => options.cpu = Some(*cpu),
+                Section::Code(code) => {
+                    let size = sizes.entry(current).or_insert(0u32);
+                    let end = *size + code.size as u32;
+                    if let Some(resolution) = &resolution {
+                        options.code_labels = resolution
+                            .in_range(current, *size, end)
+                            .map(|n| (n.offset - *size, options.overlay_name(&n.name)))
+                            .collect();
+                    }
+                    if let Some(symbols) = &symbols {
+                        options.code_offset = *size;
+                        options.code_symbols = symbols
+                            .defined()
+                            .iter()
+                            .filter(|symbol| symbol.section == current)
+                            .filter_map(|symbol| {
+                                let offset = symbol.offset?;
+                                (*size..end)
+                                    .contains(&offset)
+                                    .then(|| (offset - *size, options.symbol_name(&symbol.name)))
+                            })
+                            .collect();
+                        options.code_patches = patches.remove(&i).unwrap_or_default();
+                    }
+                    *size = end;
+                }
+                Section::BSS(bss) => *sizes.entry(current).or_insert(0) += bss,
+                _ => (),
+            }
+            if let Some(offset) = self
+                .section_offsets()
+                .get(i)
+                .filter(|_| options.show_offsets)
+            {
+                write!(f, "{offset:08x}: ")?;
+            }
+            section.fmt_with_options(f, &options)?;
             writeln!(f)?;
         }
         Ok(())
     }
 }
 
+/// Reads sections through the terminating [NOP](Section::NOP), along with
+/// the offset each starts at.
+///
+/// When `lenient`, a section whose tag isn't known is read as
+/// [Unknown](Section::Unknown), holding the bytes up to the next place a
+/// section appears to start. See [resync].
+#[binrw::parser(reader, endian)]
+fn parse_sections(defer_code: bool, lenient: bool) -> binrw::BinResult<(Vec<Section>, Vec<u64>)> {
+    let mut sections = Vec::new();
+    let mut offsets = Vec::new();
+    loop {
+        let position = reader.stream_position()?;
+        offsets.push(position);
+        let section = match <Section as binrw::BinRead>::read_options(reader, endian, (defer_code,))
+        {
+            Ok(section) => section,
+            Err(e) if lenient => {
+                reader.seek(SeekFrom::Start(position))?;
+                let magic = <u8 as binrw::BinRead>::read_options(reader, endian, ())?;
+                if is_section_tag(magic) {
+                    return Err(e);
+                }
+                let Some(next) = resync(reader, endian, defer_code, position + 1)? else {
+                    return Err(e);
+                };
+                reader.seek(SeekFrom::Start(position + 1))?;
+                let mut bytes = vec![0; (next - position - 1) as usize];
+                reader.read_exact(&mut bytes)?;
+                Section::Unknown(magic, bytes)
+            }
+            Err(e) => return Err(e),
+        };
+        let done = matches!(section, Section::NOP);
+        sections.push(section);
+        if done {
+            return Ok((sections, offsets));
+        }
+    }
+}
+
+/// Returns `true` if `magic` is the tag of a known [Section]. Every tag is
+/// even and no greater than that of [Def2](Section::Def2).
+fn is_section_tag(magic: u8) -> bool {
+    magic.is_multiple_of(2) && magic <= 84
+}
+
+/// The number of sections which must read after a place for a section to
+/// appear to start there.
+const RESYNC_SECTIONS: usize = 3;
+
+/// Returns the first offset from `start` where a section appears to start,
+/// or `None` if there isn't one. A section appears to start where it and
+/// the [RESYNC_SECTIONS] after it can be read, or where sections can be read
+/// through a [NOP](Section::NOP) which ends the stream.
+fn resync<R: Read + Seek>(
+    reader: &mut R,
+    endian: binrw::Endian,
+    defer_code: bool,
+    start: u64,
+) -> binrw::BinResult<Option<u64>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    for candidate in start..end {
+        reader.seek(SeekFrom::Start(candidate))?;
+        let magic = <u8 as binrw::BinRead>::read_options(reader, endian, ())?;
+        if !is_section_tag(magic) {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(candidate))?;
+        let mut plausible = true;
+        for _ in 0..RESYNC_SECTIONS {
+            match <Section as binrw::BinRead>::read_options(reader, endian, (defer_code,)) {
+                Ok(Section::NOP) => {
+                    plausible = reader.stream_position()? == end;
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => {
+                    plausible = false;
+                    break;
+                }
+            }
+        }
+        if plausible {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns `name` as it is stored in a section, after its one byte length.
+fn name_bytes(name: &str) -> Result<Vec<u8>> {
+    if name.len() > u8::MAX.into() {
+        return Err(Error::InvalidName(format!(
+            "{name} is {} bytes long; names are at most {} bytes",
+            name.len(),
+            u8::MAX
+        )));
+    }
+    if name.contains('\0') {
+        return Err(Error::InvalidName(format!("{name:?} contains a NUL")));
+    }
+    Ok(name.as_bytes().to_vec())
+}
+
 /// Machine code section.
 ///
 /// Contains executable instructions for the target [CPU](Section::CPU).
@@ -784,23 +2475,100 @@ impl display::DisplayWithOptions for OBJ {
 /// |--------|--------|-------------------|
 /// | 0      | `u16`  | Size of the code. |
 /// | 1      | `[u8]` | Machine code.     |
+///
+/// When read with `defer_code`, the machine code is skipped and only its
+/// position in the stream is kept. See [io::ReadOptions::skip_code_bytes].
 #[binrw]
 #[brw(little)]
+#[br(import(defer_code: bool))]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Code {
+    #[cfg_attr(feature = "serde", serde(skip))]
     size: u16,
-    #[br(count = size)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[br(parse_with = skip_code, args(size, defer_code))]
+    #[bw(ignore)]
+    deferred: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::hex"))]
+    #[br(if(deferred.is_none()), count = size)]
+    #[bw(assert(deferred.is_none(), "code at offset {:#x} was not read", deferred.unwrap_or_default()))]
+    #[bw(assert(code.len() == *size as usize, "code section is {} bytes but holds {} bytes of code", size, code.len()))]
     code: Vec<u8>,
 }
 
+/// Skips `size` bytes of code if `defer`, returning where they start.
+#[binrw::parser(reader)]
+fn skip_code(size: u16, defer: bool) -> binrw::BinResult<Option<u64>> {
+    if !defer {
+        return Ok(None);
+    }
+    let position = reader.stream_position()?;
+    reader.seek(binrw::io::SeekFrom::Current(size as i64))?;
+    Ok(Some(position))
+}
+
 impl Code {
+    /// Creates a code section. It is an error for `code` to be longer than
+    /// `u16::MAX` bytes; [Section::code_from_bytes] splits longer code
+    /// into several sections.
+    pub fn new(code: Vec<u8>) -> Result<Self> {
+        let Ok(size) = u16::try_from(code.len()) else {
+            bail!(
+                "code is {} bytes long; a section holds at most {} bytes",
+                code.len(),
+                u16::MAX
+            );
+        };
+        Ok(Self {
+            size,
+            deferred: None,
+            code,
+        })
+    }
+
+    /// Returns the size of the code as stored in the file. It is the same as
+    /// the length of [code](Self::code) for any section that was read.
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
     /// Returns the code for this section as bytes. Their format can be determined by the value
-    /// set in the [CPU](Section::CPU).
-    pub fn code(&self) -> &Vec<u8> {
-        &self.code
+    /// set in the [CPU](Section::CPU). It is an error for the code to have
+    /// been [deferred](Self::deferred) and not [loaded](Self::load).
+    pub fn code(&self) -> Result<&[u8]> {
+        if let Some(position) = self.deferred {
+            bail!("code at offset {position:#x} was not read");
+        }
+        Ok(&self.code)
+    }
+
+    /// Returns the position of the code in the stream it was read from, if
+    /// it was skipped rather than read. See [load](Self::load).
+    pub fn deferred(&self) -> Option<u64> {
+        self.deferred
+    }
+
+    /// Reads deferred code from `reader`, the stream it was skipped in.
+    /// Does nothing if the code was already read.
+    pub fn load(&mut self, reader: &mut (impl Read + Seek)) -> Result<()> {
+        let Some(position) = self.deferred else {
+            return Ok(());
+        };
+        let mut code = vec![0; self.size as usize];
+        reader.seek(std::io::SeekFrom::Start(position))?;
+        reader.read_exact(&mut code)?;
+        self.code = code;
+        self.deferred = None;
+        Ok(())
     }
 }
 
+/// How deeply [Expression]s may nest when they are read. Real objects
+/// nest a few levels at most; the limit keeps a corrupt file from
+/// exhausting the stack.
+pub const MAX_EXPRESSION_DEPTH: u16 = 64;
+
 /// An expression used in relocations.
 ///
 /// PSY-Q uses a sophisticated expression system for calculating relocated
@@ -822,7 +2590,14 @@ impl Code {
 /// - `(sectstart(1)+$100)` - Section 1 start plus 0x100
 #[binrw]
 #[brw(little)]
+#[br(import(depth: u16))]
+#[br(pre_assert(
+    depth < MAX_EXPRESSION_DEPTH,
+    "expression is nested more than {MAX_EXPRESSION_DEPTH} deep"
+))]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Expression {
     /// A constant value.
     ///
@@ -1024,7 +2799,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(32u8))]
-    Equals(Box<Expression>, Box<Expression>),
+    Equals(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Inequality comparison.
     ///
@@ -1040,7 +2818,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(34u8))]
-    NotEquals(Box<Expression>, Box<Expression>),
+    NotEquals(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Less than or equal.
     ///
@@ -1056,7 +2837,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(36u8))]
-    LTE(Box<Expression>, Box<Expression>),
+    LTE(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Less than.
     ///
@@ -1072,7 +2856,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(38u8))]
-    LessThan(Box<Expression>, Box<Expression>),
+    LessThan(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Greater than or equal.
     ///
@@ -1088,7 +2875,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(40u8))]
-    GTE(Box<Expression>, Box<Expression>),
+    GTE(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Greater than.
     ///
@@ -1104,7 +2894,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(42u8))]
-    GreaterThan(Box<Expression>, Box<Expression>),
+    GreaterThan(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     //
     // Arithmetic operators
@@ -1123,7 +2916,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(44u8))]
-    Add(Box<Expression>, Box<Expression>),
+    Add(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Subtraction.
     ///
@@ -1139,7 +2935,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(46u8))]
-    Subtract(Box<Expression>, Box<Expression>),
+    Subtract(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Multiplication.
     ///
@@ -1155,7 +2954,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(48u8))]
-    Multiply(Box<Expression>, Box<Expression>),
+    Multiply(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Division.
     ///
@@ -1171,7 +2973,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(50u8))]
-    Divide(Box<Expression>, Box<Expression>),
+    Divide(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Bitwise AND.
     ///
@@ -1187,7 +2992,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(52u8))]
-    And(Box<Expression>, Box<Expression>),
+    And(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Bitwise OR operator.
     ///
@@ -1207,7 +3015,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(54u8))]
-    Or(Box<Expression>, Box<Expression>),
+    Or(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Bitwise XOR.
     ///
@@ -1223,7 +3034,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(56u8))]
-    XOR(Box<Expression>, Box<Expression>),
+    XOR(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Left shift.
     ///
@@ -1239,7 +3053,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(58u8))]
-    LeftShift(Box<Expression>, Box<Expression>),
+    LeftShift(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Right shift.
     ///
@@ -1255,7 +3072,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(60u8))]
-    RightShift(Box<Expression>, Box<Expression>),
+    RightShift(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Modulo.
     ///
@@ -1271,7 +3091,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(62u8))]
-    Mod(Box<Expression>, Box<Expression>),
+    Mod(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Dashes operator.
     ///
@@ -1287,7 +3110,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(64u8))]
-    Dashes(Box<Expression>, Box<Expression>),
+    Dashes(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     //
     // Special operators (primarily for Saturn/SH-2)
@@ -1300,13 +3126,16 @@ pub enum Expression {
     ///
     /// # Structure on Disk
     ///
-    /// | Offset | Type   | Description |
-    /// |--------|--------|-------------|
-    /// | 0      | `u8`   | Magic: 0x42  |
+    /// | Offset                        | Type         | Description         |
+    /// |-------------------------------|--------------|---------------------|
+    /// | 0                             | `u8`         | Magic: 0x42         |
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(66u8))]
-    Revword(Box<Expression>, Box<Expression>),
+    Revword(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Check0.
     ///
@@ -1316,13 +3145,16 @@ pub enum Expression {
     ///
     /// # Structure on Disk
     ///
-    /// | Offset | Type   | Description |
-    /// |--------|--------|-------------|
-    /// | 0      | `u8`   | Magic: 0x44  |
+    /// | Offset                        | Type         | Description         |
+    /// |-------------------------------|--------------|---------------------|
+    /// | 0                             | `u8`         | Magic: 0x44         |
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(68u8))]
-    Check0(Box<Expression>, Box<Expression>),
+    Check0(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Check1.
     ///
@@ -1338,7 +3170,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(70u8))]
-    Check1(Box<Expression>, Box<Expression>),
+    Check1(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Bit range extraction.
     ///
@@ -1354,7 +3189,10 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(72u8))]
-    BitRange(Box<Expression>, Box<Expression>),
+    BitRange(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 
     /// Arithmetic shift with check.
     ///
@@ -1370,64 +3208,281 @@ pub enum Expression {
     /// | 1                             | `Expression` | Left [Expression].  |
     /// | sizeof(*left_expression*) + 1 | `Expression` | Right [Expression]. |
     #[brw(magic(74u8))]
-    ArshiftChk(Box<Expression>, Box<Expression>),
+    ArshiftChk(
+        #[br(args(depth + 1))] Box<Expression>,
+        #[br(args(depth + 1))] Box<Expression>,
+    ),
 }
 
-impl fmt::Display for Expression {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Expression {
+    /// Returns the name of this expression's variant, e.g. `"Add"`.
+    pub fn kind(&self) -> &'static str {
         match self {
-            Self::Constant(value) => write!(f, "${value:x}"),
-            Self::SymbolAddressIndex(addr) => write!(f, "[{addr:x}]"),
-            Self::SectionAddressIndex(base) => write!(f, "sectbase({base:x})"),
-            // untested
-            Self::Bank(bank) => write!(f, "bank({bank:x})"),
-            // untested
-            Self::SectionOffset(section) => write!(f, "sectof({section:x})"),
-            // untested
-            Self::Offset(bank) => write!(f, "offs({bank:x})"),
-            Self::SectionStart(offset) => write!(f, "sectstart({offset:x})"),
-            // untested
-            Self::GroupStart(group) => write!(f, "groupstart({group:x})"),
-            // untested
-            Self::GroupOffset(group) => write!(f, "groupof({group:x})"),
-            // untested
-            Self::Segment(segment) => write!(f, "seg({segment:x})"),
-            // untested
-            Self::GroupOrg(group) => write!(f, "grouporg({group:x})"),
-            Self::SectionEnd(offset) => write!(f, "sectend({offset:x})"),
-
-            // comparison
-            Self::Equals(lhs, rhs) => write!(f, "({lhs}={rhs})"),
-            Self::NotEquals(lhs, rhs) => write!(f, "({lhs}<>{rhs})"),
-            Self::LTE(lhs, rhs) => write!(f, "({lhs}<={rhs})"),
-            Self::LessThan(lhs, rhs) => write!(f, "({lhs}<{rhs})"),
-            Self::GTE(lhs, rhs) => write!(f, "({lhs}>={rhs})"),
-            Self::GreaterThan(lhs, rhs) => write!(f, "({lhs}>{rhs})"),
-
-            // arithmatic
-            Self::Add(lhs, rhs) => write!(f, "({lhs}+{rhs})"),
-            Self::Subtract(lhs, rhs) => write!(f, "({lhs}-{rhs})"),
-            Self::Multiply(lhs, rhs) => write!(f, "({lhs}*{rhs})"),
-            Self::Divide(lhs, rhs) => write!(f, "({lhs}/{rhs})",),
-            Self::And(lhs, rhs) => write!(f, "({lhs}&{rhs})"),
-            Self::Or(lhs, rhs) => write!(f, "({lhs}!{rhs})"),
-            Self::XOR(lhs, rhs) => write!(f, "({lhs}^{rhs})"),
-            Self::LeftShift(lhs, rhs) => write!(f, "({lhs}<<{rhs})"),
-            Self::RightShift(lhs, rhs) => write!(f, "({lhs}>>{rhs})"),
-            Self::Mod(lhs, rhs) => write!(f, "({lhs}%%{rhs})"),
-            Self::Dashes(lhs, rhs) => write!(f, "({lhs}---{rhs})"),
+            Self::Constant(_) => "Constant",
+            Self::SymbolAddressIndex(_) => "SymbolAddressIndex",
+            Self::SectionAddressIndex(_) => "SectionAddressIndex",
+            Self::Bank(_) => "Bank",
+            Self::SectionOffset(_) => "SectionOffset",
+            Self::Offset(_) => "Offset",
+            Self::SectionStart(_) => "SectionStart",
+            Self::GroupStart(_) => "GroupStart",
+            Self::GroupOffset(_) => "GroupOffset",
+            Self::Segment(_) => "Segment",
+            Self::GroupOrg(_) => "GroupOrg",
+            Self::SectionEnd(_) => "SectionEnd",
+            Self::Equals(..) => "Equals",
+            Self::NotEquals(..) => "NotEquals",
+            Self::LTE(..) => "LTE",
+            Self::LessThan(..) => "LessThan",
+            Self::GTE(..) => "GTE",
+            Self::GreaterThan(..) => "GreaterThan",
+            Self::Add(..) => "Add",
+            Self::Subtract(..) => "Subtract",
+            Self::Multiply(..) => "Multiply",
+            Self::Divide(..) => "Divide",
+            Self::And(..) => "And",
+            Self::Or(..) => "Or",
+            Self::XOR(..) => "XOR",
+            Self::LeftShift(..) => "LeftShift",
+            Self::RightShift(..) => "RightShift",
+            Self::Mod(..) => "Mod",
+            Self::Dashes(..) => "Dashes",
+            Self::Revword(..) => "Revword",
+            Self::Check0(..) => "Check0",
+            Self::Check1(..) => "Check1",
+            Self::BitRange(..) => "BitRange",
+            Self::ArshiftChk(..) => "ArshiftChk",
+        }
+    }
 
-            // keyword
-            Self::Revword(lhs, rhs) => write!(f, "({lhs}-revword-{rhs})"),
-            Self::Check0(lhs, rhs) => write!(f, "({lhs}-check0-{rhs})"),
-            Self::Check1(lhs, rhs) => write!(f, "({lhs}-check1-{rhs})"),
-            Self::BitRange(lhs, rhs) => write!(f, "({lhs}-bitrange-{rhs})"),
-            Self::ArshiftChk(lhs, rhs) => write!(f, "({lhs}-arshift_chk-{rhs})"),
+    /// Returns the left and right operands of a binary expression.
+    pub fn operands(&self) -> Option<(&Expression, &Expression)> {
+        match self {
+            Self::Equals(lhs, rhs)
+            | Self::NotEquals(lhs, rhs)
+            | Self::LTE(lhs, rhs)
+            | Self::LessThan(lhs, rhs)
+            | Self::GTE(lhs, rhs)
+            | Self::GreaterThan(lhs, rhs)
+            | Self::Add(lhs, rhs)
+            | Self::Subtract(lhs, rhs)
+            | Self::Multiply(lhs, rhs)
+            | Self::Divide(lhs, rhs)
+            | Self::And(lhs, rhs)
+            | Self::Or(lhs, rhs)
+            | Self::XOR(lhs, rhs)
+            | Self::LeftShift(lhs, rhs)
+            | Self::RightShift(lhs, rhs)
+            | Self::Mod(lhs, rhs)
+            | Self::Dashes(lhs, rhs)
+            | Self::Revword(lhs, rhs)
+            | Self::Check0(lhs, rhs)
+            | Self::Check1(lhs, rhs)
+            | Self::BitRange(lhs, rhs)
+            | Self::ArshiftChk(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
         }
     }
-}
 
-/// A relocation patch to be applied by the linker.
+    /// Parses an expression written as it is displayed, like
+    /// `(sectbase(2)+$b4)`. See [display::expr::parse].
+    pub fn parse(s: &str) -> Result<Self, display::expr::ParseError> {
+        display::expr::parse(s)
+    }
+
+    /// Renders this expression in `style`. See [display::expr].
+    pub fn render(&self, style: &display::expr::ExprStyle) -> String {
+        display::expr::render(self, style)
+    }
+
+    /// Computes the value of this expression with the addresses `resolver`
+    /// provides. See [eval] for details.
+    pub fn evaluate(
+        &self,
+        resolver: &impl eval::ExpressionResolver,
+    ) -> Result<u32, eval::EvalError> {
+        eval::evaluate(self, resolver)
+    }
+
+    /// Calls `visit` with this expression and then each of its operands,
+    /// depth first.
+    pub fn walk(&self, visit: &mut impl FnMut(&Expression)) {
+        visit(self);
+        if let Some((lhs, rhs)) = self.operands() {
+            lhs.walk(visit);
+            rhs.walk(visit);
+        }
+    }
+
+    /// Returns the numbers of the symbols this expression mentions, in the
+    /// order they first appear. Besides symbol addresses, this includes
+    /// the symbols of `bank`, `offs`, `seg`, and `grouporg`.
+    pub fn referenced_symbols(&self) -> Vec<u16> {
+        self.referenced(|e| match e {
+            Expression::SymbolAddressIndex(n)
+            | Expression::Bank(n)
+            | Expression::Offset(n)
+            | Expression::Segment(n)
+            | Expression::GroupOrg(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Returns the numbers of the sections this expression mentions, in
+    /// the order they first appear: those of `sectbase`, `sectof`,
+    /// `sectstart`, and `sectend`.
+    pub fn referenced_sections(&self) -> Vec<u16> {
+        self.referenced(|e| match e {
+            Expression::SectionAddressIndex(n)
+            | Expression::SectionOffset(n)
+            | Expression::SectionStart(n)
+            | Expression::SectionEnd(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    fn referenced(&self, number: impl Fn(&Expression) -> Option<u16>) -> Vec<u16> {
+        let mut numbers = Vec::new();
+        self.walk(&mut |e| {
+            if let Some(n) = number(e).filter(|n| !numbers.contains(n)) {
+                numbers.push(n);
+            }
+        });
+        numbers
+    }
+
+    /// Like [walk](Self::walk), but `visit` may change each expression.
+    /// Operands are visited after `visit` has changed their parent.
+    pub fn walk_mut(&mut self, visit: &mut impl FnMut(&mut Expression)) {
+        visit(self);
+        if let Some((lhs, rhs)) = self.operands_mut() {
+            lhs.walk_mut(visit);
+            rhs.walk_mut(visit);
+        }
+    }
+
+    /// Replaces each symbol number this expression mentions, those
+    /// [referenced_symbols](Self::referenced_symbols) returns, with `f` of
+    /// it, e.g. to point a patch at another symbol.
+    pub fn map_symbols(&mut self, mut f: impl FnMut(u16) -> u16) {
+        self.walk_mut(&mut |e| match e {
+            Expression::SymbolAddressIndex(n)
+            | Expression::Bank(n)
+            | Expression::Offset(n)
+            | Expression::Segment(n)
+            | Expression::GroupOrg(n) => *n = f(*n),
+            _ => (),
+        });
+    }
+
+    /// Replaces each [Constant](Self::Constant) in this expression with `f`
+    /// of its value, e.g. to change the addend of a patch.
+    pub fn map_constants(&mut self, mut f: impl FnMut(u32) -> u32) {
+        self.walk_mut(&mut |e| {
+            if let Expression::Constant(value) = e {
+                *value = f(*value);
+            }
+        });
+    }
+
+    fn operands_mut(&mut self) -> Option<(&mut Expression, &mut Expression)> {
+        match self {
+            Self::Equals(lhs, rhs)
+            | Self::NotEquals(lhs, rhs)
+            | Self::LTE(lhs, rhs)
+            | Self::LessThan(lhs, rhs)
+            | Self::GTE(lhs, rhs)
+            | Self::GreaterThan(lhs, rhs)
+            | Self::Add(lhs, rhs)
+            | Self::Subtract(lhs, rhs)
+            | Self::Multiply(lhs, rhs)
+            | Self::Divide(lhs, rhs)
+            | Self::And(lhs, rhs)
+            | Self::Or(lhs, rhs)
+            | Self::XOR(lhs, rhs)
+            | Self::LeftShift(lhs, rhs)
+            | Self::RightShift(lhs, rhs)
+            | Self::Mod(lhs, rhs)
+            | Self::Dashes(lhs, rhs)
+            | Self::Revword(lhs, rhs)
+            | Self::Check0(lhs, rhs)
+            | Self::Check1(lhs, rhs)
+            | Self::BitRange(lhs, rhs)
+            | Self::ArshiftChk(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = display::expr::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let function =
+            |f: &mut fmt::Formatter, name: &str, value: &u16| write!(f, "{name}({value:x})");
+        let binary = |f: &mut fmt::Formatter, lhs: &Expression, op: &str, rhs: &Expression| {
+            write!(f, "({lhs}{op}{rhs})")
+        };
+
+        match self {
+            Self::Constant(value) => write!(f, "${value:x}"),
+            Self::SymbolAddressIndex(addr) => write!(f, "[{addr:x}]"),
+            Self::SectionAddressIndex(base) => function(f, labels::SECTBASE, base),
+            // untested
+            Self::Bank(bank) => function(f, labels::BANK, bank),
+            // untested
+            Self::SectionOffset(section) => function(f, labels::SECTOF, section),
+            // untested
+            Self::Offset(bank) => function(f, labels::OFFS, bank),
+            Self::SectionStart(offset) => function(f, labels::SECTSTART, offset),
+            // untested
+            Self::GroupStart(group) => function(f, labels::GROUPSTART, group),
+            // untested
+            Self::GroupOffset(group) => function(f, labels::GROUPOF, group),
+            // untested
+            Self::Segment(segment) => function(f, labels::SEG, segment),
+            // untested
+            Self::GroupOrg(group) => function(f, labels::GROUPORG, group),
+            Self::SectionEnd(offset) => function(f, labels::SECTEND, offset),
+
+            // comparison
+            Self::Equals(lhs, rhs) => binary(f, lhs, labels::EQUALS, rhs),
+            Self::NotEquals(lhs, rhs) => binary(f, lhs, labels::NOT_EQUALS, rhs),
+            Self::LTE(lhs, rhs) => binary(f, lhs, labels::LTE, rhs),
+            Self::LessThan(lhs, rhs) => binary(f, lhs, labels::LESS_THAN, rhs),
+            Self::GTE(lhs, rhs) => binary(f, lhs, labels::GTE, rhs),
+            Self::GreaterThan(lhs, rhs) => binary(f, lhs, labels::GREATER_THAN, rhs),
+
+            // arithmatic
+            Self::Add(lhs, rhs) => binary(f, lhs, labels::ADD, rhs),
+            Self::Subtract(lhs, rhs) => binary(f, lhs, labels::SUBTRACT, rhs),
+            Self::Multiply(lhs, rhs) => binary(f, lhs, labels::MULTIPLY, rhs),
+            Self::Divide(lhs, rhs) => binary(f, lhs, labels::DIVIDE, rhs),
+            Self::And(lhs, rhs) => binary(f, lhs, labels::AND, rhs),
+            Self::Or(lhs, rhs) => binary(f, lhs, labels::OR, rhs),
+            Self::XOR(lhs, rhs) => binary(f, lhs, labels::XOR, rhs),
+            Self::LeftShift(lhs, rhs) => binary(f, lhs, labels::LEFT_SHIFT, rhs),
+            Self::RightShift(lhs, rhs) => binary(f, lhs, labels::RIGHT_SHIFT, rhs),
+            Self::Mod(lhs, rhs) => binary(f, lhs, labels::MOD, rhs),
+            Self::Dashes(lhs, rhs) => binary(f, lhs, labels::DASHES, rhs),
+
+            // keyword
+            Self::Revword(lhs, rhs) => binary(f, lhs, labels::REVWORD, rhs),
+            Self::Check0(lhs, rhs) => binary(f, lhs, labels::CHECK0, rhs),
+            Self::Check1(lhs, rhs) => binary(f, lhs, labels::CHECK1, rhs),
+            Self::BitRange(lhs, rhs) => binary(f, lhs, labels::BIT_RANGE, rhs),
+            Self::ArshiftChk(lhs, rhs) => binary(f, lhs, labels::ARSHIFT_CHK, rhs),
+        }
+    }
+}
+
+/// A relocation patch to be applied by the linker.
 ///
 /// Patches modify code or data at a specific offset using a calculated expression.
 ///
@@ -1436,13 +3491,19 @@ impl fmt::Display for Expression {
 /// | Tag | Description                                              | Expression             |
 /// |-----|----------------------------------------------------------|------------------------|
 /// | 8   | Write 32-bit expression value (big-endian?)              | ``                     |
-/// | 10  | Unknown                                                  |                        |
+/// | 10  | SH-2 PC-relative displacement (see below)                | `($2-arshift_chk-...)` |
 /// | 16  | Write 32-bit expression value (little-endian?)           | ``                     |
 /// | 30  | Possibly related to register allocation.                 |                        |
 /// | 74  | Function symbol relocation (24-bit, little-endian).      | `[14]`                 |
 /// | 82  | Copy expression high 16-bytes into instruction low bytes | `($20+sectbase(f001))` |
 /// | 84  | Copy expression low 16-bytes into instruction low bytes  | `($20+sectbase(f001))` |
 ///
+/// Tag 10 is found in the Saturn `LIBSN.LIB`, at odd offsets within SH-2
+/// code. Its expression shifts the distance from the instruction to a
+/// longword-aligned target right by 2 with `-arshift_chk-`, as the 8-bit
+/// displacement of a `mov.l @(disp,pc)` would be. Which bits it writes
+/// hasn't been confirmed, so [reloc] can't apply it.
+///
 /// # Structure on Disk
 ///
 /// | Offset | Type         | Description                                              |
@@ -1453,6 +3514,7 @@ impl fmt::Display for Expression {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Patch {
     /// The type of patch (determines how the expression value is applied).
     tag: u8,
@@ -1462,6 +3524,54 @@ pub struct Patch {
     expression: Expression,
 }
 
+impl Patch {
+    /// Creates a patch applying `expression` at `offset` in the current
+    /// section.
+    pub fn new(tag: u8, offset: u16, expression: Expression) -> Self {
+        Self {
+            tag,
+            offset,
+            expression,
+        }
+    }
+
+    /// Returns the type of patch.
+    pub fn tag(&self) -> u8 {
+        self.tag
+    }
+
+    /// Returns the offset of the patch from the start of the preceding
+    /// code in the current section.
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the expression which calculates the patched value.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Returns the expression which calculates the patched value, to be
+    /// changed. See [Expression::map_symbols] and
+    /// [Expression::map_constants].
+    pub fn expression_mut(&mut self) -> &mut Expression {
+        &mut self.expression
+    }
+
+    /// Moves the patch to `offset` from the start of the preceding code.
+    pub fn set_offset(&mut self, offset: u16) {
+        self.offset = offset;
+    }
+
+    /// Writes `value`, usually the value of the [expression](Self::expression),
+    /// into `code` as this patch's tag describes. `code` starts at the
+    /// preceding code in the patch's section. See [reloc] for the tags that
+    /// can be applied.
+    pub fn apply(&self, code: &mut [u8], value: u32) -> Result<(), reloc::PatchError> {
+        reloc::apply(self, code, value)
+    }
+}
+
 /// Section header information.
 ///
 /// Defines properties of a section such as its group, alignment, and type name.
@@ -1478,17 +3588,49 @@ pub struct Patch {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LNKHeader {
     section: u16,
     group: u16,
     align: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(type_name.len() == *type_name_size as usize, "LNKHeader name is {} bytes but its size is {}", type_name.len(), type_name_size))]
     type_name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = type_name_size)]
     type_name: Vec<u8>,
 }
 
 impl LNKHeader {
+    /// Creates a section header. It is an error for `type_name` to be longer
+    /// than 255 bytes.
+    pub fn new(section: u16, group: u16, align: u8, type_name: &str) -> Result<Self> {
+        let type_name = name_bytes(type_name)?;
+        Ok(Self {
+            section,
+            group,
+            align,
+            type_name_size: type_name.len() as u8,
+            type_name,
+        })
+    }
+
+    /// Returns the ID used to refer to this section.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the group this section belongs to.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the alignment of the section in bytes.
+    pub fn align(&self) -> u8 {
+        self.align
+    }
+
     /// Returns the section type name (e.g., ".text", ".data", ".bss").
     pub fn type_name(&self) -> String {
         String::from_utf8_lossy(&self.type_name).into_owned()
@@ -1523,16 +3665,42 @@ impl fmt::Debug for LNKHeader {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LocalSymbol {
     section: u16,
     offset: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "LocalSymbol name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>,
 }
 
 impl LocalSymbol {
+    /// Creates a local symbol. It is an error for `name` to be longer than
+    /// 255 bytes.
+    pub fn new(section: u16, offset: u32, name: &str) -> Result<Self> {
+        let name = name_bytes(name)?;
+        Ok(Self {
+            section,
+            offset,
+            name_size: name.len() as u8,
+            name,
+        })
+    }
+
+    /// Returns the ID of the section the symbol is in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the symbol within its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1565,16 +3733,42 @@ impl fmt::Debug for LocalSymbol {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GroupSymbol {
     number: u16,
     sym_type: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "GroupSymbol name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>,
 }
 
 impl GroupSymbol {
+    /// Creates a group symbol. It is an error for `name` to be longer than
+    /// 255 bytes.
+    pub fn new(number: u16, sym_type: u8, name: &str) -> Result<Self> {
+        let name = name_bytes(name)?;
+        Ok(Self {
+            number,
+            sym_type,
+            name_size: name.len() as u8,
+            name,
+        })
+    }
+
+    /// Returns the number the group is referred to by.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the type of the symbol. Its meaning is not known.
+    pub fn sym_type(&self) -> u8 {
+        self.sym_type
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1609,17 +3803,49 @@ impl fmt::Debug for GroupSymbol {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct XDEF {
     number: u16,
     section: u16,
     offset: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(symbol_name.len() == *symbol_name_size as usize, "XDEF name is {} bytes but its size is {}", symbol_name.len(), symbol_name_size))]
     symbol_name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = symbol_name_size)]
     symbol_name: Vec<u8>,
 }
 
 impl XDEF {
+    /// Creates an exported symbol at `offset` in `section`. It is an error
+    /// for `name` to be longer than 255 bytes.
+    pub fn new(number: u16, section: u16, offset: u32, name: &str) -> Result<Self> {
+        let symbol_name = name_bytes(name)?;
+        Ok(Self {
+            number,
+            section,
+            offset,
+            symbol_name_size: symbol_name.len() as u8,
+            symbol_name,
+        })
+    }
+
+    /// Returns the number patches refer to the symbol by.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the ID of the section the symbol is defined in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the offset of the symbol within its section.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
     pub fn symbol_name(&self) -> String {
         // TODO: can a starred symbol be here as well?
         String::from_utf8_lossy(&self.symbol_name).into_owned()
@@ -1641,15 +3867,35 @@ impl XDEF {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct XREF {
     number: u16,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(symbol_name.len() == *symbol_name_size as usize, "XREF name is {} bytes but its size is {}", symbol_name.len(), symbol_name_size))]
     symbol_name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = symbol_name_size)]
     symbol_name: Vec<u8>,
 }
 
 impl XREF {
+    /// Creates a reference to a symbol defined elsewhere. It is an error for
+    /// `name` to be longer than 255 bytes.
+    pub fn new(number: u16, name: &str) -> Result<Self> {
+        let symbol_name = name_bytes(name)?;
+        Ok(Self {
+            number,
+            symbol_name_size: symbol_name.len() as u8,
+            symbol_name,
+        })
+    }
+
+    /// Returns the number patches refer to the symbol by.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
     pub fn symbol_name(&self) -> String {
         String::from_utf8_lossy(&self.symbol_name).into_owned()
     }
@@ -1667,14 +3913,29 @@ impl XREF {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Filename {
     number: u16,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *size as usize, "Filename is {} bytes but its size is {}", name.len(), size))]
     size: u8,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = size)]
     name: Vec<u8>,
 }
 
 impl Filename {
+    /// Creates a file name for line information to refer to by `number`. It
+    /// is an error for `name` to be longer than 255 bytes.
+    pub fn new(number: u16, name: &str) -> Result<Self> {
+        let name = name_bytes(name)?;
+        Ok(Self {
+            number,
+            size: name.len() as u8,
+            name,
+        })
+    }
+
     pub fn number(&self) -> u16 {
         self.number
     }
@@ -1706,6 +3967,7 @@ impl fmt::Debug for Filename {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SetMXInfo {
     offset: u16,
     value: u8,
@@ -1725,17 +3987,49 @@ pub struct SetMXInfo {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct XBSS {
     number: u16,
     section: u16,
     size: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "XBSS name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
 
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>,
 }
 
 impl XBSS {
+    /// Creates an uninitialized symbol of `size` bytes in `section`. It is an
+    /// error for `name` to be longer than 255 bytes.
+    pub fn new(number: u16, section: u16, size: u32, name: &str) -> Result<Self> {
+        let name = name_bytes(name)?;
+        Ok(Self {
+            number,
+            section,
+            size,
+            name_size: name.len() as u8,
+            name,
+        })
+    }
+
+    /// Returns the number patches refer to the symbol by.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Returns the ID of the section the symbol is allocated in.
+    pub fn section(&self) -> u16 {
+        self.section
+    }
+
+    /// Returns the size of the symbol in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
@@ -1752,6 +4046,7 @@ impl XBSS {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SetSLDLineNum {
     offset: u16,
     linenum: u32,
@@ -1769,6 +4064,7 @@ pub struct SetSLDLineNum {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SetSLDLineNumFile {
     offset: u16,
     linenum: u32,
@@ -1780,6 +4076,7 @@ pub struct SetSLDLineNumFile {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ProcedureCall {
     distance: u8,
     symbol: u16,
@@ -1790,6 +4087,7 @@ pub struct ProcedureCall {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ProcedureDefinition {
     symbol: u16,
 }
@@ -1816,6 +4114,7 @@ pub struct ProcedureDefinition {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FunctionStart {
     section: u16,
     offset: u32,
@@ -1827,7 +4126,10 @@ pub struct FunctionStart {
     mask: u32,
     mask_offset: i32,
 
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "FunctionStart name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>,
 }
@@ -1851,6 +4153,7 @@ impl FunctionStart {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SectionOffsetLine {
     section: u16,
     offset: u32,
@@ -1873,13 +4176,17 @@ pub struct SectionOffsetLine {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Def {
     section: u16,
     value: u32,
     class: u16,
     def_type: u16,
     size: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "Def name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>,
 }
@@ -1889,12 +4196,30 @@ impl Def {
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
+
+    /// Returns the kind of symbol defined.
+    pub fn storage_class(&self) -> types::StorageClass {
+        types::StorageClass::from_u16(self.class)
+    }
+
+    /// Returns the type of the symbol, unpacked.
+    pub fn type_desc(&self) -> types::TypeDesc {
+        types::TypeDesc::from_u16(self.def_type)
+    }
+
+    /// Returns the type of the symbol in C, like `int ()`. See
+    /// [TypeDesc::describe](types::TypeDesc::describe).
+    pub fn describe(&self) -> String {
+        self.type_desc().describe("", &[])
+    }
 }
 
 /// Dimension specification for arrays.
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Dim {
     /// No dimensions (scalar).
     ///
@@ -1946,6 +4271,7 @@ impl fmt::Display for Dim {
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Def2 {
     section: u16,
     value: u32,
@@ -1953,10 +4279,16 @@ pub struct Def2 {
     def_type: u16, // 34 00
     size: u32,     // 04 00 00 00
     dims: Dim,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(tag.len() == *tag_size as usize, "Def2 tag is {} bytes but its size is {}", tag.len(), tag_size))]
     tag_size: u8,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = tag_size)]
     tag: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[bw(assert(name.len() == *name_size as usize, "Def2 name is {} bytes but its size is {}", name.len(), name_size))]
     name_size: u8, // 06
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::lossy_string"))]
     #[br(count = name_size)]
     name: Vec<u8>, // 75 5F 63 68 61 72
 }
@@ -1969,6 +4301,32 @@ impl Def2 {
     pub fn name(&self) -> String {
         String::from_utf8_lossy(&self.name).into_owned()
     }
+
+    /// Returns the kind of symbol defined.
+    pub fn storage_class(&self) -> types::StorageClass {
+        types::StorageClass::from_u16(self.class)
+    }
+
+    /// Returns the type of the symbol, unpacked.
+    pub fn type_desc(&self) -> types::TypeDesc {
+        types::TypeDesc::from_u16(self.def_type)
+    }
+
+    /// Returns the type of the symbol in C, like `unsigned char [4]`, named
+    /// by its tag and sized by its dimensions. The end of a structure,
+    /// `.eos`, has no type of its own and is described as the structure it
+    /// ends.
+    pub fn describe(&self) -> String {
+        let tag = self.tag();
+        if self.storage_class() == types::StorageClass::EndOfStruct && !tag.is_empty() {
+            return format!("struct {tag}");
+        }
+        let dims = match self.dims {
+            Dim::None => vec![],
+            Dim::Value(size) => vec![size],
+        };
+        self.type_desc().describe(&tag, &dims)
+    }
 }
 
 pub mod cputype {
@@ -2003,13 +4361,113 @@ pub mod cputype {
 
     /// Hitachi SH-2 - Sega Saturn.
     pub const HITACHI_SH2: u8 = 8;
-}
 
-fn unimplemented(s: &str) -> bool {
-    eprintln!("Unimplemented: {s}");
-    false
+    use std::fmt;
+
+    /// A processor type as it is stored in a [CPU](super::Section::CPU)
+    /// section. Identifiers without a constant in this module are kept as
+    /// [Unknown](CpuType::Unknown).
+    ///
+    /// ```
+    /// use psyk::cputype::{self, CpuType};
+    ///
+    /// assert_eq!(CpuType::MipsR3000, CpuType::from(cputype::MIPS_R3000));
+    /// assert_eq!("MIPS R3000", CpuType::MipsR3000.to_string());
+    /// assert_eq!(42, u8::from(CpuType::Unknown(42)));
+    /// ```
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize), serde(into = "u8"))]
+    pub enum CpuType {
+        Motorola68000,
+        Motorola68010,
+        Motorola68020,
+        Motorola68030,
+        Motorola68040,
+        Wdc65816,
+        ZilogZ80,
+        MipsR3000,
+        HitachiSh2,
+        Unknown(u8),
+    }
+
+    impl CpuType {
+        /// Returns the name of the processor, or `None` if it is
+        /// [Unknown](Self::Unknown).
+        pub fn name(&self) -> Option<&'static str> {
+            Some(match self {
+                Self::Motorola68000 => "Motorola 68000",
+                Self::Motorola68010 => "Motorola 68010",
+                Self::Motorola68020 => "Motorola 68020",
+                Self::Motorola68030 => "Motorola 68030",
+                Self::Motorola68040 => "Motorola 68040",
+                Self::Wdc65816 => "WDC 65816",
+                Self::ZilogZ80 => "Zilog Z80",
+                Self::MipsR3000 => "MIPS R3000",
+                Self::HitachiSh2 => "Hitachi SH-2",
+                Self::Unknown(_) => return None,
+            })
+        }
+
+        /// Returns `true` for the Motorola 68000 family.
+        pub fn is_68k(&self) -> bool {
+            matches!(
+                self,
+                Self::Motorola68000
+                    | Self::Motorola68010
+                    | Self::Motorola68020
+                    | Self::Motorola68030
+                    | Self::Motorola68040
+            )
+        }
+    }
+
+    impl From<u8> for CpuType {
+        fn from(cpu: u8) -> Self {
+            match cpu {
+                MOTOROLA_68000 => Self::Motorola68000,
+                MOTOROLA_68010 => Self::Motorola68010,
+                MOTOROLA_68020 => Self::Motorola68020,
+                MOTOROLA_68030 => Self::Motorola68030,
+                MOTOROLA_68040 => Self::Motorola68040,
+                WDC_65816 => Self::Wdc65816,
+                ZILOG_Z80 => Self::ZilogZ80,
+                MIPS_R3000 => Self::MipsR3000,
+                HITACHI_SH2 => Self::HitachiSh2,
+                cpu => Self::Unknown(cpu),
+            }
+        }
+    }
+
+    impl From<CpuType> for u8 {
+        fn from(cpu: CpuType) -> Self {
+            match cpu {
+                CpuType::Motorola68000 => MOTOROLA_68000,
+                CpuType::Motorola68010 => MOTOROLA_68010,
+                CpuType::Motorola68020 => MOTOROLA_68020,
+                CpuType::Motorola68030 => MOTOROLA_68030,
+                CpuType::Motorola68040 => MOTOROLA_68040,
+                CpuType::Wdc65816 => WDC_65816,
+                CpuType::ZilogZ80 => ZILOG_Z80,
+                CpuType::MipsR3000 => MIPS_R3000,
+                CpuType::HitachiSh2 => HITACHI_SH2,
+                CpuType::Unknown(cpu) => cpu,
+            }
+        }
+    }
+
+    /// The processor's name, or its identifier if it is unknown.
+    impl fmt::Display for CpuType {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.name() {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "processor type {}", u8::from(*self)),
+            }
+        }
+    }
 }
 
+pub use cputype::CpuType;
+
 /// A section within an OBJ file.
 ///
 /// Sections can contain code, data, relocations, symbols, or debug information.
@@ -2025,7 +4483,10 @@ fn unimplemented(s: &str) -> bool {
 /// - And many more!
 #[binrw]
 #[brw(little)]
+#[br(import(defer_code: bool))]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Section {
     /// End of file marker.
     ///
@@ -2046,7 +4507,7 @@ pub enum Section {
     /// | 0      | `u8`   | Magic: 0x2         |
     /// | 1      | `Code` | A [Code] structure |
     #[brw(magic(2u8))]
-    Code(Code),
+    Code(#[br(args(defer_code))] Code),
 
     /// Run at offset.
     ///
@@ -2055,8 +4516,10 @@ pub enum Section {
     /// | Offset | Type  | Description       |
     /// |--------|-------|-------------------|
     /// | 0      | `u8`  | Magic: 0x4        |
-    /// | 1      | `u16` |                   |
-    /// | 3      | `u16` |                   |
+    /// | 1      | `u16` | Section ID.       |
+    /// | 3      | `u16` | Offset.           |
+    ///
+    /// Untested. The order of the fields follows the `DUMPOBJ` output.
     #[brw(magic(4u8))]
     RunAtOffset(u16, u16),
 
@@ -2149,14 +4612,35 @@ pub enum Section {
     GroupSymbol(GroupSymbol),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description      |
+    /// |--------|-------|------------------|
+    /// | 0      | `u8`  | Magic: 0x16      |
+    /// | 1      | `u16` | Register offset. |
     #[brw(magic(22u8))]
     ByteSizeRegister(u16),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description      |
+    /// |--------|-------|------------------|
+    /// | 0      | `u8`  | Magic: 0x18      |
+    /// | 1      | `u16` | Register offset. |
     #[brw(magic(24u8))]
     WordSizeRegister(u16),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description      |
+    /// |--------|-------|------------------|
+    /// | 0      | `u8`  | Magic: 0x1A      |
+    /// | 1      | `u16` | Register offset. |
     #[brw(magic(26u8))]
     LongSizeRegister(u16),
 
@@ -2171,7 +4655,19 @@ pub enum Section {
     #[brw(magic(28u8))]
     Filename(Filename),
 
-    /// Untested
+    /// Associates the following line information with a source file.
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description                           |
+    /// |--------|-------|---------------------------------------|
+    /// | 0      | `u8`  | Magic: 0x1E                           |
+    /// | 1      | `u16` | File number defined by [Filename].    |
+    /// | 3      | `u32` | Line number.                          |
+    ///
+    /// The field widths match the `DUMPOBJ` output, but haven't been
+    /// confirmed against an object containing this directive: none of the
+    /// captured SDK listings include one.
     #[brw(magic(30u8))]
     SetToFile(u16, u32),
 
@@ -2196,6 +4692,13 @@ pub enum Section {
     VeryLocalSymbol(LocalSymbol),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description      |
+    /// |--------|-------|------------------|
+    /// | 0      | `u8`  | Magic: 0x2A      |
+    /// | 1      | `u16` | Register offset. |
     #[brw(magic(42u8))]
     Set3ByteRegister(u16),
 
@@ -2221,7 +4724,11 @@ pub enum Section {
     ///
     /// Constants for CPU identifiers can be found in the [cputype] module.
     #[brw(magic(46u8))]
-    CPU(u8),
+    CPU(
+        #[br(map = |cpu: u8| CpuType::from(cpu))]
+        #[bw(map = |cpu: &CpuType| u8::from(*cpu))]
+        CpuType,
+    ),
 
     /// External BSS symbol.
     ///
@@ -2316,26 +4823,65 @@ pub enum Section {
     EndSLDInfo(u16),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description   |
+    /// |--------|-------|---------------|
+    /// | 0      | `u8`  | Magic: 0x3E   |
+    /// | 1      | `u32` | Repeat count. |
+    ///
+    /// `DUMPOBJ` shows only the count of the repeat directives. Nothing
+    /// follows the count, so the byte, word, long, or 3-byte unit being
+    /// repeated isn't part of the record, and how the linker finds it hasn't
+    /// been confirmed. The sections read and write as they are laid out here.
     #[brw(magic(62u8))]
     RepeatByte(u32),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description   |
+    /// |--------|-------|---------------|
+    /// | 0      | `u8`  | Magic: 0x40   |
+    /// | 1      | `u32` | Repeat count. |
+    ///
+    /// See [RepeatByte](Self::RepeatByte) for what is known of the data.
     #[brw(magic(64u8))]
     RepeatWord(u32),
 
     /// Untested
-    #[brw(magic(66u8), assert(unimplemented("ProcedureCall")))]
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description   |
+    /// |--------|-------|---------------|
+    /// | 0      | `u8`  | Magic: 0x42   |
+    /// | 1      | `u32` | Repeat count. |
+    ///
+    /// See [RepeatByte](Self::RepeatByte) for what is known of the data.
+    #[brw(magic(66u8))]
     RepeatLong(u32),
 
     /// Untested
-    #[brw(magic(68u8), assert(unimplemented("ProcedureCall")))]
+    #[brw(magic(68u8))]
     ProcedureCall(ProcedureCall),
 
     /// Untested
-    #[brw(magic(70u8), assert(unimplemented("ProcedureDefinition")))]
+    #[brw(magic(70u8))]
     ProcedureDefinition(ProcedureDefinition),
 
     /// Untested
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type  | Description   |
+    /// |--------|-------|---------------|
+    /// | 0      | `u8`  | Magic: 0x48   |
+    /// | 1      | `u32` | Repeat count. |
+    ///
+    /// See [RepeatByte](Self::RepeatByte) for what is known of the data.
     #[brw(magic(72u8))]
     Repeat3Byte(u32),
 
@@ -2410,102 +4956,462 @@ pub enum Section {
     /// | 1      | `Def2` | A [Def2] structure. |
     #[brw(magic(84u8))]
     Def2(Def2),
+
+    /// A section whose tag isn't known, with the bytes that follow it up to
+    /// where the next section appears to start. Only read
+    /// [leniently](io::ReadOptions::lenient), and written back as it was
+    /// read.
+    ///
+    /// # Structure on Disk
+    ///
+    /// | Offset | Type   | Description          |
+    /// |--------|--------|----------------------|
+    /// | 0      | `u8`   | Magic: any unknown   |
+    /// | 1      | `[u8]` | The skipped bytes.   |
+    #[br(pre_assert(false))]
+    Unknown(u8, #[br(count = 0)] Vec<u8>),
 }
 
-/// Returns true if the LC_ALL or LANG environment variable indicates British English.
-fn is_en_gb() -> bool {
-    let lang = if let Ok(l) = std::env::var("LC_ALL") {
-        l
-    } else if let Ok(l) = std::env::var("LANG") {
-        l
-    } else {
-        "".to_string()
-    };
+/// Computes a CRC-32 over the serialized form of `value`. Writing fails
+/// only for [deferred](Code::deferred) code and malformed sections.
+fn fingerprint(value: &impl for<'a> BinWrite<Args<'a> = ()>) -> Result<u32> {
+    let mut writer = NoSeek::new(Crc32::new());
+    value.write_le(&mut writer)?;
+    Ok(writer.into_inner().finish())
+}
+
+impl Section {
+    /// Returns `bytes` as [Code] sections of at most `u16::MAX` bytes each,
+    /// which is how code too long for one section is stored. Empty `bytes`
+    /// are no sections.
+    pub fn code_from_bytes(bytes: &[u8]) -> Vec<Section> {
+        bytes
+            .chunks(u16::MAX as usize)
+            .map(|chunk| {
+                Section::Code(Code {
+                    size: chunk.len() as u16,
+                    deferred: None,
+                    code: chunk.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a CRC-32 of this section as it is stored on disk, including
+    /// its tag. Comparing fingerprints is a quick way to find which sections
+    /// differ between two objects. It is an error for the section to be
+    /// [deferred](Code::deferred) code.
+    pub fn fingerprint(&self) -> Result<u32> {
+        fingerprint(self)
+    }
+
+    /// Returns the name of this section's variant, e.g. `"Code"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NOP => "NOP",
+            Self::Code(_) => "Code",
+            Self::RunAtOffset(..) => "RunAtOffset",
+            Self::SectionSwitch(_) => "SectionSwitch",
+            Self::BSS(_) => "BSS",
+            Self::Patch(_) => "Patch",
+            Self::XDEF(_) => "XDEF",
+            Self::XREF(_) => "XREF",
+            Self::LNKHeader(_) => "LNKHeader",
+            Self::LocalSymbol(_) => "LocalSymbol",
+            Self::GroupSymbol(_) => "GroupSymbol",
+            Self::ByteSizeRegister(_) => "ByteSizeRegister",
+            Self::WordSizeRegister(_) => "WordSizeRegister",
+            Self::LongSizeRegister(_) => "LongSizeRegister",
+            Self::Filename(_) => "Filename",
+            Self::SetToFile(..) => "SetToFile",
+            Self::SetToLine(_) => "SetToLine",
+            Self::IncrementLineNumber => "IncrementLineNumber",
+            Self::IncrementLineNumberByte(_) => "IncrementLineNumberByte",
+            Self::IncrementLineNumberWord(_) => "IncrementLineNumberWord",
+            Self::VeryLocalSymbol(_) => "VeryLocalSymbol",
+            Self::Set3ByteRegister(_) => "Set3ByteRegister",
+            Self::SetMXInfo(_) => "SetMXInfo",
+            Self::CPU(_) => "CPU",
+            Self::XBSS(_) => "XBSS",
+            Self::IncSLDLineNum(_) => "IncSLDLineNum",
+            Self::IncSLDLineNumByte(..) => "IncSLDLineNumByte",
+            Self::IncSLDLineNumWord(..) => "IncSLDLineNumWord",
+            Self::SetSLDLineNum(_) => "SetSLDLineNum",
+            Self::SetSLDLineNumFile(_) => "SetSLDLineNumFile",
+            Self::EndSLDInfo(_) => "EndSLDInfo",
+            Self::RepeatByte(_) => "RepeatByte",
+            Self::RepeatWord(_) => "RepeatWord",
+            Self::RepeatLong(_) => "RepeatLong",
+            Self::ProcedureCall(_) => "ProcedureCall",
+            Self::ProcedureDefinition(_) => "ProcedureDefinition",
+            Self::Repeat3Byte(_) => "Repeat3Byte",
+            Self::FunctionStart(_) => "FunctionStart",
+            Self::FunctionEnd(_) => "FunctionEnd",
+            Self::BlockStart(_) => "BlockStart",
+            Self::BlockEnd(_) => "BlockEnd",
+            Self::Def(_) => "Def",
+            Self::Def2(_) => "Def2",
+            Self::Unknown(..) => "Unknown",
+        }
+    }
+    /// Returns the tag byte which precedes this section on disk, e.g. `2`
+    /// for [Section::Code].
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::NOP => 0,
+            Self::Code(_) => 2,
+            Self::RunAtOffset(..) => 4,
+            Self::SectionSwitch(_) => 6,
+            Self::BSS(_) => 8,
+            Self::Patch(_) => 10,
+            Self::XDEF(_) => 12,
+            Self::XREF(_) => 14,
+            Self::LNKHeader(_) => 16,
+            Self::LocalSymbol(_) => 18,
+            Self::GroupSymbol(_) => 20,
+            Self::ByteSizeRegister(_) => 22,
+            Self::WordSizeRegister(_) => 24,
+            Self::LongSizeRegister(_) => 26,
+            Self::Filename(_) => 28,
+            Self::SetToFile(..) => 30,
+            Self::SetToLine(_) => 32,
+            Self::IncrementLineNumber => 34,
+            Self::IncrementLineNumberByte(_) => 36,
+            Self::IncrementLineNumberWord(_) => 38,
+            Self::VeryLocalSymbol(_) => 40,
+            Self::Set3ByteRegister(_) => 42,
+            Self::SetMXInfo(_) => 44,
+            Self::CPU(_) => 46,
+            Self::XBSS(_) => 48,
+            Self::IncSLDLineNum(_) => 50,
+            Self::IncSLDLineNumByte(..) => 52,
+            Self::IncSLDLineNumWord(..) => 54,
+            Self::SetSLDLineNum(_) => 56,
+            Self::SetSLDLineNumFile(_) => 58,
+            Self::EndSLDInfo(_) => 60,
+            Self::RepeatByte(_) => 62,
+            Self::RepeatWord(_) => 64,
+            Self::RepeatLong(_) => 66,
+            Self::ProcedureCall(_) => 68,
+            Self::ProcedureDefinition(_) => 70,
+            Self::Repeat3Byte(_) => 72,
+            Self::FunctionStart(_) => 74,
+            Self::FunctionEnd(_) => 76,
+            Self::BlockStart(_) => 78,
+            Self::BlockEnd(_) => 80,
+            Self::Def(_) => 82,
+            Self::Def2(_) => 84,
+            Self::Unknown(magic, _) => *magic,
+        }
+    }
+}
 
-    lang.starts_with("en_GB")
+/// Returns `true` if the primary opcode of `word` exists on the R3000.
+/// rabbitizer decodes the MIPS III instruction set, so data such as
+/// `0xffffffff` would otherwise be shown as `sd`.
+fn is_r3000_opcode(word: u32) -> bool {
+    matches!(
+        word >> 26,
+        0x00..=0x13 | 0x20..=0x26 | 0x28..=0x2b | 0x2e | 0x30..=0x33 | 0x38..=0x3b
+    )
 }
 
-impl fmt::Display for Section {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.fmt_with_options(f, &display::Options::default())
+/// Disassembles a single instruction word. Words that aren't valid
+/// instructions, including any that make rabbitizer panic, are returned as
+/// `None`.
+pub(crate) fn disassemble(word: u32) -> Option<String> {
+    disassemble_with_operand(word, None)
+}
+
+/// Disassembles a single instruction word like [disassemble], writing
+/// `operand` in place of its immediate, jump target, or branch target.
+pub(crate) fn disassemble_with_operand(word: u32, operand: Option<&str>) -> Option<String> {
+    disassemble_instruction(word, 0x80000000, operand)
+}
+
+/// Disassembles a single instruction word like [disassemble], as if it were
+/// at address `vram`.
+pub(crate) fn disassemble_at(word: u32, vram: u32) -> Option<String> {
+    disassemble_instruction(word, vram, None)
+}
+
+fn disassemble_instruction(word: u32, vram: u32, operand: Option<&str>) -> Option<String> {
+    if !is_r3000_opcode(word) {
+        return None;
     }
+    std::panic::catch_unwind(|| {
+        let instruction = Instruction::new(word, vram, InstrCategory::CPU);
+        instruction
+            .is_valid()
+            .then(|| instruction.disassemble(operand, 0))
+    })
+    .ok()
+    .flatten()
 }
 
-impl display::DisplayWithOptions for Section {
-    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
-        options.write_indent(f)?;
-        match self {
-            Self::NOP => write!(f, "0 : End of file"),
-            Self::Code(code) => {
-                write!(f, "2 : Code {} bytes", code.code.len())?;
-                match options.code_format {
-                    display::CodeFormat::Disassembly => {
-                        writeln!(f, "\n")?;
-                        for instruction in code.code.chunks(4) {
-                            if instruction.len() == 4 {
-                                let ins = u32::from_le_bytes(instruction.try_into().unwrap());
-                                let asm = Instruction::new(ins, 0x80000000, InstrCategory::CPU)
-                                    .disassemble(None, 0);
-                                options.write_indent(f)?;
-                                writeln!(f, "    /* {ins:08x} */   {asm}")?;
-                            } else {
-                                write!(f, "    /* ")?;
-                                for byte in instruction {
-                                    write!(f, "{byte:02x}")?;
-                                }
-                                writeln!(f, " */ ; invalid")?;
-                            }
-                        }
-                    }
-                    display::CodeFormat::Hex => {
-                        writeln!(f, "\n")?;
-                        for (i, chunk) in code.code.chunks(16).enumerate() {
-                            options.write_indent(f)?;
-                            write!(f, "{:04x}:", i * 16)?;
-                            for byte in chunk {
-                                write!(f, " {:02x}", byte)?;
-                            }
-                            writeln!(f)?;
-                        }
-                    }
-                    display::CodeFormat::None => (),
+/// Returns the distance in bytes from a branch instruction to its target,
+/// or `None` if `word` isn't a branch.
+pub(crate) fn branch_offset(word: u32) -> Option<i32> {
+    if !is_r3000_opcode(word) {
+        return None;
+    }
+    std::panic::catch_unwind(|| {
+        let instruction = Instruction::new(word, 0x80000000, InstrCategory::CPU);
+        (instruction.is_valid() && instruction.is_branch()).then(|| instruction.branch_offset())
+    })
+    .ok()
+    .flatten()
+}
+
+/// Writes the disassembly of `code`, stopping early with a note if the
+/// instruction or time budget in `options` is exhausted.
+///
+/// Each line is built in a buffer and written at once, which is faster than
+/// formatting it piece by piece.
+///
+/// As [annotated disassembly](display::CodeFormat::AnnotatedDisassembly),
+/// each instruction is preceded by its offset in its section and its address
+/// from [base_address](display::Options::base_address), symbols defined in
+/// the code are written as labels, and patches follow the instructions they
+/// apply to as comments.
+fn fmt_disassembly(f: &mut fmt::Formatter, code: &[u8], options: &display::Options) -> fmt::Result {
+    let annotated = options.code_format == display::CodeFormat::AnnotatedDisassembly;
+    let start = std::time::Instant::now();
+    let mut line = String::with_capacity(80);
+    for (i, instruction) in code.chunks(4).enumerate() {
+        if i >= options.max_instructions || start.elapsed() > options.max_disassembly_time {
+            options.write_indent(f)?;
+            write!(
+                f,
+                "    ; disassembly truncated after {i} instructions ({} of {} bytes)",
+                i * 4,
+                code.len()
+            )?;
+            match (&options.module, options.section) {
+                (Some(module), Some(section)) => write!(f, " in {module} section {section}")?,
+                (Some(module), None) => write!(f, " in {module}")?,
+                (None, Some(section)) => write!(f, " in section {section}")?,
+                (None, None) => (),
+            }
+            writeln!(f)?;
+            break;
+        }
+
+        let offset = i as u32 * 4;
+        let at_offset = |(o, _): &&(u32, String)| (offset..offset + 4).contains(o);
+        if annotated {
+            for (_, symbol) in options.code_symbols.iter().filter(at_offset) {
+                options.write_indent(f)?;
+                writeln!(f, "{symbol}:")?;
+            }
+        }
+        for (_, label) in options.code_labels.iter().filter(at_offset) {
+            options.write_indent(f)?;
+            writeln!(f, "    ; {label}")?;
+        }
+
+        line.clear();
+        options.push_indent(&mut line);
+        line.push_str("    /* ");
+        let section_offset = options.code_offset.wrapping_add(offset);
+        let vram = options.base_address.wrapping_add(section_offset);
+        if annotated {
+            line.push_str(&format!("{section_offset:06x} {vram:08x} "));
+        }
+        if instruction.len() == 4 {
+            let ins = u32::from_le_bytes(instruction.try_into().unwrap());
+            push_hex(&mut line, &ins.to_be_bytes());
+            line.push_str(" */   ");
+            let asm = if annotated {
+                disassemble_at(ins, vram)
+            } else {
+                disassemble(ins)
+            };
+            match asm {
+                Some(asm) => line.push_str(&asm),
+                None => {
+                    line.push_str(".word 0x");
+                    push_hex(&mut line, &ins.to_be_bytes());
+                    line.push_str("  ; data?");
+                }
+            }
+        } else {
+            push_hex(&mut line, instruction);
+            line.push_str(" */ ; invalid");
+        }
+        if annotated {
+            for (_, patch) in options.code_patches.iter().filter(at_offset) {
+                line.push_str("  ; ");
+                line.push_str(patch);
+            }
+        }
+        line.push('\n');
+        f.write_str(&line)?;
+    }
+    Ok(())
+}
+
+/// ` (decoded)`, written after a raw number in a listing if `options`
+/// [decode types](display::Options::decode_types), or nothing.
+fn decoded(options: &display::Options, decode: impl FnOnce() -> String) -> String {
+    if options.decode_types {
+        format!(" ({})", decode())
+    } else {
+        String::new()
+    }
+}
+
+/// Appends `bytes` to `line` as lowercase hex, two digits each.
+fn push_hex(line: &mut String, bytes: &[u8]) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    for byte in bytes {
+        line.push(DIGITS[(byte >> 4) as usize] as char);
+        line.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+}
+
+/// Returns the number of bytes to group when showing code for `cpu` as hex.
+///
+/// The 68000 and SH-2 are big-endian with 16-bit instruction words, so each
+/// group of two bytes, in file order, reads as the instruction's value. The
+/// 65816 and Z80 have instructions of any length and aren't grouped.
+fn hex_width(cpu: CpuType) -> usize {
+    match cpu {
+        cpu if cpu.is_68k() => 2,
+        CpuType::HitachiSh2 => 2,
+        CpuType::Wdc65816 | CpuType::ZilogZ80 => 1,
+        _ => 4,
+    }
+}
+
+/// Writes `code` 16 bytes to a row, grouping the bytes of each `width` byte
+/// instruction. Like [fmt_disassembly], rows are built in a buffer.
+fn fmt_hex(
+    f: &mut fmt::Formatter,
+    code: &[u8],
+    width: usize,
+    options: &display::Options,
+) -> fmt::Result {
+    let mut line = String::with_capacity(80);
+    for (i, row) in code.chunks(16).enumerate() {
+        line.clear();
+        options.push_indent(&mut line);
+        push_hex(&mut line, &(i as u16 * 16).to_be_bytes());
+        line.push(':');
+        for unit in row.chunks(width) {
+            line.push(' ');
+            push_hex(&mut line, unit);
+        }
+        line.push('\n');
+        f.write_str(&line)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_options(f, &display::Options::default())
+    }
+}
+
+impl display::DisplayWithOptions for Section {
+    fn fmt_with_options(&self, f: &mut fmt::Formatter, options: &display::Options) -> fmt::Result {
+        options.write_indent(f)?;
+        match self {
+            Self::NOP => write!(f, "0 : {}", labels::END_OF_FILE),
+            Self::Code(code) => {
+                write!(f, "2 : {} {} bytes", labels::CODE, code.size)?;
+                if options.show_fingerprints {
+                    display::write_fingerprint(f, self.fingerprint())?;
+                }
+                if code.deferred.is_some() && options.code_format != display::CodeFormat::None {
+                    writeln!(f, "\n")?;
+                    options.write_indent(f)?;
+                    return writeln!(f, "    ; code not read");
+                }
+                let hex = options.code_format.shows_hex();
+                if !hex && !options.code_format.shows_disassembly() {
+                    return Ok(());
+                }
+                writeln!(f, "\n")?;
+                if hex {
+                    fmt_hex(f, &code.code, 1, options)?;
+                }
+                if !options.code_format.shows_disassembly() {
+                    return Ok(());
+                }
+                match options.cpu {
+                    None | Some(CpuType::MipsR3000) => {
+                        if hex {
+                            writeln!(f)?;
+                        }
+                        fmt_disassembly(f, &code.code, options)?
+                    }
+                    Some(cpu) if hex => {
+                        options.write_indent(f)?;
+                        writeln!(
+                            f,
+                            "    ; disassembly not supported for processor type {}",
+                            u8::from(cpu)
+                        )?;
+                    }
+                    Some(cpu) => {
+                        options.write_indent(f)?;
+                        writeln!(
+                            f,
+                            "    ; disassembly not supported for processor type {}, showing hex",
+                            u8::from(cpu)
+                        )?;
+                        fmt_hex(f, &code.code, hex_width(cpu), options)?;
+                    }
                 }
                 Ok(())
             }
             Self::RunAtOffset(section_id, offset) => {
-                write!(f, "4 : Run at offset {offset:x} in {section_id:x}")
+                write!(
+                    f,
+                    "4 : {} {offset:x} in {section_id:x}",
+                    labels::RUN_AT_OFFSET
+                )
+            }
+            Self::SectionSwitch(section_id) => {
+                write!(f, "6 : {} {section_id:x}", labels::SWITCH_TO_SECTION)
             }
-            Self::SectionSwitch(section_id) => write!(f, "6 : Switch to section {section_id:x}"),
             Self::BSS(size) => {
-                let uninit = if is_en_gb() {
-                    "Uninitialised"
-                } else {
-                    "Uninitialized"
+                let uninit = match options.spelling {
+                    display::Spelling::BritishEnglish => labels::UNINITIALISED_DATA,
+                    display::Spelling::UsEnglish => labels::UNINITIALIZED_DATA,
                 };
-                write!(f, "8 : {} data, {} bytes", uninit, size)
+                write!(f, "8 : {}, {} bytes", uninit, size)
             }
             Self::Patch(patch) => write!(
                 f,
-                "10 : Patch type {} at offset {:x} with {}",
-                patch.tag, patch.offset, patch.expression
+                "10 : {} {} at offset {:x} with {}",
+                labels::PATCH,
+                patch.tag,
+                patch.offset,
+                patch.expression
             ),
             Self::XDEF(xdef) => write!(
                 f,
-                "12 : XDEF symbol number {:x} '{}' at offset {:x} in section {:x}",
+                "12 : {} {:x} '{}' at offset {:x} in section {:x}",
+                labels::XDEF,
                 xdef.number,
-                xdef.symbol_name(),
+                options.symbol_name(&xdef.symbol_name()),
                 xdef.offset,
                 xdef.section
             ),
             Self::XREF(xref) => write!(
                 f,
-                "14 : XREF symbol number {:x} '{}'",
+                "14 : {} {:x} '{}'",
+                labels::XREF,
                 xref.number,
-                xref.symbol_name()
+                options.symbol_name(&xref.symbol_name())
             ),
             Self::LNKHeader(section) => write!(
                 f,
-                "16 : Section symbol number {:x} '{}' in group {} alignment {}",
+                "16 : {} {:x} '{}' in group {} alignment {}",
+                labels::SECTION_SYMBOL,
                 section.section,
                 section.type_name(),
                 section.group,
@@ -2513,94 +5419,151 @@ impl display::DisplayWithOptions for Section {
             ),
             Self::LocalSymbol(symbol) => write!(
                 f,
-                "18 : Local symbol '{}' at offset {:x} in section {:x}",
-                symbol.name(),
+                "18 : {} '{}' at offset {:x} in section {:x}",
+                labels::LOCAL_SYMBOL,
+                options.symbol_name(&symbol.name()),
                 symbol.offset,
                 symbol.section
             ),
             Self::GroupSymbol(symbol) => write!(
                 f,
-                "20 : Group symbol number {:x} `{}` type {}",
+                "20 : {} {:x} `{}` type {}",
+                labels::GROUP_SYMBOL,
                 symbol.number,
                 symbol.name(),
                 symbol.sym_type,
             ),
             Self::ByteSizeRegister(register) => {
-                write!(f, "22 : Set byte size register to reg offset {register}",)
+                write!(
+                    f,
+                    "22 : {} to reg offset {register}",
+                    labels::BYTE_SIZE_REGISTER
+                )
             }
             Self::WordSizeRegister(register) => {
-                write!(f, "24 : Set word size register to reg offset {register}",)
+                write!(
+                    f,
+                    "24 : {} to reg offset {register}",
+                    labels::WORD_SIZE_REGISTER
+                )
             }
             Self::LongSizeRegister(register) => {
-                write!(f, "26 : Set long size register to reg offset {register}",)
+                write!(
+                    f,
+                    "26 : {} to reg offset {register}",
+                    labels::LONG_SIZE_REGISTER
+                )
             }
             Self::Filename(filename) => write!(
                 f,
-                "28 : Define file number {:x} as \"{}\"",
+                "28 : {} {:x} as \"{}\"",
+                labels::DEFINE_FILE,
                 filename.number,
                 filename.name()
             ),
-            Self::SetToFile(file, line) => write!(f, "30 : Set to {file:x}, line {line}",),
-            Self::SetToLine(line) => write!(f, "32 : Set to line {line}",),
-            Self::IncrementLineNumber => write!(f, "34 : Increment line number",),
-            Self::IncrementLineNumberByte(num) => write!(f, "36 : Increment line number by {num}",),
-            Self::IncrementLineNumberWord(num) => write!(f, "38 : Increment line number by {num}",),
+            Self::SetToFile(file, line) => {
+                write!(f, "30 : {} {file:x}, line {line}", labels::SET_TO_FILE)
+            }
+            Self::SetToLine(line) => write!(f, "32 : {} {line}", labels::SET_TO_LINE),
+            Self::IncrementLineNumber => write!(f, "34 : {}", labels::INCREMENT_LINE_NUMBER),
+            Self::IncrementLineNumberByte(num) => {
+                write!(f, "36 : {} by {num}", labels::INCREMENT_LINE_NUMBER)
+            }
+            Self::IncrementLineNumberWord(num) => {
+                write!(f, "38 : {} by {num}", labels::INCREMENT_LINE_NUMBER)
+            }
             Self::VeryLocalSymbol(symbol) => write!(
                 f,
-                "40 : Very local symbol '{}' at offset {:x} in section {:x}",
+                "40 : {} '{}' at offset {:x} in section {:x}",
+                labels::VERY_LOCAL_SYMBOL,
                 symbol.name(),
                 symbol.offset,
                 symbol.section,
             ),
             Self::Set3ByteRegister(register) => {
-                write!(f, "42 : Set 3-byte size register to reg offset {register}",)
+                write!(
+                    f,
+                    "42 : {} to reg offset {register}",
+                    labels::THREE_BYTE_SIZE_REGISTER
+                )
             }
             Self::SetMXInfo(set_mx_info) => write!(
                 f,
-                "44 : Set MX info at offset {:x} to {:x}",
-                set_mx_info.offset, set_mx_info.value,
+                "44 : {} at offset {:x} to {:x}",
+                labels::SET_MX_INFO,
+                set_mx_info.offset,
+                set_mx_info.value,
             ),
-            Self::CPU(cpu) => write!(f, "46 : Processor type {}", { *cpu }),
+            Self::CPU(cpu) => {
+                write!(f, "46 : {} {}", labels::PROCESSOR_TYPE, u8::from(*cpu))?;
+                match cpu.name() {
+                    Some(name) if !options.dumpobj_compat => write!(f, " ({name})"),
+                    _ => Ok(()),
+                }
+            }
             Self::XBSS(xbss) => write!(
                 f,
-                "48 : XBSS symbol number {:x} '{}' size {:x} in section {:x}",
+                "48 : {} {:x} '{}' size {:x} in section {:x}",
+                labels::XBSS,
                 xbss.number,
                 xbss.name(),
                 xbss.size,
                 xbss.section
             ),
-            Self::IncSLDLineNum(offset) => write!(f, "50 : Inc SLD linenum at offset {offset:x}"),
+            Self::IncSLDLineNum(offset) => {
+                write!(f, "50 : {} at offset {offset:x}", labels::INC_SLD_LINENUM)
+            }
             Self::IncSLDLineNumByte(offset, byte) => write!(
                 f,
-                "52 : Inc SLD linenum by byte {byte} at offset {offset:x}"
+                "52 : {} by byte {byte} at offset {offset:x}",
+                labels::INC_SLD_LINENUM
             ),
             Self::IncSLDLineNumWord(offset, word) => write!(
                 f,
-                "54 : Inc SLD linenum by word {word} at offset {offset:x}"
+                "54 : {} by word {word} at offset {offset:x}",
+                labels::INC_SLD_LINENUM
             ),
             Self::SetSLDLineNum(line) => write!(
                 f,
-                "56 : Set SLD linenum to {} at offset {:x}",
-                line.linenum, line.offset
+                "56 : {} to {} at offset {:x}",
+                labels::SET_SLD_LINENUM,
+                line.linenum,
+                line.offset
             ),
             Self::SetSLDLineNumFile(line) => write!(
                 f,
-                "58 : Set SLD linenum to {} at offset {:x} in file {:x}",
-                line.linenum, line.offset, line.file
+                "58 : {} to {} at offset {:x} in file {:x}",
+                labels::SET_SLD_LINENUM,
+                line.linenum,
+                line.offset,
+                line.file
             ),
-            Self::EndSLDInfo(offset) => write!(f, "60 : End SLD info at offset {offset:x}"),
-
-            Self::RepeatByte(count) => write!(f, "62 : Repeat byte {count} times"),
-            Self::RepeatWord(count) => write!(f, "64 : Repeat word {count} times"),
-            Self::RepeatLong(count) => write!(f, "66 : Repeat long {count} times"),
-            Self::ProcedureCall(call) => write!(f, "68 : <<<<Unimplemented>>>> {:?}", call),
-            Self::ProcedureDefinition(definition) => {
-                write!(f, "68 : <<<<Unimplemented>>>> {:?}", definition)
+            Self::EndSLDInfo(offset) => {
+                write!(f, "60 : {} at offset {offset:x}", labels::END_SLD_INFO)
+            }
+
+            Self::RepeatByte(count) => write!(f, "62 : {} {count} times", labels::REPEAT_BYTE),
+            Self::RepeatWord(count) => write!(f, "64 : {} {count} times", labels::REPEAT_WORD),
+            Self::RepeatLong(count) => write!(f, "66 : {} {count} times", labels::REPEAT_LONG),
+            Self::ProcedureCall(call) => write!(
+                f,
+                "68 : {} distance {:x} symbol {:x}",
+                labels::UNIMPLEMENTED,
+                call.distance,
+                call.symbol
+            ),
+            Self::ProcedureDefinition(definition) => write!(
+                f,
+                "70 : {} symbol {:x}",
+                labels::UNIMPLEMENTED,
+                definition.symbol
+            ),
+            Self::Repeat3Byte(count) => {
+                write!(f, "72 : {} {count} times", labels::REPEAT_3_BYTE)
             }
-            Self::Repeat3Byte(count) => write!(f, "70 : Repeat 3-byte {count} times"),
             Self::FunctionStart(start) => write!(
                 f,
-                "74 : Function start :\n\
+                "74 : {} :\n\
                 \x20 section {:04x}\n\
                 \x20 offset ${:08x}\n\
                 \x20 file {:04x}\n\
@@ -2611,6 +5574,7 @@ impl display::DisplayWithOptions for Section {
                 \x20 mask ${:08x}\n\
                 \x20 mask offset {}\n\
                 \x20 name {}",
+                labels::FUNCTION_START,
                 start.section,
                 start.offset,
                 start.file,
@@ -2624,66 +5588,100 @@ impl display::DisplayWithOptions for Section {
             ),
             Self::FunctionEnd(end) => write!(
                 f,
-                "76 : Function end :\n\
+                "76 : {} :\n\
                 \x20 section {:04x}\n\
                 \x20 offset ${:08x}\n\
                 \x20 end line {}",
-                end.section, end.offset, end.linenum
+                labels::FUNCTION_END,
+                end.section,
+                end.offset,
+                end.linenum
             ),
             // n.b.! the missing newline before section is intentional to match the output of OBJDUMP.EXE
             Self::BlockStart(start) => write!(
                 f,
-                "78 : Block start :\
+                "78 : {} :\
                 \x20 section {:04x}\n\
                 \x20 offset ${:08x}\n\
                 \x20 start line {}",
-                start.section, start.offset, start.linenum
+                labels::BLOCK_START,
+                start.section,
+                start.offset,
+                start.linenum
             ),
             Self::BlockEnd(end) => write!(
                 f,
-                "80 : Block end\n\
+                "80 : {}\n\
                 \x20 section {:04x}\n\
                 \x20 offset ${:08x}\n\
                 \x20 end line {}",
-                end.section, end.offset, end.linenum
+                labels::BLOCK_END,
+                end.section,
+                end.offset,
+                end.linenum
             ),
             Self::Def(def) => write!(
                 f,
-                "82 : Def :\n\
+                "82 : {} :\n\
                 \x20 section {:04x}\n\
                 \x20 value ${:08x}\n\
-                \x20 class {}\n\
-                \x20 type {}\n\
+                \x20 class {}{}\n\
+                \x20 type {}{}\n\
                 \x20 size {}\n\
                 \x20 name : {}",
+                labels::DEF,
                 def.section,
                 def.value,
                 def.class,
+                decoded(options, || def.storage_class().to_string()),
                 def.def_type,
+                decoded(options, || def.describe()),
                 def.size,
                 def.name()
             ),
             Self::Def2(def) => write!(
                 f,
-                "84 : Def2 :\n\
+                "84 : {} :\n\
                 \x20 section {:04x}\n\
                 \x20 value ${:08x}\n\
-                \x20 class {}\n\
-                \x20 type {}\n\
+                \x20 class {}{}\n\
+                \x20 type {}{}\n\
                 \x20 size {}\n\
                 \x20 dims {} \n\
                 \x20 tag {}\n\
                 {}",
+                labels::DEF2,
                 def.section,
                 def.value,
                 def.class,
+                decoded(options, || def.storage_class().to_string()),
                 def.def_type,
+                decoded(options, || def.describe()),
                 def.size,
                 def.dims,
                 def.tag(),
                 def.name()
             ),
+            Self::Unknown(magic, bytes) => {
+                write!(
+                    f,
+                    "{magic} : {}, {} bytes skipped",
+                    labels::UNKNOWN_SECTION,
+                    bytes.len()
+                )?;
+                if options.code_format.shows_hex() && !bytes.is_empty() {
+                    writeln!(f, "\n")?;
+                    fmt_hex(f, bytes, 1, options)?;
+                }
+                Ok(())
+            }
+        }?;
+
+        // code sections include the fingerprint before any listing
+        if options.show_fingerprints && !matches!(self, Self::Code(_)) {
+            display::write_fingerprint(f, self.fingerprint())?;
         }
+        Ok(())
     }
 }
 
@@ -2760,6 +5758,56 @@ mod test {
         assert_eq!(name, path_to_module_name(Path::new("some/a͢b.obj")));
     }
 
+    #[test]
+    fn test_section_bytes() {
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text").expect("header")),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".text").expect("header")),
+            Section::LNKHeader(LNKHeader::new(3, 0, 8, ".data").expect("header")),
+            Section::Code(Code::new(vec![9]).expect("code")),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![1, 2]).expect("code")),
+            Section::SectionSwitch(3),
+            Section::Code(Code::new(vec![5]).expect("code")),
+            Section::SectionSwitch(1),
+            Section::BSS(2),
+            Section::Code(Code::new(vec![3]).expect("code")),
+            Section::NOP,
+        ]);
+        assert_eq!(vec![1, 2], obj.section_ids(".text"));
+        assert_eq!(vec![1, 2, 0, 0, 3], obj.section_bytes(1).unwrap());
+        assert!(obj.section_bytes(2).unwrap().is_empty());
+        assert_eq!(vec![5], obj.section_bytes(3).unwrap());
+        assert!(obj.section_ids(".bss").is_empty());
+    }
+
+    #[test]
+    fn test_set_name() {
+        let obj = OBJ::new(vec![Section::NOP]);
+        let metadata =
+            ModuleMetadata::new("A".into(), SystemTime::now(), 0, vec![]).expect("metadata");
+        let mut module = Module::new(obj, metadata);
+        module.set_name("longername").expect("name");
+        assert_eq!(*b"LONGERNA", module.metadata.name);
+        module.set_name("👾☕☕").expect("name");
+        assert_eq!("👾☕", module.name());
+        assert!(module.set_name("").is_err());
+        assert!(module.set_name("A B").is_err());
+        assert_eq!("👾☕", module.name());
+
+        let mut lib = LIB::new(vec![module.clone(), module]);
+        lib.objs[1].set_name("b").expect("name");
+        assert!(lib.rename_module("a", "c").is_err());
+        assert!(lib.rename_module("b", "👾☕").is_err());
+        lib.rename_module("👾☕", "A").expect("rename");
+        // only the case changes
+        lib.rename_module("a", "a").expect("rename");
+        assert_eq!(
+            vec!["A", "B"],
+            lib.modules().iter().map(|m| m.name()).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_path_to_module_name_missing_file_name() {
@@ -2822,7 +5870,7 @@ mod test {
         let Section::CPU(cpu) = lnk.sections.first().expect("module[0].obj.sections[0]") else {
             unreachable!();
         };
-        assert_eq!(*cpu, cputype::MIPS_R3000);
+        assert_eq!(*cpu, CpuType::MipsR3000);
         /*
                 assert_eq!(section.section, 61444);
                 assert_eq!(section.group, 0);
@@ -2839,6 +5887,278 @@ mod test {
         assert_eq!(writer.into_inner(), bytes);
     }
 
+    #[test]
+    fn test_zero_export_metadata() {
+        // a module without exports, like _FXTFDI in LIBSN.LIB
+        let bytes = b"\
+            LIB\x01_FXTFDI \x51\xa5\x34\x1f\x15\x00\x00\x00\
+            \x1a\x00\x00\x00\x00LNK\x02\x00"
+            .to_vec();
+        let lib = LIB::read(&mut Cursor::new(&bytes)).unwrap();
+        let module = lib.modules().first().expect("module");
+        assert!(module.exports().is_empty());
+        assert_eq!(1, module.metadata.exports.len());
+        assert_eq!(21, module.metadata.offset);
+        assert_eq!(module.metadata.offset, module.metadata.expected_offset());
+
+        // rebuilding with or without the terminator produces the same bytes
+        let created = module.created_at().expect("created");
+        for exports in [vec![], module.metadata.exports.clone()] {
+            let metadata =
+                ModuleMetadata::new(module.name(), created, 5, exports).expect("metadata");
+            let rebuilt = LIB::new(vec![Module::new(module.obj.clone(), metadata)]);
+            let mut writer = Cursor::new(Vec::new());
+            rebuilt.write_le(&mut writer).unwrap();
+            assert_eq!(bytes, writer.into_inner());
+        }
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let modules = ["A", "B", "C"]
+            .iter()
+            .map(|name| {
+                let obj = OBJ::new(vec![Section::NOP]);
+                let metadata = ModuleMetadata::new(
+                    name.to_string(),
+                    UNIX_EPOCH + Duration::from_secs(820_000_000),
+                    5,
+                    vec![],
+                )
+                .expect("metadata");
+                Module::new(obj, metadata)
+            })
+            .collect::<Vec<_>>();
+        let mut writer = Cursor::new(Vec::new());
+        LIB::new(modules).write_le(&mut writer).unwrap();
+        let bytes = writer.into_inner();
+        let lib = LIB::read(&mut Cursor::new(&bytes)).unwrap();
+        assert!(lib.size_mismatches().is_empty());
+
+        // pad B's size as some librarians do
+        let mut padded = bytes.clone();
+        let size = 4 + 26 + 16;
+        padded[size..size + 4].copy_from_slice(&30u32.to_le_bytes());
+        let mut lib = LIB::read(&mut Cursor::new(&padded)).unwrap();
+        assert_eq!(3, lib.modules().len());
+        assert_eq!(
+            vec![SizeMismatch {
+                index: 1,
+                declared: 30,
+                actual: 26
+            }],
+            lib.size_mismatches()
+        );
+
+        lib.repair_sizes().expect("repair");
+        assert!(lib.size_mismatches().is_empty());
+        let mut writer = Cursor::new(Vec::new());
+        lib.write_le(&mut writer).unwrap();
+        assert_eq!(bytes, writer.into_inner());
+    }
+
+    #[test]
+    fn test_damaged_metadata() {
+        let obj = OBJ::new(vec![Section::NOP]);
+        let mut metadata = ModuleMetadata::new(
+            "BLANK".to_string(),
+            UNIX_EPOCH + Duration::from_secs(820_000_000),
+            0,
+            vec![Export::new("blank".into()).expect("export")],
+        )
+        .expect("metadata");
+        metadata.name = [b' '; 8];
+        metadata.created = 0xffff_ffff;
+        assert_eq!("", metadata.name());
+        assert_eq!(None, metadata.created_datetime());
+        assert_eq!("0xffffffff", metadata.created());
+
+        let lib = LIB::new(vec![Module::new(obj, metadata)]);
+        assert!(lib
+            .to_string()
+            .contains("<unnamed> 0xffffffff        blank \n"));
+    }
+
+    #[test]
+    fn test_common_exports() {
+        let common = Export::new("*StFunc1".into()).expect("export");
+        assert!(common.is_common());
+        assert_eq!("*StFunc1", common.name());
+        assert_eq!("StFunc1", common.symbol_name());
+        assert!(common == Export::common("StFunc1").expect("export"));
+        let mut writer = Cursor::new(Vec::new());
+        common.write_le(&mut writer).unwrap();
+        assert_eq!(b"\x08\0StFunc1", writer.into_inner().as_slice());
+
+        let defined = Export::new("StFunc1".into()).expect("export");
+        assert!(!defined.is_common());
+        assert_eq!(defined.name(), defined.symbol_name());
+
+        let obj = OBJ::new(vec![
+            Section::XDEF(XDEF::new(1, 1, 0, "StSetRing").expect("xdef")),
+            Section::XBSS(XBSS::new(2, 2, 4, "StFunc1").expect("xbss")),
+            Section::NOP,
+        ]);
+        assert_eq!(vec!["StSetRing", "StFunc1"], obj.exports());
+        assert_eq!(
+            vec!["StSetRing", "*StFunc1"],
+            obj.exports_detailed()
+                .expect("exports")
+                .iter()
+                .map(Export::name)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_exports_psylib_order() {
+        let obj = OBJ::new(vec![
+            Section::XDEF(XDEF::new(7, 1, 0, "calloc").expect("xdef")),
+            Section::XBSS(XBSS::new(3, 2, 8, "SearchPoint").expect("xbss")),
+            Section::XDEF(XDEF::new(2, 1, 8, "malloc").expect("xdef")),
+            Section::XDEF(XDEF::new(9, 1, 16, "malloc").expect("xdef")),
+            Section::NOP,
+        ]);
+        assert_eq!(
+            vec!["calloc", "SearchPoint", "malloc", "malloc"],
+            obj.exports()
+        );
+        let names = |exports: Vec<Export>| exports.iter().map(Export::name).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["malloc", "*SearchPoint", "calloc"],
+            names(obj.exports_psylib_order().expect("exports"))
+        );
+
+        let metadata =
+            ModuleMetadata::from_obj("MALLOC", SystemTime::now(), &obj).expect("metadata");
+        assert_eq!(vec!["malloc", "*SearchPoint", "calloc"], metadata.exports());
+    }
+
+    #[test]
+    fn test_long_names() {
+        let long = "x".repeat(300);
+        assert_eq!(
+            Some(format!(
+                "{long} is 300 bytes long; names are at most 255 bytes"
+            )),
+            Export::new(long.clone()).err().map(|e| e.to_string())
+        );
+        assert!(Export::new("a\0b".into()).is_err());
+        assert!(Export::new("x".repeat(255)).is_ok());
+        assert!(Export::new(format!("*{}", "x".repeat(254))).is_ok());
+        assert_eq!(
+            Some(format!(
+                "{} is 255 bytes long; common names are at most 254 bytes",
+                "x".repeat(255)
+            )),
+            Export::common(&"x".repeat(255))
+                .err()
+                .map(|e| e.to_string())
+        );
+
+        // a hand-built XDEF can't be written with a size that doesn't match
+        let obj = OBJ::new(vec![
+            Section::XDEF(XDEF {
+                number: 1,
+                section: 1,
+                offset: 0,
+                symbol_name_size: long.len() as u8,
+                symbol_name: long.clone().into_bytes(),
+            }),
+            Section::NOP,
+        ]);
+        let e = obj.to_bytes().unwrap_err();
+        assert!(
+            format!("{e:#}").contains("XDEF name is 300 bytes but its size is 44"),
+            "{e:#}"
+        );
+
+        // nor can it be added to a LIB
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("LONG.OBJ");
+        std::fs::write(&path, crate::test::TWO_MBYTE).expect("write");
+        assert!(ModuleMetadata::new_from_path(&path, &obj).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let module = |name: &str, symbols: &[&str]| {
+            let mut sections = symbols
+                .iter()
+                .enumerate()
+                .map(|(i, symbol)| Section::XDEF(XDEF::new(i as u16 + 1, 1, 0, symbol).unwrap()))
+                .collect::<Vec<_>>();
+            sections.push(Section::NOP);
+            let obj = OBJ::new(sections);
+            let exports = obj
+                .exports()
+                .into_iter()
+                .map(Export::new)
+                .collect::<Result<_>>()
+                .expect("exports");
+            let metadata = ModuleMetadata::new(
+                name.to_string(),
+                UNIX_EPOCH + Duration::from_secs(820_000_000),
+                0,
+                exports,
+            )
+            .expect("metadata");
+            Module::new(obj, metadata)
+        };
+        let mut lib = LIB::new(vec![
+            module("EXIT", &["exit", "_exit"]),
+            module("ABORT", &["abort"]),
+            module("MYEXIT", &["exit"]),
+            module("exit", &["atexit"]),
+        ]);
+        lib.repair_sizes().expect("repair");
+        assert_eq!(
+            vec![
+                "exit is exported by both EXIT and MYEXIT",
+                "more than one module is named exit",
+            ],
+            lib.validate()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+
+        let mut lib = LIB::new(vec![module("ABORT", &["abort"])]);
+        assert_eq!(
+            vec![LibIssue::SizeMismatch {
+                module: "ABORT".into(),
+                declared: 27,
+                actual: 47,
+            }],
+            lib.validate()
+        );
+        lib.repair_sizes().expect("repair");
+        assert!(lib.validate().is_empty());
+
+        // an OBJ that can't be written is reported rather than aborting
+        let mut module = module("BROKEN", &[]);
+        module.obj = OBJ::new(vec![
+            Section::Code(Code {
+                size: 4,
+                deferred: None,
+                code: vec![0; 8],
+            }),
+            Section::NOP,
+        ]);
+        let mut lib = LIB::new(vec![module]);
+        let issues = lib.validate();
+        assert!(
+            matches!(&issues[..], [LibIssue::Unwritable { module, .. }] if module == "BROKEN"),
+            "{issues:?}"
+        );
+        assert!(
+            issues[0].to_string().contains("holds 8 bytes"),
+            "{}",
+            issues[0]
+        );
+        assert!(lib.repair_sizes().is_err());
+    }
+
     #[test]
     fn test_object_entry() {
         let bytes = b"\
@@ -3078,8 +6398,24 @@ mod test {
 
         assert_eq!(obj.name(), "SPRINTF");
         // assert_eq!(obj.created, 2167611567);
-        // TODO: this should be based on locale
         assert_eq!(obj.created(), "15-05-96 16:09:38");
+        assert_eq!(
+            "1996-05-15T16:09:38",
+            obj.metadata
+                .created_with_format(&display::DateFormat::Iso8601)
+        );
+        let options = display::Options::builder()
+            .date_format(display::DateFormat::Iso8601)
+            .build();
+        assert!(display::PsyXDisplayable::wrap(&obj, options)
+            .to_string()
+            .starts_with("SPRINTF  1996-05-15T16:09:38 sprintf "));
+        let options = display::Options::builder()
+            .date_format(display::DateFormat::Custom("%Y%m%d".into()))
+            .build();
+        assert!(display::PsyXDisplayable::wrap(&obj, options)
+            .to_string()
+            .starts_with("SPRINTF  19960515          sprintf "));
         assert_eq!(obj.metadata.offset, 29);
         assert_eq!(obj.metadata.size, 3621);
         assert_eq!(obj.metadata.exports.len(), 2);
@@ -3094,7 +6430,91 @@ mod test {
         let Section::CPU(cpu) = lnk.sections.first().expect("obj[0].obj.sections[0]") else {
             panic!("expected a section");
         };
-        assert_eq!(*cpu, cputype::MIPS_R3000);
+        assert_eq!(*cpu, CpuType::MipsR3000);
+        // the file is defined, but no line information references it
+        assert!(lnk.file_references().is_empty());
+        let lines = lnk.line_map().expect("line map");
+        assert!(lines.is_empty());
+        assert!(lines.warnings().is_empty());
+
+        // with line information for the .text section it is named
+        let mut sections = lnk.sections.clone();
+        sections.splice(
+            sections.len() - 1..sections.len() - 1,
+            [
+                Section::SectionSwitch(2),
+                sld_line(0x10, 9),
+                Section::IncSLDLineNum(0x18),
+                Section::EndSLDInfo(0x20),
+            ],
+        );
+        let lines = OBJ::new(sections).line_map().expect("line map");
+        assert!(lines.warnings().is_empty());
+        // offsets are relative to the last code in .text, at 0x2C8
+        assert_eq!(None, lines.lookup(2, 0x2C8 + 0x0C));
+        assert_eq!(
+            "C:\\PSX\\SRC\\C2\\SPRINTF.C:10",
+            lines.lookup(2, 0x2C8 + 0x14).expect("line").to_string()
+        );
+        assert_eq!(11, lines.lookup(2, 0x2C8 + 0x18).expect("line").line);
+        assert_eq!(None, lines.lookup(2, 0x2C8 + 0x20));
+
+        let symbols = lnk.symbols();
+        let sprintf = symbols.find("sprintf").expect("sprintf");
+        assert_eq!(
+            (Some(0x0A), 2, Some(0)),
+            (sprintf.number, sprintf.section, sprintf.offset)
+        );
+        assert_eq!(
+            vec![(0x0C, "memchr"), (0x0B, "strlen"), (0x0D, "memmove")],
+            symbols
+                .undefined()
+                .iter()
+                .map(|s| (s.number, s.name.as_str()))
+                .collect::<Vec<_>>()
+        );
+        let memchr = symbols.by_number(0x0C).expect("memchr");
+        assert_eq!("memchr", memchr.name());
+        assert!(!memchr.is_defined());
+        assert_eq!("strlen", symbols.by_number(0x0B).expect("strlen").name());
+        assert_eq!("sprintf", symbols.by_number(0x0A).expect("sprintf").name());
+
+        let patches = lnk
+            .sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::Patch(patch) => Some(patch),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let referenced = patches
+            .iter()
+            .filter_map(|p| match p.expression() {
+                Expression::SymbolAddressIndex(n) => Some((p.tag(), p.offset(), *n)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0x4A, 0x42C, 0x0B),
+                (0x4A, 0x440, 0x0C),
+                (0x4A, 0x500, 0x0D)
+            ],
+            referenced
+        );
+        assert_eq!(
+            vec!["strlen", "memchr", "memmove"],
+            referenced
+                .iter()
+                .map(|(_, _, n)| symbols.by_number(*n).expect("symbol").name())
+                .collect::<Vec<_>>()
+        );
+        assert!(patches
+            .iter()
+            .any(|p| (p.tag(), p.offset()) == (0x52, 0x2B0)));
+        for code in lnk.iter_code() {
+            assert_eq!(code.code().unwrap().len(), code.size() as usize);
+        }
         /*
         assert_eq!(section.section, 1);
         assert_eq!(section.group, 0);
@@ -3110,52 +6530,488 @@ mod test {
         assert_eq!(writer.into_inner(), bytes);
     }
 
+    /// `2MBYTE.OBJ` from PsyQ 3.3, the startup code for a 2 MB PlayStation.
+    pub(crate) const TWO_MBYTE: &[u8] = b"\
+        \x4C\x4E\x4B\x02\x2E\x07\x10\x08\x28\x00\x00\x08\x06\x2E\x72\x64\
+        \x61\x74\x61\x10\x09\x28\x00\x00\x08\x05\x2E\x74\x65\x78\x74\x10\
+        \x0A\x28\x00\x00\x08\x05\x2E\x64\x61\x74\x61\x10\x0B\x28\x00\x00\
+        \x08\x06\x2E\x73\x64\x61\x74\x61\x10\x0C\x28\x00\x00\x08\x05\x2E\
+        \x73\x62\x73\x73\x10\x0D\x28\x00\x00\x08\x04\x2E\x62\x73\x73\x06\
+        \x08\x28\x06\x09\x28\x06\x0A\x28\x06\x0B\x28\x06\x0C\x28\x06\x0D\
+        \x28\x06\x09\x28\x02\xC4\x00\x08\x00\xE0\x03\x00\x00\x00\x00\x00\
+        \x00\x02\x3C\x00\x00\x42\x24\x00\x00\x03\x3C\x00\x00\x63\x24\x00\
+        \x00\x40\xAC\x04\x00\x42\x24\x2B\x08\x43\x00\xFC\xFF\x20\x14\x00\
+        \x00\x00\x00\x04\x00\x02\x24\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x04\x3C\x00\x00\x84\x24\x21\
+        \x20\x82\x00\x00\x00\x82\x8C\x00\x80\x08\x3C\x25\xE8\x48\x00\x00\
+        \x00\x04\x3C\x00\x00\x84\x24\xC0\x20\x04\x00\xC2\x20\x04\x00\x00\
+        \x00\x03\x3C\x00\x00\x63\x8C\x00\x00\x00\x00\x23\x28\x43\x00\x23\
+        \x28\xA4\x00\x25\x20\x88\x00\x00\x00\x01\x3C\x00\x00\x3F\xAC\x00\
+        \x00\x1C\x3C\x00\x00\x9C\x27\x21\xF0\xA0\x03\x00\x00\x00\x0C\x04\
+        \x00\x84\x20\x00\x00\x1F\x3C\x00\x00\xFF\x8F\x00\x00\x00\x00\x00\
+        \x00\x00\x0C\x00\x00\x00\x00\x4D\x00\x00\x00\x00\x00\x20\x00\x00\
+        \x00\x20\x00\x00\x00\x20\x00\x00\x00\x20\x00\x0A\x52\x08\x00\x0C\
+        \x0C\x28\x0A\x54\x0C\x00\x0C\x0C\x28\x0A\x52\x10\x00\x16\x0D\x28\
+        \x0A\x54\x14\x00\x16\x0D\x28\x0A\x52\x40\x00\x2C\x04\x09\x28\x00\
+        \xB4\x00\x00\x00\x0A\x54\x44\x00\x2C\x04\x09\x28\x00\xB4\x00\x00\
+        \x00\x0A\x52\x58\x00\x16\x0D\x28\x0A\x54\x5C\x00\x16\x0D\x28\x0A\
+        \x52\x68\x00\x02\x17\x28\x0A\x54\x6C\x00\x02\x17\x28\x0A\x52\x80\
+        \x00\x2C\x04\x0C\x28\x00\x00\x00\x00\x00\x0A\x54\x84\x00\x2C\x04\
+        \x0C\x28\x00\x00\x00\x00\x00\x0A\x52\x88\x00\x0C\x0B\x28\x0A\x54\
+        \x8C\x00\x0C\x0B\x28\x0A\x4A\x94\x00\x02\x14\x28\x0A\x52\x9C\x00\
+        \x2C\x04\x0C\x28\x00\x00\x00\x00\x00\x0A\x54\xA0\x00\x2C\x04\x0C\
+        \x28\x00\x00\x00\x00\x00\x0A\x4A\xA8\x00\x02\x16\x28\x06\x0C\x28\
+        \x08\x04\x00\x00\x00\x0E\x14\x28\x08\x49\x6E\x69\x74\x48\x65\x61\
+        \x70\x0E\x17\x28\x0A\x5F\x73\x74\x61\x63\x6B\x73\x69\x7A\x65\x0C\
+        \x0F\x28\x09\x28\x08\x00\x00\x00\x10\x5F\x5F\x53\x4E\x5F\x45\x4E\
+        \x54\x52\x59\x5F\x50\x4F\x49\x4E\x54\x0C\x0E\x28\x09\x28\x00\x00\
+        \x00\x00\x06\x5F\x5F\x6D\x61\x69\x6E\x0E\x16\x28\x04\x6D\x61\x69\
+        \x6E\x0C\x11\x28\x09\x28\xA8\x00\x00\x00\x05\x73\x74\x75\x70\x30\
+        \x0C\x12\x28\x09\x28\x2C\x00\x00\x00\x05\x73\x74\x75\x70\x31\x0C\
+        \x13\x28\x09\x28\x08\x00\x00\x00\x05\x73\x74\x75\x70\x32\x00";
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let obj = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+        let json = serde_json::to_value(&obj).unwrap();
+        assert_eq!(
+            serde_json::json!({"type": "CPU", "value": 7}),
+            json["sections"][0]
+        );
+        assert_eq!(
+            serde_json::json!({"section": 0x2808, "group": 0, "align": 8, "type_name": ".rdata"}),
+            json["sections"][1]["value"]
+        );
+        let sections = json["sections"].as_array().unwrap();
+        let code = sections.iter().find(|s| s["type"] == "Code").unwrap();
+        assert!(code["value"]["code"]
+            .as_str()
+            .unwrap()
+            .starts_with("0800e00300000000"));
+        let patch = sections.iter().find(|s| s["type"] == "Patch").unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "tag": 82,
+                "offset": 8,
+                "expression": {"type": "SectionStart", "value": 0x280C},
+            }),
+            patch["value"]
+        );
+        let xref = sections.iter().find(|s| s["type"] == "XREF").unwrap();
+        assert_eq!(
+            serde_json::json!({"number": 0x2814, "symbol_name": "InitHeap"}),
+            xref["value"]
+        );
+
+        let metadata = ModuleMetadata::new(
+            "2MBYTE".into(),
+            UNIX_EPOCH + Duration::from_secs(820_000_000),
+            0,
+            vec![Export::new("__main".into()).expect("export")],
+        )
+        .expect("metadata");
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!("2MBYTE", json["name"]);
+        assert!(json["created"].as_str().unwrap().starts_with("1995-12-26T"));
+        assert_eq!(serde_json::json!(["__main"]), json["exports"]);
+    }
+
     #[test]
     fn test_2_mbyte() {
-        let bytes = b"\
-            \x4C\x4E\x4B\x02\x2E\x07\x10\x08\x28\x00\x00\x08\x06\x2E\x72\x64\
-            \x61\x74\x61\x10\x09\x28\x00\x00\x08\x05\x2E\x74\x65\x78\x74\x10\
-            \x0A\x28\x00\x00\x08\x05\x2E\x64\x61\x74\x61\x10\x0B\x28\x00\x00\
-            \x08\x06\x2E\x73\x64\x61\x74\x61\x10\x0C\x28\x00\x00\x08\x05\x2E\
-            \x73\x62\x73\x73\x10\x0D\x28\x00\x00\x08\x04\x2E\x62\x73\x73\x06\
-            \x08\x28\x06\x09\x28\x06\x0A\x28\x06\x0B\x28\x06\x0C\x28\x06\x0D\
-            \x28\x06\x09\x28\x02\xC4\x00\x08\x00\xE0\x03\x00\x00\x00\x00\x00\
-            \x00\x02\x3C\x00\x00\x42\x24\x00\x00\x03\x3C\x00\x00\x63\x24\x00\
-            \x00\x40\xAC\x04\x00\x42\x24\x2B\x08\x43\x00\xFC\xFF\x20\x14\x00\
-            \x00\x00\x00\x04\x00\x02\x24\x00\x00\x00\x00\x00\x00\x00\x00\x00\
-            \x00\x00\x00\x00\x00\x00\x00\x00\x00\x04\x3C\x00\x00\x84\x24\x21\
-            \x20\x82\x00\x00\x00\x82\x8C\x00\x80\x08\x3C\x25\xE8\x48\x00\x00\
-            \x00\x04\x3C\x00\x00\x84\x24\xC0\x20\x04\x00\xC2\x20\x04\x00\x00\
-            \x00\x03\x3C\x00\x00\x63\x8C\x00\x00\x00\x00\x23\x28\x43\x00\x23\
-            \x28\xA4\x00\x25\x20\x88\x00\x00\x00\x01\x3C\x00\x00\x3F\xAC\x00\
-            \x00\x1C\x3C\x00\x00\x9C\x27\x21\xF0\xA0\x03\x00\x00\x00\x0C\x04\
-            \x00\x84\x20\x00\x00\x1F\x3C\x00\x00\xFF\x8F\x00\x00\x00\x00\x00\
-            \x00\x00\x0C\x00\x00\x00\x00\x4D\x00\x00\x00\x00\x00\x20\x00\x00\
-            \x00\x20\x00\x00\x00\x20\x00\x00\x00\x20\x00\x0A\x52\x08\x00\x0C\
-            \x0C\x28\x0A\x54\x0C\x00\x0C\x0C\x28\x0A\x52\x10\x00\x16\x0D\x28\
-            \x0A\x54\x14\x00\x16\x0D\x28\x0A\x52\x40\x00\x2C\x04\x09\x28\x00\
-            \xB4\x00\x00\x00\x0A\x54\x44\x00\x2C\x04\x09\x28\x00\xB4\x00\x00\
-            \x00\x0A\x52\x58\x00\x16\x0D\x28\x0A\x54\x5C\x00\x16\x0D\x28\x0A\
-            \x52\x68\x00\x02\x17\x28\x0A\x54\x6C\x00\x02\x17\x28\x0A\x52\x80\
-            \x00\x2C\x04\x0C\x28\x00\x00\x00\x00\x00\x0A\x54\x84\x00\x2C\x04\
-            \x0C\x28\x00\x00\x00\x00\x00\x0A\x52\x88\x00\x0C\x0B\x28\x0A\x54\
-            \x8C\x00\x0C\x0B\x28\x0A\x4A\x94\x00\x02\x14\x28\x0A\x52\x9C\x00\
-            \x2C\x04\x0C\x28\x00\x00\x00\x00\x00\x0A\x54\xA0\x00\x2C\x04\x0C\
-            \x28\x00\x00\x00\x00\x00\x0A\x4A\xA8\x00\x02\x16\x28\x06\x0C\x28\
-            \x08\x04\x00\x00\x00\x0E\x14\x28\x08\x49\x6E\x69\x74\x48\x65\x61\
-            \x70\x0E\x17\x28\x0A\x5F\x73\x74\x61\x63\x6B\x73\x69\x7A\x65\x0C\
-            \x0F\x28\x09\x28\x08\x00\x00\x00\x10\x5F\x5F\x53\x4E\x5F\x45\x4E\
-            \x54\x52\x59\x5F\x50\x4F\x49\x4E\x54\x0C\x0E\x28\x09\x28\x00\x00\
-            \x00\x00\x06\x5F\x5F\x6D\x61\x69\x6E\x0E\x16\x28\x04\x6D\x61\x69\
-            \x6E\x0C\x11\x28\x09\x28\xA8\x00\x00\x00\x05\x73\x74\x75\x70\x30\
-            \x0C\x12\x28\x09\x28\x2C\x00\x00\x00\x05\x73\x74\x75\x70\x31\x0C\
-            \x13\x28\x09\x28\x08\x00\x00\x00\x05\x73\x74\x75\x70\x32\x00";
-        let mut data = Cursor::new(&bytes);
+        let mut data = Cursor::new(TWO_MBYTE);
         let lnk = OBJ::read(&mut data).unwrap();
 
         eprintln!("obj: {:?}", lnk);
     }
 
+    #[test]
+    fn test_2_mbyte_fingerprints() {
+        let lnk = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+
+        // the OBJ fingerprint is the CRC-32 of its serialized bytes
+        assert_eq!(0x7147d310, lnk.fingerprint().unwrap());
+
+        // each section's fingerprint is the CRC-32 of its serialized bytes, so
+        // identical sections, like repeated section switches, share one
+        const SECTION_FINGERPRINTS: [u32; 44] = [
+            0xd4ba8e70, 0x03162d2d, 0x57166058, 0x588c52fd, 0x49e8d66d, 0xc349ae4e, 0xd6d53771,
+            0x06a08752, 0x1fbbb613, 0x3496e5d0, 0x2d8dd491, 0x62cc4256, 0x7bd77317, 0x1fbbb613,
+            0x86935bf2, 0xd8307c9f, 0xfbe93942, 0x800ab73b, 0xa3d3f2e6, 0x3fdb99d0, 0x603f4fb2,
+            0xe889a4b3, 0xcb50e16e, 0xe2aaed42, 0xc173a89f, 0x5e5a43ca, 0x01be95a8, 0x26975aca,
+            0x054e1f17, 0xabc6832b, 0xfab615c6, 0x37fb69fd, 0xfd21b42e, 0x62cc4256, 0x79302b8b,
+            0x9934e724, 0x1d2a4377, 0x9abfadd0, 0x7b588492, 0xf4b5574c, 0xcaa12e21, 0x2e299217,
+            0x6c998171, 0xd202ef8d,
+        ];
+        let fingerprints = lnk
+            .sections()
+            .iter()
+            .map(|s| s.fingerprint().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(SECTION_FINGERPRINTS, fingerprints[..]);
+
+        // changing a single code byte only changes that section
+        let mut changed = lnk.clone();
+        let index = changed
+            .sections
+            .iter()
+            .position(|s| matches!(s, Section::Code(_)))
+            .expect("code section");
+        if let Section::Code(code) = &mut changed.sections[index] {
+            code.code[0] ^= 0xff;
+        }
+        assert_ne!(lnk.fingerprint().unwrap(), changed.fingerprint().unwrap());
+        for (i, (a, b)) in lnk.sections().iter().zip(changed.sections()).enumerate() {
+            assert_eq!(
+                i != index,
+                a.fingerprint().unwrap() == b.fingerprint().unwrap()
+            );
+        }
+
+        let options = display::Options {
+            show_fingerprints: true,
+            ..Default::default()
+        };
+        let listing = display::PsyXDisplayable::wrap(&lnk, options).to_string();
+        assert_eq!(lnk.sections().len(), listing.matches(" [crc:").count());
+        assert!(listing.contains(&format!(
+            "Code 196 bytes [crc:{:08x}]",
+            lnk.sections()[index].fingerprint().unwrap()
+        )));
+    }
+
+    #[test]
+    fn test_2_mbyte_offsets() {
+        let lnk = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+
+        // the CPU, then the .rdata, .text, and .data symbols
+        assert_eq!(lnk.sections().len(), lnk.section_offsets().len());
+        assert_eq!([4, 6, 0x13, 0x1f, 0x2b], lnk.section_offsets()[..5]);
+        assert_eq!(
+            TWO_MBYTE.len() as u64 - 1,
+            *lnk.section_offsets().last().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_2_mbyte_diff() {
+        let lnk = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+
+        let mut changed = lnk.clone();
+        let index = lnk
+            .sections()
+            .iter()
+            .position(|s| matches!(s, Section::Code(_)))
+            .expect("code section");
+        if let Section::Code(code) = &mut changed.sections[index] {
+            code.code[0] ^= 0xff;
+        }
+        let diff = diff::diff_objs(&lnk, &changed);
+        assert_eq!(1, diff.sections.len());
+        assert_eq!(index, diff.sections[0].index);
+        assert!(diff::diff_objs(&lnk, &lnk).is_empty());
+    }
+
+    #[test]
+    fn test_2_mbyte_sizes() {
+        let lnk = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+
+        let sizes = lnk.section_sizes();
+        assert_eq!(196, sizes.get(".text").code);
+        assert_eq!(4, sizes.get(".sbss").bss);
+        assert_eq!(200, sizes.total());
+    }
+
+    #[test]
+    fn test_2_mbyte_apply_patches() {
+        let lnk = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+        let index = lnk
+            .sections()
+            .iter()
+            .position(|s| matches!(s, Section::Code(_)))
+            .expect("code section");
+
+        // link the code with made up addresses
+        struct Layout;
+        impl eval::ExpressionResolver for Layout {
+            fn symbol_address(&self, symbol: u16) -> Option<u32> {
+                Some(0x8003_0000 + u32::from(symbol))
+            }
+            fn section_base(&self, _section: u16) -> Option<u32> {
+                Some(0x8001_0000)
+            }
+            fn section_start(&self, section: u16) -> Option<u32> {
+                // .sbss straddles a 64k boundary
+                Some(if section == 0x280C {
+                    0x8001_8000
+                } else {
+                    0x8001_4000
+                })
+            }
+            fn section_end(&self, section: u16) -> Option<u32> {
+                Some(0x8002_1234 + u32::from(section - 0x280C))
+            }
+        }
+        let Section::Code(code) = &lnk.sections()[index] else {
+            unreachable!();
+        };
+        let mut code = code.code().unwrap().to_vec();
+        for section in &lnk.sections()[index + 1..] {
+            let Section::Patch(patch) = section else {
+                break;
+            };
+            let value = patch.expression().evaluate(&Layout).expect("value");
+            patch.apply(&mut code, value).expect("apply");
+        }
+        let instructions = (8..0x18)
+            .step_by(4)
+            .map(|i| {
+                let word = u32::from_le_bytes(code[i..i + 4].try_into().unwrap());
+                disassemble(word).expect("instruction")
+            })
+            .collect::<Vec<_>>();
+        // the bounds of .sbss, then .bss
+        assert_eq!(
+            vec![
+                "lui         $v0, 0x8002",
+                "addiu       $v0, $v0, -0x8000",
+                "lui         $v1, 0x8002",
+                "addiu       $v1, $v1, 0x1235",
+            ],
+            instructions
+        );
+    }
+
+    #[test]
+    fn test_edit_patches() {
+        let edited = |edit: &dyn Fn(&mut Patch)| {
+            let mut obj = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+            obj.patches_mut().for_each(edit);
+            let bytes = obj.to_bytes().unwrap();
+            assert_eq!(obj, OBJ::read(&mut Cursor::new(&bytes)).unwrap());
+            bytes
+        };
+        let changed = |bytes: &[u8]| {
+            assert_eq!(TWO_MBYTE.len(), bytes.len());
+            (0..bytes.len())
+                .filter(|&i| TWO_MBYTE[i] != bytes[i])
+                .collect::<Vec<_>>()
+        };
+        // the high and low halves of the address
+        let addends = TWO_MBYTE
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == [0x09, 0x28, 0x00, 0xB4])
+            .map(|(i, _)| i + 3)
+            .collect::<Vec<_>>();
+        assert_eq!(2, addends.len());
+
+        // only the addends change
+        let bytes = edited(&|patch| {
+            if patch.expression().to_string() == "(sectbase(2809)+$b4)" {
+                patch.expression_mut().map_constants(|_| 0xb8);
+            }
+        });
+        assert_eq!(addends, changed(&bytes));
+        assert!(addends.iter().all(|&i| bytes[i] == 0xB8));
+
+        // as does the symbol of [2814] (InitHeap)
+        let bytes = edited(&|patch| {
+            patch
+                .expression_mut()
+                .map_symbols(|n| if n == 0x2814 { 0x2817 } else { n });
+        });
+        let [i] = changed(&bytes)[..] else {
+            panic!("{:?}", changed(&bytes));
+        };
+        assert_eq!([0x17, 0x28], bytes[i..i + 2]);
+
+        let bytes = edited(&|patch| {
+            if patch.offset() == 0x94 {
+                patch.set_offset(0x98);
+            }
+        });
+        let [i] = changed(&bytes)[..] else {
+            panic!("{:?}", changed(&bytes));
+        };
+        assert_eq!([0x4A, 0x98, 0x00], bytes[i - 1..i + 2]);
+    }
+
+    #[test]
+    fn test_2_mbyte_to_asm() {
+        let obj = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+        let source = obj.to_asm(&asm::AsmOptions::default()).unwrap();
+
+        assert!(source.contains("\txdef\t__SN_ENTRY_POINT\n"));
+        assert!(source.contains("\txref\t_stacksize\n"));
+        assert!(source.contains("\tsection\t.text\n\talign\t8\n__main:\n\tjr"));
+        // the entry point follows `jr $ra; nop` at offset 8
+        assert!(source.contains(
+            "\tnop\n\
+            __SN_ENTRY_POINT:\n\
+            stup2:\n\
+            \tlui         $v0, %hi(sectstart(.sbss))\n"
+        ));
+        assert!(source.contains(
+            "\tlui         $v1, %hi(_stacksize)\n\
+            \tlw          $v1, %lo(_stacksize)($v1)\n"
+        ));
+        assert!(source.contains("\tbnez        $at, L2809_18\n"));
+        assert!(source.contains("\tjal         InitHeap\n"));
+        assert!(source.contains("\tsection\t.sbss\n\talign\t8\nL280c_0:\n\tds.b\t4\n"));
+
+        let options = asm::AsmOptions { comments: true };
+        let source = obj.to_asm(&options).unwrap();
+        assert!(source.contains("\tjr          $ra                         ; 000000: 0800e003\n"));
+    }
+
+    #[test]
+    fn test_spelling() {
+        let bss = Section::BSS(4);
+        assert_eq!("8 : Uninitialized data, 4 bytes", bss.to_string());
+        let options = display::Options::builder()
+            .spelling(display::Spelling::from_locale("en_GB.UTF-8"))
+            .build();
+        assert_eq!(
+            "8 : Uninitialised data, 4 bytes",
+            display::PsyXDisplayable::wrap(&bss, options).to_string()
+        );
+    }
+
+    #[test]
+    fn test_code_size_mismatch() {
+        let obj = OBJ::new(vec![
+            Section::Code(Code {
+                size: 4,
+                deferred: None,
+                code: vec![0; 8],
+            }),
+            Section::NOP,
+        ]);
+        let e = obj.to_bytes().expect_err("size mismatch");
+        assert!(
+            e.to_string()
+                .contains("code section is 4 bytes but holds 8 bytes of code"),
+            "{e}"
+        );
+    }
+
+    fn disassembly_options() -> display::Options {
+        display::Options {
+            code_format: display::CodeFormat::Disassembly,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_disassemble_data() {
+        let code = Section::Code(Code {
+            size: 12,
+            deferred: None,
+            code: vec![0xff; 12],
+        });
+        let listing = display::PsyXDisplayable::wrap(&code, disassembly_options()).to_string();
+        assert_eq!(
+            "2 : Code 12 bytes\n\n\
+            \x20   /* ffffffff */   .word 0xffffffff  ; data?\n\
+            \x20   /* ffffffff */   .word 0xffffffff  ; data?\n\
+            \x20   /* ffffffff */   .word 0xffffffff  ; data?\n",
+            listing
+        );
+    }
+
+    #[test]
+    fn test_annotated_disassembly() {
+        let obj = OBJ::read(&mut Cursor::new(TWO_MBYTE)).unwrap();
+        let options = display::Options::builder()
+            .code_format(display::CodeFormat::AnnotatedDisassembly)
+            .build();
+        assert_eq!(
+            include_str!("../tests/data/cmd/annotated/2MBYTE.TXT"),
+            display::PsyXDisplayable::wrap(&obj, options).to_string()
+        );
+
+        let options = display::Options::builder()
+            .code_format(display::CodeFormat::AnnotatedDisassembly)
+            .base_address(0x80010000)
+            .build();
+        let listing = display::PsyXDisplayable::wrap(&obj, options).to_string();
+        assert!(listing.contains(
+            "stup0:\n    /* 0000a8 800100a8 0c000000 */   jal         func_80000000  ; patch 74: [2816]\n"
+        ), "{listing}");
+
+        let options = display::Options::builder()
+            .code_format(display::CodeFormat::Disassembly)
+            .build();
+        let listing = display::PsyXDisplayable::wrap(&obj, options).to_string();
+        assert!(!listing.contains("stup0:"), "{listing}");
+        assert!(!listing.contains("; patch"), "{listing}");
+    }
+
+    #[test]
+    fn test_disassembly_budget() {
+        // jr $ra; nop; jr $ra; nop
+        let words = [0x03e00008u32, 0, 0x03e00008, 0];
+        let obj = OBJ::new(vec![
+            Section::Code(Code {
+                size: 16,
+                deferred: None,
+                code: words.iter().flat_map(|w| w.to_le_bytes()).collect(),
+            }),
+            Section::BSS(4),
+            Section::NOP,
+        ]);
+        let options = display::Options {
+            max_instructions: 2,
+            module: Some("TEST".into()),
+            ..disassembly_options()
+        };
+        let listing = display::PsyXDisplayable::wrap(&obj, options).to_string();
+        assert!(listing.contains(
+            "\
+            \x20   /* 03e00008 */   jr          $ra\n\
+            \x20   /* 00000000 */   nop\n\
+            \x20   ; disassembly truncated after 2 instructions (8 of 16 bytes) in TEST section 0\n"
+        ));
+        assert_eq!(1, listing.matches("jr ").count());
+        // the rest of the listing follows
+        assert!(listing.contains("8 : Uninitialized data, 4 bytes"));
+        assert!(listing.contains("0 : End of file"));
+
+        let listing = display::PsyXDisplayable::wrap(&obj, disassembly_options()).to_string();
+        assert_eq!(2, listing.matches("jr ").count());
+        assert!(!listing.contains("truncated"));
+    }
+
+    #[test]
+    fn test_observer() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+        impl display::Observer for Recorder {
+            fn module(&self, name: &str) {
+                self.0.lock().unwrap().push(name.to_string());
+            }
+        }
+
+        let module = |name: &str| {
+            Module::new(
+                OBJ::new(vec![Section::NOP]),
+                ModuleMetadata::new(name.into(), SystemTime::now(), 0, vec![]).expect("metadata"),
+            )
+        };
+        let lib = LIB::new(vec![module("A"), module("B")]);
+        let recorder = Arc::new(Recorder::default());
+        let options = display::Options {
+            recursive: true,
+            observer: Some(recorder.clone()),
+            ..Default::default()
+        };
+        let _ = display::PsyXDisplayable::wrap(&lib, options).to_string();
+        assert_eq!(vec!["A", "B"], *recorder.0.lock().unwrap());
+    }
+
     #[test]
     fn test_section() {
         let bytes = b"\x3A\x00\x00\x26\x00\x00\x00\x09\x00";
@@ -3205,6 +7061,58 @@ mod test {
         let _ = Section::read(&mut data).unwrap();
     }
 
+    #[test]
+    fn test_functions() {
+        let bytes = b"\
+            \x4A\x7C\x55\xB4\x05\x00\x00\xA7\x59\x00\x00\x00\x00\x1D\x00\x20\
+            \x00\x00\x00\x1F\x00\x00\x00\x03\x80\xF8\xFF\xFF\xFF\x06\x63\x61\
+            \x6C\x6C\x6F\x63"
+            .to_vec();
+        let calloc = Section::read(&mut Cursor::new(&bytes)).unwrap();
+        let body = (0..0x40).collect::<Vec<u8>>();
+        let obj = OBJ::new(vec![
+            filename(0x59A7, "CALLOC.C"),
+            Section::SectionSwitch(0x557C),
+            Section::Code(Code::new(vec![0; 0x5B4]).expect("code")),
+            calloc,
+            Section::Code(Code::new(body.clone()).expect("code")),
+            Section::FunctionEnd(SectionOffsetLine {
+                section: 0x557C,
+                offset: 0x5F4,
+                linenum: 12,
+            }),
+            Section::NOP,
+        ]);
+
+        let functions = obj.functions();
+        assert_eq!(1, functions.len());
+        let function = &functions[0];
+        assert_eq!("calloc", function.name);
+        assert_eq!(
+            (0x557C, 0x5B4, Some(0x5F4), Some(0x40)),
+            (
+                function.section,
+                function.start,
+                function.end,
+                function.size()
+            )
+        );
+        assert_eq!((0, Some(12)), (function.start_line, function.end_line));
+        assert_eq!(Some("CALLOC.C"), function.file_name.as_deref());
+        assert_eq!(
+            (29, 32, 31, 0x80030000, -8),
+            (
+                function.frame_register,
+                function.frame_size,
+                function.return_pc_register,
+                function.mask,
+                function.mask_offset
+            )
+        );
+        assert_eq!(Some(body.as_slice()), function.code(&obj));
+        assert!(functions::Functions::of_obj(&obj).warnings.is_empty());
+    }
+
     #[test]
     fn test_def2() {
         let bytes = b"\
@@ -3226,6 +7134,17 @@ mod test {
         // assert_eq!(def2.dims, Dim::None);
         assert_eq!(def2.tag(), "_physadr");
         assert_eq!(def2.name(), ".eos");
+        assert_eq!(types::StorageClass::EndOfStruct, def2.storage_class());
+        assert_eq!(types::BaseType::Null, def2.type_desc().base);
+        assert!(def2.type_desc().derivations.is_empty());
+        assert_eq!("struct _physadr", def2.describe());
+
+        let section = Section::Def2(def2);
+        assert!(section.to_string().contains("  class 102\n  type 0\n"));
+        let options = display::Options::builder().decode_types(true).build();
+        assert!(display::PsyXDisplayable::wrap(&section, options)
+            .to_string()
+            .contains("  class 102 (C_EOS)\n  type 0 (struct _physadr)\n"));
     }
 
     #[test]
@@ -3251,4 +7170,285 @@ b"\x68\x00\x2F\x86\x2F\x96\x2F\xA6\x2F\xB6\x2F\xC6\x2F\xD6\x2F\xE6\x4F\x22\x6E\x
         let mut data = Cursor::new(&bytes);
         let _ = OBJ::read(&mut data).unwrap();
     }
+
+    #[test]
+    fn test_disassemble_other_cpu() {
+        // the SH-2 header from LIBSN, with `rts; nop` appended
+        let bytes = b"\x4C\x4E\x4B\x02\x2E\x08\x14\x0B\x33\x80\x03\x62\x73\x73\x10\x0C\x33\x0B\x33\x08\x06\x62\x73\x73\x65\x6E\x64\x06\x0C\x33\x0C\x0A\x33\x0C\x33\x00\x00\x00\x00\x03\x65\x6E\x64\x00";
+        let mut obj = OBJ::read(&mut Cursor::new(&bytes)).unwrap();
+        obj.sections.insert(
+            obj.sections.len() - 1,
+            Section::Code(Code {
+                size: 4,
+                deferred: None,
+                code: vec![0x00, 0x0b, 0x00, 0x09],
+            }),
+        );
+        let listing = display::PsyXDisplayable::wrap(&obj, disassembly_options()).to_string();
+        assert!(listing.contains(
+            "2 : Code 4 bytes\n\n\
+            \x20   ; disassembly not supported for processor type 8, showing hex\n\
+            0000: 000b 0009\n"
+        ));
+
+        // sections formatted on their own are still disassembled as R3000 code
+        let code = &obj.sections[obj.sections.len() - 2];
+        let listing = display::PsyXDisplayable::wrap(code, disassembly_options()).to_string();
+        assert!(!listing.contains("not supported"));
+
+        // 68000 code is grouped by instruction word too, and Z80 code by byte
+        obj.sections[0] = Section::CPU(CpuType::Motorola68000);
+        obj.sections.insert(
+            obj.sections.len() - 1,
+            Section::Code(Code {
+                size: 6,
+                deferred: None,
+                code: vec![0x4e, 0x71, 0x4e, 0x75, 0x00, 0x01],
+            }),
+        );
+        let listing = display::PsyXDisplayable::wrap(&obj, disassembly_options()).to_string();
+        assert!(listing.contains("0000: 4e71 4e75 0001\n"));
+        obj.sections[0] = Section::CPU(CpuType::ZilogZ80);
+        let listing = display::PsyXDisplayable::wrap(&obj, disassembly_options()).to_string();
+        assert!(listing.contains("0000: 4e 71 4e 75 00 01\n"));
+    }
+
+    #[test]
+    fn test_cpu_type() {
+        let bytes = b"\x4C\x4E\x4B\x02\x2E\x08\x2E\x09\x00";
+        let obj = OBJ::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(Some(CpuType::HitachiSh2), obj.cpu());
+        assert_eq!(Section::CPU(CpuType::Unknown(9)), obj.sections[1]);
+        let mut writer = Cursor::new(Vec::new());
+        obj.write_le(&mut writer).unwrap();
+        assert_eq!(bytes, writer.into_inner().as_slice());
+        assert_eq!(None, OBJ::new(vec![Section::NOP]).cpu());
+
+        for cpu in 0..=u8::MAX {
+            assert_eq!(cpu, u8::from(CpuType::from(cpu)));
+        }
+
+        let listing = obj.to_string();
+        assert!(listing.contains("46 : Processor type 8\n46 : Processor type 9\n"));
+        let options = display::Options::builder().dumpobj_compat(false).build();
+        let listing = display::PsyXDisplayable::wrap(&obj, options).to_string();
+        assert!(listing.contains("46 : Processor type 8 (Hitachi SH-2)\n46 : Processor type 9\n"));
+    }
+
+    fn filename(number: u16, name: &str) -> Section {
+        Section::Filename(Filename {
+            number,
+            size: name.len() as u8,
+            name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn sld_line(offset: u16, file: u16) -> Section {
+        Section::SetSLDLineNumFile(SetSLDLineNumFile {
+            offset,
+            linenum: 10,
+            file,
+        })
+    }
+
+    #[test]
+    fn test_file_references() {
+        let legacy = OBJ::new(vec![
+            filename(1, "MAIN.C"),
+            filename(2, "UTIL.H"),
+            Section::SetToFile(1, 10),
+            Section::IncrementLineNumber,
+            Section::SetToFile(2, 4),
+            Section::SetToFile(1, 12),
+            Section::NOP,
+        ]);
+        let sld = OBJ::new(vec![
+            filename(1, "MAIN.C"),
+            filename(2, "UTIL.H"),
+            sld_line(0x10, 1),
+            sld_line(0x20, 2),
+            sld_line(0x30, 1),
+            Section::NOP,
+        ]);
+        let mixed = OBJ::new(vec![
+            filename(1, "MAIN.C"),
+            filename(2, "UTIL.H"),
+            Section::SetToFile(1, 10),
+            sld_line(0x20, 2),
+            sld_line(0x30, 1),
+            Section::SetToFile(3, 1),
+            Section::NOP,
+        ]);
+
+        let files = |obj: &OBJ| {
+            obj.file_references()
+                .into_iter()
+                .map(|r| r.file)
+                .collect::<Vec<FileIdOrName>>()
+        };
+        assert_eq!(
+            vec![
+                FileIdOrName::Name("MAIN.C".into()),
+                FileIdOrName::Name("UTIL.H".into())
+            ],
+            files(&legacy)
+        );
+        assert_eq!(files(&legacy), files(&sld));
+        assert_eq!(files(&legacy), files(&mixed)[..2]);
+
+        let legacy_refs = legacy.file_references();
+        assert!(legacy_refs
+            .iter()
+            .all(|r| r.mechanism == LineInfoMechanism::Legacy && r.first_offset.is_none()));
+
+        let sld_refs = sld.file_references();
+        assert!(sld_refs
+            .iter()
+            .all(|r| r.mechanism == LineInfoMechanism::SLD));
+        assert_eq!(Some(0x10), sld_refs[0].first_offset);
+        assert_eq!(Some(0x20), sld_refs[1].first_offset);
+
+        let mixed_refs = mixed.file_references();
+        assert_eq!(3, mixed_refs.len());
+        assert_eq!(LineInfoMechanism::Mixed, mixed_refs[0].mechanism);
+        assert_eq!(Some(0x30), mixed_refs[0].first_offset);
+        assert_eq!(LineInfoMechanism::SLD, mixed_refs[1].mechanism);
+        assert_eq!(FileIdOrName::Id(3), mixed_refs[2].file);
+        assert_eq!(
+            vec![1, 2, 3],
+            mixed_refs.iter().map(|r| r.id).collect::<Vec<_>>()
+        );
+
+        let findings = mixed.verify(&verify::Options::default());
+        assert_eq!(2, findings.len());
+        assert_eq!(
+            "warning: \"MAIN.C\" is referenced by both legacy and SLD line information",
+            findings[0].to_string()
+        );
+        assert_eq!(
+            "warning: line information refers to undefined file number 3",
+            findings[1].to_string()
+        );
+        assert!(legacy.verify(&verify::Options::default()).is_empty());
+        assert!(sld.verify(&verify::Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_symbol_index() {
+        let module = |name: &str, exports: &[&str]| {
+            let exports = exports
+                .iter()
+                .map(|e| Export::new(e.to_string()).expect("export"))
+                .collect();
+            let metadata =
+                ModuleMetadata::new(name.into(), SystemTime::now(), 0, exports).expect("metadata");
+            Module::new(OBJ::new(vec![Section::NOP]), metadata)
+        };
+        let lib = LIB::new(vec![
+            module("OPEN", &["PCopen"]),
+            module("A", &["a", "shared"]),
+            module("B", &["b", "shared"]),
+        ]);
+
+        assert_eq!("OPEN", lib.find_module_by_export("PCopen").unwrap().name());
+        assert!(lib.find_module_by_export("pcopen").is_none());
+        assert_eq!("A", lib.find_module_by_export("shared").unwrap().name());
+
+        let index = lib.symbol_index();
+        assert_eq!(4, index.len());
+        assert_eq!("OPEN", index.find("PCopen").unwrap().name());
+        assert!(index.find("missing").is_none());
+        assert!(index.find_all("missing").is_empty());
+        assert_eq!(
+            vec!["A", "B"],
+            index
+                .find_all("shared")
+                .iter()
+                .map(|m| m.name())
+                .collect::<Vec<_>>()
+        );
+        let duplicates = index.duplicates();
+        assert_eq!(1, duplicates.len());
+        assert_eq!("shared", duplicates[0].0);
+        assert!(LIB::new(vec![]).symbol_index().is_empty());
+    }
+
+    #[test]
+    fn test_iter_sections() {
+        let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+        let sections = lib.iter_sections().collect::<Vec<_>>();
+        assert_eq!(
+            lib.modules()
+                .iter()
+                .map(|m| m.object().sections().len() - 1)
+                .sum::<usize>(),
+            sections.len()
+        );
+        assert!(!sections.iter().any(|(_, s)| matches!(s, Section::NOP)));
+        assert_eq!("MAIN", sections[0].0.name());
+        assert_eq!("HELPER", sections.last().unwrap().0.name());
+
+        let code = lib
+            .iter_sections()
+            .filter(|(_, s)| matches!(s, Section::Code(_)))
+            .count();
+        assert_eq!(2, code);
+        assert_eq!(
+            code,
+            lib.modules()
+                .iter()
+                .map(|m| m.object().iter_code().count())
+                .sum::<usize>()
+        );
+        assert_eq!(
+            vec![0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00],
+            lib.modules()[0]
+                .object()
+                .iter_code()
+                .next()
+                .unwrap()
+                .code()
+                .unwrap()
+        );
+        assert_eq!(0, LIB::new(vec![]).iter_sections().count());
+    }
+
+    #[test]
+    fn test_edit_modules() {
+        let module = |name: &str, code: u8| {
+            let metadata =
+                ModuleMetadata::new(name.into(), SystemTime::now(), 0, vec![]).expect("metadata");
+            let code = Section::Code(Code {
+                size: 1,
+                deferred: None,
+                code: vec![code],
+            });
+            Module::new(OBJ::new(vec![code, Section::NOP]), metadata)
+        };
+        let names = |lib: &LIB| lib.modules().iter().map(Module::name).collect::<Vec<_>>();
+
+        let mut lib = LIB::new(vec![module("A", 1)]);
+        lib.push_module(module("B", 2));
+        assert_eq!(vec!["A", "B"], names(&lib));
+
+        // names are case-insensitive
+        let replaced = lib.replace_module(module("b", 3)).expect("replaced");
+        assert_eq!(module("B", 2).object(), replaced.object());
+        assert_eq!(vec!["A", "b"], names(&lib));
+
+        // replacing a module that doesn't exist adds it
+        assert!(lib.replace_module(module("C", 4)).is_none());
+        assert_eq!(vec!["A", "b", "C"], names(&lib));
+
+        assert!(lib.remove_module("missing").is_none());
+        assert_eq!("b", lib.remove_module("B").expect("removed").name());
+        assert_eq!("A", lib.remove_module("a").expect("removed").name());
+        assert_eq!("C", lib.remove_module("c").expect("removed").name());
+        assert!(lib.modules().is_empty());
+
+        // an empty LIB can be edited, but not written
+        assert!(io::write_lib_to(&lib, &mut Vec::new()).is_err());
+        lib.push_module(module("D", 5));
+        assert!(io::write_lib_to(&lib, &mut Vec::new()).is_ok());
+    }
 }
@@ -0,0 +1,351 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Source locations of the code in an OBJ.
+//!
+//! Line information is a sequence of [Section]s each of which sets or
+//! advances the current file and line at an offset in the current section.
+//! [LineMap::of_obj] runs that sequence once so that the location of any
+//! offset can be looked up.
+//!
+//! Source line debugger (SLD) records carry their own offset, which is
+//! taken to be relative to the code they follow, like a
+//! [Patch](crate::Patch)'s. The older records, like
+//! [SetToFile](Section::SetToFile), have no offset and apply at the end of
+//! the current section. Files are named by [Filename] sections, whichever
+//! records refer to them; see [OBJ::file_references].
+//!
+//! Records which can't be applied, like an increment before any line has
+//! been set, are reported as [warnings](LineMap::warnings) and skipped.
+//!
+//! ```
+//! use psyk::{Code, Filename, Section, OBJ};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let obj = OBJ::new(vec![
+//!     Section::Filename(Filename::new(1, "MAIN.C")?),
+//!     Section::SectionSwitch(1),
+//!     Section::SetToFile(1, 10),
+//!     Section::Code(Code::new(vec![0; 8])?),
+//!     Section::IncrementLineNumberByte(2),
+//!     Section::NOP,
+//! ]);
+//! let lines = obj.line_map()?;
+//! assert_eq!("MAIN.C:10", lines.lookup(1, 4).expect("line").to_string());
+//! assert_eq!("MAIN.C:12", lines.lookup(1, 8).expect("line").to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::{bail, Result};
+
+use crate::{FileIdOrName, Section, OBJ};
+
+/// A line of a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+/// Written as `file:line`.
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Where the code at an offset in a section came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineEntry {
+    pub section: u16,
+    pub offset: u32,
+    /// The location of the code from `offset` until the next entry in the
+    /// section, or `None` where line information ends.
+    pub location: Option<SourceLocation>,
+}
+
+/// A line information record that couldn't be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineWarning {
+    /// The index of the record within the OBJ.
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LineWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "section {}: {}", self.index, self.message)
+    }
+}
+
+/// The source locations of an OBJ's code. See [OBJ::line_map].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineMap {
+    /// Ordered by section and offset. Entries at the same offset are kept
+    /// in the order they were recorded.
+    entries: Vec<LineEntry>,
+    warnings: Vec<LineWarning>,
+}
+
+/// The line being tracked in one section.
+#[derive(Default)]
+struct State {
+    /// The size of the section so far.
+    size: u32,
+    /// The start of the last code in the section.
+    code_start: u32,
+    file: Option<u16>,
+    line: Option<u32>,
+}
+
+impl LineMap {
+    /// Runs the line information of `obj`. It is an error for a line number
+    /// to be incremented past `u32::MAX`.
+    pub fn of_obj(obj: &OBJ) -> Result<Self> {
+        let names = obj
+            .file_references()
+            .into_iter()
+            .map(|file_ref| (file_ref.id, file_ref.file))
+            .collect::<HashMap<u16, FileIdOrName>>();
+
+        let mut map = Self::default();
+        let mut states: HashMap<u16, State> = HashMap::new();
+        let mut current = None;
+        for (index, section) in obj.sections().iter().enumerate() {
+            // (offset relative to the last code, file, line, increment)
+            let (offset, file, line, increment) = match section {
+                Section::SectionSwitch(id) => {
+                    current = Some(*id);
+                    continue;
+                }
+                Section::Code(code) => {
+                    if let Some(state) = current.map(|id| states.entry(id).or_default()) {
+                        state.code_start = state.size;
+                        state.size += code.size() as u32;
+                    }
+                    continue;
+                }
+                Section::BSS(size) => {
+                    if let Some(state) = current.map(|id| states.entry(id).or_default()) {
+                        state.size += size;
+                    }
+                    continue;
+                }
+                Section::SetSLDLineNumFile(set) => {
+                    (Some(set.offset), Some(set.file), Some(set.linenum), 0)
+                }
+                Section::SetSLDLineNum(set) => (Some(set.offset), None, Some(set.linenum), 0),
+                Section::IncSLDLineNum(offset) => (Some(*offset), None, None, 1),
+                Section::IncSLDLineNumByte(offset, by) => (Some(*offset), None, None, *by as u32),
+                Section::IncSLDLineNumWord(offset, by) => (Some(*offset), None, None, *by),
+                Section::SetToFile(file, line) => (None, Some(*file), Some(*line), 0),
+                Section::SetToLine(line) => (None, None, Some(*line), 0),
+                Section::IncrementLineNumber => (None, None, None, 1),
+                Section::IncrementLineNumberByte(by) => (None, None, None, *by as u32),
+                Section::IncrementLineNumberWord(by) => (None, None, None, *by),
+                Section::EndSLDInfo(offset) => {
+                    let Some(id) = current else {
+                        map.warn(index, "line information ends before any section switch");
+                        continue;
+                    };
+                    let state = states.entry(id).or_default();
+                    state.line = None;
+                    map.entries.push(LineEntry {
+                        section: id,
+                        offset: state.code_start + *offset as u32,
+                        location: None,
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let Some(id) = current else {
+                map.warn(index, "line information comes before any section switch");
+                continue;
+            };
+            let state = states.entry(id).or_default();
+            if file.is_some() {
+                state.file = file;
+            }
+            let line = match (line, state.line) {
+                (Some(line), _) => line,
+                (None, Some(previous)) => match previous.checked_add(increment) {
+                    Some(line) => line,
+                    None => bail!("section {index}: line {previous} + {increment} overflows"),
+                },
+                (None, None) => {
+                    map.warn(index, "line number incremented before it is set");
+                    continue;
+                }
+            };
+            state.line = Some(line);
+            let Some(file) = state.file else {
+                map.warn(index, &format!("line {line} is set before any file"));
+                continue;
+            };
+            let file = match names.get(&file) {
+                Some(FileIdOrName::Name(name)) => name.clone(),
+                _ => {
+                    map.warn(index, &format!("file number {file:x} is not defined"));
+                    format!("{file:x}")
+                }
+            };
+            map.entries.push(LineEntry {
+                section: id,
+                offset: offset.map_or(state.size, |offset| state.code_start + offset as u32),
+                location: Some(SourceLocation { file, line }),
+            });
+        }
+        map.entries
+            .sort_by_key(|entry| (entry.section, entry.offset));
+        Ok(map)
+    }
+
+    fn warn(&mut self, index: usize, message: &str) {
+        self.warnings.push(LineWarning {
+            index,
+            message: message.to_string(),
+        });
+    }
+
+    /// Returns the source location of the code at `offset` in `section`,
+    /// if there is line information for it.
+    pub fn lookup(&self, section: u16, offset: u32) -> Option<SourceLocation> {
+        let end = self
+            .entries
+            .partition_point(|entry| (entry.section, entry.offset) <= (section, offset));
+        self.entries[..end]
+            .last()
+            .filter(|entry| entry.section == section)
+            .and_then(|entry| entry.location.clone())
+    }
+
+    /// Returns every entry, ordered by section and offset.
+    pub fn entries(&self) -> &[LineEntry] {
+        &self.entries
+    }
+
+    /// Returns an iterator over every entry. See [LineMap::entries].
+    pub fn iter(&self) -> impl Iterator<Item = &LineEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the records which couldn't be applied, in the order they
+    /// appear in the OBJ.
+    pub fn warnings(&self) -> &[LineWarning] {
+        &self.warnings
+    }
+
+    /// Returns `true` if there is no line information.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a LineMap {
+    type Item = &'a LineEntry;
+    type IntoIter = std::slice::Iter<'a, LineEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Filename, SetSLDLineNum, SetSLDLineNumFile};
+
+    fn set(offset: u16, linenum: u32, file: u16) -> Section {
+        Section::SetSLDLineNumFile(SetSLDLineNumFile {
+            offset,
+            linenum,
+            file,
+        })
+    }
+
+    fn set_line(offset: u16, linenum: u32) -> Section {
+        Section::SetSLDLineNum(SetSLDLineNum { offset, linenum })
+    }
+
+    fn location(file: &str, line: u32) -> Option<SourceLocation> {
+        Some(SourceLocation {
+            file: file.into(),
+            line,
+        })
+    }
+
+    #[test]
+    fn test_sld() {
+        let obj = OBJ::new(vec![
+            Section::Filename(Filename::new(1, "A.C").expect("filename")),
+            Section::Filename(Filename::new(2, "B.H").expect("filename")),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 16]).expect("code")),
+            set(0, 10, 1),
+            Section::IncSLDLineNum(4),
+            Section::IncSLDLineNumWord(8, 300),
+            set(12, 5, 2),
+            Section::Code(Code::new(vec![0; 8]).expect("code")),
+            set_line(0, 7),
+            Section::EndSLDInfo(4),
+            Section::NOP,
+        ]);
+        let lines = obj.line_map().expect("lines");
+        assert!(lines.warnings().is_empty());
+        assert_eq!(None, lines.lookup(0, 0));
+        assert_eq!(location("A.C", 10), lines.lookup(1, 0));
+        assert_eq!(location("A.C", 10), lines.lookup(1, 3));
+        assert_eq!(location("A.C", 11), lines.lookup(1, 4));
+        assert_eq!(location("A.C", 311), lines.lookup(1, 8));
+        assert_eq!(location("B.H", 5), lines.lookup(1, 12));
+        // offsets are relative to the second code
+        assert_eq!(location("B.H", 7), lines.lookup(1, 16));
+        assert_eq!(None, lines.lookup(1, 20));
+        assert_eq!(None, lines.lookup(2, 0));
+        assert_eq!(
+            vec![0, 4, 8, 12, 16, 20],
+            lines.iter().map(|e| e.offset).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let obj = OBJ::new(vec![
+            Section::IncSLDLineNum(0),
+            Section::SectionSwitch(1),
+            Section::IncSLDLineNum(0),
+            set_line(0, 3),
+            Section::SetToFile(9, 4),
+            Section::NOP,
+        ]);
+        let lines = obj.line_map().expect("lines");
+        assert_eq!(
+            vec![
+                "section 0: line information comes before any section switch",
+                "section 2: line number incremented before it is set",
+                "section 3: line 3 is set before any file",
+                "section 4: file number 9 is not defined",
+            ],
+            lines
+                .warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(location("9", 4), lines.lookup(1, 0));
+
+        let overflow = OBJ::new(vec![
+            Section::SectionSwitch(1),
+            Section::SetToFile(1, u32::MAX),
+            Section::IncrementLineNumber,
+            Section::NOP,
+        ]);
+        assert!(overflow.line_map().is_err());
+    }
+}
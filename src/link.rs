@@ -312,6 +312,24 @@ use winnow::token::take_while;
 use winnow::ModalResult;
 use winnow::Parser;
 
+mod closure;
+mod compat;
+mod graph;
+mod linker;
+mod reach;
+mod resolve;
+
+pub use closure::{closure, resolve_members, ClosureResult, EmitMode};
+pub use compat::{
+    section_compat, section_compat_named, CompatIssue, CompatIssueKind, CompatSource,
+};
+pub use graph::{Dependency, DependencyGraph, ExternalSymbol};
+pub use linker::{
+    Executable, LinkedSection, Linker, DEFAULT_TEXT_BASE, ENTRY_POINT, EXE_HEADER_SIZE,
+};
+pub use reach::{reachable_modules, LibModule, ReachabilityReport};
+pub use resolve::{resolve_definitions, DuplicateDefinition, ResolvedSymbol, SymbolResolution};
+
 #[derive(Debug, PartialEq)]
 pub enum Attribute {
     BSS,
@@ -0,0 +1,580 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Extracting the modules of a LIB that a set of symbols depends on.
+//!
+//! [closure] starts from the modules that define some root symbols and
+//! follows each module's [XREF](crate::XREF)s to the modules that define
+//! them, the way the linker pulls members out of a library. Symbols that no
+//! module in the LIB defines are left as imports. The modules found can be
+//! written out as they are or [combined](EmitMode::Combined) into a single
+//! OBJ, for example to build an overlay.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::{io, link};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+//! let closure = link::closure(&lib, &["helper"])?;
+//! assert_eq!(vec!["HELPER".to_string()], closure.module_names());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::error::{bail, Result};
+
+use crate::{DefinitionStrength, Expression, Module, Section, LIB, OBJ};
+
+/// How [ClosureResult::emit] writes out the modules of a closure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmitMode {
+    /// One OBJ per module, unchanged.
+    #[default]
+    SeparateModules,
+    /// A single OBJ holding every module. References between the modules
+    /// are resolved, so only the closure's imports remain as XREFs.
+    Combined,
+}
+
+/// The modules of a LIB needed to define a set of symbols.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosureResult {
+    /// The modules needed, in the order they appear in the LIB.
+    pub modules: Vec<Module>,
+    /// The symbols referenced by the modules that none of them define,
+    /// sorted.
+    pub imports: Vec<String>,
+}
+
+impl ClosureResult {
+    /// The names of the modules in the closure.
+    pub fn module_names(&self) -> Vec<String> {
+        self.modules.iter().map(Module::name).collect()
+    }
+
+    /// The symbols defined by the modules in the closure, sorted.
+    pub fn exports(&self) -> Vec<String> {
+        self.modules
+            .iter()
+            .flat_map(|m| m.object().exports())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The number of bytes of code and initialized data in the closure.
+    pub fn code_size(&self) -> u64 {
+        self.sections()
+            .map(|section| match section {
+                Section::Code(code) => code.size as u64,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// The number of bytes of uninitialized data in the closure, including
+    /// external BSS symbols.
+    pub fn bss_size(&self) -> u64 {
+        self.sections()
+            .map(|section| match section {
+                Section::BSS(size) => *size as u64,
+                Section::XBSS(xbss) => xbss.size as u64,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.modules.iter().flat_map(|m| m.object().sections())
+    }
+
+    /// Returns each OBJ to write and its name. Modules are named after
+    /// themselves; a [combined](EmitMode::Combined) OBJ is named `OVERLAY`.
+    pub fn emit(&self, mode: EmitMode) -> Vec<(String, OBJ)> {
+        match mode {
+            EmitMode::SeparateModules => self
+                .modules
+                .iter()
+                .map(|m| (m.name(), m.object().clone()))
+                .collect(),
+            EmitMode::Combined => vec![("OVERLAY".into(), combine(&self.modules))],
+        }
+    }
+}
+
+/// Finds the modules of `lib` needed to define each of `roots`.
+///
+/// Each symbol is taken from the first module in the LIB that strongly
+/// defines it, or the first that defines it at all if none do. It is an
+/// error for a root not to be defined by any module.
+pub fn closure(lib: &LIB, roots: &[&str]) -> Result<ClosureResult> {
+    resolve_members(lib, &[], roots)
+}
+
+/// Finds the modules of `lib` needed to link `objects`, and to define each
+/// of `roots`, the way the linker selects library members.
+///
+/// Members are only pulled in for symbols that `objects` reference but
+/// don't define, so a definition in `objects` is used in place of a
+/// library's. The imports of the result include the symbols `objects`
+/// reference that neither they nor the selected modules define.
+pub fn resolve_members(lib: &LIB, objects: &[&OBJ], roots: &[&str]) -> Result<ClosureResult> {
+    let modules = lib.modules();
+    let mut definitions: HashMap<String, (usize, DefinitionStrength)> = HashMap::new();
+    for (index, module) in modules.iter().enumerate() {
+        for definition in module.object().definitions() {
+            let replace = definitions
+                .get(&definition.name)
+                .is_none_or(|(_, strength)| {
+                    *strength == DefinitionStrength::Common
+                        && definition.strength == DefinitionStrength::Strong
+                });
+            if replace {
+                definitions.insert(definition.name, (index, definition.strength));
+            }
+        }
+    }
+    let provided: HashSet<String> = objects.iter().flat_map(|obj| obj.exports()).collect();
+
+    let mut selected: BTreeSet<usize> = BTreeSet::new();
+    let mut pending: Vec<String> = objects.iter().flat_map(|obj| references(obj)).collect();
+    for root in roots {
+        if provided.contains(*root) {
+            continue;
+        }
+        let Some((index, _)) = definitions.get(*root) else {
+            bail!("{root} is not defined by any module");
+        };
+        if selected.insert(*index) {
+            pending.extend(references(modules[*index].object()));
+        }
+    }
+    while let Some(name) = pending.pop() {
+        if provided.contains(&name) {
+            continue;
+        }
+        if let Some((index, _)) = definitions.get(&name) {
+            if selected.insert(*index) {
+                pending.extend(references(modules[*index].object()));
+            }
+        }
+    }
+
+    let modules: Vec<Module> = selected.into_iter().map(|i| modules[i].clone()).collect();
+    let defined: BTreeSet<String> = modules
+        .iter()
+        .flat_map(|m| m.object().exports())
+        .chain(provided)
+        .collect();
+    let imports = modules
+        .iter()
+        .map(Module::object)
+        .chain(objects.iter().copied())
+        .flat_map(references)
+        .filter(|name| !defined.contains(name))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Ok(ClosureResult { modules, imports })
+}
+
+pub(super) fn references(obj: &OBJ) -> Vec<String> {
+    obj.sections()
+        .iter()
+        .filter_map(|section| match section {
+            Section::XREF(xref) => Some(xref.symbol_name()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Concatenates the sections of `modules` into one OBJ.
+///
+/// Section, group, and symbol ids share one number space in an OBJ, as do
+/// file numbers, so every module's ids are renumbered. Each module keeps its
+/// own sections. XREFs to symbols defined by another module are dropped and
+/// their uses point at the definition instead, and XREFs to the same import
+/// are merged.
+fn combine(modules: &[Module]) -> OBJ {
+    let mut next_id: u16 = 1;
+    let mut allocate = || {
+        let id = next_id;
+        next_id += 1;
+        id
+    };
+
+    // definitions first, so references in any module can be resolved
+    let mut ids: Vec<HashMap<u16, u16>> = vec![HashMap::new(); modules.len()];
+    let mut definitions: HashMap<String, (u16, DefinitionStrength)> = HashMap::new();
+    let mut define = |name: String, id: u16, strength: DefinitionStrength| {
+        let replace = definitions.get(&name).is_none_or(|(_, existing)| {
+            *existing == DefinitionStrength::Common && strength == DefinitionStrength::Strong
+        });
+        if replace {
+            definitions.insert(name, (id, strength));
+        }
+    };
+    for (module, ids) in modules.iter().zip(ids.iter_mut()) {
+        let strengths: HashMap<String, DefinitionStrength> = module
+            .object()
+            .definitions()
+            .into_iter()
+            .map(|d| (d.name, d.strength))
+            .collect();
+        for section in module.object().sections() {
+            match section {
+                Section::LNKHeader(header) => {
+                    ids.insert(header.section, allocate());
+                }
+                Section::GroupSymbol(group) => {
+                    ids.insert(group.number, allocate());
+                }
+                Section::XDEF(xdef) => {
+                    let id = allocate();
+                    ids.insert(xdef.number, id);
+                    let name = xdef.symbol_name();
+                    let strength = strengths[&name];
+                    define(name, id, strength);
+                }
+                Section::XBSS(xbss) => {
+                    let id = allocate();
+                    ids.insert(xbss.number, id);
+                    define(xbss.name(), id, DefinitionStrength::Common);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut imports: HashMap<String, u16> = HashMap::new();
+    let mut next_file: u16 = 1;
+    let mut cpu = None;
+    let mut sections = Vec::new();
+    for (module, ids) in modules.iter().zip(ids.iter_mut()) {
+        let mut files: HashMap<u16, u16> = HashMap::new();
+        for section in module.object().sections() {
+            match section {
+                Section::XREF(xref) => {
+                    let name = xref.symbol_name();
+                    let definition = definitions.get(&name).map(|(id, _)| id);
+                    if let Some(id) = definition.or(imports.get(&name)) {
+                        ids.insert(xref.number, *id);
+                        continue;
+                    }
+                    let id = allocate();
+                    ids.insert(xref.number, id);
+                    imports.insert(name, id);
+                }
+                Section::Filename(filename) => {
+                    files.insert(filename.number, next_file);
+                    next_file += 1;
+                }
+                Section::CPU(value) if cpu.replace(*value) == Some(*value) => continue,
+                Section::NOP => continue,
+                _ => (),
+            }
+            sections.push(renumber(section, ids, &files));
+        }
+    }
+    sections.push(Section::NOP);
+    OBJ::new(sections)
+}
+
+/// Returns a copy of `section` with its ids and file numbers replaced.
+/// Numbers without a replacement are left alone.
+fn renumber(section: &Section, ids: &HashMap<u16, u16>, files: &HashMap<u16, u16>) -> Section {
+    let id = |n: &mut u16| {
+        if let Some(new) = ids.get(n) {
+            *n = *new;
+        }
+    };
+    let file = |n: &mut u16| {
+        if let Some(new) = files.get(n) {
+            *n = *new;
+        }
+    };
+
+    let mut section = section.clone();
+    match &mut section {
+        Section::LNKHeader(header) => {
+            id(&mut header.section);
+            id(&mut header.group);
+        }
+        Section::SectionSwitch(section) => id(section),
+        Section::XDEF(xdef) => {
+            id(&mut xdef.number);
+            id(&mut xdef.section);
+        }
+        Section::XREF(xref) => id(&mut xref.number),
+        Section::XBSS(xbss) => {
+            id(&mut xbss.number);
+            id(&mut xbss.section);
+        }
+        Section::LocalSymbol(symbol) | Section::VeryLocalSymbol(symbol) => id(&mut symbol.section),
+        Section::GroupSymbol(group) => id(&mut group.number),
+        Section::Patch(patch) => patch.expression.walk_mut(&mut |e| match e {
+            Expression::SymbolAddressIndex(n)
+            | Expression::SectionAddressIndex(n)
+            | Expression::Bank(n)
+            | Expression::SectionOffset(n)
+            | Expression::Offset(n)
+            | Expression::SectionStart(n)
+            | Expression::GroupStart(n)
+            | Expression::GroupOffset(n)
+            | Expression::Segment(n)
+            | Expression::GroupOrg(n)
+            | Expression::SectionEnd(n) => id(n),
+            _ => (),
+        }),
+        Section::Filename(filename) => file(&mut filename.number),
+        Section::SetToFile(n, _) => file(n),
+        Section::SetSLDLineNumFile(line) => file(&mut line.file),
+        Section::FunctionStart(start) => {
+            id(&mut start.section);
+            file(&mut start.file);
+        }
+        Section::FunctionEnd(end) | Section::BlockStart(end) | Section::BlockEnd(end) => {
+            id(&mut end.section)
+        }
+        Section::Def(def) => id(&mut def.section),
+        Section::Def2(def) => id(&mut def.section),
+        Section::ProcedureCall(call) => id(&mut call.symbol),
+        Section::ProcedureDefinition(definition) => id(&mut definition.symbol),
+        _ => (),
+    }
+    section
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, CpuType, LNKHeader, ModuleMetadata, Patch, XDEF, XREF};
+    use std::time::SystemTime;
+
+    fn xdef(number: u16, name: &str) -> Section {
+        Section::XDEF(XDEF {
+            number,
+            section: 1,
+            offset: 0,
+            symbol_name_size: name.len() as u8,
+            symbol_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn xref(number: u16, name: &str) -> Section {
+        Section::XREF(XREF {
+            number,
+            symbol_name_size: name.len() as u8,
+            symbol_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    /// A module with a `.text` section 1 of `size` bytes that defines
+    /// `defines` and calls each of `calls`.
+    fn module(name: &str, size: u16, defines: &[&str], calls: &[&str]) -> Module {
+        let mut sections = vec![
+            Section::CPU(CpuType::MipsR3000),
+            Section::LNKHeader(LNKHeader {
+                section: 1,
+                group: 0,
+                align: 8,
+                type_name_size: 5,
+                type_name: b".text".to_vec(),
+            }),
+            Section::SectionSwitch(1),
+            Section::Code(Code {
+                size,
+                deferred: None,
+                code: vec![0; size as usize],
+            }),
+        ];
+        for (i, call) in calls.iter().enumerate() {
+            let number = 10 + i as u16;
+            sections.push(xref(number, call));
+            sections.push(Section::Patch(Patch {
+                tag: 74,
+                offset: 0,
+                expression: Expression::SymbolAddressIndex(number),
+            }));
+        }
+        for (i, name) in defines.iter().enumerate() {
+            sections.push(xdef(2 + i as u16, name));
+        }
+        sections.push(Section::NOP);
+        let metadata =
+            ModuleMetadata::new(name.into(), SystemTime::now(), 0, vec![]).expect("metadata");
+        Module::new(OBJ::new(sections), metadata)
+    }
+
+    fn lib() -> LIB {
+        LIB::new(vec![
+            module("A", 4, &["a"], &["b", "printf"]),
+            module("B", 8, &["b", "b2"], &["c"]),
+            module("C", 12, &["c"], &["printf", "a"]),
+            module("D", 16, &["d"], &["a"]),
+            module("E", 20, &["c"], &[]),
+        ])
+    }
+
+    fn xrefs(obj: &OBJ) -> Vec<(u16, String)> {
+        obj.sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::XREF(x) => Some((x.number, x.symbol_name())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_closure() {
+        let lib = lib();
+        let closure = closure(&lib, &["a"]).expect("closure");
+        assert_eq!(vec!["A", "B", "C"], closure.module_names());
+        assert_eq!(vec!["printf"], closure.imports);
+        assert_eq!(vec!["a", "b", "b2", "c"], closure.exports());
+        assert_eq!(24, closure.code_size());
+        assert_eq!(0, closure.bss_size());
+
+        let closure = super::closure(&lib, &["c"]).expect("closure");
+        assert_eq!(vec!["A", "B", "C"], closure.module_names());
+
+        let closure = super::closure(&lib, &["b2", "d"]).expect("closure");
+        assert_eq!(vec!["A", "B", "C", "D"], closure.module_names());
+
+        assert!(super::closure(&lib, &["missing"]).is_err());
+    }
+
+    /// A module reserving `size` bytes of `.bss` section 2 for `name`.
+    fn common(module_name: &str, name: &str, size: u32) -> Module {
+        let sections = vec![
+            Section::LNKHeader(LNKHeader {
+                section: 2,
+                group: 0,
+                align: 8,
+                type_name_size: 4,
+                type_name: b".bss".to_vec(),
+            }),
+            Section::SectionSwitch(2),
+            Section::BSS(size),
+            Section::XDEF(XDEF {
+                number: 3,
+                section: 2,
+                offset: 0,
+                symbol_name_size: name.len() as u8,
+                symbol_name: name.as_bytes().to_vec(),
+            }),
+            Section::NOP,
+        ];
+        let metadata = ModuleMetadata::new(module_name.into(), SystemTime::now(), 0, vec![])
+            .expect("metadata");
+        Module::new(OBJ::new(sections), metadata)
+    }
+
+    #[test]
+    fn test_closure_prefers_strong() {
+        let lib = LIB::new(vec![
+            common("HEAP", "heap", 16),
+            module("A", 4, &["a"], &["heap"]),
+            module("STRONG", 4, &["heap"], &[]),
+        ]);
+        let closure = closure(&lib, &["a"]).expect("closure");
+        assert_eq!(vec!["A", "STRONG"], closure.module_names());
+
+        let lib = LIB::new(vec![
+            common("HEAP", "heap", 16),
+            module("A", 4, &["a"], &["heap"]),
+        ]);
+        let closure = super::closure(&lib, &["a"]).expect("closure");
+        assert_eq!(vec!["HEAP", "A"], closure.module_names());
+
+        let combined = &closure.emit(EmitMode::Combined)[0].1;
+        assert!(xrefs(combined).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_members() {
+        let lib = lib();
+        let user = module("USER", 4, &["b", "main"], &["a", "exit"]);
+        let members = resolve_members(&lib, &[user.object()], &[]).expect("members");
+        // the user's b is used in place of B, so C isn't needed
+        assert_eq!(vec!["A"], members.module_names());
+        assert_eq!(vec!["exit", "printf"], members.imports);
+
+        let members = resolve_members(&lib, &[user.object()], &["main", "d"]).expect("members");
+        assert_eq!(vec!["A", "D"], members.module_names());
+        assert!(resolve_members(&lib, &[user.object()], &["missing"]).is_err());
+    }
+
+    #[test]
+    fn test_emit_separate() {
+        let closure = closure(&lib(), &["b"]).expect("closure");
+        let emitted = closure.emit(EmitMode::SeparateModules);
+        assert_eq!(
+            vec!["A", "B", "C"],
+            emitted.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(closure.modules[1].object(), &emitted[1].1);
+    }
+
+    #[test]
+    fn test_emit_combined() {
+        let closure = closure(&lib(), &["a"]).expect("closure");
+        let emitted = closure.emit(EmitMode::Combined);
+        assert_eq!(1, emitted.len());
+        let (name, obj) = &emitted[0];
+        assert_eq!("OVERLAY", name);
+
+        assert_eq!(closure.exports(), {
+            let mut exports = obj.exports();
+            exports.sort();
+            exports
+        });
+        let xrefs = xrefs(obj);
+        assert_eq!(1, xrefs.len());
+        assert_eq!("printf", xrefs[0].1);
+
+        // one CPU, three sections, and a single terminating NOP
+        let count = |kind| obj.sections().iter().filter(|s| s.kind() == kind).count();
+        assert_eq!(1, count("CPU"));
+        assert_eq!(3, count("LNKHeader"));
+        assert_eq!(1, count("NOP"));
+
+        // every patch refers to an XDEF or the remaining XREF
+        let mut defined: HashMap<u16, String> = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::XDEF(x) => Some((x.number, x.symbol_name())),
+                _ => None,
+            })
+            .collect();
+        defined.extend(xrefs);
+        let targets: Vec<String> = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::Patch(p) => match p.expression {
+                    Expression::SymbolAddressIndex(n) => Some(defined[&n].clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["b", "printf", "c", "printf", "a"], targets);
+
+        // section switches follow their module's renumbered header
+        let switches: Vec<u16> = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::SectionSwitch(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(3, switches.iter().collect::<BTreeSet<_>>().len());
+    }
+}
@@ -0,0 +1,293 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Checking that OBJs destined for one link agree about their sections.
+//!
+//! Each OBJ declares its sections with an [LNKHeader](crate::LNKHeader):
+//! an assembler-assigned id, a type name like `.text`, an alignment, and an
+//! optional group. OBJs from different compilers or assemblers can declare
+//! the same section differently. How the linker resolves that isn't
+//! documented, so [section_compat] reports it before linking.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::verify::Severity;
+use crate::{Section, OBJ};
+
+/// Where a section declaration came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompatSource {
+    /// The index of the OBJ in the slice that was checked.
+    pub obj: usize,
+    /// The name of the OBJ or module, or `#` and its index if it wasn't
+    /// named.
+    pub name: String,
+    /// The id of the section in the OBJ.
+    pub section: u16,
+}
+
+impl fmt::Display for CompatSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (section {})", self.name, self.section)
+    }
+}
+
+/// The specific conflict described by a [CompatIssue]. Each pairs the first
+/// declaration found with a later one that disagrees with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompatIssueKind {
+    /// Sections with the same type name have different alignments.
+    Alignment {
+        type_name: String,
+        first: (CompatSource, u8),
+        second: (CompatSource, u8),
+    },
+    /// Sections with the same type name are in different groups.
+    Group {
+        type_name: String,
+        first: (CompatSource, u16),
+        second: (CompatSource, u16),
+    },
+    /// Sections with the same id have different type names.
+    SectionId {
+        id: u16,
+        first: (CompatSource, String),
+        second: (CompatSource, String),
+    },
+}
+
+/// A disagreement between the section declarations of two OBJs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompatIssue {
+    /// [Severity::Error] for [SectionId](CompatIssueKind::SectionId)
+    /// conflicts, otherwise [Severity::Warning].
+    pub severity: Severity,
+    pub kind: CompatIssueKind,
+}
+
+impl fmt::Display for CompatIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        match &self.kind {
+            CompatIssueKind::Alignment {
+                type_name,
+                first,
+                second,
+            } => write!(
+                f,
+                "{type_name} is aligned to {} in {} but {} in {}",
+                first.1, first.0, second.1, second.0
+            ),
+            CompatIssueKind::Group {
+                type_name,
+                first,
+                second,
+            } => write!(
+                f,
+                "{type_name} is in group {} in {} but group {} in {}",
+                first.1, first.0, second.1, second.0
+            ),
+            CompatIssueKind::SectionId { id, first, second } => write!(
+                f,
+                "section {id} is {} in {} but {} in {}",
+                first.1, first.0.name, second.1, second.0.name
+            ),
+        }
+    }
+}
+
+/// Checks the section declarations of `objs` against each other. Sources
+/// are named by their index. See [section_compat_named].
+pub fn section_compat(objs: &[&OBJ]) -> Vec<CompatIssue> {
+    let names = (0..objs.len()).map(|i| format!("#{i}")).collect::<Vec<_>>();
+    section_compat_named(
+        &names
+            .iter()
+            .zip(objs)
+            .map(|(name, obj)| (name.as_str(), *obj))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Checks the section declarations of `objs`, each a name and an OBJ, for:
+///
+/// * sections with the same type name and different alignments
+/// * sections with the same type name in different groups
+/// * sections with the same id and different type names
+///
+/// Each conflicting value is reported once, against the first declaration
+/// of the section.
+pub fn section_compat_named(objs: &[(&str, &OBJ)]) -> Vec<CompatIssue> {
+    let mut by_name: BTreeMap<String, (CompatSource, u8, u16)> = BTreeMap::new();
+    let mut by_id: BTreeMap<u16, (CompatSource, String)> = BTreeMap::new();
+    let mut issues: Vec<CompatIssue> = Vec::new();
+    // (kind, section, conflicting value) of each issue, so each is reported once
+    let mut reported: BTreeSet<(u8, String, String)> = BTreeSet::new();
+    let mut report = |key: (u8, String, String), severity: Severity, kind: CompatIssueKind| {
+        if reported.insert(key) {
+            issues.push(CompatIssue { severity, kind });
+        }
+    };
+
+    for (index, (name, obj)) in objs.iter().enumerate() {
+        for section in obj.sections() {
+            let Section::LNKHeader(header) = section else {
+                continue;
+            };
+            let source = CompatSource {
+                obj: index,
+                name: name.to_string(),
+                section: header.section(),
+            };
+            let type_name = header.type_name();
+
+            let (first, align, group) = by_name
+                .entry(type_name.clone())
+                .or_insert_with(|| (source.clone(), header.align(), header.group()))
+                .clone();
+            if header.align() != align {
+                let kind = CompatIssueKind::Alignment {
+                    type_name: type_name.clone(),
+                    first: (first.clone(), align),
+                    second: (source.clone(), header.align()),
+                };
+                let key = (0, type_name.clone(), header.align().to_string());
+                report(key, Severity::Warning, kind);
+            }
+            if header.group() != group {
+                let kind = CompatIssueKind::Group {
+                    type_name: type_name.clone(),
+                    first: (first, group),
+                    second: (source.clone(), header.group()),
+                };
+                let key = (1, type_name.clone(), header.group().to_string());
+                report(key, Severity::Warning, kind);
+            }
+
+            let (first, first_name) = by_id
+                .entry(header.section())
+                .or_insert_with(|| (source.clone(), type_name.clone()))
+                .clone();
+            if type_name != first_name {
+                let key = (2, header.section().to_string(), type_name.clone());
+                let kind = CompatIssueKind::SectionId {
+                    id: header.section(),
+                    first: (first, first_name),
+                    second: (source, type_name),
+                };
+                report(key, Severity::Error, kind);
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LNKHeader;
+
+    fn header(section: u16, group: u16, align: u8, type_name: &str) -> Section {
+        Section::LNKHeader(LNKHeader {
+            section,
+            group,
+            align,
+            type_name_size: type_name.len() as u8,
+            type_name: type_name.as_bytes().to_vec(),
+        })
+    }
+
+    fn obj(headers: Vec<Section>) -> OBJ {
+        let mut sections = headers;
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    fn source(obj: usize, name: &str, section: u16) -> CompatSource {
+        CompatSource {
+            obj,
+            name: name.into(),
+            section,
+        }
+    }
+
+    #[test]
+    fn test_consistent() {
+        let a = obj(vec![header(1, 0, 8, ".text"), header(2, 0, 8, ".data")]);
+        let b = obj(vec![header(1, 0, 8, ".text")]);
+        assert!(section_compat(&[&a, &b, &a]).is_empty());
+    }
+
+    #[test]
+    fn test_alignment() {
+        let a = obj(vec![header(2, 0, 8, ".data")]);
+        let b = obj(vec![header(2, 0, 4, ".data")]);
+        let c = obj(vec![header(2, 0, 4, ".data")]);
+        let issues = section_compat_named(&[("A.OBJ", &a), ("B.OBJ", &b), ("C.OBJ", &c)]);
+        assert_eq!(
+            vec![CompatIssue {
+                severity: Severity::Warning,
+                kind: CompatIssueKind::Alignment {
+                    type_name: ".data".into(),
+                    first: (source(0, "A.OBJ", 2), 8),
+                    second: (source(1, "B.OBJ", 2), 4),
+                },
+            }],
+            issues
+        );
+        assert_eq!(
+            "warning: .data is aligned to 8 in A.OBJ (section 2) but 4 in B.OBJ (section 2)",
+            issues[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_group() {
+        let a = obj(vec![header(1, 0, 8, ".text")]);
+        let b = obj(vec![header(1, 3, 8, ".text")]);
+        assert_eq!(
+            vec![CompatIssue {
+                severity: Severity::Warning,
+                kind: CompatIssueKind::Group {
+                    type_name: ".text".into(),
+                    first: (source(0, "#0", 1), 0),
+                    second: (source(1, "#1", 1), 3),
+                },
+            }],
+            section_compat(&[&a, &b])
+        );
+    }
+
+    #[test]
+    fn test_section_id() {
+        let a = obj(vec![header(1, 0, 8, ".text"), header(2, 0, 8, ".data")]);
+        let b = obj(vec![header(1, 0, 8, ".data"), header(2, 0, 8, ".text")]);
+        let issues = section_compat_named(&[("A.OBJ", &a), ("B.OBJ", &b)]);
+        assert_eq!(
+            vec![
+                CompatIssue {
+                    severity: Severity::Error,
+                    kind: CompatIssueKind::SectionId {
+                        id: 1,
+                        first: (source(0, "A.OBJ", 1), ".text".into()),
+                        second: (source(1, "B.OBJ", 1), ".data".into()),
+                    },
+                },
+                CompatIssue {
+                    severity: Severity::Error,
+                    kind: CompatIssueKind::SectionId {
+                        id: 2,
+                        first: (source(0, "A.OBJ", 2), ".data".into()),
+                        second: (source(1, "B.OBJ", 2), ".text".into()),
+                    },
+                },
+            ],
+            issues
+        );
+        assert_eq!(
+            "error: section 1 is .text in A.OBJ but .data in B.OBJ",
+            issues[0].to_string()
+        );
+    }
+}
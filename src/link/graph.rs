@@ -0,0 +1,287 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Which modules of a LIB depend on which.
+//!
+//! A module depends on another when it [XREF](crate::XREF)s a symbol the
+//! other defines with an [XDEF](crate::XDEF) or [XBSS](crate::XBSS). Symbols
+//! no module defines are external, left for other LIBs or the program to
+//! provide.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::io;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+//! let graph = lib.dependency_graph();
+//! assert_eq!(vec!["MAIN", "HELPER"], graph.modules);
+//! assert!(graph.dependencies.is_empty());
+//! assert!(graph.to_dot().starts_with("digraph dependencies {"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use super::closure::references;
+use crate::LIB;
+
+/// Module `from` references `symbols` that module `to` defines. Modules are
+/// indexes into [DependencyGraph::modules].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub from: usize,
+    pub to: usize,
+    /// The symbols, sorted.
+    pub symbols: Vec<String>,
+}
+
+/// A symbol no module defines and the modules that reference it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalSymbol {
+    pub name: String,
+    /// Indexes into [DependencyGraph::modules], in order.
+    pub referenced_by: Vec<usize>,
+}
+
+/// The modules of a [LIB] and the symbols they need from each other. See
+/// [LIB::dependency_graph].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// The module names, in the order of the LIB.
+    pub modules: Vec<String>,
+    /// One edge for each pair of modules, sorted by `from` and then `to`.
+    /// A module's references to its own symbols aren't included.
+    pub dependencies: Vec<Dependency>,
+    /// The symbols referenced that no module defines, sorted by name.
+    pub external: Vec<ExternalSymbol>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph of `lib`. A symbol defined by more than one module
+    /// comes from the first, as it would when linking.
+    pub fn of_lib(lib: &LIB) -> Self {
+        let mut definitions: HashMap<String, usize> = HashMap::new();
+        for (i, module) in lib.modules().iter().enumerate() {
+            for name in module.object().exports() {
+                definitions.entry(name).or_insert(i);
+            }
+        }
+
+        let mut edges: BTreeMap<(usize, usize), BTreeSet<String>> = BTreeMap::new();
+        let mut external: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (from, module) in lib.modules().iter().enumerate() {
+            for name in references(module.object()) {
+                match definitions.get(&name) {
+                    Some(&to) if to == from => (),
+                    Some(&to) => {
+                        edges.entry((from, to)).or_default().insert(name);
+                    }
+                    None => {
+                        external.entry(name).or_default().insert(from);
+                    }
+                }
+            }
+        }
+
+        Self {
+            modules: lib.modules().iter().map(|m| m.name()).collect(),
+            dependencies: edges
+                .into_iter()
+                .map(|((from, to), symbols)| Dependency {
+                    from,
+                    to,
+                    symbols: symbols.into_iter().collect(),
+                })
+                .collect(),
+            external: external
+                .into_iter()
+                .map(|(name, modules)| ExternalSymbol {
+                    name,
+                    referenced_by: modules.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the graph in the Graphviz DOT language. Each edge is labeled
+    /// with its symbols, and external symbols are grouped in a cluster named
+    /// `external`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (i, name) in self.modules.iter().enumerate() {
+            dot += &format!("    m{i} [label={}];\n", dot_string(name));
+        }
+        for dependency in &self.dependencies {
+            dot += &format!(
+                "    m{} -> m{} [label={}];\n",
+                dependency.from,
+                dependency.to,
+                dot_string(&dependency.symbols.join("\n"))
+            );
+        }
+        if !self.external.is_empty() {
+            dot += "    subgraph cluster_external {\n";
+            dot += "        label=\"external\";\n";
+            dot += "        node [shape=box, style=dashed];\n";
+            for (i, symbol) in self.external.iter().enumerate() {
+                dot += &format!("        x{i} [label={}];\n", dot_string(&symbol.name));
+            }
+            dot += "    }\n";
+            for (i, symbol) in self.external.iter().enumerate() {
+                for from in &symbol.referenced_by {
+                    dot += &format!("    m{from} -> x{i} [style=dashed];\n");
+                }
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+}
+
+/// `s` as a quoted DOT string. Newlines become `\n`, which DOT centers.
+fn dot_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// Lists each module followed by the modules it depends on and the symbols
+/// it needs from them, then each external symbol followed by the modules
+/// that reference it.
+impl fmt::Display for DependencyGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, name) in self.modules.iter().enumerate() {
+            write!(f, "{name}")?;
+            let dependencies = self
+                .dependencies
+                .iter()
+                .filter(|d| d.from == i)
+                .map(|d| format!("{} ({})", self.modules[d.to], d.symbols.join(", ")))
+                .collect::<Vec<_>>();
+            if !dependencies.is_empty() {
+                write!(f, " -> {}", dependencies.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        if !self.external.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "external:")?;
+        }
+        for symbol in &self.external {
+            let modules = symbol
+                .referenced_by
+                .iter()
+                .map(|&i| self.modules[i].as_str())
+                .collect::<Vec<_>>();
+            writeln!(f, "{} <- {}", symbol.name, modules.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::{Export, Module, ModuleMetadata, Section, OBJ, XDEF, XREF};
+
+    fn module(name: &str, defines: &[&str], calls: &[&str]) -> Module {
+        let mut sections = Vec::new();
+        for (i, name) in defines.iter().enumerate() {
+            sections.push(Section::XDEF(XDEF::new(i as u16 + 1, 1, 0, name).unwrap()));
+        }
+        for (i, name) in calls.iter().enumerate() {
+            sections.push(Section::XREF(XREF::new(i as u16 + 100, name).unwrap()));
+        }
+        sections.push(Section::NOP);
+        let exports = defines
+            .iter()
+            .map(|e| Export::new(e.to_string()).expect("export"))
+            .collect();
+        let metadata =
+            ModuleMetadata::new(name.into(), SystemTime::now(), 0, exports).expect("metadata");
+        Module::new(OBJ::new(sections), metadata)
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let lib = LIB::new(vec![
+            module(
+                "PRINTF",
+                &["printf", "vprintf"],
+                &["putchar", "strlen", "vprintf"],
+            ),
+            module("STRLEN", &["strlen"], &[]),
+            module("PUTS", &["puts"], &["putchar", "strlen", "write"]),
+            module("PUTCHAR", &["putchar"], &["write"]),
+            module("STRLEN2", &["strlen"], &["abort"]),
+        ]);
+        let graph = lib.dependency_graph();
+        assert_eq!(
+            vec!["PRINTF", "STRLEN", "PUTS", "PUTCHAR", "STRLEN2"],
+            graph.modules
+        );
+        let edges = graph
+            .dependencies
+            .iter()
+            .map(|d| (d.from, d.to, d.symbols.join(",")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, 1, "strlen".to_string()),
+                (0, 3, "putchar".to_string()),
+                (2, 1, "strlen".to_string()),
+                (2, 3, "putchar".to_string()),
+            ],
+            edges
+        );
+        assert_eq!(
+            vec![
+                ExternalSymbol {
+                    name: "abort".into(),
+                    referenced_by: vec![4],
+                },
+                ExternalSymbol {
+                    name: "write".into(),
+                    referenced_by: vec![2, 3],
+                },
+            ],
+            graph.external
+        );
+
+        assert_eq!(
+            "\
+PRINTF -> STRLEN (strlen), PUTCHAR (putchar)
+STRLEN
+PUTS -> STRLEN (strlen), PUTCHAR (putchar)
+PUTCHAR
+STRLEN2
+
+external:
+abort <- STRLEN2
+write <- PUTS, PUTCHAR
+",
+            graph.to_string()
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph dependencies {\n"), "{dot}");
+        assert!(dot.ends_with("}\n"), "{dot}");
+        assert!(dot.contains("    m0 [label=\"PRINTF\"];\n"), "{dot}");
+        assert!(dot.contains("    m0 -> m3 [label=\"putchar\"];\n"), "{dot}");
+        assert!(dot.contains("        x1 [label=\"write\"];\n"), "{dot}");
+        assert!(dot.contains("    m3 -> x1 [style=dashed];\n"), "{dot}");
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+    }
+
+    #[test]
+    fn test_dot_string() {
+        assert_eq!(r#""a\"b\\c\nd""#, dot_string("a\"b\\c\nd"));
+    }
+}
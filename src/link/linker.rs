@@ -0,0 +1,793 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Linking OBJs and LIBs into a PS-X EXE.
+//!
+//! [Linker] is a first cut at what `psylink` does for a program without a
+//! linker script:
+//!
+//! 1. Each LIB, in the order added, contributes the modules needed by the
+//!    OBJs and modules before it. See [resolve_members].
+//! 2. The section declarations are [checked](section_compat_named) and
+//!    conflicting section ids are an error.
+//! 3. Every symbol is [resolved](resolve_definitions). Duplicate strong
+//!    definitions and references that nothing defines are errors.
+//! 4. Sections are laid out from the text base in the order they're first
+//!    declared, sections in the same group kept together, each aligned as
+//!    its [LNKHeader](crate::LNKHeader) says. Sections that only reserve
+//!    space, like `.bss`, follow all of the others.
+//! 5. Every [Patch](crate::Patch) is [evaluated](crate::Expression::evaluate)
+//!    and [applied](crate::Patch::apply).
+//!
+//! Overlays, linker scripts, and GP-relative patches aren't supported.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::io;
+//! use psyk::link::Linker;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let exe = Linker::new()
+//!     .add_obj(io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?)
+//!     .add_lib(io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?)
+//!     .link()?;
+//! assert_eq!(0x8001_0000, exe.symbols["main"]);
+//! assert_eq!(0x8001_0000, exe.entry);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+
+use crate::error::{bail, Error, Result};
+
+use super::{resolve_definitions, resolve_members, section_compat_named};
+use crate::eval::ExpressionResolver;
+use crate::symtab::{SymbolEntry, SymbolKind, SymbolTable};
+use crate::verify::Severity;
+use crate::{LNKHeader, Patch, Section, LIB, OBJ};
+
+/// Where code is placed unless [Linker::set_text_base] says otherwise.
+pub const DEFAULT_TEXT_BASE: u32 = 0x8001_0000;
+
+/// The symbol execution starts at. If it isn't defined, `main` is used.
+pub const ENTRY_POINT: &str = "__SN_ENTRY_POINT";
+
+/// The size of a PS-X EXE header, and the alignment of the code after it.
+pub const EXE_HEADER_SIZE: usize = 0x800;
+
+const EXE_MAGIC: &[u8; 8] = b"PS-X EXE";
+
+/// Links OBJs and LIBs into an [Executable].
+#[derive(Clone, Debug)]
+pub struct Linker {
+    objs: Vec<(String, OBJ)>,
+    libs: Vec<LIB>,
+    text_base: u32,
+}
+
+impl Default for Linker {
+    fn default() -> Self {
+        Self {
+            objs: Vec::new(),
+            libs: Vec::new(),
+            text_base: DEFAULT_TEXT_BASE,
+        }
+    }
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an OBJ, named in errors by `#` and the order it was added.
+    pub fn add_obj(self, obj: OBJ) -> Self {
+        let name = format!("#{}", self.objs.len());
+        self.add_named_obj(&name, obj)
+    }
+
+    /// Adds an OBJ, named in errors by `name`.
+    pub fn add_named_obj(mut self, name: &str, obj: OBJ) -> Self {
+        self.objs.push((name.to_string(), obj));
+        self
+    }
+
+    /// Adds a LIB. Only the modules needed to define symbols referenced by
+    /// what was added before are linked.
+    pub fn add_lib(mut self, lib: LIB) -> Self {
+        self.libs.push(lib);
+        self
+    }
+
+    /// Sets the address the first section is placed at.
+    pub fn set_text_base(mut self, base: u32) -> Self {
+        self.text_base = base;
+        self
+    }
+
+    /// Links everything added into an executable.
+    pub fn link(&self) -> Result<Executable> {
+        let mut units = self.objs.clone();
+        for lib in &self.libs {
+            let objs = units.iter().map(|(_, obj)| obj).collect::<Vec<_>>();
+            let members = resolve_members(lib, &objs, &[])?;
+            units.extend(
+                members
+                    .modules
+                    .into_iter()
+                    .map(|module| (module.name(), module.object().clone())),
+            );
+        }
+        let named = units
+            .iter()
+            .map(|(name, obj)| (name.as_str(), obj))
+            .collect::<Vec<_>>();
+
+        let conflicts = section_compat_named(&named)
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            bail!("{}", conflicts.join("\n"));
+        }
+
+        let resolution = resolve_definitions(&named);
+        if !resolution.duplicates.is_empty() {
+            let duplicates = resolution
+                .duplicates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            bail!("{}", duplicates.join("\n"));
+        }
+        let undefined = units
+            .iter()
+            .flat_map(|(_, obj)| obj.symbols().undefined().to_vec())
+            .map(|symbol| symbol.name)
+            .filter(|name| !resolution.symbols.contains_key(name))
+            .collect::<BTreeSet<_>>();
+        if !undefined.is_empty() {
+            bail!(
+                "undefined symbols: {}",
+                undefined.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        let mut units = units
+            .iter()
+            .map(|(name, obj)| {
+                // only the chosen definition of a common symbol is allocated
+                let owns = |symbol: &str| {
+                    resolution
+                        .symbols
+                        .get(symbol)
+                        .is_some_and(|resolved| resolved.source == *name)
+                };
+                Unit::new(name, obj, owns).map_err(|e| within(name, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let layout = Layout::new(&mut units, self.text_base);
+
+        let mut symbols = BTreeMap::new();
+        for (name, resolved) in &resolution.symbols {
+            let unit = units
+                .iter()
+                .find(|unit| unit.name == resolved.source)
+                .expect("resolved symbols come from a unit");
+            let address = unit.definition_address(name).ok_or_else(|| {
+                Error::other(format!("{name} in {} could not be placed", unit.name))
+            })?;
+            symbols.insert(name.clone(), address);
+        }
+
+        let mut sections = layout.sections;
+        for unit in &units {
+            let resolver = UnitResolver {
+                unit,
+                starts: &layout.starts,
+                symbols: &symbols,
+            };
+            for (id, piece) in &unit.pieces {
+                let base = unit.bases[id];
+                let output = sections
+                    .iter_mut()
+                    .find(|section| section.name == unit.headers[id].type_name())
+                    .expect("every piece is laid out");
+                for (start, patch) in &piece.patches {
+                    let address = base + start + patch.offset() as u32;
+                    let value = patch
+                        .expression()
+                        .evaluate(&resolver)
+                        .map_err(|e| within(&format!("{} at {address:#x}", unit.name), e))?;
+                    let offset = base - output.address + start;
+                    // bss and cleared sections have no bytes to patch
+                    let Some(data) = output.data.get_mut(offset as usize..) else {
+                        bail!(
+                            "in {}: patch at {address:#x} is outside the data of {}",
+                            unit.name,
+                            output.name
+                        );
+                    };
+                    patch.apply(data, value).map_err(|e| within(unit.name, e))?;
+                }
+            }
+        }
+
+        let entry = symbols
+            .get(ENTRY_POINT)
+            .or_else(|| symbols.get("main"))
+            .copied()
+            .unwrap_or(self.text_base);
+        Ok(Executable {
+            entry,
+            text_address: self.text_base,
+            sections,
+            symbols,
+        })
+    }
+}
+
+/// A section of a linked program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkedSection {
+    /// The type name of the section, like `.text`.
+    pub name: String,
+    pub address: u32,
+    pub size: u32,
+    /// `true` if the section only reserves space, like `.bss`.
+    pub bss: bool,
+    /// The contents of the section, or nothing if it only reserves space.
+    pub data: Vec<u8>,
+}
+
+/// A linked program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Executable {
+    /// The address execution starts at.
+    pub entry: u32,
+    /// The address the first section is loaded at.
+    pub text_address: u32,
+    /// The sections of the program, in the order they're laid out.
+    pub sections: Vec<LinkedSection>,
+    /// The address of every symbol defined.
+    pub symbols: BTreeMap<String, u32>,
+}
+
+impl Executable {
+    /// Returns the initialized sections and the gaps between them, from the text address,
+    /// padded to a multiple of [EXE_HEADER_SIZE].
+    pub fn text(&self) -> Vec<u8> {
+        let mut text = Vec::new();
+        for section in self.sections.iter().filter(|s| !s.bss) {
+            text.resize((section.address - self.text_address) as usize, 0);
+            text.extend(&section.data);
+        }
+        text.resize(text.len().next_multiple_of(EXE_HEADER_SIZE), 0);
+        text
+    }
+
+    /// Returns the address and size of the sections that only reserve space.
+    pub fn bss(&self) -> (u32, u32) {
+        let bss = self.sections.iter().filter(|s| s.bss && s.size > 0);
+        let Some(start) = bss.clone().map(|s| s.address).min() else {
+            return (0, 0);
+        };
+        let end = bss.map(|s| s.address + s.size).max().unwrap_or(start);
+        (start, end - start)
+    }
+
+    /// Writes the program as a PS-X EXE.
+    ///
+    /// | Offset | Type      | Description                                  |
+    /// |--------|-----------|----------------------------------------------|
+    /// | 0      | `[u8; 8]` | `PS-X EXE`                                   |
+    /// | 0x10   | `u32`     | `pc0`, the [entry](Self::entry) address.     |
+    /// | 0x14   | `u32`     | `gp0`. Always 0.                             |
+    /// | 0x18   | `u32`     | `t_addr`, the [text address](Self::text_address). |
+    /// | 0x1C   | `u32`     | `t_size`, the size of the [text](Self::text). |
+    /// | 0x28   | `u32`     | `b_addr`, the start of the [bss](Self::bss). |
+    /// | 0x2C   | `u32`     | `b_size`, the size of the bss.               |
+    /// | 0x800  | `[u8]`    | The text.                                    |
+    ///
+    /// Every other field of the header is 0.
+    pub fn write(&self, write: &mut impl Write) -> Result<()> {
+        let text = self.text();
+        let (bss_address, bss_size) = self.bss();
+        let mut header = [0u8; EXE_HEADER_SIZE];
+        header[..8].copy_from_slice(EXE_MAGIC);
+        for (offset, value) in [
+            (0x10, self.entry),
+            (0x18, self.text_address),
+            (0x1C, text.len() as u32),
+            (0x28, bss_address),
+            (0x2C, bss_size),
+        ] {
+            header[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+        }
+        write.write_all(&header)?;
+        write.write_all(&text)?;
+        Ok(())
+    }
+
+    /// Returns the program as a PS-X EXE. See [write](Self::write).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes).expect("writing to a Vec succeeds");
+        bytes
+    }
+}
+
+/// Prefixes `error` with the unit, or the place in it, where it happened.
+fn within(place: &str, error: impl std::fmt::Display) -> Error {
+    Error::other(format!("in {place}: {error}"))
+}
+
+/// The contents one OBJ contributes to one of its sections.
+#[derive(Default)]
+struct Piece<'a> {
+    data: Vec<u8>,
+    /// Each patch, with the offset of the code before it.
+    patches: Vec<(u32, &'a Patch)>,
+    has_code: bool,
+}
+
+/// An OBJ being linked.
+struct Unit<'a> {
+    name: &'a str,
+    headers: BTreeMap<u16, &'a LNKHeader>,
+    pieces: BTreeMap<u16, Piece<'a>>,
+    symbols: SymbolTable,
+    /// The offset in its piece of each XBSS symbol this OBJ allocates.
+    xbss: HashMap<u16, u32>,
+    /// The address of each piece, once laid out.
+    bases: HashMap<u16, u32>,
+}
+
+impl<'a> Unit<'a> {
+    fn new(name: &'a str, obj: &'a OBJ, owns: impl Fn(&str) -> bool) -> Result<Self> {
+        let mut unit = Self {
+            name,
+            headers: BTreeMap::new(),
+            pieces: BTreeMap::new(),
+            symbols: obj.symbols(),
+            xbss: HashMap::new(),
+            bases: HashMap::new(),
+        };
+        let mut current = None;
+        let mut code_start = HashMap::new();
+        for section in obj.sections() {
+            match section {
+                Section::LNKHeader(header) => {
+                    unit.headers.insert(header.section(), header);
+                }
+                Section::SectionSwitch(id) => current = Some(*id),
+                Section::Code(code) => {
+                    let piece = unit.piece(current)?;
+                    let start = piece.data.len() as u32;
+                    piece.data.extend(code.code()?);
+                    piece.has_code = true;
+                    code_start.insert(current, start);
+                }
+                Section::BSS(size) => {
+                    let piece = unit.piece(current)?;
+                    piece.data.resize(piece.data.len() + *size as usize, 0);
+                }
+                Section::Patch(patch) => {
+                    let start = code_start.get(&current).copied().unwrap_or_default();
+                    unit.piece(current)?.patches.push((start, patch));
+                }
+                Section::XBSS(xbss) if owns(&xbss.name()) => {
+                    let piece = unit.piece(Some(xbss.section()))?;
+                    let offset = piece.data.len().next_multiple_of(4);
+                    piece.data.resize(offset + xbss.size() as usize, 0);
+                    unit.xbss.insert(xbss.number(), offset as u32);
+                }
+                _ => (),
+            }
+        }
+        for id in unit.pieces.keys() {
+            if !unit.headers.contains_key(id) {
+                bail!("section {id} is used but not declared");
+            }
+        }
+        Ok(unit)
+    }
+
+    fn piece(&mut self, section: Option<u16>) -> Result<&mut Piece<'a>> {
+        let Some(section) = section else {
+            bail!("code or data comes before the first section switch");
+        };
+        Ok(self.pieces.entry(section).or_default())
+    }
+
+    /// The address of the definition of `name` in this OBJ.
+    fn definition_address(&self, name: &str) -> Option<u32> {
+        let symbol = self.symbols.find(name)?;
+        self.address(&SymbolEntry::Defined(symbol))
+    }
+
+    /// The address of `symbol`, if this OBJ defines it.
+    fn address(&self, symbol: &SymbolEntry) -> Option<u32> {
+        let SymbolEntry::Defined(symbol) = symbol else {
+            return None;
+        };
+        let base = *self.bases.get(&symbol.section)?;
+        match symbol.kind {
+            SymbolKind::Xbss => Some(base + self.xbss.get(&symbol.number?)?),
+            _ => Some(base + symbol.offset?),
+        }
+    }
+}
+
+/// The sections of a link and where they are.
+struct Layout {
+    sections: Vec<LinkedSection>,
+    /// The start and end of each section, by type name.
+    starts: HashMap<String, (u32, u32)>,
+}
+
+impl Layout {
+    /// Lays out the sections of `units` from `base`, setting the base of
+    /// each unit's sections.
+    fn new(units: &mut [Unit], base: u32) -> Self {
+        // type name, group, and alignment in the order first declared
+        let mut declared: Vec<(String, u16, u32)> = Vec::new();
+        let mut code = BTreeSet::new();
+        let mut zeroed = BTreeSet::new();
+        for unit in units.iter() {
+            for (id, header) in &unit.headers {
+                let name = header.type_name();
+                let align = u32::from(header.align()).max(1);
+                match declared.iter_mut().find(|(n, _, _)| *n == name) {
+                    Some((_, _, a)) => *a = (*a).max(align),
+                    None => declared.push((name.clone(), header.group(), align)),
+                }
+                match unit.pieces.get(id) {
+                    Some(piece) if piece.has_code => {
+                        code.insert(name);
+                    }
+                    Some(piece) if !piece.data.is_empty() => {
+                        zeroed.insert(name);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        // sections that only reserve space, named like `.bss` or not
+        let bss = declared
+            .iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| {
+                !code.contains(*name) && (zeroed.contains(*name) || name.ends_with("bss"))
+            })
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let group_rank = |group: u16, index: usize| {
+            if group == 0 {
+                return index;
+            }
+            declared
+                .iter()
+                .position(|(_, g, _)| *g == group)
+                .expect("the group was declared")
+        };
+        let mut order = (0..declared.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| {
+            let (name, group, _) = &declared[i];
+            (bss.contains(name), group_rank(*group, i), i)
+        });
+
+        let mut sections = Vec::new();
+        let mut starts = HashMap::new();
+        let mut address = base;
+        for i in order {
+            let (name, _, align) = &declared[i];
+            address = address.next_multiple_of(*align);
+            let start = address;
+            let mut data = Vec::new();
+            for unit in units.iter_mut() {
+                let ids = unit
+                    .headers
+                    .iter()
+                    .filter(|(_, header)| header.type_name() == *name)
+                    .map(|(id, header)| (*id, u32::from(header.align()).max(1)))
+                    .collect::<Vec<_>>();
+                for (id, align) in ids {
+                    address = address.next_multiple_of(align);
+                    unit.bases.insert(id, address);
+                    if let Some(piece) = unit.pieces.get(&id) {
+                        data.resize((address - start) as usize, 0);
+                        data.extend(&piece.data);
+                        address += piece.data.len() as u32;
+                    }
+                }
+            }
+            if bss.contains(name) {
+                data.clear();
+            }
+            starts.insert(name.clone(), (start, address));
+            sections.push(LinkedSection {
+                name: name.clone(),
+                address: start,
+                size: address - start,
+                bss: bss.contains(name),
+                data,
+            });
+        }
+        Self { sections, starts }
+    }
+}
+
+/// Resolves the expressions of one unit's patches.
+struct UnitResolver<'a> {
+    unit: &'a Unit<'a>,
+    starts: &'a HashMap<String, (u32, u32)>,
+    symbols: &'a BTreeMap<String, u32>,
+}
+
+impl UnitResolver<'_> {
+    fn bounds(&self, section: u16) -> Option<(u32, u32)> {
+        let header = self.unit.headers.get(&section)?;
+        self.starts.get(&header.type_name()).copied()
+    }
+}
+
+impl ExpressionResolver for UnitResolver<'_> {
+    fn symbol_address(&self, symbol: u16) -> Option<u32> {
+        let symbol = self.unit.symbols.by_number(symbol)?;
+        // a common symbol may have been allocated by another unit
+        self.symbols
+            .get(symbol.name())
+            .copied()
+            .or_else(|| self.unit.address(&symbol))
+    }
+
+    fn section_base(&self, section: u16) -> Option<u32> {
+        self.unit.bases.get(&section).copied()
+    }
+
+    fn section_start(&self, section: u16) -> Option<u32> {
+        self.bounds(section).map(|(start, _)| start)
+    }
+
+    fn section_end(&self, section: u16) -> Option<u32> {
+        self.bounds(section).map(|(_, end)| end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Expression, XBSS, XDEF, XREF};
+    use binrw::io::Cursor;
+    use binrw::BinRead;
+
+    /// An OBJ with `code` in `.text`, section 1, defining `symbols` at the
+    /// given offsets and referencing `imports`.
+    fn obj(code: &[u8], symbols: &[(&str, u32)], imports: &[&str]) -> OBJ {
+        let mut sections = vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text").unwrap()),
+            Section::LNKHeader(LNKHeader::new(2, 0, 8, ".bss").unwrap()),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(code.to_vec()).unwrap()),
+        ];
+        let mut number = 3;
+        for (name, offset) in symbols {
+            sections.push(Section::XDEF(XDEF::new(number, 1, *offset, name).unwrap()));
+            number += 1;
+        }
+        for name in imports {
+            sections.push(Section::XREF(XREF::new(number, name).unwrap()));
+            number += 1;
+        }
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    fn word(exe: &Executable, address: u32) -> u32 {
+        let text = exe.text();
+        let offset = (address - exe.text_address) as usize;
+        u32::from_le_bytes(text[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn header_field(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_link_2mbyte() {
+        let startup = OBJ::read(&mut Cursor::new(crate::test::TWO_MBYTE)).unwrap();
+        // jr $ra; nop, for each stub
+        let stubs = obj(
+            &[
+                0x08, 0x00, 0xE0, 0x03, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0xE0, 0x03, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+            &[("main", 0), ("InitHeap", 8), ("_stacksize", 0)],
+            &[],
+        );
+
+        let exe = Linker::new()
+            .add_named_obj("2MBYTE", startup)
+            .add_named_obj("STUBS", stubs)
+            .link()
+            .unwrap();
+
+        let names = exe
+            .sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.bss))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (".rdata", false),
+                (".text", false),
+                (".data", false),
+                (".sdata", false),
+                (".sbss", true),
+                (".bss", true),
+            ],
+            names
+        );
+
+        // the startup code is first in .text, then the stubs
+        let text = &exe.sections[1];
+        assert_eq!(DEFAULT_TEXT_BASE, text.address);
+        assert_eq!(0xC8 + 0x10, text.size);
+        assert_eq!(DEFAULT_TEXT_BASE + 8, exe.symbols[ENTRY_POINT]);
+        assert_eq!(exe.symbols[ENTRY_POINT], exe.entry);
+        let main = exe.symbols["main"];
+        assert_eq!((DEFAULT_TEXT_BASE + 0xC4).next_multiple_of(8), main);
+        assert_eq!(main + 8, exe.symbols["InitHeap"]);
+
+        // jal InitHeap
+        let jal = word(&exe, DEFAULT_TEXT_BASE + 0x94);
+        assert_eq!(
+            0x0C00_0000 | ((exe.symbols["InitHeap"] >> 2) & 0x03FF_FFFF),
+            jal
+        );
+        // lui/addiu of the start of .sbss
+        let sbss = exe.sections[4].address;
+        let hi = word(&exe, DEFAULT_TEXT_BASE + 8) & 0xFFFF;
+        let lo = word(&exe, DEFAULT_TEXT_BASE + 0xC) & 0xFFFF;
+        assert_eq!(sbss, (hi << 16).wrapping_add(lo as u16 as i16 as u32));
+
+        let bytes = exe.to_bytes();
+        assert_eq!(b"PS-X EXE", &bytes[..8]);
+        assert_eq!(exe.entry, header_field(&bytes, 0x10));
+        assert_eq!(0, header_field(&bytes, 0x14));
+        assert_eq!(DEFAULT_TEXT_BASE, header_field(&bytes, 0x18));
+        let size = header_field(&bytes, 0x1C) as usize;
+        assert_eq!(0x800, size);
+        assert_eq!(EXE_HEADER_SIZE + size, bytes.len());
+        assert_eq!(
+            (sbss, 4),
+            (header_field(&bytes, 0x28), header_field(&bytes, 0x2C))
+        );
+    }
+
+    #[test]
+    fn test_link_lib() {
+        let lib = LIB::new(vec![
+            crate::Module::new(
+                obj(&[0; 4], &[("helper", 0)], &[]),
+                crate::ModuleMetadata::new(
+                    "HELPER".into(),
+                    std::time::SystemTime::now(),
+                    0,
+                    vec![],
+                )
+                .expect("metadata"),
+            ),
+            crate::Module::new(
+                obj(&[0; 4], &[("unused", 0)], &[]),
+                crate::ModuleMetadata::new(
+                    "UNUSED".into(),
+                    std::time::SystemTime::now(),
+                    0,
+                    vec![],
+                )
+                .expect("metadata"),
+            ),
+        ]);
+        let mut main = obj(&[0; 8], &[("main", 0)], &["helper"]);
+        let mut sections = main.sections().clone();
+        let index = sections.len() - 2;
+        sections.insert(
+            index,
+            Section::Patch(Patch::new(
+                crate::reloc::WORD,
+                4,
+                Expression::SymbolAddressIndex(4),
+            )),
+        );
+        main = OBJ::new(sections);
+
+        let exe = Linker::new()
+            .set_text_base(0x8002_0000)
+            .add_obj(main)
+            .add_lib(lib)
+            .link()
+            .unwrap();
+        assert_eq!(
+            vec!["helper", "main"],
+            exe.symbols.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(0x8002_0000, exe.symbols["main"]);
+        assert_eq!(0x8002_0008, exe.symbols["helper"]);
+        assert_eq!(0x8002_0008, word(&exe, 0x8002_0004));
+    }
+
+    #[test]
+    fn test_common_symbols() {
+        let with_bss = |size: u32| {
+            let mut sections = obj(&[0; 4], &[], &[]).sections().clone();
+            sections.insert(
+                sections.len() - 1,
+                Section::XBSS(XBSS::new(3, 2, size, "buffer").unwrap()),
+            );
+            OBJ::new(sections)
+        };
+        let exe = Linker::new()
+            .add_obj(with_bss(16))
+            .add_obj(with_bss(64))
+            .link()
+            .unwrap();
+        let bss = &exe.sections[1];
+        assert_eq!((".bss", 64), (bss.name.as_str(), bss.size));
+        assert_eq!(bss.address, exe.symbols["buffer"]);
+        assert_eq!((bss.address, 64), exe.bss());
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = Linker::new()
+            .add_obj(obj(&[0; 4], &[("main", 0)], &["printf", "exit", "printf"]))
+            .link()
+            .unwrap_err();
+        assert_eq!("undefined symbols: exit, printf", e.to_string());
+
+        let e = Linker::new()
+            .add_named_obj("A", obj(&[0; 4], &[("main", 0)], &[]))
+            .add_named_obj("B", obj(&[0; 4], &[("main", 0)], &[]))
+            .link()
+            .unwrap_err();
+        assert_eq!("main is defined by both A and B", e.to_string());
+
+        let data = OBJ::new(vec![
+            Section::LNKHeader(LNKHeader::new(1, 0, 8, ".data").unwrap()),
+            Section::NOP,
+        ]);
+        let e = Linker::new()
+            .add_named_obj("A", obj(&[0; 4], &[], &[]))
+            .add_named_obj("B", data)
+            .link()
+            .unwrap_err();
+        assert!(e
+            .to_string()
+            .contains("section 1 is .text in A but .data in B"));
+
+        // a patch into bss, which has no bytes, after another OBJ's bss
+        let bss = |symbol: &str, patched: bool| {
+            let mut sections = obj(&[0; 4], &[(symbol, 0)], &[]).sections().clone();
+            sections.pop();
+            sections.extend([Section::SectionSwitch(2), Section::BSS(8)]);
+            if patched {
+                sections.push(Section::Patch(Patch::new(0x10, 4, Expression::Constant(1))));
+            }
+            sections.push(Section::NOP);
+            OBJ::new(sections)
+        };
+        let e = Linker::new()
+            .add_named_obj("A", bss("main", false))
+            .add_named_obj("B", bss("other", true))
+            .link()
+            .unwrap_err();
+        assert!(e.to_string().contains("B: patch at 0x"), "{e}");
+        assert!(e.to_string().contains("outside the data of .bss"), "{e}");
+    }
+}
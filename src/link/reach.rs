@@ -0,0 +1,231 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Finding the LIB modules a program uses, and the ones it doesn't.
+//!
+//! [reachable_modules] starts from the [XREF](crate::XREF)s of some root
+//! OBJs and looks each symbol up in the export tables of the LIBs, following
+//! the references of every module found. Modules nothing reaches are dead
+//! weight for that program.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::{io, link};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let main = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?;
+//! let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+//! let report = link::reachable_modules(&[main], &[&lib]);
+//! assert!(report.required.is_empty());
+//! assert_eq!(2, report.unreferenced.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use super::closure::references;
+use crate::{LIB, OBJ};
+
+/// A module of one of the LIBs given to [reachable_modules].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LibModule {
+    /// The index of the LIB.
+    pub lib: usize,
+    pub name: String,
+}
+
+impl fmt::Display for LibModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}:{}", self.lib, self.name)
+    }
+}
+
+/// The modules a set of roots needs. See [reachable_modules].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReachabilityReport {
+    /// The modules the roots need, in the order of their LIBs.
+    pub required: Vec<LibModule>,
+    /// The modules the roots don't need, in the order of their LIBs.
+    pub unreferenced: Vec<LibModule>,
+    /// The symbols neither the roots nor any LIB define, sorted.
+    pub unresolved: Vec<String>,
+}
+
+impl fmt::Display for ReachabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = self
+            .required
+            .iter()
+            .map(|m| ("required", m.lib.to_string(), m.name.as_str()))
+            .chain(
+                self.unreferenced
+                    .iter()
+                    .map(|m| ("unreferenced", m.lib.to_string(), m.name.as_str())),
+            )
+            .chain(
+                self.unresolved
+                    .iter()
+                    .map(|name| ("unresolved", "-".to_string(), name.as_str())),
+            )
+            .collect::<Vec<_>>();
+        let lib_width = rows
+            .iter()
+            .map(|(_, lib, _)| lib.len())
+            .max()
+            .unwrap_or_default()
+            .max("lib".len());
+        writeln!(f, "{:<12}  {:>lib_width$}  name", "status", "lib")?;
+        for (status, lib, name) in rows {
+            writeln!(f, "{status:<12}  {lib:>lib_width$}  {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the modules of `libs` needed to resolve the references of `roots`.
+///
+/// Symbols are looked up in the export tables of the LIBs, not the modules'
+/// OBJs. Each symbol comes from the first module of the first LIB that
+/// exports it, unless a root defines it.
+pub fn reachable_modules(roots: &[OBJ], libs: &[&LIB]) -> ReachabilityReport {
+    let mut exporters: HashMap<String, (usize, usize)> = HashMap::new();
+    for (l, lib) in libs.iter().enumerate() {
+        for (m, module) in lib.modules().iter().enumerate() {
+            for name in module.exports() {
+                exporters.entry(name).or_insert((l, m));
+            }
+        }
+    }
+    let provided: HashSet<String> = roots.iter().flat_map(OBJ::exports).collect();
+
+    let mut selected: BTreeSet<(usize, usize)> = BTreeSet::new();
+    let mut unresolved: BTreeSet<String> = BTreeSet::new();
+    let mut pending: Vec<String> = roots.iter().flat_map(references).collect();
+    while let Some(name) = pending.pop() {
+        if provided.contains(&name) {
+            continue;
+        }
+        match exporters.get(&name) {
+            Some(&(l, m)) => {
+                if selected.insert((l, m)) {
+                    pending.extend(references(libs[l].modules()[m].object()));
+                }
+            }
+            None => {
+                unresolved.insert(name);
+            }
+        }
+    }
+
+    let mut report = ReachabilityReport {
+        unresolved: unresolved.into_iter().collect(),
+        ..Default::default()
+    };
+    for (l, lib) in libs.iter().enumerate() {
+        for (m, module) in lib.modules().iter().enumerate() {
+            let module = LibModule {
+                lib: l,
+                name: module.name(),
+            };
+            if selected.contains(&(l, m)) {
+                report.required.push(module);
+            } else {
+                report.unreferenced.push(module);
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::{Export, Module, ModuleMetadata, Section, XDEF, XREF};
+
+    fn obj(defines: &[&str], calls: &[&str]) -> OBJ {
+        let mut sections = Vec::new();
+        for (i, name) in defines.iter().enumerate() {
+            sections.push(Section::XDEF(XDEF::new(i as u16 + 1, 1, 0, name).unwrap()));
+        }
+        for (i, name) in calls.iter().enumerate() {
+            sections.push(Section::XREF(XREF::new(i as u16 + 100, name).unwrap()));
+        }
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    fn module(name: &str, defines: &[&str], calls: &[&str]) -> Module {
+        let exports = defines
+            .iter()
+            .map(|e| Export::new(e.to_string()).expect("export"))
+            .collect();
+        let metadata =
+            ModuleMetadata::new(name.into(), SystemTime::now(), 0, exports).expect("metadata");
+        Module::new(obj(defines, calls), metadata)
+    }
+
+    #[test]
+    fn test_reachable_modules() {
+        let libc = LIB::new(vec![
+            module("PRINTF", &["printf"], &["putchar", "strlen"]),
+            module("STRLEN", &["strlen"], &[]),
+            module("PUTCHAR", &["putchar"], &["write"]),
+            module("UNUSED", &["unused"], &["strlen"]),
+        ]);
+        let extra = LIB::new(vec![
+            module("STRLEN2", &["strlen"], &[]),
+            module("EXIT", &["exit"], &[]),
+        ]);
+        let roots = [obj(&["main", "write"], &["printf", "exit", "missing"])];
+
+        let report = reachable_modules(&roots, &[&libc, &extra]);
+        let names =
+            |modules: &[LibModule]| modules.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["#0:PRINTF", "#0:STRLEN", "#0:PUTCHAR", "#1:EXIT"],
+            names(&report.required)
+        );
+        assert_eq!(vec!["#0:UNUSED", "#1:STRLEN2"], names(&report.unreferenced));
+        assert_eq!(vec!["missing"], report.unresolved);
+
+        assert_eq!(
+            "\
+status        lib  name
+required        0  PRINTF
+required        0  STRLEN
+required        0  PUTCHAR
+required        1  EXIT
+unreferenced    0  UNUSED
+unreferenced    1  STRLEN2
+unresolved      -  missing
+",
+            report.to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let report = ReachabilityReport {
+            required: vec![LibModule {
+                lib: 0,
+                name: "PUTCHAR".into(),
+            }],
+            unreferenced: vec![LibModule {
+                lib: 1,
+                name: "STRLEN2".into(),
+            }],
+            unresolved: vec!["missing".into()],
+        };
+        let json = serde_json::to_value(&report).expect("JSON");
+        assert_eq!("PUTCHAR", json["required"][0]["name"]);
+        assert_eq!(1, json["unreferenced"][0]["lib"]);
+        assert_eq!("missing", json["unresolved"][0]);
+    }
+}
@@ -0,0 +1,267 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Deciding which definition of a symbol a link uses.
+//!
+//! A symbol can be defined by more than one OBJ in a link. What happens
+//! depends on each definition's [DefinitionStrength]:
+//!
+//! * a strong definition replaces any common definitions, without error
+//! * common definitions are merged into one, the size of the largest
+//! * two strong definitions are an error
+//!
+//! The memory-size OBJs shipped with the SDK (`2MBYTE.OBJ`, `8MBYTE.OBJ`)
+//! rely on this: they reserve space that `LIBSN` also describes, and linking
+//! either one with `LIBSN` succeeds.
+//!
+//! ```
+//! use psyk::link::resolve_definitions;
+//! use psyk::{io, DefinitionStrength};
+//! use std::path::Path;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let main = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?;
+//! let resolution = resolve_definitions(&[("MAIN", &main), ("COPY", &main)]);
+//! assert_eq!(DefinitionStrength::Strong, resolution.symbols["main"].strength);
+//! assert_eq!(1, resolution.duplicates.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{DefinitionStrength, OBJ};
+
+/// The definition of a symbol a link uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedSymbol {
+    /// The OBJ or module the definition was taken from.
+    pub source: String,
+    pub strength: DefinitionStrength,
+    /// The largest size of the merged common definitions, or 0 for a strong
+    /// definition.
+    pub size: u32,
+}
+
+/// A symbol with more than one strong definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateDefinition {
+    pub name: String,
+    /// The OBJ or module with the definition that was kept.
+    pub first: String,
+    pub second: String,
+}
+
+impl fmt::Display for DuplicateDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is defined by both {} and {}",
+            self.name, self.first, self.second
+        )
+    }
+}
+
+/// The definitions chosen for every symbol defined in a link.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolResolution {
+    pub symbols: BTreeMap<String, ResolvedSymbol>,
+    /// Each strong definition of a symbol after its first.
+    pub duplicates: Vec<DuplicateDefinition>,
+}
+
+impl SymbolResolution {
+    /// Adds the definitions of `obj`, named `source`.
+    pub fn add(&mut self, source: &str, obj: &OBJ) {
+        for definition in obj.definitions() {
+            let Some(existing) = self.symbols.get_mut(&definition.name) else {
+                self.symbols.insert(
+                    definition.name,
+                    ResolvedSymbol {
+                        source: source.to_string(),
+                        strength: definition.strength,
+                        size: definition.size,
+                    },
+                );
+                continue;
+            };
+            match (existing.strength, definition.strength) {
+                (DefinitionStrength::Strong, DefinitionStrength::Strong) => {
+                    self.duplicates.push(DuplicateDefinition {
+                        name: definition.name,
+                        first: existing.source.clone(),
+                        second: source.to_string(),
+                    });
+                }
+                (DefinitionStrength::Strong, DefinitionStrength::Common) => (),
+                (DefinitionStrength::Common, DefinitionStrength::Strong) => {
+                    *existing = ResolvedSymbol {
+                        source: source.to_string(),
+                        strength: DefinitionStrength::Strong,
+                        size: 0,
+                    };
+                }
+                (DefinitionStrength::Common, DefinitionStrength::Common) => {
+                    if definition.size > existing.size {
+                        existing.source = source.to_string();
+                        existing.size = definition.size;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolves the definitions of `objs`, each a name and an OBJ, in the
+/// order they're linked.
+pub fn resolve_definitions(objs: &[(&str, &OBJ)]) -> SymbolResolution {
+    let mut resolution = SymbolResolution::default();
+    for (source, obj) in objs {
+        resolution.add(source, obj);
+    }
+    resolution
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LNKHeader, Section, XBSS, XDEF};
+
+    fn header(id: u16, name: &str) -> Section {
+        Section::LNKHeader(LNKHeader {
+            section: id,
+            group: 0,
+            align: 8,
+            type_name_size: name.len() as u8,
+            type_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn xdef(number: u16, section: u16, offset: u32, name: &str) -> Section {
+        Section::XDEF(XDEF {
+            number,
+            section,
+            offset,
+            symbol_name_size: name.len() as u8,
+            symbol_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    /// An OBJ with `strong` defined in `.text` and `common` in `.bss`, each
+    /// `size` bytes.
+    fn obj(strong: &[&str], common: &[&str], size: u32) -> OBJ {
+        let mut sections = vec![header(1, ".text"), header(2, ".bss")];
+        sections.push(Section::SectionSwitch(1));
+        sections.push(Section::Code(Code {
+            size: 4,
+            deferred: None,
+            code: vec![0; 4],
+        }));
+        sections.push(Section::SectionSwitch(2));
+        sections.push(Section::BSS(size * common.len() as u32));
+        let mut number = 3;
+        for name in strong {
+            sections.push(xdef(number, 1, 0, name));
+            number += 1;
+        }
+        for (i, name) in common.iter().enumerate() {
+            sections.push(xdef(number, 2, size * i as u32, name));
+            number += 1;
+        }
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    #[test]
+    fn test_definitions() {
+        let mut obj = obj(&["main"], &["buffer", "count"], 16);
+        let mut sections = obj.sections().clone();
+        let name = b"heap".to_vec();
+        sections.insert(
+            sections.len() - 1,
+            Section::XBSS(XBSS {
+                number: 10,
+                section: 2,
+                size: 64,
+                name_size: name.len() as u8,
+                name,
+            }),
+        );
+        obj = OBJ::new(sections);
+        assert_eq!(
+            vec![
+                ("main", DefinitionStrength::Strong, 0),
+                ("buffer", DefinitionStrength::Common, 16),
+                ("count", DefinitionStrength::Common, 16),
+                ("heap", DefinitionStrength::Common, 64),
+            ],
+            obj.definitions()
+                .iter()
+                .map(|d| (d.name.as_str(), d.strength, d.size))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_unnamed_bss_section_is_common() {
+        let obj = OBJ::new(vec![
+            Section::SectionSwitch(3),
+            Section::BSS(8),
+            xdef(1, 3, 0, "flag"),
+            Section::NOP,
+        ]);
+        assert_eq!(
+            vec![(DefinitionStrength::Common, 8)],
+            obj.definitions()
+                .iter()
+                .map(|d| (d.strength, d.size))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_precedence() {
+        let strong = obj(&["a"], &[], 0);
+        let small = obj(&[], &["a"], 4);
+        let large = obj(&[], &["a"], 32);
+
+        // strong beats common in either order
+        for objs in [
+            [("strong", &strong), ("small", &small)],
+            [("small", &small), ("strong", &strong)],
+        ] {
+            let resolution = resolve_definitions(&objs);
+            assert!(resolution.duplicates.is_empty());
+            assert_eq!(
+                ResolvedSymbol {
+                    source: "strong".into(),
+                    strength: DefinitionStrength::Strong,
+                    size: 0,
+                },
+                resolution.symbols["a"]
+            );
+        }
+
+        // common definitions merge by the largest size
+        let resolution = resolve_definitions(&[("small", &small), ("large", &large)]);
+        assert!(resolution.duplicates.is_empty());
+        assert_eq!(
+            ("large", 32),
+            (
+                resolution.symbols["a"].source.as_str(),
+                resolution.symbols["a"].size
+            )
+        );
+
+        // strong definitions conflict
+        let resolution =
+            resolve_definitions(&[("one", &strong), ("small", &small), ("two", &strong)]);
+        assert_eq!("one", resolution.symbols["a"].source);
+        assert_eq!(
+            "a is defined by both one and two",
+            resolution.duplicates[0].to_string()
+        );
+        assert_eq!(1, resolution.duplicates.len());
+    }
+}
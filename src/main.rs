@@ -2,15 +2,31 @@
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
 use std::env;
+use std::fs::File;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use anyhow::bail;
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 
 mod dos;
 
+use psyk::asm;
+use psyk::assembler::{self, NamePolicy};
 use psyk::cli::{self, get_binary_name};
+use psyk::display;
+use psyk::display::human;
+use psyk::editors;
+use psyk::ldscript;
+use psyk::names::NameOverlay;
+use psyk::poke;
+use psyk::splat;
+use psyk::symbols::CaseFold;
+use psyk::verify;
 
 /// Inspect, extract, and create PSY-Q LIB and OBJ files.
 #[derive(Debug, Parser)]
@@ -20,6 +36,14 @@ pub struct App {
     #[arg(required = false)]
     lib_or_obj: Option<PathBuf>,
 
+    /// print warnings about anything unusual in the files read
+    #[clap(short, long, global = true)]
+    verbose: bool,
+
+    /// read past sections of unknown types rather than failing
+    #[clap(long, global = true)]
+    lenient: bool,
+
     #[clap(subcommand)]
     command: Option<CLICommand>,
 }
@@ -40,9 +64,65 @@ enum CLICommand {
         #[clap(short, long)]
         disassemble: bool,
 
+        /// show disassembly with addresses, symbol labels, and patches
+        #[clap(long)]
+        annotate: bool,
+
+        /// the address assumed for the start of each section with --annotate
+        #[clap(long, value_parser = cli::parse_address, default_value = "0x80000000")]
+        base_address: u32,
+
         /// recursively print all OBJ entries in a LIB
         #[clap(short, long)]
         recursive: bool,
+
+        /// follow the class and type of Def and Def2 sections with their
+        /// decoded forms
+        #[clap(long)]
+        decode_types: bool,
+
+        /// append a CRC-32 fingerprint to each section and module
+        #[clap(long)]
+        fingerprints: bool,
+
+        /// append a content-derived stable id to each module
+        #[clap(long)]
+        stable_ids: bool,
+
+        /// print the file offset of each section before it
+        #[clap(long)]
+        offsets: bool,
+
+        /// print the name of each module to stderr before it is listed
+        #[clap(long)]
+        progress: bool,
+
+        /// a names file naming locations the LIB or OBJ doesn't
+        #[clap(long)]
+        names: Option<PathBuf>,
+
+        /// don't prefix names from the names file with `~`
+        #[clap(long)]
+        no_mark_overlay: bool,
+
+        /// the format of the listing
+        #[clap(long, value_enum, default_value_t)]
+        format: cli::ListFormat,
+    },
+
+    /// Lists the named locations in the code of a LIB or OBJ
+    Functions {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// a names file naming locations the LIB or OBJ doesn't
+        #[clap(long)]
+        names: Option<PathBuf>,
+
+        /// don't prefix names from the names file with `~`
+        #[clap(long)]
+        no_mark_overlay: bool,
     },
 
     /// splits a LIB into multiple OBJs
@@ -50,26 +130,63 @@ enum CLICommand {
         /// the LIB to extract
         #[arg(required = true)]
         lib: PathBuf,
+
+        /// the modules to extract, like `SPRINTF` or `sprintf.obj`. All
+        /// modules are extracted if none are given
+        modules: Vec<String>,
+
+        /// the directory to write OBJs to, or `-` to write a single module
+        /// to stdout
+        #[clap(short, long, visible_alias = "output-dir")]
+        output: Option<PathBuf>,
+
+        /// write the single module named to stdout, like `-o -`
+        #[clap(long, conflicts_with = "output")]
+        stdout: bool,
+
+        /// what to do when more than one module has the same name
+        #[clap(long, value_enum, default_value_t)]
+        on_collision: cli::OnCollision,
     },
 
     /// Create a new LIB containing provided OBJs into a LIB
     Create {
-        /// the LIB to create
+        /// the LIB to create, or `-` to write it to stdout
         #[arg(required = true)]
         lib: PathBuf,
-        /// the OBJs to include
+        /// the OBJs to include, or `-` to read one from stdin
         #[arg(num_args=1..)]
         objs: Vec<PathBuf>,
+        /// write the LIB even if modules export the same symbol
+        #[arg(long)]
+        force: bool,
+        /// the timestamp of every module, as seconds since 1970 or ISO 8601.
+        /// Defaults to `SOURCE_DATE_EPOCH` if it is set, otherwise the
+        /// creation time of each OBJ
+        #[arg(long, value_parser = cli::parse_timestamp)]
+        timestamp: Option<SystemTime>,
+        /// whether module names are uppercased or keep the case of their OBJs
+        #[clap(long, value_enum, default_value_t)]
+        name_case: NameCase,
+        /// what to do with modules whose names differ only in case, or not at all
+        #[clap(long, value_enum, default_value_t)]
+        on_collision: NameCollision,
     },
 
-    /// Adds an OBJ into an existing LIB
+    /// Adds one or more OBJs to the end of an existing LIB
     Add {
         /// the LIB to modify
         #[arg(required = true)]
         lib: PathBuf,
-        /// the OBJ to add
+        /// the OBJs to add, or `-` to read one from stdin
         #[arg(required = true)]
-        obj: PathBuf,
+        objs: Vec<PathBuf>,
+        /// write the LIB even if modules export the same symbol
+        #[arg(long)]
+        force: bool,
+        /// keep the original LIB with a .BAK extension
+        #[arg(long)]
+        backup: bool,
     },
 
     /// Updates one or more OBJs in an existing LIB
@@ -80,6 +197,15 @@ enum CLICommand {
         /// the OBJs to update
         #[arg(num_args=1..)]
         objs: Vec<PathBuf>,
+        /// keep the timestamps of the modules being replaced
+        #[arg(long)]
+        preserve_timestamps: bool,
+        /// write the LIB even if modules export the same symbol
+        #[arg(long)]
+        force: bool,
+        /// keep the original LIB with a .BAK extension
+        #[arg(long)]
+        backup: bool,
     },
 
     /// Updates one or more OBJs in an existing LIB
@@ -90,7 +216,568 @@ enum CLICommand {
         /// the OBJs to delete
         #[arg(num_args=1..)]
         obj_names: Vec<String>,
+        /// keep the original LIB with a .BAK extension
+        #[arg(long)]
+        backup: bool,
+    },
+
+    /// Renames a module in an existing LIB, keeping its timestamp and
+    /// exports
+    Rename {
+        /// the LIB to modify
+        #[arg(required = true)]
+        lib: PathBuf,
+        /// the module to rename
+        #[arg(required = true)]
+        old: String,
+        /// its new name
+        #[arg(required = true)]
+        new: String,
+        /// keep the original LIB with a .BAK extension
+        #[arg(long)]
+        backup: bool,
+    },
+
+    /// Combines two LIBs: the first's modules, then the second's new ones
+    Merge {
+        /// the LIB to write, or `-` to write it to stdout
+        #[arg(required = true)]
+        output: PathBuf,
+        /// the LIB whose modules come first
+        #[arg(required = true)]
+        first: PathBuf,
+        /// the LIB whose modules are added
+        #[arg(required = true)]
+        second: PathBuf,
+        /// what to do with a module both LIBs have
+        #[clap(long, value_enum, default_value_t)]
+        strategy: MergeStrategy,
+    },
+
+    /// Checks a LIB or OBJ for problems
+    Verify {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// plausible module dates, e.g. 1988-01-01..2005-12-31
+        #[arg(long, value_parser = verify::parse_date_range)]
+        timestamp_range: Option<RangeInclusive<NaiveDate>>,
+
+        /// treat implausible module timestamps as errors
+        #[arg(long)]
+        strict_timestamps: bool,
+
+        /// treat modules with incorrect sizes in their metadata as errors
+        #[arg(long)]
+        strict_sizes: bool,
+
+        /// reuse and update the results of unchanged modules in this file
+        #[clap(long)]
+        baseline: Option<PathBuf>,
+    },
+
+    /// Reports when the modules of every LIB in a directory were built
+    Chronology {
+        /// a directory to search for LIBs
+        #[arg(required = true)]
+        dir: PathBuf,
+
+        /// report by library or by module
+        #[clap(long, value_enum, default_value_t)]
+        by: cli::ChronologyView,
+
+        /// the number of recently built modules to list
+        #[clap(short = 'n', long, default_value_t = 20)]
+        count: usize,
+
+        #[command(flatten)]
+        style: ReportArgs,
+    },
+
+    /// Writes an index of the symbols in LIBs and OBJs for `find`
+    Index {
+        /// a directory to search for LIBs and OBJs
+        #[arg(required_unless_present = "update")]
+        dir: Option<PathBuf>,
+
+        /// the index file
+        #[clap(short, long, default_value = "psyk.idx")]
+        output: PathBuf,
+
+        /// refresh changed files in an existing index
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// Finds the LIBs and OBJs which provide a symbol
+    Find {
+        /// the symbol to find
+        #[arg(required = true)]
+        symbol: String,
+
+        /// a directory to search for LIBs and OBJs
+        #[arg(required_unless_present = "index")]
+        dir: Option<PathBuf>,
+
+        /// an index written by `index` to search instead of a directory
+        #[clap(long)]
+        index: Option<PathBuf>,
+
+        /// ignore case when comparing symbol names
+        #[clap(short = 'i', long)]
+        ignore_case: bool,
+    },
+
+    /// Reports instructions which encode absolute addresses without a patch
+    CheckRelocs {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// fail if any high severity suspects are found
+        #[arg(long)]
+        deny_high: bool,
+    },
+
+    /// Counts the section variants and expression operators used by a LIB or OBJ
+    Census {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// only print untested section variants and expression operators
+        #[arg(long)]
+        rare_only: bool,
+
+        /// fail if any untested section variants or expression operators are used
+        #[arg(long)]
+        deny_untested: bool,
+    },
+
+    /// Extracts the modules of a LIB needed to define a set of symbols
+    Closure {
+        /// the LIB to extract modules from
+        #[arg(required = true)]
+        lib: PathBuf,
+
+        /// the symbols to define
+        #[arg(required = true)]
+        symbols: Vec<String>,
+
+        /// the directory to write OBJs to
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+
+        /// write a single OBJ, OVERLAY.OBJ, with references between the
+        /// modules resolved
+        #[arg(long)]
+        combined: bool,
+    },
+
+    /// Runs analyzer plugins over a LIB or OBJ
+    Analyze {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// a plugin to run. May be repeated. All plugins run if none are
+        /// selected
+        #[clap(long = "plugin")]
+        plugins: Vec<String>,
+
+        /// a directory of plugin shared libraries to load
+        #[clap(long)]
+        plugin_dir: Option<PathBuf>,
+    },
+
+    /// Salvages the readable modules of a damaged LIB into a new LIB
+    Recover {
+        /// the damaged LIB
+        #[arg(required = true)]
+        lib: PathBuf,
+
+        /// the LIB to write recovered modules to, or `-` to write it to stdout
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+
+        /// write the damage report to a file instead of stdout
+        #[clap(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Overwrites code bytes in a LIB module, addressed by symbol and offset
+    Poke {
+        /// the LIB to patch
+        #[arg(required = true)]
+        lib: PathBuf,
+
+        /// the module containing the symbol
+        #[arg(required = true)]
+        module: String,
+
+        /// the symbol and offset to patch, e.g. main+0x10
+        #[arg(required = true)]
+        target: String,
+
+        /// the bytes to write in hex, e.g. "00 00 00 00"
+        #[clap(long, required = true)]
+        bytes: String,
+
+        /// write the patched LIB to a file instead of updating LIB
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// allow overwriting bytes a relocation is applied to
+        #[clap(long)]
+        allow_reloc_overlap: bool,
+
+        /// write the change without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+
+    /// Prints summary statistics for a LIB or OBJ
+    Stat {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        #[command(flatten)]
+        style: ReportArgs,
+    },
+
+    /// Prints the section sizes of each module of a LIB or of an OBJ
+    Sizes {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        #[command(flatten)]
+        style: ReportArgs,
+    },
+
+    /// Prints every offset in an OBJ that refers to each external symbol
+    Xref {
+        /// the OBJ to read
+        #[arg(required = true)]
+        obj: PathBuf,
+    },
+
+    /// Prints which modules of a LIB reference symbols defined by which
+    Graph {
+        /// the LIB to read
+        #[arg(required = true)]
+        lib: PathBuf,
+
+        /// the output format
+        #[clap(short, long, value_enum, default_value_t)]
+        format: cli::GraphFormat,
+    },
+
+    /// Writes the bytes of one section of an OBJ, without patches applied
+    DumpSection {
+        /// the OBJ to read
+        #[arg(required = true)]
+        obj: PathBuf,
+
+        /// the section type, like `.text`, or its id in hex with `--by-id`
+        #[arg(required = true)]
+        section: String,
+
+        /// the file to write, or `-` for stdout
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+
+        /// select the section by its id rather than its type
+        #[arg(long)]
+        by_id: bool,
+    },
+
+    /// Compares the listing of a LIB or OBJ with a capture of DUMPOBJ.EXE's output
+    CheckCompat {
+        /// a LIB or OBJ file
+        #[arg(required = true)]
+        lib_or_obj: PathBuf,
+
+        /// the captured DUMPOBJ.EXE output
+        #[arg(required = true)]
+        expected: PathBuf,
+
+        /// the number of lines to skip at the start of the capture
+        #[arg(long, default_value_t = 0)]
+        skip_lines: usize,
     },
+
+    /// Compares two LIBs or two OBJs
+    Diff {
+        /// the original LIB or OBJ
+        #[arg(required = true)]
+        old: PathBuf,
+
+        /// the changed LIB or OBJ
+        #[arg(required = true)]
+        new: PathBuf,
+
+        /// only list the fingerprints of changed sections
+        #[clap(long)]
+        brief: bool,
+    },
+
+    /// Generates a syntax highlighting grammar for listings
+    GenerateGrammar {
+        /// the grammar format
+        #[clap(short, long, value_enum)]
+        format: GrammarFormat,
+
+        /// the file to write, defaults to stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Generates a GNU ld script fragment reserving space for XBSS symbols
+    LdscriptBss {
+        /// OBJs or LIBs declaring XBSS symbols
+        #[arg(required = true)]
+        lib_or_objs: Vec<PathBuf>,
+
+        /// the file to write, defaults to stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// wrap definitions in PROVIDE() so they can be overridden
+        #[clap(long)]
+        provide: bool,
+
+        /// define PSY-Q style section start and length symbols (__bss, __bsslen)
+        #[clap(long)]
+        compat_symbols: bool,
+    },
+
+    /// Writes an OBJ as assembly source
+    ExportAsm {
+        /// the OBJ to export
+        obj: PathBuf,
+
+        /// the file to write, defaults to stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// comment each line with its offset and bytes
+        #[clap(long)]
+        comments: bool,
+    },
+
+    /// Matches the code segments of a splat config against LIB modules
+    SplatMatch {
+        /// the splat YAML config
+        #[arg(required = true)]
+        config: PathBuf,
+
+        /// the binary described by the config
+        #[arg(required = true)]
+        rom: PathBuf,
+
+        /// LIBs to search for matching modules
+        #[arg(required = true)]
+        libs: Vec<PathBuf>,
+
+        /// the lowest confidence reported as a match, from 0 to 1
+        #[clap(long, default_value_t = 0.8)]
+        min_confidence: f64,
+
+        /// the output format
+        #[clap(short, long, value_enum, default_value_t)]
+        format: ReportFormat,
+    },
+
+    /// Bundles a LIB with a manifest, an optional changelog, and an optional signature
+    Package {
+        /// the LIB to bundle
+        #[arg(required = true)]
+        lib: PathBuf,
+
+        /// the bundle to write
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+
+        /// a changelog to include
+        #[clap(long)]
+        changelog: Option<PathBuf>,
+
+        /// a raw 32-byte ed25519 secret key to sign the manifest with
+        #[clap(long)]
+        sign: Option<PathBuf>,
+    },
+
+    /// Verifies a bundle and extracts its files
+    Unpack {
+        /// the bundle to extract
+        #[arg(required = true)]
+        bundle: PathBuf,
+
+        /// the directory to extract into
+        #[clap(short, long, required = true)]
+        output: PathBuf,
+
+        /// a raw 32-byte ed25519 public key to check the signature with
+        #[clap(long)]
+        verify_key: Option<PathBuf>,
+
+        /// extract the bundle even if it fails verification
+        #[clap(long)]
+        force: bool,
+    },
+}
+
+/// The `--strategy` of `psyk merge`. See [psyk::MergeStrategy].
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum MergeStrategy {
+    /// keep the first library's module
+    KeepFirst,
+    /// replace the first library's module with the second's, in place
+    KeepSecond,
+    /// fail, naming every module in both
+    #[default]
+    ErrorOnConflict,
+}
+
+impl From<MergeStrategy> for psyk::MergeStrategy {
+    fn from(strategy: MergeStrategy) -> Self {
+        match strategy {
+            MergeStrategy::KeepFirst => Self::KeepFirst,
+            MergeStrategy::KeepSecond => Self::KeepSecond,
+            MergeStrategy::ErrorOnConflict => Self::ErrorOnConflict,
+        }
+    }
+}
+
+/// The `--name-case` of `psyk create`. See [psyk::NameCase].
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum NameCase {
+    /// uppercase ASCII letters, as `PSYLIB.EXE` does
+    #[default]
+    Upper,
+    /// keep the case of the file name, e.g. `foo.obj` is added as `foo`
+    Preserve,
+}
+
+impl From<NameCase> for psyk::NameCase {
+    fn from(case: NameCase) -> Self {
+        match case {
+            NameCase::Upper => Self::Upper,
+            NameCase::Preserve => Self::Preserve,
+        }
+    }
+}
+
+/// The `--on-collision` of `psyk create`. See [assembler::NameCollision].
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum NameCollision {
+    /// fail, naming the module and where it came from
+    #[default]
+    Error,
+    /// leave the later module out
+    FirstWins,
+    /// number the later modules, shortening their names to fit, e.g.
+    /// `UTIL1`, `UTIL2`, or `LONGNAM1`
+    Suffix,
+}
+
+impl From<NameCollision> for assembler::NameCollision {
+    fn from(collision: NameCollision) -> Self {
+        match collision {
+            NameCollision::Error => Self::Error,
+            NameCollision::FirstWins => Self::FirstWins,
+            NameCollision::Suffix => Self::Suffix,
+        }
+    }
+}
+
+/// How report commands write numbers and dates. See [human::ReportStyle].
+#[derive(Debug, Args)]
+struct ReportArgs {
+    /// group counts, show sizes in KiB, MiB, ..., and write ISO 8601 dates
+    #[arg(long)]
+    human: bool,
+
+    /// how byte counts are written
+    #[arg(long, value_enum)]
+    bytes: Option<ByteUnits>,
+
+    /// the thousands separator, e.g. ' ', ',', or '.'
+    #[arg(long, value_parser = human::parse_separator)]
+    locale_sep: Option<char>,
+}
+
+impl From<ReportArgs> for human::ReportStyle {
+    fn from(args: ReportArgs) -> Self {
+        Self::from_options(args.human, args.bytes.map(Into::into), args.locale_sep)
+    }
+}
+
+/// The `--bytes` of report commands. See [human::ByteUnits].
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ByteUnits {
+    /// plain byte counts
+    Raw,
+    /// byte counts with thousands separators
+    Grouped,
+    /// KiB, MiB, ... (powers of 1024)
+    Iec,
+    /// kB, MB, ... (powers of 1000)
+    Si,
+}
+
+impl From<ByteUnits> for human::ByteUnits {
+    fn from(units: ByteUnits) -> Self {
+        match units {
+            ByteUnits::Raw => Self::Raw,
+            ByteUnits::Grouped => Self::Grouped,
+            ByteUnits::Iec => Self::Iec,
+            ByteUnits::Si => Self::Si,
+        }
+    }
+}
+
+/// The `--format` of `psyk generate-grammar`. See
+/// [editors::GrammarFormat].
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GrammarFormat {
+    /// a TextMate grammar (`.tmLanguage.json`), also used by VS Code and
+    /// Sublime Text
+    #[value(name = "textmate")]
+    TextMate,
+    /// a Vim syntax file
+    Vim,
+}
+
+impl From<GrammarFormat> for editors::GrammarFormat {
+    fn from(format: GrammarFormat) -> Self {
+        match format {
+            GrammarFormat::TextMate => Self::TextMate,
+            GrammarFormat::Vim => Self::Vim,
+        }
+    }
+}
+
+/// The `--format` of `psyk splat-match`. See [splat::ReportFormat].
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ReportFormat {
+    /// a table for people
+    #[default]
+    Text,
+    /// YAML which can be pasted into a splat config as comments
+    Yaml,
+}
+
+impl From<ReportFormat> for splat::ReportFormat {
+    fn from(format: ReportFormat) -> Self {
+        match format {
+            ReportFormat::Text => Self::Text,
+            ReportFormat::Yaml => Self::Yaml,
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -101,26 +788,318 @@ fn main() -> Result<()> {
     }
 
     let args = App::parse();
+    cli::set_verbose(args.verbose);
+    cli::set_lenient(args.lenient);
 
     match args.command {
         Some(command) => match command {
+            CLICommand::List {
+                lib_or_obj,
+                format: cli::ListFormat::Json,
+                ..
+            } => cli::list_json(&mut std::io::stdout(), &lib_or_obj)?,
             CLICommand::List {
                 lib_or_obj,
                 code,
                 disassemble,
+                annotate,
+                base_address,
                 recursive,
-            } => cli::info(
+                decode_types,
+                fingerprints,
+                stable_ids,
+                offsets,
+                progress,
+                names,
+                no_mark_overlay,
+                format: cli::ListFormat::Text,
+            } => {
+                let mut options = display::Options::modern();
+                options.spelling = cli::locale_spelling();
+                if annotate {
+                    options.code_format = display::CodeFormat::AnnotatedDisassembly;
+                } else if disassemble {
+                    options.code_format = display::CodeFormat::Disassembly;
+                } else if code {
+                    options.code_format = display::CodeFormat::Hex;
+                }
+                options.base_address = base_address;
+                options.decode_types = decode_types;
+                options.recursive = recursive;
+                options.show_fingerprints = fingerprints;
+                options.show_stable_ids = stable_ids;
+                options.show_offsets = offsets;
+                if progress {
+                    options.observer = Some(Arc::new(cli::ProgressReporter));
+                }
+                if let Some(names) = names {
+                    options.names = Some(Arc::new(NameOverlay::load(&names)?));
+                }
+                options.mark_overlay = !no_mark_overlay;
+                cli::info_with_options(&mut std::io::stdout(), &lib_or_obj, options)?
+            }
+            CLICommand::Functions {
+                lib_or_obj,
+                names,
+                no_mark_overlay,
+            } => {
+                let names = names
+                    .map(|names| NameOverlay::load(&names))
+                    .transpose()?
+                    .map(Arc::new);
+                cli::functions(&mut std::io::stdout(), &lib_or_obj, names, !no_mark_overlay)?
+            }
+            CLICommand::Extract {
+                lib,
+                modules,
+                output,
+                stdout,
+                on_collision,
+            } => match modules.as_slice() {
+                [module] if stdout => cli::extract_to(&mut std::io::stdout().lock(), &lib, module)?,
+                _ if stdout => bail!(
+                    "--stdout writes one module, but {} were named",
+                    modules.len()
+                ),
+                _ => cli::extract(&lib, &modules, output.as_deref(), on_collision)?,
+            },
+            CLICommand::Create {
+                lib,
+                objs,
+                force,
+                timestamp,
+                name_case,
+                on_collision,
+            } => {
+                let options = cli::JoinOptions {
+                    force,
+                    timestamp,
+                    names: NamePolicy {
+                        case: name_case.into(),
+                        on_collision: on_collision.into(),
+                    },
+                };
+                cli::join_with(&lib, objs, &options)?
+            }
+            CLICommand::Add {
+                lib,
+                objs,
+                force,
+                backup,
+            } => cli::add(&lib, objs, force, backup)?,
+            CLICommand::Update {
+                lib,
+                objs,
+                preserve_timestamps,
+                force,
+                backup,
+            } => cli::update(&lib, objs, preserve_timestamps, force, backup)?,
+            CLICommand::Delete {
+                lib,
+                obj_names,
+                backup,
+            } => cli::delete(&lib, obj_names, backup)?,
+            CLICommand::Rename {
+                lib,
+                old,
+                new,
+                backup,
+            } => cli::rename(&lib, &old, &new, backup)?,
+            CLICommand::Merge {
+                output,
+                first,
+                second,
+                strategy,
+            } => cli::merge(&output, &first, &second, strategy.into())?,
+            CLICommand::Verify {
+                lib_or_obj,
+                timestamp_range,
+                strict_timestamps,
+                strict_sizes,
+                baseline,
+            } => {
+                let mut options = verify::Options::default();
+                if let Some(range) = timestamp_range {
+                    options.timestamps.range = range;
+                }
+                options.timestamps.strict = strict_timestamps;
+                options.strict_sizes = strict_sizes;
+                cli::verify(
+                    &mut std::io::stdout(),
+                    &lib_or_obj,
+                    &options,
+                    baseline.as_deref(),
+                )?
+            }
+            CLICommand::Chronology {
+                dir,
+                by,
+                count,
+                style,
+            } => cli::chronology(&mut std::io::stdout(), &dir, by, count, &style.into())?,
+            CLICommand::Index {
+                dir,
+                output,
+                update,
+            } => cli::index(&mut std::io::stdout(), dir.as_deref(), &output, update)?,
+            CLICommand::Find {
+                symbol,
+                dir,
+                index,
+                ignore_case,
+            } => cli::find(
+                &mut std::io::stdout(),
+                &symbol,
+                if ignore_case {
+                    CaseFold::Insensitive
+                } else {
+                    CaseFold::Sensitive
+                },
+                dir.as_deref(),
+                index.as_deref(),
+            )?,
+            CLICommand::CheckRelocs {
+                lib_or_obj,
+                deny_high,
+            } => cli::check_relocs(&mut std::io::stdout(), &lib_or_obj, deny_high)?,
+            CLICommand::Census {
+                lib_or_obj,
+                rare_only,
+                deny_untested,
+            } => cli::census(
                 &mut std::io::stdout(),
                 &lib_or_obj,
-                code,
-                disassemble,
-                recursive,
+                rare_only,
+                deny_untested,
             )?,
-            CLICommand::Extract { lib } => cli::split(&lib)?,
-            CLICommand::Create { lib, objs } => cli::join(&lib, objs)?,
-            CLICommand::Add { lib, obj } => cli::add(&lib, &obj)?,
-            CLICommand::Update { lib, objs } => cli::update(&lib, objs)?,
-            CLICommand::Delete { lib, obj_names } => cli::delete(&lib, obj_names)?,
+            CLICommand::Closure {
+                lib,
+                symbols,
+                output,
+                combined,
+            } => cli::closure(&mut std::io::stdout(), &lib, &symbols, &output, combined)?,
+            CLICommand::Analyze {
+                lib_or_obj,
+                plugins,
+                plugin_dir,
+            } => cli::analyze(
+                &mut std::io::stdout(),
+                &lib_or_obj,
+                &plugins,
+                plugin_dir.as_deref(),
+            )?,
+            CLICommand::Recover {
+                lib,
+                output,
+                report,
+            } => cli::recover(&mut std::io::stdout(), &lib, &output, report.as_deref())?,
+            CLICommand::Poke {
+                lib,
+                module,
+                target,
+                bytes,
+                output,
+                allow_reloc_overlap,
+                yes,
+            } => cli::poke(
+                &mut std::io::stdout(),
+                &mut std::io::stdin().lock(),
+                &lib,
+                &module,
+                &target,
+                &bytes,
+                output.as_deref(),
+                &poke::Options {
+                    allow_reloc_overlap,
+                },
+                yes,
+            )?,
+            CLICommand::Stat { lib_or_obj, style } => {
+                cli::stat_with_style(&mut std::io::stdout(), &lib_or_obj, &style.into())?
+            }
+            CLICommand::Sizes { lib_or_obj, style } => {
+                cli::sizes_with_style(&mut std::io::stdout(), &lib_or_obj, &style.into())?
+            }
+            CLICommand::Xref { obj } => cli::xref(&mut std::io::stdout(), &obj)?,
+            CLICommand::Graph { lib, format } => cli::graph(&mut std::io::stdout(), &lib, format)?,
+            CLICommand::DumpSection {
+                obj,
+                section,
+                output,
+                by_id,
+            } => cli::dump_section(&obj, &section, by_id, &output)?,
+            CLICommand::CheckCompat {
+                lib_or_obj,
+                expected,
+                skip_lines,
+            } => cli::check_compat(&mut std::io::stdout(), &lib_or_obj, &expected, skip_lines)?,
+            CLICommand::Diff { old, new, brief } => {
+                cli::diff(&mut std::io::stdout(), &old, &new, brief)?
+            }
+            CLICommand::GenerateGrammar { format, output } => match output {
+                Some(output) => cli::generate_grammar(&mut File::create(output)?, format.into())?,
+                None => cli::generate_grammar(&mut std::io::stdout(), format.into())?,
+            },
+            CLICommand::SplatMatch {
+                config,
+                rom,
+                libs,
+                min_confidence,
+                format,
+            } => cli::splat_match(
+                &mut std::io::stdout(),
+                &config,
+                &rom,
+                &libs,
+                min_confidence,
+                format.into(),
+            )?,
+            CLICommand::Package {
+                lib,
+                output,
+                changelog,
+                sign,
+            } => cli::package(&lib, &output, changelog.as_deref(), sign.as_deref())?,
+            CLICommand::Unpack {
+                bundle,
+                output,
+                verify_key,
+                force,
+            } => cli::unpack(
+                &mut std::io::stdout(),
+                &bundle,
+                &output,
+                verify_key.as_deref(),
+                force,
+            )?,
+            CLICommand::LdscriptBss {
+                lib_or_objs,
+                output,
+                provide,
+                compat_symbols,
+            } => {
+                let options = ldscript::Options {
+                    provide,
+                    compat_symbols,
+                };
+                match output {
+                    Some(output) => {
+                        cli::ldscript_bss(&mut File::create(output)?, &lib_or_objs, &options)?
+                    }
+                    None => cli::ldscript_bss(&mut std::io::stdout(), &lib_or_objs, &options)?,
+                }
+            }
+            CLICommand::ExportAsm {
+                obj,
+                output,
+                comments,
+            } => {
+                let options = asm::AsmOptions { comments };
+                match output {
+                    Some(output) => cli::export_asm(&mut File::create(output)?, &obj, &options)?,
+                    None => cli::export_asm(&mut std::io::stdout(), &obj, &options)?,
+                }
+            }
         },
         None => match args.lib_or_obj {
             Some(lib_or_obj) => {
@@ -0,0 +1,213 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Relocation-insensitive hashes of MIPS code.
+//!
+//! Code in an [OBJ] hasn't been linked yet, so any word with a relocation
+//! will differ from the same word in a linked binary. Masking the address
+//! bits of instructions that are commonly relocated (`j`, `jal`, `lui`,
+//! `addiu`, loads, stores, ...) makes the two comparable.
+
+use std::collections::HashMap;
+
+use super::{Result, Section, OBJ};
+
+/// Clears the bits of `word` which are likely to be changed by relocation.
+pub fn mask_word(word: u32) -> u32 {
+    match word >> 26 {
+        // j, jal
+        0x02 | 0x03 => word & 0xfc00_0000,
+        // addi, addiu, slti, sltiu, andi, ori, xori, lui
+        0x08..=0x0f => word & 0xffff_0000,
+        // loads and stores, including coprocessors
+        0x20..=0x2e | 0x30..=0x33 | 0x38..=0x3b => word & 0xffff_0000,
+        _ => word,
+    }
+}
+
+/// Masks each little-endian word of `code`. Trailing bytes that don't make
+/// up a whole word are ignored.
+pub fn masked_words(code: &[u8]) -> Vec<u32> {
+    code.chunks_exact(4)
+        .map(|w| mask_word(u32::from_le_bytes(w.try_into().expect("word"))))
+        .collect()
+}
+
+const BASE: u64 = 0x0000_0100_0000_01b3;
+
+/// A polynomial hash of `words` which can also be computed incrementally
+/// over a sliding window with [RollingHash].
+pub fn hash(words: &[u32]) -> u64 {
+    words.iter().fold(0u64, |h, w| {
+        h.wrapping_mul(BASE).wrapping_add(*w as u64 + 1)
+    })
+}
+
+/// Hashes every window of a fixed length over a sequence of words.
+pub struct RollingHash<'a> {
+    words: &'a [u32],
+    len: usize,
+    /// `BASE` to the power of `len - 1`
+    high: u64,
+    next: usize,
+    hash: u64,
+}
+
+impl<'a> RollingHash<'a> {
+    pub fn new(words: &'a [u32], len: usize) -> Self {
+        let high = (1..len).fold(1u64, |h, _| h.wrapping_mul(BASE));
+        Self {
+            words,
+            len,
+            high,
+            next: 0,
+            hash: 0,
+        }
+    }
+}
+
+impl Iterator for RollingHash<'_> {
+    /// The word offset of a window and its hash.
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 || self.next + self.len > self.words.len() {
+            return None;
+        }
+        if self.next == 0 {
+            self.hash = hash(&self.words[..self.len]);
+        } else {
+            let out = self.words[self.next - 1] as u64 + 1;
+            let inn = self.words[self.next + self.len - 1] as u64 + 1;
+            self.hash = self
+                .hash
+                .wrapping_sub(out.wrapping_mul(self.high))
+                .wrapping_mul(BASE)
+                .wrapping_add(inn);
+        }
+        let offset = self.next;
+        self.next += 1;
+        Some((offset, self.hash))
+    }
+}
+
+/// The masked code of a function defined by an [OBJ].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    /// The exported name of the function, or `None` for code which precedes
+    /// the first export.
+    pub name: Option<String>,
+    /// The offset of the function in its section.
+    pub offset: u32,
+    pub words: Vec<u32>,
+}
+
+impl Function {
+    pub fn hash(&self) -> u64 {
+        hash(&self.words)
+    }
+}
+
+/// Returns the code of the `.text` section of `obj`. Sections are assumed
+/// to be `.text` if no [LNKHeader](super::LNKHeader) names them. It is an
+/// error for the code to have been [deferred](super::Code::deferred).
+pub fn text(obj: &OBJ) -> Result<Vec<u8>> {
+    let mut names = HashMap::new();
+    let mut current = None;
+    let mut text = Vec::new();
+    for section in obj.sections() {
+        match section {
+            Section::LNKHeader(header) => {
+                names.insert(header.section(), header.type_name());
+            }
+            Section::SectionSwitch(id) => current = Some(*id),
+            Section::Code(code) => {
+                let is_text = current
+                    .and_then(|id| names.get(&id))
+                    .is_none_or(|name| name == ".text");
+                if is_text {
+                    text.extend(code.code()?);
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(text)
+}
+
+/// Splits the `.text` section of `obj` into functions at each exported
+/// symbol. It is an error for the code to have been
+/// [deferred](super::Code::deferred).
+pub fn functions(obj: &OBJ) -> Result<Vec<Function>> {
+    let text_sections = obj
+        .sections()
+        .iter()
+        .filter_map(|s| match s {
+            Section::LNKHeader(h) if h.type_name() == ".text" => Some(h.section()),
+            _ => None,
+        })
+        .collect::<Vec<u16>>();
+
+    let mut starts = obj
+        .sections()
+        .iter()
+        .filter_map(|s| match s {
+            Section::XDEF(xdef)
+                if text_sections.is_empty() || text_sections.contains(&xdef.section()) =>
+            {
+                Some((xdef.offset(), Some(xdef.symbol_name())))
+            }
+            _ => None,
+        })
+        .collect::<Vec<(u32, Option<String>)>>();
+    starts.sort();
+    if starts.first().is_none_or(|(offset, _)| *offset > 0) {
+        starts.insert(0, (0, None));
+    }
+
+    let words = masked_words(&text(obj)?);
+    let end = words.len() as u32 * 4;
+    Ok(starts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (offset, name))| {
+            let next = starts.get(i + 1).map_or(end, |(o, _)| *o).min(end);
+            (*offset < next).then(|| Function {
+                name: name.clone(),
+                offset: *offset,
+                words: words[(*offset / 4) as usize..(next / 4) as usize].to_vec(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mask_word() {
+        // jal 0x80012345
+        assert_eq!(0x0c00_0000, mask_word(0x0c00_48d1));
+        // lui $v0, 0x8001
+        assert_eq!(0x3c02_0000, mask_word(0x3c02_8001));
+        // lw $ra, 0x10($sp)
+        assert_eq!(0x8fbf_0000, mask_word(0x8fbf_0010));
+        // addu $v0, $a0, $a1 and beq are left alone
+        assert_eq!(0x0085_1021, mask_word(0x0085_1021));
+        assert_eq!(0x1080_001d, mask_word(0x1080_001d));
+    }
+
+    #[test]
+    fn test_rolling_hash() {
+        let words = [1, 2, 3, 4, 5, 0xffff_ffff, 7];
+        for len in 1..=words.len() {
+            let rolled = RollingHash::new(&words, len).collect::<Vec<_>>();
+            assert_eq!(words.len() - len + 1, rolled.len());
+            for (offset, h) in rolled {
+                assert_eq!(hash(&words[offset..offset + len]), h);
+            }
+        }
+        assert_eq!(0, RollingHash::new(&words, 8).count());
+    }
+}
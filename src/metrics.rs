@@ -0,0 +1,313 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Opt-in metrics for reading and writing files.
+//!
+//! Services embedding psyk can install a [Recorder] with [set_recorder] to
+//! observe every call to the [io](super::io) read and write functions. Like
+//! the `log` crate, a recorder can only be installed once per process.
+//!
+//! ```
+//! use psyk::metrics::{self, InMemoryRecorder};
+//!
+//! let recorder = InMemoryRecorder::new();
+//! metrics::set_recorder(Box::new(recorder.clone())).expect("first recorder");
+//!
+//! // ... read some files ...
+//!
+//! let snapshot = recorder.snapshot();
+//! println!("{} files parsed", snapshot.parsed());
+//! ```
+//!
+//! When no recorder is installed, instrumentation costs a single relaxed
+//! atomic load per call. No timing or section counting is done.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use binrw::meta::ReadMagic;
+
+use super::error::{Error, Result};
+use super::{LIB, OBJ};
+
+/// The type of file that was read or written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileKind {
+    LIB,
+    OBJ,
+    /// A COFF object for the little-endian MIPS R3000. Some tools in the
+    /// SDKs were built with other toolchains.
+    COFF,
+    /// A `PS-X EXE` executable, as written by `PSYLINK.EXE`.
+    EXE,
+    /// Debugging symbols written by `PSYLINK.EXE` alongside an executable.
+    SYM,
+    /// The file could not be identified, e.g. it is missing or empty.
+    Unknown,
+}
+
+impl FileKind {
+    /// The machine type in a COFF file header for the little-endian MIPS
+    /// R3000.
+    const COFF_MIPSEL: [u8; 2] = 0x0162u16.to_le_bytes();
+
+    /// Identifies a file by its magic number, which is in at most the
+    /// first 8 bytes of `bytes`.
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        match bytes {
+            _ if bytes.starts_with(&LIB::MAGIC) => Self::LIB,
+            _ if bytes.starts_with(&OBJ::MAGIC) => Self::OBJ,
+            _ if bytes.starts_with(b"PS-X EXE") => Self::EXE,
+            _ if bytes.starts_with(b"MND") => Self::SYM,
+            _ if bytes.starts_with(&Self::COFF_MIPSEL) => Self::COFF,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Why reading or writing a file failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    /// The file does not exist.
+    NotFound,
+    /// Any other I/O error.
+    Io,
+    /// The file starts with the wrong magic number for the requested type.
+    BadMagic,
+    /// The file is too small or its magic number isn't recognized.
+    Unrecognized,
+    /// The file contents could not be parsed or serialized.
+    Format,
+}
+
+impl ErrorKind {
+    /// Classifies an error returned by the [io](super::io) functions.
+    pub fn classify(error: &Error) -> Self {
+        match error {
+            Error::BadMagic { .. } => Self::BadMagic,
+            Error::Parse {
+                source: binrw::Error::BadMagic { .. },
+                ..
+            } => Self::BadMagic,
+            Error::Parse { .. } => Self::Format,
+            Error::Io(error) if error.kind() == std::io::ErrorKind::NotFound => Self::NotFound,
+            Error::Io(_) => Self::Io,
+            _ => Self::Unrecognized,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::NotFound => "not_found",
+            Self::Io => "io",
+            Self::BadMagic => "bad_magic",
+            Self::Unrecognized => "unrecognized",
+            Self::Format => "format",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The result of reading or writing a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    Err(ErrorKind),
+}
+
+impl Outcome {
+    pub(crate) fn of<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => Self::Ok,
+            Err(e) => Self::Err(ErrorKind::classify(e)),
+        }
+    }
+}
+
+/// The number of sections of each [kind](super::Section::kind) in a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SectionCounts {
+    pub counts: BTreeMap<&'static str, u64>,
+}
+
+impl SectionCounts {
+    /// Counts the sections of `obj`.
+    pub fn of_obj(obj: &OBJ) -> Self {
+        let mut counts = Self::default();
+        counts.add_obj(obj);
+        counts
+    }
+
+    /// Counts the sections of every module in `lib`.
+    pub fn of_lib(lib: &LIB) -> Self {
+        let mut counts = Self::default();
+        for module in lib.modules() {
+            counts.add_obj(module.object());
+        }
+        counts
+    }
+
+    fn add_obj(&mut self, obj: &OBJ) {
+        for section in obj.sections() {
+            *self.counts.entry(section.kind()).or_default() += 1;
+        }
+    }
+
+    /// Adds the counts from `other` to these counts.
+    pub fn merge(&mut self, other: &SectionCounts) {
+        for (kind, count) in &other.counts {
+            *self.counts.entry(kind).or_default() += count;
+        }
+    }
+}
+
+/// Receives metrics. Implementations must be cheap and thread-safe as they
+/// are called inline by the [io](super::io) functions.
+pub trait Recorder: Send + Sync {
+    /// Called after a file has been read and parsed, successfully or not.
+    /// `bytes` is the size of the file, or 0 if it couldn't be read.
+    fn record_parse(&self, kind: FileKind, bytes: u64, duration: Duration, outcome: &Outcome);
+
+    /// Called after a file has been serialized and written, successfully or
+    /// not.
+    fn record_write(&self, _kind: FileKind, _bytes: u64, _duration: Duration, _outcome: &Outcome) {}
+
+    /// Called after a file has been parsed successfully with the number of
+    /// sections it contains.
+    fn record_sections(&self, _counts: &SectionCounts) {}
+}
+
+/// Returned by [set_recorder] when a recorder has already been installed.
+#[derive(Debug)]
+pub struct SetRecorderError;
+
+impl fmt::Display for SetRecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a metrics recorder has already been installed")
+    }
+}
+
+impl std::error::Error for SetRecorderError {}
+
+static RECORDER: OnceLock<Box<dyn Recorder>> = OnceLock::new();
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the process-wide recorder. Fails if a recorder has already been
+/// installed.
+pub fn set_recorder(recorder: Box<dyn Recorder>) -> Result<(), SetRecorderError> {
+    RECORDER.set(recorder).map_err(|_| SetRecorderError)?;
+    INSTALLED.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Returns `true` once a recorder has been installed. Until then, `io`
+/// reads and writes check this and record nothing.
+#[inline]
+pub fn is_enabled() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Returns the installed recorder, if any.
+#[inline]
+pub fn recorder() -> Option<&'static dyn Recorder> {
+    if !is_enabled() {
+        return None;
+    }
+    RECORDER.get().map(|r| r.as_ref())
+}
+
+/// Totals for one [FileKind] and operation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// The number of successful operations.
+    pub ok: u64,
+    /// The number of failed operations.
+    pub errors: u64,
+    /// The total size of all files.
+    pub bytes: u64,
+    /// The total time spent.
+    pub duration: Duration,
+}
+
+impl Totals {
+    fn record(&mut self, bytes: u64, duration: Duration, outcome: &Outcome) {
+        match outcome {
+            Outcome::Ok => self.ok += 1,
+            Outcome::Err(_) => self.errors += 1,
+        }
+        self.bytes += bytes;
+        self.duration += duration;
+    }
+}
+
+/// A point in time copy of the metrics held by an [InMemoryRecorder].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub parses: BTreeMap<FileKind, Totals>,
+    pub writes: BTreeMap<FileKind, Totals>,
+    /// Failed reads and writes by cause.
+    pub errors: BTreeMap<ErrorKind, u64>,
+    pub sections: SectionCounts,
+}
+
+impl Snapshot {
+    /// The number of files parsed successfully.
+    pub fn parsed(&self) -> u64 {
+        self.parses.values().map(|t| t.ok).sum()
+    }
+}
+
+/// A [Recorder] which keeps running totals in memory.
+///
+/// Clones share the same totals, so a clone can be installed with
+/// [set_recorder] while the original is used to take [snapshots](Self::snapshot).
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryRecorder {
+    metrics: Arc<Mutex<Snapshot>>,
+}
+
+impl InMemoryRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the current totals.
+    pub fn snapshot(&self) -> Snapshot {
+        self.metrics.lock().expect("metrics").clone()
+    }
+}
+
+impl Recorder for InMemoryRecorder {
+    fn record_parse(&self, kind: FileKind, bytes: u64, duration: Duration, outcome: &Outcome) {
+        let mut metrics = self.metrics.lock().expect("metrics");
+        metrics
+            .parses
+            .entry(kind)
+            .or_default()
+            .record(bytes, duration, outcome);
+        if let Outcome::Err(error) = outcome {
+            *metrics.errors.entry(*error).or_default() += 1;
+        }
+    }
+
+    fn record_write(&self, kind: FileKind, bytes: u64, duration: Duration, outcome: &Outcome) {
+        let mut metrics = self.metrics.lock().expect("metrics");
+        metrics
+            .writes
+            .entry(kind)
+            .or_default()
+            .record(bytes, duration, outcome);
+        if let Outcome::Err(error) = outcome {
+            *metrics.errors.entry(*error).or_default() += 1;
+        }
+    }
+
+    fn record_sections(&self, counts: &SectionCounts) {
+        self.metrics.lock().expect("metrics").sections.merge(counts);
+    }
+}
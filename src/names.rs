@@ -0,0 +1,468 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Names for locations that an archive doesn't name itself.
+//!
+//! Release builds of the SDK libraries were stripped of their local
+//! symbols, but many of the functions they defined have since been
+//! identified. A names file records those names so they can be shown in
+//! listings without modifying the archives:
+//!
+//! ```text
+//! # LIBRARY:MODULE:SECTION+OFFSET name
+//! LIBC2:SPRINTF:sprintf+0x2c0 pad     # pads a field to its width
+//! LIBGPU:*:.text+0x10 gpu_wait
+//! symbol _padd=pad_output
+//! ```
+//!
+//! `SECTION` is either a section name such as `.text` or a symbol defined
+//! by the module, and `OFFSET` is added to its start. `LIBRARY` and
+//! `MODULE` are compared without regard to case and `*` matches any name.
+//! A loose OBJ uses its file name without the extension as both its
+//! library and module name. `symbol OLD=NEW` shows the symbol `OLD` as
+//! `NEW` wherever it appears.
+//!
+//! Names from an overlay are shown with a [MARKER] so they aren't mistaken
+//! for names from the archive. If a name is given to a location the archive
+//! already names, the archive's name is kept and a warning is reported.
+//!
+//! ```
+//! use psyk::names::NameOverlay;
+//!
+//! let overlay: NameOverlay = "*:MAIN:main+4 after_prologue".parse().unwrap();
+//! assert_eq!(1, overlay.names().len());
+//! assert!("*:MAIN:main+4".parse::<NameOverlay>().is_err());
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{bail, Error, Result};
+
+use super::symbols::{self, symbol_eq};
+use super::{Section, OBJ};
+
+/// Prefixed to names from an overlay when they're shown.
+pub const MARKER: &str = "~";
+
+/// A name for a location in a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverlayName {
+    pub library: String,
+    pub module: String,
+    /// A section name or a symbol defined by the module.
+    pub anchor: String,
+    /// The offset of the location from the start of `anchor`.
+    pub offset: u32,
+    pub name: String,
+    /// The line of the names file the name was read from.
+    pub line: usize,
+}
+
+impl OverlayName {
+    fn applies_to(&self, library: &str, module: &str) -> bool {
+        (self.library == "*" || symbols::module_eq(&self.library, library))
+            && (self.module == "*" || symbols::module_eq(&self.module, module))
+    }
+}
+
+/// Names and symbol aliases read from a names file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameOverlay {
+    names: Vec<OverlayName>,
+    aliases: Vec<(String, String)>,
+}
+
+/// A location named by an overlay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedName {
+    pub section: u16,
+    pub offset: u32,
+    pub name: String,
+}
+
+/// The names an overlay gives to the locations of one module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Resolution {
+    pub names: Vec<ResolvedName>,
+    /// Names that couldn't be placed or that conflict with the module's own
+    /// symbols.
+    pub warnings: Vec<String>,
+}
+
+impl Resolution {
+    /// The names in `section` at or after `start` and before `end`.
+    pub fn in_range(
+        &self,
+        section: u16,
+        start: u32,
+        end: u32,
+    ) -> impl Iterator<Item = &ResolvedName> {
+        self.names
+            .iter()
+            .filter(move |n| n.section == section && (start..end).contains(&n.offset))
+    }
+}
+
+impl NameOverlay {
+    /// Reads a names file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::other(format!("could not read {}: {e}", path.display())))?;
+        text.parse()
+            .map_err(|e: Error| Error::other(format!("{}: {e}", path.display())))
+    }
+
+    /// The location names, in the order they were read.
+    pub fn names(&self) -> &[OverlayName] {
+        &self.names
+    }
+
+    /// The name `symbol` is shown as, if it has been aliased.
+    pub fn alias(&self, symbol: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .rev()
+            .find(|(old, _)| symbol_eq(old, symbol))
+            .map(|(_, new)| new.as_str())
+    }
+
+    /// Places the names for `module` of `library` in `obj`. Names on a
+    /// location `obj` already has a symbol for are dropped with a warning.
+    pub fn resolve(&self, library: &str, module: &str, obj: &OBJ) -> Resolution {
+        let mut sections = HashMap::new();
+        let mut defined = HashMap::new();
+        let mut real = HashMap::new();
+        for section in obj.sections() {
+            match section {
+                Section::LNKHeader(header) => {
+                    sections.insert(header.type_name(), header.section);
+                }
+                Section::XDEF(xdef) => {
+                    defined.insert(xdef.symbol_name(), (xdef.section, xdef.offset));
+                    real.insert((xdef.section, xdef.offset), xdef.symbol_name());
+                }
+                Section::LocalSymbol(symbol) => {
+                    defined.insert(symbol.name(), (symbol.section, symbol.offset));
+                    real.entry((symbol.section, symbol.offset))
+                        .or_insert_with(|| symbol.name());
+                }
+                _ => (),
+            }
+        }
+
+        let mut resolution = Resolution::default();
+        for name in self.names.iter().filter(|n| n.applies_to(library, module)) {
+            let start = sections
+                .get(&name.anchor)
+                .map(|section| (*section, 0))
+                .or_else(|| defined.get(&name.anchor).copied());
+            let Some((section, start)) = start else {
+                resolution.warnings.push(format!(
+                    "line {}: {module} has no section or symbol named {}",
+                    name.line, name.anchor
+                ));
+                continue;
+            };
+            let offset = start.wrapping_add(name.offset);
+            if let Some(symbol) = real.get(&(section, offset)) {
+                if !symbol_eq(symbol, &name.name) {
+                    resolution.warnings.push(format!(
+                        "line {}: {module} already names {}+0x{:x} {symbol}, ignoring {}",
+                        name.line, name.anchor, name.offset, name.name
+                    ));
+                }
+                continue;
+            }
+            resolution.names.push(ResolvedName {
+                section,
+                offset,
+                name: name.name.clone(),
+            });
+        }
+        resolution.names.sort_by_key(|n| (n.section, n.offset));
+        resolution
+    }
+}
+
+/// Parses a names file. Every malformed line is reported with its number.
+impl FromStr for NameOverlay {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut overlay = Self::default();
+        let mut errors = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match parse_line(line, line_number) {
+                Ok(Entry::Name(name)) => overlay.names.push(name),
+                Ok(Entry::Alias(old, new)) => overlay.aliases.push((old, new)),
+                Err(e) => errors.push(format!("line {line_number}: {e}")),
+            }
+        }
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"));
+        }
+        Ok(overlay)
+    }
+}
+
+enum Entry {
+    Name(OverlayName),
+    Alias(String, String),
+}
+
+fn parse_line(line: &str, number: usize) -> std::result::Result<Entry, String> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if let ["symbol", alias @ ..] = fields.as_slice() {
+        return match alias {
+            [alias] => match alias.split_once('=') {
+                Some((old, new)) if !old.is_empty() && !new.is_empty() => {
+                    Ok(Entry::Alias(old.into(), new.into()))
+                }
+                _ => Err(format!("expected `symbol OLD=NEW`, found `{line}`")),
+            },
+            _ => Err(format!("expected `symbol OLD=NEW`, found `{line}`")),
+        };
+    }
+
+    let [location, name] = fields.as_slice() else {
+        return Err(format!(
+            "expected `LIBRARY:MODULE:SECTION+OFFSET name`, found `{line}`"
+        ));
+    };
+    let [library, module, anchor] = location.split(':').collect::<Vec<_>>()[..] else {
+        return Err(format!(
+            "expected a location of the form LIBRARY:MODULE:SECTION+OFFSET, found `{location}`"
+        ));
+    };
+    let (anchor, offset) = match anchor.rsplit_once('+') {
+        Some((anchor, offset)) => (anchor, parse_offset(offset)?),
+        None => (anchor, 0),
+    };
+    if library.is_empty() || module.is_empty() || anchor.is_empty() {
+        return Err(format!(
+            "the library, module, and section of `{location}` must not be empty"
+        ));
+    }
+    Ok(Entry::Name(OverlayName {
+        library: library.into(),
+        module: module.into(),
+        anchor: anchor.into(),
+        offset,
+        name: name.to_string(),
+        line: number,
+    }))
+}
+
+/// Parses a hexadecimal offset prefixed with `0x` or a decimal offset.
+fn parse_offset(offset: &str) -> std::result::Result<u32, String> {
+    let parsed = match offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => offset.parse(),
+    };
+    parsed.map_err(|_| format!("`{offset}` is not an offset"))
+}
+
+/// A named location in a section of an [OBJ] that contains code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub section: u16,
+    pub offset: u32,
+    pub name: String,
+    /// `true` if the name came from a [NameOverlay].
+    pub overlay: bool,
+}
+
+/// The exported and local symbols in the code sections of `obj` and the
+/// names from `resolution`, ordered by section and offset.
+pub fn functions(obj: &OBJ, resolution: &Resolution) -> Vec<Label> {
+    let mut current = 0;
+    let mut code_sections = Vec::new();
+    for section in obj.sections() {
+        match section {
+            Section::SectionSwitch(id) => current = *id,
+            Section::Code(_) if !code_sections.contains(&current) => code_sections.push(current),
+            _ => (),
+        }
+    }
+
+    let mut labels = obj
+        .sections()
+        .iter()
+        .filter_map(|section| match section {
+            Section::XDEF(xdef) => Some((xdef.section, xdef.offset, xdef.symbol_name())),
+            Section::LocalSymbol(symbol) => Some((symbol.section, symbol.offset, symbol.name())),
+            _ => None,
+        })
+        .filter(|(section, _, _)| code_sections.contains(section))
+        .map(|(section, offset, name)| Label {
+            section,
+            offset,
+            name,
+            overlay: false,
+        })
+        .chain(resolution.names.iter().map(|n| Label {
+            section: n.section,
+            offset: n.offset,
+            name: n.name.clone(),
+            overlay: true,
+        }))
+        .collect::<Vec<_>>();
+    labels.sort_by_key(|l| (l.section, l.offset));
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LNKHeader, LocalSymbol, XDEF};
+
+    fn obj() -> OBJ {
+        let name = b".text".to_vec();
+        let xdef = b"sprintf".to_vec();
+        let local = b"loop".to_vec();
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader {
+                section: 1,
+                group: 0,
+                align: 8,
+                type_name_size: name.len() as u8,
+                type_name: name,
+            }),
+            Section::SectionSwitch(1),
+            Section::Code(crate::Code {
+                size: 16,
+                deferred: None,
+                code: vec![0; 16],
+            }),
+            Section::XDEF(XDEF {
+                number: 1,
+                section: 1,
+                offset: 4,
+                symbol_name_size: xdef.len() as u8,
+                symbol_name: xdef,
+            }),
+            Section::LocalSymbol(LocalSymbol {
+                section: 1,
+                offset: 12,
+                name_size: local.len() as u8,
+                name: local,
+            }),
+            Section::NOP,
+        ])
+    }
+
+    #[test]
+    fn test_parse() {
+        let overlay: NameOverlay = "\
+            # a comment\n\
+            LIBC2:SPRINTF:sprintf+0x2c0 pad   # trailing comment\n\
+            *:*:.text pad2\n\
+            \n\
+            LIBC2:SPRINTF:.data+16 table\n\
+            symbol _padd=pad_output\n"
+            .parse()
+            .unwrap();
+        assert_eq!(3, overlay.names().len());
+        assert_eq!(
+            OverlayName {
+                library: "LIBC2".into(),
+                module: "SPRINTF".into(),
+                anchor: "sprintf".into(),
+                offset: 0x2c0,
+                name: "pad".into(),
+                line: 2,
+            },
+            overlay.names()[0]
+        );
+        assert_eq!(
+            (".text", 0),
+            (
+                overlay.names()[1].anchor.as_str(),
+                overlay.names()[1].offset
+            )
+        );
+        assert_eq!(
+            (16, 5),
+            (overlay.names()[2].offset, overlay.names()[2].line)
+        );
+        assert_eq!(Some("pad_output"), overlay.alias("_padd"));
+        assert_eq!(None, overlay.alias("_PADD"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "*:*:.text+4 ok\n*:*:.text+4\n\nfoo bar\nsymbol a=\n*:*:.text+0xzz bad\n"
+            .parse::<NameOverlay>()
+            .unwrap_err()
+            .to_string();
+        let lines = error.lines().collect::<Vec<_>>();
+        assert_eq!(4, lines.len(), "{error}");
+        assert!(lines[0].starts_with("line 2: "), "{error}");
+        assert!(lines[1].starts_with("line 4: "), "{error}");
+        assert!(lines[2].starts_with("line 5: "), "{error}");
+        assert!(lines[3].starts_with("line 6: "), "{error}");
+        assert!(lines[3].contains("`0xzz` is not an offset"), "{error}");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let overlay: NameOverlay = "\
+            lib:sprintf:sprintf+4 helper\n\
+            *:SPRINTF:.text+4 other\n\
+            *:SPRINTF:.text+0xc loop\n\
+            *:SPRINTF:.text+0 start\n\
+            *:SPRINTF:missing+0 lost\n\
+            *:OTHER:.text+0 elsewhere\n"
+            .parse()
+            .unwrap();
+        let resolution = overlay.resolve("LIB", "SPRINTF", &obj());
+        assert_eq!(
+            vec![
+                ResolvedName {
+                    section: 1,
+                    offset: 0,
+                    name: "start".into()
+                },
+                ResolvedName {
+                    section: 1,
+                    offset: 8,
+                    name: "helper".into()
+                },
+            ],
+            resolution.names
+        );
+        // `other` conflicts with sprintf, `loop` matches the real name
+        assert_eq!(2, resolution.warnings.len(), "{:?}", resolution.warnings);
+        assert!(resolution.warnings[0].contains("sprintf, ignoring other"));
+        assert!(resolution.warnings[1].contains("no section or symbol named missing"));
+        assert_eq!(1, resolution.in_range(1, 4, 16).count());
+    }
+
+    #[test]
+    fn test_functions() {
+        let overlay: NameOverlay = "*:*:.text+8 helper".parse().unwrap();
+        let obj = obj();
+        let labels = functions(&obj, &overlay.resolve("", "SPRINTF", &obj));
+        assert_eq!(
+            vec![
+                ("sprintf".to_string(), 4, false),
+                ("helper".to_string(), 8, true),
+                ("loop".to_string(), 12, false),
+            ],
+            labels
+                .into_iter()
+                .map(|l| (l.name, l.offset, l.overlay))
+                .collect::<Vec<_>>()
+        );
+    }
+}
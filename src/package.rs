@@ -0,0 +1,519 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Signed redistribution bundles of a [LIB](crate::LIB).
+//!
+//! A bundle is a zip archive holding a LIB, optionally its changelog, a
+//! [Manifest] of their sizes and SHA-256 hashes, and optionally an ed25519
+//! signature of the manifest. Reading a bundle checks its files against
+//! the manifest and, given a public key, the manifest against the
+//! signature. The result is a [BundleVerification].
+//!
+//! ```
+//! use std::io::Cursor;
+//! use psyk::package::{self, BundleFile, BundleVerification};
+//! # use psyk::{Module, ModuleMetadata, Section, LIB, OBJ};
+//! # let obj = OBJ::new(vec![Section::NOP]);
+//! # let metadata = ModuleMetadata::new("A".into(), std::time::SystemTime::now(), 0, vec![])?;
+//! # let lib_bytes = LIB::new(vec![Module::new(obj, metadata)]).to_bytes()?;
+//!
+//! let secret = [7; 32];
+//! let lib = BundleFile::new("A.LIB", lib_bytes);
+//! let mut bundle = Cursor::new(Vec::new());
+//! package::write_bundle(&mut bundle, &lib, None, Some(&secret))?;
+//!
+//! let public = package::public_key(&secret);
+//! let read = package::read_bundle(&mut bundle, Some(&public))?;
+//! assert_eq!(BundleVerification::Verified, read.verification);
+//! assert_eq!(lib, read.lib);
+//! # Ok::<(), psyk::Error>(())
+//! ```
+//!
+//! Files are stored uncompressed. Keys are raw 32-byte ed25519 secret or
+//! public keys, as read by [read_key].
+//!
+//! # Manifest
+//!
+//! The manifest is UTF-8 text. The first line identifies the format, and
+//! each following line lists a file, the LIB first:
+//!
+//! ```text
+//! psyk-bundle 1
+//! <SHA-256 in hex> <size in bytes> <name>
+//! ```
+
+use std::fmt;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::{bail, Error, Result};
+
+use super::io;
+
+/// The name of the manifest in a bundle.
+pub const MANIFEST: &str = "MANIFEST";
+
+/// The name of the manifest's detached signature in a bundle.
+pub const SIGNATURE: &str = "MANIFEST.sig";
+
+/// The first line of a [Manifest].
+const HEADER: &str = "psyk-bundle 1";
+
+/// A file stored in a bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl BundleFile {
+    pub fn new(name: &str, bytes: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            bytes,
+        }
+    }
+
+    /// Reads the file at `path`, naming it after the file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let Some(name) = path.file_name() else {
+            bail!("{} is not a file", path.display());
+        };
+        Ok(Self::new(&name.to_string_lossy(), io::read_bytes(path)?))
+    }
+}
+
+/// A file listed in a [Manifest].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub size: u64,
+    pub sha256: [u8; 32],
+}
+
+impl ManifestEntry {
+    fn of(file: &BundleFile) -> Self {
+        Self {
+            name: file.name.clone(),
+            size: file.bytes.len() as u64,
+            sha256: Sha256::digest(&file.bytes).into(),
+        }
+    }
+}
+
+/// The sizes and hashes of the files in a bundle, the LIB first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for entry in &self.entries {
+            writeln!(f, "{} {} {}", hex(&entry.sha256), entry.size, entry.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            bail!("the manifest doesn't start with {HEADER:?}");
+        }
+        let entries = lines
+            .map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let (Some(sha256), Some(size), Some(name)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    bail!("malformed manifest line {line:?}");
+                };
+                let (Some(sha256), Ok(size)) = (unhex(sha256), size.parse()) else {
+                    bail!("malformed manifest line {line:?}");
+                };
+                Ok(ManifestEntry {
+                    name: name.to_string(),
+                    size,
+                    sha256,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if entries.is_empty() {
+            bail!("the manifest doesn't list a LIB");
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Whether a bundle read by [read_bundle] can be trusted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BundleVerification {
+    /// The files match the manifest, and the manifest was signed with the
+    /// secret key of the public key given.
+    Verified,
+    /// The files match the manifest. No public key was given, so the
+    /// signature, if there is one, wasn't checked.
+    Unchecked,
+    /// `file`'s size or hash isn't the one in the manifest.
+    HashMismatch { file: String },
+    /// A public key was given, but the bundle has no signature.
+    MissingSignature,
+    /// The signature wasn't made with the secret key of the public key
+    /// given, or the manifest has changed since it was signed.
+    BadSignature,
+}
+
+impl BundleVerification {
+    /// Returns `true` if nothing was found to be wrong.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Verified | Self::Unchecked)
+    }
+}
+
+impl fmt::Display for BundleVerification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Verified => write!(f, "the manifest and signature are valid"),
+            Self::Unchecked => write!(f, "the manifest is valid, the signature wasn't checked"),
+            Self::HashMismatch { file } => write!(f, "{file} doesn't match the manifest"),
+            Self::MissingSignature => write!(f, "the bundle isn't signed"),
+            Self::BadSignature => write!(f, "the signature doesn't match the key"),
+        }
+    }
+}
+
+/// A bundle read by [read_bundle].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bundle {
+    pub lib: BundleFile,
+    pub changelog: Option<BundleFile>,
+    pub manifest: Manifest,
+    pub signature: Option<Vec<u8>>,
+    pub verification: BundleVerification,
+}
+
+impl Bundle {
+    /// Returns the LIB, the changelog, and the manifest and signature as
+    /// they are stored in the bundle.
+    pub fn files(&self) -> Vec<BundleFile> {
+        let mut files = vec![self.lib.clone()];
+        files.extend(self.changelog.clone());
+        files.push(BundleFile::new(MANIFEST, self.manifest.to_string().into()));
+        if let Some(signature) = &self.signature {
+            files.push(BundleFile::new(SIGNATURE, signature.clone()));
+        }
+        files
+    }
+}
+
+/// Writes a bundle of `lib` and `changelog` to `write`, signing it with
+/// `secret_key` if there is one. It is an error for `lib` not to be a LIB.
+pub fn write_bundle(
+    write: impl Write + Seek,
+    lib: &BundleFile,
+    changelog: Option<&BundleFile>,
+    secret_key: Option<&[u8; 32]>,
+) -> Result<()> {
+    io::read_lib_from(&mut std::io::Cursor::new(&lib.bytes))
+        .map_err(|e| Error::other(format!("{}: {e}", lib.name)))?;
+    let files = std::iter::once(lib).chain(changelog).collect::<Vec<_>>();
+    for file in &files {
+        check_name(&file.name)?;
+    }
+    if changelog.is_some_and(|changelog| changelog.name == lib.name) {
+        bail!("the LIB and changelog are both named {}", lib.name);
+    }
+
+    let manifest = Manifest {
+        entries: files.iter().map(|file| ManifestEntry::of(file)).collect(),
+    }
+    .to_string();
+    let mut zip = ZipWriter::new(write);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<()> {
+        zip.start_file(name, options).map_err(Error::other)?;
+        zip.write_all(bytes)?;
+        Ok(())
+    };
+    for file in &files {
+        add(&file.name, &file.bytes)?;
+    }
+    add(MANIFEST, manifest.as_bytes())?;
+    if let Some(secret_key) = secret_key {
+        let signature = SigningKey::from_bytes(secret_key).sign(manifest.as_bytes());
+        add(SIGNATURE, &signature.to_bytes())?;
+    }
+    zip.finish().map_err(Error::other)?;
+    Ok(())
+}
+
+/// Reads a bundle from `read` and verifies it, checking its signature
+/// with `public_key` if there is one. It is an error for the bundle to be
+/// missing its manifest or a file the manifest lists.
+pub fn read_bundle(read: impl Read + Seek, public_key: Option<&[u8; 32]>) -> Result<Bundle> {
+    let mut zip = ZipArchive::new(read).map_err(Error::other)?;
+    let manifest_text =
+        read_file(&mut zip, MANIFEST)?.ok_or_else(|| Error::other("the bundle has no manifest"))?;
+    let manifest: Manifest = String::from_utf8(manifest_text.clone())
+        .map_err(Error::other)?
+        .parse()?;
+    let signature = read_file(&mut zip, SIGNATURE)?;
+
+    let mut files = Vec::new();
+    let mut verification = None;
+    for entry in &manifest.entries {
+        check_name(&entry.name)?;
+        let Some(bytes) = read_file(&mut zip, &entry.name)? else {
+            bail!("{} is in the manifest but not the bundle", entry.name);
+        };
+        let file = BundleFile::new(&entry.name, bytes);
+        if verification.is_none() && ManifestEntry::of(&file) != *entry {
+            verification = Some(BundleVerification::HashMismatch {
+                file: entry.name.clone(),
+            });
+        }
+        files.push(file);
+    }
+
+    let verification = verification.unwrap_or_else(|| match (public_key, &signature) {
+        (None, _) => BundleVerification::Unchecked,
+        (Some(_), None) => BundleVerification::MissingSignature,
+        (Some(public_key), Some(signature)) => {
+            if verify(public_key, &manifest_text, signature) {
+                BundleVerification::Verified
+            } else {
+                BundleVerification::BadSignature
+            }
+        }
+    });
+
+    let mut files = files.into_iter();
+    Ok(Bundle {
+        lib: files.next().expect("the manifest lists a LIB"),
+        changelog: files.next(),
+        manifest,
+        signature,
+        verification,
+    })
+}
+
+/// Reads a raw 32-byte ed25519 secret or public key from the file at
+/// `path`.
+pub fn read_key(path: &Path) -> Result<[u8; 32]> {
+    let bytes = io::read_bytes(path)?;
+    bytes.as_slice().try_into().map_err(|_| {
+        Error::other(format!(
+            "{}: a key is 32 bytes, not {}",
+            path.display(),
+            bytes.len()
+        ))
+    })
+}
+
+/// Returns the public key of `secret_key`.
+pub fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    SigningKey::from_bytes(secret_key)
+        .verifying_key()
+        .to_bytes()
+}
+
+/// `true` if `signature` is `public_key`'s signature of `message`.
+fn verify(public_key: &[u8; 32], message: &[u8], signature: &[u8]) -> bool {
+    let (Ok(key), Ok(signature)) = (
+        VerifyingKey::from_bytes(public_key),
+        Signature::from_slice(signature),
+    ) else {
+        return false;
+    };
+    key.verify(message, &signature).is_ok()
+}
+
+/// Reads the file `name` from `zip`, or `None` if there isn't one. The
+/// bytes are read as they are stored, so a file that has been changed is
+/// caught by the manifest rather than the zip's checksum.
+fn read_file(zip: &mut ZipArchive<impl Read + Seek>, name: &str) -> Result<Option<Vec<u8>>> {
+    let Some(index) = zip.index_for_name(name) else {
+        return Ok(None);
+    };
+    let mut file = zip.by_index_raw(index).map_err(Error::other)?;
+    if file.compression() != CompressionMethod::Stored {
+        bail!("{name} is compressed");
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+/// Fails unless `name` can be extracted into a directory as it is.
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name == MANIFEST
+        || name == SIGNATURE
+        || name.contains(['/', '\\', ':'])
+    {
+        bail!("{name:?} can't be the name of a file in a bundle");
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 || !s.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{Module, ModuleMetadata, Section, LIB, OBJ};
+
+    const SECRET: [u8; 32] = [7; 32];
+    /// 2000-01-01, within the range of PSY-Q timestamps.
+    const CREATED: Duration = Duration::from_secs(946_684_800);
+
+    fn lib() -> BundleFile {
+        let obj = OBJ::new(vec![Section::BSS(4), Section::NOP]);
+        let metadata =
+            ModuleMetadata::new("A".into(), UNIX_EPOCH + CREATED, 0, vec![]).expect("metadata");
+        let lib = LIB::new(vec![Module::new(obj, metadata)]);
+        BundleFile::new("A.LIB", lib.to_bytes().expect("lib"))
+    }
+
+    fn bundle(files: &[BundleFile], signature: Option<&[u8]>, manifest: &Manifest) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let manifest = manifest.to_string();
+        let named = files
+            .iter()
+            .map(|file| (file.name.as_str(), file.bytes.as_slice()))
+            .chain([(MANIFEST, manifest.as_bytes())])
+            .chain(signature.map(|signature| (SIGNATURE, signature)));
+        for (name, bytes) in named {
+            zip.start_file(name, options).expect("file");
+            zip.write_all(bytes).expect("write");
+        }
+        zip.finish().expect("zip").into_inner()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let changelog = BundleFile::new("CHANGELOG.md", b"# 1.0\n".to_vec());
+        let mut written = Cursor::new(Vec::new());
+        write_bundle(&mut written, &lib(), Some(&changelog), Some(&SECRET)).expect("write");
+
+        let public = public_key(&SECRET);
+        let read = read_bundle(&mut written, Some(&public)).expect("read");
+        assert_eq!(BundleVerification::Verified, read.verification);
+        assert_eq!(lib(), read.lib);
+        assert_eq!(Some(changelog), read.changelog);
+        assert_eq!(
+            vec!["A.LIB", "CHANGELOG.md", MANIFEST, SIGNATURE],
+            read.files()
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(read.manifest, read.manifest.to_string().parse().unwrap());
+
+        let read = read_bundle(&mut written, None).expect("read");
+        assert_eq!(BundleVerification::Unchecked, read.verification);
+
+        let mut unsigned = Cursor::new(Vec::new());
+        write_bundle(&mut unsigned, &lib(), None, None).expect("write");
+        let read = read_bundle(&mut unsigned, Some(&public)).expect("read");
+        assert_eq!(BundleVerification::MissingSignature, read.verification);
+        assert!(!read.verification.is_valid());
+    }
+
+    #[test]
+    fn test_flipped_byte() {
+        let mut written = Cursor::new(Vec::new());
+        write_bundle(&mut written, &lib(), None, Some(&SECRET)).expect("write");
+        let mut bytes = written.into_inner();
+
+        // the LIB is stored first and uncompressed
+        let lib = lib().bytes;
+        let offset = bytes
+            .windows(lib.len())
+            .position(|w| w == lib)
+            .expect("stored LIB");
+        bytes[offset + lib.len() - 2] ^= 1;
+
+        let read = read_bundle(Cursor::new(bytes), Some(&public_key(&SECRET))).expect("read");
+        assert_eq!(
+            BundleVerification::HashMismatch {
+                file: "A.LIB".into()
+            },
+            read.verification
+        );
+        assert_eq!(
+            "A.LIB doesn't match the manifest",
+            read.verification.to_string()
+        );
+    }
+
+    #[test]
+    fn test_signature_failures() {
+        let mut written = Cursor::new(Vec::new());
+        write_bundle(&mut written, &lib(), None, Some(&SECRET)).expect("write");
+        let read = read_bundle(&mut written, Some(&public_key(&[8; 32]))).expect("read");
+        assert_eq!(BundleVerification::BadSignature, read.verification);
+
+        // a LIB and manifest replaced together keep the old signature
+        let mut other = lib();
+        other.bytes[4 + 8] ^= 1;
+        let manifest = Manifest {
+            entries: vec![ManifestEntry::of(&other)],
+        };
+        let signature = read.signature.expect("signature");
+        let tampered = bundle(&[other], Some(&signature), &manifest);
+        let read = read_bundle(Cursor::new(tampered), Some(&public_key(&SECRET))).expect("read");
+        assert_eq!(BundleVerification::BadSignature, read.verification);
+    }
+
+    #[test]
+    fn test_errors() {
+        let not_lib = BundleFile::new("A.LIB", b"LNK\x02\0".to_vec());
+        let mut written = Cursor::new(Vec::new());
+        assert!(write_bundle(&mut written, &not_lib, None, None).is_err());
+        let changelog = BundleFile::new("../CHANGELOG", vec![]);
+        assert!(write_bundle(&mut written, &lib(), Some(&changelog), None).is_err());
+
+        // a manifest listing a file that isn't there
+        let manifest = Manifest {
+            entries: vec![ManifestEntry::of(&lib())],
+        };
+        let missing = bundle(&[], None, &manifest);
+        let e = read_bundle(Cursor::new(missing), None).expect_err("missing");
+        assert_eq!("A.LIB is in the manifest but not the bundle", e.to_string());
+
+        assert!("psyk-bundle 2\n".parse::<Manifest>().is_err());
+        assert!("psyk-bundle 1\n00 1 A.LIB\n".parse::<Manifest>().is_err());
+        assert!("psyk-bundle 1\n".parse::<Manifest>().is_err());
+    }
+}
@@ -0,0 +1,793 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Analyzer plugins.
+//!
+//! An [AnalyzerPlugin] looks at the sections of each module in a [LIB] or
+//! [OBJ] and reports what it finds as [verify](super::verify) [Findings](Finding), the same
+//! format `psyk verify` uses. Plugins are collected in a [Registry], which
+//! starts with the plugins built into psyk, such as [StringsPlugin].
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::{io, plugin};
+//!
+//! # fn main() -> Result<()> {
+//! let file = io::read(Path::new("SOME.LIB"))?;
+//! let registry = plugin::Registry::with_builtins();
+//! for finding in registry.analyze(&file, &["strings"])? {
+//!     println!("{finding}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Dynamic Plugins
+//!
+//! With the `dynamic-plugins` feature, plugins can also be loaded from
+//! shared libraries with [Registry::load_dir]. Rust trait objects can't
+//! safely cross a shared library boundary, so dynamic plugins export the C
+//! functions described in [abi] instead. A plugin written in Rust can
+//! implement [AnalyzerPlugin] and export it with [export_plugin](crate::export_plugin).
+//! Libraries built for a different [ABI_VERSION] are rejected when they are
+//! loaded.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::{bail, Result};
+
+use super::io::Type;
+use super::verify::{Finding, FindingKind, Severity};
+use super::{Section, LIB, OBJ};
+
+#[cfg(all(feature = "dynamic-plugins", unix))]
+pub mod dynamic;
+
+/// The version of the plugin interface. It is incremented whenever
+/// [AnalyzerPlugin] or [abi] change incompatibly.
+pub const ABI_VERSION: u32 = 1;
+
+/// Identifies a kind of [Section] by the tag it is stored with on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SectionKindId(u8);
+
+impl SectionKindId {
+    pub const CODE: Self = Self(2);
+    pub const SECTION_SWITCH: Self = Self(6);
+    pub const BSS: Self = Self(8);
+    pub const PATCH: Self = Self(10);
+    pub const XDEF: Self = Self(12);
+    pub const XREF: Self = Self(14);
+    pub const LNK_HEADER: Self = Self(16);
+    pub const LOCAL_SYMBOL: Self = Self(18);
+    pub const FILENAME: Self = Self(28);
+    pub const XBSS: Self = Self(48);
+
+    /// The kind of `section`.
+    pub fn of(section: &Section) -> Self {
+        Self(section.tag())
+    }
+
+    pub fn from_tag(tag: u8) -> Self {
+        Self(tag)
+    }
+
+    pub fn tag(&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for SectionKindId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// What a plugin knows about the module it is analyzing.
+///
+/// The symbol, section, and file tables are collected from the whole
+/// [OBJ], so they are complete even when a plugin only wants some kinds of
+/// section.
+pub struct ModuleContext<'a> {
+    module: Option<String>,
+    obj: &'a OBJ,
+    symbols: BTreeMap<u16, String>,
+    sections: BTreeMap<u16, String>,
+    files: BTreeMap<u16, String>,
+    code: BTreeMap<u16, Vec<u8>>,
+}
+
+impl<'a> ModuleContext<'a> {
+    /// Creates a context for `obj`. `module` is the name of the module
+    /// containing it, if it came from a [LIB].
+    pub fn new(module: Option<String>, obj: &'a OBJ) -> Self {
+        let mut context = Self {
+            module,
+            obj,
+            symbols: BTreeMap::new(),
+            sections: BTreeMap::new(),
+            files: BTreeMap::new(),
+            code: BTreeMap::new(),
+        };
+        let mut current = 0;
+        for section in obj.sections() {
+            match section {
+                Section::SectionSwitch(id) => current = *id,
+                Section::Code(code) => context
+                    .code
+                    .entry(current)
+                    .or_default()
+                    .extend_from_slice(&code.code),
+                Section::XDEF(xdef) => {
+                    context.symbols.insert(xdef.number, xdef.symbol_name());
+                }
+                Section::XREF(xref) => {
+                    context.symbols.insert(xref.number, xref.symbol_name());
+                }
+                Section::XBSS(xbss) => {
+                    context.symbols.insert(xbss.number, xbss.name());
+                }
+                Section::LNKHeader(header) => {
+                    context.sections.insert(header.section, header.type_name());
+                }
+                Section::Filename(file) => {
+                    context.files.insert(file.number, file.name());
+                }
+                _ => (),
+            }
+        }
+        context
+    }
+
+    /// The name of the module, if the [OBJ] came from a [LIB].
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+
+    pub fn obj(&self) -> &OBJ {
+        self.obj
+    }
+
+    /// Names of the symbols defined, referenced, or reserved in
+    /// uninitialized data, by symbol number.
+    pub fn symbols(&self) -> &BTreeMap<u16, String> {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, number: u16) -> Option<&str> {
+        self.symbols.get(&number).map(String::as_str)
+    }
+
+    /// Section type names, e.g. `.text`, by section id.
+    pub fn sections(&self) -> &BTreeMap<u16, String> {
+        &self.sections
+    }
+
+    pub fn section_name(&self, id: u16) -> Option<&str> {
+        self.sections.get(&id).map(String::as_str)
+    }
+
+    /// Source file names by file number.
+    pub fn files(&self) -> &BTreeMap<u16, String> {
+        &self.files
+    }
+
+    pub fn file(&self, number: u16) -> Option<&str> {
+        self.files.get(&number).map(String::as_str)
+    }
+
+    /// The code of the section `id`: every [Code](Section::Code) section
+    /// written while it was current, joined in order. Uninitialized data
+    /// isn't included.
+    pub fn code(&self, id: u16) -> Option<&[u8]> {
+        self.code.get(&id).map(Vec::as_slice)
+    }
+
+    /// Creates a [Finding] about this module from `plugin`.
+    pub fn finding(&self, plugin: &str, severity: Severity, message: impl Into<String>) -> Finding {
+        Finding {
+            severity,
+            module: self.module.clone(),
+            kind: FindingKind::Plugin {
+                plugin: plugin.into(),
+                message: message.into(),
+            },
+        }
+    }
+}
+
+/// An analysis run over each module of a [LIB] or [OBJ].
+pub trait AnalyzerPlugin {
+    /// The [ABI_VERSION] the plugin was built against. A [Registry] only
+    /// accepts plugins built against its own version.
+    fn abi_version(&self) -> u32 {
+        ABI_VERSION
+    }
+
+    /// The name used to select the plugin, e.g. `psyk analyze --plugin`.
+    fn name(&self) -> &str;
+
+    /// Returns `true` if sections of `kind` should be passed to
+    /// [analyze_module](Self::analyze_module).
+    fn wants_section(&self, kind: SectionKindId) -> bool;
+
+    /// Analyzes one module. `sections` are the sections of the module's
+    /// [OBJ] the plugin [wants](Self::wants_section), in order. Modules
+    /// without any are skipped.
+    fn analyze_module(&self, ctx: &ModuleContext, sections: &[&Section]) -> Vec<Finding>;
+}
+
+/// Reports NUL terminated runs of printable ASCII in code and data, which
+/// are usually string literals.
+pub struct StringsPlugin {
+    /// The shortest run reported, not including the terminator.
+    pub min_len: usize,
+}
+
+impl Default for StringsPlugin {
+    fn default() -> Self {
+        Self { min_len: 4 }
+    }
+}
+
+impl StringsPlugin {
+    /// Finds strings in `bytes`, returning the offset and text of each.
+    pub fn strings(&self, bytes: &[u8]) -> Vec<(usize, String)> {
+        let printable = |b: &u8| (0x20..0x7f).contains(b) || *b == b'\t' || *b == b'\n';
+        let mut strings = Vec::new();
+        let mut start = 0;
+        for (i, byte) in bytes.iter().enumerate() {
+            if printable(byte) {
+                continue;
+            }
+            if *byte == 0 && i - start >= self.min_len.max(1) {
+                let text = String::from_utf8_lossy(&bytes[start..i]).into_owned();
+                strings.push((start, text));
+            }
+            start = i + 1;
+        }
+        strings
+    }
+}
+
+impl AnalyzerPlugin for StringsPlugin {
+    fn name(&self) -> &str {
+        "strings"
+    }
+
+    fn wants_section(&self, kind: SectionKindId) -> bool {
+        kind == SectionKindId::CODE
+    }
+
+    fn analyze_module(&self, ctx: &ModuleContext, _sections: &[&Section]) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (id, code) in &ctx.code {
+            let section = match ctx.section_name(*id) {
+                Some(name) => name.to_string(),
+                None => format!("section {id}"),
+            };
+            for (offset, text) in self.strings(code) {
+                findings.push(ctx.finding(
+                    self.name(),
+                    Severity::Note,
+                    format!("{section} {offset:#06x}: {text:?}"),
+                ));
+            }
+        }
+        findings
+    }
+}
+
+/// The plugins available to analyze files.
+#[derive(Default)]
+pub struct Registry {
+    plugins: Vec<Box<dyn AnalyzerPlugin>>,
+}
+
+impl Registry {
+    /// Creates a registry without any plugins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry containing the plugins built into psyk.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(Box::new(StringsPlugin::default()))
+            .expect("built-in plugins are compatible");
+        registry
+    }
+
+    /// Adds `plugin` to the registry. Plugins built against a different
+    /// [ABI_VERSION], or with the same name as a registered plugin, are
+    /// rejected.
+    pub fn register(&mut self, plugin: Box<dyn AnalyzerPlugin>) -> Result<()> {
+        if plugin.abi_version() != ABI_VERSION {
+            bail!(
+                "plugin {} was built for plugin ABI version {}, expected {ABI_VERSION}",
+                plugin.name(),
+                plugin.abi_version()
+            );
+        }
+        if self.get(plugin.name()).is_some() {
+            bail!("a plugin named {} is already registered", plugin.name());
+        }
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn AnalyzerPlugin> {
+        self.plugins
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+
+    /// The names of the registered plugins, in the order they were
+    /// registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|p| p.name())
+    }
+
+    /// Loads every plugin in `dir`. Libraries that can't be loaded or
+    /// registered are skipped and returned with the reason.
+    #[cfg(all(feature = "dynamic-plugins", unix))]
+    pub fn load_dir(
+        &mut self,
+        dir: &std::path::Path,
+    ) -> Result<Vec<(std::path::PathBuf, crate::Error)>> {
+        let mut rejected = Vec::new();
+        for path in dynamic::find_libraries(dir)? {
+            let result = dynamic::load(&path).and_then(|p| self.register(Box::new(p)));
+            if let Err(e) = result {
+                rejected.push((path, e));
+            }
+        }
+        Ok(rejected)
+    }
+
+    /// Runs the plugins named `plugins` over `obj`, or all plugins if none
+    /// are named.
+    pub fn analyze_obj(
+        &self,
+        module: Option<String>,
+        obj: &OBJ,
+        plugins: &[&str],
+    ) -> Result<Vec<Finding>> {
+        let selected = self.select(plugins)?;
+        Ok(self.run(&selected, module, obj))
+    }
+
+    /// Runs the plugins named `plugins` over each module in `lib`, or all
+    /// plugins if none are named.
+    pub fn analyze_lib(&self, lib: &LIB, plugins: &[&str]) -> Result<Vec<Finding>> {
+        let selected = self.select(plugins)?;
+        Ok(lib
+            .modules()
+            .iter()
+            .flat_map(|m| self.run(&selected, Some(m.name()), m.object()))
+            .collect())
+    }
+
+    /// Runs the plugins named `plugins` over a [LIB] or [OBJ].
+    pub fn analyze(&self, file: &Type, plugins: &[&str]) -> Result<Vec<Finding>> {
+        match file {
+            Type::LIB(lib) => self.analyze_lib(lib, plugins),
+            Type::OBJ(obj) => self.analyze_obj(None, obj, plugins),
+        }
+    }
+
+    fn select(&self, names: &[&str]) -> Result<Vec<&dyn AnalyzerPlugin>> {
+        if names.is_empty() {
+            return Ok(self.plugins.iter().map(|p| p.as_ref()).collect());
+        }
+        names
+            .iter()
+            .map(|name| match self.get(name) {
+                Some(plugin) => Ok(plugin),
+                None => bail!(
+                    "unknown plugin: {name} (available: {})",
+                    self.names().collect::<Vec<_>>().join(", ")
+                ),
+            })
+            .collect()
+    }
+
+    fn run(
+        &self,
+        plugins: &[&dyn AnalyzerPlugin],
+        module: Option<String>,
+        obj: &OBJ,
+    ) -> Vec<Finding> {
+        let ctx = ModuleContext::new(module, obj);
+        let mut findings = Vec::new();
+        for plugin in plugins {
+            let sections = obj
+                .sections()
+                .iter()
+                .filter(|s| plugin.wants_section(SectionKindId::of(s)))
+                .collect::<Vec<_>>();
+            if !sections.is_empty() {
+                findings.extend(plugin.analyze_module(&ctx, &sections));
+            }
+        }
+        findings
+    }
+}
+
+/// The C interface exported by dynamic plugins.
+///
+/// A plugin library exports these functions, without name mangling:
+///
+/// | Function                                 | Description                                                   |
+/// |------------------------------------------|---------------------------------------------------------------|
+/// | `u32 psyk_plugin_abi_version()`          | The [ABI_VERSION] the plugin was built for. Called first.     |
+/// | `const char *psyk_plugin_name()`         | The plugin name, a NUL terminated string that is never freed. |
+/// | `bool psyk_plugin_wants_section(u8 tag)` | [AnalyzerPlugin::wants_section] for a [SectionKindId] tag.    |
+/// | `void psyk_plugin_analyze(...)`          | Analyzes a module. See [AnalyzeFn](abi::AnalyzeFn).           |
+///
+/// Findings are passed back through an [EmitFn](abi::EmitFn) as they are made, so no
+/// memory allocated by the plugin is freed by psyk or vice versa.
+pub mod abi {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use binrw::io::Cursor;
+    use binrw::BinRead;
+
+    use super::{AnalyzerPlugin, ModuleContext, SectionKindId};
+    use crate::verify::{FindingKind, Severity};
+    use crate::OBJ;
+
+    pub const ABI_VERSION_SYMBOL: &str = "psyk_plugin_abi_version";
+    pub const NAME_SYMBOL: &str = "psyk_plugin_name";
+    pub const WANTS_SECTION_SYMBOL: &str = "psyk_plugin_wants_section";
+    pub const ANALYZE_SYMBOL: &str = "psyk_plugin_analyze";
+
+    /// [Severity::Note] as passed to an [EmitFn].
+    pub const SEVERITY_NOTE: u8 = 0;
+    /// [Severity::Warning] as passed to an [EmitFn].
+    pub const SEVERITY_WARNING: u8 = 1;
+    /// [Severity::Error] as passed to an [EmitFn].
+    pub const SEVERITY_ERROR: u8 = 2;
+
+    pub type AbiVersionFn = unsafe extern "C" fn() -> u32;
+    pub type NameFn = unsafe extern "C" fn() -> *const c_char;
+    pub type WantsSectionFn = unsafe extern "C" fn(tag: u8) -> bool;
+
+    /// Receives one finding. `message` is a NUL terminated string that is
+    /// only valid for the duration of the call.
+    pub type EmitFn =
+        unsafe extern "C" fn(state: *mut c_void, severity: u8, message: *const c_char);
+
+    /// Analyzes a module. `module` is the NUL terminated module name, or
+    /// null for a bare OBJ. `obj` points to the `obj_len` bytes of the OBJ
+    /// as it is stored on disk. Each finding is passed to `emit` along with
+    /// `state`.
+    pub type AnalyzeFn = unsafe extern "C" fn(
+        module: *const c_char,
+        obj: *const u8,
+        obj_len: usize,
+        emit: EmitFn,
+        state: *mut c_void,
+    );
+
+    pub fn severity_to_abi(severity: Severity) -> u8 {
+        match severity {
+            Severity::Note => SEVERITY_NOTE,
+            Severity::Warning => SEVERITY_WARNING,
+            Severity::Error => SEVERITY_ERROR,
+        }
+    }
+
+    /// Converts a severity from an [EmitFn]. Unknown values are errors.
+    pub fn severity_from_abi(severity: u8) -> Severity {
+        match severity {
+            SEVERITY_NOTE => Severity::Note,
+            SEVERITY_WARNING => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Implements `psyk_plugin_analyze` for an [AnalyzerPlugin]. Used by
+    /// [export_plugin](crate::export_plugin).
+    ///
+    /// # Safety
+    ///
+    /// The arguments must be as described by [AnalyzeFn].
+    #[doc(hidden)]
+    pub unsafe fn analyze(
+        plugin: &dyn AnalyzerPlugin,
+        module: *const c_char,
+        obj: *const u8,
+        obj_len: usize,
+        emit: EmitFn,
+        state: *mut c_void,
+    ) {
+        let module = (!module.is_null()).then(|| {
+            unsafe { CStr::from_ptr(module) }
+                .to_string_lossy()
+                .into_owned()
+        });
+        let bytes = unsafe { std::slice::from_raw_parts(obj, obj_len) };
+        let findings = match OBJ::read(&mut Cursor::new(bytes)) {
+            Ok(obj) => {
+                let ctx = ModuleContext::new(module, &obj);
+                let sections = obj
+                    .sections()
+                    .iter()
+                    .filter(|s| plugin.wants_section(SectionKindId::of(s)))
+                    .collect::<Vec<_>>();
+                plugin.analyze_module(&ctx, &sections)
+            }
+            Err(e) => {
+                let ctx = ModuleContext::new(module, &EMPTY);
+                vec![ctx.finding(plugin.name(), Severity::Error, format!("{e}"))]
+            }
+        };
+        for finding in findings {
+            let message = match finding.kind {
+                FindingKind::Plugin { message, .. } => message,
+                kind => kind.to_string(),
+            };
+            let message = std::ffi::CString::new(message.replace('\0', " "))
+                .expect("NUL bytes were replaced");
+            unsafe { emit(state, severity_to_abi(finding.severity), message.as_ptr()) };
+        }
+    }
+
+    static EMPTY: std::sync::LazyLock<OBJ> =
+        std::sync::LazyLock::new(|| OBJ::new(vec![crate::Section::NOP]));
+}
+
+/// Exports an [AnalyzerPlugin] from a `cdylib` through the C functions in
+/// [abi](crate::plugin::abi). `$plugin` is an expression creating the plugin, which
+/// is evaluated once.
+///
+/// ```ignore
+/// psyk::export_plugin!(MyPlugin::default());
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:expr) => {
+        static PSYK_PLUGIN: ::std::sync::LazyLock<
+            ::std::boxed::Box<dyn $crate::plugin::AnalyzerPlugin + Send + Sync>,
+        > = ::std::sync::LazyLock::new(|| ::std::boxed::Box::new($plugin));
+        static PSYK_PLUGIN_NAME: ::std::sync::LazyLock<::std::ffi::CString> =
+            ::std::sync::LazyLock::new(|| {
+                ::std::ffi::CString::new(PSYK_PLUGIN.name()).expect("plugin name")
+            });
+
+        #[no_mangle]
+        pub extern "C" fn psyk_plugin_abi_version() -> u32 {
+            $crate::plugin::ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn psyk_plugin_name() -> *const ::std::ffi::c_char {
+            PSYK_PLUGIN_NAME.as_ptr()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn psyk_plugin_wants_section(tag: u8) -> bool {
+            PSYK_PLUGIN.wants_section($crate::plugin::SectionKindId::from_tag(tag))
+        }
+
+        /// # Safety
+        ///
+        /// See [psyk::plugin::abi::AnalyzeFn].
+        #[no_mangle]
+        pub unsafe extern "C" fn psyk_plugin_analyze(
+            module: *const ::std::ffi::c_char,
+            obj: *const u8,
+            obj_len: usize,
+            emit: $crate::plugin::abi::EmitFn,
+            state: *mut ::std::ffi::c_void,
+        ) {
+            unsafe {
+                $crate::plugin::abi::analyze(
+                    PSYK_PLUGIN.as_ref(),
+                    module,
+                    obj,
+                    obj_len,
+                    emit,
+                    state,
+                )
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Filename, LNKHeader, XDEF, XREF};
+
+    fn obj() -> OBJ {
+        OBJ::new(vec![
+            Section::LNKHeader(LNKHeader {
+                section: 1,
+                group: 0,
+                align: 8,
+                type_name_size: 6,
+                type_name: b".rdata".to_vec(),
+            }),
+            Section::Filename(Filename {
+                number: 1,
+                size: 5,
+                name: b"src.c".to_vec(),
+            }),
+            Section::SectionSwitch(1),
+            Section::Code(Code {
+                size: 5,
+                deferred: None,
+                code: b"\x01\x02hel".to_vec(),
+            }),
+            Section::Code(Code {
+                size: 7,
+                deferred: None,
+                code: b"lo\0ab\0\0".to_vec(),
+            }),
+            Section::XDEF(XDEF {
+                number: 2,
+                section: 1,
+                offset: 0,
+                symbol_name_size: 5,
+                symbol_name: b"hello".to_vec(),
+            }),
+            Section::XREF(XREF {
+                number: 3,
+                symbol_name_size: 6,
+                symbol_name: b"printf".to_vec(),
+            }),
+            Section::NOP,
+        ])
+    }
+
+    /// Reports what it was given so the context and plumbing can be checked.
+    struct Probe;
+
+    impl AnalyzerPlugin for Probe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn wants_section(&self, kind: SectionKindId) -> bool {
+            kind == SectionKindId::XDEF || kind == SectionKindId::XREF
+        }
+
+        fn analyze_module(&self, ctx: &ModuleContext, sections: &[&Section]) -> Vec<Finding> {
+            vec![ctx.finding(
+                self.name(),
+                Severity::Warning,
+                format!(
+                    "{} sections, symbols {:?}, section 1 {:?}, file 1 {:?}, {} code bytes",
+                    sections.len(),
+                    ctx.symbols().values().collect::<Vec<_>>(),
+                    ctx.section_name(1),
+                    ctx.file(1),
+                    ctx.code(1).map_or(0, |c| c.len()),
+                ),
+            )]
+        }
+    }
+
+    struct Future;
+
+    impl AnalyzerPlugin for Future {
+        fn abi_version(&self) -> u32 {
+            ABI_VERSION + 1
+        }
+
+        fn name(&self) -> &str {
+            "future"
+        }
+
+        fn wants_section(&self, _kind: SectionKindId) -> bool {
+            true
+        }
+
+        fn analyze_module(&self, _ctx: &ModuleContext, _sections: &[&Section]) -> Vec<Finding> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_section_kind() {
+        let obj = obj();
+        for section in obj.sections() {
+            let mut bytes = binrw::io::Cursor::new(Vec::new());
+            binrw::BinWrite::write_le(section, &mut bytes).expect("section");
+            assert_eq!(bytes.into_inner()[0], SectionKindId::of(section).tag());
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::with_builtins();
+        registry.register(Box::new(Probe)).expect("probe");
+        assert!(registry.register(Box::new(Probe)).is_err());
+        let e = registry.register(Box::new(Future)).expect_err("future");
+        assert!(e.to_string().contains("plugin ABI version 2, expected 1"));
+        assert_eq!(
+            vec!["strings", "probe"],
+            registry.names().collect::<Vec<_>>()
+        );
+
+        let findings = registry
+            .analyze_obj(Some("HELLO".into()), &obj(), &["probe"])
+            .expect("findings");
+        assert_eq!(1, findings.len());
+        assert_eq!(
+            "warning: HELLO: probe: 2 sections, symbols [\"hello\", \"printf\"], \
+            section 1 Some(\".rdata\"), file 1 Some(\"src.c\"), 12 code bytes",
+            findings[0].to_string()
+        );
+
+        let e = registry
+            .analyze_obj(None, &obj(), &["missing"])
+            .expect_err("missing");
+        assert_eq!(
+            "unknown plugin: missing (available: strings, probe)",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let findings = Registry::with_builtins()
+            .analyze_obj(None, &obj(), &[])
+            .expect("findings");
+        // "ab" is too short, and "hello" spans both code sections
+        assert_eq!(
+            vec!["note: strings: .rdata 0x0002: \"hello\""],
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_abi_round_trip() {
+        unsafe extern "C" fn collect(
+            state: *mut std::ffi::c_void,
+            severity: u8,
+            message: *const std::ffi::c_char,
+        ) {
+            let findings = unsafe { &mut *(state as *mut Vec<(Severity, String)>) };
+            let message = unsafe { std::ffi::CStr::from_ptr(message) };
+            findings.push((
+                abi::severity_from_abi(severity),
+                message.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let mut bytes = binrw::io::Cursor::new(Vec::new());
+        binrw::BinWrite::write_le(&obj(), &mut bytes).expect("obj");
+        let bytes = bytes.into_inner();
+        let module = std::ffi::CString::new("HELLO").expect("name");
+        let mut findings: Vec<(Severity, String)> = Vec::new();
+        unsafe {
+            abi::analyze(
+                &Probe,
+                module.as_ptr(),
+                bytes.as_ptr(),
+                bytes.len(),
+                collect,
+                &mut findings as *mut _ as *mut std::ffi::c_void,
+            );
+            abi::analyze(
+                &Probe,
+                std::ptr::null(),
+                bytes.as_ptr(),
+                3,
+                collect,
+                &mut findings as *mut _ as *mut std::ffi::c_void,
+            );
+        }
+        assert_eq!(2, findings.len());
+        assert_eq!(Severity::Warning, findings[0].0);
+        assert!(findings[0].1.starts_with("2 sections"));
+        assert_eq!(Severity::Error, findings[1].0);
+    }
+}
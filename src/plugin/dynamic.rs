@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Loading [AnalyzerPlugins](super::AnalyzerPlugin) from shared libraries.
+//!
+//! Libraries are opened with `dlopen`. The [ABI version](super::ABI_VERSION)
+//! is checked before any other function in the library is looked up or
+//! called, so a library built for another version is closed and rejected.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+
+use crate::error::{bail, Error, Result};
+use binrw::io::Cursor;
+use binrw::BinWrite;
+
+use super::abi::{self, AbiVersionFn, AnalyzeFn, NameFn, WantsSectionFn};
+use super::{AnalyzerPlugin, ModuleContext, SectionKindId, ABI_VERSION};
+use crate::verify::{Finding, Severity};
+use crate::Section;
+
+#[cfg_attr(target_os = "linux", link(name = "dl"))]
+extern "C" {
+    fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+    fn dlerror() -> *mut c_char;
+}
+
+const RTLD_NOW: c_int = 2;
+
+/// The extension of shared libraries on this platform.
+#[cfg(target_os = "macos")]
+const EXTENSION: &str = "dylib";
+#[cfg(not(target_os = "macos"))]
+const EXTENSION: &str = "so";
+
+fn last_error() -> String {
+    let error = unsafe { dlerror() };
+    if error.is_null() {
+        "unknown error".into()
+    } else {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// An open shared library, closed when dropped.
+struct Library {
+    handle: *mut c_void,
+}
+
+impl Library {
+    fn open(path: &Path) -> Result<Self> {
+        let Some(path_str) = path.to_str() else {
+            bail!("{}: plugin paths must be valid UTF-8", path.display());
+        };
+        let c_path = CString::new(path_str).map_err(Error::other)?;
+        let handle = unsafe { dlopen(c_path.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            bail!("{}: {}", path.display(), last_error());
+        }
+        Ok(Self { handle })
+    }
+
+    /// Looks up the function `name`.
+    ///
+    /// # Safety
+    ///
+    /// `F` must be the type of the function.
+    unsafe fn function<F: Copy>(&self, name: &str) -> Result<F> {
+        let c_name = CString::new(name).map_err(Error::other)?;
+        let symbol = unsafe { dlsym(self.handle, c_name.as_ptr()) };
+        if symbol.is_null() {
+            bail!("missing {name}");
+        }
+        Ok(unsafe { std::mem::transmute_copy::<*mut c_void, F>(&symbol) })
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe { dlclose(self.handle) };
+    }
+}
+
+/// A plugin loaded from a shared library.
+pub struct DynamicPlugin {
+    name: String,
+    path: PathBuf,
+    wants_section: WantsSectionFn,
+    analyze: AnalyzeFn,
+    // keeps the functions above loaded
+    _library: Library,
+}
+
+impl DynamicPlugin {
+    /// The library the plugin was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Loads the plugin in the shared library at `path`.
+///
+/// Fails if the library can't be opened, doesn't export the functions in
+/// [abi], or was built for a different [ABI_VERSION].
+pub fn load(path: &Path) -> Result<DynamicPlugin> {
+    let library = Library::open(path)?;
+    let version = unsafe {
+        let abi_version: AbiVersionFn = library.function(abi::ABI_VERSION_SYMBOL)?;
+        abi_version()
+    };
+    if version != ABI_VERSION {
+        bail!(
+            "{}: built for plugin ABI version {version}, expected {ABI_VERSION}",
+            path.display()
+        );
+    }
+
+    let (name, wants_section, analyze) = unsafe {
+        let name: NameFn = library.function(abi::NAME_SYMBOL)?;
+        let name = name();
+        if name.is_null() {
+            bail!("{}: {} returned null", path.display(), abi::NAME_SYMBOL);
+        }
+        (
+            CStr::from_ptr(name).to_string_lossy().into_owned(),
+            library.function::<WantsSectionFn>(abi::WANTS_SECTION_SYMBOL)?,
+            library.function::<AnalyzeFn>(abi::ANALYZE_SYMBOL)?,
+        )
+    };
+    Ok(DynamicPlugin {
+        name,
+        path: path.to_path_buf(),
+        wants_section,
+        analyze,
+        _library: library,
+    })
+}
+
+/// Finds the shared libraries directly inside `dir`, sorted by path.
+pub fn find_libraries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut libraries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == EXTENSION) {
+            libraries.push(path);
+        }
+    }
+    libraries.sort();
+    Ok(libraries)
+}
+
+unsafe extern "C" fn collect(state: *mut c_void, severity: u8, message: *const c_char) {
+    let findings = unsafe { &mut *(state as *mut Vec<(Severity, String)>) };
+    let message = unsafe { CStr::from_ptr(message) };
+    findings.push((
+        abi::severity_from_abi(severity),
+        message.to_string_lossy().into_owned(),
+    ));
+}
+
+impl AnalyzerPlugin for DynamicPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn wants_section(&self, kind: SectionKindId) -> bool {
+        unsafe { (self.wants_section)(kind.tag()) }
+    }
+
+    fn analyze_module(&self, ctx: &ModuleContext, _sections: &[&Section]) -> Vec<Finding> {
+        let mut bytes = Cursor::new(Vec::new());
+        ctx.obj()
+            .write_le(&mut bytes)
+            .expect("writing to memory can't fail");
+        let bytes = bytes.into_inner();
+        let module = ctx
+            .module()
+            .map(|m| CString::new(m.replace('\0', " ")).expect("NUL bytes were replaced"));
+
+        let mut findings: Vec<(Severity, String)> = Vec::new();
+        unsafe {
+            (self.analyze)(
+                module.as_ref().map_or(std::ptr::null(), |m| m.as_ptr()),
+                bytes.as_ptr(),
+                bytes.len(),
+                collect,
+                &mut findings as *mut _ as *mut c_void,
+            )
+        };
+        findings
+            .into_iter()
+            .map(|(severity, message)| ctx.finding(&self.name, severity, message))
+            .collect()
+    }
+}
@@ -0,0 +1,396 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Overwriting code bytes in a [Module](super::Module) in place.
+//!
+//! A position is named by a symbol and an offset from it. The symbol's
+//! section and offset are mapped through the [Code](super::Code) sections
+//! of the [OBJ], the same way a linker places them, so a patch may start in
+//! one [Code](super::Code) section and finish in the next. Nothing is
+//! inserted or removed, so module sizes and metadata stay valid.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::io;
+//!
+//! # fn main() -> Result<()> {
+//! let lib = io::read_lib(Path::new("LIBSN.LIB"))?;
+//! let mut module = lib.modules()[0].clone();
+//! // replace the second instruction of `main` with a nop
+//! let patched = module.patch_code("main", 4, &[0, 0, 0, 0])?;
+//! println!("patched {:#x?} in section {}", patched.range, patched.section);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::error::{bail, Error, Result};
+
+use super::symbols::CaseFold;
+use super::{Patch, Section, OBJ};
+
+/// Options for [Module::patch_code_with](super::Module::patch_code_with).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Allow bytes that a relocation [Patch] is applied to be overwritten.
+    /// The linker still applies the patch on top of the new bytes.
+    pub allow_reloc_overlap: bool,
+}
+
+/// The bytes changed by [Module::patch_code](super::Module::patch_code).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchedRange {
+    /// The section containing the patched bytes.
+    pub section: u16,
+    /// The patched bytes as offsets from the start of the section.
+    pub range: Range<u32>,
+    /// The number of [Code](super::Code) sections written to.
+    pub chunks: usize,
+    /// The whole words containing `range`, starting at
+    /// [word_start](Self::word_start), before they were patched.
+    pub before: Vec<u8>,
+    /// The same words after they were patched.
+    pub after: Vec<u8>,
+}
+
+impl PatchedRange {
+    /// The offset in the section of the first word containing patched
+    /// bytes.
+    pub fn word_start(&self) -> u32 {
+        self.range.start & !3
+    }
+
+    /// The bytes in `range` before they were patched.
+    pub fn old_bytes(&self) -> &[u8] {
+        let start = (self.range.start - self.word_start()) as usize;
+        &self.before[start..start + self.range.len()]
+    }
+}
+
+/// The error returned when a patch would overwrite bytes that a relocation
+/// [Patch] is applied to, as the source of an [Other](Error::Other)
+/// error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelocConflict {
+    pub section: u16,
+    /// The bytes that would have been patched.
+    pub range: Range<u32>,
+    /// The offset in the section of the word the relocation applies to.
+    pub offset: u32,
+    pub patch: Patch,
+}
+
+impl fmt::Display for RelocConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}..{:#x} in section {} overlaps the relocation at {:#x} (patch type {})",
+            self.range.start,
+            self.range.end,
+            self.section,
+            self.offset,
+            self.patch.tag()
+        )
+    }
+}
+
+impl std::error::Error for RelocConflict {}
+
+/// Where the code and relocations of one section are in an [OBJ].
+#[derive(Default)]
+struct Layout {
+    /// The index of each [Code](super::Code) section and its offset in the
+    /// section.
+    chunks: Vec<(usize, u32)>,
+    /// The word offset each relocation applies to.
+    relocations: Vec<(u32, Patch)>,
+    /// The offset of the end of the last code in the section.
+    code_end: u32,
+}
+
+fn layout(obj: &OBJ, id: u16) -> Layout {
+    let mut layout = Layout::default();
+    let mut current = 0;
+    let mut size = 0;
+    let mut base = 0;
+    for (index, section) in obj.sections.iter().enumerate() {
+        match section {
+            Section::SectionSwitch(switch) => current = *switch,
+            _ if current != id => (),
+            Section::Code(code) => {
+                base = size;
+                layout.chunks.push((index, base));
+                size += code.code.len() as u32;
+                layout.code_end = size;
+            }
+            Section::BSS(bss) => size += bss,
+            Section::Patch(patch) => {
+                let offset = (base + patch.offset as u32) & !3;
+                layout.relocations.push((offset, patch.clone()));
+            }
+            _ => (),
+        }
+    }
+    layout
+}
+
+/// Finds the section and offset of `symbol`, and the offset of the next
+/// symbol in the same section.
+fn find_symbol(obj: &OBJ, symbol: &str) -> Option<(u16, u32, Option<u32>)> {
+    let symbols = obj
+        .sections
+        .iter()
+        .filter_map(|section| match section {
+            Section::XDEF(xdef) => Some((xdef.symbol_name.as_slice(), xdef.section, xdef.offset)),
+            Section::LocalSymbol(local) => {
+                Some((local.name.as_slice(), local.section, local.offset))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let (_, section, offset) = *symbols
+        .iter()
+        .find(|(n, _, _)| CaseFold::Sensitive.matches_bytes(n, symbol.as_bytes()))?;
+    let next = symbols
+        .iter()
+        .filter(|(_, s, o)| *s == section && *o > offset)
+        .map(|(_, _, o)| *o)
+        .min();
+    Some((section, offset, next))
+}
+
+/// Reads the bytes of the section from `range`, stopping at the first byte
+/// that isn't code.
+fn read(obj: &OBJ, layout: &Layout, range: Range<u32>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for offset in range {
+        let byte = layout.chunks.iter().find_map(|(index, start)| {
+            let Section::Code(code) = &obj.sections[*index] else {
+                unreachable!("chunks are code");
+            };
+            offset
+                .checked_sub(*start)
+                .and_then(|i| code.code.get(i as usize))
+        });
+        match byte {
+            Some(byte) => bytes.push(*byte),
+            None => break,
+        }
+    }
+    bytes
+}
+
+pub(crate) fn patch_code(
+    obj: &mut OBJ,
+    symbol: &str,
+    offset_within_symbol: u32,
+    bytes: &[u8],
+    options: &Options,
+) -> Result<PatchedRange> {
+    let Some((section, symbol_offset, next)) = find_symbol(obj, symbol) else {
+        bail!("symbol not found: {symbol}");
+    };
+    let layout = layout(obj, section);
+    let symbol_end = next.unwrap_or(layout.code_end).min(layout.code_end);
+    if symbol_offset >= symbol_end {
+        bail!("{symbol} has no code in section {section}");
+    }
+
+    let start = symbol_offset + offset_within_symbol;
+    let end = start + bytes.len() as u32;
+    if end > symbol_end {
+        bail!(
+            "{} byte(s) at {symbol}+{offset_within_symbol:#x} extend past the end of {symbol} ({} bytes)",
+            bytes.len(),
+            symbol_end - symbol_offset
+        );
+    }
+
+    if !options.allow_reloc_overlap {
+        if let Some((offset, patch)) = layout
+            .relocations
+            .iter()
+            .find(|(offset, _)| *offset < end && start < offset + 4)
+        {
+            return Err(Error::other(RelocConflict {
+                section,
+                range: start..end,
+                offset: *offset,
+                patch: patch.clone(),
+            }));
+        }
+    }
+
+    let words = start & !3..(end + 3) & !3;
+    let before = read(obj, &layout, words.clone());
+    if (before.len() as u32) < end - words.start {
+        bail!("{start:#x}..{end:#x} in section {section} is not all code");
+    }
+
+    let mut chunks = 0;
+    for (index, chunk_start) in &layout.chunks {
+        let Section::Code(code) = &mut obj.sections[*index] else {
+            unreachable!("chunks are code");
+        };
+        let chunk_end = chunk_start + code.code.len() as u32;
+        let (from, to) = (start.max(*chunk_start), end.min(chunk_end));
+        if from >= to {
+            continue;
+        }
+        code.code[(from - chunk_start) as usize..(to - chunk_start) as usize]
+            .copy_from_slice(&bytes[(from - start) as usize..(to - start) as usize]);
+        chunks += 1;
+    }
+
+    Ok(PatchedRange {
+        section,
+        range: start..end,
+        chunks,
+        before,
+        after: read(obj, &layout, words),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Expression, LocalSymbol, XDEF};
+
+    fn xdef(name: &str, section: u16, offset: u32) -> Section {
+        Section::XDEF(XDEF {
+            number: 1,
+            section,
+            offset,
+            symbol_name_size: name.len() as u8,
+            symbol_name: name.as_bytes().to_vec(),
+        })
+    }
+
+    fn code(bytes: &[u8]) -> Section {
+        Section::Code(Code {
+            size: bytes.len() as u16,
+            deferred: None,
+            code: bytes.to_vec(),
+        })
+    }
+
+    /// Section 1 holds `first` (8 bytes, split over two Code sections) and
+    /// `second` (8 bytes). Section 2 is switched to in between, and the
+    /// last word of `second` is relocated.
+    fn obj() -> OBJ {
+        OBJ::new(vec![
+            Section::SectionSwitch(1),
+            code(&[1, 1, 1, 1, 2, 2]),
+            Section::SectionSwitch(2),
+            code(&[9; 8]),
+            Section::SectionSwitch(1),
+            code(&[2, 2, 3, 3, 3, 3, 4, 4, 4, 4]),
+            Section::Patch(Patch {
+                tag: 16,
+                offset: 6,
+                expression: Expression::Constant(0),
+            }),
+            xdef("first", 1, 0),
+            Section::LocalSymbol(LocalSymbol {
+                section: 1,
+                offset: 8,
+                name_size: 6,
+                name: b"second".to_vec(),
+            }),
+            xdef("other", 2, 0),
+            Section::NOP,
+        ])
+    }
+
+    fn code_bytes(obj: &OBJ) -> Vec<Vec<u8>> {
+        obj.sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::Code(code) => Some(code.code.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spanning_chunks() {
+        let mut obj = obj();
+        let patched =
+            patch_code(&mut obj, "first", 3, &[5, 6, 7, 8], &Options::default()).expect("patch");
+        assert_eq!(
+            vec![
+                vec![1, 1, 1, 5, 6, 7],
+                vec![9; 8],
+                vec![8, 2, 3, 3, 3, 3, 4, 4, 4, 4]
+            ],
+            code_bytes(&obj)
+        );
+        assert_eq!(
+            (1, 3..7, 2),
+            (patched.section, patched.range.clone(), patched.chunks)
+        );
+        assert_eq!(0, patched.word_start());
+        assert_eq!(vec![1, 1, 1, 1, 2, 2, 2, 2], patched.before);
+        assert_eq!(vec![1, 1, 1, 5, 6, 7, 8, 2], patched.after);
+        assert_eq!(&[1, 2, 2, 2], patched.old_bytes());
+
+        // `first` ends where `second` begins
+        let e = patch_code(&mut obj, "first", 6, &[0; 4], &Options::default())
+            .expect_err("past the end");
+        assert!(e
+            .to_string()
+            .contains("extend past the end of first (8 bytes)"));
+        assert!(patch_code(&mut obj, "missing", 0, &[0], &Options::default()).is_err());
+    }
+
+    #[test]
+    fn test_reloc_conflict() {
+        let mut obj = obj();
+        let e =
+            patch_code(&mut obj, "second", 5, &[0, 0], &Options::default()).expect_err("conflict");
+        let Error::Other(e) = e else {
+            panic!("{e:?}");
+        };
+        let conflict = e.downcast_ref::<RelocConflict>().expect("RelocConflict");
+        assert_eq!(
+            (13..15, 12, 16),
+            (
+                conflict.range.clone(),
+                conflict.offset,
+                conflict.patch.tag()
+            )
+        );
+        assert_eq!(self::obj(), obj);
+
+        // the word before the relocation is free
+        assert!(patch_code(&mut obj, "second", 0, &[0; 4], &Options::default()).is_ok());
+        let options = Options {
+            allow_reloc_overlap: true,
+        };
+        let patched = patch_code(&mut obj, "second", 4, &[0; 4], &options).expect("patch");
+        assert_eq!(vec![4, 4, 4, 4], patched.old_bytes());
+        assert_eq!(
+            vec![0, 0, 0, 0, 0, 0, 0, 0],
+            code_bytes(&obj)[2][2..].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_case_sensitive_symbols() {
+        let mut obj = OBJ::new(vec![
+            Section::SectionSwitch(1),
+            code(&[1; 8]),
+            xdef("Foo", 1, 0),
+            xdef("foo", 1, 4),
+            Section::NOP,
+        ]);
+        let patched = patch_code(&mut obj, "foo", 0, &[0; 4], &Options::default()).expect("patch");
+        assert_eq!(4..8, patched.range);
+        let patched = patch_code(&mut obj, "Foo", 0, &[0; 4], &Options::default()).expect("patch");
+        assert_eq!(0..4, patched.range);
+        assert!(patch_code(&mut obj, "FOO", 0, &[0; 4], &Options::default()).is_err());
+    }
+}
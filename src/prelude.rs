@@ -0,0 +1,29 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! The types and functions most programs need.
+//!
+//! ```
+//! use psyk::prelude::*;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let lib = read_lib(std::path::Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+//! for module in lib.modules() {
+//!     println!("{}: {:?}", module.name(), module.exports());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Items are only added to a prelude version, never removed or changed.
+//! Anything that would break a glob import of [v1] goes in a new version.
+
+/// The first version of the prelude. [psyk::prelude](self) re-exports it.
+pub mod v1 {
+    pub use crate::io::{read, read_lib, read_obj, write_lib_to, write_obj_to, Type};
+    pub use crate::simple::{self, Archive};
+    pub use crate::verify::{Finding, Severity};
+    pub use crate::{Code, Export, Module, ModuleMetadata, Section, LIB, OBJ};
+}
+
+pub use v1::*;
@@ -0,0 +1,349 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Salvaging modules from damaged [LIB] files.
+//!
+//! A [LIB] interrupted while being written, or written to media that lost
+//! part of it, can't be read by [LIB::read](binrw::BinRead). [recover] reads
+//! as many modules as it can from the start of the file, then scans past the
+//! damage for the next offset where a well formed module begins and carries
+//! on from there.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::io;
+//!
+//! # fn main() -> Result<()> {
+//! let recovered = io::read_lib_recover(Path::new("BROKEN.LIB"))?;
+//! for region in &recovered.damage {
+//!     println!("{region}");
+//! }
+//! let lib = recovered.salvage();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::error::{bail, Result};
+use binrw::io::Cursor;
+use binrw::{meta::ReadMagic, BinRead};
+
+use super::{Module, ModuleMetadata, LIB, OBJ};
+
+/// The size of the `LIB` magic and version.
+const LIB_HEADER_SIZE: usize = 4;
+
+/// What should have been found at the start of a [DamagedRegion].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    /// The metadata of the next module.
+    ModuleMetadata,
+    /// The OBJ of a module whose metadata was read.
+    Obj { module: String, size: u32 },
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ModuleMetadata => write!(f, "module metadata"),
+            Self::Obj { module, size } => write!(f, "a {size} byte OBJ for module {module}"),
+        }
+    }
+}
+
+/// A range of a file that couldn't be read as modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DamagedRegion {
+    /// The offset of the first damaged byte.
+    pub start: u64,
+    /// The offset after the last damaged byte. This is the size of the file
+    /// if nothing could be recovered after `start`.
+    pub end: u64,
+    pub expected: Expected,
+}
+
+impl DamagedRegion {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl fmt::Display for DamagedRegion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}..{:#x} ({} bytes): expected {}",
+            self.start,
+            self.end,
+            self.len(),
+            self.expected
+        )
+    }
+}
+
+/// A module found by scanning past a [DamagedRegion].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// The offset of the module in the damaged file.
+    pub offset: u64,
+    pub module: Module,
+}
+
+/// The result of [recover].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveredLib {
+    /// Modules read before the first damaged region.
+    pub leading: Vec<Module>,
+    /// Modules found after the first damaged region, in file order.
+    pub trailing: Vec<Candidate>,
+    /// Each damaged region, in file order.
+    pub damage: Vec<DamagedRegion>,
+    /// The number of zero bytes after the last module. Tools which write
+    /// whole blocks leave these behind and they are not treated as damage.
+    pub padding: u64,
+}
+
+impl RecoveredLib {
+    /// Returns `true` if no damage was found.
+    pub fn is_intact(&self) -> bool {
+        self.damage.is_empty()
+    }
+
+    /// All recovered modules in file order.
+    pub fn modules(&self) -> impl Iterator<Item = &Module> {
+        self.leading
+            .iter()
+            .chain(self.trailing.iter().map(|c| &c.module))
+    }
+
+    /// Builds a new [LIB] from the recovered modules.
+    ///
+    /// The [LIB] will be empty, and can't be written, if no modules were
+    /// recovered.
+    pub fn salvage(&self) -> LIB {
+        LIB::new(self.modules().cloned().collect())
+    }
+}
+
+/// Why the module at an offset couldn't be read.
+struct Failure {
+    expected: Expected,
+}
+
+/// Reads the module at `offset`. The module's sizes must agree with its
+/// metadata and the OBJ must end exactly where the metadata says it does.
+fn module_at(bytes: &[u8], offset: usize) -> std::result::Result<Module, Failure> {
+    let metadata_failure = || Failure {
+        expected: Expected::ModuleMetadata,
+    };
+    let mut cursor = Cursor::new(&bytes[offset..]);
+    let metadata = ModuleMetadata::read_le(&mut cursor).map_err(|_| metadata_failure())?;
+    if metadata.offset != metadata.expected_offset()
+        || metadata.size < metadata.offset
+        || !metadata
+            .name
+            .iter()
+            .all(|b| b.is_ascii_graphic() || *b == b' ')
+    {
+        return Err(metadata_failure());
+    }
+
+    let obj_failure = || Failure {
+        expected: Expected::Obj {
+            module: metadata.name(),
+            size: metadata.size - metadata.offset,
+        },
+    };
+    let start = offset + metadata.offset as usize;
+    let end = offset + metadata.size as usize;
+    if end > bytes.len() {
+        return Err(obj_failure());
+    }
+    let mut cursor = Cursor::new(&bytes[start..end]);
+    let obj = OBJ::read(&mut cursor).map_err(|_| obj_failure())?;
+    if cursor.position() as usize != end - start {
+        return Err(obj_failure());
+    }
+    Ok(Module::new(obj, metadata))
+}
+
+/// Returns `true` if a module could plausibly start at `offset`: its stored
+/// metadata offset points at an OBJ magic number.
+fn plausible_start(bytes: &[u8], offset: usize) -> bool {
+    let Some(field) = bytes.get(offset + 12..offset + 16) else {
+        return false;
+    };
+    let metadata_size = u32::from_le_bytes(field.try_into().expect("u32")) as usize;
+    metadata_size > 20
+        && bytes
+            .get(offset + metadata_size..offset + metadata_size + 3)
+            .is_some_and(|magic| magic == OBJ::MAGIC)
+}
+
+/// Recovers the modules in the [LIB] `bytes`.
+///
+/// Modules are read from the start of the file until one can't be read.
+/// From there, every later offset is tried until a complete module is
+/// found, and everything in between is reported as a [DamagedRegion].
+/// Zeros at the end of the file are counted as padding.
+///
+/// Fails only if `bytes` doesn't start with the LIB magic number.
+pub fn recover(bytes: &[u8]) -> Result<RecoveredLib> {
+    if bytes.len() < LIB_HEADER_SIZE || bytes[0..3] != LIB::MAGIC {
+        bail!("not a LIB");
+    }
+
+    let mut recovered = RecoveredLib::default();
+    let mut offset = LIB_HEADER_SIZE;
+    while offset < bytes.len() {
+        if bytes[offset..].iter().all(|b| *b == 0) {
+            recovered.padding = (bytes.len() - offset) as u64;
+            break;
+        }
+
+        let failure = match module_at(bytes, offset) {
+            Ok(module) => {
+                let size = module.metadata.size as usize;
+                if recovered.damage.is_empty() {
+                    recovered.leading.push(module);
+                } else {
+                    recovered.trailing.push(Candidate {
+                        offset: offset as u64,
+                        module,
+                    });
+                }
+                offset += size;
+                continue;
+            }
+            Err(failure) => failure,
+        };
+
+        let next = (offset + 1..bytes.len())
+            .filter(|o| plausible_start(bytes, *o))
+            .find_map(|o| module_at(bytes, o).ok().map(|module| (o, module)));
+        let end = next.as_ref().map_or(bytes.len(), |(o, _)| *o);
+        recovered.damage.push(DamagedRegion {
+            start: offset as u64,
+            end: end as u64,
+            expected: failure.expected,
+        });
+        offset = end;
+    }
+    Ok(recovered)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Export, Section};
+    use binrw::BinWrite;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn module(name: &str, fill: u8) -> Module {
+        let obj = OBJ::new(vec![
+            Section::Code(Code {
+                size: 16,
+                deferred: None,
+                code: vec![fill; 16],
+            }),
+            Section::NOP,
+        ]);
+        let mut bytes = Cursor::new(Vec::new());
+        obj.write_le(&mut bytes).expect("obj");
+        let metadata = ModuleMetadata::new(
+            name.into(),
+            UNIX_EPOCH + Duration::from_secs(820_000_000),
+            bytes.into_inner().len() as u32,
+            vec![Export::new(name.to_lowercase()).expect("export")],
+        )
+        .expect("metadata");
+        Module::new(obj, metadata)
+    }
+
+    fn lib_bytes(modules: &[Module]) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        LIB::new(modules.to_vec())
+            .write_le(&mut bytes)
+            .expect("lib");
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_intact() {
+        let modules = [module("A", 1), module("B", 2)];
+        let mut bytes = lib_bytes(&modules);
+        bytes.extend([0; 7]);
+        let recovered = recover(&bytes).expect("recover");
+        assert!(recovered.is_intact());
+        assert_eq!(modules.to_vec(), recovered.leading);
+        assert_eq!(7, recovered.padding);
+        assert!(recover(b"LNK\x02").is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let modules = [module("A", 1), module("B", 2), module("C", 3)];
+        let bytes = lib_bytes(&modules);
+        let c = bytes.len() - modules[2].metadata.size as usize;
+        let truncated = &bytes[..c + 30];
+
+        let recovered = recover(truncated).expect("recover");
+        assert_eq!(modules[..2].to_vec(), recovered.leading);
+        assert!(recovered.trailing.is_empty());
+        assert_eq!(
+            vec![DamagedRegion {
+                start: c as u64,
+                end: truncated.len() as u64,
+                expected: Expected::Obj {
+                    module: "C".into(),
+                    size: modules[2].metadata.size - modules[2].metadata.offset,
+                },
+            }],
+            recovered.damage
+        );
+        assert_eq!(
+            format!(
+                "{c:#x}..{:#x} (30 bytes): expected a 24 byte OBJ for module C",
+                c + 30
+            ),
+            recovered.damage[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_zeroed_span() {
+        let modules = [module("A", 1), module("B", 2), module("C", 3)];
+        let mut bytes = lib_bytes(&modules);
+        let b = 4 + modules[0].metadata.size as usize;
+        let c = b + modules[1].metadata.size as usize;
+        bytes[b + 10..c - 4].fill(0);
+
+        let recovered = recover(&bytes).expect("recover");
+        assert_eq!(modules[..1].to_vec(), recovered.leading);
+        assert_eq!(
+            vec![Candidate {
+                offset: c as u64,
+                module: modules[2].clone(),
+            }],
+            recovered.trailing
+        );
+        assert_eq!(1, recovered.damage.len());
+        assert_eq!(
+            (b as u64, c as u64),
+            (recovered.damage[0].start, recovered.damage[0].end)
+        );
+        assert_eq!(Expected::ModuleMetadata, recovered.damage[0].expected);
+
+        let salvaged = recovered.salvage();
+        assert_eq!(
+            vec![modules[0].clone(), modules[2].clone()],
+            *salvaged.modules()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Applying a [Patch] to the code it relocates.
+//!
+//! A patch's [tag](Patch::tag) says which bits of the code at its
+//! [offset](Patch::offset) receive the value of its expression. The tags
+//! understood for the R3000 are:
+//!
+//! | Tag | Constant     | Bits written                                        |
+//! |-----|--------------|-----------------------------------------------------|
+//! | 8   | [WORD_8]     | the whole 32-bit word                               |
+//! | 16  | [WORD]       | the whole 32-bit word                               |
+//! | 74  | [JUMP]       | the 26-bit target of a `j` or `jal`, the value >> 2 |
+//! | 82  | [HI16]       | the upper immediate of a `lui`                      |
+//! | 84  | [LO16]       | the lower 16-bit immediate of the following `addiu`, `ori`, or load or store |
+//!
+//! The lower immediate is sign extended by the instruction that uses it, so
+//! [HI16] rounds the upper half up when bit 15 of the value is set. A `lui`
+//! and `addiu` patched with the same value load that value.
+//!
+//! ```
+//! use psyk::{Expression, Patch};
+//! use psyk::reloc;
+//!
+//! // lui $v0, 0; addiu $v0, $v0, 0
+//! let mut code = [0x00, 0x00, 0x02, 0x3c, 0x00, 0x00, 0x42, 0x24];
+//! let value = 0x8001_8000;
+//! Patch::new(reloc::HI16, 0, Expression::Constant(value)).apply(&mut code, value)?;
+//! Patch::new(reloc::LO16, 4, Expression::Constant(value)).apply(&mut code, value)?;
+//! assert_eq!([0x02, 0x80, 0x02, 0x3c, 0x00, 0x80, 0x42, 0x24], code);
+//! # Ok::<(), psyk::reloc::PatchError>(())
+//! ```
+
+use std::fmt;
+
+use crate::Patch;
+
+/// Writes the whole word.
+pub const WORD_8: u8 = 8;
+/// Writes the whole word.
+pub const WORD: u8 = 16;
+/// Writes the target of a `j` or `jal`.
+pub const JUMP: u8 = 74;
+/// Writes the upper half of a value loaded with `lui`.
+pub const HI16: u8 = 82;
+/// Writes the lower half of a value, completing a [HI16].
+pub const LO16: u8 = 84;
+
+/// Why a [Patch] couldn't be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch's tag isn't one that can be applied.
+    UnknownTag(u8),
+    /// The word the patch writes isn't all inside the code.
+    OutOfBounds { offset: u16, len: usize },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownTag(tag) => write!(f, "patch type {tag} can't be applied"),
+            Self::OutOfBounds { offset, len } => write!(
+                f,
+                "a patch at offset {offset:#x} is outside of {len} bytes of code"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Writes `value` into `code` as `patch` describes. See [Patch::apply].
+pub fn apply(patch: &Patch, code: &mut [u8], value: u32) -> Result<(), PatchError> {
+    let (mask, bits) = match patch.tag() {
+        WORD_8 | WORD => (u32::MAX, value),
+        JUMP => (0x03FF_FFFF, value >> 2),
+        HI16 => (0xFFFF, value.wrapping_add(0x8000) >> 16),
+        LO16 => (0xFFFF, value),
+        tag => return Err(PatchError::UnknownTag(tag)),
+    };
+
+    let start = patch.offset() as usize;
+    let Some(word) = code.get_mut(start..start + 4) else {
+        return Err(PatchError::OutOfBounds {
+            offset: patch.offset(),
+            len: code.len(),
+        });
+    };
+    let old = u32::from_le_bytes(word.try_into().expect("4 bytes"));
+    word.copy_from_slice(&((old & !mask) | (bits & mask)).to_le_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Expression;
+
+    fn patch(tag: u8, offset: u16) -> Patch {
+        Patch::new(tag, offset, Expression::Constant(0))
+    }
+
+    fn word(code: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(code[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut code = [0u8; 8];
+        apply(&patch(WORD, 4), &mut code, 0x8001_0000).unwrap();
+        assert_eq!(0x8001_0000, word(&code, 4));
+        apply(&patch(WORD_8, 0), &mut code, 0x1234_5678).unwrap();
+        assert_eq!(0x1234_5678, word(&code, 0));
+
+        // jal 0
+        let mut code = 0x0C00_0000u32.to_le_bytes();
+        apply(&patch(JUMP, 0), &mut code, 0x8001_0040).unwrap();
+        assert_eq!(0x0C00_4010, word(&code, 0));
+
+        // lui $v0, 0; addiu $v0, $v0, 0
+        let mut code = [0x3C02_0000u32.to_le_bytes(), 0x2442_0000u32.to_le_bytes()].concat();
+        for (value, hi, lo) in [(0x8001_7FFF, 0x8001, 0x7FFF), (0x8001_8000, 0x8002, 0x8000)] {
+            apply(&patch(HI16, 0), &mut code, value).unwrap();
+            apply(&patch(LO16, 4), &mut code, value).unwrap();
+            assert_eq!(
+                (0x3C02_0000 | hi, 0x2442_0000 | lo),
+                (word(&code, 0), word(&code, 4))
+            );
+            // what the instructions load
+            let loaded = (hi << 16).wrapping_add(lo as u16 as i16 as u32);
+            assert_eq!(value, loaded);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut code = [0u8; 8];
+        assert_eq!(
+            Err(PatchError::UnknownTag(10)),
+            apply(&patch(10, 0), &mut code, 0)
+        );
+        assert_eq!(
+            Err(PatchError::OutOfBounds { offset: 6, len: 8 }),
+            apply(&patch(WORD, 6), &mut code, 0)
+        );
+        assert_eq!(
+            "a patch at offset 0x6 is outside of 8 bytes of code",
+            apply(&patch(WORD, 6), &mut code, 0)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!([0u8; 8], code);
+    }
+}
@@ -0,0 +1,46 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Field serializers for the `serde` feature.
+//!
+//! Names are written as strings, decoded the same lossy way as the
+//! accessors that return them. Code is written as a hex string and module
+//! timestamps as ISO-8601 dates and times.
+
+use chrono::NaiveDateTime;
+use serde::ser::SerializeSeq;
+use serde::Serializer;
+
+use crate::{Export, FromPSYQTimestamp};
+
+pub(crate) fn lossy_string<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(bytes))
+}
+
+/// A module name, without the spaces padding it to 8 bytes.
+pub(crate) fn module_name<S: Serializer>(name: &[u8; 8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(String::from_utf8_lossy(name).trim_end())
+}
+
+pub(crate) fn hex<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    serializer.serialize_str(&hex)
+}
+
+/// A PSY-Q timestamp, or `null` if it doesn't decode.
+pub(crate) fn timestamp<S: Serializer>(created: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    match NaiveDateTime::from_psyq_timestamp(*created) {
+        Some(created) => serializer.serialize_str(&created.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// The names of an export table, without its terminator.
+pub(crate) fn exports<S: Serializer>(exports: &[Export], serializer: S) -> Result<S::Ok, S::Error> {
+    let names = exports.iter().filter(|e| !e.name.is_empty());
+    let mut seq = serializer.serialize_seq(None)?;
+    for export in names {
+        seq.serialize_element(&export.name())?;
+    }
+    seq.end()
+}
@@ -0,0 +1,211 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! A small, stable interface for the most common tasks.
+//!
+//! Open a [LIB] or [OBJ], list its modules, and get their exports and code
+//! without learning the [Module], [ModuleMetadata](super::ModuleMetadata),
+//! and [Section] model underneath.
+//!
+//! ```
+//! use psyk::simple;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let archive = simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+//! for name in archive.module_names() {
+//!     println!("{name}: {:?}", archive.exports_of(&name)?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Stability
+//!
+//! The rest of the crate models the file formats closely and will change as
+//! more of them is understood. The functions in this module won't: their
+//! signatures and behavior only change in a new major version.
+
+use std::fs::File;
+use std::path::Path;
+
+use crate::error::{bail, Result};
+
+use super::io::{self, Type};
+use super::symbols;
+use super::{path_to_module_name, Module, Section, LIB, OBJ};
+
+/// A [LIB] or an [OBJ] opened with [open].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Archive {
+    Lib(LIB),
+    /// An OBJ and a module name derived from its file name, the same as
+    /// `psylib` would give it.
+    Obj {
+        name: String,
+        obj: OBJ,
+    },
+}
+
+/// Opens the LIB or OBJ at `path`.
+///
+/// ```
+/// use psyk::simple::{self, Archive};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let lib = simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+/// assert!(matches!(lib, Archive::Lib(_)));
+///
+/// let obj = simple::open("tests/data/synthetic/MAIN.OBJ")?;
+/// assert_eq!(vec!["MAIN"], obj.module_names());
+/// # Ok(())
+/// # }
+/// ```
+pub fn open(path: impl AsRef<Path>) -> Result<Archive> {
+    let path = path.as_ref();
+    Ok(match io::read(path)? {
+        Type::LIB(lib) => Archive::Lib(lib),
+        Type::OBJ(obj) => Archive::Obj {
+            name: String::from_utf8_lossy(&path_to_module_name(path))
+                .trim_end()
+                .to_string(),
+            obj,
+        },
+    })
+}
+
+impl Archive {
+    /// The names of the modules, in order. An OBJ has one module.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let archive = psyk::simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+    /// assert_eq!(vec!["MAIN", "HELPER"], archive.module_names());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn module_names(&self) -> Vec<String> {
+        match self {
+            Self::Lib(lib) => lib.modules().iter().map(Module::name).collect(),
+            Self::Obj { name, .. } => vec![name.clone()],
+        }
+    }
+
+    /// The symbols exported by the module `name`.
+    ///
+    /// For a LIB, these are the exports recorded in the module's metadata.
+    /// For an OBJ, they are the symbols it defines.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let archive = psyk::simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+    /// assert_eq!(vec!["helper"], archive.exports_of("HELPER")?);
+    /// // module names ignore case, symbol names don't
+    /// assert_eq!(vec!["helper"], archive.exports_of("helper")?);
+    /// assert!(archive.exports_of("MISSING").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exports_of(&self, name: &str) -> Result<Vec<String>> {
+        match self {
+            Self::Lib(lib) => Ok(find_module(lib, name)?.exports()),
+            Self::Obj { .. } => Ok(self.obj(name)?.exports()),
+        }
+    }
+
+    /// When the module `name` was built, formatted as `DD-MM-YY HH:MM:SS`.
+    /// OBJs don't record when they were built, so this is `None` for an
+    /// OBJ.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let archive = psyk::simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+    /// assert_eq!(Some("15-05-96 16:09:38".into()), archive.created_of("MAIN")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn created_of(&self, name: &str) -> Result<Option<String>> {
+        match self {
+            Self::Lib(lib) => Ok(Some(find_module(lib, name)?.created())),
+            Self::Obj { .. } => self.obj(name).map(|_| None),
+        }
+    }
+
+    /// The code of `section`, e.g. `.text`, in the module `name`. Code
+    /// written to the section in several pieces is joined in order.
+    /// Uninitialized data is not included.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let archive = psyk::simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+    /// // jr $ra; nop
+    /// assert_eq!(
+    ///     vec![0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00],
+    ///     archive.code_of("MAIN", ".text")?
+    /// );
+    /// assert!(archive.code_of("MAIN", ".data").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn code_of(&self, name: &str, section: &str) -> Result<Vec<u8>> {
+        let obj = match self {
+            Self::Lib(lib) => find_module(lib, name)?.object(),
+            Self::Obj { .. } => self.obj(name)?,
+        };
+        let Some(id) = obj.sections().iter().find_map(|s| match s {
+            Section::LNKHeader(header) if header.type_name() == section => Some(header.section()),
+            _ => None,
+        }) else {
+            bail!("{name} has no {section} section");
+        };
+
+        let mut code = Vec::new();
+        let mut current = 0;
+        for s in obj.sections() {
+            match s {
+                Section::SectionSwitch(switch) => current = *switch,
+                Section::Code(c) if current == id => code.extend(c.code()?),
+                _ => (),
+            }
+        }
+        Ok(code)
+    }
+
+    /// Writes the LIB or OBJ to `path`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let archive = psyk::simple::open("tests/data/synthetic/SIMPLE.LIB")?;
+    /// let copy = std::env::temp_dir().join("psyk-simple-save.LIB");
+    /// archive.save(&copy)?;
+    /// assert_eq!(archive, psyk::simple::open(&copy)?);
+    /// # std::fs::remove_file(copy)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = File::create(path.as_ref())?;
+        match self {
+            Self::Lib(lib) => io::write_lib_to(lib, &mut file)?,
+            Self::Obj { obj, .. } => io::write_obj_to(obj, &mut file)?,
+        };
+        Ok(())
+    }
+
+    fn obj(&self, name: &str) -> Result<&OBJ> {
+        match self {
+            Self::Obj { name: n, obj } if symbols::module_eq(n, name) => Ok(obj),
+            _ => bail!("module not found: {name}"),
+        }
+    }
+}
+
+fn find_module<'a>(lib: &'a LIB, name: &str) -> Result<&'a Module> {
+    match lib
+        .modules()
+        .iter()
+        .find(|m| symbols::module_eq(&m.name(), name))
+    {
+        Some(module) => Ok(module),
+        None => bail!("module not found: {name}"),
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! How much memory the sections of an OBJ take.
+//!
+//! [SectionSizes::of_obj] adds up the bytes of [Code](Section::Code),
+//! [BSS](Section::BSS), and [XBSS](Section::XBSS) sections by the type of
+//! the section they belong to, like `.text` or `.sbss`. Section types come
+//! from the OBJ's [LNKHeader](crate::LNKHeader)s. A section without one is
+//! named by its id in hex.
+//!
+//! ```
+//! use psyk::{Code, LNKHeader, Section, OBJ};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let obj = OBJ::new(vec![
+//!     Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text")?),
+//!     Section::LNKHeader(LNKHeader::new(2, 0, 8, ".bss")?),
+//!     Section::SectionSwitch(1),
+//!     Section::Code(Code::new(vec![0; 8])?),
+//!     Section::SectionSwitch(2),
+//!     Section::BSS(16),
+//!     Section::NOP,
+//! ]);
+//! let sizes = obj.section_sizes();
+//! assert_eq!(8, sizes.get(".text").code);
+//! assert_eq!(16, sizes.get(".bss").bss);
+//! assert_eq!(24, sizes.total());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Section, LIB, OBJ};
+
+/// The bytes of one type of section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SectionSize {
+    /// Bytes of initialized code or data.
+    pub code: u64,
+    /// Bytes of uninitialized data, including external BSS.
+    pub bss: u64,
+}
+
+impl SectionSize {
+    /// Returns all of the bytes, initialized or not.
+    pub fn total(&self) -> u64 {
+        self.code + self.bss
+    }
+}
+
+/// The bytes of an OBJ's sections by section type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SectionSizes {
+    sizes: BTreeMap<String, SectionSize>,
+}
+
+impl SectionSizes {
+    /// Adds up the sections of `obj`. Code or BSS before any section switch
+    /// isn't counted.
+    pub fn of_obj(obj: &OBJ) -> Self {
+        let names = obj
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::LNKHeader(header) => Some((header.section(), header.type_name())),
+                _ => None,
+            })
+            .collect::<HashMap<u16, String>>();
+        let name = |id: u16| names.get(&id).cloned().unwrap_or(format!("{id:x}"));
+
+        let mut sizes = Self::default();
+        let mut current = None;
+        for section in obj.sections() {
+            match section {
+                Section::SectionSwitch(id) => current = Some(*id),
+                Section::Code(code) => {
+                    if let Some(id) = current {
+                        sizes.entry(name(id)).code += code.size() as u64;
+                    }
+                }
+                Section::BSS(size) => {
+                    if let Some(id) = current {
+                        sizes.entry(name(id)).bss += *size as u64;
+                    }
+                }
+                Section::XBSS(xbss) => sizes.entry(name(xbss.section())).bss += xbss.size() as u64,
+                _ => (),
+            }
+        }
+        sizes
+    }
+
+    fn entry(&mut self, name: String) -> &mut SectionSize {
+        self.sizes.entry(name).or_default()
+    }
+
+    /// Returns the bytes of sections of type `name`, e.g. `".text"`.
+    pub fn get(&self, name: &str) -> SectionSize {
+        self.sizes.get(name).copied().unwrap_or_default()
+    }
+
+    /// Returns the bytes of every section type, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SectionSize)> {
+        self.sizes.iter().map(|(name, size)| (name.as_str(), size))
+    }
+
+    /// Returns the bytes of all sections.
+    pub fn total(&self) -> u64 {
+        self.sizes.values().map(SectionSize::total).sum()
+    }
+
+    /// Adds the sizes of `other` to these.
+    pub fn merge(&mut self, other: &SectionSizes) {
+        for (name, size) in &other.sizes {
+            let entry = self.entry(name.clone());
+            entry.code += size.code;
+            entry.bss += size.bss;
+        }
+    }
+}
+
+/// Returns the section sizes of each module of `lib`, in order.
+pub fn size_report(lib: &LIB) -> Vec<(String, SectionSizes)> {
+    lib.modules()
+        .iter()
+        .map(|module| (module.name(), SectionSizes::of_obj(module.object())))
+        .collect()
+}
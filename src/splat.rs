@@ -0,0 +1,700 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Match the segments of a [splat](https://github.com/ethteck/splat) config
+//! against the modules of PSY-Q libraries.
+//!
+//! Only the parts of a splat config needed to locate code are read: the
+//! `target_path` option and each segment's `name`, `type`, `start`, and
+//! `vram`, including subsegments written in either list or mapping form.
+//! Reading configs with `load_config` requires the `splat` feature.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::{io, splat};
+//!
+//! # #[cfg(not(feature = "splat"))]
+//! # fn main() {}
+//! # #[cfg(feature = "splat")]
+//! # fn main() -> Result<()> {
+//! let config = splat::load_config(&std::fs::read_to_string("game.yaml")?)?;
+//! let libc = io::read_lib(Path::new("LIBC.LIB"))?;
+//! let rom = std::fs::read("baserom.bin")?;
+//! for m in config.correlate_with(&rom, &[("LIBC", &libc)]) {
+//!     println!("{m}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+
+#[cfg(feature = "splat")]
+use crate::error::Error;
+use crate::error::{bail, Result};
+#[cfg(feature = "splat")]
+use yaml_rust2::{Yaml, YamlLoader};
+
+use super::masked::{self, RollingHash};
+use super::LIB;
+
+/// How [SegmentMatch]es are reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A table for people
+    #[default]
+    Text,
+    /// YAML which can be pasted into a splat config as comments
+    Yaml,
+}
+
+/// Segment types which contain code.
+const CODE_TYPES: [&str; 5] = ["asm", "c", "hasm", "code", "textbin"];
+
+/// Functions shorter than this many words are too common to identify a
+/// module, e.g. `jr $ra; nop`.
+const MIN_FUNCTION_WORDS: usize = 4;
+
+/// A segment or subsegment of a splat config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub name: String,
+    /// The splat segment type, e.g. `c` or `asm`.
+    pub kind: String,
+    /// The offset of the segment in the target binary.
+    pub start: u64,
+    /// The offset of the end of the segment, which is the start of the
+    /// segment that follows it.
+    pub end: Option<u64>,
+    pub vram: Option<u64>,
+    pub subsegments: Vec<Segment>,
+}
+
+impl Segment {
+    /// Returns `true` if this segment contains code and has no subsegments.
+    pub fn is_code(&self) -> bool {
+        self.subsegments.is_empty() && CODE_TYPES.contains(&self.kind.as_str())
+    }
+
+    fn leaves<'a>(&'a self, leaves: &mut Vec<&'a Segment>) {
+        if self.subsegments.is_empty() {
+            leaves.push(self);
+        }
+        for subsegment in &self.subsegments {
+            subsegment.leaves(leaves);
+        }
+    }
+}
+
+/// The parts of a splat config used for matching.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SplatConfig {
+    /// The binary described by the config, from `options.target_path`.
+    pub target_path: Option<PathBuf>,
+    pub segments: Vec<Segment>,
+}
+
+/// The library module which best matches a segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub library: String,
+    pub module: String,
+    /// The module function found at the start of the segment or, if none
+    /// was, the largest function found in it.
+    pub function: Option<String>,
+    /// The fraction of the segment covered by code from the module.
+    pub confidence: f64,
+    /// The size of the module's `.text` in bytes.
+    pub module_size: u64,
+}
+
+/// The result of matching a single code segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentMatch {
+    pub segment: String,
+    pub start: u64,
+    pub size: u64,
+    pub best: Option<Candidate>,
+}
+
+impl SegmentMatch {
+    /// How close the size of the segment is to the size of the matched
+    /// module, from 0 to 1.
+    pub fn size_agreement(&self) -> Option<f64> {
+        let best = self.best.as_ref()?;
+        let (a, b) = (self.size as f64, best.module_size as f64);
+        Some(if a.max(b) == 0.0 {
+            1.0
+        } else {
+            a.min(b) / a.max(b)
+        })
+    }
+}
+
+impl fmt::Display for SegmentMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:#x}+{:#x}: ", self.segment, self.start, self.size)?;
+        match &self.best {
+            Some(best) => write!(
+                f,
+                "{} {} {} (confidence {:.2}, size {:.2})",
+                best.library,
+                best.module,
+                best.function.as_deref().unwrap_or("-"),
+                best.confidence,
+                self.size_agreement().unwrap_or(0.0)
+            ),
+            None => write!(f, "no match"),
+        }
+    }
+}
+
+struct Indexed {
+    library: usize,
+    module: usize,
+    function: Option<String>,
+    words: Vec<u32>,
+}
+
+/// The word range of a segment matched by an indexed function.
+type Hit<'a> = (usize, usize, &'a Indexed);
+
+impl SplatConfig {
+    /// Code segments without subsegments, in config order.
+    pub fn code_segments(&self) -> Vec<&Segment> {
+        let mut leaves = Vec::new();
+        for segment in &self.segments {
+            segment.leaves(&mut leaves);
+        }
+        leaves.into_iter().filter(|s| s.is_code()).collect()
+    }
+
+    /// Reads the binary at [SplatConfig::target_path] and matches each code
+    /// segment against the modules of `libs`.
+    pub fn correlate(&self, libs: &[(&str, &LIB)]) -> Result<Vec<SegmentMatch>> {
+        let Some(path) = &self.target_path else {
+            bail!("The config does not set options.target_path");
+        };
+        let rom = std::fs::read(path)?;
+        Ok(self.correlate_with(&rom, libs))
+    }
+
+    /// Matches each code segment of `rom` against the modules of `libs`,
+    /// which are named for reporting.
+    pub fn correlate_with(&self, rom: &[u8], libs: &[(&str, &LIB)]) -> Vec<SegmentMatch> {
+        // every function and the complete text of every module, by length
+        let mut index: BTreeMap<usize, HashMap<u64, Vec<Indexed>>> = BTreeMap::new();
+        let mut sizes = HashMap::new();
+        for (l, (_, lib)) in libs.iter().enumerate() {
+            for (m, module) in lib.modules().iter().enumerate() {
+                let obj = module.object();
+                // modules read without their code can't be matched
+                let (Ok(text), Ok(functions)) = (masked::text(obj), masked::functions(obj)) else {
+                    continue;
+                };
+                let text = masked::masked_words(&text);
+                sizes.insert((l, m), text.len() as u64 * 4);
+                let whole = (functions.len() != 1).then_some(masked::Function {
+                    name: None,
+                    offset: 0,
+                    words: text,
+                });
+                for function in functions.into_iter().chain(whole) {
+                    if function.words.len() < MIN_FUNCTION_WORDS {
+                        continue;
+                    }
+                    index
+                        .entry(function.words.len())
+                        .or_default()
+                        .entry(function.hash())
+                        .or_default()
+                        .push(Indexed {
+                            library: l,
+                            module: m,
+                            function: function.name,
+                            words: function.words,
+                        });
+                }
+            }
+        }
+
+        self.code_segments()
+            .into_iter()
+            .map(|segment| {
+                let end = segment
+                    .end
+                    .unwrap_or(rom.len() as u64)
+                    .min(rom.len() as u64);
+                let start = segment.start.min(end);
+                let words = masked::masked_words(&rom[start as usize..end as usize]);
+
+                // word ranges of the segment covered by each module
+                let mut hits: HashMap<(usize, usize), Vec<Hit>> = HashMap::new();
+                for (len, functions) in &index {
+                    for (offset, hash) in RollingHash::new(&words, *len) {
+                        let Some(candidates) = functions.get(&hash) else {
+                            continue;
+                        };
+                        for candidate in candidates {
+                            if candidate.words == words[offset..offset + len] {
+                                hits.entry((candidate.library, candidate.module))
+                                    .or_default()
+                                    .push((offset, offset + len, candidate));
+                            }
+                        }
+                    }
+                }
+
+                let best = hits
+                    .into_iter()
+                    .map(|(key, mut ranges)| {
+                        ranges.sort_by_key(|(s, e, _)| (*s, *e));
+                        let mut covered = 0;
+                        let mut reached = 0;
+                        for (s, e, _) in &ranges {
+                            covered += e.saturating_sub((*s).max(reached));
+                            reached = reached.max(*e);
+                        }
+                        let function = ranges
+                            .iter()
+                            .filter(|(_, _, c)| c.function.is_some())
+                            .find(|(s, _, _)| *s == 0)
+                            .or_else(|| {
+                                ranges
+                                    .iter()
+                                    .filter(|(_, _, c)| c.function.is_some())
+                                    .max_by_key(|(s, e, _)| e - s)
+                            })
+                            .and_then(|(_, _, c)| c.function.clone());
+                        (key, covered, function)
+                    })
+                    .max_by(|(a, a_covered, _), (b, b_covered, _)| {
+                        // prefer coverage, then the module closest in size
+                        let distance =
+                            |k: &(usize, usize)| sizes[k].abs_diff(words.len() as u64 * 4);
+                        a_covered
+                            .cmp(b_covered)
+                            .then(distance(b).cmp(&distance(a)))
+                            .then(b.cmp(a))
+                    })
+                    .map(|((l, m), covered, function)| Candidate {
+                        library: libs[l].0.to_string(),
+                        module: libs[l].1.modules()[m].name(),
+                        function,
+                        confidence: covered as f64 / words.len().max(1) as f64,
+                        module_size: sizes[&(l, m)],
+                    });
+
+                SegmentMatch {
+                    segment: segment.name.clone(),
+                    start,
+                    size: end - start,
+                    best,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Parses a splat config. Requires the `splat` feature.
+///
+/// Any YAML is accepted, including anchors, aliases, and `<<` merge keys.
+/// Numbers may be written in decimal or in hex with a `0x` prefix, with or
+/// without `_` separators.
+#[cfg(feature = "splat")]
+pub fn load_config(yaml: &str) -> Result<SplatConfig> {
+    let documents = YamlLoader::load_from_str(yaml).map_err(Error::other)?;
+    let Some(document) = documents.first() else {
+        return Ok(SplatConfig::default());
+    };
+    let target_path = get(document, "options")
+        .and_then(|o| get(o, "target_path"))
+        .and_then(scalar)
+        .map(PathBuf::from);
+
+    let segments = match get(document, "segments") {
+        Some(segments) => parse_segments(segments, None)?,
+        None => Vec::new(),
+    };
+    Ok(SplatConfig {
+        target_path,
+        segments,
+    })
+}
+
+/// Returns the value of `key` in the mapping `value`, including the keys
+/// of mappings merged into it with `<<`.
+#[cfg(feature = "splat")]
+fn get<'a>(value: &'a Yaml, key: &str) -> Option<&'a Yaml> {
+    let Yaml::Hash(hash) = value else {
+        return None;
+    };
+    if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
+        return Some(value);
+    }
+    match hash.get(&Yaml::String("<<".to_string()))? {
+        Yaml::Array(merged) => merged.iter().find_map(|m| get(m, key)),
+        merged => get(merged, key),
+    }
+}
+
+/// Returns a scalar as a string. Numbers are written in decimal.
+#[cfg(feature = "splat")]
+fn scalar(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::String(s) | Yaml::Real(s) => Some(s.clone()),
+        Yaml::Integer(n) => Some(n.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "splat")]
+fn parse_number(value: &Yaml) -> Result<u64> {
+    let s = match value {
+        Yaml::Integer(n) => return u64::try_from(*n).map_err(Error::other),
+        Yaml::String(s) => s,
+        _ => bail!("expected a number, found {value:?}"),
+    };
+    let n = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+        None => s.replace('_', "").parse(),
+    };
+    n.map_err(|_| Error::other(format!("expected a number, found {s}")))
+}
+
+/// Parses a list of segments. Each segment ends where the next begins and
+/// the last ends with its parent.
+#[cfg(feature = "splat")]
+fn parse_segments(value: &Yaml, parent_end: Option<u64>) -> Result<Vec<Segment>> {
+    let Yaml::Array(items) = value else {
+        bail!("expected a list of segments");
+    };
+
+    let mut segments: Vec<Segment> = Vec::new();
+    for item in items {
+        let (start, kind, name, vram, subsegments) = match item {
+            Yaml::Array(fields) => {
+                let Some(start) = fields.first() else {
+                    bail!("empty segment");
+                };
+                let start = parse_number(start)?;
+                (
+                    start,
+                    fields.get(1).and_then(scalar),
+                    fields.get(2).and_then(scalar),
+                    None,
+                    None,
+                )
+            }
+            Yaml::Hash(_) => {
+                let Some(start) = get(item, "start") else {
+                    bail!("segment without a start");
+                };
+                (
+                    parse_number(start)?,
+                    get(item, "type").and_then(scalar),
+                    get(item, "name").and_then(scalar),
+                    get(item, "vram").map(parse_number).transpose()?,
+                    get(item, "subsegments"),
+                )
+            }
+            _ => bail!("expected a segment, found {item:?}"),
+        };
+
+        if let Some(previous) = segments.last_mut() {
+            previous.end = Some(start);
+        }
+        // a segment with only a start marks the end of the previous one
+        let Some(kind) = kind else {
+            continue;
+        };
+        segments.push(Segment {
+            name: name.unwrap_or_else(|| format!("{start:X}")),
+            kind,
+            start,
+            end: None,
+            vram,
+            subsegments: Vec::new(),
+        });
+        if let Some(subsegments) = subsegments {
+            let segment = segments.last_mut().expect("segment");
+            segment.subsegments = parse_segments(subsegments, None)?;
+        }
+    }
+
+    if let Some(last) = segments.last_mut() {
+        if last.end.is_none() {
+            last.end = parent_end;
+        }
+    }
+    // subsegments end with their parent
+    for segment in &mut segments {
+        if let Some(last) = segment.subsegments.last_mut() {
+            if last.end.is_none() {
+                last.end = segment.end;
+            }
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assembler::{LibAssembler, ModuleOptions};
+    use crate::Section;
+    use crate::{Code, LNKHeader, OBJ, XDEF};
+
+    /// Builds an OBJ with a `.text` section containing `functions`.
+    fn module(functions: &[(&str, &[u32])]) -> OBJ {
+        let mut sections = vec![
+            Section::LNKHeader(LNKHeader {
+                section: 1,
+                group: 0,
+                align: 8,
+                type_name_size: 5,
+                type_name: b".text".to_vec(),
+            }),
+            Section::SectionSwitch(1),
+        ];
+        let mut offset = 0;
+        let mut code = Vec::new();
+        for (i, (name, words)) in functions.iter().enumerate() {
+            sections.push(Section::XDEF(XDEF {
+                number: i as u16 + 2,
+                section: 1,
+                offset,
+                symbol_name_size: name.len() as u8,
+                symbol_name: name.as_bytes().to_vec(),
+            }));
+            code.extend(words.iter().flat_map(|w| w.to_le_bytes()));
+            offset += words.len() as u32 * 4;
+        }
+        sections.insert(
+            2,
+            Section::Code(Code {
+                size: code.len() as u16,
+                deferred: None,
+                code,
+            }),
+        );
+        sections.push(Section::NOP);
+        OBJ::new(sections)
+    }
+
+    /// A function of `n` words which differ based on `seed`, including
+    /// words that would be relocated.
+    fn function(seed: u32, n: u32) -> Vec<u32> {
+        let mut words = vec![
+            // addiu $sp, $sp, -0x18
+            0x27bd_ffe8,
+            // sw $ra, 0x10($sp)
+            0xafbf_0010,
+            // lui $v0, %hi(...)
+            0x3c02_0000,
+            // jal ...
+            0x0c00_0000,
+        ];
+        // addu $vN, $aN, $aN
+        words.extend((0..n).map(|i| 0x0085_1021 + ((seed * 31 + i) % 8) * 0x1_0000));
+        // jr $ra
+        words.push(0x03e0_0008);
+        words
+    }
+
+    /// A segment from `start` to `end` without subsegments.
+    fn segment(name: &str, kind: &str, start: u64, end: u64) -> Segment {
+        Segment {
+            name: name.into(),
+            kind: kind.into(),
+            start,
+            end: Some(end),
+            vram: None,
+            subsegments: Vec::new(),
+        }
+    }
+
+    /// Writes `words` to `rom`, applying relocations to the masked words.
+    fn link(rom: &mut Vec<u8>, words: &[u32]) {
+        for word in words {
+            let word = if masked::mask_word(*word) != *word || *word == 0x0c00_0000 {
+                word | 0x1234
+            } else {
+                *word
+            };
+            rom.extend(word.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_correlate() {
+        let (memcpy, memset, strlen, strcpy) = (
+            function(1, 8),
+            function(2, 12),
+            function(3, 6),
+            function(4, 9),
+        );
+        let lib = LibAssembler::new()
+            .push_obj(
+                "MEMORY",
+                module(&[("memcpy", &memcpy), ("memset", &memset)]),
+                ModuleOptions::default(),
+            )
+            .push_obj(
+                "STRLEN",
+                module(&[("strlen", &strlen)]),
+                ModuleOptions::default(),
+            )
+            .push_obj(
+                "STRCPY",
+                module(&[("strcpy", &strcpy)]),
+                ModuleOptions::default(),
+            )
+            .timestamp(std::time::UNIX_EPOCH + std::time::Duration::from_secs(820_000_000))
+            .assemble()
+            .expect("lib");
+
+        // header, STRCPY, unknown code, MEMORY, then STRLEN
+        let mut rom = vec![0; 0x100];
+        link(&mut rom, &strcpy);
+        let unknown = rom.len();
+        link(&mut rom, &[0x0000_000d; 16]);
+        let memory = rom.len();
+        link(&mut rom, &memcpy);
+        link(&mut rom, &memset);
+        let strlen_start = rom.len();
+        link(&mut rom, &strlen);
+        let end = rom.len();
+
+        let (unknown, memory, strlen_start, end) = (
+            unknown as u64,
+            memory as u64,
+            strlen_start as u64,
+            end as u64,
+        );
+        let config = SplatConfig {
+            target_path: None,
+            segments: vec![
+                segment("header", "header", 0, 0x100),
+                Segment {
+                    vram: Some(0x8001_0000),
+                    subsegments: vec![
+                        segment("strcpy", "c", 0x100, unknown),
+                        segment(&format!("{unknown:X}"), "asm", unknown, memory),
+                        segment("memory", "c", memory, strlen_start),
+                        segment("strlen", "asm", strlen_start, end),
+                    ],
+                    ..segment("main", "code", 0x100, end)
+                },
+            ],
+        };
+        assert_eq!(4, config.code_segments().len());
+
+        let matches = config.correlate_with(&rom, &[("LIBC", &lib)]);
+        let summary = matches
+            .iter()
+            .map(|m| {
+                (
+                    m.segment.clone(),
+                    m.best.as_ref().map(|b| b.module.as_str()),
+                    m.best.as_ref().and_then(|b| b.function.as_deref()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("strcpy".to_string(), Some("STRCPY"), Some("strcpy")),
+                (format!("{unknown:X}"), None, None),
+                ("memory".to_string(), Some("MEMORY"), Some("memcpy")),
+                ("strlen".to_string(), Some("STRLEN"), Some("strlen")),
+            ],
+            summary
+        );
+        for m in [&matches[0], &matches[2], &matches[3]] {
+            assert_eq!(1.0, m.best.as_ref().expect("match").confidence);
+            assert_eq!(Some(1.0), m.size_agreement());
+        }
+        assert_eq!(
+            "memory 0x178+0x78: LIBC MEMORY memcpy (confidence 1.00, size 1.00)",
+            matches[2].to_string()
+        );
+    }
+
+    #[cfg(feature = "splat")]
+    #[test]
+    fn test_load_config() {
+        let config = load_config(
+            "\
+name: Test (North America)
+sha1: 0123456789abcdef0123456789abcdef01234567
+options:
+  basename: test
+  target_path: rom.bin
+  platform: psx
+  section_order: [\".rodata\", \".text\", \".data\", \".bss\"]
+ram: &ram 0x8001_0000
+code: &code
+  type: code
+  vram: *ram
+segments:
+  - name: header
+    type: header
+    start: 0x0
+  - <<: *code
+    name: main
+    start: 0x100
+    subsegments:
+      - [0x100, c, strcpy]
+      - [0x120, asm] # not in any library
+      - [
+          0x160,
+          c,
+          memory,
+        ]
+      - { start: 0x1F0, type: asm, name: strlen }
+  - [0x210]
+",
+        )
+        .expect("config");
+        assert_eq!(
+            SplatConfig {
+                target_path: Some(PathBuf::from("rom.bin")),
+                segments: vec![
+                    segment("header", "header", 0, 0x100),
+                    Segment {
+                        vram: Some(0x8001_0000),
+                        subsegments: vec![
+                            segment("strcpy", "c", 0x100, 0x120),
+                            segment("120", "asm", 0x120, 0x160),
+                            segment("memory", "c", 0x160, 0x1f0),
+                            segment("strlen", "asm", 0x1f0, 0x210),
+                        ],
+                        ..segment("main", "code", 0x100, 0x210)
+                    },
+                ],
+            },
+            config
+        );
+        assert_eq!(SplatConfig::default(), load_config("").expect("empty"));
+    }
+
+    #[cfg(feature = "splat")]
+    #[test]
+    fn test_load_config_errors() {
+        assert!(load_config("segments:\n  - name: x\n    type: c\n").is_err());
+        assert!(load_config("segments:\n  - [zzz, c]\n").is_err());
+        assert!(load_config("segments: 1\n").is_err());
+        assert!(load_config("segments:\n  - [-1, c]\n").is_err());
+        let e = load_config("segments: [0x100, c").expect_err("unterminated");
+        assert!(e.to_string().contains("flow sequence"), "{e}");
+    }
+
+    #[test]
+    fn test_correlate_without_segments() {
+        assert!(SplatConfig::default().correlate(&[]).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! SN Systems `.SYM` debugging symbols.
+//!
+//! `PSYLINK.EXE` writes a SYM file alongside an executable for the
+//! debugger. Where an [OBJ](super::OBJ) describes symbols and source lines
+//! relative to its sections, a SYM describes them at their linked
+//! addresses.
+//!
+//! # Structure on Disk
+//!
+//! | Offset | Type       | Description                      |
+//! |--------|------------|----------------------------------|
+//! | 0      | `[u8;3]`   | Magic: "MND"                     |
+//! | 3      | `u8`       | Format version (1)               |
+//! | 4      | `u8`       | Target unit                      |
+//! | 5      | `[u8;3]`   | Reserved                         |
+//! | 8      | `[Record]` | [Records](Record) until the end. |
+//!
+//! Each record is a `u32` address followed by a tag byte and the
+//! [entry](Entry) it identifies.
+//!
+//! ```
+//! use psyk::sym::{Entry, SYM};
+//!
+//! let sym = SYM::new(vec![(0x8001_0000, Entry::symbol("main")?)]);
+//! assert_eq!(vec![(0x8001_0000, "main".to_string())], sym.symbols().collect::<Vec<_>>());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fmt;
+
+use crate::error::{bail, Result};
+use binrw::binrw;
+use binrw::helpers::until_eof;
+use binrw::BinWrite;
+
+use crate::display::labels;
+
+/// A debugging symbol file.
+#[binrw]
+#[brw(little, magic = b"MND")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SYM {
+    version: u8,
+    target: u8,
+    reserved: [u8; 3],
+
+    #[br(parse_with = until_eof)]
+    records: Vec<Record>,
+}
+
+impl SYM {
+    /// Creates a version 1 SYM for target unit 0 from `(address, entry)`
+    /// pairs.
+    pub fn new(records: Vec<(u32, Entry)>) -> Self {
+        Self {
+            version: 1,
+            target: 0,
+            reserved: [0; 3],
+            records: records
+                .into_iter()
+                .map(|(address, entry)| Record { address, entry })
+                .collect(),
+        }
+    }
+
+    /// Returns the format version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the target unit the executable was linked for.
+    pub fn target(&self) -> u8 {
+        self.target
+    }
+
+    /// Returns every record, in order.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Returns the address and name of each [symbol](Entry::Symbol) and
+    /// [label](Entry::Label).
+    pub fn symbols(&self) -> impl Iterator<Item = (u32, String)> + '_ {
+        self.records
+            .iter()
+            .filter_map(|record| match &record.entry {
+                Entry::Symbol(name) | Entry::Label(name) => {
+                    Some((record.address, name.to_string()))
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the address and name of each [function](Entry::FunctionStart).
+    pub fn functions(&self) -> impl Iterator<Item = (u32, String)> + '_ {
+        self.records
+            .iter()
+            .filter_map(|record| match &record.entry {
+                Entry::FunctionStart(start) => Some((record.address, start.name.to_string())),
+                _ => None,
+            })
+    }
+
+    /// Returns the SYM as it is stored on disk.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = binrw::io::Cursor::new(Vec::new());
+        self.write(&mut bytes)?;
+        Ok(bytes.into_inner())
+    }
+}
+
+/// A listing like `SNDUMP.EXE`'s. Each record starts with its offset in the
+/// file, its address, and its tag.
+impl fmt::Display for SYM {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Header : MND version {}", self.version)?;
+        writeln!(f, "Target unit {}", self.target)?;
+        let mut offset = 8;
+        for record in &self.records {
+            writeln!(f, "{offset:06x}: {record}")?;
+            offset += record.size();
+        }
+        Ok(())
+    }
+}
+
+/// An [entry](Entry) and the address it describes.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    address: u32,
+    entry: Entry,
+}
+
+impl Record {
+    /// The address of a symbol or label, or where a line or function
+    /// starts or ends. For a [Def](Entry::Def), its value.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    pub fn entry(&self) -> &Entry {
+        &self.entry
+    }
+
+    /// The number of bytes the record occupies on disk.
+    fn size(&self) -> usize {
+        let mut bytes = binrw::io::Cursor::new(Vec::new());
+        self.write_le(&mut bytes)
+            .map_or(0, |_| bytes.into_inner().len())
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "${:08x} {:x} {}",
+            self.address,
+            self.entry.tag(),
+            self.entry
+        )
+    }
+}
+
+/// A length-prefixed name.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Name {
+    #[bw(assert(name.len() == *size as usize, "SYM name is {} bytes but its size is {}", name.len(), size))]
+    size: u8,
+    #[br(count = size)]
+    name: Vec<u8>,
+}
+
+impl Name {
+    /// It is an error for `name` to be longer than 255 bytes.
+    pub fn new(name: &str) -> Result<Self> {
+        if name.len() > u8::MAX.into() {
+            bail!(
+                "{name} is {} bytes long; names are at most {} bytes",
+                name.len(),
+                u8::MAX
+            );
+        }
+        Ok(Self {
+            size: name.len() as u8,
+            name: name.as_bytes().to_vec(),
+        })
+    }
+}
+
+/// Non-UTF-8 characters are replaced with the Unicode replacement character.
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.name))
+    }
+}
+
+/// A function's frame and where it starts.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionStart {
+    pub frame_register: u16,
+    pub frame_size: u32,
+    pub return_pc_register: u16,
+    pub mask: u32,
+    pub mask_offset: i32,
+    pub line: u32,
+    pub file: Name,
+    pub name: Name,
+}
+
+/// A type definition for a symbol.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Def {
+    pub class: u16,
+    pub def_type: u16,
+    pub size: u32,
+    pub name: Name,
+}
+
+/// A type definition for an array or structure.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Def2 {
+    pub class: u16,
+    pub def_type: u16,
+    pub size: u32,
+    #[bw(calc = dims.len() as u16)]
+    dim_count: u16,
+    #[br(count = dim_count)]
+    pub dims: Vec<u32>,
+    pub tag: Name,
+    pub name: Name,
+}
+
+/// The contents of a [Record].
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// A global symbol.
+    #[brw(magic(0x01u8))]
+    Symbol(Name),
+
+    /// A local label.
+    #[brw(magic(0x02u8))]
+    Label(Name),
+
+    /// The next line starts at the address.
+    #[brw(magic(0x80u8))]
+    IncSLDLineNum,
+
+    /// The line at the address is this many lines after the previous one.
+    #[brw(magic(0x82u8))]
+    IncSLDLineNumByte(u8),
+
+    /// Like [IncSLDLineNumByte](Self::IncSLDLineNumByte), for larger
+    /// increments.
+    #[brw(magic(0x84u8))]
+    IncSLDLineNumWord(u16),
+
+    /// The line at the address.
+    #[brw(magic(0x86u8))]
+    SetSLDLineNum(u32),
+
+    /// The line and file at the address.
+    #[brw(magic(0x88u8))]
+    SetSLDLineNumFile { line: u32, file: Name },
+
+    /// Line information ends at the address.
+    #[brw(magic(0x8Au8))]
+    EndSLDInfo,
+
+    #[brw(magic(0x8Cu8))]
+    FunctionStart(FunctionStart),
+
+    /// A function ends at the address, on `line`.
+    #[brw(magic(0x8Eu8))]
+    FunctionEnd { line: u32 },
+
+    #[brw(magic(0x90u8))]
+    BlockStart { line: u32 },
+
+    #[brw(magic(0x92u8))]
+    BlockEnd { line: u32 },
+
+    #[brw(magic(0x94u8))]
+    Def(Def),
+
+    #[brw(magic(0x96u8))]
+    Def2(Def2),
+
+    /// An overlay loaded at the address.
+    #[brw(magic(0x98u8))]
+    Overlay { length: u32, id: u32 },
+
+    /// The overlay which following records describe. The address is its
+    /// id.
+    #[brw(magic(0x9Au8))]
+    SetOverlay,
+}
+
+impl Entry {
+    /// A global symbol named `name`.
+    pub fn symbol(name: &str) -> Result<Self> {
+        Ok(Self::Symbol(Name::new(name)?))
+    }
+
+    /// Returns the tag byte which precedes this entry on disk.
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Symbol(_) => 0x01,
+            Self::Label(_) => 0x02,
+            Self::IncSLDLineNum => 0x80,
+            Self::IncSLDLineNumByte(_) => 0x82,
+            Self::IncSLDLineNumWord(_) => 0x84,
+            Self::SetSLDLineNum(_) => 0x86,
+            Self::SetSLDLineNumFile { .. } => 0x88,
+            Self::EndSLDInfo => 0x8A,
+            Self::FunctionStart(_) => 0x8C,
+            Self::FunctionEnd { .. } => 0x8E,
+            Self::BlockStart { .. } => 0x90,
+            Self::BlockEnd { .. } => 0x92,
+            Self::Def(_) => 0x94,
+            Self::Def2(_) => 0x96,
+            Self::Overlay { .. } => 0x98,
+            Self::SetOverlay => 0x9A,
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Symbol(name) | Self::Label(name) => write!(f, "{name}"),
+            Self::IncSLDLineNum => write!(f, "{}", labels::INC_SLD_LINENUM),
+            Self::IncSLDLineNumByte(byte) => {
+                write!(f, "{} by byte {byte}", labels::INC_SLD_LINENUM)
+            }
+            Self::IncSLDLineNumWord(word) => {
+                write!(f, "{} by word {word}", labels::INC_SLD_LINENUM)
+            }
+            Self::SetSLDLineNum(line) => write!(f, "{} to {line}", labels::SET_SLD_LINENUM),
+            Self::SetSLDLineNumFile { line, file } => {
+                write!(f, "{} to {line} in file {file}", labels::SET_SLD_LINENUM)
+            }
+            Self::EndSLDInfo => write!(f, "{}", labels::END_SLD_INFO),
+            Self::FunctionStart(start) => write!(
+                f,
+                "{} :\n\
+                \x20 frame reg {}\n\
+                \x20 frame size {}\n\
+                \x20 return pc reg {}\n\
+                \x20 mask ${:08x}\n\
+                \x20 mask offset {}\n\
+                \x20 line {}\n\
+                \x20 file {}\n\
+                \x20 name {}",
+                labels::FUNCTION_START,
+                start.frame_register,
+                start.frame_size,
+                start.return_pc_register,
+                start.mask,
+                start.mask_offset,
+                start.line,
+                start.file,
+                start.name
+            ),
+            Self::FunctionEnd { line } => write!(f, "{} at line {line}", labels::FUNCTION_END),
+            Self::BlockStart { line } => write!(f, "{} at line {line}", labels::BLOCK_START),
+            Self::BlockEnd { line } => write!(f, "{} at line {line}", labels::BLOCK_END),
+            Self::Def(def) => write!(
+                f,
+                "{} class {} type {} size {} name {}",
+                labels::DEF,
+                def.class,
+                def.def_type,
+                def.size,
+                def.name
+            ),
+            Self::Def2(def) => write!(
+                f,
+                "{} class {} type {} size {} dims {:?} tag {} name {}",
+                labels::DEF2,
+                def.class,
+                def.def_type,
+                def.size,
+                def.dims,
+                def.tag,
+                def.name
+            ),
+            Self::Overlay { length, id } => write!(f, "Overlay id {id:x} length {length:x}"),
+            Self::SetOverlay => write!(f, "Set overlay"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use binrw::io::Cursor;
+    use binrw::BinRead;
+
+    fn bytes() -> Vec<u8> {
+        let mut bytes = b"MND\x01\x00\x00\x00\x00".to_vec();
+        let mut record = |address: u32, tag: u8, data: &[u8]| {
+            bytes.extend(address.to_le_bytes());
+            bytes.push(tag);
+            bytes.extend(data);
+        };
+        record(0x8001_0000, 0x01, b"\x04main");
+        record(0x8001_0010, 0x02, b"\x04loop");
+        record(
+            0x8001_0000,
+            0x8C,
+            b"\x1d\x00\x18\x00\x00\x00\x1f\x00\x00\x00\x00\x80\xfc\xff\xff\xff\
+            \x0a\x00\x00\x00\x06MAIN.C\x04main",
+        );
+        record(0x8001_0000, 0x88, b"\x0a\x00\x00\x00\x06MAIN.C");
+        record(0x8001_0008, 0x80, b"");
+        record(0x8001_000c, 0x82, b"\x02");
+        record(0x8001_0014, 0x8E, b"\x0c\x00\x00\x00");
+        record(0x8001_0014, 0x8A, b"");
+        record(0x8002_0000, 0x94, b"\x02\x00\x04\x00\x04\x00\x00\x00\x01i");
+        record(
+            0x8002_0004,
+            0x96,
+            b"\x08\x00\x34\x00\x10\x00\x00\x00\x01\x00\x04\x00\x00\x00\x00\x03buf",
+        );
+        bytes
+    }
+
+    #[test]
+    fn test_read() {
+        let bytes = bytes();
+        let sym = SYM::read(&mut Cursor::new(&bytes)).expect("sym");
+        assert_eq!(1, sym.version());
+        assert_eq!(10, sym.records().len());
+        assert_eq!(
+            vec![
+                (0x8001_0000, "main".to_string()),
+                (0x8001_0010, "loop".to_string())
+            ],
+            sym.symbols().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(0x8001_0000, "main".to_string())],
+            sym.functions().collect::<Vec<_>>()
+        );
+        let Entry::Def2(def) = sym.records()[9].entry() else {
+            panic!("expected a Def2");
+        };
+        assert_eq!(vec![4], def.dims);
+        assert_eq!(bytes, sym.to_bytes().expect("bytes"));
+    }
+
+    #[test]
+    fn test_display() {
+        let sym = SYM::read(&mut Cursor::new(bytes())).expect("sym");
+        let listing = sym.to_string();
+        assert!(listing.starts_with(
+            "Header : MND version 1\n\
+            Target unit 0\n\
+            000008: $80010000 1 main\n\
+            000012: $80010010 2 loop\n\
+            00001c: $80010000 8c Function start :\n\
+            \x20 frame reg 29\n"
+        ));
+        assert!(listing.contains("$80010000 88 Set SLD linenum to 10 in file MAIN.C\n"));
+        assert!(listing.contains("$80010014 8e Function end at line 12\n"));
+        assert!(
+            listing.contains("$80020004 96 Def2 class 8 type 52 size 16 dims [4] tag  name buf\n")
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut bytes = bytes();
+        bytes.pop();
+        assert!(SYM::read(&mut Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Comparing symbol and module names.
+//!
+//! Symbol names are case-sensitive. The assembler and linker treat `Main`
+//! and `main` as different symbols, and libraries rely on it: `LIBSN`
+//! exports `__main` while other modules reference `main`. Every comparison
+//! of symbol names in this crate goes through [symbol_eq] or a [CaseFold],
+//! which is [CaseFold::Sensitive] unless a caller opts in to
+//! [CaseFold::Insensitive] for interactive searches like `psyk find -i`.
+//!
+//! Module names are not case-sensitive. `psylib` upper-cases the names it
+//! derives from file names, so a module is found with [module_eq] whatever
+//! case it was asked for in.
+//!
+//! Two symbols in one module that differ only in case are almost always a
+//! mistake. [case_collisions] finds them, and
+//! [verify](super::verify) reports them as warnings.
+//!
+//! ```
+//! use psyk::symbols::{self, CaseFold};
+//!
+//! assert!(!symbols::symbol_eq("GetCr", "getcr"));
+//! assert!(CaseFold::Insensitive.matches("GetCr", "getcr"));
+//! assert!(symbols::module_eq("libapi", "LIBAPI"));
+//! ```
+
+/// Whether symbol names are compared with or without regard to case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseFold {
+    /// Names match only if their bytes are the same.
+    #[default]
+    Sensitive,
+    /// Names match if they differ only in ASCII case.
+    Insensitive,
+}
+
+impl CaseFold {
+    /// Returns `true` if the symbol names `a` and `b` match.
+    pub fn matches(self, a: &str, b: &str) -> bool {
+        self.matches_bytes(a.as_bytes(), b.as_bytes())
+    }
+
+    /// Returns `true` if the symbol names `a` and `b`, as stored in an OBJ,
+    /// match.
+    pub fn matches_bytes(self, a: &[u8], b: &[u8]) -> bool {
+        match self {
+            Self::Sensitive => a == b,
+            Self::Insensitive => a.eq_ignore_ascii_case(b),
+        }
+    }
+}
+
+/// Returns `true` if `a` and `b` are the same symbol.
+pub fn symbol_eq(a: &str, b: &str) -> bool {
+    CaseFold::Sensitive.matches(a, b)
+}
+
+/// Returns `true` if `a` and `b` name the same module.
+pub fn module_eq(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Finds pairs of different names in `names` that differ only in case.
+/// Each pair is in the order the names first appear.
+pub fn case_collisions<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<(String, String)> {
+    let mut seen: Vec<&str> = Vec::new();
+    let mut collisions = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            continue;
+        }
+        if let Some(first) = seen.iter().find(|s| CaseFold::Insensitive.matches(s, name)) {
+            collisions.push((first.to_string(), name.to_string()));
+        }
+        seen.push(name);
+    }
+    collisions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_case_fold() {
+        assert!(symbol_eq("main", "main"));
+        assert!(!symbol_eq("Main", "main"));
+        assert!(!CaseFold::default().matches("GetCr", "getcr"));
+        assert!(CaseFold::Insensitive.matches("GetCr", "getcr"));
+        assert!(!CaseFold::Insensitive.matches("GetCr", "GetCr2"));
+        assert!(CaseFold::Sensitive.matches_bytes(b"foo", b"foo"));
+        assert!(!CaseFold::Sensitive.matches_bytes(b"Foo", b"foo"));
+        assert!(module_eq("libapi", "LIBAPI"));
+        assert!(!module_eq("LIBAPI", "LIBAPI2"));
+    }
+
+    #[test]
+    fn test_case_collisions() {
+        assert_eq!(
+            vec![("Foo".to_string(), "foo".to_string())],
+            case_collisions(["Foo", "bar", "foo", "Foo", "baz"])
+        );
+        assert!(case_collisions(["__main", "main"]).is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Every symbol an OBJ defines or references, in one place.
+//!
+//! Symbols appear in an OBJ as several kinds of [Section]: `XDEF` and
+//! `XBSS` define numbered symbols other modules can reference, `XREF`
+//! declares numbered symbols this module needs, and `LocalSymbol` and
+//! `VeryLocalSymbol` name locations for debuggers. A [SymbolTable] gathers
+//! them with their numbers, sections, and offsets.
+//!
+//! The numbers matter most. A [Patch](crate::Patch) refers to a symbol with
+//! [Expression::SymbolAddressIndex](crate::Expression::SymbolAddressIndex),
+//! and [SymbolTable::by_number] finds the symbol it means.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::io;
+//! use psyk::symtab::SymbolKind;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?;
+//! let symbols = obj.symbols();
+//! let main = &symbols.defined()[0];
+//! assert_eq!(("main", SymbolKind::Xdef), (main.name.as_str(), main.kind));
+//! let number = main.number.expect("number");
+//! assert_eq!("main", symbols.by_number(number).expect("main").name());
+//! assert!(symbols.undefined().is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{DefinitionStrength, Section, OBJ};
+
+/// The section an OBJ defines a symbol with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// An exported symbol, from [Section::XDEF].
+    Xdef,
+    /// An exported uninitialized symbol, from [Section::XBSS].
+    Xbss,
+    /// A debugging symbol, from [Section::LocalSymbol].
+    Local,
+    /// A debugging symbol, from [Section::VeryLocalSymbol].
+    VeryLocal,
+}
+
+/// A symbol defined by an OBJ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefinedSymbol {
+    pub name: String,
+    /// The number patches refer to the symbol by. Local symbols don't have
+    /// one.
+    pub number: Option<u16>,
+    pub section: u16,
+    /// The offset of the symbol in its section. XBSS symbols are placed by
+    /// the linker and don't have one.
+    pub offset: Option<u32>,
+    pub kind: SymbolKind,
+    /// How the symbol is defined, as [OBJ::definitions] reports it. Local
+    /// symbols aren't definitions the linker sees and don't have one.
+    pub strength: Option<DefinitionStrength>,
+}
+
+/// A symbol an OBJ references but doesn't define, from [Section::XREF].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndefinedSymbol {
+    pub name: String,
+    pub number: u16,
+}
+
+/// A symbol found by its number. See [SymbolTable::by_number].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolEntry<'a> {
+    Defined(&'a DefinedSymbol),
+    Undefined(&'a UndefinedSymbol),
+}
+
+impl<'a> SymbolEntry<'a> {
+    pub fn name(&self) -> &'a str {
+        match *self {
+            Self::Defined(symbol) => &symbol.name,
+            Self::Undefined(symbol) => &symbol.name,
+        }
+    }
+
+    /// Returns `true` if the OBJ defines the symbol.
+    pub fn is_defined(&self) -> bool {
+        matches!(self, Self::Defined(_))
+    }
+}
+
+/// Where a numbered symbol is kept in a [SymbolTable].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Slot {
+    Defined(usize),
+    Undefined(usize),
+}
+
+/// The symbols of an OBJ. See [OBJ::symbols].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    defined: Vec<DefinedSymbol>,
+    undefined: Vec<UndefinedSymbol>,
+    numbers: HashMap<u16, Slot>,
+}
+
+impl SymbolTable {
+    /// Builds the symbol table of `obj`.
+    pub fn of_obj(obj: &OBJ) -> Self {
+        let bss = obj.uninitialized_sections();
+        let mut table = Self::default();
+        for section in obj.sections() {
+            let symbol = match section {
+                Section::XDEF(xdef) => DefinedSymbol {
+                    name: xdef.symbol_name(),
+                    number: Some(xdef.number),
+                    section: xdef.section,
+                    offset: Some(xdef.offset),
+                    kind: SymbolKind::Xdef,
+                    strength: Some(if bss.contains_key(&xdef.section) {
+                        DefinitionStrength::Common
+                    } else {
+                        DefinitionStrength::Strong
+                    }),
+                },
+                Section::XBSS(xbss) => DefinedSymbol {
+                    name: xbss.name(),
+                    number: Some(xbss.number),
+                    section: xbss.section,
+                    offset: None,
+                    kind: SymbolKind::Xbss,
+                    strength: Some(DefinitionStrength::Common),
+                },
+                Section::LocalSymbol(local) | Section::VeryLocalSymbol(local) => DefinedSymbol {
+                    name: local.name(),
+                    number: None,
+                    section: local.section,
+                    offset: Some(local.offset),
+                    kind: if matches!(section, Section::LocalSymbol(_)) {
+                        SymbolKind::Local
+                    } else {
+                        SymbolKind::VeryLocal
+                    },
+                    strength: None,
+                },
+                Section::XREF(xref) => {
+                    table
+                        .numbers
+                        .insert(xref.number, Slot::Undefined(table.undefined.len()));
+                    table.undefined.push(UndefinedSymbol {
+                        name: xref.symbol_name(),
+                        number: xref.number,
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+            if let Some(number) = symbol.number {
+                table
+                    .numbers
+                    .insert(number, Slot::Defined(table.defined.len()));
+            }
+            table.defined.push(symbol);
+        }
+        table
+    }
+
+    /// Returns the symbols the OBJ defines, in the order they appear.
+    pub fn defined(&self) -> &[DefinedSymbol] {
+        &self.defined
+    }
+
+    /// Returns the symbols the OBJ references but doesn't define, in the
+    /// order they appear.
+    pub fn undefined(&self) -> &[UndefinedSymbol] {
+        &self.undefined
+    }
+
+    /// Returns the symbol with `number`, defined or not.
+    pub fn by_number(&self, number: u16) -> Option<SymbolEntry<'_>> {
+        Some(match self.numbers.get(&number)? {
+            Slot::Defined(i) => SymbolEntry::Defined(&self.defined[*i]),
+            Slot::Undefined(i) => SymbolEntry::Undefined(&self.undefined[*i]),
+        })
+    }
+
+    /// Returns the defined symbol named `name`.
+    pub fn find(&self, name: &str) -> Option<&DefinedSymbol> {
+        self.defined
+            .iter()
+            .find(|symbol| crate::symbols::symbol_eq(&symbol.name, name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, LocalSymbol, XBSS, XDEF};
+
+    #[test]
+    fn test_kinds() {
+        let name = |s: &str| s.as_bytes().to_vec();
+        let local = LocalSymbol {
+            section: 1,
+            offset: 8,
+            name_size: 4,
+            name: name("loop"),
+        };
+        let obj = OBJ::new(vec![
+            Section::LocalSymbol(local.clone()),
+            Section::VeryLocalSymbol(LocalSymbol {
+                name: name("$L1"),
+                name_size: 3,
+                ..local
+            }),
+            Section::XBSS(XBSS {
+                number: 7,
+                section: 2,
+                size: 16,
+                name_size: 6,
+                name: name("buffer"),
+            }),
+            Section::NOP,
+        ]);
+        let table = obj.symbols();
+        assert_eq!(
+            vec![
+                ("loop", None, Some(8), SymbolKind::Local),
+                ("$L1", None, Some(8), SymbolKind::VeryLocal),
+                ("buffer", Some(7), None, SymbolKind::Xbss),
+            ],
+            table
+                .defined()
+                .iter()
+                .map(|s| (s.name.as_str(), s.number, s.offset, s.kind))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, table.find("buffer").expect("buffer").section);
+        assert!(table.find("Buffer").is_none());
+        assert!(table.by_number(7).expect("buffer").is_defined());
+        assert!(table.by_number(8).is_none());
+        assert_eq!(
+            SymbolTable::default(),
+            OBJ::new(vec![Section::NOP]).symbols()
+        );
+    }
+
+    #[test]
+    fn test_strength() {
+        let name = |s: &str| s.as_bytes().to_vec();
+        let xdef = |number, section, symbol: &str| {
+            Section::XDEF(XDEF {
+                number,
+                section,
+                offset: 0,
+                symbol_name_size: symbol.len() as u8,
+                symbol_name: name(symbol),
+            })
+        };
+        let obj = OBJ::new(vec![
+            Section::SectionSwitch(1),
+            Section::Code(Code {
+                size: 4,
+                deferred: None,
+                code: vec![0; 4],
+            }),
+            Section::SectionSwitch(3),
+            Section::BSS(8),
+            xdef(1, 1, "main"),
+            xdef(2, 3, "flag"),
+            Section::XBSS(XBSS {
+                number: 3,
+                section: 3,
+                size: 16,
+                name_size: 6,
+                name: name("buffer"),
+            }),
+            Section::LocalSymbol(LocalSymbol {
+                section: 1,
+                offset: 0,
+                name_size: 4,
+                name: name("loop"),
+            }),
+            Section::NOP,
+        ]);
+        let table = obj.symbols();
+        assert_eq!(
+            vec![
+                ("main", Some(DefinitionStrength::Strong)),
+                ("flag", Some(DefinitionStrength::Common)),
+                ("buffer", Some(DefinitionStrength::Common)),
+                ("loop", None),
+            ],
+            table
+                .defined()
+                .iter()
+                .map(|s| (s.name.as_str(), s.strength))
+                .collect::<Vec<_>>()
+        );
+        for definition in obj.definitions() {
+            let symbol = table.find(&definition.name).expect("defined");
+            assert_eq!(Some(definition.strength), symbol.strength);
+        }
+    }
+}
@@ -0,0 +1,358 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! The storage classes and types of [Def](crate::Def) and
+//! [Def2](crate::Def2) debug information.
+//!
+//! They are encoded as in COFF. A storage class like `C_EXT` or `C_MOS`
+//! says what kind of symbol is defined. A type packs a [BaseType] into its
+//! low four bits and up to six [Derivation]s, two bits each, above it. The
+//! first derivation applies to the symbol itself, so `0x24`, a function
+//! returning `int`, is [BaseType::Int] derived by [Derivation::Function].
+//!
+//! ```
+//! use psyk::types::{BaseType, Derivation, StorageClass, TypeDesc};
+//!
+//! assert_eq!(StorageClass::External, StorageClass::from_u16(2));
+//! assert_eq!("C_EXT", StorageClass::External.to_string());
+//!
+//! let desc = TypeDesc::from_u16(0x3c);
+//! assert_eq!(BaseType::UChar, desc.base);
+//! assert_eq!(vec![Derivation::Array], desc.derivations);
+//! assert_eq!("unsigned char [4]", desc.describe("", &[4]));
+//! assert_eq!("int (*)()", TypeDesc::from_u16(0x94).to_string());
+//! ```
+
+use std::fmt;
+
+/// The kind of symbol a [Def](crate::Def) or [Def2](crate::Def2) defines,
+/// a COFF storage class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StorageClass {
+    /// `C_EFCN`, the end of a function
+    EndOfFunction,
+    /// `C_NULL`
+    Null,
+    /// `C_AUTO`, a local variable on the stack
+    Auto,
+    /// `C_EXT`, a global symbol
+    External,
+    /// `C_STAT`, a static symbol
+    Static,
+    /// `C_REG`, a register variable
+    Register,
+    /// `C_EXTDEF`, an external definition
+    ExternalDef,
+    /// `C_LABEL`
+    Label,
+    /// `C_ULABEL`, an undefined label
+    UndefinedLabel,
+    /// `C_MOS`, a member of a structure
+    MemberOfStruct,
+    /// `C_ARG`, a function argument
+    Argument,
+    /// `C_STRTAG`, a structure tag
+    StructTag,
+    /// `C_MOU`, a member of a union
+    MemberOfUnion,
+    /// `C_UNTAG`, a union tag
+    UnionTag,
+    /// `C_TPDEF`, a typedef
+    Typedef,
+    /// `C_USTATIC`, an undefined static
+    UndefinedStatic,
+    /// `C_ENTAG`, an enumeration tag
+    EnumTag,
+    /// `C_MOE`, a member of an enumeration
+    MemberOfEnum,
+    /// `C_REGPARM`, a register parameter
+    RegisterParam,
+    /// `C_FIELD`, a bit field
+    Field,
+    /// `C_BLOCK`, the start or end of a block
+    Block,
+    /// `C_FCN`, the start or end of a function
+    Function,
+    /// `C_EOS`, the end of a structure, union, or enumeration
+    EndOfStruct,
+    /// `C_FILE`, a source file
+    File,
+    /// `C_LINE`
+    Line,
+    /// `C_ALIAS`, a duplicated tag
+    Alias,
+    /// `C_HIDDEN`
+    Hidden,
+    Unknown(u16),
+}
+
+impl StorageClass {
+    /// Returns the storage class numbered `class`.
+    pub fn from_u16(class: u16) -> Self {
+        match class {
+            0xffff => Self::EndOfFunction,
+            0 => Self::Null,
+            1 => Self::Auto,
+            2 => Self::External,
+            3 => Self::Static,
+            4 => Self::Register,
+            5 => Self::ExternalDef,
+            6 => Self::Label,
+            7 => Self::UndefinedLabel,
+            8 => Self::MemberOfStruct,
+            9 => Self::Argument,
+            10 => Self::StructTag,
+            11 => Self::MemberOfUnion,
+            12 => Self::UnionTag,
+            13 => Self::Typedef,
+            14 => Self::UndefinedStatic,
+            15 => Self::EnumTag,
+            16 => Self::MemberOfEnum,
+            17 => Self::RegisterParam,
+            18 => Self::Field,
+            100 => Self::Block,
+            101 => Self::Function,
+            102 => Self::EndOfStruct,
+            103 => Self::File,
+            104 => Self::Line,
+            105 => Self::Alias,
+            106 => Self::Hidden,
+            class => Self::Unknown(class),
+        }
+    }
+
+    /// Returns the COFF name of the storage class, like `C_EXT`, or `None`
+    /// if it is [Unknown](Self::Unknown).
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::EndOfFunction => "C_EFCN",
+            Self::Null => "C_NULL",
+            Self::Auto => "C_AUTO",
+            Self::External => "C_EXT",
+            Self::Static => "C_STAT",
+            Self::Register => "C_REG",
+            Self::ExternalDef => "C_EXTDEF",
+            Self::Label => "C_LABEL",
+            Self::UndefinedLabel => "C_ULABEL",
+            Self::MemberOfStruct => "C_MOS",
+            Self::Argument => "C_ARG",
+            Self::StructTag => "C_STRTAG",
+            Self::MemberOfUnion => "C_MOU",
+            Self::UnionTag => "C_UNTAG",
+            Self::Typedef => "C_TPDEF",
+            Self::UndefinedStatic => "C_USTATIC",
+            Self::EnumTag => "C_ENTAG",
+            Self::MemberOfEnum => "C_MOE",
+            Self::RegisterParam => "C_REGPARM",
+            Self::Field => "C_FIELD",
+            Self::Block => "C_BLOCK",
+            Self::Function => "C_FCN",
+            Self::EndOfStruct => "C_EOS",
+            Self::File => "C_FILE",
+            Self::Line => "C_LINE",
+            Self::Alias => "C_ALIAS",
+            Self::Hidden => "C_HIDDEN",
+            Self::Unknown(_) => return None,
+        })
+    }
+}
+
+/// The COFF name of the storage class, or `unknown`.
+impl fmt::Display for StorageClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name().unwrap_or("unknown"))
+    }
+}
+
+/// The fundamental type a [TypeDesc] is derived from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    /// `T_NULL`, no type, as of a function returning nothing
+    Null,
+    /// `T_ARG`, a function argument
+    Arg,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    Struct,
+    Union,
+    Enum,
+    /// `T_MOE`, a member of an enumeration
+    MemberOfEnum,
+    UChar,
+    UShort,
+    UInt,
+    ULong,
+}
+
+impl BaseType {
+    /// Returns the base type in the low four bits of `def_type`.
+    pub fn from_u16(def_type: u16) -> Self {
+        match def_type & 0xf {
+            0 => Self::Null,
+            1 => Self::Arg,
+            2 => Self::Char,
+            3 => Self::Short,
+            4 => Self::Int,
+            5 => Self::Long,
+            6 => Self::Float,
+            7 => Self::Double,
+            8 => Self::Struct,
+            9 => Self::Union,
+            10 => Self::Enum,
+            11 => Self::MemberOfEnum,
+            12 => Self::UChar,
+            13 => Self::UShort,
+            14 => Self::UInt,
+            _ => Self::ULong,
+        }
+    }
+
+    /// Returns the type as it is written in C, like `unsigned char`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "void",
+            Self::Arg => "arg",
+            Self::Char => "char",
+            Self::Short => "short",
+            Self::Int => "int",
+            Self::Long => "long",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::Struct => "struct",
+            Self::Union => "union",
+            Self::Enum => "enum",
+            Self::MemberOfEnum => "enum member",
+            Self::UChar => "unsigned char",
+            Self::UShort => "unsigned short",
+            Self::UInt => "unsigned int",
+            Self::ULong => "unsigned long",
+        }
+    }
+}
+
+/// A pointer, function, or array made from another type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Derivation {
+    /// `DT_PTR`
+    Pointer,
+    /// `DT_FCN`, a function returning the type
+    Function,
+    /// `DT_ARY`
+    Array,
+}
+
+/// A type unpacked from the `def_type` of a [Def](crate::Def) or
+/// [Def2](crate::Def2).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeDesc {
+    pub base: BaseType,
+    /// The derivations, starting with the one applied to the symbol itself.
+    pub derivations: Vec<Derivation>,
+}
+
+impl TypeDesc {
+    /// Unpacks `def_type`. Derivations end at the first two bits that are
+    /// zero, `DT_NON`.
+    pub fn from_u16(def_type: u16) -> Self {
+        let derivations = (4..16)
+            .step_by(2)
+            .map(|shift| (def_type >> shift) & 0x3)
+            .take_while(|&bits| bits != 0)
+            .map(|bits| match bits {
+                1 => Derivation::Pointer,
+                2 => Derivation::Function,
+                _ => Derivation::Array,
+            })
+            .collect();
+        Self {
+            base: BaseType::from_u16(def_type),
+            derivations,
+        }
+    }
+
+    /// Returns the type as a C abstract declarator, like `char *[4]`.
+    /// Structures, unions, and enumerations are named by `tag` when it isn't
+    /// empty. Arrays take their sizes from `dims` in order, and are written
+    /// `[]` once it runs out.
+    pub fn describe(&self, tag: &str, dims: &[u32]) -> String {
+        let mut declarator = String::new();
+        let mut dims = dims.iter();
+        let mut pointer = false;
+        for derivation in &self.derivations {
+            if pointer && *derivation != Derivation::Pointer {
+                declarator = format!("({declarator})");
+            }
+            match derivation {
+                Derivation::Pointer => declarator.insert(0, '*'),
+                Derivation::Function => declarator.push_str("()"),
+                Derivation::Array => match dims.next() {
+                    Some(dim) => declarator.push_str(&format!("[{dim}]")),
+                    None => declarator.push_str("[]"),
+                },
+            }
+            pointer = *derivation == Derivation::Pointer;
+        }
+
+        let base = match self.base {
+            BaseType::Struct | BaseType::Union | BaseType::Enum if !tag.is_empty() => {
+                format!("{} {tag}", self.base.name())
+            }
+            base => base.name().to_string(),
+        };
+        if declarator.is_empty() {
+            base
+        } else {
+            format!("{base} {declarator}")
+        }
+    }
+}
+
+/// The type without a tag or array sizes. See [TypeDesc::describe].
+impl fmt::Display for TypeDesc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.describe("", &[]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_storage_class() {
+        for class in (0..=18).chain(100..=106).chain([0xffff]) {
+            let storage_class = StorageClass::from_u16(class);
+            assert!(storage_class.name().is_some(), "{class}");
+        }
+        assert_eq!(StorageClass::MemberOfStruct, StorageClass::from_u16(8));
+        assert_eq!(StorageClass::EndOfStruct, StorageClass::from_u16(102));
+        assert_eq!(StorageClass::Unknown(50), StorageClass::from_u16(50));
+        assert_eq!("unknown", StorageClass::Unknown(50).to_string());
+    }
+
+    #[test]
+    fn test_describe() {
+        let describe =
+            |def_type, tag, dims: &[u32]| TypeDesc::from_u16(def_type).describe(tag, dims);
+        assert_eq!("int", describe(0x04, "", &[]));
+        assert_eq!("char *", describe(0x12, "", &[]));
+        assert_eq!("int ()", describe(0x24, "", &[]));
+        assert_eq!("int *()", describe(0x64, "", &[]));
+        assert_eq!("int (*)()", describe(0x94, "", &[]));
+        assert_eq!("char *[4]", describe(0x72, "", &[4]));
+        assert_eq!("char (*)[]", describe(0xd2, "", &[]));
+        assert_eq!("struct _physadr", describe(0x08, "_physadr", &[]));
+        assert_eq!("union", describe(0x09, "", &[]));
+        assert_eq!("struct _physadr *", describe(0x18, "_physadr", &[]));
+        assert_eq!("unsigned char [4]", describe(0x3c, "", &[4]));
+        assert_eq!(
+            vec![Derivation::Pointer, Derivation::Pointer],
+            TypeDesc::from_u16(0x55).derivations
+        );
+        assert_eq!(6, TypeDesc::from_u16(0xffff).derivations.len());
+    }
+}
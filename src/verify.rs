@@ -0,0 +1,1282 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Consistency checks for [LIB] and [OBJ](super::OBJ) files.
+//!
+//! Checks never stop at the first problem. Instead, each problem is reported
+//! as a [Finding] with a [Severity] and it is up to the caller to decide
+//! which findings are fatal.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use anyhow::Result;
+//! use psyk::io;
+//! use psyk::verify;
+//!
+//! # fn main() -> Result<()> {
+//! let lib = io::read_lib(Path::new("SOME.LIB"))?;
+//! for finding in lib.verify(&verify::Options::default()) {
+//!     println!("{finding}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::error::{bail, Error, Result};
+use chrono::{Months, NaiveDate, NaiveDateTime};
+
+use super::kernel::{self, KernelCallInfo};
+use super::symbols;
+use super::{
+    Export, Expression, FileIdOrName, FromPSYQTimestamp, LineInfoMechanism, Section, LIB, OBJ,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How serious a [Finding] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Information that isn't a problem, such as the results of an
+    /// [analyzer plugin](super::plugin).
+    Note,
+    /// Something unusual that doesn't prevent the file from being used.
+    Warning,
+    /// Something that should cause verification to fail.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Note => write!(f, "note"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The specific problem described by a [Finding].
+#[derive(Clone, Debug, PartialEq)]
+pub enum FindingKind {
+    /// The module timestamp does not decode to a valid date and time.
+    UndecodableTimestamp { raw: u32 },
+    /// The module timestamp decodes, but falls outside of
+    /// [TimestampPolicy::range].
+    TimestampOutOfRange { raw: u32, created: NaiveDateTime },
+    /// The module timestamp is more than [TimestampPolicy::outlier_years]
+    /// newer than every other module in the same archive.
+    TimestampOutlier {
+        raw: u32,
+        created: NaiveDateTime,
+        newest_other: NaiveDateTime,
+    },
+    /// Line information refers to a file number that no
+    /// [Section::Filename](super::Section::Filename) defines.
+    UndefinedFile { file: u16 },
+    /// The same file is referenced by both legacy and SLD line information.
+    MixedLineInfo { file: FileIdOrName },
+    /// The stored offset to the end of the module metadata doesn't match
+    /// the size of its name, timestamp, sizes, and export table, so
+    /// rebuilding the module wouldn't reproduce it.
+    MetadataOffset { stored: u32, expected: u32 },
+    /// The size stored in a module's metadata doesn't match the bytes the
+    /// module was read from. See [SizeMismatch](super::SizeMismatch).
+    SizeMismatch { declared: u32, actual: u32 },
+    /// Two symbols defined by the module differ only in case. See
+    /// [symbols](super::symbols).
+    CaseCollision { first: String, second: String },
+    /// A loose OBJ defines a symbol with the same name as a library's
+    /// wrapper for a BIOS call, replacing it for every module linked with
+    /// the OBJ. See [kernel].
+    ShadowsKernelCall { call: KernelCallInfo },
+    /// A finding reported by an [analyzer plugin](super::plugin).
+    Plugin { plugin: String, message: String },
+    /// The exports stored in a module's metadata aren't the symbols its
+    /// OBJ defines with [XDEF](super::Section::XDEF) and
+    /// [XBSS](super::Section::XBSS), so the linker would search for the
+    /// wrong symbols. The order of the exports isn't checked.
+    ExportMismatch {
+        /// Stored exports the OBJ doesn't define.
+        unknown: Vec<String>,
+        /// Symbols the OBJ defines which aren't stored.
+        omitted: Vec<String>,
+    },
+    /// A patch at the section with index `section` applies beyond the end
+    /// of the code it follows in the current section.
+    PatchOutOfRange {
+        section: usize,
+        offset: u16,
+        code_size: u32,
+    },
+    /// The XREF at the section with index `section` declares a symbol no
+    /// patch refers to.
+    UnusedXref {
+        section: usize,
+        number: u16,
+        name: String,
+    },
+    /// The section with index `section` uses a number already used by
+    /// `first`. Sections, groups, and symbols are each numbered separately,
+    /// since expressions say which they refer to.
+    DuplicateSymbolNumber {
+        section: usize,
+        number: u16,
+        name: String,
+        first: String,
+    },
+    /// The section switch at the section with index `section` switches to
+    /// a section no [LNKHeader](super::LNKHeader) declares.
+    UndeclaredSection { section: usize, id: u16 },
+}
+
+impl FindingKind {
+    /// Returns `true` if this finding concerns a module timestamp.
+    pub fn is_timestamp(&self) -> bool {
+        matches!(
+            self,
+            Self::UndecodableTimestamp { .. }
+                | Self::TimestampOutOfRange { .. }
+                | Self::TimestampOutlier { .. }
+        )
+    }
+
+    /// Returns the index of the section within its OBJ this finding
+    /// concerns, if it concerns one.
+    pub fn section(&self) -> Option<usize> {
+        match self {
+            Self::PatchOutOfRange { section, .. }
+            | Self::UnusedXref { section, .. }
+            | Self::DuplicateSymbolNumber { section, .. }
+            | Self::UndeclaredSection { section, .. } => Some(*section),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UndecodableTimestamp { raw } => {
+                write!(f, "timestamp {raw:#010x} does not decode to a valid date")
+            }
+            Self::TimestampOutOfRange { raw, created } => write!(
+                f,
+                "timestamp {raw:#010x} ({}) is outside of the plausible range",
+                created.format(DATETIME_FORMAT)
+            ),
+            Self::TimestampOutlier {
+                raw,
+                created,
+                newest_other,
+            } => write!(
+                f,
+                "timestamp {raw:#010x} ({}) is far newer than every other module (newest: {})",
+                created.format(DATETIME_FORMAT),
+                newest_other.format(DATETIME_FORMAT),
+            ),
+            Self::UndefinedFile { file } => {
+                write!(
+                    f,
+                    "line information refers to undefined file number {file:x}"
+                )
+            }
+            Self::MixedLineInfo { file } => {
+                write!(
+                    f,
+                    "{file} is referenced by both legacy and SLD line information"
+                )
+            }
+            Self::MetadataOffset { stored, expected } => write!(
+                f,
+                "metadata offset {stored} does not match the {expected} bytes of metadata"
+            ),
+            Self::SizeMismatch { declared, actual } => write!(
+                f,
+                "metadata declares a size of {declared} bytes but the module is {actual} bytes"
+            ),
+            Self::CaseCollision { first, second } => {
+                write!(f, "{first} and {second} differ only in case")
+            }
+            Self::ShadowsKernelCall { call } => write!(
+                f,
+                "{} shadows the {} wrapper for {call}",
+                call.symbol, call.library
+            ),
+            Self::Plugin { plugin, message } => write!(f, "{plugin}: {message}"),
+            Self::ExportMismatch { unknown, omitted } => {
+                write!(f, "exports don't match the OBJ")?;
+                if !unknown.is_empty() {
+                    write!(f, "; not defined: {}", unknown.join(", "))?;
+                }
+                if !omitted.is_empty() {
+                    write!(f, "; not exported: {}", omitted.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::PatchOutOfRange {
+                offset, code_size, ..
+            } => write!(
+                f,
+                "patch at offset {offset:x} is beyond the {code_size} bytes of code it follows"
+            ),
+            Self::UnusedXref { number, name, .. } => {
+                write!(f, "XREF {name} ({number:x}) is never referenced by a patch")
+            }
+            Self::DuplicateSymbolNumber {
+                number,
+                name,
+                first,
+                ..
+            } => write!(f, "{name} reuses number {number:x} from {first}"),
+            Self::UndeclaredSection { id, .. } => {
+                write!(f, "switch to undeclared section {id:x}")
+            }
+        }
+    }
+}
+
+/// A single problem found while verifying a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The name of the module the problem was found in, if any.
+    pub module: Option<String>,
+    /// What the problem is.
+    pub kind: FindingKind,
+}
+
+impl Finding {
+    /// Returns the index of the section the problem was found at, if any.
+    /// See [FindingKind::section].
+    pub fn section(&self) -> Option<usize> {
+        self.kind.section()
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(module) = &self.module {
+            write!(f, "{module}: ")?;
+        }
+        if let Some(section) = self.section() {
+            write!(f, "section {section}: ")?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// Rules for deciding whether module timestamps are plausible.
+///
+/// Timestamps are only informational, so problems are reported as
+/// [Severity::Warning] unless [TimestampPolicy::strict] is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimestampPolicy {
+    /// Dates considered plausible. Defaults to the years PSY-Q was in use,
+    /// 1988-01-01 through 2005-12-31.
+    pub range: RangeInclusive<NaiveDate>,
+    /// A module dated more than this many years after every other module in
+    /// the same archive is reported as an outlier.
+    pub outlier_years: u32,
+    /// Report timestamp problems as errors rather than warnings.
+    pub strict: bool,
+}
+
+impl Default for TimestampPolicy {
+    fn default() -> Self {
+        Self {
+            range: NaiveDate::from_ymd_opt(1988, 1, 1).expect("date")
+                ..=NaiveDate::from_ymd_opt(2005, 12, 31).expect("date"),
+            outlier_years: 5,
+            strict: false,
+        }
+    }
+}
+
+impl TimestampPolicy {
+    /// The severity of findings produced by this policy.
+    pub fn severity(&self) -> Severity {
+        if self.strict {
+            Severity::Error
+        } else {
+            Severity::Warning
+        }
+    }
+
+    /// Checks the timestamps of every module in `lib`.
+    pub fn check(&self, lib: &LIB) -> Vec<Finding> {
+        let timestamps = lib
+            .modules()
+            .iter()
+            .map(|m| (m.name(), m.metadata.created))
+            .collect::<Vec<(String, u32)>>();
+        self.check_timestamps(&timestamps)
+    }
+
+    fn check_timestamps(&self, timestamps: &[(String, u32)]) -> Vec<Finding> {
+        let decoded = timestamps
+            .iter()
+            .map(|(_, raw)| NaiveDateTime::from_psyq_timestamp(*raw))
+            .collect::<Vec<Option<NaiveDateTime>>>();
+
+        let mut findings = Vec::new();
+        let mut report = |module: &str, kind: FindingKind| {
+            findings.push(Finding {
+                severity: self.severity(),
+                module: Some(module.to_string()),
+                kind,
+            })
+        };
+
+        for (i, ((name, raw), created)) in timestamps.iter().zip(&decoded).enumerate() {
+            let raw = *raw;
+            let Some(created) = *created else {
+                report(name, FindingKind::UndecodableTimestamp { raw });
+                continue;
+            };
+
+            if !self.range.contains(&created.date()) {
+                report(name, FindingKind::TimestampOutOfRange { raw, created });
+            }
+
+            let newest_other = decoded
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .filter_map(|(_, other)| *other)
+                .max();
+            if let Some(newest_other) = newest_other {
+                let threshold = newest_other
+                    .checked_add_months(Months::new(self.outlier_years * 12))
+                    .unwrap_or(NaiveDateTime::MAX);
+                if created > threshold {
+                    report(
+                        name,
+                        FindingKind::TimestampOutlier {
+                            raw,
+                            created,
+                            newest_other,
+                        },
+                    );
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Parses a date range in the form `FROM..TO`, where both dates are written
+/// as `YYYY-MM-DD`. Both ends of the range are inclusive.
+pub fn parse_date_range(s: &str) -> Result<RangeInclusive<NaiveDate>> {
+    let Some((from, to)) = s.split_once("..") else {
+        bail!("expected a date range in the form YYYY-MM-DD..YYYY-MM-DD: {s}");
+    };
+    let from = NaiveDate::parse_from_str(from, DATE_FORMAT).map_err(Error::other)?;
+    let to = NaiveDate::parse_from_str(to, DATE_FORMAT).map_err(Error::other)?;
+    if from > to {
+        bail!("date range start is after its end: {s}");
+    }
+    Ok(from..=to)
+}
+
+/// Selects and configures the checks run by [LIB::verify].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    pub timestamps: TimestampPolicy,
+    /// Report modules whose declared size is wrong as errors rather than
+    /// warnings.
+    pub strict_sizes: bool,
+}
+
+fn check_file_references(obj: &OBJ) -> Vec<Finding> {
+    obj.file_references()
+        .into_iter()
+        .filter_map(|file_ref| {
+            let kind = match (file_ref.file, file_ref.mechanism) {
+                (FileIdOrName::Id(file), _) => FindingKind::UndefinedFile { file },
+                (file, LineInfoMechanism::Mixed) => FindingKind::MixedLineInfo { file },
+                _ => return None,
+            };
+            Some(Finding {
+                severity: Severity::Warning,
+                module: None,
+                kind,
+            })
+        })
+        .collect()
+}
+
+fn check_case_collisions(obj: &OBJ) -> Vec<Finding> {
+    let exports = obj.exports();
+    symbols::case_collisions(exports.iter().map(String::as_str))
+        .into_iter()
+        .map(|(first, second)| Finding {
+            severity: Severity::Warning,
+            module: None,
+            kind: FindingKind::CaseCollision { first, second },
+        })
+        .collect()
+}
+
+fn check_kernel_shadows(obj: &OBJ) -> Vec<Finding> {
+    kernel::shadowed_kernel_calls(obj)
+        .into_iter()
+        .map(|call| Finding {
+            severity: Severity::Warning,
+            module: None,
+            kind: FindingKind::ShadowsKernelCall { call },
+        })
+        .collect()
+}
+
+fn check_patches(obj: &OBJ) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut current = None;
+    // the size of each section and where its last code starts
+    let mut sizes: HashMap<Option<u16>, u32> = HashMap::new();
+    let mut code_start: HashMap<Option<u16>, u32> = HashMap::new();
+    for (index, section) in obj.sections().iter().enumerate() {
+        match section {
+            Section::SectionSwitch(id) => current = Some(*id),
+            Section::Code(code) => {
+                let size = sizes.entry(current).or_default();
+                code_start.insert(current, *size);
+                *size += code.size() as u32;
+            }
+            Section::BSS(bss) => *sizes.entry(current).or_default() += bss,
+            Section::Patch(patch) => {
+                let start = code_start.get(&current).copied();
+                let code_size = start.map_or(0, |start| sizes[&current] - start);
+                if patch.offset() as u32 >= code_size {
+                    findings.push(Finding {
+                        severity: Severity::Error,
+                        module: None,
+                        kind: FindingKind::PatchOutOfRange {
+                            section: index,
+                            offset: patch.offset(),
+                            code_size,
+                        },
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    findings
+}
+
+fn check_symbol_numbers(obj: &OBJ) -> Vec<Finding> {
+    let mut referenced = HashSet::new();
+    for section in obj.sections() {
+        if let Section::Patch(patch) = section {
+            patch.expression().walk(&mut |e| {
+                if let Expression::SymbolAddressIndex(number) = e {
+                    referenced.insert(*number);
+                }
+            });
+        }
+    }
+
+    let mut findings = Vec::new();
+    let mut numbers: HashMap<(&str, u16), String> = HashMap::new();
+    for (index, section) in obj.sections().iter().enumerate() {
+        let (number, name) = match section {
+            Section::LNKHeader(header) => (("section", header.section()), header.type_name()),
+            Section::GroupSymbol(group) => (("group", group.number()), group.name()),
+            Section::XDEF(xdef) => (("symbol", xdef.number()), xdef.symbol_name()),
+            Section::XBSS(xbss) => (("symbol", xbss.number()), xbss.name()),
+            Section::XREF(xref) => {
+                if !referenced.contains(&xref.number()) {
+                    findings.push(Finding {
+                        severity: Severity::Note,
+                        module: None,
+                        kind: FindingKind::UnusedXref {
+                            section: index,
+                            number: xref.number(),
+                            name: xref.symbol_name(),
+                        },
+                    });
+                }
+                (("symbol", xref.number()), xref.symbol_name())
+            }
+            _ => continue,
+        };
+        match numbers.get(&number) {
+            Some(first) => findings.push(Finding {
+                severity: Severity::Error,
+                module: None,
+                kind: FindingKind::DuplicateSymbolNumber {
+                    section: index,
+                    number: number.1,
+                    name,
+                    first: first.clone(),
+                },
+            }),
+            None => {
+                numbers.insert(number, name);
+            }
+        }
+    }
+    findings
+}
+
+fn check_section_switches(obj: &OBJ) -> Vec<Finding> {
+    let declared = obj
+        .sections()
+        .iter()
+        .filter_map(|section| match section {
+            Section::LNKHeader(header) => Some(header.section()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    obj.sections()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, section)| match section {
+            Section::SectionSwitch(id) if !declared.contains(id) => Some(Finding {
+                severity: Severity::Error,
+                module: None,
+                kind: FindingKind::UndeclaredSection {
+                    section: index,
+                    id: *id,
+                },
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_exports(lib: &LIB) -> Vec<Finding> {
+    lib.modules()
+        .iter()
+        .filter_map(|module| {
+            let stored = module.exports();
+            let defined = module.object().exports_detailed().map_or_else(
+                |_| module.object().exports(),
+                |exports| exports.iter().map(Export::name).collect(),
+            );
+            let unknown = stored
+                .iter()
+                .filter(|name| !defined.contains(name))
+                .cloned()
+                .collect::<Vec<_>>();
+            let omitted = defined
+                .iter()
+                .filter(|name| !stored.contains(name))
+                .cloned()
+                .collect::<Vec<_>>();
+            (!unknown.is_empty() || !omitted.is_empty()).then(|| Finding {
+                severity: Severity::Warning,
+                module: Some(module.name()),
+                kind: FindingKind::ExportMismatch { unknown, omitted },
+            })
+        })
+        .collect()
+}
+
+fn check_metadata(lib: &LIB) -> Vec<Finding> {
+    lib.modules()
+        .iter()
+        .filter_map(|module| {
+            let stored = module.metadata.offset;
+            let expected = module.metadata.expected_offset();
+            (stored != expected).then(|| Finding {
+                severity: Severity::Error,
+                module: Some(module.name()),
+                kind: FindingKind::MetadataOffset { stored, expected },
+            })
+        })
+        .collect()
+}
+
+fn check_sizes(lib: &LIB, strict: bool) -> Vec<Finding> {
+    lib.size_mismatches()
+        .iter()
+        .map(|mismatch| Finding {
+            severity: if strict {
+                Severity::Error
+            } else {
+                Severity::Warning
+            },
+            module: Some(lib.modules()[mismatch.index].name()),
+            kind: FindingKind::SizeMismatch {
+                declared: mismatch.declared,
+                actual: mismatch.actual,
+            },
+        })
+        .collect()
+}
+
+/// Runs all checks against `lib`, including the checks run by [verify_obj]
+/// for each module. Modules aren't checked for
+/// [shadowing](FindingKind::ShadowsKernelCall) kernel calls, since the
+/// libraries that wrap them are expected to define them.
+pub fn verify_lib(lib: &LIB, options: &Options) -> Vec<Finding> {
+    let mut findings = options.timestamps.check(lib);
+    findings.extend(check_metadata(lib));
+    findings.extend(check_sizes(lib, options.strict_sizes));
+    findings.extend(check_exports(lib));
+    for module in lib.modules() {
+        findings.extend(
+            check_module(module.object(), options)
+                .into_iter()
+                .map(|f| Finding {
+                    module: Some(module.name()),
+                    ..f
+                }),
+        );
+    }
+    findings
+}
+
+/// Runs all checks against `obj`.
+pub fn verify_obj(obj: &OBJ, options: &Options) -> Vec<Finding> {
+    let mut findings = check_module(obj, options);
+    findings.extend(check_kernel_shadows(obj));
+    findings
+}
+
+/// The checks of [verify_obj] that also apply to the modules of a LIB.
+fn check_module(obj: &OBJ, _options: &Options) -> Vec<Finding> {
+    let mut findings = check_file_references(obj);
+    findings.extend(check_case_collisions(obj));
+    findings.extend(check_section_switches(obj));
+    findings.extend(check_patches(obj));
+    findings.extend(check_symbol_numbers(obj));
+    findings
+}
+
+/// The per-module results of a previous [verify_lib_incremental] run.
+///
+/// Only the checks of each module's OBJ are kept. Checks that compare
+/// modules with each other, like timestamp outliers, or that look at
+/// metadata are cheap and always run again.
+///
+/// A baseline is saved as tab-separated lines of text: a `psyk-verify`
+/// header with the format version, then a `module` line with the name and
+/// [fingerprint](super::Module::fingerprint) of each module, each followed
+/// by a `finding` line for each of its findings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    modules: Vec<BaselineModule>,
+    /// The number of modules checked by the run that produced this
+    /// baseline. Not saved.
+    pub checked: usize,
+    /// The number of modules whose findings were reused by the run that
+    /// produced this baseline. Not saved.
+    pub reused: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct BaselineModule {
+    name: String,
+    fingerprint: u32,
+    findings: Vec<Finding>,
+}
+
+const BASELINE_VERSION: u32 = 1;
+
+impl Baseline {
+    /// Reads a baseline written by [Baseline::save].
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes the baseline to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        Ok(std::fs::write(path, self.to_string())?)
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        let mut lines = s.lines();
+        match lines.next().map(|l| l.split('\t').collect::<Vec<_>>()) {
+            Some(header) if header == ["psyk-verify", &BASELINE_VERSION.to_string()] => (),
+            Some(header) if header.first() == Some(&"psyk-verify") => {
+                bail!("unsupported baseline version: {}", header[1..].join(" "))
+            }
+            _ => bail!("not a psyk verify baseline"),
+        }
+
+        let mut baseline = Self::default();
+        for line in lines {
+            let fields = line.split('\t').map(unescape).collect::<Vec<_>>();
+            let fields = fields.iter().map(String::as_str).collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["module", name, fingerprint] => baseline.modules.push(BaselineModule {
+                    name: name.to_string(),
+                    fingerprint: u32::from_str_radix(fingerprint, 16).map_err(Error::other)?,
+                    findings: Vec::new(),
+                }),
+                ["finding", severity, kind @ ..] => {
+                    let Some(module) = baseline.modules.last_mut() else {
+                        bail!("finding before any module: {line}");
+                    };
+                    let severity = match *severity {
+                        "note" => Severity::Note,
+                        "warning" => Severity::Warning,
+                        "error" => Severity::Error,
+                        _ => bail!("unknown severity: {line}"),
+                    };
+                    module.findings.push(Finding {
+                        severity,
+                        module: Some(module.name.clone()),
+                        kind: decode_kind(kind)
+                            .ok_or_else(|| Error::other(format!("unknown finding: {line}")))?,
+                    });
+                }
+                _ => bail!("unexpected line in baseline: {line}"),
+            }
+        }
+        Ok(baseline)
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "psyk-verify\t{BASELINE_VERSION}")?;
+        for module in &self.modules {
+            writeln!(
+                f,
+                "module\t{}\t{:08x}",
+                escape(&module.name),
+                module.fingerprint
+            )?;
+            for finding in &module.findings {
+                let Some(kind) = encode_kind(&finding.kind) else {
+                    continue;
+                };
+                let fields = kind.iter().map(|k| escape(k)).collect::<Vec<_>>();
+                writeln!(f, "finding\t{}\t{}", finding.severity, fields.join("\t"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// The fields of the kinds of finding checked for each module of a LIB.
+fn encode_kind(kind: &FindingKind) -> Option<Vec<String>> {
+    Some(match kind {
+        FindingKind::UndefinedFile { file } => vec!["undefined-file".into(), file.to_string()],
+        FindingKind::MixedLineInfo { file } => match file {
+            FileIdOrName::Id(id) => vec!["mixed-line-info".into(), "id".into(), id.to_string()],
+            FileIdOrName::Name(name) => {
+                vec!["mixed-line-info".into(), "name".into(), name.clone()]
+            }
+        },
+        FindingKind::CaseCollision { first, second } => {
+            vec!["case-collision".into(), first.clone(), second.clone()]
+        }
+        FindingKind::Plugin { plugin, message } => {
+            vec!["plugin".into(), plugin.clone(), message.clone()]
+        }
+        FindingKind::PatchOutOfRange {
+            section,
+            offset,
+            code_size,
+        } => vec![
+            "patch-out-of-range".into(),
+            section.to_string(),
+            offset.to_string(),
+            code_size.to_string(),
+        ],
+        FindingKind::UnusedXref {
+            section,
+            number,
+            name,
+        } => vec![
+            "unused-xref".into(),
+            section.to_string(),
+            number.to_string(),
+            name.clone(),
+        ],
+        FindingKind::DuplicateSymbolNumber {
+            section,
+            number,
+            name,
+            first,
+        } => vec![
+            "duplicate-symbol-number".into(),
+            section.to_string(),
+            number.to_string(),
+            name.clone(),
+            first.clone(),
+        ],
+        FindingKind::UndeclaredSection { section, id } => vec![
+            "undeclared-section".into(),
+            section.to_string(),
+            id.to_string(),
+        ],
+        _ => return None,
+    })
+}
+
+fn decode_kind(fields: &[&str]) -> Option<FindingKind> {
+    Some(match fields {
+        ["undefined-file", file] => FindingKind::UndefinedFile {
+            file: file.parse().ok()?,
+        },
+        ["mixed-line-info", "id", id] => FindingKind::MixedLineInfo {
+            file: FileIdOrName::Id(id.parse().ok()?),
+        },
+        ["mixed-line-info", "name", name] => FindingKind::MixedLineInfo {
+            file: FileIdOrName::Name(name.to_string()),
+        },
+        ["case-collision", first, second] => FindingKind::CaseCollision {
+            first: first.to_string(),
+            second: second.to_string(),
+        },
+        ["plugin", plugin, message] => FindingKind::Plugin {
+            plugin: plugin.to_string(),
+            message: message.to_string(),
+        },
+        ["patch-out-of-range", section, offset, code_size] => FindingKind::PatchOutOfRange {
+            section: section.parse().ok()?,
+            offset: offset.parse().ok()?,
+            code_size: code_size.parse().ok()?,
+        },
+        ["unused-xref", section, number, name] => FindingKind::UnusedXref {
+            section: section.parse().ok()?,
+            number: number.parse().ok()?,
+            name: name.to_string(),
+        },
+        ["duplicate-symbol-number", section, number, name, first] => {
+            FindingKind::DuplicateSymbolNumber {
+                section: section.parse().ok()?,
+                number: number.parse().ok()?,
+                name: name.to_string(),
+                first: first.to_string(),
+            }
+        }
+        ["undeclared-section", section, id] => FindingKind::UndeclaredSection {
+            section: section.parse().ok()?,
+            id: id.parse().ok()?,
+        },
+        _ => return None,
+    })
+}
+
+/// Runs the same checks as [verify_lib], reusing the findings in
+/// `baseline` for modules whose name and fingerprint haven't changed.
+/// Modules that are new or changed are checked, and modules that were
+/// removed are forgotten. Returns the findings and a baseline for the next
+/// run.
+pub fn verify_lib_incremental(
+    lib: &LIB,
+    options: &Options,
+    baseline: &Baseline,
+) -> (Vec<Finding>, Baseline) {
+    let mut findings = options.timestamps.check(lib);
+    findings.extend(check_metadata(lib));
+    findings.extend(check_sizes(lib, options.strict_sizes));
+    findings.extend(check_exports(lib));
+
+    let mut next = Baseline::default();
+    for module in lib.modules() {
+        let name = module.name();
+        // modules with deferred code can't be fingerprinted, so are always
+        // checked and left out of the next baseline
+        let fingerprint = module.fingerprint().ok();
+        let previous = baseline
+            .modules
+            .iter()
+            .find(|m| m.name == name && Some(m.fingerprint) == fingerprint);
+        let module_findings = match previous {
+            Some(previous) => {
+                next.reused += 1;
+                previous.findings.clone()
+            }
+            None => {
+                next.checked += 1;
+                check_module(module.object(), options)
+                    .into_iter()
+                    .map(|f| Finding {
+                        module: Some(name.clone()),
+                        ..f
+                    })
+                    .collect()
+            }
+        };
+        findings.extend(module_findings.iter().cloned());
+        if let Some(fingerprint) = fingerprint {
+            next.modules.push(BaselineModule {
+                name,
+                fingerprint,
+                findings: module_findings,
+            });
+        }
+    }
+    (findings, next)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Module, ModuleMetadata, Section, OBJ};
+    use std::time::SystemTime;
+
+    fn module(name: &str, created: u32) -> Module {
+        let mut metadata =
+            ModuleMetadata::new(name.into(), SystemTime::now(), 0, vec![]).expect("metadata");
+        metadata.created = created;
+        Module::new(OBJ::new(vec![Section::NOP]), metadata)
+    }
+
+    fn timestamp(y: i32, m: u32, d: u32) -> u32 {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .expect("date")
+            .and_hms_opt(12, 30, 0)
+            .expect("time")
+            .to_psyq_timestamp()
+    }
+
+    fn lib() -> LIB {
+        LIB::new(vec![
+            module("A", timestamp(1995, 3, 1)),
+            module("B", 0),
+            module("C", timestamp(1996, 7, 14)),
+            module("D", timestamp(2079, 1, 1)),
+            module("E", timestamp(1997, 11, 30)),
+        ])
+    }
+
+    fn count(findings: &[Finding], f: impl Fn(&FindingKind) -> bool) -> usize {
+        findings.iter().filter(|finding| f(&finding.kind)).count()
+    }
+
+    #[test]
+    fn test_timestamp_findings() {
+        let findings = lib().verify(&Options::default());
+        assert_eq!(3, findings.len());
+        assert_eq!(
+            1,
+            count(&findings, |k| matches!(
+                k,
+                FindingKind::UndecodableTimestamp { raw: 0 }
+            ))
+        );
+        assert_eq!(
+            1,
+            count(&findings, |k| matches!(
+                k,
+                FindingKind::TimestampOutOfRange { .. }
+            ))
+        );
+        assert_eq!(
+            1,
+            count(&findings, |k| matches!(
+                k,
+                FindingKind::TimestampOutlier { .. }
+            ))
+        );
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+        assert_eq!(Some("B".to_string()), findings[0].module);
+        assert!(findings[1..]
+            .iter()
+            .all(|f| f.module.as_deref() == Some("D")));
+    }
+
+    #[test]
+    fn test_strict_timestamps() {
+        let options = Options {
+            timestamps: TimestampPolicy {
+                strict: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let findings = lib().verify(&options);
+        assert_eq!(3, findings.len());
+        assert!(findings.iter().all(|f| f.severity == Severity::Error));
+    }
+
+    #[test]
+    fn test_timestamp_range() {
+        let options = Options {
+            timestamps: TimestampPolicy {
+                range: parse_date_range("1996-01-01..2100-01-01").expect("range"),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let findings = lib().verify(&options);
+        // A is now too old, D is in range but still an outlier
+        assert_eq!(3, findings.len());
+        assert_eq!(Some("A".to_string()), findings[0].module);
+    }
+
+    #[test]
+    fn test_finding_display() {
+        let findings = lib().verify(&Options::default());
+        assert_eq!(
+            "warning: B: timestamp 0x00000000 does not decode to a valid date",
+            findings[0].to_string()
+        );
+        assert_eq!(
+            "warning: D: timestamp 0x63c0c621 (2079-01-01 12:30:00) is outside of the plausible range",
+            findings[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_metadata_offset() {
+        let mut lib = lib();
+        assert!(lib
+            .verify(&Options::default())
+            .iter()
+            .all(|f| f.kind.is_timestamp()));
+
+        lib.objs[2].metadata.offset += 1;
+        let findings = lib.verify(&Options::default());
+        let finding = findings
+            .iter()
+            .find(|f| !f.kind.is_timestamp())
+            .expect("offset finding");
+        assert_eq!(
+            "error: C: metadata offset 22 does not match the 21 bytes of metadata",
+            finding.to_string()
+        );
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let mut lib = lib();
+        lib.repair_sizes().expect("repair");
+        let mut bytes = binrw::io::Cursor::new(Vec::new());
+        binrw::BinWrite::write_le(&lib, &mut bytes).expect("lib");
+        let mut bytes = bytes.into_inner();
+        // the size of A, which starts after the LIB header
+        bytes[20..24].copy_from_slice(&100u32.to_le_bytes());
+        let lib = <LIB as binrw::BinRead>::read(&mut binrw::io::Cursor::new(bytes)).expect("lib");
+
+        let sizes = |options: &Options| {
+            lib.verify(options)
+                .into_iter()
+                .filter(|f| matches!(f.kind, FindingKind::SizeMismatch { .. }))
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["warning: A: metadata declares a size of 100 bytes but the module is 26 bytes"],
+            sizes(&Options::default())
+        );
+        let strict = Options {
+            strict_sizes: true,
+            ..Default::default()
+        };
+        assert!(sizes(&strict)[0].starts_with("error: A: "));
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        assert!(parse_date_range("1988-01-01").is_err());
+        assert!(parse_date_range("2005-01-01..1988-01-01").is_err());
+        assert!(parse_date_range("1988-1-1..2005-13-01").is_err());
+        assert_eq!(
+            TimestampPolicy::default().range,
+            parse_date_range("1988-01-01..2005-12-31").expect("range")
+        );
+    }
+
+    #[test]
+    fn test_case_collision() {
+        let obj = OBJ::new(vec![xdef("Foo"), xdef("bar"), xdef("foo"), Section::NOP]);
+        let findings = verify_obj(&obj, &Options::default());
+        assert_eq!(
+            vec!["warning: Foo and foo differ only in case"],
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_kernel_shadow() {
+        let obj = OBJ::new(vec![xdef("main"), xdef("printf"), Section::NOP]);
+        let findings = verify_obj(&obj, &Options::default());
+        assert_eq!(
+            vec!["warning: printf shadows the LIBC wrapper for kernel A0:0x3F"],
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        );
+
+        // the libraries themselves define the wrappers
+        let metadata =
+            ModuleMetadata::new("PRINTF".into(), SystemTime::now(), 0, vec![]).expect("metadata");
+        let lib = LIB::new(vec![Module::new(obj, metadata)]);
+        assert!(!verify_lib(&lib, &Options::default())
+            .iter()
+            .any(|f| matches!(f.kind, FindingKind::ShadowsKernelCall { .. })));
+    }
+
+    /// An XDEF numbered by its name, so that different symbols have
+    /// different numbers.
+    fn xdef(symbol: &str) -> Section {
+        Section::XDEF(crate::XDEF {
+            number: symbol.bytes().map(u16::from).sum(),
+            section: 1,
+            offset: 0,
+            symbol_name_size: symbol.len() as u8,
+            symbol_name: symbol.as_bytes().to_vec(),
+        })
+    }
+
+    fn colliding(name: &str, symbols: &[&str]) -> Module {
+        let mut sections = symbols.iter().map(|s| xdef(s)).collect::<Vec<_>>();
+        sections.push(Section::NOP);
+        let exports = symbols
+            .iter()
+            .map(|s| crate::Export::new(s.to_string()).expect("export"))
+            .collect();
+        let mut metadata =
+            ModuleMetadata::new(name.into(), SystemTime::now(), 0, exports).expect("metadata");
+        metadata.created = timestamp(1996, 1, 1);
+        Module::new(OBJ::new(sections), metadata)
+    }
+
+    #[test]
+    fn test_incremental() {
+        let options = Options::default();
+        let lib = LIB::new(vec![
+            colliding("A", &["Foo", "foo"]),
+            colliding("B", &["bar"]),
+            colliding("C", &["Baz", "baz"]),
+        ]);
+        let (findings, baseline) = lib.verify_incremental(&options, &Baseline::default());
+        assert_eq!(lib.verify(&options), findings);
+        assert_eq!((3, 0), (baseline.checked, baseline.reused));
+
+        let baseline = Baseline::parse(&baseline.to_string()).expect("baseline");
+        let (again, baseline) = lib.verify_incremental(&options, &baseline);
+        assert_eq!(findings, again);
+        assert_eq!((0, 3), (baseline.checked, baseline.reused));
+
+        // B changes, C is removed
+        let changed = LIB::new(vec![
+            colliding("A", &["Foo", "foo"]),
+            colliding("B", &["bar", "Bar"]),
+        ]);
+        let (findings, next) = changed.verify_incremental(&options, &baseline);
+        assert_eq!(changed.verify(&options), findings);
+        assert_eq!((1, 1), (next.checked, next.reused));
+        assert_eq!(
+            vec![
+                "warning: A: Foo and foo differ only in case",
+                "warning: B: bar and Bar differ only in case"
+            ],
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    /// A module whose OBJ and metadata disagree in every way that
+    /// [check_module] and [check_exports] look for.
+    fn corrupted() -> Module {
+        let obj = OBJ::new(vec![
+            Section::LNKHeader(crate::LNKHeader::new(1, 0, 8, ".text").expect("header")),
+            Section::SectionSwitch(2),
+            Section::SectionSwitch(1),
+            Section::Code(crate::Code::new(vec![0; 8]).expect("code")),
+            Section::Patch(crate::Patch::new(82, 4, Expression::SymbolAddressIndex(3))),
+            Section::Patch(crate::Patch::new(84, 8, Expression::SymbolAddressIndex(3))),
+            Section::XREF(crate::XREF::new(3, "used").expect("xref")),
+            Section::XREF(crate::XREF::new(4, "unused").expect("xref")),
+            Section::XDEF(crate::XDEF::new(3, 1, 0, "main").expect("xdef")),
+            Section::NOP,
+        ]);
+        let mut metadata = ModuleMetadata::new(
+            "BROKEN".into(),
+            SystemTime::now(),
+            0,
+            vec![crate::Export::new("start".into()).expect("export")],
+        )
+        .expect("metadata");
+        metadata.created = timestamp(1996, 1, 1);
+        Module::new(obj, metadata)
+    }
+
+    #[test]
+    fn test_corrupted_module() {
+        let lib = LIB::new(vec![corrupted()]);
+        let findings = lib.verify(&Options::default());
+        assert_eq!(
+            vec![
+                "warning: BROKEN: exports don't match the OBJ; not defined: start; not exported: main",
+                "error: BROKEN: section 1: switch to undeclared section 2",
+                "error: BROKEN: section 5: patch at offset 8 is beyond the 8 bytes of code it follows",
+                "note: BROKEN: section 7: XREF unused (4) is never referenced by a patch",
+                "error: BROKEN: section 8: main reuses number 3 from used",
+            ],
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![None, Some(1), Some(5), Some(7), Some(8)],
+            findings.iter().map(Finding::section).collect::<Vec<_>>()
+        );
+        assert!(findings
+            .iter()
+            .all(|f| f.module.as_deref() == Some("BROKEN")));
+
+        // the OBJ checks survive a baseline
+        let (incremental, baseline) =
+            lib.verify_incremental(&Options::default(), &Baseline::default());
+        assert_eq!(findings, incremental);
+        let baseline = Baseline::parse(&baseline.to_string()).expect("baseline");
+        let (reused, baseline) = lib.verify_incremental(&Options::default(), &baseline);
+        assert_eq!(findings, reused);
+        assert_eq!(1, baseline.reused);
+    }
+
+    #[test]
+    fn test_baseline_format() {
+        let mut baseline = Baseline::default();
+        baseline.modules.push(BaselineModule {
+            name: "A".into(),
+            fingerprint: 0x1234abcd,
+            findings: vec![Finding {
+                severity: Severity::Warning,
+                module: Some("A".into()),
+                kind: FindingKind::MixedLineInfo {
+                    file: FileIdOrName::Name("a\tb\\c.c".into()),
+                },
+            }],
+        });
+        let text = baseline.to_string();
+        assert_eq!(
+            "psyk-verify\t1\nmodule\tA\t1234abcd\nfinding\twarning\tmixed-line-info\tname\ta\\tb\\\\c.c\n",
+            text
+        );
+        assert_eq!(baseline, Baseline::parse(&text).expect("parse"));
+        assert!(Baseline::parse("psyk-verify\t2\n")
+            .expect_err("version")
+            .to_string()
+            .contains("unsupported baseline version: 2"));
+    }
+}
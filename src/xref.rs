@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! What the patches of an OBJ refer to.
+//!
+//! Each [Patch](crate::Patch) writes the value of an
+//! [Expression](crate::Expression) into the
+//! code. [OBJ::references] lists every symbol and section those
+//! expressions mention along with where the patch is, which is enough to
+//! build a call graph or find every use of an external symbol. Symbols
+//! are given by number; [Reference::symbol] finds the `XREF` or `XDEF`
+//! with that number.
+//!
+//! ```
+//! use std::path::Path;
+//! use psyk::io;
+//! use psyk::xref::SymbolOrSection;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))?;
+//! let symbols = obj.symbols();
+//! for reference in obj.references() {
+//!     if let SymbolOrSection::Symbol(_) = reference.symbol_or_section {
+//!         let name = reference.symbol(&symbols).map(|s| s.name());
+//!         println!("{:x}: {name:?}", reference.patch_offset);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::symtab::{SymbolEntry, SymbolTable};
+use crate::{Section, OBJ};
+
+/// Something an expression refers to by number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolOrSection {
+    Symbol(u16),
+    Section(u16),
+}
+
+/// A symbol or section referred to by a patch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reference {
+    /// The offset of the patched bytes from the start of `active_section`.
+    pub patch_offset: u32,
+    /// The section the patch is in.
+    pub active_section: u16,
+    pub symbol_or_section: SymbolOrSection,
+}
+
+impl Reference {
+    /// Returns the symbol this refers to, if it refers to a symbol and
+    /// `symbols` has one with its number.
+    pub fn symbol<'a>(&self, symbols: &'a SymbolTable) -> Option<SymbolEntry<'a>> {
+        match self.symbol_or_section {
+            SymbolOrSection::Symbol(number) => symbols.by_number(number),
+            SymbolOrSection::Section(_) => None,
+        }
+    }
+}
+
+/// Lists what every patch of `obj` refers to, in the order the patches
+/// appear. A patch that mentions several symbols or sections has a
+/// [Reference] for each, symbols first. Patches before the first section
+/// switch aren't in any section and are skipped.
+pub fn references(obj: &OBJ) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut sizes: HashMap<u16, u32> = HashMap::new();
+    let mut code_start: HashMap<u16, u32> = HashMap::new();
+    let mut current = None;
+    for section in obj.sections() {
+        match section {
+            Section::SectionSwitch(id) => current = Some(*id),
+            Section::Code(code) => {
+                if let Some(id) = current {
+                    let size = sizes.entry(id).or_default();
+                    code_start.insert(id, *size);
+                    *size += code.size() as u32;
+                }
+            }
+            Section::BSS(bss) => {
+                if let Some(id) = current {
+                    *sizes.entry(id).or_default() += bss;
+                }
+            }
+            Section::Patch(patch) => {
+                let Some(id) = current else {
+                    continue;
+                };
+                let start = code_start.get(&id).copied().unwrap_or_default();
+                let expression = patch.expression();
+                let referents = expression
+                    .referenced_symbols()
+                    .into_iter()
+                    .map(SymbolOrSection::Symbol)
+                    .chain(
+                        expression
+                            .referenced_sections()
+                            .into_iter()
+                            .map(SymbolOrSection::Section),
+                    );
+                references.extend(referents.map(|symbol_or_section| Reference {
+                    patch_offset: start + patch.offset() as u32,
+                    active_section: id,
+                    symbol_or_section,
+                }));
+            }
+            _ => (),
+        }
+    }
+    references
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Code, Expression, Patch};
+
+    #[test]
+    fn test_references() {
+        let expression = Expression::Add(
+            Box::new(Expression::SectionStart(2)),
+            Box::new(Expression::Add(
+                Box::new(Expression::SymbolAddressIndex(7)),
+                Box::new(Expression::SymbolAddressIndex(7)),
+            )),
+        );
+        assert_eq!(vec![7], expression.referenced_symbols());
+        assert_eq!(vec![2], expression.referenced_sections());
+        assert!(Expression::Constant(7).referenced_symbols().is_empty());
+
+        let obj = OBJ::new(vec![
+            Section::Patch(Patch::new(0x10, 0, Expression::SymbolAddressIndex(1))),
+            Section::SectionSwitch(1),
+            Section::Code(Code::new(vec![0; 8]).expect("code")),
+            Section::Code(Code::new(vec![0; 8]).expect("code")),
+            Section::Patch(Patch::new(0x10, 4, expression)),
+            Section::NOP,
+        ]);
+        let reference = |symbol_or_section| Reference {
+            patch_offset: 12,
+            active_section: 1,
+            symbol_or_section,
+        };
+        assert_eq!(
+            vec![
+                reference(SymbolOrSection::Symbol(7)),
+                reference(SymbolOrSection::Section(2)),
+            ],
+            references(&obj)
+        );
+    }
+}
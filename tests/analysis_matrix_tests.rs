@@ -0,0 +1,346 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Runs every analysis against archives with one, two, and no modules.
+//!
+//! Analyses are written with multi-module archives in mind, but the SDK
+//! ships LIBs with a single module and an empty [LIB] can be built in
+//! memory. Each analysis registered in [ANALYSES] is run against each
+//! archive from [archives] and must not panic, must not print `NaN` or an
+//! infinity, and checks its own documented degenerate values.
+//!
+//! Empty archives can't be written, so analyses that read a file return
+//! `None` for them.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use psyk::cli::{self, ChronologyView};
+use psyk::display::human::ReportStyle;
+use psyk::{analysis, chronology, diff, io, ldscript, link, verify, Module, LIB};
+use tempfile::TempDir;
+
+/// A LIB to run the analyses against and the file it was written to.
+struct Archive {
+    name: String,
+    lib: LIB,
+    path: Option<PathBuf>,
+}
+
+impl Archive {
+    fn modules(&self) -> usize {
+        self.lib.modules().len()
+    }
+
+    /// The directory containing only this archive.
+    fn dir(&self) -> Option<&Path> {
+        self.path.as_deref().and_then(Path::parent)
+    }
+}
+
+/// An analysis run against each archive. Returns its output, or `None` if
+/// it doesn't apply to the archive.
+type Analysis = fn(&Archive) -> Result<Option<String>>;
+
+/// Every analysis in the matrix. New analyses should be added here.
+const ANALYSES: &[(&str, Analysis)] = &[
+    ("list", list),
+    ("stat", stat),
+    ("verify", verify),
+    ("chronology", chronology),
+    ("chronology-cli", chronology_cli),
+    ("diff", diff),
+    ("diff-cli", diff_cli),
+    ("census", census),
+    ("census-cli", census_cli),
+    ("closure", closure),
+    ("section-compat", section_compat),
+    ("relocs", relocs),
+    ("ldscript-bss", ldscript_bss),
+    ("analyze", analyze),
+    ("index", index),
+    ("sizes", sizes),
+    ("functions", functions),
+];
+
+fn output(run: impl FnOnce(&mut Vec<u8>) -> psyk::error::Result<()>) -> Result<Option<String>> {
+    let mut bytes = Vec::new();
+    run(&mut bytes)?;
+    Ok(Some(String::from_utf8(bytes)?))
+}
+
+fn list(archive: &Archive) -> Result<Option<String>> {
+    let listing = format!("{}", archive.lib);
+    assert_eq!(archive.modules() + 2, listing.lines().count(), "{listing}");
+    Ok(Some(listing))
+}
+
+fn stat(archive: &Archive) -> Result<Option<String>> {
+    let Some(path) = &archive.path else {
+        return Ok(None);
+    };
+    output(|w| cli::stat(w, path))
+}
+
+fn verify(archive: &Archive) -> Result<Option<String>> {
+    let findings = verify::verify_lib(&archive.lib, &verify::Options::default());
+    if archive.modules() == 0 {
+        assert!(findings.is_empty());
+    }
+    Ok(Some(format!("{findings:?}")))
+}
+
+fn chronology(archive: &Archive) -> Result<Option<String>> {
+    let entries = archive
+        .lib
+        .modules()
+        .iter()
+        .map(|m| (PathBuf::from(&archive.name), m.metadata().clone()));
+    let report = chronology::analyze(entries);
+    // one library, unless there are no modules; too few modules for a cluster
+    assert_eq!(archive.modules().min(1), report.libraries.len());
+    assert!(report.clusters.is_empty());
+    Ok(Some(format!("{report:?}")))
+}
+
+fn chronology_cli(archive: &Archive) -> Result<Option<String>> {
+    let Some(dir) = archive.dir() else {
+        return Ok(None);
+    };
+    let style = ReportStyle::default();
+    let mut text = String::new();
+    for view in [ChronologyView::Library, ChronologyView::Module] {
+        text += &output(|w| cli::chronology(w, dir, view, 10, &style))?.unwrap_or_default();
+    }
+    Ok(Some(text))
+}
+
+fn diff(archive: &Archive) -> Result<Option<String>> {
+    let diff = diff::diff_libs(&archive.lib, &archive.lib);
+    assert!(diff.is_empty());
+    let text = format!("{diff}{}", diff.brief());
+    assert_eq!("", text);
+    Ok(Some(text))
+}
+
+fn diff_cli(archive: &Archive) -> Result<Option<String>> {
+    let Some(path) = &archive.path else {
+        return Ok(None);
+    };
+    let text = output(|w| cli::diff(w, path, path, false))?.unwrap_or_default();
+    assert_eq!("", text);
+    output(|w| cli::diff(w, path, path, true))
+}
+
+fn census(archive: &Archive) -> Result<Option<String>> {
+    let census = archive.lib.format_census();
+    assert_eq!(archive.modules(), census.modules.len());
+    assert_eq!(
+        archive.modules() as u64,
+        census
+            .total()
+            .sections
+            .get("NOP")
+            .copied()
+            .unwrap_or_default()
+    );
+    Ok(Some(format!("{census:?}")))
+}
+
+fn census_cli(archive: &Archive) -> Result<Option<String>> {
+    let Some(path) = &archive.path else {
+        return Ok(None);
+    };
+    output(|w| cli::census(w, path, false, false))
+}
+
+fn closure(archive: &Archive) -> Result<Option<String>> {
+    let exports = archive
+        .lib
+        .modules()
+        .iter()
+        .flat_map(Module::exports)
+        .collect::<Vec<_>>();
+    let roots = exports.iter().map(String::as_str).collect::<Vec<_>>();
+    let closure = link::closure(&archive.lib, &roots)?;
+    assert_eq!(archive.modules(), closure.modules.len());
+    Ok(Some(format!(
+        "{:?} {} {}",
+        closure.module_names(),
+        closure.code_size(),
+        closure.bss_size()
+    )))
+}
+
+fn section_compat(archive: &Archive) -> Result<Option<String>> {
+    let objs = archive
+        .lib
+        .modules()
+        .iter()
+        .map(Module::object)
+        .collect::<Vec<_>>();
+    let issues = link::section_compat(&objs);
+    if archive.modules() < 2 {
+        assert!(issues.is_empty());
+    }
+    Ok(Some(format!("{issues:?}")))
+}
+
+fn relocs(archive: &Archive) -> Result<Option<String>> {
+    let suspects = archive
+        .lib
+        .modules()
+        .iter()
+        .flat_map(|m| analysis::unpatched_addresses(m.object()))
+        .collect::<Vec<_>>();
+    let Some(path) = &archive.path else {
+        return Ok(Some(format!("{suspects:?}")));
+    };
+    output(|w| cli::check_relocs(w, path, false))
+}
+
+fn ldscript_bss(archive: &Archive) -> Result<Option<String>> {
+    let objs = archive
+        .lib
+        .modules()
+        .iter()
+        .map(Module::object)
+        .collect::<Vec<_>>();
+    Ok(Some(ldscript::bss_fragment(
+        &objs,
+        &ldscript::Options::default(),
+    )))
+}
+
+fn analyze(archive: &Archive) -> Result<Option<String>> {
+    let Some(path) = &archive.path else {
+        return Ok(None);
+    };
+    output(|w| cli::analyze(w, path, &[], None))
+}
+
+fn index(archive: &Archive) -> Result<Option<String>> {
+    let Some(dir) = archive.dir() else {
+        return Ok(None);
+    };
+    let index = psyk::index::build(dir)?;
+    let symbols = index.files.iter().map(|f| f.symbols.len()).sum::<usize>();
+    let exports = archive
+        .lib
+        .modules()
+        .iter()
+        .map(|m| m.exports().len())
+        .sum::<usize>();
+    assert!(symbols >= exports);
+    Ok(Some(format!("{symbols}")))
+}
+
+fn sizes(archive: &Archive) -> Result<Option<String>> {
+    if archive.modules() == 0 {
+        // an empty LIB can't be written
+        assert!(io::serialized_size_lib(&archive.lib).is_err());
+        assert!(io::write_lib_to(&archive.lib, &mut Vec::new()).is_err());
+        return Ok(None);
+    }
+    let size = io::serialized_size_lib(&archive.lib)?;
+    if let Some(path) = &archive.path {
+        assert_eq!(std::fs::metadata(path)?.len(), size);
+    }
+    Ok(Some(size.to_string()))
+}
+
+fn functions(archive: &Archive) -> Result<Option<String>> {
+    let Some(path) = &archive.path else {
+        return Ok(None);
+    };
+    output(|w| cli::functions(w, path, None, true))
+}
+
+/// The archives each analysis is run against. Each written archive is in a
+/// directory of its own in `tmp`.
+fn archives(tmp: &Path) -> Vec<Archive> {
+    let module = |path: &str| Module::new_from_path(Path::new(path)).expect("module");
+    let mut archives = vec![
+        Archive {
+            name: "SINGLE.LIB".into(),
+            lib: LIB::new(vec![module("tests/data/synthetic/MAIN.OBJ")]),
+            path: None,
+        },
+        Archive {
+            name: "SIMPLE.LIB".into(),
+            lib: io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib"),
+            path: None,
+        },
+    ];
+    let cdsfile = Path::new("tests/data/psy-q/3.6/PSX/UTILITY/MENU/CDSFILE.LIB");
+    if let Ok(lib) = io::read_lib(cdsfile) {
+        archives.push(Archive {
+            name: "CDSFILE.LIB".into(),
+            lib,
+            path: None,
+        });
+    }
+    for (i, archive) in archives.iter_mut().enumerate() {
+        let dir = tmp.join(i.to_string());
+        std::fs::create_dir(&dir).expect("dir");
+        let path = dir.join(&archive.name);
+        io::write_lib_to(
+            &archive.lib,
+            &mut std::fs::File::create(&path).expect("create"),
+        )
+        .expect("write");
+        archive.path = Some(path);
+    }
+    archives.push(Archive {
+        name: "EMPTY.LIB".into(),
+        lib: LIB::new(vec![]),
+        path: None,
+    });
+    archives
+}
+
+/// Returns `true` if `text` contains a `NaN` or an infinity as a word.
+fn non_finite(text: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric())
+        .any(|word| matches!(word, "NaN" | "inf"))
+}
+
+/// Returns `true` if a line of `text` that introduces a list, a line without
+/// indentation ending in `:` or a single word, is followed by a blank line,
+/// another heading, or the end of the text.
+fn empty_heading(text: &str) -> bool {
+    let heading = |line: &str| {
+        !line.starts_with(' ')
+            && (line.ends_with(':') || (!line.is_empty() && line.chars().all(char::is_alphabetic)))
+    };
+    let lines = text.lines().collect::<Vec<_>>();
+    lines.iter().enumerate().any(|(i, line)| {
+        heading(line) && lines.get(i + 1).is_none_or(|next| !next.starts_with(' '))
+    })
+}
+
+#[test]
+fn test_analysis_matrix() {
+    let tmp = TempDir::new().expect("tempdir");
+    let archives = archives(tmp.path());
+    let mut failures = Vec::new();
+    for archive in &archives {
+        for (name, analysis) in ANALYSES {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| analysis(archive)));
+            let failure = match result {
+                Err(_) => "panicked".to_string(),
+                Ok(Err(e)) => format!("failed: {e}"),
+                Ok(Ok(Some(text))) if non_finite(&text) => {
+                    format!("printed a non-finite number: {text}")
+                }
+                Ok(Ok(Some(text))) if empty_heading(&text) => {
+                    format!("printed a heading with nothing under it: {text}")
+                }
+                Ok(Ok(_)) => continue,
+            };
+            failures.push(format!("{name} on {}: {failure}", archive.name));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -97,3 +97,51 @@ fn test_dumpobj_bad_option() {
         .stderr(predicate::str::contains("Invalid option: /?"))
         .stderr(predicate::str::contains("Usage"));
 }
+
+#[test]
+fn test_dumpobj_option_order() {
+    let hex = "0000: 08 00 e0 03 00 00 00 00\n";
+    let disassembly = "/* 03e00008 */   jr          $ra\n";
+    let obj = "tests/data/synthetic/MAIN.OBJ";
+
+    for args in [[obj, "/c"], ["/c", obj], ["/C", obj]] {
+        dumpobj()
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(hex))
+            .stdout(predicate::str::contains(disassembly).not());
+    }
+
+    for args in [[obj, "/d"], ["/D", obj]] {
+        dumpobj()
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(hex).not())
+            .stdout(predicate::str::contains(disassembly));
+    }
+
+    for args in [[obj, "/c", "/d"], ["/d", obj, "/c"], ["/c", "/d", obj]] {
+        dumpobj()
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "{hex}\n    {disassembly}"
+            )));
+    }
+}
+
+#[test]
+fn test_dumpobj_two_files() {
+    dumpobj()
+        .args([
+            "tests/data/synthetic/MAIN.OBJ",
+            "/c",
+            "tests/data/synthetic/HELPER.OBJ",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage:"));
+}
@@ -1,11 +1,19 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::SystemTime;
 
 use assert_cmd::cargo;
 use assert_cmd::prelude::*;
+use binrw::io::Cursor;
+use binrw::BinWrite;
+use chrono::{Local, NaiveDate, TimeZone};
 use predicates::prelude::*;
+use psyk::{Module, ModuleMetadata, Section, LIB, OBJ};
+use tempfile::TempDir;
 
 #[inline]
 fn psyk() -> Command {
@@ -101,6 +109,117 @@ fn test_psyk_list_valid_file() {
         ));
 }
 
+#[test]
+fn test_psyk_stdin_round_trip() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lib = tmp.path().join("SIMPLE.LIB");
+    fs::copy("tests/data/synthetic/SIMPLE.LIB", &lib).expect("copy");
+    let original = psyk::io::read_lib(&lib).expect("lib");
+    let created = original.modules()[1]
+        .created_at()
+        .expect("created")
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("since epoch");
+
+    let output = psyk()
+        .args(["extract", "--stdout"])
+        .arg(&lib)
+        .arg("helper")
+        .output()
+        .expect("extract");
+    assert!(output.status.success());
+    let helper = output.stdout;
+    assert_eq!(
+        fs::read("tests/data/synthetic/HELPER.OBJ").expect("read"),
+        helper
+    );
+
+    psyk()
+        .arg("delete")
+        .arg(&lib)
+        .arg("HELPER")
+        .assert()
+        .success();
+    assert_cmd::Command::from_std(psyk())
+        .arg("add")
+        .arg(&lib)
+        .arg("-")
+        .env("SOURCE_DATE_EPOCH", created.as_secs().to_string())
+        .write_stdin(helper.clone())
+        .assert()
+        .success();
+    psyk()
+        .arg("rename")
+        .arg(&lib)
+        .args([psyk::cli::STDIN_MODULE, "HELPER"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read("tests/data/synthetic/SIMPLE.LIB").expect("read"),
+        fs::read(&lib).expect("read")
+    );
+
+    let created = tmp.path().join("HELPER.LIB");
+    assert_cmd::Command::from_std(psyk())
+        .arg("create")
+        .arg(&created)
+        .arg("-")
+        .write_stdin(helper.clone())
+        .assert()
+        .success();
+    let created = psyk::io::read_lib(&created).expect("lib");
+    assert_eq!(psyk::cli::STDIN_MODULE, created.modules()[0].name());
+    assert_eq!(
+        original.modules()[1].object(),
+        created.modules()[0].object()
+    );
+
+    assert_cmd::Command::from_std(psyk())
+        .arg("add")
+        .arg(&lib)
+        .args(["-", "-"])
+        .write_stdin(helper)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("- (stdin) can only be given once"));
+    psyk()
+        .args(["extract", "--stdout"])
+        .arg(&lib)
+        .args(["MAIN", "HELPER"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--stdout writes one module, but 2",
+        ));
+}
+
+#[test]
+fn test_psyk_list_annotate() {
+    psyk()
+        .arg("list")
+        .arg("--annotate")
+        .arg("--base-address")
+        .arg("0x80010000")
+        .arg("tests/data/synthetic/MAIN.OBJ")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\
+            main:\n    \
+            /* 000000 80010000 03e00008 */   jr          $ra\n",
+        ));
+
+    psyk()
+        .arg("list")
+        .arg("--annotate")
+        .arg("--base-address")
+        .arg("0xbadaddress")
+        .arg("tests/data/synthetic/MAIN.OBJ")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid address"));
+}
+
 #[test]
 fn test_psyk_list_file_not_found() {
     psyk()
@@ -140,6 +259,108 @@ fn test_psyk_add_file_not_found() {
         .stderr(predicate::str::contains("Error"));
 }
 
+#[cfg(feature = "serde")]
+#[test]
+fn test_psyk_list_json() {
+    let output = psyk()
+        .args([
+            "list",
+            "--format",
+            "json",
+            "tests/data/synthetic/SIMPLE.LIB",
+        ])
+        .output()
+        .expect("list");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("JSON");
+    let modules = json["modules"].as_array().expect("modules");
+    assert_eq!(2, modules.len());
+    assert_eq!("MAIN", modules[0]["metadata"]["name"]);
+    assert_eq!("main", modules[0]["metadata"]["exports"][0]);
+    let sections = &modules[0]["obj"]["sections"];
+    assert_eq!(serde_json::json!({"type": "CPU", "value": 7}), sections[0]);
+    assert_eq!("0800e00300000000", sections[3]["value"]["code"]);
+}
+
+#[cfg(feature = "package")]
+#[test]
+fn test_psyk_package_unpack() {
+    let tmp = TempDir::new().expect("tempdir");
+    let secret = tmp.path().join("secret.key");
+    let public = tmp.path().join("public.key");
+    let other = tmp.path().join("other.key");
+    fs::write(&secret, [7; 32]).expect("secret");
+    fs::write(&public, psyk::package::public_key(&[7; 32])).expect("public");
+    fs::write(&other, psyk::package::public_key(&[8; 32])).expect("other");
+    let changelog = tmp.path().join("CHANGELOG.md");
+    fs::write(&changelog, "# 1.0\n").expect("changelog");
+    let bundle = tmp.path().join("SIMPLE.psykpkg");
+
+    psyk()
+        .arg("package")
+        .arg("tests/data/synthetic/SIMPLE.LIB")
+        .arg("-o")
+        .arg(&bundle)
+        .arg("--changelog")
+        .arg(&changelog)
+        .arg("--sign")
+        .arg(&secret)
+        .assert()
+        .success();
+
+    let out = tmp.path().join("out");
+    psyk()
+        .arg("unpack")
+        .arg(&bundle)
+        .arg("-o")
+        .arg(&out)
+        .arg("--verify-key")
+        .arg(&public)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "the manifest and signature are valid",
+        ));
+    assert_eq!(
+        fs::read("tests/data/synthetic/SIMPLE.LIB").expect("lib"),
+        fs::read(out.join("SIMPLE.LIB")).expect("unpacked")
+    );
+    assert_eq!(
+        "# 1.0\n",
+        fs::read_to_string(out.join("CHANGELOG.md")).expect("changelog")
+    );
+    assert!(out.join("MANIFEST").exists());
+    assert!(out.join("MANIFEST.sig").exists());
+
+    // the wrong key extracts nothing without --force
+    let refused = tmp.path().join("refused");
+    psyk()
+        .arg("unpack")
+        .arg(&bundle)
+        .arg("-o")
+        .arg(&refused)
+        .arg("--verify-key")
+        .arg(&other)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the signature doesn't match the key",
+        ));
+    assert!(!refused.exists());
+
+    psyk()
+        .arg("unpack")
+        .arg(&bundle)
+        .arg("-o")
+        .arg(&refused)
+        .arg("--verify-key")
+        .arg(&other)
+        .arg("--force")
+        .assert()
+        .success();
+    assert!(refused.join("SIMPLE.LIB").exists());
+}
+
 #[test]
 fn test_psyk_create_missing_args() {
     psyk()
@@ -160,6 +381,58 @@ fn test_psyk_create_file_not_found() {
         .stderr(predicate::str::contains("File not found"));
 }
 
+#[test]
+fn test_psyk_create_duplicate_exports() {
+    let tmp = TempDir::new().expect("tempdir");
+    let code = [0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00];
+    code_obj(&tmp.path().join("EXIT.OBJ"), "exit", &code);
+    code_obj(&tmp.path().join("MYEXIT.OBJ"), "exit", &code);
+    code_obj(&tmp.path().join("OTHER.OBJ"), "other", &code);
+    let lib = tmp.path().join("LIBX.LIB");
+
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .arg(tmp.path().join("EXIT.OBJ"))
+        .arg(tmp.path().join("MYEXIT.OBJ"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "exit is exported by both EXIT and MYEXIT",
+        ))
+        .stderr(predicate::str::contains("--force"));
+    assert!(!lib.exists());
+
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .arg(tmp.path().join("EXIT.OBJ"))
+        .arg(tmp.path().join("OTHER.OBJ"))
+        .assert()
+        .success();
+    psyk()
+        .arg("add")
+        .arg(&lib)
+        .arg(tmp.path().join("MYEXIT.OBJ"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "exit is exported by both EXIT and MYEXIT",
+        ));
+    psyk()
+        .args(["add", "--force"])
+        .arg(&lib)
+        .arg(tmp.path().join("MYEXIT.OBJ"))
+        .assert()
+        .success();
+    psyk()
+        .arg("list")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("MYEXIT"));
+}
+
 #[test]
 fn test_psyk_deletem_issing_args() {
     psyk()
@@ -180,6 +453,89 @@ fn test_psyk_delete_file_not_found() {
         .stderr(predicate::str::contains("Error"));
 }
 
+#[test]
+fn test_psyk_create_timestamp() {
+    let tmp = TempDir::new().expect("tempdir");
+    let create = |lib: &str| {
+        let mut cmd = psyk();
+        cmd.arg("create").arg(tmp.path().join(lib)).args([
+            "tests/data/synthetic/MAIN.OBJ",
+            "tests/data/synthetic/HELPER.OBJ",
+        ]);
+        cmd
+    };
+
+    create("A.LIB")
+        .env("SOURCE_DATE_EPOCH", "832176727")
+        .assert()
+        .success();
+    create("B.LIB")
+        .args(["--timestamp", "1996-05-15T16:12:06Z"])
+        .assert()
+        .success();
+    // the option wins
+    create("C.LIB")
+        .env("SOURCE_DATE_EPOCH", "0")
+        .args(["--timestamp", "832176726"])
+        .assert()
+        .success();
+    let a = std::fs::read(tmp.path().join("A.LIB")).expect("A");
+    assert_eq!(a, std::fs::read(tmp.path().join("B.LIB")).expect("B"));
+    assert_eq!(a, std::fs::read(tmp.path().join("C.LIB")).expect("C"));
+    psyk()
+        .arg("list")
+        .arg(tmp.path().join("A.LIB"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("MAIN     15-05-96 16:12:06"));
+
+    create("D.LIB")
+        .env("SOURCE_DATE_EPOCH", "0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("from 1980 through 2107"));
+    create("E.LIB")
+        .args(["--timestamp", "soon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ISO 8601"));
+}
+
+#[test]
+fn test_psyk_rename() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lib = tmp.path().join("SIMPLE.LIB");
+    std::fs::copy("tests/data/synthetic/SIMPLE.LIB", &lib).expect("copy");
+
+    psyk()
+        .args(["rename"])
+        .arg(&lib)
+        .arg("main")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage:"));
+    psyk()
+        .args(["rename"])
+        .arg(&lib)
+        .args(["main", "helper"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already named HELPER"));
+    psyk()
+        .args(["rename"])
+        .arg(&lib)
+        .args(["main", "start"])
+        .assert()
+        .success();
+    psyk()
+        .arg("list")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("START "))
+        .stdout(predicate::str::contains("MAIN ").not());
+}
+
 #[test]
 fn test_psyk_update_missing_args() {
     psyk()
@@ -227,3 +583,1405 @@ fn test_psyk_extract_file_not_found() {
         .failure()
         .stderr(predicate::str::contains("Error"));
 }
+
+/// Writes a LIB with one zeroed, one 2079, and several plausible timestamps.
+fn implausible_timestamps_lib(dir: &Path) -> PathBuf {
+    let module = |name: &str, created: SystemTime| {
+        Module::new(
+            OBJ::new(vec![Section::NOP]),
+            ModuleMetadata::new(name.into(), created, 4, vec![]).expect("metadata"),
+        )
+    };
+    let year = |y: i32| {
+        let dt = NaiveDate::from_ymd_opt(y, 6, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        SystemTime::from(Local.from_local_datetime(&dt).unwrap())
+    };
+    let lib = LIB::new(vec![
+        module("ZERO", year(1995)),
+        module("A", year(1995)),
+        module("B", year(1996)),
+        module("FUTURE", year(2079)),
+        module("C", year(1997)),
+    ]);
+
+    let path = dir.join("TIMES.LIB");
+    let mut writer = Cursor::new(Vec::new());
+    lib.write(&mut writer).unwrap();
+    let mut bytes = writer.into_inner();
+    // zero the timestamp of the first module
+    bytes[12..16].fill(0);
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn test_psyk_verify_timestamps() {
+    let dir = TempDir::new().unwrap();
+    let lib = implausible_timestamps_lib(dir.path());
+
+    psyk()
+        .arg("verify")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "warning: ZERO: timestamp 0x00000000 does not decode to a valid date",
+        ))
+        .stdout(predicate::str::contains("warning: FUTURE: timestamp"));
+
+    psyk()
+        .arg("verify")
+        .arg("--strict-timestamps")
+        .arg(&lib)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("error: FUTURE: timestamp"))
+        .stderr(predicate::str::contains("3 error(s)"));
+
+    psyk()
+        .arg("verify")
+        .arg("--timestamp-range")
+        .arg("1990-01-01")
+        .arg(&lib)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("YYYY-MM-DD..YYYY-MM-DD"));
+
+    psyk()
+        .arg("stat")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Modules:               5"))
+        .stdout(predicate::str::contains("Suspicious timestamps: 3"));
+}
+
+/// An OBJ declaring `_stacksize` and `_ramsize` as 4 byte XBSS symbols in
+/// `.sbss`, and `buffer` in `.bss` after a single byte `flag`.
+fn xbss_obj(dir: &Path) -> PathBuf {
+    let mut bytes = b"LNK\x02".to_vec();
+    for (section, name) in [(1u16, ".sbss"), (2, ".bss")] {
+        bytes.push(16);
+        bytes.extend(section.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes.push(8);
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+    }
+    for (number, section, size, name) in [
+        (3u16, 1u16, 4u32, "_stacksize"),
+        (4, 1, 4, "_ramsize"),
+        (5, 2, 1, "flag"),
+        (6, 2, 64, "buffer"),
+    ] {
+        bytes.push(48);
+        bytes.extend(number.to_le_bytes());
+        bytes.extend(section.to_le_bytes());
+        bytes.extend(size.to_le_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+    }
+    bytes.push(0);
+
+    let path = dir.join("XBSS.OBJ");
+    fs::write(&path, bytes).expect("write");
+    path
+}
+
+#[test]
+fn test_psyk_ldscript_bss() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj = xbss_obj(tmp.path());
+    let fragment = tmp.path().join("bss.ld");
+
+    psyk()
+        .arg("ldscript-bss")
+        .arg("--provide")
+        .arg("--compat-symbols")
+        .arg(&obj)
+        .arg("-o")
+        .arg(&fragment)
+        .assert()
+        .success();
+
+    let script = fs::read_to_string(&fragment).expect("fragment");
+    assert!(script.contains(
+        "\
+        PROVIDE(_stacksize = .);\n\
+        . += 4;\n\
+        PROVIDE(_ramsize = .);\n\
+        . += 4;\n"
+    ));
+    assert!(script.contains(". += 1;\n. = ALIGN(8);\nPROVIDE(buffer = .);\n"));
+    assert!(script.contains("PROVIDE(__bsslen = . - __bss);"));
+
+    // optionally make sure GNU ld accepts the fragment
+    let main = tmp.path().join("main.ld");
+    fs::write(
+        &main,
+        format!(
+            "SECTIONS {{ .bss (NOLOAD) : {{ INCLUDE {} }} }}\n",
+            fragment.display()
+        ),
+    )
+    .expect("script");
+    let Ok(output) = Command::new("ld")
+        .arg("--verbose")
+        .args(["-b", "binary", "/dev/null", "-T"])
+        .arg(&main)
+        .arg("-o")
+        .arg(tmp.path().join("out"))
+        .output()
+    else {
+        eprintln!("ld not available");
+        return;
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_psyk_export_asm() {
+    psyk()
+        .arg("export-asm")
+        .arg("tests/data/synthetic/MAIN.OBJ")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\txdef\tmain\n\n\tsection\t.text\n\talign\t8\nmain:\n\tjr          $ra\n",
+        ));
+
+    psyk()
+        .arg("export-asm")
+        .arg("tests/data/synthetic/SIMPLE.LIB")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a LIB"));
+}
+
+/// Writes an OBJ exporting `name` from a single code section.
+fn code_obj(path: &Path, name: &str, code: &[u8]) {
+    let mut bytes = b"LNK\x02".to_vec();
+    bytes.push(2);
+    bytes.extend((code.len() as u16).to_le_bytes());
+    bytes.extend(code);
+    bytes.push(12);
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.push(name.len() as u8);
+    bytes.extend(name.as_bytes());
+    bytes.push(0);
+    fs::create_dir_all(path.parent().expect("parent")).expect("dir");
+    fs::write(path, bytes).expect("write");
+}
+
+/// The name and exports of each module of the LIB at `path`.
+fn module_exports(path: &Path) -> Vec<(String, Vec<String>)> {
+    psyk::io::read_lib(path)
+        .expect("lib")
+        .modules()
+        .iter()
+        .map(|m| (m.name(), m.exports()))
+        .collect()
+}
+
+#[test]
+fn test_psyk_create_name_policy() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let lower = tmp.path().join("foo.obj");
+    let upper = tmp.path().join("other").join("FOO.OBJ");
+    code_obj(&lower, "lower", &[0; 4]);
+    code_obj(&upper, "upper", &[0; 4]);
+    let lib = tmp.path().join("FOO.LIB");
+    let create = |args: &[&str]| {
+        let mut command = psyk();
+        command
+            .arg("create")
+            .args(args)
+            .arg(&lib)
+            .arg(&lower)
+            .arg(&upper);
+        command.assert()
+    };
+
+    // both are named FOO, which is an error even with --force
+    create(&["--force"])
+        .failure()
+        .stderr(predicate::str::contains("Duplicate module name FOO"));
+    assert!(!lib.exists());
+    // and with their case kept, as names differ only in case
+    create(&["--name-case", "preserve"])
+        .failure()
+        .stderr(predicate::str::contains("Duplicate module name FOO"));
+
+    create(&["--name-case", "preserve", "--on-collision", "suffix"]).success();
+    assert_eq!(
+        vec![
+            ("foo".to_string(), vec!["lower".to_string()]),
+            ("FOO1".to_string(), vec!["upper".to_string()]),
+        ],
+        module_exports(&lib)
+    );
+
+    create(&["--on-collision", "first-wins"]).success();
+    assert_eq!(
+        vec![("FOO".to_string(), vec!["lower".to_string()])],
+        module_exports(&lib)
+    );
+}
+
+#[test]
+fn test_psyk_merge() {
+    let tmp = TempDir::new().expect("tempdir");
+    let code = [0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00];
+    code_obj(&tmp.path().join("HELPER.OBJ"), "helper_v2", &code);
+    code_obj(&tmp.path().join("EXTRA.OBJ"), "extra", &code);
+    code_obj(&tmp.path().join("OTHER.OBJ"), "main", &code);
+    let patches = tmp.path().join("PATCHES.LIB");
+    psyk()
+        .arg("create")
+        .arg(&patches)
+        .arg(tmp.path().join("HELPER.OBJ"))
+        .arg(tmp.path().join("EXTRA.OBJ"))
+        .assert()
+        .success();
+    let simple = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let merged = tmp.path().join("MERGED.LIB");
+    let merge = |strategy: &str| {
+        let mut cmd = psyk();
+        cmd.arg("merge")
+            .args([&merged, simple, &patches])
+            .args(["--strategy", strategy]);
+        cmd
+    };
+
+    merge("error-on-conflict")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "modules are in both libraries: HELPER",
+        ));
+    assert!(!merged.exists());
+
+    merge("keep-first").assert().success();
+    let exports = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    assert_eq!(
+        vec![
+            ("MAIN".into(), exports(&["main"])),
+            ("HELPER".into(), exports(&["helper"])),
+            ("EXTRA".into(), exports(&["extra"])),
+        ],
+        module_exports(&merged)
+    );
+
+    merge("keep-second").assert().success();
+    assert_eq!(
+        vec![
+            ("MAIN".into(), exports(&["main"])),
+            ("HELPER".into(), exports(&["helper_v2"])),
+            ("EXTRA".into(), exports(&["extra"])),
+        ],
+        module_exports(&merged)
+    );
+
+    let other = tmp.path().join("OTHER.LIB");
+    psyk()
+        .arg("create")
+        .arg(&other)
+        .arg(tmp.path().join("OTHER.OBJ"))
+        .assert()
+        .success();
+    psyk()
+        .arg("merge")
+        .args([&merged, simple, &other])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "main is exported by both MAIN and OTHER",
+        ));
+}
+
+fn stable_id(listing: &str, module: &str) -> String {
+    listing
+        .lines()
+        .find(|l| l.starts_with(module))
+        .and_then(|l| l.split("[id:").nth(1))
+        .and_then(|id| id.strip_suffix(']'))
+        .expect("stable id")
+        .to_string()
+}
+
+#[test]
+fn test_psyk_list_stable_ids() {
+    let tmp = TempDir::new().expect("tempdir");
+    let code = [0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00];
+
+    // the same module in two SDK mirrors, at different archive positions
+    let mut listings = Vec::new();
+    for (mirror, other_first) in [("mirror-a/psyq/lib", false), ("b/SDK/LIB", true)] {
+        let dir = tmp.path().join(mirror);
+        code_obj(&dir.join("RET.OBJ"), "ret", &code);
+        code_obj(&dir.join("OTHER.OBJ"), "other", &[0; 4]);
+        let lib = dir.join("LIBX.LIB");
+        let mut create = psyk();
+        create.arg("create").arg(&lib);
+        if other_first {
+            create.arg(dir.join("OTHER.OBJ"));
+        }
+        create.arg(dir.join("RET.OBJ")).assert().success();
+
+        let output = psyk()
+            .args(["list", "--stable-ids"])
+            .arg(&lib)
+            .output()
+            .expect("list");
+        listings.push(String::from_utf8(output.stdout).expect("utf-8"));
+    }
+    let id = stable_id(&listings[0], "RET");
+    assert_eq!(26, id.len());
+    assert_eq!(id, stable_id(&listings[1], "RET"));
+    assert_ne!(id, stable_id(&listings[1], "OTHER"));
+
+    // a one byte change to the code changes the id
+    let dir = tmp.path().join("changed");
+    let mut changed = code;
+    changed[4] = 1;
+    code_obj(&dir.join("RET.OBJ"), "ret", &changed);
+    let lib = dir.join("LIBX.LIB");
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .arg(dir.join("RET.OBJ"))
+        .assert()
+        .success();
+    let output = psyk()
+        .args(["list", "--stable-ids"])
+        .arg(&lib)
+        .output()
+        .expect("list");
+    let listing = String::from_utf8(output.stdout).expect("utf-8");
+    assert_ne!(id, stable_id(&listing, "RET"));
+}
+
+#[test]
+fn test_psyk_verbose() {
+    let dir = TempDir::new().expect("tempdir");
+    // a ProcedureCall, whose format is untested, then the end
+    let obj = dir.path().join("CALL.OBJ");
+    fs::write(&obj, b"LNK\x02\x44\x01\x02\x00\x00").expect("write");
+
+    psyk()
+        .arg("list")
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("68 : "))
+        .stderr("");
+
+    psyk()
+        .arg("--verbose")
+        .arg("list")
+        .arg(&obj)
+        .assert()
+        .success()
+        .stderr(format!(
+            "warning: {}: section 68 at 0x4 is read in an untested format\n",
+            obj.display()
+        ));
+}
+
+#[test]
+fn test_psyk_list_offsets() {
+    psyk()
+        .args(["list", "--offsets", "tests/data/synthetic/MAIN.OBJ"])
+        .assert()
+        .success()
+        .stdout(
+            "Header : LNK version 2\n\
+            00000004: 46 : Processor type 7\n\
+            00000006: 16 : Section symbol number 1 '.text' in group 0 alignment 8\n\
+            00000012: 6 : Switch to section 1\n\
+            00000015: 2 : Code 8 bytes\n\
+            00000020: 12 : XDEF symbol number 1 'main' at offset 0 in section 1\n\
+            0000002e: 0 : End of file\n\n",
+        );
+
+    // sections of a LIB's modules are at their offsets in the LIB
+    let output = psyk()
+        .args(["list", "-r", "--offsets", "tests/data/synthetic/SIMPLE.LIB"])
+        .output()
+        .expect("list");
+    let listing = String::from_utf8(output.stdout).expect("utf-8");
+    assert!(
+        listing.contains("\n00000022:     46 : Processor type 7\n"),
+        "{listing}"
+    );
+    assert!(
+        listing.contains("\n0000006d:     46 : Processor type 7\n"),
+        "{listing}"
+    );
+}
+
+#[test]
+fn test_psyk_graph() {
+    psyk()
+        .args(["graph", "tests/data/synthetic/SIMPLE.LIB"])
+        .assert()
+        .success()
+        .stdout("MAIN\nHELPER\n");
+    psyk()
+        .args([
+            "graph",
+            "--format",
+            "dot",
+            "tests/data/synthetic/SIMPLE.LIB",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "digraph dependencies {\n    m0 [label=\"MAIN\"];\n    m1 [label=\"HELPER\"];\n}\n",
+        );
+    psyk()
+        .args(["graph", "tests/data/synthetic/MAIN.OBJ"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a LIB"));
+}
+
+#[test]
+fn test_psyk_sizes() {
+    psyk()
+        .args(["sizes", "tests/data/synthetic/SIMPLE.LIB"])
+        .assert()
+        .success()
+        .stdout(
+            "   .text    .data     .bss    .sbss    total module\n\
+            \x20      8        0        0        0        8 MAIN\n\
+            \x20     12        0        0        0       12 HELPER\n\
+            \x20     20        0        0        0       20 (TOTALS)\n",
+        );
+    psyk()
+        .args(["sizes", "tests/data/synthetic/MAIN.OBJ"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "       8        0        0        0        8 MAIN.OBJ\n",
+        ));
+    psyk()
+        .args(["sizes", "--human", "tests/data/synthetic/SIMPLE.LIB"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "    20 B      0 B      0 B      0 B     20 B (TOTALS)\n",
+        ));
+}
+
+#[test]
+fn test_psyk_stat_styles() {
+    let tmp = TempDir::new().expect("tempdir");
+    code_obj(&tmp.path().join("BIG.OBJ"), "big", &[0; 0xc000]);
+    code_obj(&tmp.path().join("SMALL.OBJ"), "small", &[0; 0x1800]);
+    let lib = tmp.path().join("SIZES.LIB");
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .arg(tmp.path().join("BIG.OBJ"))
+        .arg(tmp.path().join("SMALL.OBJ"))
+        .assert()
+        .success();
+
+    // the modules are dated now, which is outside of the plausible range
+    let raw = "\
+        Modules:               2\n\
+        Exports:               2\n\
+        Sections:              6\n\
+        Code bytes:            55296\n\
+        Suspicious timestamps: 2\n";
+    psyk().arg("stat").arg(&lib).assert().success().stdout(raw);
+    psyk()
+        .args(["stat", "--bytes", "raw"])
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(raw);
+
+    psyk()
+        .args(["stat", "--bytes", "iec"])
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(
+            "\
+            Modules:               2\n\
+            Exports:               2\n\
+            Sections:              6\n\
+            Code bytes:            54.0 KiB\n\
+            Suspicious timestamps: 2\n",
+        );
+
+    psyk()
+        .args(["stat", "--bytes", "grouped", "--locale-sep", " "])
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Code bytes:            55 296\n"));
+
+    psyk()
+        .args(["stat", "--human"])
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Code bytes:            54.0 KiB\n",
+        ));
+
+    psyk()
+        .args(["stat", "--locale-sep", "::"])
+        .arg(&lib)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("single character"));
+}
+
+#[test]
+fn test_psyk_check_relocs() {
+    let tmp = TempDir::new().expect("tempdir");
+    // lui $v0, 0x8001 ; lw $v0, 0x1234($v0) ; jal 0x1000 ; nop
+    let code = [0x3c02_8001u32, 0x8c42_1234, 0x0c00_0400, 0]
+        .iter()
+        .flat_map(|w| w.to_le_bytes())
+        .collect::<Vec<u8>>();
+
+    // the patches for the jal and both halves of the address are missing
+    let obj = tmp.path().join("BAD.OBJ");
+    code_obj(&obj, "bad", &code);
+    psyk()
+        .arg("check-relocs")
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "high: section 0 offset 0x0000: 3c028001 lui $v0, 0x8001",
+        ))
+        .stdout(predicate::str::contains("jump target 0x1000 is outside"));
+    psyk()
+        .args(["check-relocs", "--deny-high"])
+        .arg(&obj)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2 high severity suspect(s)"));
+
+    let obj = tmp.path().join("GOOD.OBJ");
+    code_obj(&obj, "good", &[0; 8]);
+    psyk()
+        .args(["check-relocs", "--deny-high"])
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(": ok\n"));
+}
+
+#[test]
+fn test_psyk_chronology() {
+    let tmp = TempDir::new().expect("tempdir");
+    let sdk = tmp.path().join("sdk");
+    for (lib, modules) in [
+        ("LIB/A.LIB", ["ONE", "TWO"]),
+        ("lib/sub/b.lib", ["THREE", "FOUR"]),
+    ] {
+        let objs = modules
+            .iter()
+            .map(|module| {
+                let obj = tmp.path().join("obj").join(format!("{module}.OBJ"));
+                code_obj(&obj, &module.to_lowercase(), &[0; 4]);
+                obj
+            })
+            .collect::<Vec<_>>();
+        let lib = sdk.join(lib);
+        fs::create_dir_all(lib.parent().expect("parent")).expect("dir");
+        psyk()
+            .arg("create")
+            .arg(&lib)
+            .args(&objs)
+            .assert()
+            .success();
+    }
+    // not a LIB, and skipped
+    fs::write(sdk.join("LIB/README.TXT"), "").expect("write");
+
+    let output = psyk()
+        .arg("chronology")
+        .arg(&sdk)
+        .output()
+        .expect("chronology");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("utf8");
+    assert!(stdout.starts_with("Library"), "{stdout}");
+    assert!(stdout.contains("LIB/A.LIB"), "{stdout}");
+    assert!(stdout.contains("lib/sub/b.lib"), "{stdout}");
+
+    psyk()
+        .args(["chronology", "--by", "module", "-n", "3"])
+        .arg(&sdk)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Most recently built modules:"))
+        .stdout(predicate::function(|s: &str| {
+            s.lines()
+                .filter(|l| l.contains(".lib") || l.contains(".LIB"))
+                .count()
+                == 3
+        }))
+        .stdout(predicate::str::contains("4 modules in 2 libraries"));
+}
+
+#[test]
+fn test_psyk_chronology_styles() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::copy(
+        "tests/data/synthetic/SIMPLE.LIB",
+        tmp.path().join("SIMPLE.LIB"),
+    )
+    .expect("copy");
+    psyk()
+        .args(["chronology", "--human"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "SIMPLE.LIB                             2  \
+            1996-05-15 16:09:38  1996-05-15 16:09:38  1996-05-15 16:09:38\n",
+        ));
+    psyk()
+        .args(["chronology", "--locale-sep", "::"])
+        .arg(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("single character"));
+}
+
+#[test]
+fn test_psyk_recover() {
+    let tmp = TempDir::new().expect("tempdir");
+    let objs = ["ONE", "TWO", "THREE"]
+        .iter()
+        .map(|module| {
+            let obj = tmp.path().join(format!("{module}.OBJ"));
+            code_obj(&obj, &module.to_lowercase(), &[0; 8]);
+            obj
+        })
+        .collect::<Vec<_>>();
+    let lib = tmp.path().join("GOOD.LIB");
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .args(&objs)
+        .assert()
+        .success();
+
+    // cut THREE short
+    let broken = tmp.path().join("BROKEN.LIB");
+    let bytes = fs::read(&lib).expect("read");
+    fs::write(&broken, &bytes[..bytes.len() - 10]).expect("write");
+    let recovered = tmp.path().join("RECOVERED.LIB");
+    psyk()
+        .arg("recover")
+        .arg(&broken)
+        .arg("-o")
+        .arg(&recovered)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2 module(s) recovered, 1 damaged region(s)",
+        ))
+        .stdout(predicate::str::contains("byte OBJ for module THREE"));
+    psyk().arg("verify").arg(&recovered).assert().success();
+
+    let report = tmp.path().join("report.txt");
+    psyk()
+        .arg("recover")
+        .arg(&lib)
+        .args(["-o".as_ref(), recovered.as_os_str(), "--report".as_ref()])
+        .arg(&report)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    assert!(fs::read_to_string(&report)
+        .expect("report")
+        .contains("3 module(s) recovered, 0 damaged region(s)"));
+
+    fs::write(&broken, b"LIB\x01garbage").expect("write");
+    psyk()
+        .arg("recover")
+        .arg(&broken)
+        .arg("-o")
+        .arg(&recovered)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no modules could be recovered"));
+}
+
+#[test]
+fn test_psyk_analyze() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj = tmp.path().join("STR.OBJ");
+    code_obj(&obj, "str", b"\x01hello, world\0\0\0");
+    psyk()
+        .args(["analyze", "--plugin", "strings"])
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout("note: strings: section 0 0x0001: \"hello, world\"\n");
+
+    let obj = tmp.path().join("NONE.OBJ");
+    code_obj(&obj, "none", &[0; 8]);
+    psyk()
+        .arg("analyze")
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(": no findings\n"));
+    psyk()
+        .args(["analyze", "--plugin", "missing"])
+        .arg(&obj)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown plugin: missing (available: strings)",
+        ));
+}
+
+#[test]
+fn test_psyk_verify_sizes() {
+    let tmp = TempDir::new().expect("tempdir");
+    let objs = ["A", "B"]
+        .iter()
+        .map(|module| {
+            let obj = tmp.path().join(format!("{module}.OBJ"));
+            code_obj(&obj, &module.to_lowercase(), &[0; 4]);
+            obj
+        })
+        .collect::<Vec<_>>();
+    let lib = tmp.path().join("PADDED.LIB");
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .args(&objs)
+        .assert()
+        .success();
+
+    // pad the declared size of A, which follows the LIB header
+    let mut bytes = fs::read(&lib).expect("read");
+    let size = u32::from_le_bytes(bytes[20..24].try_into().expect("size"));
+    bytes[20..24].copy_from_slice(&(size + 4).to_le_bytes());
+    fs::write(&lib, bytes).expect("write");
+
+    let expected = format!(
+        "A: metadata declares a size of {} bytes but the module is {size} bytes",
+        size + 4
+    );
+    psyk()
+        .arg("verify")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("warning: {expected}")));
+    psyk()
+        .args(["verify", "--strict-sizes"])
+        .arg(&lib)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!("error: {expected}")));
+}
+
+#[test]
+fn test_psyk_poke() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj = tmp.path().join("MAIN.OBJ");
+    // jr ra; addu v0, zero, zero
+    code_obj(
+        &obj,
+        "main",
+        &[0x08, 0x00, 0xe0, 0x03, 0x21, 0x10, 0x00, 0x00],
+    );
+    // switch to section 1 before the code, where `main` is defined
+    let mut bytes = fs::read(&obj).expect("read");
+    bytes.splice(4..4, [6, 1, 0]);
+    fs::write(&obj, bytes).expect("write");
+    let lib = tmp.path().join("MAIN.LIB");
+    psyk().arg("create").arg(&lib).arg(&obj).assert().success();
+    let original = fs::read(&lib).expect("read");
+
+    assert_cmd::Command::from_std(psyk())
+        .arg("poke")
+        .arg(&lib)
+        .args(["MAIN", "main+0x4", "--bytes", "00 00 00 00"])
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("- 0x0004  00001021"))
+        .stdout(predicate::str::contains("+ 0x0004  00000000  nop"))
+        .stderr(predicate::str::contains("not written"));
+    assert_eq!(original, fs::read(&lib).expect("read"));
+
+    let patched = tmp.path().join("PATCHED.LIB");
+    psyk()
+        .arg("poke")
+        .arg(&lib)
+        .args(["MAIN", "main+4", "--bytes", "00000000", "--yes", "-o"])
+        .arg(&patched)
+        .assert()
+        .success();
+    let patched = fs::read(&patched).expect("read");
+    assert_eq!(original.len(), patched.len());
+    let changed = original
+        .iter()
+        .zip(&patched)
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(2, changed.len());
+    assert_eq!(1, changed[1] - changed[0]);
+
+    psyk()
+        .arg("poke")
+        .arg(&lib)
+        .args(["MAIN", "main+6", "--bytes", "00 00 00 00", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("extend past the end of main"));
+}
+
+#[test]
+fn test_psyk_index_and_find() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lib = tmp.path().join("SIMPLE.LIB");
+    fs::copy("tests/data/synthetic/SIMPLE.LIB", &lib).expect("copy");
+    let idx = tmp.path().join("psyk.idx");
+
+    psyk()
+        .arg("index")
+        .arg(tmp.path())
+        .arg("-o")
+        .arg(&idx)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 symbol(s) in 1 file(s)"));
+    psyk()
+        .args(["find", "helper", "--index"])
+        .arg(&idx)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SIMPLE.LIB: HELPER (export)"))
+        .stderr(predicate::str::is_empty());
+
+    fs::File::options()
+        .write(true)
+        .open(&lib)
+        .and_then(|f| f.set_modified(SystemTime::UNIX_EPOCH))
+        .expect("touch");
+    psyk()
+        .args(["find", "main", "--index"])
+        .arg(&idx)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SIMPLE.LIB: MAIN (export)"))
+        .stderr(predicate::str::contains(
+            "changed since the index was built",
+        ));
+    psyk()
+        .args(["index", "--update", "-o"])
+        .arg(&idx)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("re-read 1 file(s)"));
+
+    psyk()
+        .args(["find", "main"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SIMPLE.LIB: MAIN (export)"));
+}
+
+#[test]
+fn test_psyk_find_ignore_case() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::copy(
+        "tests/data/synthetic/SIMPLE.LIB",
+        tmp.path().join("SIMPLE.LIB"),
+    )
+    .expect("copy");
+
+    psyk()
+        .args(["find", "HELPER"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    psyk()
+        .args(["find", "-i", "HELPER"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SIMPLE.LIB: HELPER (export)"));
+}
+
+#[test]
+fn test_psyk_verify_baseline() {
+    let tmp = TempDir::new().expect("tempdir");
+    let baseline = tmp.path().join(".psyk-verify");
+
+    psyk()
+        .args(["verify", "tests/data/synthetic/SIMPLE.LIB", "--baseline"])
+        .arg(&baseline)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "checked 2 module(s), skipped 0 unchanged",
+        ));
+    psyk()
+        .args(["verify", "tests/data/synthetic/SIMPLE.LIB", "--baseline"])
+        .arg(&baseline)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "checked 0 module(s), skipped 2 unchanged",
+        ));
+    psyk()
+        .args(["verify", "tests/data/synthetic/MAIN.OBJ", "--baseline"])
+        .arg(&baseline)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_psyk_census() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj = tmp.path().join("REPEAT.OBJ");
+    // an untested RepeatByte section
+    let mut bytes = b"LNK\x02".to_vec();
+    bytes.push(62);
+    bytes.extend(4u32.to_le_bytes());
+    bytes.push(0);
+    fs::write(&obj, bytes).expect("write");
+
+    psyk()
+        .args([
+            "census",
+            "tests/data/synthetic/SIMPLE.LIB",
+            "--deny-untested",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"  LNKHeader +2\n").expect("regex"))
+        .stdout(predicate::str::contains("Untested").not());
+    psyk()
+        .args(["census", "--rare-only"])
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^Untested\n  RepeatByte +1\n$").expect("regex"));
+    psyk()
+        .args(["census", "--deny-untested"])
+        .arg(&obj)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "uses untested constructs: RepeatByte",
+        ));
+}
+
+#[test]
+fn test_psyk_closure() {
+    let tmp = TempDir::new().expect("tempdir");
+    psyk()
+        .args(["closure", "tests/data/synthetic/SIMPLE.LIB", "helper", "-o"])
+        .arg(tmp.path().join("separate"))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Modules\n  HELPER\n"));
+    assert!(tmp.path().join("separate/HELPER.OBJ").exists());
+    assert!(!tmp.path().join("separate/MAIN.OBJ").exists());
+
+    psyk()
+        .args([
+            "closure",
+            "tests/data/synthetic/SIMPLE.LIB",
+            "main",
+            "helper",
+            "--combined",
+            "-o",
+        ])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  MAIN\n  HELPER\n"));
+    let obj = psyk::io::read_obj(&tmp.path().join("OVERLAY.OBJ")).expect("read");
+    let mut exports = obj.exports();
+    exports.sort();
+    assert_eq!(vec!["helper", "main"], exports);
+
+    psyk()
+        .args([
+            "closure",
+            "tests/data/synthetic/SIMPLE.LIB",
+            "missing",
+            "-o",
+        ])
+        .arg(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "missing is not defined by any module",
+        ));
+}
+
+#[test]
+fn test_psyk_kernel_symbols() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj = tmp.path().join("PRINTF.OBJ");
+    code_obj(
+        &obj,
+        "printf",
+        &[0x08, 0x00, 0xe0, 0x03, 0x00, 0x00, 0x00, 0x00],
+    );
+
+    psyk()
+        .args(["find", "printf"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "PRINTF.OBJ: PRINTF (definition) [kernel A0:0x3F]",
+        ));
+    psyk()
+        .args(["find", "helper", "tests/data/synthetic"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[kernel").not());
+    psyk()
+        .arg("verify")
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "warning: printf shadows the LIBC wrapper for kernel A0:0x3F",
+        ));
+}
+
+#[test]
+fn test_psyk_stdout_output() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lib = tmp.path().join("SIMPLE.LIB");
+    let objs = [
+        "tests/data/synthetic/MAIN.OBJ",
+        "tests/data/synthetic/HELPER.OBJ",
+    ];
+
+    psyk().arg("create").arg(&lib).args(objs).assert().success();
+    let output = psyk()
+        .args(["create", "-"])
+        .args(objs)
+        .output()
+        .expect("create");
+    assert!(output.status.success());
+    assert_eq!(fs::read(&lib).expect("read"), output.stdout);
+
+    let output = psyk()
+        .args([
+            "extract",
+            "tests/data/synthetic/SIMPLE.LIB",
+            "main",
+            "-o",
+            "-",
+        ])
+        .output()
+        .expect("extract");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read("tests/data/synthetic/MAIN.OBJ").expect("read"),
+        output.stdout
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MAIN.OBJ"));
+
+    psyk()
+        .args(["extract", "tests/data/synthetic/SIMPLE.LIB", "-o", "-"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "writes one module, but 2 were selected",
+        ));
+    psyk()
+        .args(["extract", "tests/data/synthetic/SIMPLE.LIB", "HELPER", "-o"])
+        .arg(tmp.path().join("objs"))
+        .assert()
+        .success();
+    assert!(tmp.path().join("objs/HELPER.OBJ").exists());
+    assert!(!tmp.path().join("objs/MAIN.OBJ").exists());
+}
+
+#[test]
+fn test_psyk_names_overlay() {
+    let tmp = TempDir::new().expect("tempdir");
+    let names = tmp.path().join("names.txt");
+    fs::write(
+        &names,
+        "# SIMPLE.LIB\n\
+         SIMPLE:MAIN:main+4 delay_slot\n\
+         *:MAIN:.text+0 entry   # main is already here\n\
+         symbol helper=assist\n",
+    )
+    .expect("write");
+    let lib = "tests/data/synthetic/SIMPLE.LIB";
+
+    psyk()
+        .args(["list", "-d", "-r", "--names"])
+        .arg(&names)
+        .arg(lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("    ; ~delay_slot\n"))
+        .stdout(predicate::str::contains("'~assist'"))
+        .stdout(predicate::str::contains("entry").not())
+        .stderr(predicate::str::contains(
+            "warning: line 3: MAIN already names .text+0x0 main, ignoring entry",
+        ));
+    psyk()
+        .args(["list", "-d", "-r", "--no-mark-overlay", "--names"])
+        .arg(&names)
+        .arg(lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("    ; delay_slot\n"))
+        .stdout(predicate::str::contains("'assist'"));
+    psyk()
+        .args(["functions", "--names"])
+        .arg(&names)
+        .arg(lib)
+        .assert()
+        .success()
+        .stdout(
+            "MAIN\n    1:00000000 main\n    1:00000004 ~delay_slot\nHELPER\n    1:00000000 ~assist\n",
+        );
+
+    // names never change the archive
+    psyk()
+        .args(["list", "-d", "-r", lib])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~").not());
+
+    fs::write(&names, "*:MAIN:main+4 ok\n*:MAIN:main+4\nsymbol x\n").expect("write");
+    psyk()
+        .args(["functions", "--names"])
+        .arg(&names)
+        .arg(lib)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("names.txt: line 2: expected"))
+        .stderr(predicate::str::contains(
+            "line 3: expected `symbol OLD=NEW`",
+        ));
+}
+
+#[test]
+fn test_psyk_update_preserve_timestamps() {
+    let tmp = TempDir::new().expect("tempdir");
+    let objs = ["A", "B", "C"].map(|name| {
+        let path = tmp.path().join(format!("{name}.OBJ"));
+        code_obj(&path, &name.to_lowercase(), &[0; 4]);
+        path
+    });
+    let lib = tmp.path().join("LIBX.LIB");
+    psyk()
+        .arg("create")
+        .arg(&lib)
+        .args(&objs)
+        .assert()
+        .success();
+
+    // give every module an old timestamp
+    let created = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(820_000_000);
+    let modules = psyk::io::read_lib(&lib)
+        .expect("lib")
+        .modules()
+        .iter()
+        .map(|m| m.clone().with_created(created))
+        .collect::<Vec<_>>();
+    fs::write(&lib, LIB::new(modules).to_bytes().expect("bytes")).expect("write");
+    let bytes = |module: &Module| {
+        let mut writer = Cursor::new(Vec::new());
+        module.write_le(&mut writer).expect("write");
+        writer.into_inner()
+    };
+    let before = psyk::io::read_lib(&lib).expect("lib");
+
+    let b = tmp.path().join("b.obj");
+    code_obj(&b, "b2", &[1; 8]);
+    psyk()
+        .args(["update", "--preserve-timestamps"])
+        .arg(&lib)
+        .arg(&b)
+        .assert()
+        .success();
+    let after = psyk::io::read_lib(&lib).expect("lib");
+    let names = after.modules().iter().map(Module::name).collect::<Vec<_>>();
+    assert_eq!(vec!["A", "B", "C"], names);
+    for i in [0, 2] {
+        assert_eq!(bytes(&before.modules()[i]), bytes(&after.modules()[i]));
+    }
+    assert_eq!(vec!["b2"], after.modules()[1].exports());
+    assert_eq!(Some(created), after.modules()[1].created_at());
+
+    // without the flag the module takes the time of its file
+    code_obj(&b, "b3", &[2; 8]);
+    psyk().arg("update").arg(&lib).arg(&b).assert().success();
+    let after = psyk::io::read_lib(&lib).expect("lib");
+    assert_eq!(vec!["b3"], after.modules()[1].exports());
+    assert_ne!(Some(created), after.modules()[1].created_at());
+}
+
+#[test]
+fn test_psyk_edit_modules() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lib = tmp.path().join("SIMPLE.LIB");
+    fs::copy("tests/data/synthetic/SIMPLE.LIB", &lib).expect("copy");
+    let modules = |lib: &Path| {
+        psyk::io::read_lib(lib)
+            .expect("lib")
+            .modules()
+            .iter()
+            .map(|m| m.name())
+            .collect::<Vec<_>>()
+    };
+
+    // module names match without regard to case
+    let helper = tmp.path().join("helper.obj");
+    code_obj(&helper, "helper2", &[0; 4]);
+    psyk()
+        .arg("update")
+        .arg(&lib)
+        .arg(&helper)
+        .assert()
+        .success();
+    let updated = psyk::io::read_lib(&lib).expect("lib");
+    assert_eq!(vec!["MAIN", "HELPER"], modules(&lib));
+    assert_eq!(vec!["helper2"], updated.modules()[1].exports());
+
+    // an OBJ that isn't in the LIB is an error, not skipped
+    let other = tmp.path().join("other.obj");
+    code_obj(&other, "other", &[0; 4]);
+    psyk()
+        .arg("update")
+        .arg(&lib)
+        .arg(&helper)
+        .arg(&other)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in"))
+        .stderr(predicate::str::contains("other.obj"));
+
+    // as is deleting a module that isn't there, and nothing is deleted
+    psyk()
+        .args(["delete"])
+        .arg(&lib)
+        .args(["main", "missing", "absent"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing, absent"));
+    assert_eq!(vec!["MAIN", "HELPER"], modules(&lib));
+
+    // names are normalized like file names, and --backup keeps the original
+    let original = std::fs::read(&lib).expect("original");
+    psyk()
+        .args(["delete", "--backup"])
+        .arg(&lib)
+        .arg("some/dir/main.obj")
+        .assert()
+        .success();
+    assert_eq!(vec!["HELPER"], modules(&lib));
+    let backup = psyk::io::backup_path(&lib);
+    assert_eq!(original, std::fs::read(&backup).expect("backup"));
+    std::fs::remove_file(&backup).expect("remove");
+
+    // the last module can't be deleted, and the LIB is left alone
+    psyk()
+        .args(["delete"])
+        .arg(&lib)
+        .arg("HELPER")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("would have no modules left"));
+    assert_eq!(vec!["HELPER"], modules(&lib));
+
+    // without --backup, none is kept
+    psyk().arg("add").arg(&lib).arg(&other).assert().success();
+    assert_eq!(vec!["HELPER", "OTHER"], modules(&lib));
+    assert!(!backup.exists());
+}
+
+#[test]
+fn test_psyk_lenient() {
+    let obj = "tests/data/synthetic/UNKNOWN.OBJ";
+    psyk().args(["list", obj]).assert().failure();
+
+    psyk()
+        .args(["--verbose", "--lenient", "list", obj])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "87 : Unknown section, 5 bytes skipped\n",
+        ))
+        .stdout(predicate::str::contains("XDEF"))
+        .stderr(format!(
+            "warning: {obj}: unknown section 87 at 0x15; skipped 6 bytes\n"
+        ));
+}
+
+#[test]
+fn test_psyk_check_compat() {
+    psyk()
+        .args([
+            "check-compat",
+            "tests/data/synthetic/MD68K.OBJ",
+            "tests/data/cmd/psy-q-md/MD68K.TXT",
+        ])
+        .assert()
+        .success()
+        .stdout("tests/data/synthetic/MD68K.OBJ: ok\n");
+
+    let dir = TempDir::new().expect("tempdir");
+    let expected = dir.path().join("SIMPLE.TXT");
+    fs::write(
+        &expected,
+        "\r\nPsyLib version 2.04\r\n\r\n\
+        Module     Date     Time   Externals defined\r\n\r\n\
+        MAIN     15-05-96 16:09:38 main \r\n\
+        HELPER   15-05-96 16:09:38 helper \r\n\
+        \x20                          helper2 \r\n\r\n",
+    )
+    .expect("write");
+    psyk()
+        .arg("check-compat")
+        .arg("tests/data/synthetic/SIMPLE.LIB")
+        .arg(&expected)
+        .assert()
+        .failure()
+        .stdout(format!(
+            "--- {}\n+++ tests/data/synthetic/SIMPLE.LIB\n\
+            @@ -4,1 +4,1 @@\n\
+            -HELPER   15-05-96 16:09:38 helper helper2 \n\
+            +HELPER   15-05-96 16:09:38 helper \n",
+            expected.display()
+        ))
+        .stderr(predicate::str::contains("1 line(s) differ"));
+}
@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
+use std::path::Path;
 use std::process::Command;
 
 use assert_cmd::cargo;
@@ -161,3 +162,192 @@ fn test_psylib_extract_bad_subcommand() {
         .stderr(predicate::str::contains("Invalid option: /?"))
         .stderr(predicate::str::contains("Usage"));
 }
+
+/// A temp dir with copies of the synthetic OBJs.
+fn objs_dir() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().expect("tempdir");
+    for name in ["MAIN.OBJ", "HELPER.OBJ", "MD68K.OBJ"] {
+        std::fs::copy(
+            Path::new("tests/data/synthetic").join(name),
+            dir.path().join(name),
+        )
+        .expect("copy");
+    }
+    dir
+}
+
+fn module_names(lib: &Path) -> Vec<String> {
+    psyk::io::read_lib(lib)
+        .expect("lib")
+        .modules()
+        .iter()
+        .map(|m| m.name())
+        .collect()
+}
+
+#[test]
+fn test_psylib_create_add_delete() {
+    let dir = objs_dir();
+    let lib = dir.path().join("TEST.LIB");
+
+    // wildcards are expanded in order when the shell doesn't
+    psylib()
+        .current_dir(dir.path())
+        .args(["/c", "TEST.LIB", "M?IN.OBJ"])
+        .assert()
+        .success();
+    assert_eq!(vec!["MAIN"], module_names(&lib));
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/a", "TEST.LIB", "HELPER.OBJ", "MD*.OBJ"])
+        .assert()
+        .success();
+    assert_eq!(vec!["MAIN", "HELPER", "MD68K"], module_names(&lib));
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/d", "TEST.LIB", "main", "MD68K"])
+        .assert()
+        .success();
+    assert_eq!(vec!["HELPER"], module_names(&lib));
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/a", "TEST.LIB", "*.LNK"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No files match *.LNK"));
+}
+
+#[test]
+fn test_psylib_list_filter() {
+    let dir = objs_dir();
+    let lib = dir.path().join("TEST.LIB");
+    psylib()
+        .current_dir(dir.path())
+        .args(["/c", "TEST.LIB", "*.obj"])
+        .assert()
+        .success();
+    assert_eq!(vec!["HELPER", "MAIN", "MD68K"], module_names(&lib));
+
+    psylib()
+        .arg("/l")
+        .arg(&lib)
+        .arg("m?in")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("MAIN "))
+        .stdout(predicate::str::contains("HELPER").not())
+        .stdout(predicate::str::contains("MD68K").not());
+
+    psylib()
+        .arg("/l")
+        .arg(&lib)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("HELPER"))
+        .stdout(predicate::str::contains("MD68K"));
+
+    psylib()
+        .arg("/l")
+        .arg(&lib)
+        .arg("NOPE")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no modules match NOPE"));
+}
+
+#[test]
+fn test_psylib_extract_named() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::copy(
+        "tests/data/synthetic/SIMPLE.LIB",
+        dir.path().join("SIMPLE.LIB"),
+    )
+    .expect("copy");
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/x", "SIMPLE.LIB", "main"])
+        .assert()
+        .success();
+    assert!(dir.path().join("MAIN.OBJ").exists());
+    assert!(!dir.path().join("HELPER.OBJ").exists());
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/x", "SIMPLE.LIB", "OTHER", "HELPER"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in SIMPLE.LIB: OTHER"));
+    assert!(!dir.path().join("HELPER.OBJ").exists());
+}
+
+#[test]
+fn test_psylib_common_exports_round_trip() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let original = Path::new("tests/data/synthetic/COMMON.LIB");
+    std::fs::copy(original, dir.path().join("COMMON.LIB")).expect("copy");
+
+    psylib()
+        .current_dir(dir.path())
+        .args(["/x", "COMMON.LIB"])
+        .assert()
+        .success();
+    psylib()
+        .current_dir(dir.path())
+        .env("SOURCE_DATE_EPOCH", "832176578")
+        .args(["/c", "REBUILT.LIB", "STREAM.OBJ", "RING.OBJ"])
+        .assert()
+        .success();
+
+    let rebuilt = psyk::io::read_lib(&dir.path().join("REBUILT.LIB")).expect("lib");
+    let export = &rebuilt.modules()[0].metadata().exports()[1];
+    assert_eq!("*StRingIdx", export);
+    assert_eq!(
+        std::fs::read(original).expect("original"),
+        std::fs::read(dir.path().join("REBUILT.LIB")).expect("rebuilt")
+    );
+}
+
+#[test]
+fn test_psylib_rename() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let lib = dir.path().join("SIMPLE.LIB");
+    std::fs::copy("tests/data/synthetic/SIMPLE.LIB", &lib).expect("copy");
+
+    psylib()
+        .args(["/r"])
+        .arg(&lib)
+        .arg("helper")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage:"));
+
+    psylib()
+        .args(["/r"])
+        .arg(&lib)
+        .args(["helper", "main"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "another module is already named MAIN",
+        ));
+
+    psylib()
+        .args(["/r"])
+        .arg(&lib)
+        .args(["nope", "other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SIMPLE.LIB: nope"));
+
+    psylib()
+        .args(["/r"])
+        .arg(&lib)
+        .args(["helper.obj", "util"])
+        .assert()
+        .success();
+    assert_eq!(vec!["MAIN", "UTIL"], module_names(&lib));
+}
@@ -7,8 +7,9 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use tempfile::TempDir;
 
-use psyk::cli;
+use psyk::cli::{self, OnCollision};
 use psyk::io;
+use psyk::{Code, LNKHeader, Section, LIB, OBJ};
 
 const PSYQ_PREFIX: &str = "tests/data/psy-q";
 
@@ -27,11 +28,7 @@ fn test_split_and_rejoin() -> Result<()> {
     let original_lib = io::read_lib(&temp_lib)?;
     let original_count = original_lib.modules().len();
 
-    // Change to temp directory for split operation
-    let original_dir = std::env::current_dir()?;
-    std::env::set_current_dir(temp_path)?;
-
-    cli::split(&temp_lib)?;
+    cli::split(&temp_lib, Some(temp_path))?;
 
     // Verify OBJ files were created
     for module in original_lib.modules() {
@@ -51,19 +48,342 @@ fn test_split_and_rejoin() -> Result<()> {
         .map(|m| temp_path.join(format!("{}.OBJ", m.name())))
         .collect();
 
-    cli::join(&rejoined_lib, obj_files)?;
+    cli::join(&rejoined_lib, obj_files, false)?;
 
     // Verify the rejoined library
     let rejoined = io::read_lib(&rejoined_lib)?;
     assert_eq!(rejoined.modules().len(), original_count);
 
-    // Restore original directory
-    std::env::set_current_dir(original_dir)?;
-
     // temp_dir automatically cleaned up here
     Ok(())
 }
 
+#[test]
+fn test_extract_to_dir() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/LIBSN.LIB");
+    let Ok(lib) = io::read_lib(Path::new(&p)) else {
+        return Ok(());
+    };
+
+    let out_dir = temp_dir.path().join("objs/libsn");
+    cli::extract(Path::new(&p), &[], Some(&out_dir), OnCollision::Error)?;
+    for module in lib.modules() {
+        let obj_file = out_dir.join(format!("{}.OBJ", module.name()));
+        let modified = fs::metadata(&obj_file)?.modified()?;
+        assert_eq!(module.created_at(), Some(modified), "{}", module.name());
+        assert_eq!(module.object(), &io::read_obj(&obj_file)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_split_times() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let lib_path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let lib = io::read_lib(lib_path)?;
+
+    cli::split(lib_path, Some(temp_dir.path()))?;
+    for module in lib.modules() {
+        let metadata = fs::metadata(temp_dir.path().join(format!("{}.OBJ", module.name())))?;
+        let created = module.created_at();
+        assert!(created.is_some(), "{}", module.name());
+        #[cfg(unix)]
+        assert_eq!(created, Some(metadata.modified()?), "{}", module.name());
+        #[cfg(windows)]
+        assert_eq!(created, Some(metadata.created()?), "{}", module.name());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_extract_named() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/LIBSN.LIB");
+    let Ok(lib) = io::read_lib(Path::new(&p)) else {
+        return Ok(());
+    };
+
+    let out_dir = temp_dir.path().join("out");
+    let names = ["snmain.obj".to_string(), "PCREAD".to_string()];
+    let e = cli::extract(Path::new(&p), &names, Some(&out_dir), OnCollision::Error).unwrap_err();
+    assert!(e.to_string().ends_with("LIBSN.LIB: PCREAD"), "{e}");
+
+    let names = ["snmain.obj".to_string(), "READ".to_string()];
+    cli::extract(Path::new(&p), &names, Some(&out_dir), OnCollision::Error)?;
+    let mut files = fs::read_dir(&out_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    files.sort();
+    assert_eq!(vec!["READ.OBJ", "SNMAIN.OBJ"], files);
+    for name in ["READ", "SNMAIN"] {
+        let module = lib
+            .modules()
+            .iter()
+            .find(|m| m.name() == name)
+            .expect("module");
+        let obj_file = out_dir.join(format!("{name}.OBJ"));
+        assert_eq!(module.object(), &io::read_obj(&obj_file)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_extract_to() -> Result<()> {
+    let lib_path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let mut bytes = Vec::new();
+    cli::extract_to(&mut bytes, lib_path, "main.obj")?;
+    assert_eq!(fs::read("tests/data/synthetic/MAIN.OBJ")?, bytes);
+
+    let e = cli::extract_to(&mut Vec::new(), lib_path, "nope").unwrap_err();
+    assert!(e.to_string().ends_with("SIMPLE.LIB: nope"), "{e}");
+    Ok(())
+}
+
+#[test]
+fn test_extract_unmatched() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let lib_path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let out_dir = temp_dir.path().join("out");
+    let names = [
+        "nope".to_string(),
+        "helper.obj".to_string(),
+        "X".to_string(),
+    ];
+    let e = cli::extract(lib_path, &names, Some(&out_dir), OnCollision::Error).unwrap_err();
+    assert!(e.to_string().ends_with("SIMPLE.LIB: nope, X"), "{e}");
+    assert!(!out_dir.exists());
+
+    cli::extract(lib_path, &names[1..2], Some(&out_dir), OnCollision::Error)?;
+    let files = fs::read_dir(&out_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(vec!["HELPER.OBJ"], files);
+    Ok(())
+}
+
+#[test]
+fn test_join_reproducible() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let objs = ["MAIN.OBJ", "HELPER.OBJ"]
+        .iter()
+        .map(|name| Path::new("tests/data/synthetic").join(name))
+        .collect::<Vec<_>>();
+
+    // an odd second is rounded down
+    let timestamp = cli::parse_timestamp("1996-05-15T16:12:07Z")?;
+    let first = temp_dir.path().join("FIRST.LIB");
+    cli::join_at(&first, objs.clone(), false, Some(timestamp))?;
+
+    // the same OBJs with new creation times
+    let copies = objs
+        .iter()
+        .map(|obj| {
+            let copy = temp_dir.path().join(obj.file_name().expect("name"));
+            fs::copy(obj, &copy)?;
+            Ok(copy)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let second = temp_dir.path().join("SECOND.LIB");
+    let even = cli::parse_timestamp("1996-05-15T16:12:06Z")?;
+    cli::join_at(&second, copies.clone(), false, Some(even))?;
+    assert_eq!(fs::read(&first)?, fs::read(&second)?);
+
+    let lib = io::read_lib(&first)?;
+    assert!(lib.modules().iter().all(|m| m.created_at() == Some(even)));
+    let module = psyk::Module::new_from_path_with_time(&copies[0], timestamp)?;
+    assert_eq!(&module, &lib.modules()[0]);
+    Ok(())
+}
+
+#[test]
+fn test_parse_timestamp() -> Result<()> {
+    let expected = cli::parse_timestamp("832176726")?;
+    assert_eq!(expected, cli::parse_timestamp("1996-05-15T16:12:06Z")?);
+    assert_eq!(expected, cli::parse_timestamp("1996-05-15T16:12:06")?);
+    assert_eq!(expected, cli::parse_timestamp("1996-05-15T18:12:06+02:00")?);
+    assert_eq!(
+        cli::parse_timestamp("832118400")?,
+        cli::parse_timestamp("1996-05-15")?
+    );
+    assert!(cli::parse_timestamp("0").is_err());
+    assert!(cli::parse_timestamp("2108-01-01").is_err());
+    assert!(cli::parse_timestamp("yesterday").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_xref() -> Result<()> {
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/2MBYTE.OBJ");
+    if !Path::new(&p).exists() {
+        return Ok(());
+    }
+    let mut out = Vec::new();
+    cli::xref(&mut out, Path::new(&p))?;
+    let out = String::from_utf8(out)?;
+    assert!(out.contains("InitHeap\n    .text+94\n"), "{out}");
+    assert!(out.contains("main\n    .text+a8\n"), "{out}");
+
+    let obj = io::read_obj(Path::new(&p))?;
+    let symbols = obj.symbols();
+    let offsets = |name: &str| {
+        obj.references()
+            .iter()
+            .filter(|r| r.symbol(&symbols).is_some_and(|s| s.name() == name))
+            .map(|r| r.patch_offset)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![0x94], offsets("InitHeap"));
+    assert_eq!(vec![0xa8], offsets("main"));
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_list_json() -> Result<()> {
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/2MBYTE.OBJ");
+    if !Path::new(&p).exists() {
+        return Ok(());
+    }
+    let mut out = Vec::new();
+    cli::list_json(&mut out, Path::new(&p))?;
+    let json: serde_json::Value = serde_json::from_slice(&out)?;
+    let sections = &json["sections"];
+    assert_eq!(serde_json::json!({"type": "CPU", "value": 7}), sections[0]);
+    assert_eq!(".rdata", sections[1]["value"]["type_name"]);
+    assert_eq!(
+        serde_json::json!({"number": 0x2814, "symbol_name": "InitHeap"}),
+        sections
+            .as_array()
+            .expect("sections")
+            .iter()
+            .find(|s| s["type"] == "XREF")
+            .expect("XREF")["value"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dump_section() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/2MBYTE.OBJ");
+    let Ok(obj) = io::read_obj(Path::new(&p)) else {
+        return Ok(());
+    };
+    let code = obj
+        .iter_code()
+        .next()
+        .expect("code")
+        .code()
+        .expect("code")
+        .to_vec();
+    assert_eq!(196, code.len());
+
+    let text = temp_dir.path().join("text.bin");
+    cli::dump_section(Path::new(&p), ".text", false, &text)?;
+    assert_eq!(code, fs::read(&text)?);
+    cli::dump_section(Path::new(&p), "2809", true, &text)?;
+    assert_eq!(code, fs::read(&text)?);
+
+    let rdata = temp_dir.path().join("rdata.bin");
+    cli::dump_section(Path::new(&p), ".rdata", false, &rdata)?;
+    assert!(fs::read(&rdata)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_dump_section_names() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let obj = OBJ::new(vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text")?),
+        Section::LNKHeader(LNKHeader::new(2, 0, 8, ".text")?),
+        Section::SectionSwitch(2),
+        Section::Code(Code::new(vec![1, 2, 3, 4])?),
+        Section::NOP,
+    ]);
+    let path = temp_dir.path().join("TWO.OBJ");
+    io::write_obj_to(&obj, &mut fs::File::create(&path)?)?;
+    let out = temp_dir.path().join("out.bin");
+
+    let e = cli::dump_section(&path, ".text", false, &out).unwrap_err();
+    assert!(e
+        .to_string()
+        .ends_with("has 2 .text sections; pick one with --by-id: 1, 2"));
+    let e = cli::dump_section(&path, ".data", false, &out).unwrap_err();
+    assert!(e.to_string().ends_with("has no .data section"));
+    let e = cli::dump_section(&path, "3", true, &out).unwrap_err();
+    assert!(e.to_string().ends_with("has no section 3"));
+    assert!(cli::dump_section(&path, "text", true, &out).is_err());
+    assert!(!out.exists());
+
+    cli::dump_section(&path, "0x2", true, &out)?;
+    assert_eq!(vec![1, 2, 3, 4], fs::read(&out)?);
+    Ok(())
+}
+
+#[test]
+fn test_extract_collisions() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let simple = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB"))?;
+    let mut modules = simple.modules().clone();
+    modules.push(modules[0].clone());
+    modules.push(modules[0].clone());
+    let lib_path = temp_dir.path().join("DUPS.LIB");
+    fs::write(&lib_path, LIB::new(modules).to_bytes()?)?;
+
+    let out_dir = temp_dir.path().join("out");
+    let e = cli::extract(&lib_path, &[], Some(&out_dir), OnCollision::Error).unwrap_err();
+    assert!(e
+        .to_string()
+        .ends_with("more than one module is named MAIN"));
+    assert!(!out_dir.exists());
+
+    cli::extract(&lib_path, &[], Some(&out_dir), OnCollision::Suffix)?;
+    let mut files = fs::read_dir(&out_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    files.sort();
+    assert_eq!(
+        vec!["HELPER.OBJ", "MAIN.1.OBJ", "MAIN.2.OBJ", "MAIN.OBJ"],
+        files
+    );
+    Ok(())
+}
+
+#[test]
+fn test_graph_libsn() -> Result<()> {
+    let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/LIBSN.LIB");
+    let lib = io::read_lib(Path::new(&p))?;
+    let graph = lib.dependency_graph();
+    let index = |name: &str| graph.modules.iter().position(|m| m == name).unwrap();
+
+    // the startup code takes the stack size from SNDEF, and the heap and
+    // main from outside the LIB
+    let (snmain, sndef) = (index("SNMAIN"), index("SNDEF"));
+    let dependency = graph
+        .dependencies
+        .iter()
+        .find(|d| d.from == snmain && d.to == sndef)
+        .expect("SNMAIN -> SNDEF");
+    assert!(dependency.symbols.contains(&"_stacksize".to_string()));
+    let init_heap = graph
+        .external
+        .iter()
+        .find(|s| s.name == "InitHeap")
+        .expect("InitHeap");
+    assert!(init_heap.referenced_by.contains(&snmain));
+
+    let mut output = Vec::new();
+    cli::graph(&mut output, Path::new(&p), cli::GraphFormat::Dot)?;
+    let dot = String::from_utf8(output)?;
+    assert!(dot.starts_with("digraph dependencies {\n"));
+    assert!(dot.contains(&format!("    m{snmain} -> m{sndef} [label=")));
+    assert!(dot.contains("subgraph cluster_external {"));
+    assert!(dot.contains("[label=\"InitHeap\"];"));
+    assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+    Ok(())
+}
+
 #[test]
 fn test_info_lib() -> Result<()> {
     let p = format!("{PSYQ_PREFIX}/3.3/PSX/LIB/LIBSN.LIB");
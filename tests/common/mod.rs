@@ -1,11 +1,11 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
-use std::fs::read_to_string;
 use std::path::Path;
 
 use binrw::io::Cursor;
 use binrw::BinWrite;
+use psyk::compat;
 use psyk::io;
 
 pub fn round_trip(path: &Path) {
@@ -36,39 +36,28 @@ pub fn round_trip(path: &Path) {
 }
 
 pub fn compare_output(lib_path: &Path, txt_path: &Path, skip_lines: usize) {
-    let bin = io::read(lib_path).expect("lib");
-    let psyk_output = format!("{bin}");
-    let psyq_output = read_to_string(txt_path).unwrap();
-
-    // Compare line by line
-    for (line_num, (psyk_line, dump_line)) in psyk_output
-        .lines()
-        .zip(
-            psyq_output
-                .lines()
-                .skip(skip_lines)
-                // TODO: wrapped lines aren't supported is psy-x
-                .filter(|l| !l.starts_with("        ")),
-        )
-        .enumerate()
-    {
-        println!("{line_num}: {dump_line}");
-        println!("{line_num}: {psyk_line}");
-        println!();
-        if psyk_line != dump_line {
-            // TODO: currently psyk doesn't handle line wrapping
-            if psyk_line.len() > 70 {
-                continue;
-            }
+    let result = compat::compare_listing(lib_path, txt_path, skip_lines).expect("listing");
+    let differences = result.err().unwrap_or_default();
+    let mismatched = differences
+        .iter()
+        .filter(|d| {
+            let (Some(psyk_line), Some(dump_line)) = (&d.actual, &d.expected) else {
+                // only the lines both listings have are compared
+                return false;
+            };
+            // TODO: psyk doesn't wrap long lines, so they may still differ
             // TODO: not specifying locale
-            if dump_line.contains("Uninitialised") {
-                continue;
-            }
-            println!(
-                "Diff at line {}: \n  psyk: {}\n  dump: {}",
-                line_num, psyk_line, dump_line
-            );
-            assert_eq!(dump_line, psyk_line);
-        }
+            psyk_line.len() <= 70 && !dump_line.contains("Uninitialised")
+        })
+        .collect::<Vec<_>>();
+    for difference in &mismatched {
+        println!("{difference}");
     }
+    assert!(
+        mismatched.is_empty(),
+        "{} line(s) of {} differ from {}",
+        mismatched.len(),
+        lib_path.display(),
+        txt_path.display()
+    );
 }
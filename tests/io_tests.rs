@@ -2,15 +2,31 @@
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
 use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use std::time::SystemTime;
 
+use binrw::io::Cursor;
+use binrw::{BinRead, BinWrite};
+
+use psyk::display::{self, PsyXDisplayable};
 use psyk::io;
+use psyk::types::{BaseType, Derivation, StorageClass};
+use psyk::Code;
+use psyk::Error;
+use psyk::Expression;
+use psyk::LNKHeader;
 use psyk::Module;
 use psyk::ModuleMetadata;
+use psyk::OpaqueLIB;
+use psyk::ParseWarning;
+use psyk::Patch;
 use psyk::Section;
 use psyk::LIB;
 use psyk::OBJ;
+use psyk::XBSS;
+use psyk::XDEF;
+use psyk::XREF;
 
 const PSYQ_PREFIX: &str = "tests/data/psy-q";
 
@@ -45,7 +61,7 @@ fn test_file_too_small() {
     let e = io::read(Path::new("/dev/null")).expect_err("error");
     assert_eq!(
         Some("File too small to contain valid PSY-Q magic number"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 }
 
@@ -54,17 +70,17 @@ fn test_bad_files() {
     let e = io::read(Path::new("tests/data/truncated.txt")).expect_err("error");
     assert_eq!(
         Some("Unrecognized magic [116, 120, 116]"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read(Path::new("tests/data/truncated.lib")).expect_err("error");
     assert_eq!(
         Some("assertion failed: `! objs.is_empty()` at 0x0"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read(Path::new("tests/data/truncated.obj")).expect_err("error");
-    let msg = e.chain().next().map(|x| format!("{x}")).expect("reason");
+    let msg = e.to_string();
     assert!(msg.contains("Error: no variants matched at 0x4..."));
 }
 
@@ -73,17 +89,17 @@ fn test_bad_obj_files() {
     let e = io::read_obj(Path::new("tests/data/truncated.txt")).expect_err("error");
     assert_eq!(
         Some("bad magic at 0x0: [116, 120, 116]"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read_obj(Path::new("tests/data/truncated.lib")).expect_err("error");
     assert_eq!(
         Some("bad magic at 0x0: [76, 73, 66]"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read_obj(Path::new("tests/data/truncated.obj")).expect_err("error");
-    let msg = e.chain().next().map(|x| format!("{x}")).expect("reason");
+    let msg = e.to_string();
     assert!(msg.contains("Error: no variants matched at 0x4..."));
 }
 
@@ -92,40 +108,1216 @@ fn test_bad_lib_files() {
     let e = io::read_lib(Path::new("tests/data/truncated.txt")).expect_err("error");
     assert_eq!(
         Some("bad magic at 0x0: [116, 120, 116]"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read_lib(Path::new("tests/data/truncated.lib")).expect_err("error");
     assert_eq!(
         Some("assertion failed: `! objs.is_empty()` at 0x0"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
     );
 
     let e = io::read_lib(Path::new("tests/data/truncated.obj")).expect_err("error");
     assert_eq!(
         Some("bad magic at 0x0: [76, 78, 75]"),
-        e.chain().next().map(|x| format!("{x}")).as_deref()
+        Some(e.to_string()).as_deref()
+    );
+}
+
+#[test]
+fn test_error_variants() {
+    let e = io::read(Path::new("tests/data/truncated.txt")).expect_err("error");
+    assert!(
+        matches!(
+            e,
+            Error::Unrecognized {
+                found: [116, 120, 116]
+            }
+        ),
+        "{e:?}"
     );
+
+    let e = io::read_obj(Path::new("tests/data/truncated.lib")).expect_err("error");
+    assert!(
+        matches!(e, Error::BadMagic { offset: 0, found } if &found == b"LIB"),
+        "{e:?}"
+    );
+
+    let e = io::read_lib(Path::new("/dev/null")).expect_err("error");
+    assert!(matches!(e, Error::TooSmall), "{e:?}");
+
+    let e = io::read(Path::new("tests/data/truncated.lib")).expect_err("error");
+    assert!(matches!(e, Error::Parse { offset: 0, .. }), "{e:?}");
+
+    let e = io::read(Path::new("tests/data/missing.obj")).expect_err("error");
+    assert!(
+        matches!(&e, Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound),
+        "{e:?}"
+    );
+
+    let e = Code::new(vec![0; 0x10000]).expect_err("error");
+    assert!(matches!(e, Error::Other(_)), "{e:?}");
+    let e = XDEF::new(1, 1, 0, "a\0b").expect_err("error");
+    assert!(matches!(e, Error::InvalidName(_)), "{e:?}");
 }
 
 #[test]
 fn test_write_errors() {
     let obj = OBJ::new(vec![Section::NOP]);
-    let metadata = ModuleMetadata::new("foo".to_string(), SystemTime::now(), 5, vec![]);
+    let metadata =
+        ModuleMetadata::new("foo".to_string(), SystemTime::now(), 5, vec![]).expect("metadata");
     let module = Module::new(obj.clone(), metadata);
     let lib = LIB::new(vec![module]);
 
     let mut file = File::open("/dev/fd").expect("file");
 
-    let e = io::write_obj(&obj.clone(), &mut file).expect_err("error");
+    let e = io::write_obj_to(&obj.clone(), &mut file).expect_err("error");
+    assert_eq!("Bad file descriptor (os error 9)", e.to_string());
+
+    let e = io::write_lib_to(&lib.clone(), &mut file).expect_err("error");
+    assert_eq!("Bad file descriptor (os error 9)", e.to_string());
+}
+
+fn lib_bytes(lib: &LIB) -> Vec<u8> {
+    let mut file = tempfile::tempfile().expect("tempfile");
+    io::write_lib_to(lib, &mut file).expect("write");
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(0)).expect("seek");
+    file.read_to_end(&mut bytes).expect("read");
+    bytes
+}
+
+#[test]
+fn test_rebuild_zero_export_modules() {
+    let lib =
+        io::read_lib(Path::new(&format!("{PSYQ_PREFIX}/3.3/PSX/LIB/LIBSN.LIB"))).expect("lib");
+
+    let modules = lib
+        .modules()
+        .iter()
+        .filter(|m| m.exports().is_empty())
+        .collect::<Vec<_>>();
+    assert!(modules.iter().any(|m| m.name() == "_FXTFDI"));
+    assert!(modules.iter().any(|m| m.name() == "_TRAMPOL"));
+
+    for module in modules {
+        let mut file = tempfile::tempfile().expect("tempfile");
+        io::write_obj_to(module.object(), &mut file).expect("write");
+        let size = file.metadata().expect("metadata").len() as u32;
+
+        let metadata = ModuleMetadata::new(
+            module.name(),
+            module.created_at().expect("created"),
+            size,
+            vec![],
+        )
+        .expect("metadata");
+        let rebuilt = Module::new(module.object().clone(), metadata);
+        assert_eq!(
+            lib_bytes(&LIB::new(vec![module.clone()])),
+            lib_bytes(&LIB::new(vec![rebuilt])),
+            "{}",
+            module.name()
+        );
+    }
+}
+
+/// The offset of each module in `lib` and the end of the file.
+fn module_offsets(lib: &LIB) -> Vec<usize> {
+    let mut offsets = vec![4];
+    for module in lib.modules() {
+        let size = lib_bytes(&LIB::new(vec![module.clone()])).len() - 4;
+        offsets.push(offsets.last().expect("offset") + size);
+    }
+    offsets
+}
+
+fn recover_bytes(bytes: &[u8]) -> psyk::recover::RecoveredLib {
+    let file = tempfile::NamedTempFile::new().expect("tempfile");
+    std::fs::write(file.path(), bytes).expect("write");
+    io::read_lib_recover(file.path()).expect("recover")
+}
+
+fn assert_salvaged(salvaged: &LIB) {
+    assert!(salvaged
+        .verify(&psyk::verify::Options::default())
+        .iter()
+        .all(|f| f.severity != psyk::verify::Severity::Error));
+    let bytes = lib_bytes(salvaged);
+    assert_eq!(bytes, lib_bytes(&recover_bytes(&bytes).salvage()));
+}
+
+#[test]
+fn test_recover_truncated_lib() {
+    let path = format!("{PSYQ_PREFIX}/3.5/PSX/LIB/LIBCD.LIB");
+    let lib = io::read_lib(Path::new(&path)).expect("lib");
+    let bytes = std::fs::read(&path).expect("bytes");
+    let offsets = module_offsets(&lib);
+    let damaged = offsets.len() / 2;
+    let cut = offsets[damaged] + 40;
+
+    let recovered = recover_bytes(&bytes[..cut]);
+    assert_eq!(&lib.modules()[..damaged], recovered.leading.as_slice());
+    assert!(recovered.trailing.is_empty());
+    assert_eq!(1, recovered.damage.len());
+    let region = &recovered.damage[0];
+    assert_eq!(
+        (offsets[damaged] as u64, cut as u64),
+        (region.start, region.end)
+    );
+    assert!(matches!(
+        &region.expected,
+        psyk::recover::Expected::Obj { module, .. } if *module == lib.modules()[damaged].name()
+    ));
+    assert_salvaged(&recovered.salvage());
+}
+
+#[test]
+fn test_recover_zeroed_lib() {
+    let path = format!("{PSYQ_PREFIX}/3.5/PSX/LIB/LIBCD.LIB");
+    let lib = io::read_lib(Path::new(&path)).expect("lib");
+    let mut bytes = std::fs::read(&path).expect("bytes");
+    let offsets = module_offsets(&lib);
+    let (first, last) = (offsets.len() / 3, offsets.len() / 2);
+    bytes[offsets[first] + 8..offsets[last] - 8].fill(0);
+
+    let recovered = recover_bytes(&bytes);
+    assert_eq!(&lib.modules()[..first], recovered.leading.as_slice());
+    assert_eq!(1, recovered.damage.len());
+    assert_eq!(offsets[first] as u64, recovered.damage[0].start);
+    // the damaged region ends where the next intact module starts
+    assert_eq!(offsets[last] as u64, recovered.damage[0].end);
+    assert_eq!(
+        &lib.modules()[last..],
+        recovered
+            .trailing
+            .iter()
+            .map(|c| c.module.clone())
+            .collect::<Vec<_>>()
+            .as_slice()
+    );
+    assert_salvaged(&recovered.salvage());
+}
+
+#[test]
+fn test_size_mismatch() {
+    let path = format!("{PSYQ_PREFIX}/3.5/PSX/LIB/LIBCD.LIB");
+    let lib = io::read_lib(Path::new(&path)).expect("lib");
+    let offsets = module_offsets(&lib);
+    let middle = lib.modules().len() / 2;
+    let actual = (offsets[middle + 1] - offsets[middle]) as u32;
+
+    // pad the declared size of the middle module
+    let mut bytes = std::fs::read(&path).expect("bytes");
+    let size = offsets[middle] + 16;
+    bytes[size..size + 4].copy_from_slice(&(actual + 16).to_le_bytes());
+    let file = tempfile::NamedTempFile::new().expect("tempfile");
+    std::fs::write(file.path(), &bytes).expect("write");
+
+    let mut padded = io::read_lib(file.path()).expect("padded");
+    assert_eq!(lib.modules(), padded.modules());
+    assert_eq!(
+        vec![psyk::SizeMismatch {
+            index: middle,
+            declared: actual + 16,
+            actual
+        }],
+        padded.size_mismatches()
+    );
+    assert!(padded
+        .verify(&psyk::verify::Options::default())
+        .iter()
+        .any(|f| matches!(
+            f.kind,
+            psyk::verify::FindingKind::SizeMismatch { declared, .. } if declared == actual + 16
+        )));
+    let strict = io::ReadOptions {
+        strict_sizes: true,
+        ..Default::default()
+    };
+    assert!(io::read_lib_with_options(file.path(), &strict).is_err());
+
+    // the lazy reader trusts the declared size and loses its place
+    let names = io::read_lib_metadata(file.path())
+        .expect("metadata")
+        .map_while(|m| m.ok().map(|m| m.name()))
+        .collect::<Vec<_>>();
+    assert_ne!(lib.modules().len(), names.len());
+
+    padded.repair_sizes().expect("repair");
+    std::fs::write(file.path(), lib_bytes(&padded)).expect("write");
+    let repaired = io::read_lib_with_options(file.path(), &strict).expect("repaired");
+    assert!(repaired.size_mismatches().is_empty());
+    let names = io::read_lib_metadata(file.path())
+        .expect("metadata")
+        .map(|m| m.expect("metadata").name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lib.modules().iter().map(|m| m.name()).collect::<Vec<_>>(),
+        names
+    );
+}
+
+#[test]
+fn test_write_to_memory() {
+    let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+    let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ")).expect("obj");
+
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    let size = io::write_lib_to(&lib, &mut cursor).expect("write");
+    let bytes = cursor.into_inner();
+    assert_eq!(size, bytes.len() as u64);
+    assert_eq!(lib_bytes(&lib), bytes);
+
+    let mut bytes = Vec::new();
+    let size = io::write_obj_to(&obj, &mut bytes).expect("write");
+    assert_eq!(size, bytes.len() as u64);
+    assert_eq!(
+        std::fs::read("tests/data/synthetic/MAIN.OBJ").expect("read"),
+        bytes
+    );
+
+    // writers that can't seek
+    let mut written = Vec::new();
+    io::write_obj_to(&obj, &mut written).expect("write");
+    assert_eq!(bytes, written);
+    let mut written = Vec::new();
+    io::write_lib_to(&lib, &mut written).expect("write");
+    assert_eq!(lib_bytes(&lib), written);
+}
+
+#[test]
+fn test_module_from_obj() {
+    let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ")).expect("obj");
+    let created = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(832_176_578);
+    let module = Module::from_obj("MAIN", created, obj.clone()).expect("module");
+    assert_eq!(vec!["main"], module.exports());
+
+    // a file with trailing bytes makes the same module
+    let mut bytes = obj.to_bytes().expect("bytes");
+    bytes.extend_from_slice(&[0x55; 5]);
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("MAIN.OBJ");
+    std::fs::write(&path, &bytes).expect("write");
+    let from_path = Module::new_from_path_with_time(&path, created).expect("module");
+    assert!(module.metadata() == from_path.metadata());
+
+    let lib = LIB::new(vec![module, from_path]);
+    let lib_path = dir.path().join("MAIN.LIB");
+    io::write_lib_to(&lib, &mut File::create(&lib_path).expect("create")).expect("write");
+    let read = io::read_lib(&lib_path).expect("lib");
+    assert!(
+        read.size_mismatches().is_empty(),
+        "{:?}",
+        read.size_mismatches()
+    );
+    for (built, read) in lib.modules().iter().zip(read.modules()) {
+        assert!(built.metadata() == read.metadata(), "{}", built.name());
+    }
+}
+
+#[test]
+fn test_to_bytes() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let lib = io::read_lib(path).expect("lib");
+    let bytes = lib.to_bytes().expect("bytes");
+    assert_eq!(std::fs::read(path).expect("read"), bytes);
+    assert_eq!(bytes, lib.to_bytes().expect("bytes"));
+    let read = io::read_lib_from(&mut Cursor::new(&bytes)).expect("lib");
+    assert_eq!(lib, read);
+
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    io::write_lib_to(&lib, &mut cursor).expect("write");
+    assert_eq!(bytes, cursor.into_inner());
+
+    let path = Path::new("tests/data/synthetic/MAIN.OBJ");
+    let obj = io::read_obj(path).expect("obj");
+    let bytes = obj.to_bytes().expect("bytes");
+    assert_eq!(std::fs::read(path).expect("read"), bytes);
+    let read = io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj");
+    assert_eq!(obj, read);
+
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    io::write_obj_to(&obj, &mut cursor).expect("write");
+    assert_eq!(bytes, cursor.into_inner());
+
+    assert!(LIB::new(vec![]).to_bytes().is_err());
+}
+
+#[test]
+fn test_build_obj() {
+    let obj = OBJ::new(vec![
+        Section::CPU(psyk::CpuType::MipsR3000),
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text").expect("header")),
+        Section::LNKHeader(LNKHeader::new(2, 0, 8, ".bss").expect("header")),
+        Section::SectionSwitch(1),
+        Section::Code(
+            Code::new(vec![0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00]).expect("code"),
+        ),
+        Section::Patch(Patch::new(0x4A, 0, Expression::SymbolAddressIndex(4))),
+        Section::XDEF(XDEF::new(3, 1, 0, "start").expect("xdef")),
+        Section::XREF(XREF::new(4, "main").expect("xref")),
+        Section::XBSS(XBSS::new(5, 2, 16, "stack").expect("xbss")),
+        Section::NOP,
+    ]);
+
+    let mut file = tempfile::tempfile().expect("tempfile");
+    io::write_obj_to(&obj, &mut file).expect("write");
+    file.seek(SeekFrom::Start(0)).expect("seek");
+    let read = io::read_obj_from(&mut file).expect("read");
+    assert_eq!(obj, read);
+    assert_eq!(vec!["start", "stack"], read.exports());
+    let listing = read.to_string();
+    assert!(listing.contains("16 : Section symbol number 1 '.text' in group 0 alignment 8"));
+    assert!(listing.contains("12 : XDEF symbol number 3 'start' at offset 0 in section 1"));
+    assert!(
+        listing.contains("14 : XREF symbol number 4 'main'"),
+        "{listing}"
+    );
+
+    let long = "x".repeat(256);
+    assert!(XDEF::new(1, 1, 0, &long).is_err());
+    assert!(XREF::new(1, &long).is_err());
+    assert!(LNKHeader::new(1, 0, 8, &long).is_err());
+    assert!(XDEF::new(1, 1, 0, &long[1..]).is_ok());
+    assert!(Code::new(vec![0; usize::from(u16::MAX) + 1]).is_err());
+    assert!(Code::new(vec![0; usize::from(u16::MAX)]).is_ok());
+}
+
+#[test]
+fn test_large_code() {
+    let code = (0..200 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let runs = Section::code_from_bytes(&code);
+    assert_eq!(4, runs.len());
+    assert!(Section::code_from_bytes(&[]).is_empty());
+
+    let mut sections = vec![
+        Section::LNKHeader(LNKHeader::new(1, 0, 8, ".text").expect("header")),
+        Section::SectionSwitch(1),
+    ];
+    sections.extend(runs);
+    sections.push(Section::NOP);
+    let obj = OBJ::new(sections);
+    assert_eq!(code.len(), obj.total_code_len());
+
+    let bytes = obj.to_bytes().expect("bytes");
+    let read = OBJ::read(&mut Cursor::new(bytes)).expect("obj");
+    assert_eq!(obj, read);
+    assert_eq!(code.len(), read.total_code_len());
+    assert_eq!(
+        vec![u16::MAX, u16::MAX, u16::MAX, 8195],
+        read.iter_code().map(Code::size).collect::<Vec<_>>()
+    );
+    assert_eq!(code, read.section_bytes(1).expect("bytes"));
+}
+
+#[test]
+fn test_debug_types() {
+    let obj = io::read_obj(Path::new("tests/data/synthetic/DEBUG.OBJ")).expect("obj");
+    let Some(Section::Def(main)) = obj.sections().iter().find(|s| matches!(s, Section::Def(_)))
+    else {
+        panic!("expected a Def");
+    };
+    assert_eq!("main", main.name());
+    assert_eq!(StorageClass::External, main.storage_class());
+    assert_eq!(BaseType::Int, main.type_desc().base);
+    assert_eq!(vec![Derivation::Function], main.type_desc().derivations);
+    assert_eq!("int ()", main.describe());
+
+    let Some(Section::Def2(buf)) = obj
+        .sections()
+        .iter()
+        .find(|s| matches!(s, Section::Def2(_)))
+    else {
+        panic!("expected a Def2");
+    };
+    assert_eq!(StorageClass::Static, buf.storage_class());
+    assert_eq!("unsigned char [4]", buf.describe());
+
+    let listing = obj.to_string();
+    assert!(listing.contains("  class 2\n  type 36\n"), "{listing}");
+    let options = display::Options::builder().decode_types(true).build();
+    let listing = PsyXDisplayable::wrap(&obj, options).to_string();
+    assert!(
+        listing.contains("  class 2 (C_EXT)\n  type 36 (int ())\n"),
+        "{listing}"
+    );
+    assert!(
+        listing.contains("  class 3 (C_STAT)\n  type 60 (unsigned char [4])\n"),
+        "{listing}"
+    );
+}
+
+#[test]
+fn test_parse_warnings() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    // a ProcedureCall, whose format is untested, then the end and 3 more
+    // bytes
+    let obj_path = dir.path().join("CALL.OBJ");
+    std::fs::write(&obj_path, b"LNK\x02\x44\x01\x02\x00\x00\x01\x02\x03").expect("write");
+    let (read, warnings) = io::read_with_warnings(&obj_path).expect("obj");
+    assert!(matches!(read, io::Type::OBJ(_)));
+    assert_eq!(
+        vec![
+            ParseWarning::UnimplementedSection {
+                magic: 68,
+                offset: 4
+            },
+            ParseWarning::TrailingBytes { count: 3 },
+        ],
+        warnings
+    );
+
+    // a module timestamped on day 0 of month 0
+    let mut bytes = std::fs::read("tests/data/synthetic/SIMPLE.LIB").expect("read");
+    bytes[12..16].fill(0);
+    let lib_path = dir.path().join("DATE.LIB");
+    std::fs::write(&lib_path, &bytes).expect("write");
+    let (_, warnings) = io::read_with_warnings(&lib_path).expect("lib");
+    assert_eq!(
+        vec![ParseWarning::InModule {
+            module: "MAIN".into(),
+            warning: Box::new(ParseWarning::SuspiciousTimestamp { created: 0 }),
+        }],
+        warnings
+    );
+    assert_eq!(
+        "MAIN: timestamp 0x00000000 is not a valid date",
+        warnings[0].to_string()
+    );
+}
+
+#[test]
+fn test_unknown_versions() {
+    let main = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ")).expect("obj");
+    assert!(main.warnings().is_empty());
+    let obj = OBJ::with_version(3, main.sections().clone());
+    let obj_file = tempfile::NamedTempFile::new().expect("tempfile");
+    io::write_obj_to(&obj, &mut File::create(obj_file.path()).expect("create")).expect("write");
+    assert_eq!(3, std::fs::read(obj_file.path()).expect("read")[3]);
+
+    let permissive = io::ReadOptions::default();
+    let strict = io::ReadOptions {
+        strict_versions: true,
+        ..Default::default()
+    };
+
+    let Ok(io::Type::OBJ(read)) = io::read_with_options(obj_file.path(), &permissive) else {
+        panic!("expected an OBJ");
+    };
+    assert_eq!(3, read.version());
+    assert_eq!(main.sections(), read.sections());
+    assert_eq!(
+        vec![ParseWarning::UnknownVersion {
+            kind: io::FileKind::OBJ,
+            found: 3
+        }],
+        read.warnings()
+    );
+    assert_eq!(
+        "unknown LNK version 3; only version 2 is known",
+        read.warnings()[0].to_string()
+    );
+    let e = io::read_with_options(obj_file.path(), &strict).expect_err("strict");
+    assert!(matches!(
+        e,
+        Error::UnknownVersion {
+            kind: io::FileKind::OBJ,
+            found: 3,
+            module: None
+        }
+    ));
+    assert_eq!(
+        "unknown LNK version 3; only version 2 is known",
+        e.to_string()
+    );
+
+    let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+    assert_eq!(LIB::VERSION, lib.version());
+    assert!(lib.warnings().is_empty());
+    let mut modules = lib.modules().clone();
+    let metadata = modules[0].metadata().clone();
+    modules[0] = Module::new(obj, metadata);
+    let mut lib = LIB::with_version(2, modules);
+    lib.repair_sizes().expect("repair");
+    let lib_file = tempfile::NamedTempFile::new().expect("tempfile");
+    io::write_lib_to(&lib, &mut File::create(lib_file.path()).expect("create")).expect("write");
+
+    let read = io::read_lib_with_options(lib_file.path(), &permissive).expect("permissive");
+    assert_eq!(2, read.version());
+    let name = read.modules()[0].name();
+    assert_eq!(
+        [
+            "unknown LIB version 2; only version 1 is known".to_string(),
+            format!("{name}: unknown LNK version 3; only version 2 is known"),
+        ],
+        read.warnings()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .as_slice()
+    );
+    let e = io::read_lib_with_options(lib_file.path(), &strict).expect_err("strict");
+    assert!(matches!(
+        e,
+        Error::UnknownVersion {
+            kind: io::FileKind::LIB,
+            found: 2,
+            ..
+        }
+    ));
+
+    let lib = LIB::new(vec![lib.modules()[0].clone()]);
+    io::write_lib_to(&lib, &mut File::create(lib_file.path()).expect("create")).expect("write");
+    let e = io::read_with_options(lib_file.path(), &strict).expect_err("strict");
+    assert_eq!(
+        format!("module {name}: unknown LNK version 3; only version 2 is known"),
+        e.to_string()
+    );
+}
+
+/// Every LIB and OBJ under `dir` and its subdirectories.
+fn fixtures(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            found.extend(fixtures(&path));
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("lib") || e.eq_ignore_ascii_case("obj"))
+        {
+            found.push(path);
+        }
+    }
+    found
+}
+
+#[test]
+fn test_serialized_size() {
+    let paths = fixtures(Path::new("tests/data"));
+    assert!(!paths.is_empty());
+    for path in paths {
+        let mut bytes = Vec::new();
+        let (size, written) = match io::read(&path) {
+            Ok(io::Type::LIB(lib)) => (
+                io::serialized_size_lib(&lib).expect("size"),
+                io::write_lib_to(&lib, &mut bytes).expect("write"),
+            ),
+            Ok(io::Type::OBJ(obj)) => (
+                io::serialized_size(&obj).expect("size"),
+                io::write_obj_to(&obj, &mut bytes).expect("write"),
+            ),
+            // truncated and damaged fixtures
+            Err(_) => continue,
+        };
+        assert_eq!(written, bytes.len() as u64, "{}", path.display());
+        assert_eq!(size, written, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_serialized_size_skip_code_bytes() {
+    let options = io::ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    let paths = fixtures(Path::new("tests/data"));
+    assert!(!paths.is_empty());
+    for path in paths {
+        let Ok(lib) = io::read_lib_with_options(&path, &options) else {
+            continue;
+        };
+        let mut bytes = Vec::new();
+        let written = io::write_lib_to(&io::read_lib(&path).expect("lib"), &mut bytes);
+        assert_eq!(
+            written.expect("write"),
+            io::serialized_size_lib(&lib).expect("size"),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+/// A writer that can't seek and records the largest single write.
+#[derive(Default)]
+struct Pipe {
+    bytes: Vec<u8>,
+    largest_write: usize,
+}
+
+impl std::io::Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.largest_write = self.largest_write.max(buf.len());
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_to_pipe() {
+    let paths = fixtures(Path::new("tests/data"));
+    assert!(!paths.is_empty());
+    for path in paths {
+        let mut expected = Vec::new();
+        let mut pipe = Pipe::default();
+        let size = match io::read(&path) {
+            Ok(io::Type::LIB(lib)) => {
+                lib.write_le(&mut Cursor::new(&mut expected))
+                    .expect("write");
+                let size = io::write_lib_to(&lib, &mut pipe).expect("write");
+                let read = LIB::read(&mut Cursor::new(&pipe.bytes)).expect("read");
+                assert_eq!(lib, read, "{}", path.display());
+                size
+            }
+            Ok(io::Type::OBJ(obj)) => {
+                obj.write_le(&mut Cursor::new(&mut expected))
+                    .expect("write");
+                io::write_obj_to(&obj, &mut pipe).expect("write")
+            }
+            Err(_) => continue,
+        };
+        assert_eq!(expected, pipe.bytes, "{}", path.display());
+        assert_eq!(size, pipe.bytes.len() as u64, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_write_to_pipe_buffer() {
+    let lib = io::read_lib(Path::new("tests/data/synthetic/SIMPLE.LIB")).expect("lib");
+    let modules = (0..64)
+        .flat_map(|_| lib.modules().iter().cloned())
+        .collect::<Vec<_>>();
+    let lib = LIB::new(modules);
+
+    let mut pipe = Pipe::default();
+    let size = io::write_lib_to(&lib, &mut pipe).expect("write");
+    let largest_module = lib
+        .modules()
+        .iter()
+        .map(|m| {
+            let mut bytes = Vec::new();
+            m.write_le(&mut Cursor::new(&mut bytes)).expect("write");
+            bytes.len()
+        })
+        .max()
+        .expect("modules");
+    assert_eq!(largest_module, pipe.largest_write);
+    assert!((pipe.largest_write as u64) < size / 64);
+}
+
+#[test]
+fn test_write_lib_atomic() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("SIMPLE.LIB");
+    let original = std::fs::read("tests/data/synthetic/SIMPLE.LIB").expect("bytes");
+    std::fs::write(&path, &original).expect("write");
+    let files = || {
+        let mut names = std::fs::read_dir(dir.path())
+            .expect("dir")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    // a LIB that fails to serialize leaves the original alone, with no
+    // temporary file behind
+    let options = io::ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    let deferred = io::read_lib_with_options(&path, &options).expect("deferred");
+    let backup = io::WriteOptions {
+        backup: true,
+        ..Default::default()
+    };
+    assert!(io::write_lib_atomic(&deferred, &path, &backup).is_err());
+    assert_eq!(original, std::fs::read(&path).expect("bytes"));
+    assert_eq!(vec!["SIMPLE.LIB"], files());
+
+    // the replaced file is kept only when asked
+    let mut lib = io::read_lib(&path).expect("lib");
+    lib.remove_module("HELPER").expect("helper");
+    io::write_lib_atomic(&lib, &path, &io::WriteOptions::default()).expect("write");
+    assert_eq!(vec!["SIMPLE.LIB"], files());
+    let shrunk = std::fs::read(&path).expect("bytes");
+
+    lib.rename_module("MAIN", "ENTRY").expect("rename");
+    io::write_lib_atomic(&lib, &path, &backup).expect("write");
+    assert_eq!(vec!["SIMPLE.BAK", "SIMPLE.LIB"], files());
+    assert_eq!(
+        shrunk,
+        std::fs::read(io::backup_path(&path)).expect("backup")
+    );
+    assert_eq!(lib, io::read_lib(&path).expect("lib"));
+
+    // there's nothing to back up for a new file
+    let new = dir.path().join("NEW.LIB");
+    io::write_lib_atomic(&lib, &new, &backup).expect("write");
+    assert!(!io::backup_path(&new).exists());
+}
+
+#[test]
+fn test_trailing_bytes() {
+    let obj_path = Path::new("tests/data/synthetic/MAIN.OBJ");
+    let obj_bytes = std::fs::read(obj_path).expect("obj");
+    assert!(io::read_obj(obj_path)
+        .expect("obj")
+        .trailing_bytes()
+        .is_empty());
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("TRAILING.OBJ");
+    let mut bytes = obj_bytes.clone();
+    bytes.extend(b"\x0c\x01\x00\x00\x00\x00\x00\x00\x00\x01x");
+    std::fs::write(&path, &bytes).expect("write");
+
+    let obj = io::read_obj(&path).expect("obj");
+    assert_eq!(&bytes[obj_bytes.len()..], obj.trailing_bytes());
+    let Ok(io::Type::OBJ(read)) = io::read(&path) else {
+        panic!("expected an OBJ");
+    };
+    assert_eq!(obj, read);
+    assert!(!obj.to_string().contains("'x'"));
+
+    let mut cursor = Cursor::new(Vec::new());
+    io::write_obj_to(&obj, &mut cursor).expect("write");
+    assert_eq!(obj_bytes, cursor.into_inner());
+
+    let mut cursor = Cursor::new(Vec::new());
+    let options = io::WriteOptions {
+        preserve_trailing_bytes: true,
+        ..Default::default()
+    };
+    io::write_obj_with_options(&obj, &mut cursor, &options).expect("write");
+    assert_eq!(bytes, cursor.into_inner());
+}
+
+#[test]
+fn test_read_opaque_lib() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let lib = io::read_lib(path).expect("lib");
+    let opaque = io::read_opaque_lib(path).expect("opaque");
+    assert_eq!(lib.to_string(), opaque.to_string());
+    assert_eq!(lib.modules().len(), opaque.modules().len());
+    for (module, opaque) in lib.modules().iter().zip(opaque.modules()) {
+        assert_eq!(module.name(), opaque.name());
+        assert_eq!(module.object(), &opaque.parse_obj().expect("obj"));
+    }
+
+    assert!(io::read_opaque_lib(Path::new("tests/data/synthetic/MAIN.OBJ")).is_err());
+}
+
+#[test]
+fn test_detect() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let kinds = [
+        (
+            "SYMMUNGE.OBJ",
+            // COFF file header: machine, 3 sections
+            b"\x62\x01\x03\x00\x00\x00\x00\x00".to_vec(),
+            io::FileKind::COFF,
+            "this is a COFF object, not a PSY-Q LNK object",
+        ),
+        (
+            "MAIN.EXE",
+            b"PS-X EXE\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+            io::FileKind::EXE,
+            "this is a PS-X EXE, not a PSY-Q LIB or LNK object",
+        ),
+        (
+            "MAIN.SYM",
+            b"MND\x01\x00\x00\x00\x00".to_vec(),
+            io::FileKind::SYM,
+            "this is a PSY-Q SYM file, not a LIB or LNK object",
+        ),
+    ];
+    for (name, header, kind, message) in kinds {
+        let path = dir.path().join(name);
+        std::fs::write(&path, header).expect("write");
+        assert_eq!(kind, io::detect(&path).expect("detect"), "{name}");
+        assert_eq!(message, io::read(&path).unwrap_err().to_string());
+        assert_eq!(message, io::read_obj(&path).unwrap_err().to_string());
+        assert_eq!(message, io::read_lib(&path).unwrap_err().to_string());
+    }
+
+    let kind = |path: &str| io::detect(Path::new(path)).expect("detect");
+    assert_eq!(io::FileKind::LIB, kind("tests/data/synthetic/SIMPLE.LIB"));
+    assert_eq!(io::FileKind::OBJ, kind("tests/data/synthetic/MAIN.OBJ"));
+    assert_eq!(io::FileKind::Unknown, kind("tests/data/truncated.txt"));
+    assert!(io::detect(&dir.path().join("MISSING.OBJ")).is_err());
+}
+
+#[test]
+fn test_read_from() {
+    let lib_path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let obj_path = Path::new("tests/data/synthetic/MAIN.OBJ");
+    let lib_bytes = std::fs::read(lib_path).expect("lib");
+    let obj_bytes = std::fs::read(obj_path).expect("obj");
+
+    let lib = io::read_lib(lib_path).expect("lib");
+    let obj = io::read_obj(obj_path).expect("obj");
+    assert_eq!(
+        lib,
+        io::read_lib_from(&mut Cursor::new(lib_bytes.clone())).expect("lib")
+    );
+    assert_eq!(
+        obj,
+        io::read_obj_from(&mut Cursor::new(obj_bytes.clone())).expect("obj")
+    );
+    let Ok(io::Type::LIB(from_stream)) = io::read_from(&mut Cursor::new(lib_bytes.clone())) else {
+        panic!("expected a LIB");
+    };
+    assert_eq!(lib, from_stream);
+
+    // reading starts at the current position and stops after the OBJ
+    let mut bytes = b"junk".to_vec();
+    bytes.extend(&obj_bytes);
+    bytes.extend(b"more");
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(4)).expect("seek");
+    let Ok(io::Type::OBJ(from_stream)) = io::read_from(&mut cursor) else {
+        panic!("expected an OBJ");
+    };
+    assert_eq!(obj, from_stream);
+    assert_eq!(4 + obj_bytes.len() as u64, cursor.position());
+
+    assert_eq!(
+        "File too small to contain valid PSY-Q magic number",
+        io::read_from(&mut Cursor::new(b"LI".to_vec()))
+            .unwrap_err()
+            .to_string()
+    );
+    let mut cursor = Cursor::new(b"ZIP\x04....".to_vec());
+    assert!(io::read_from(&mut cursor).is_err());
+    assert_eq!(0, cursor.position());
+    assert!(io::read_lib_from(&mut Cursor::new(obj_bytes)).is_err());
+}
+
+#[test]
+fn test_skip_code_bytes() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let options = io::ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    let lib = io::read_lib(path).expect("lib");
+    let mut deferred = io::read_lib_with_options(path, &options).expect("deferred");
+
+    // listings without code are the same
+    let listing = psyk::display::Options::builder().recursive(true).build();
+    assert_eq!(
+        PsyXDisplayable::wrap(&lib, listing.clone()).to_string(),
+        PsyXDisplayable::wrap(&deferred, listing).to_string()
+    );
+    let code = deferred
+        .modules()
+        .iter()
+        .flat_map(|m| m.object().iter_code())
+        .collect::<Vec<_>>();
+    assert!(code.iter().all(|c| c.code().is_err()));
+    assert!(code.iter().all(|c| c.deferred().is_some()));
+
+    // sizes come from the recorded lengths, but nothing that needs the
+    // bytes can be computed until they're loaded
+    assert!(deferred.validate().is_empty());
+    let mut repaired = deferred.clone();
+    repaired.repair_sizes().expect("repair");
+    assert_eq!(deferred, repaired);
+    for (module, read) in deferred.modules().iter().zip(lib.modules()) {
+        assert_eq!(
+            io::serialized_size(read.object()).expect("size"),
+            io::serialized_size(module.object()).expect("size")
+        );
+        let e = module.fingerprint().expect_err("fingerprint");
+        assert!(format!("{e}").contains("was not read"), "{e}");
+        assert!(module.stable_id().is_err());
+        assert!(module.symbols().is_err());
+    }
+    let fingerprints = psyk::display::Options::builder()
+        .recursive(true)
+        .show_fingerprints(true)
+        .build();
+    let listing = PsyXDisplayable::wrap(&deferred, fingerprints).to_string();
+    assert!(
+        listing.contains("MAIN     15-05-96 16:09:38 main  [crc:unread]"),
+        "{listing}"
+    );
+
+    // deferred code can't be written until it's loaded
+    assert!(io::write_lib_to(&deferred, &mut Cursor::new(Vec::new())).is_err());
+    deferred
+        .load_code(&mut File::open(path).expect("file"))
+        .expect("load");
+    assert_eq!(lib, deferred);
+
+    let Ok(io::Type::OBJ(obj)) =
+        io::read_with_options(Path::new("tests/data/synthetic/MAIN.OBJ"), &options)
+    else {
+        panic!("expected an OBJ");
+    };
+    assert_eq!(
+        1,
+        obj.iter_code().filter(|c| c.deferred().is_some()).count()
+    );
+    assert_eq!(
+        io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ"))
+            .expect("obj")
+            .to_string(),
+        obj.to_string()
+    );
+}
+
+#[test]
+fn test_section_offsets() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let bytes = std::fs::read(path).expect("bytes");
+    let lib = io::read_lib(path).expect("lib");
+    let modules = lib.modules();
+    assert_eq!(Some(4), modules[0].offset());
+    assert_eq!(0x22, modules[0].object().section_offsets()[0]);
+    assert_eq!(0x6d, modules[1].object().section_offsets()[0]);
+    for module in modules {
+        let obj = module.object();
+        assert_eq!(obj.sections().len(), obj.section_offsets().len());
+        for (section, offset) in obj.sections().iter().zip(obj.section_offsets()) {
+            let tag = bytes[*offset as usize];
+            match section {
+                Section::CPU(_) => assert_eq!(46, tag),
+                Section::Code(_) => assert_eq!(2, tag),
+                Section::NOP => assert_eq!(0, tag),
+                _ => (),
+            }
+        }
+    }
+
+    // an OBJ's offsets are from its own start, and built ones have none
+    let obj = io::read_obj(Path::new("tests/data/synthetic/MAIN.OBJ")).expect("obj");
+    assert_eq!([4, 6, 0x12, 0x15, 0x20, 0x2e], obj.section_offsets());
+    assert_eq!(obj, OBJ::new(obj.sections().clone()));
+    assert!(OBJ::new(obj.sections().clone())
+        .section_offsets()
+        .is_empty());
+    assert_eq!(
+        None,
+        Module::new(obj, modules[0].metadata().clone()).offset()
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_read_lib() {
+    let serial =
+        |path: &Path| io::read_lib_from(&mut File::open(path).expect("file")).expect("lib");
+
+    let mut paths = vec![Path::new("tests/data/synthetic/SIMPLE.LIB").to_path_buf()];
+    paths.extend(
+        ["LIBGS.LIB", "LIBCD.LIB"]
+            .iter()
+            .map(|name| Path::new(PSYQ_PREFIX).join("3.5/PSX/LIB").join(name))
+            .filter(|path| path.exists()),
+    );
+    for path in &paths {
+        let (serial, parallel) = (serial(path), io::read_lib(path).expect("parallel"));
+        assert_eq!(serial, parallel);
+        for (a, b) in serial.modules().iter().zip(parallel.modules()) {
+            assert_eq!(a.offset(), b.offset());
+            assert_eq!(a.object().section_offsets(), b.object().section_offsets());
+        }
+    }
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let simple = serial(&paths[0]);
+    let modules = (0..64)
+        .flat_map(|_| simple.modules().iter().cloned())
+        .collect::<Vec<_>>();
+    let many = dir.path().join("MANY.LIB");
+    io::write_lib_to(
+        &LIB::new(modules),
+        &mut File::create(&many).expect("create"),
+    )
+    .expect("write");
+    assert_eq!(serial(&many), io::read_lib(&many).expect("parallel"));
+
+    // the failing module is named
+    let mut bytes = std::fs::read(&paths[0]).expect("bytes");
+    let last = simple.modules().last().expect("module");
+    let magic = bytes.windows(3).rposition(|w| w == b"LNK").expect("magic");
+    bytes[magic] = b'X';
+    let broken = dir.path().join("BROKEN.LIB");
+    std::fs::write(&broken, &bytes).expect("write");
+    let error = io::read_lib(&broken).expect_err("broken");
+    assert!(format!("{error:#}").contains(&last.name()), "{error:#}");
+}
+
+#[test]
+fn test_rename_module() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let original = std::fs::read(path).expect("bytes");
+    let mut lib = io::read_lib(path).expect("lib");
+    lib.rename_module("helper", "util").expect("rename");
+    assert!(lib.rename_module("util", "main").is_err());
+
+    let renamed = lib_bytes(&lib);
+    let name = original
+        .windows(8)
+        .position(|w| w == b"HELPER  ")
+        .expect("name");
+    assert_eq!(original.len(), renamed.len());
+    assert_eq!(b"UTIL    ", &renamed[name..name + 8]);
+    assert_eq!(original[..name], renamed[..name]);
+    assert_eq!(original[name + 8..], renamed[name + 8..]);
+
+    let file = tempfile::NamedTempFile::new().expect("tempfile");
+    std::fs::write(file.path(), &renamed).expect("write");
+    let reread = io::read_lib(file.path()).expect("reread");
+    assert_eq!(
+        vec!["MAIN", "UTIL"],
+        reread
+            .modules()
+            .iter()
+            .map(|m| m.name())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(lib.modules()[1].created(), reread.modules()[1].created());
+    assert_eq!(lib.modules()[1].exports(), reread.modules()[1].exports());
+}
+
+#[test]
+fn test_untrusted_module_sizes() {
+    let simple = std::fs::read("tests/data/synthetic/SIMPLE.LIB").expect("bytes");
+    let with_size = |size: u32| {
+        let mut bytes = simple.clone();
+        bytes[20..24].copy_from_slice(&size.to_le_bytes());
+        bytes
+    };
+
+    // a size smaller than the metadata used to wrap around
+    let Err(e) = OpaqueLIB::read(&mut Cursor::new(with_size(4))) else {
+        panic!("size 4 was read");
+    };
+    assert!(
+        format!("{e}").contains("is smaller than its metadata"),
+        "{e}"
+    );
+
+    // a size past the end of the file fails before it is allocated
+    let Err(e) = OpaqueLIB::read(&mut Cursor::new(with_size(u32::MAX))) else {
+        panic!("size u32::MAX was read");
+    };
+    assert!(format!("{e}").contains("remain"), "{e}");
+
+    // parsing doesn't depend on the declared size; it's only reported
+    let lib = io::read_lib_from(&mut Cursor::new(with_size(u32::MAX))).expect("lib");
+    assert_eq!(u32::MAX, lib.size_mismatches()[0].declared);
+}
+
+#[test]
+fn test_max_exports() {
+    let path = Path::new("tests/data/synthetic/SIMPLE.LIB");
+    let options = |max_exports| io::ReadOptions {
+        max_exports,
+        ..Default::default()
+    };
+    io::read_lib_with_options(path, &options(Some(1))).expect("lib");
+    let e = io::read_lib_with_options(path, &options(Some(0))).expect_err("error");
+    assert!(format!("{e}").contains("more than 0 exports"), "{e}");
+    let e = io::read_with_options(path, &options(Some(0))).expect_err("error");
+    assert!(matches!(e, Error::Parse { .. }), "{e:?}");
+
+    // exports that never end
+    let mut bytes = b"LIB\x01MAIN    \0\0\0\0\x14\0\0\0\x14\0\0\0".to_vec();
+    for _ in 0..=ModuleMetadata::MAX_EXPORTS {
+        bytes.extend(b"\x01a");
+    }
+    let e = io::read_from(&mut Cursor::new(&bytes)).expect_err("error");
+    assert!(format!("{e}").contains("exports at 0x"), "{e}");
+    assert!(OpaqueLIB::read(&mut Cursor::new(&bytes)).is_err());
+}
+
+#[test]
+fn test_nested_expressions() {
+    let nested = |depth| {
+        (0..depth).fold(Expression::Constant(1), |e, _| {
+            Expression::Add(Box::new(e), Box::new(Expression::Constant(1)))
+        })
+    };
+    let obj = |expression| {
+        OBJ::new(vec![
+            Section::SectionSwitch(1),
+            Section::Patch(Patch::new(0x10, 0, expression)),
+            Section::NOP,
+        ])
+    };
+    let write = |obj: &OBJ| {
+        let mut bytes = Vec::new();
+        io::write_obj_to(obj, &mut Cursor::new(&mut bytes)).expect("write");
+        bytes
+    };
+
+    let deepest = obj(nested(psyk::MAX_EXPRESSION_DEPTH - 1));
+    let bytes = write(&deepest);
     assert_eq!(
-        "Bad file descriptor (os error 9)",
-        e.chain().next().map(|x| format!("{x}")).as_deref().unwrap()
+        deepest,
+        io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj")
     );
 
-    let e = io::write_lib(&lib.clone(), &mut file).expect_err("error");
+    let bytes = write(&obj(nested(psyk::MAX_EXPRESSION_DEPTH)));
+    assert!(io::read_obj_from(&mut Cursor::new(&bytes)).is_err());
+
+    // used to overflow the stack
+    let mut bytes = b"LNK\x02\x06\x01\x00\x0a\x10\x00\x00".to_vec();
+    bytes.resize(bytes.len() + 1_000_000, 44);
+    assert!(io::read_obj_from(&mut Cursor::new(&bytes)).is_err());
+}
+
+#[test]
+fn test_lenient_unknown_section() {
+    let path = Path::new("tests/data/synthetic/UNKNOWN.OBJ");
+    let e = io::read_obj(path).expect_err("error");
+    assert!(matches!(e, Error::Parse { offset: 0x15, .. }), "{e:?}");
+
+    let options = io::ReadOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let Ok(io::Type::OBJ(obj)) = io::read_with_options(path, &options) else {
+        panic!("expected an OBJ");
+    };
+    assert_eq!(
+        Some(&Section::Unknown(0x57, vec![1, 3, 5, 7, 9])),
+        obj.sections().get(3)
+    );
+    assert!(obj.sections().iter().any(|s| matches!(s, Section::Code(_))));
+    assert!(obj.sections().iter().any(|s| matches!(s, Section::XDEF(_))));
     assert_eq!(
-        "Bad file descriptor (os error 9)",
-        e.chain().next().map(|x| format!("{x}")).as_deref().unwrap()
+        vec![ParseWarning::UnknownSection {
+            magic: 0x57,
+            offset: 0x15,
+            skipped_bytes: 6
+        }],
+        obj.warnings()
     );
+
+    // the unknown bytes are written back as they were read
+    assert_eq!(
+        std::fs::read(path).expect("read"),
+        obj.to_bytes().expect("bytes")
+    );
+
+    // garbage with nothing to resync to is still an error
+    let mut bytes = std::fs::read(path).expect("read");
+    bytes.truncate(0x16);
+    bytes.extend([0xff; 8]);
+    let garbage = tempfile::NamedTempFile::new().expect("tempfile");
+    std::fs::write(garbage.path(), &bytes).expect("write");
+    assert!(io::read_with_options(garbage.path(), &options).is_err());
 }
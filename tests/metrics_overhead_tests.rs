@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+//! Checks that reads record nothing until a metrics recorder is installed.
+//! The recorder is process-wide, so this lives in its own test binary. The
+//! cost of recording is measured by `benches/metrics.rs`.
+
+use binrw::io::Cursor;
+use psyk::io;
+use psyk::metrics::{self, InMemoryRecorder};
+use psyk::{Section, OBJ};
+
+#[test]
+fn test_no_recorder() {
+    let bytes = OBJ::new(vec![Section::BSS(4), Section::NOP])
+        .to_bytes()
+        .expect("bytes");
+
+    assert!(!metrics::is_enabled());
+    for _ in 0..10 {
+        io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj");
+    }
+    assert!(!metrics::is_enabled());
+    assert!(metrics::recorder().is_none());
+
+    // reads before the recorder was installed weren't kept anywhere
+    let recorder = InMemoryRecorder::new();
+    metrics::set_recorder(Box::new(recorder.clone())).expect("recorder");
+    assert!(metrics::is_enabled());
+    assert_eq!(0, recorder.snapshot().parsed());
+
+    io::read_obj_from(&mut Cursor::new(&bytes)).expect("obj");
+    assert_eq!(1, recorder.snapshot().parsed());
+}
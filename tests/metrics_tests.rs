@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+use std::fs::File;
+use std::path::Path;
+use std::time::SystemTime;
+
+use psyk::io;
+use psyk::metrics::{self, ErrorKind, FileKind, InMemoryRecorder};
+use psyk::{Module, ModuleMetadata, Section, LIB, OBJ};
+use tempfile::TempDir;
+
+fn obj() -> OBJ {
+    OBJ::new(vec![Section::BSS(4), Section::BSS(8), Section::NOP])
+}
+
+#[test]
+fn test_in_memory_recorder() {
+    let tmp = TempDir::new().expect("tempdir");
+    let obj_path = tmp.path().join("A.OBJ");
+    let lib_path = tmp.path().join("A.LIB");
+
+    // nothing is recorded before a recorder is installed
+    io::write_obj_to(&obj(), &mut File::create(&obj_path).expect("obj")).expect("write");
+
+    let recorder = InMemoryRecorder::new();
+    metrics::set_recorder(Box::new(recorder.clone())).expect("recorder");
+    assert!(metrics::set_recorder(Box::new(InMemoryRecorder::new())).is_err());
+    assert_eq!(0, recorder.snapshot().writes.len());
+
+    let mut lib = LIB::new(vec![
+        Module::new(
+            obj(),
+            ModuleMetadata::new("A".into(), SystemTime::now(), 0, vec![]).expect("metadata"),
+        ),
+        Module::new(
+            obj(),
+            ModuleMetadata::new("B".into(), SystemTime::now(), 0, vec![]).expect("metadata"),
+        ),
+    ]);
+    lib.repair_sizes().expect("sizes");
+    io::write_lib_to(&lib, &mut File::create(&lib_path).expect("lib")).expect("write");
+    let lib_size = std::fs::metadata(&lib_path).expect("lib").len();
+    let obj_size = std::fs::metadata(&obj_path).expect("obj").len();
+
+    io::read(&obj_path).expect("obj");
+    io::read_lib(&lib_path).expect("lib");
+    assert!(io::read(Path::new("tests/data/truncated.obj")).is_err());
+    assert!(io::read_obj(&lib_path).is_err());
+    assert!(io::read(&tmp.path().join("missing.obj")).is_err());
+
+    let snapshot = recorder.snapshot();
+    assert_eq!(2, snapshot.parsed());
+
+    let objs = &snapshot.parses[&FileKind::OBJ];
+    assert_eq!((1, 1), (objs.ok, objs.errors));
+    assert_eq!(obj_size + 4, objs.bytes);
+    let libs = &snapshot.parses[&FileKind::LIB];
+    assert_eq!((1, 1), (libs.ok, libs.errors));
+    assert_eq!(2 * lib_size, libs.bytes);
+    let unknown = &snapshot.parses[&FileKind::Unknown];
+    assert_eq!((0, 1, 0), (unknown.ok, unknown.errors, unknown.bytes));
+
+    assert_eq!(1, snapshot.errors[&ErrorKind::Format]);
+    assert_eq!(1, snapshot.errors[&ErrorKind::BadMagic]);
+    assert_eq!(1, snapshot.errors[&ErrorKind::NotFound]);
+
+    let writes = &snapshot.writes[&FileKind::LIB];
+    assert_eq!((1, 0, lib_size), (writes.ok, writes.errors, writes.bytes));
+    assert!(!snapshot.writes.contains_key(&FileKind::OBJ));
+
+    // one OBJ and a LIB with two modules
+    assert_eq!(Some(&6), snapshot.sections.counts.get("BSS"));
+    assert_eq!(Some(&3), snapshot.sections.counts.get("NOP"));
+    assert_eq!(2, snapshot.sections.counts.len());
+
+    // the other read paths are recorded too
+    io::read_opaque_lib(&lib_path).expect("opaque");
+    io::read_lib_metadata(&lib_path).expect("metadata");
+    io::read_lib_recover(&lib_path).expect("recover");
+    let options = io::ReadOptions {
+        skip_code_bytes: true,
+        ..Default::default()
+    };
+    io::read_with_options(&obj_path, &options).expect("obj");
+    io::read_lib_with_options(&lib_path, &options).expect("lib");
+    assert!(io::read_lib_metadata(&obj_path).is_err());
+    assert!(io::read_sym(&obj_path).is_err());
+
+    let snapshot = recorder.snapshot();
+    let objs = &snapshot.parses[&FileKind::OBJ];
+    assert_eq!((2, 3), (objs.ok, objs.errors));
+    let libs = &snapshot.parses[&FileKind::LIB];
+    assert_eq!((5, 1), (libs.ok, libs.errors));
+    assert_eq!(6 * lib_size, libs.bytes);
+    assert_eq!(3, snapshot.errors[&ErrorKind::BadMagic]);
+
+    // recovered and skip-code modules are counted, opaque ones aren't
+    assert_eq!(Some(&(6 + 2 + 4 + 4)), snapshot.sections.counts.get("BSS"));
+}
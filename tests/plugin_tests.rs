@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+#![cfg(all(feature = "dynamic-plugins", unix))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use assert_cmd::cargo;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use psyk::plugin::{self, Registry};
+use psyk::{Section, OBJ};
+use tempfile::TempDir;
+
+/// A plugin using only the C interface, so it doesn't depend on psyk.
+const PLUGIN: &str = r#"
+use std::ffi::{c_char, c_void};
+
+#[no_mangle]
+pub extern "C" fn psyk_plugin_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn psyk_plugin_name() -> *const c_char {
+    c"tiny".as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn psyk_plugin_wants_section(tag: u8) -> bool {
+    tag == 0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn psyk_plugin_analyze(
+    module: *const c_char,
+    _obj: *const u8,
+    obj_len: usize,
+    emit: unsafe extern "C" fn(*mut c_void, u8, *const c_char),
+    state: *mut c_void,
+) {
+    let message = format!("{obj_len} bytes, module: {}\0", !module.is_null());
+    emit(state, 1, message.as_ptr() as *const c_char);
+}
+"#;
+
+/// Builds the plugin above for `abi_version` into `dir`.
+fn build_plugin(dir: &Path, name: &str, abi_version: u32) -> PathBuf {
+    let source = dir.join(format!("{name}.rs"));
+    fs::write(
+        &source,
+        format!("const ABI_VERSION: u32 = {abi_version};\n{PLUGIN}"),
+    )
+    .expect("write");
+    let output = dir.join(format!(
+        "{}{name}.{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_EXTENSION
+    ));
+    let status = Command::new(std::env::var("RUSTC").unwrap_or("rustc".into()))
+        .args(["--edition", "2021", "--crate-type", "cdylib", "-o"])
+        .arg(&output)
+        .arg(&source)
+        .status()
+        .expect("rustc");
+    assert!(status.success());
+    output
+}
+
+#[test]
+fn test_dynamic_plugins() {
+    let tmp = TempDir::new().expect("tempdir");
+    let current = build_plugin(tmp.path(), "current", plugin::ABI_VERSION);
+    let future = build_plugin(tmp.path(), "future", plugin::ABI_VERSION + 1);
+
+    let e = plugin::dynamic::load(&future)
+        .err()
+        .expect("future plugins are rejected");
+    assert!(e.to_string().ends_with(&format!(
+        "built for plugin ABI version {}, expected {}",
+        plugin::ABI_VERSION + 1,
+        plugin::ABI_VERSION
+    )));
+
+    let mut registry = Registry::new();
+    let rejected = registry.load_dir(tmp.path()).expect("load");
+    assert_eq!(
+        vec![future],
+        rejected.into_iter().map(|(p, _)| p).collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["tiny"], registry.names().collect::<Vec<_>>());
+    let findings = registry
+        .analyze_obj(Some("A".into()), &OBJ::new(vec![Section::NOP]), &["tiny"])
+        .expect("findings");
+    assert_eq!(
+        vec!["warning: A: tiny: 5 bytes, module: true"],
+        findings.iter().map(|f| f.to_string()).collect::<Vec<_>>()
+    );
+    assert!(plugin::dynamic::load(&current).is_ok());
+
+    let obj = tmp.path().join("EMPTY.OBJ");
+    fs::write(&obj, b"LNK\x02\x00").expect("write");
+    Command::new(cargo::cargo_bin!("psyk"))
+        .args(["analyze", "--plugin", "tiny", "--plugin-dir"])
+        .arg(tmp.path())
+        .arg(&obj)
+        .assert()
+        .success()
+        .stdout("warning: tiny: 5 bytes, module: false\n")
+        .stderr(predicate::str::contains("could not load plugin"));
+}
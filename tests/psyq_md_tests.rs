@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+use std::path::Path;
+
+use psyk::display::{self, PsyXDisplayable};
+use psyk::io;
+
+mod common;
+
+use common::{compare_output, round_trip};
+
+const CMD_DATA_PREFIX: &str = "tests/data/cmd/psy-q-md";
+
+/// A 68000 object using the run at offset, register size, repeat, and MX
+/// info sections.
+const MD68K: &str = "tests/data/synthetic/MD68K.OBJ";
+
+#[test]
+pub fn test_roundtrip() {
+    round_trip(Path::new(MD68K));
+}
+
+#[test]
+pub fn test_output() {
+    compare_output(
+        Path::new(MD68K),
+        Path::new(&format!("{CMD_DATA_PREFIX}/MD68K.TXT")),
+        0,
+    );
+}
+
+#[test]
+pub fn test_listing() {
+    let Ok(io::Type::OBJ(obj)) = io::read(Path::new(MD68K)) else {
+        panic!("expected an OBJ");
+    };
+    let options = display::Options {
+        code_format: display::CodeFormat::Disassembly,
+        ..Default::default()
+    };
+    let listing = PsyXDisplayable::wrap(&obj, options).to_string();
+    assert!(listing.contains(
+        "2 : Code 4 bytes\n\n\
+        \x20   ; disassembly not supported for processor type 0, showing hex\n\
+        0000: 4e71 4e75\n"
+    ));
+}
+
+#[test]
+pub fn test_legacy_line_info() {
+    use psyk::{Code, CpuType, FileIdOrName, Filename, LineInfoMechanism, Section, OBJ};
+
+    // 68000 compilers use the legacy directives rather than SLD ones
+    let obj = OBJ::new(vec![
+        Section::CPU(CpuType::Motorola68000),
+        Section::Filename(Filename::new(1, "MAIN.C").expect("filename")),
+        Section::SectionSwitch(1),
+        Section::SetToFile(1, 10),
+        Section::Code(Code::new(vec![0x4e, 0x71, 0x4e, 0x75]).expect("code")),
+        Section::IncrementLineNumber,
+        Section::NOP,
+    ]);
+    let bytes = obj.to_bytes().expect("bytes");
+
+    // a u16 file number and a u32 line number
+    let set_to_file = [30, 1, 0, 10, 0, 0, 0];
+    assert!(bytes.windows(7).any(|w| w == set_to_file));
+    let read = io::read_obj_from(&mut std::io::Cursor::new(&bytes)).expect("read");
+    assert_eq!(obj, read);
+
+    let refs = read.file_references();
+    assert_eq!(1, refs.len());
+    assert_eq!(LineInfoMechanism::Legacy, refs[0].mechanism);
+    assert_eq!(FileIdOrName::Name("MAIN.C".into()), refs[0].file);
+
+    let lines = read.line_map().expect("lines");
+    assert_eq!("MAIN.C:10", lines.lookup(1, 0).expect("line").to_string());
+    assert_eq!("MAIN.C:11", lines.lookup(1, 4).expect("line").to_string());
+}
@@ -1,12 +1,22 @@
 // SPDX-FileCopyrightText: © 2025 TTKB, LLC
 // SPDX-License-Identifier: BSD-3-CLAUSE
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use binrw::io::Cursor;
+use binrw::{BinRead, BinWrite};
+use psyk::analysis;
+use psyk::chronology;
+use psyk::diff;
 use psyk::io;
+use psyk::link;
+use psyk::verify;
 use psyk::Module;
+use psyk::ModuleMetadata;
 use psyk::Section;
 use serde_json::{self};
 
@@ -276,6 +286,38 @@ pub fn test(path: &Path) {
     }
 }
 
+pub fn test_timestamps(path: &Path) {
+    let Ok(io::Type::LIB(lib)) = io::read(path) else {
+        return;
+    };
+
+    let findings = lib.verify(&verify::Options::default());
+    assert!(
+        findings.iter().all(|f| !f.kind.is_timestamp()),
+        "{}: {findings:?}",
+        path.display()
+    );
+}
+
+pub fn test_relocations(path: &Path) {
+    let Ok(io::Type::LIB(lib)) = io::read(path) else {
+        return;
+    };
+
+    for module in lib.modules() {
+        let high = analysis::unpatched_addresses(module.object())
+            .into_iter()
+            .filter(|s| s.severity == analysis::Severity::High)
+            .collect::<Vec<_>>();
+        assert!(
+            high.is_empty(),
+            "{}: {}: {high:?}",
+            path.display(),
+            module.name()
+        );
+    }
+}
+
 const PRIVATE_TEST_DATA_PREFIX: &str = "target/.private/tests/data";
 const PSYQ_PREFIX: &str = "tests/data/psy-q";
 
@@ -316,6 +358,58 @@ fn path_47(file: &str) -> PathBuf {
     PathBuf::from(format!("{PRIVATE_TEST_DATA_PREFIX}/Psy-Q_47/{file}"))
 }
 
+#[test]
+fn test_cdsfile_trailing_bytes() {
+    // n.b.! there is an extra XDEF at the end of this OBJ
+    // a NULL byte at 0x2554 acts as the EOF marker even
+    // though there is one additional section in the file
+    // DUMPOBJ.EXE does not find this section, either.
+    let path = path_33("PSX/UTILITY/MENU/CDSFILE.OBJ");
+    let Ok(bytes) = std::fs::read(&path) else {
+        return;
+    };
+    let obj = io::read_obj(&path).expect("obj");
+    let trailing = obj.trailing_bytes();
+    assert_eq!(bytes.len() - 0x2555, trailing.len());
+    let Ok(Section::XDEF(xdef)) = Section::read(&mut Cursor::new(trailing)) else {
+        panic!("expected an XDEF after the NOP");
+    };
+
+    // preserved
+    let mut cursor = Cursor::new(Vec::new());
+    let options = io::WriteOptions {
+        preserve_trailing_bytes: true,
+        ..Default::default()
+    };
+    io::write_obj_with_options(&obj, &mut cursor, &options).expect("write");
+    assert_eq!(bytes, cursor.into_inner());
+
+    // strict, as DUMPOBJ.EXE reads it
+    let mut cursor = Cursor::new(Vec::new());
+    io::write_obj_to(&obj, &mut cursor).expect("write");
+    assert_eq!(bytes[..0x2555], cursor.into_inner());
+    let listing = obj.to_string();
+    assert!(listing.ends_with("0 : End of file\n"));
+    assert!(!listing.contains(&format!("'{}'", xdef.symbol_name())));
+}
+
+#[test]
+fn test_symmunge_is_coff() {
+    for path in [
+        path_35("PSYQ/SRC/SYMMUNGE/SYMMUNGE.OBJ"),
+        path_36("PSYQ/SRC/SYMMUNGE/SYMMUNGE.OBJ"),
+    ] {
+        let Ok(kind) = io::detect(&path) else {
+            continue;
+        };
+        assert_eq!(io::FileKind::COFF, kind);
+        assert_eq!(
+            "this is a COFF object, not a PSY-Q LNK object",
+            io::read(&path).unwrap_err().to_string()
+        );
+    }
+}
+
 #[test]
 fn test_psyq_33() {
     round_trip(&path_33("PSX/LIB/2MBYTE.OBJ"));
@@ -324,11 +418,7 @@ fn test_psyq_33() {
     round_trip(&path_33("PSX/LIB/NONE2.OBJ"));
     round_trip(&path_33("PSX/LIB/NONE3.OBJ"));
     round_trip(&path_33("PSX/SAMPLE/MODULE/EXECMENU/FONTTEX1.OBJ"));
-    // n.b.! there is an extra XDEF at the end of this OBJ
-    // a NULL byte at 0x2554 acts as the EOF marker even
-    // though there is one additional section in the file
-    // DUMPOBJ.EXE does not find this section, either.
-    //round_trip(&path_33("PSX/UTILITY/MENU/CDSFILE.OBJ"));
+    // see test_cdsfile_trailing_bytes
 
     round_trip(&path_33("PSX/LIB/LIBAPI.LIB"));
     round_trip(&path_33("PSX/LIB/LIBC.LIB"));
@@ -357,7 +447,7 @@ fn test_psyq_35() {
     round_trip(&path_35("PSX/LIB/MALLOC.OBJ"));
     round_trip(&path_35("PSX/LIB/NONE2.OBJ"));
     round_trip(&path_35("PSX/LIB/NONE3.OBJ"));
-    //round_trip(&path_35("PSYQ/SRC/SYMMUNGE/SYMMUNGE.OBJ")); // coff file
+    // SYMMUNGE.OBJ is a COFF file, see test_symmunge_is_coff
 
     round_trip(&path_35("PSX/LIB/LIBAPI.LIB"));
     round_trip(&path_35("PSX/LIB/LIBC.LIB"));
@@ -394,7 +484,7 @@ fn test_psyq_36() {
     round_trip(&path_36("PSX/UTILITY/MENU/SDATA.OBJ"));
     round_trip(&path_36("PSX/UTILITY/MENU/SOUND.OBJ"));
     round_trip(&path_36("PSX/UTILITY/MENU/STRING.OBJ"));
-    //round_trip(&path_36("PSYQ/SRC/SYMMUNGE/SYMMUNGE.OBJ")); // coff file
+    // SYMMUNGE.OBJ is a COFF file, see test_symmunge_is_coff
 
     round_trip(&path_36("PSX/LIB/LIBAPI.LIB"));
     round_trip(&path_36("PSX/LIB/LIBC.LIB"));
@@ -527,6 +617,278 @@ fn test_psyq_47() {
     round_trip(&path_47("LIB/NOHEAP.OBJ"));
 }
 
+#[test]
+fn test_psyq_timestamps() {
+    for lib in [
+        "LIBAPI", "LIBC", "LIBC2", "LIBCARD", "LIBCD", "LIBCOMB", "LIBETC", "LIBGPU", "LIBGS",
+        "LIBGTE", "LIBMATH", "LIBPRESS", "LIBSN", "LIBSND", "LIBSPU", "LIBTAP",
+    ] {
+        let file = format!("PSX/LIB/{lib}.LIB");
+        test_timestamps(&path_33(&file));
+        test_timestamps(&path_35(&file));
+        test_timestamps(&path_36(&file));
+        test_timestamps(&path_40(&file));
+        test_timestamps(&path_46(&format!("LIB/{lib}.LIB")));
+        test_timestamps(&path_47(&format!("LIB/{lib}.LIB")));
+    }
+}
+
+#[test]
+fn test_psyq_relocations() {
+    for lib in [
+        "LIBAPI", "LIBC", "LIBC2", "LIBCARD", "LIBCD", "LIBCOMB", "LIBETC", "LIBGPU", "LIBGS",
+        "LIBGTE", "LIBMATH", "LIBPRESS", "LIBSN", "LIBSND", "LIBSPU", "LIBTAP",
+    ] {
+        let file = format!("PSX/LIB/{lib}.LIB");
+        test_relocations(&path_33(&file));
+        test_relocations(&path_35(&file));
+        test_relocations(&path_36(&file));
+        test_relocations(&path_40(&file));
+        test_relocations(&path_46(&format!("LIB/{lib}.LIB")));
+        test_relocations(&path_47(&format!("LIB/{lib}.LIB")));
+    }
+}
+
+#[test]
+fn test_opaque_libgs() {
+    let path = path_33("PSX/LIB/LIBGS.LIB");
+    let start = std::time::Instant::now();
+    let Ok(opaque) = io::read_opaque_lib(&path) else {
+        return;
+    };
+    let scanned = start.elapsed();
+    let start = std::time::Instant::now();
+    let lib = io::read_lib(&path).expect("lib");
+    let parsed = start.elapsed();
+    eprintln!("LIBGS.LIB: metadata scan {scanned:?}, full parse {parsed:?}");
+
+    assert_eq!(lib.to_string(), opaque.to_string());
+    for (module, opaque) in lib.modules().iter().zip(opaque.modules()) {
+        assert_eq!(module.exports(), opaque.exports());
+        assert_eq!(module.created_at(), opaque.created_at());
+    }
+    let last = opaque.modules().last().expect("module");
+    assert_eq!(
+        lib.modules().last().expect("module").object(),
+        &last.parse_obj().expect("obj")
+    );
+}
+
+#[test]
+fn test_psyq_diff_libc() {
+    let (Ok(old), Ok(new)) = (
+        io::read_lib(&path_35("PSX/LIB/LIBC.LIB")),
+        io::read_lib(&path_36("PSX/LIB/LIBC.LIB")),
+    ) else {
+        return;
+    };
+    assert!(diff::diff_libs(&old, &old).is_empty());
+    assert!(diff::diff_libs(&old, &old).to_string().is_empty());
+
+    let diff = diff::diff_libs(&old, &new);
+    assert!(!diff.is_empty());
+    let listing = diff.to_string();
+    for name in &diff.added {
+        assert!(old.modules().iter().all(|m| &m.name() != name));
+        assert!(listing.contains(&format!("+ {name}\n")));
+    }
+    for name in &diff.removed {
+        assert!(new.modules().iter().all(|m| &m.name() != name));
+        assert!(listing.contains(&format!("- {name}\n")));
+    }
+    for module in &diff.changed {
+        let find = |lib: &psyk::LIB| {
+            lib.modules()
+                .iter()
+                .find(|m| m.name() == module.name)
+                .expect("module")
+                .object()
+                .clone()
+        };
+        let (old_obj, new_obj) = (find(&old), find(&new));
+        let code_size = |obj: &psyk::OBJ| obj.iter_code().map(|c| c.size() as usize).sum::<usize>();
+        if let Some((old_size, new_size)) = module.obj.code_size {
+            assert_eq!(
+                (code_size(&old_obj), code_size(&new_obj)),
+                (old_size, new_size)
+            );
+        }
+        assert_eq!(module.obj, diff::diff_objs(&old_obj, &new_obj));
+        assert!(listing.contains(&format!("~ {}\n", module.name)));
+    }
+}
+
+#[test]
+fn test_psyq_chronology() {
+    let path = path_33("PSX/LIB/LIBSN.LIB");
+    let Ok(metadata) = io::read_lib_metadata(&path) else {
+        return;
+    };
+    let entries = metadata
+        .map(|m| (path.clone(), m.expect("metadata")))
+        .collect::<Vec<_>>();
+    let report = chronology::analyze(entries.into_iter());
+    assert_eq!(0, report.invalid);
+    assert_eq!(1, report.clusters.len());
+
+    // LIBSN was built in one pass on 1995-09-20
+    let day = chrono::NaiveDate::from_ymd_opt(1995, 9, 20).expect("date");
+    let cluster = &report.clusters[0];
+    assert_eq!(report.entries.len(), cluster.modules);
+    assert!(cluster.start >= day.and_hms_opt(20, 44, 22).expect("time"));
+    assert!(cluster.end <= day.and_hms_opt(20, 45, 4).expect("time"));
+}
+
+/// How the exports stored in a module's metadata differ from those a
+/// module rebuilt from its OBJ lists, by `OBJ::exports_psylib_order`.
+/// Common symbols are prefixed with `*` in both.
+#[derive(Debug)]
+pub struct ExportDiscrepancy {
+    pub module: String,
+    pub stored: Vec<String>,
+    pub derived: Vec<String>,
+}
+
+impl ExportDiscrepancy {
+    /// Stored exports the OBJ doesn't define.
+    pub fn unknown(&self) -> Vec<&String> {
+        self.stored
+            .iter()
+            .filter(|e| !self.derived.contains(e))
+            .collect()
+    }
+
+    /// Symbols the OBJ defines which aren't stored.
+    pub fn omitted(&self) -> Vec<&String> {
+        self.derived
+            .iter()
+            .filter(|e| !self.stored.contains(e))
+            .collect()
+    }
+
+    /// `true` if the exports are the same but stored in a different order.
+    pub fn is_reordered(&self) -> bool {
+        self.unknown().is_empty() && self.omitted().is_empty()
+    }
+}
+
+/// Compares the stored exports of every module in the LIB at `path` with
+/// those of metadata rebuilt from its OBJ. Returns `None` if the LIB can't
+/// be read.
+pub fn export_discrepancies(path: &Path) -> Option<Vec<ExportDiscrepancy>> {
+    let Ok(io::Type::LIB(lib)) = io::read(path) else {
+        return None;
+    };
+    Some(
+        lib.modules()
+            .iter()
+            .map(|module| {
+                let rebuilt =
+                    ModuleMetadata::from_obj(&module.name(), SystemTime::now(), module.object())
+                        .expect("metadata");
+                ExportDiscrepancy {
+                    module: module.name(),
+                    stored: module.exports(),
+                    derived: rebuilt.exports(),
+                }
+            })
+            .filter(|d| d.stored != d.derived)
+            .collect(),
+    )
+}
+
+#[test]
+fn test_psyq_export_tables() {
+    for lib in ["LIBAPI", "LIBC", "LIBSN"] {
+        let file = format!("PSX/LIB/{lib}.LIB");
+        for path in [
+            path_33(&file),
+            path_35(&file),
+            path_36(&file),
+            path_40(&file),
+        ] {
+            let Some(discrepancies) = export_discrepancies(&path) else {
+                continue;
+            };
+            let unexpected = discrepancies
+                .iter()
+                // I_HEAP2's export table was edited by hand after PSYLIB.EXE
+                // wrote it: its one export is five symbol names separated by
+                // spaces, which no OBJ can define.
+                .filter(|d| !(lib == "LIBAPI" && d.module == "I_HEAP2"))
+                .map(|d| {
+                    format!(
+                        "{}:{}: unknown {:?}, omitted {:?}, reordered {}\n  stored:  {:?}\n  rebuilt: {:?}",
+                        path.display(),
+                        d.module,
+                        d.unknown(),
+                        d.omitted(),
+                        d.is_reordered(),
+                        d.stored,
+                        d.derived
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+        }
+    }
+}
+
+#[test]
+fn test_psyq_section_compat() {
+    let mut modules = Vec::new();
+    for lib in ["LIBAPI", "LIBC", "LIBSN"] {
+        let Ok(io::Type::LIB(lib)) = io::read(&path_33(&format!("PSX/LIB/{lib}.LIB"))) else {
+            return;
+        };
+        modules.extend(lib.modules().iter().cloned());
+    }
+    let names = modules.iter().map(|m| m.name()).collect::<Vec<_>>();
+    let objs = names
+        .iter()
+        .zip(&modules)
+        .map(|(name, module)| (name.as_str(), module.object()))
+        .collect::<Vec<_>>();
+    let issues = link::section_compat_named(&objs);
+    assert!(issues.is_empty(), "{issues:#?}");
+}
+
+#[test]
+fn test_psyq_poke() {
+    let Ok(io::Type::LIB(lib)) = io::read(&path_33("PSX/LIB/LIBAPI.LIB")) else {
+        return;
+    };
+    let mut module = lib
+        .modules()
+        .iter()
+        .find(|m| m.name() == "A56")
+        .expect("A56")
+        .clone();
+    let original = module.object().clone();
+
+    // nop out the first instruction of the `exit` BIOS stub
+    let patched = module.patch_code("exit", 0, &[0; 4]).expect("patch");
+    assert_eq!(4, patched.range.len());
+    assert_eq!(1, patched.chunks);
+    assert_eq!(vec![0; 4], patched.after);
+
+    let mut before = Cursor::new(Vec::new());
+    original.write_le(&mut before).expect("write");
+    let mut after = Cursor::new(Vec::new());
+    module.object().write_le(&mut after).expect("write");
+    let (before, after) = (before.into_inner(), after.into_inner());
+    assert_eq!(before.len(), after.len());
+    let changed = before
+        .iter()
+        .zip(&after)
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert!(!changed.is_empty());
+    assert!(changed[changed.len() - 1] - changed[0] < 4);
+}
+
 const CMD_DATA_PREFIX: &str = "tests/data/cmd/psy-q-psx";
 
 #[inline]
@@ -753,3 +1115,351 @@ fn test_output_40() {
     compare_obj_output_40("PSX/LIB/NONE3");
     compare_obj_output_40("PSX/LIB/POWERON");
 }
+
+#[test]
+fn test_psyq_format_census() {
+    let Ok(io::Type::OBJ(obj)) = io::read(&path_33("PSX/LIB/2MBYTE.OBJ")) else {
+        return;
+    };
+    // counted from the dumpobj listing in tests/data/cmd
+    let census = obj.format_census();
+    assert_eq!(
+        BTreeMap::from([
+            ("BSS", 1),
+            ("CPU", 1),
+            ("Code", 1),
+            ("LNKHeader", 6),
+            ("NOP", 1),
+            ("Patch", 18),
+            ("SectionSwitch", 7),
+            ("XDEF", 5),
+            ("XREF", 3),
+        ]),
+        census.sections
+    );
+    assert_eq!(
+        BTreeMap::from([
+            ("Add", 4),
+            ("Constant", 4),
+            ("SectionAddressIndex", 4),
+            ("SectionEnd", 4),
+            ("SectionStart", 4),
+            ("SymbolAddressIndex", 4),
+        ]),
+        census.expressions
+    );
+    assert!(census.uses_untested().is_empty());
+}
+
+#[test]
+fn test_psyq_expression_round_trip() {
+    use psyk::display::expr::{self, ExprStyle};
+    use psyk::Expression;
+
+    let Ok(entries) = std::fs::read_dir(path_33("PSX/LIB")) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(io::Type::LIB(lib)) = io::read(&entry.path()) else {
+            continue;
+        };
+        for (_, section) in lib.iter_sections() {
+            let Section::Patch(patch) = section else {
+                continue;
+            };
+            let e = patch.expression();
+            assert_eq!(e.to_string(), e.render(&ExprStyle::default()));
+            assert_eq!(e, &Expression::parse(&e.to_string()).expect("parse"));
+            for style in [ExprStyle::default(), ExprStyle::minimal()] {
+                let text = e.render(&style);
+                assert_eq!(e, &expr::parse(&text).expect("parse"), "{text}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_psyq_closure() {
+    use binrw::BinRead;
+    use psyk::link::EmitMode;
+    use psyk::OBJ;
+
+    let Ok(io::Type::LIB(lib)) = io::read(&path_33("PSX/LIB/LIBAPI.LIB")) else {
+        return;
+    };
+    let closure = link::closure(&lib, &["InitHeap"]).expect("closure");
+    assert!(closure.modules[0]
+        .object()
+        .exports()
+        .contains(&"InitHeap".into()));
+    assert_eq!(
+        closure,
+        link::closure(&lib, &["InitHeap"]).expect("closure")
+    );
+    for name in &closure.imports {
+        assert!(!lib
+            .modules()
+            .iter()
+            .any(|m| m.object().exports().contains(name)));
+    }
+
+    let separate = closure.emit(EmitMode::SeparateModules);
+    let combined = closure.emit(EmitMode::Combined);
+    for (name, obj) in separate.iter().chain(&combined) {
+        let mut bytes = Vec::new();
+        io::write_obj_to(obj, &mut bytes).expect("write");
+        assert_eq!(
+            obj,
+            &OBJ::read(&mut Cursor::new(bytes)).expect("read"),
+            "{name}"
+        );
+        let findings = verify::verify_obj(obj, &verify::Options::default());
+        assert!(
+            findings
+                .iter()
+                .all(|f| f.severity < verify::Severity::Error),
+            "{name}: {findings:?}"
+        );
+    }
+
+    // the combined OBJ exports and imports what the modules do together
+    let (_, obj) = &combined[0];
+    let exports: HashSet<String> = obj.exports().into_iter().collect();
+    let separate_exports: HashSet<String> =
+        separate.iter().flat_map(|(_, obj)| obj.exports()).collect();
+    assert_eq!(separate_exports, exports);
+    let imports: HashSet<String> = obj
+        .sections()
+        .iter()
+        .filter_map(|s| match s {
+            Section::XREF(xref) => Some(xref.symbol_name()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        closure.imports.iter().cloned().collect::<HashSet<_>>(),
+        imports
+    );
+}
+
+#[test]
+fn test_psyq_reachable_modules() {
+    use psyk::{OBJ, XREF};
+
+    let Ok(io::Type::LIB(lib)) = io::read(&path_33("PSX/LIB/LIBC.LIB")) else {
+        return;
+    };
+    let root = OBJ::new(vec![
+        Section::XREF(XREF::new(1, "sprintf").expect("xref")),
+        Section::NOP,
+    ]);
+    let report = link::reachable_modules(&[root], &[&lib]);
+    let exports: HashSet<String> = lib
+        .modules()
+        .iter()
+        .filter(|m| report.required.iter().any(|r| r.name == m.name()))
+        .flat_map(Module::exports)
+        .collect();
+    for name in ["sprintf", "memchr", "strlen", "memmove"] {
+        assert!(exports.contains(name), "{name}: {report}");
+    }
+    assert!(report.required.iter().any(|m| m.name == "SPRINTF"));
+    assert_eq!(
+        lib.modules().len(),
+        report.required.len() + report.unreferenced.len()
+    );
+}
+
+#[test]
+fn test_psyq_kernel_wrappers() {
+    use psyk::kernel::{self, KernelTable};
+
+    let Ok(io::Type::LIB(lib)) = io::read(&path_33("PSX/LIB/LIBAPI.LIB")) else {
+        return;
+    };
+    for (name, table, index) in [
+        ("exit", KernelTable::B0, 0x38),
+        ("InitHeap", KernelTable::A0, 0x39),
+        ("read", KernelTable::B0, 0x34),
+        ("write", KernelTable::B0, 0x35),
+    ] {
+        let call = kernel::is_kernel_symbol(name).expect(name);
+        assert_eq!(
+            ("LIBAPI", table, index),
+            (call.library, call.table, call.index)
+        );
+
+        // the wrapper loads the table address into t2 and the index into t1
+        let module = lib
+            .modules()
+            .iter()
+            .find(|m| m.exports().contains(&name.to_string()))
+            .expect(name);
+        let code: Vec<u8> = module
+            .object()
+            .sections()
+            .iter()
+            .filter_map(|s| match s {
+                Section::Code(code) => Some(code.code().expect("code").to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let address = match table {
+            KernelTable::A0 => 0xa0u32,
+            KernelTable::B0 => 0xb0,
+            KernelTable::C0 => 0xc0,
+        };
+        for instruction in [0x240a0000 | address, 0x24090000 | index as u32] {
+            assert!(
+                code.windows(4).any(|w| w == instruction.to_le_bytes()),
+                "{name}: {instruction:08x}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_psyq_names_overlay() {
+    use psyk::cli;
+    use psyk::display::{CodeFormat, Options};
+    use psyk::names::NameOverlay;
+    use std::sync::Arc;
+
+    let path = path_33("PSX/LIB/LIBC2.LIB");
+    let Ok(io::Type::LIB(lib)) = io::read(&path) else {
+        return;
+    };
+    let module = lib
+        .modules()
+        .iter()
+        .find(|m| m.name() == "SPRINTF")
+        .expect("SPRINTF");
+    let overlay: NameOverlay = "LIBC2:SPRINTF:sprintf+0x8 pad_helper\n\
+                                LIBC2:SPRINTF:sprintf+0 not_sprintf\n"
+        .parse()
+        .expect("names");
+
+    // the real symbol wins over the overlay
+    let resolution = overlay.resolve("LIBC2", "SPRINTF", module.object());
+    assert_eq!(1, resolution.names.len());
+    assert_eq!("pad_helper", resolution.names[0].name);
+    assert_eq!(1, resolution.warnings.len());
+    assert!(resolution.warnings[0].contains("sprintf, ignoring not_sprintf"));
+
+    let overlay = Arc::new(overlay);
+    let mut functions = Vec::new();
+    cli::functions(&mut functions, &path, Some(overlay.clone()), true).expect("functions");
+    let functions = String::from_utf8(functions).expect("utf-8");
+    assert!(functions.contains(" ~pad_helper\n"), "{functions}");
+    assert!(!functions.contains("not_sprintf"), "{functions}");
+
+    let mut listing = Vec::new();
+    let options = Options::builder()
+        .code_format(CodeFormat::Disassembly)
+        .recursive(true)
+        .names(overlay)
+        .build();
+    cli::info_with_options(&mut listing, &path, options).expect("list");
+    let listing = String::from_utf8(listing).expect("utf-8");
+    assert!(listing.contains("    ; ~pad_helper\n"), "{listing}");
+    assert!(!listing.contains("not_sprintf"));
+}
+
+#[test]
+fn test_psyq_definition_strength() {
+    use psyk::DefinitionStrength;
+
+    let Ok(io::Type::LIB(libsn)) = io::read(&path_33("PSX/LIB/LIBSN.LIB")) else {
+        return;
+    };
+    for memory in ["2MBYTE", "8MBYTE"] {
+        let Ok(io::Type::OBJ(obj)) = io::read(&path_33(&format!("PSX/LIB/{memory}.OBJ"))) else {
+            return;
+        };
+        // the memory-size OBJs are linked with the startup code in LIBSN
+        let members = link::resolve_members(&libsn, &[&obj], &["__SN_ENTRY_POINT"]).expect(memory);
+        let names = members.module_names();
+        let mut objs = vec![(memory, &obj)];
+        objs.extend(
+            names
+                .iter()
+                .map(String::as_str)
+                .zip(members.modules.iter().map(Module::object)),
+        );
+        let resolution = link::resolve_definitions(&objs);
+        assert!(
+            resolution.duplicates.is_empty(),
+            "{memory}: {:?}",
+            resolution.duplicates
+        );
+        for definition in obj.definitions() {
+            if definition.strength == DefinitionStrength::Strong {
+                assert_eq!(memory, resolution.symbols[&definition.name].source);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_psyq_symbol_index() {
+    for path in [
+        path_33("PSX/LIB/LIBSN.LIB"),
+        path_35("PSX/LIB/LIBSN.LIB"),
+        path_36("PSX/LIB/LIBSN.LIB"),
+    ] {
+        let Ok(io::Type::LIB(lib)) = io::read(&path) else {
+            continue;
+        };
+        let open = lib.find_module_by_export("PCopen").expect("PCopen");
+        assert_eq!("OPEN", open.name());
+
+        let index = lib.symbol_index();
+        assert_eq!("OPEN", index.find("PCopen").expect("PCopen").name());
+        assert!(index.find("pcopen").is_none());
+        for module in lib.modules() {
+            for export in module.exports() {
+                assert!(index
+                    .find_all(&export)
+                    .iter()
+                    .any(|m| m.name() == module.name()));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_psyq_file_references() {
+    use psyk::{FileIdOrName, LineInfoMechanism};
+
+    let Ok(io::Type::LIB(lib)) = io::read(&path_33("PSX/LIB/LIBC2.LIB")) else {
+        return;
+    };
+    let sprintf = lib.find_module_by_export("sprintf").expect("sprintf");
+    let refs = sprintf.object().file_references();
+
+    // SPRINTF only has source line debugger information
+    assert!(!refs.is_empty());
+    for file_ref in &refs {
+        assert_eq!(
+            LineInfoMechanism::SLD,
+            file_ref.mechanism,
+            "{}",
+            file_ref.file
+        );
+        assert!(matches!(file_ref.file, FileIdOrName::Name(_)));
+    }
+
+    // the line map names the same files
+    let lines = sprintf.object().line_map().expect("lines");
+    assert!(lines.warnings().is_empty(), "{:?}", lines.warnings());
+    let files = lines
+        .iter()
+        .filter_map(|entry| entry.location.as_ref())
+        .map(|location| FileIdOrName::Name(location.file.clone()))
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        refs.into_iter().map(|r| r.file).collect::<HashSet<_>>(),
+        files
+    );
+}
@@ -3,6 +3,9 @@
 
 use std::path::PathBuf;
 
+use psyk::display::{self, PsyXDisplayable};
+use psyk::{io, CpuType, Expression, Section};
+
 mod common;
 
 use common::{compare_output, round_trip};
@@ -44,3 +47,56 @@ pub fn test_output() {
     compare_lib_output("dos/GNUSHLIB/LIB/LIBSN");
     compare_lib_output("dos/GNUSHLIB/LIB/LIBSTDCX");
 }
+
+/// Every module of the Saturn libraries is SH-2 code, shown as hex grouped by
+/// instruction word.
+#[test]
+pub fn test_sh2_listing() {
+    let Ok(lib) = io::read_lib(&path_sat("dos/GNUSHLIB/LIB/LIBSN.LIB")) else {
+        return;
+    };
+    let options = display::Options {
+        code_format: display::CodeFormat::Disassembly,
+        ..Default::default()
+    };
+    for module in lib.modules() {
+        let obj = module.object();
+        assert!(
+            obj.sections()
+                .iter()
+                .any(|s| matches!(s, Section::CPU(CpuType::HitachiSh2))),
+            "{} is not SH-2",
+            module.name()
+        );
+        let listing = PsyXDisplayable::wrap(obj, options.clone()).to_string();
+        for code in obj.iter_code().filter(|c| c.size() >= 16) {
+            let bytes = &code.code().expect("code")[..4];
+            let row = format!(
+                "0000: {:02x}{:02x} {:02x}{:02x} ",
+                bytes[0], bytes[1], bytes[2], bytes[3]
+            );
+            assert!(listing.contains(&row), "{}: {row}", module.name());
+        }
+    }
+}
+
+/// The keyword expressions of SH-2 patches display as text that parses back
+/// to the same expression.
+#[test]
+pub fn test_sh2_expressions() {
+    for name in ["LIBSN", "LIBC", "LIBGCC"] {
+        let Ok(lib) = io::read_lib(&path_sat(&format!("dos/GNUSHLIB/LIB/{name}.LIB"))) else {
+            return;
+        };
+        for module in lib.modules() {
+            for section in module.object().sections() {
+                let Section::Patch(patch) = section else {
+                    continue;
+                };
+                let text = patch.expression().to_string();
+                let parsed = text.parse::<Expression>().expect(&text);
+                assert_eq!(patch.expression(), &parsed, "{}: {text}", module.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: © 2025 TTKB, LLC
+// SPDX-License-Identifier: BSD-3-CLAUSE
+
+use std::fs;
+use std::process::Command;
+
+use assert_cmd::cargo;
+use psyk::prelude::*;
+use tempfile::TempDir;
+
+const LIB_PATH: &str = "tests/data/synthetic/SIMPLE.LIB";
+const OBJ_PATH: &str = "tests/data/synthetic/HELPER.OBJ";
+
+/// The facade can produce the module listing from the README without the
+/// underlying model.
+#[test]
+fn test_listing() {
+    let archive = simple::open(LIB_PATH).expect("open");
+    let listing = archive
+        .module_names()
+        .iter()
+        .map(|name| {
+            format!(
+                "{name:<8} {} {} ",
+                archive.created_of(name).expect("created").expect("lib"),
+                archive.exports_of(name).expect("exports").join(" ")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let output = Command::new(cargo::cargo_bin!("psyk"))
+        .arg(LIB_PATH)
+        .output()
+        .expect("psyk");
+    let stdout = String::from_utf8(output.stdout).expect("utf-8");
+    let expected = stdout
+        .lines()
+        .skip_while(|l| !l.is_empty())
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(expected, listing);
+}
+
+/// Each module of a LIB can be written out and read back as an OBJ with the
+/// same exports and code.
+#[test]
+fn test_extract_and_save() {
+    let tmp = TempDir::new().expect("tempdir");
+    let Archive::Lib(lib) = simple::open(LIB_PATH).expect("open") else {
+        panic!("not a LIB");
+    };
+    let archive = Archive::Lib(lib.clone());
+    for module in lib.modules() {
+        let path = tmp.path().join(format!("{}.OBJ", module.name()));
+        Archive::Obj {
+            name: module.name(),
+            obj: module.object().clone(),
+        }
+        .save(&path)
+        .expect("save");
+
+        let obj = simple::open(&path).expect("open");
+        assert_eq!(vec![module.name()], obj.module_names());
+        let name = &module.name();
+        assert_eq!(
+            archive.exports_of(name).expect("lib"),
+            obj.exports_of(name).expect("obj")
+        );
+        assert_eq!(
+            archive.code_of(name, ".text").expect("lib"),
+            obj.code_of(name, ".text").expect("obj")
+        );
+        assert_eq!(None, obj.created_of(name).expect("obj"));
+    }
+
+    let copy = tmp.path().join("HELPER.OBJ");
+    simple::open(OBJ_PATH)
+        .expect("open")
+        .save(&copy)
+        .expect("save");
+    assert_eq!(
+        fs::read(OBJ_PATH).expect("read"),
+        fs::read(copy).expect("read")
+    );
+}